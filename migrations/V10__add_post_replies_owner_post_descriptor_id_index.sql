@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS post_replies_owner_post_descriptor_id_idx ON post_replies (owner_post_descriptor_id);
\ No newline at end of file
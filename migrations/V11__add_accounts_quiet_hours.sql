@@ -0,0 +1,3 @@
+ALTER TABLE accounts ADD COLUMN quiet_hours_start smallint default null;
+ALTER TABLE accounts ADD COLUMN quiet_hours_end smallint default null;
+ALTER TABLE accounts ADD COLUMN tz_offset_minutes smallint not null default 0;
\ No newline at end of file
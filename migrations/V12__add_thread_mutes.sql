@@ -0,0 +1,15 @@
+create table thread_mutes
+(
+    id               bigserial primary key,
+    owner_account_id bigint not null
+        constraint fk_owner_account_id
+            references accounts (id)
+            on update cascade on delete cascade,
+    site_name        varchar(64) not null,
+    board_code       varchar(64) not null,
+    thread_no        bigint      not null,
+    muted_until      timestamp with time zone not null
+);
+
+create unique index thread_mutes_unique_idx
+    on thread_mutes (owner_account_id, site_name, board_code, thread_no);
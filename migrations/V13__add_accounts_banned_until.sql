@@ -0,0 +1 @@
+ALTER TABLE accounts ADD COLUMN banned_until timestamp with time zone default null;
\ No newline at end of file
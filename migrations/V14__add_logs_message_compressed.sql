@@ -0,0 +1,2 @@
+ALTER TABLE logs ADD COLUMN message_compressed bytea default null;
+ALTER TABLE logs ADD COLUMN compressed boolean not null default false;
\ No newline at end of file
@@ -0,0 +1,13 @@
+create table web_push_subscriptions
+(
+    id                     bigserial primary key,
+    owner_account_token_id bigint not null
+        constraint fk_owner_account_token_id
+            references account_tokens (id)
+            on update cascade on delete cascade,
+    p256dh                 varchar(128) not null,
+    auth                   varchar(64)  not null
+);
+
+create unique index web_push_subscriptions_owner_account_token_id_idx
+    on web_push_subscriptions (owner_account_token_id);
@@ -0,0 +1,5 @@
+alter table threads
+    add column died_on timestamp with time zone default null;
+
+create index threads_died_on_idx
+    on threads (died_on);
@@ -0,0 +1,4 @@
+drop index if exists owner_account_id_idx;
+
+create index owner_account_id_idx
+    on account_tokens (owner_account_id);
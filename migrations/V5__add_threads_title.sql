@@ -0,0 +1,2 @@
+alter table threads
+    add column title varchar default null;
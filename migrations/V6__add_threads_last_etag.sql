@@ -0,0 +1 @@
+ALTER TABLE threads ADD COLUMN last_etag varchar;
@@ -0,0 +1,37 @@
+create table catalog_watches
+(
+    id                  bigserial primary key,
+    owner_account_id    bigint not null
+        constraint fk_owner_account_id
+            references accounts (id)
+            on update cascade on delete cascade,
+    site_name           varchar(64)  not null,
+    board_code          varchar(64)  not null,
+    filter              varchar(128) not null,
+    application_type    bigint       not null,
+    last_seen_thread_no bigint       not null default 0,
+    created_on          timestamp with time zone default (now() AT TIME ZONE 'utc'::text) not null
+);
+
+create unique index catalog_watches_unique_idx
+    on catalog_watches (owner_account_id, site_name, board_code, filter);
+
+create index catalog_watches_catalog_idx
+    on catalog_watches (site_name, board_code);
+
+create table catalog_thread_notifications
+(
+    id                            bigserial primary key,
+    owner_catalog_watch_id        bigint not null
+        constraint fk_owner_catalog_watch_id
+            references catalog_watches (id)
+            on update cascade on delete cascade,
+    thread_no                     bigint not null,
+    subject                       varchar default null,
+    notification_delivery_attempt smallint default 0,
+    notification_delivered_on     timestamp with time zone default null,
+    created_on                    timestamp with time zone default (now() AT TIME ZONE 'utc'::text) not null
+);
+
+create unique index catalog_thread_notifications_unique_idx
+    on catalog_thread_notifications (owner_catalog_watch_id, thread_no);
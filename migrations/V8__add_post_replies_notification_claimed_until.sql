@@ -0,0 +1 @@
+ALTER TABLE post_replies ADD COLUMN notification_claimed_until timestamp with time zone default null;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE post_watches ADD COLUMN filter_regex varchar default null;
\ No newline at end of file
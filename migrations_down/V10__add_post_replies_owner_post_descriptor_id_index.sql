@@ -0,0 +1 @@
+drop index if exists post_replies_owner_post_descriptor_id_idx;
\ No newline at end of file
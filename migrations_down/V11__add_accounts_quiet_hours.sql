@@ -0,0 +1,6 @@
+alter table accounts
+    drop column if exists quiet_hours_start;
+alter table accounts
+    drop column if exists quiet_hours_end;
+alter table accounts
+    drop column if exists tz_offset_minutes;
\ No newline at end of file
@@ -0,0 +1 @@
+drop table if exists thread_mutes;
\ No newline at end of file
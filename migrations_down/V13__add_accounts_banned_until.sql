@@ -0,0 +1,2 @@
+alter table accounts
+    drop column if exists banned_until;
\ No newline at end of file
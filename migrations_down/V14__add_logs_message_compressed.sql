@@ -0,0 +1,6 @@
+delete from logs
+    where compressed = true;
+alter table logs
+    drop column if exists compressed;
+alter table logs
+    drop column if exists message_compressed;
\ No newline at end of file
@@ -0,0 +1 @@
+drop table if exists web_push_subscriptions;
\ No newline at end of file
@@ -0,0 +1,4 @@
+drop index if exists threads_died_on_idx;
+
+alter table threads
+    drop column if exists died_on;
@@ -0,0 +1,2 @@
+alter table threads
+    drop column if exists last_etag;
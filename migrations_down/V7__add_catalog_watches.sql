@@ -0,0 +1,3 @@
+drop table if exists catalog_thread_notifications;
+
+drop table if exists catalog_watches;
@@ -0,0 +1,2 @@
+alter table post_replies
+    drop column if exists notification_claimed_until;
\ No newline at end of file
@@ -0,0 +1,2 @@
+alter table post_watches
+    drop column if exists filter_regex;
\ No newline at end of file
@@ -1,2 +1,34 @@
-pub static USER_ID_HASH_ITERATIONS: usize = 16;
-pub static MAX_POST_URL_LENGTH: usize = 256;
\ No newline at end of file
+pub static DEFAULT_USER_ID_HASH_ITERATIONS: usize = 16;
+pub static MAX_USER_ID_HASH_ITERATIONS: usize = 1024;
+pub static MIN_USER_ID_LENGTH: usize = 32;
+pub static MAX_USER_ID_LENGTH: usize = 128;
+// Hex encoded sha3-512 of the user_id
+pub static ACCOUNT_ID_LENGTH: usize = 128;
+pub static MAX_POST_URL_LENGTH: usize = 256;
+pub static MAX_SITE_NAME_LENGTH: usize = 32;
+pub static MAX_BOARD_CODE_LENGTH: usize = 32;
+pub static MAX_CATALOG_FILTER_LENGTH: usize = 128;
+pub static MAX_FILTER_REGEX_LENGTH: usize = 256;
+pub static MAX_WATCH_POSTS_PER_REQUEST: usize = 128;
+pub static MAX_LIST_ACCOUNTS_PAGE_SIZE: usize = 100;
+pub static MAX_BULK_EXTEND_EXPIRY_ACCOUNTS: usize = 256;
+pub static REPLAY_REPLIES_PERIOD_HOURS: i32 = 24;
+pub static MAX_THREAD_MUTE_MINUTES: i64 = 7 * 24 * 60;
+pub static MAX_BAN_DAYS: i64 = 10 * 365;
+pub static DEFAULT_MAX_WATCHES_PER_ACCOUNT: usize = 1000;
+pub static DEFAULT_MAX_REPLIES_PER_THREAD: usize = 20;
+pub static DEFAULT_DEAD_THREADS_RETENTION_DAYS: u64 = 7;
+pub static DEFAULT_INVITES_CLEANUP_INTERVAL_MINUTES: u64 = 60;
+
+pub static DEFAULT_MAX_REQUEST_BODY_SIZE: usize = 64 * 1024;
+pub static DEFAULT_SERVER_BIND_ADDR: &str = "0.0.0.0:3000";
+pub static DEFAULT_SITE_CONCURRENCY_LIMIT: usize = 4;
+pub static MIN_WATCHER_CHUNK_SIZE: usize = 16;
+pub static DEFAULT_WATCHER_MAX_CONCURRENCY: usize = 256;
+pub static MAX_WATCHER_CONCURRENCY: usize = 1024;
+pub static DEFAULT_BOARDS_CACHE_TTL_SECONDS: u64 = 60 * 60;
+pub static DEFAULT_HTTP_REQUEST_TIMEOUT_SECONDS: u64 = 30;
+pub static DEFAULT_HTTP_CONNECT_TIMEOUT_SECONDS: u64 = 10;
+pub static HTTP_POOL_MAX_IDLE_PER_HOST: usize = 64;
+pub static HTTP_TCP_KEEPALIVE_SECONDS: u64 = 60;
+pub static ACCOUNTS_CACHE_VERIFICATION_SAMPLE_SIZE: usize = 100;
\ No newline at end of file
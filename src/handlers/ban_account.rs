@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{constants, error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, collect_limited, empty_success_response, error_response_str, error_response_string, max_request_body_size};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository;
+use crate::model::repository::account_repository::{AccountId, BanAccountResult};
+
+/// Bans the account for [days] days, 0 days lifts the ban.
+#[derive(Serialize, Deserialize)]
+pub struct BanAccountRequest {
+    pub user_id: String,
+    pub days: i64
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: BanAccountRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into BanAccountRequest")?;
+
+    if request.days < 0 || request.days > constants::MAX_BAN_DAYS {
+        let error_message = format!("days must be in range 0..={}", constants::MAX_BAN_DAYS);
+
+        error!("ban_account() {}", error_message);
+
+        let response_json = error_response_string(ErrorCode::InvalidParameter, &error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+
+    let banned_until = if request.days > 0 {
+        Some(chrono::offset::Utc::now() + chrono::Duration::days(request.days))
+    } else {
+        None
+    };
+
+    let result = account_repository::ban_account(database, &account_id, banned_until)
+        .await
+        .with_context(|| {
+            return format!("Failed to ban account with account_id: \'{}\'", account_id);
+        })?;
+
+    if result != BanAccountResult::Ok {
+        let (error_code, error_message) = match result {
+            BanAccountResult::Ok => unreachable!(),
+            BanAccountResult::AccountDoesNotExist => (ErrorCode::AccountNotFound, "Account does not exist")
+        };
+
+        error!(
+            "ban_account() Failed to ban account_id \'{}\': \"{}\"",
+            account_id.format_token(),
+            error_message
+        );
+
+        let response_json = error_response_str(error_code, error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let response_json = empty_success_response()?;
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    info!(
+        "ban_account() Successfully banned account with account_id \'{}\' for {} days",
+        account_id.format_token(),
+        request.days
+    );
+
+    return Ok(response);
+}
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{constants, error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, ServerSuccessResponse, collect_limited, error_response_str, error_response_string, is_valid_days_count, max_request_body_size, success_response};
+use crate::helpers::serde_helpers::{deserialize_datetime, serialize_datetime_option};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository;
+use crate::model::repository::account_repository::AccountId;
+
+/// [account_ids] are the user ids the accounts were created with, same as user_id of the other
+/// account handlers.
+#[derive(Serialize, Deserialize)]
+pub struct BulkExtendExpiryRequest {
+    pub account_ids: Vec<String>,
+    pub extra_days: u64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BulkExtendExpiryResponse {
+    pub extended: Vec<ExtendedAccountResponse>,
+    pub not_found: Vec<String>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExtendedAccountResponse {
+    pub account_id: String,
+    #[serde(
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub valid_until: Option<DateTime<Utc>>
+}
+
+impl ServerSuccessResponse for BulkExtendExpiryResponse {
+
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: BulkExtendExpiryRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into BulkExtendExpiryRequest")?;
+
+    let extra_days = request.extra_days.min(i64::MAX as u64) as i64;
+
+    if !is_valid_days_count(extra_days) {
+        error!("bulk_extend_expiry() bad extra_days: {}", extra_days);
+
+        let response_json = error_response_str(ErrorCode::InvalidParameter, "extra_days must be in range 0..365")?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    if request.account_ids.is_empty() || request.account_ids.len() > constants::MAX_BULK_EXTEND_EXPIRY_ACCOUNTS {
+        let full_error_message = format!(
+            "account_ids count must be in range 1..{}",
+            constants::MAX_BULK_EXTEND_EXPIRY_ACCOUNTS
+        );
+
+        error!("bulk_extend_expiry() {}", full_error_message);
+
+        let response_json = error_response_string(ErrorCode::InvalidParameter, &full_error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let mut account_ids = Vec::<AccountId>::with_capacity(request.account_ids.len());
+    for user_id in &request.account_ids {
+        account_ids.push(AccountId::from_user_id(user_id)?);
+    }
+
+    let extended_accounts = account_repository::bulk_extend_account_expiry(
+        database,
+        &account_ids,
+        extra_days
+    )
+        .await
+        .with_context(|| {
+            return format!("Failed to extend expiry date of {} accounts", account_ids.len());
+        })?;
+
+    let mut extended = Vec::<ExtendedAccountResponse>::with_capacity(extended_accounts.len());
+    let mut not_found = Vec::<String>::new();
+
+    for account_id in &account_ids {
+        let formatted_account_id = account_id.format_token().to_string();
+
+        match extended_accounts.get(account_id) {
+            Some(valid_until) => {
+                extended.push(ExtendedAccountResponse {
+                    account_id: formatted_account_id,
+                    valid_until: Some(valid_until.clone())
+                });
+            }
+            None => {
+                not_found.push(formatted_account_id);
+            }
+        }
+    }
+
+    let extended_count = extended.len();
+    let not_found_count = not_found.len();
+    let bulk_extend_expiry_response = BulkExtendExpiryResponse { extended, not_found };
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(success_response(bulk_extend_expiry_response)?)))?;
+
+    info!(
+        "bulk_extend_expiry() extra_days: {}, extended: {}, not_found: {}",
+        extra_days,
+        extended_count,
+        not_found_count
+    );
+
+    return Ok(response);
+}
@@ -1,13 +1,13 @@
 use std::sync::Arc;
 
 use anyhow::Context;
-use http_body_util::{BodyExt, Full};
+use http_body_util::Full;
 use hyper::body::{Bytes, Incoming};
 use hyper::Response;
 use serde::{Deserialize, Serialize};
 
 use crate::{error, info};
-use crate::handlers::shared::{ContentType, empty_success_response, error_response_str};
+use crate::handlers::shared::{ContentType, ErrorCode, collect_limited, empty_success_response, error_response_str, is_valid_days_count, max_request_body_size};
 use crate::helpers::string_helpers::FormatToken;
 use crate::model::database::db::Database;
 use crate::model::repository::account_repository::{AccountId, CreateAccountResult};
@@ -24,10 +24,7 @@ pub async fn handle(
     body: Incoming,
     database: &Arc<Database>
 ) -> anyhow::Result<Response<Full<Bytes>>> {
-    let body_bytes = body.collect()
-        .await
-        .context("Failed to collect body")?
-        .to_bytes();
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
 
     let body_as_string = String::from_utf8(body_bytes.to_vec())
         .context("Failed to convert body into a string")?;
@@ -38,10 +35,10 @@ pub async fn handle(
     let account_id = AccountId::from_user_id(&request.user_id)?;
     let valid_for_days = request.valid_for_days as i64;
 
-    if valid_for_days <= 0 || valid_for_days > 365 {
+    if !is_valid_days_count(valid_for_days) {
         error!("create_account() bad valid_for_days: {}", valid_for_days);
 
-        let response_json = error_response_str("valid_for_days must be in range 0..365")?;
+        let response_json = error_response_str(ErrorCode::InvalidParameter, "valid_for_days must be in range 0..365")?;
         let response = Response::builder()
             .json()
             .status(200)
@@ -56,9 +53,10 @@ pub async fn handle(
         .await?;
 
     if result != CreateAccountResult::Ok {
-        let error_message = match result {
+        let (error_code, error_message) = match result {
             CreateAccountResult::Ok => unreachable!(),
-            CreateAccountResult::AccountAlreadyExists => "Account already exists"
+            CreateAccountResult::AccountAlreadyExists => (ErrorCode::AccountAlreadyExists, "Account already exists"),
+            CreateAccountResult::AccountIsBanned => (ErrorCode::AccountBanned, "Account is banned")
         };
 
         let full_error_message = format!(
@@ -69,7 +67,7 @@ pub async fn handle(
 
         error!("create_account() {}", full_error_message);
 
-        let response_json = error_response_str("Account already exists")?;
+        let response_json = error_response_str(error_code, error_message)?;
         let response = Response::builder()
             .json()
             .status(200)
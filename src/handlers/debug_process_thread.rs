@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, ServerSuccessResponse, collect_limited, error_response_string, max_request_body_size, success_response};
+use crate::model::data::chan::ThreadDescriptor;
+use crate::model::database::db::Database;
+use crate::model::repository::site_repository::SiteRepository;
+use crate::service::thread_watcher::{ThreadProcessingReport, ThreadWatcher};
+
+#[derive(Serialize, Deserialize)]
+pub struct DebugProcessThreadRequest {
+    pub site_name: String,
+    pub board_code: String,
+    pub thread_no: u64
+}
+
+impl ServerSuccessResponse for ThreadProcessingReport {
+
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: DebugProcessThreadRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into DebugProcessThreadRequest")?;
+
+    let thread_descriptor = ThreadDescriptor::new(
+        request.site_name.clone(),
+        request.board_code.clone(),
+        request.thread_no
+    );
+
+    if site_repository.by_site_descriptor(thread_descriptor.site_descriptor()).is_none() {
+        let full_error_message = format!("Site \'{}\' is not supported", request.site_name);
+
+        let response_json = error_response_string(ErrorCode::SiteNotSupported, &full_error_message)?;
+        error!("debug_process_thread() {}", full_error_message);
+
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let report = ThreadWatcher::process_single_thread(&thread_descriptor, database, site_repository)
+        .await
+        .context(format!("Failed to process thread {}", thread_descriptor))?;
+
+    info!("debug_process_thread({}) report: {:?}", thread_descriptor, report);
+
+    let response_json = success_response(report)?;
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    return Ok(response);
+}
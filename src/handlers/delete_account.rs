@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, ServerSuccessResponse, collect_limited, error_response_str, max_request_body_size, success_response, throttle_account};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository;
+use crate::model::repository::account_repository::{AccountId, DeleteAccountResult};
+use crate::router::TestContext;
+
+#[derive(Serialize, Deserialize)]
+pub struct DeleteAccountRequest {
+    pub user_id: String
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeleteAccountResponse {
+    pub deleted_post_watches: u64
+}
+
+impl ServerSuccessResponse for DeleteAccountResponse {
+
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    test_context: Option<TestContext>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: DeleteAccountRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into DeleteAccountRequest")?;
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/delete_account").await?;
+
+    let result = account_repository::delete_account(database, &account_id)
+        .await
+        .with_context(|| {
+            return format!(
+                "Failed to delete account with account_id: \'{}\'",
+                account_id
+            );
+        })?;
+
+    let deleted_post_watches = match result {
+        DeleteAccountResult::Ok(deleted_post_watches) => deleted_post_watches,
+        DeleteAccountResult::AccountDoesNotExist => {
+            error!(
+                "delete_account() account with account_id \'{}\' does not exist",
+                account_id.format_token()
+            );
+
+            let response_json = error_response_str(ErrorCode::AccountNotFound, "Account does not exist")?;
+            let response = Response::builder()
+                .json()
+                .status(200)
+                .body(Full::new(Bytes::from(response_json)))?;
+
+            return Ok(response);
+        }
+    };
+
+    let delete_account_response = DeleteAccountResponse { deleted_post_watches };
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(success_response(delete_account_response)?)))?;
+
+    info!(
+        "delete_account() Successfully deleted account. account_id: \'{}\', deleted_post_watches: {}",
+        account_id.format_token(),
+        deleted_post_watches
+    );
+
+    return Ok(response);
+}
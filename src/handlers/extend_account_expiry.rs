@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, RequestFailed, ServerSuccessResponse, collect_limited, error_response_str, max_request_body_size, success_response, throttle_account};
+use crate::helpers::serde_helpers::{deserialize_datetime, serialize_datetime_option};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository::AccountId;
+use crate::model::repository::invites_repository;
+use crate::model::repository::invites_repository::ExtendAccountExpiryResult;
+use crate::router::TestContext;
+
+#[derive(Serialize, Deserialize)]
+pub struct ExtendAccountExpiryRequest {
+    pub user_id: String,
+    pub invite: String
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExtendAccountExpiryResponse {
+    #[serde(
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub valid_until: Option<DateTime<Utc>>
+}
+
+impl ServerSuccessResponse for ExtendAccountExpiryResponse {
+
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    test_context: Option<TestContext>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: ExtendAccountExpiryRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into ExtendAccountExpiryRequest")?;
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/extend_account_expiry").await?;
+
+    if request.invite.is_empty() {
+        error!("extend_account_expiry() invite is empty");
+
+        let response_json = error_response_str(ErrorCode::InvalidParameter, "invite is empty")?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let result = invites_repository::extend_account_expiry(
+        &request.invite,
+        &account_id,
+        database
+    )
+        .await
+        .with_context(|| {
+            return format!(
+                "Failed to extend account expiry date for account with account_id: \'{}\'",
+                account_id
+            );
+        })?;
+
+    let valid_until = match result {
+        ExtendAccountExpiryResult::Ok(valid_until) => valid_until,
+        ExtendAccountExpiryResult::AccountDoesNotExist |
+        ExtendAccountExpiryResult::InviteIsNotValid => {
+            let (error_code, error_message) = match result {
+                ExtendAccountExpiryResult::Ok(_) => unreachable!(),
+                ExtendAccountExpiryResult::AccountDoesNotExist => (ErrorCode::AccountNotFound, "Account does not exist"),
+                ExtendAccountExpiryResult::InviteIsNotValid => (ErrorCode::InviteNotValid, "Invite does not exist or already expired")
+            };
+
+            error!(
+                "extend_account_expiry() Failed to extend account expiry date for account_id \'{}\': \"{}\"",
+                account_id.format_token(),
+                error_message
+            );
+
+            let response_json = error_response_str(error_code, error_message)?;
+            let mut response_builder = Response::builder()
+                .json()
+                .status(200);
+
+            if result == ExtendAccountExpiryResult::InviteIsNotValid {
+                // Counts towards the lockout of the IP so that invites can't be brute forced
+                response_builder = response_builder.extension(RequestFailed);
+            }
+
+            let response = response_builder.body(Full::new(Bytes::from(response_json)))?;
+            return Ok(response);
+        }
+    };
+
+    let response_json = success_response(ExtendAccountExpiryResponse { valid_until: Some(valid_until) })?;
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    info!(
+        "extend_account_expiry() Successfully extended account expiry date. \
+        account_id: \'{}\', valid_until: {:?}",
+        account_id.format_token(),
+        valid_until
+    );
+
+    return Ok(response);
+}
@@ -1,12 +1,12 @@
 use std::sync::Arc;
 
 use anyhow::Context;
-use http_body_util::{BodyExt, Full};
+use http_body_util::Full;
 use hyper::body::{Bytes, Incoming};
 use hyper::Response;
 use serde::{Deserialize, Serialize};
 
-use crate::handlers::shared::{ContentType, ServerSuccessResponse, success_response};
+use crate::handlers::shared::{ContentType, ServerSuccessResponse, collect_limited, max_request_body_size, success_response};
 use crate::info;
 use crate::model::database::db::Database;
 use crate::model::repository::invites_repository;
@@ -31,10 +31,7 @@ pub async fn handle(
     database: &Arc<Database>,
     host_address: &String
 ) -> anyhow::Result<Response<Full<Bytes>>> {
-    let body_bytes = body.collect()
-        .await
-        .context("Failed to collect body")?
-        .to_bytes();
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
 
     let body_as_string = String::from_utf8(body_bytes.to_vec())
         .context("Failed to convert body into a string")?;
@@ -2,19 +2,20 @@ use std::sync::Arc;
 
 use anyhow::Context;
 use chrono::{DateTime, Utc};
-use http_body_util::{BodyExt, Full};
+use http_body_util::Full;
 use hyper::body::{Bytes, Incoming};
 use hyper::Response;
 use serde::{Deserialize, Serialize};
 
 use crate::{error, info};
-use crate::handlers::shared::{ContentType, error_response_str, error_response_string, ServerSuccessResponse, success_response};
+use crate::handlers::shared::{ContentType, ErrorCode, ServerSuccessResponse, collect_limited, error_response_str, error_response_string, max_request_body_size, success_response, throttle_account};
 use crate::helpers::serde_helpers::{deserialize_datetime, serialize_datetime_option};
 use crate::helpers::serde_helpers::{deserialize_application_type, serialize_application_type};
 use crate::helpers::string_helpers::FormatToken;
 use crate::model::database::db::Database;
 use crate::model::repository::account_repository;
 use crate::model::repository::account_repository::{AccountId, ApplicationType};
+use crate::router::TestContext;
 
 #[derive(Serialize, Deserialize)]
 pub struct AccountInfoRequest {
@@ -44,12 +45,10 @@ impl ServerSuccessResponse for AccountInfoResponse {
 pub async fn handle(
     _query: &str,
     body: Incoming,
-    database: &Arc<Database>
+    database: &Arc<Database>,
+    test_context: Option<TestContext>
 ) -> anyhow::Result<Response<Full<Bytes>>> {
-    let body_bytes = body.collect()
-        .await
-        .context("Failed to collect body")?
-        .to_bytes();
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
 
     let body_as_string = String::from_utf8(body_bytes.to_vec())
         .context("Failed to convert body into a string")?;
@@ -66,7 +65,7 @@ pub async fn handle(
 
         error!("get_account_info() {}", error_message);
 
-        let response_json = error_response_string(&error_message)?;
+        let response_json = error_response_string(ErrorCode::UnsupportedApplicationType, &error_message)?;
         let response = Response::builder()
             .json()
             .status(200)
@@ -76,6 +75,7 @@ pub async fn handle(
     }
 
     let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/get_account_info").await?;
 
     let account = account_repository::get_account(&account_id, database)
         .await
@@ -92,7 +92,7 @@ pub async fn handle(
             account_id.format_token()
         );
 
-        let response_json = error_response_str("Account does not exist")?;
+        let response_json = error_response_str(ErrorCode::AccountNotFound, "Account does not exist")?;
         let response = Response::builder()
             .json()
             .status(200)
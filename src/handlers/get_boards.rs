@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::handlers::shared::{ContentType, ServerSuccessResponse, success_response};
+use crate::model::data::chan::ChanBoard;
+use crate::model::repository::site_repository::SiteRepository;
+
+#[derive(Serialize, Deserialize)]
+pub struct GetBoardsResponse {
+    pub sites: Vec<SiteBoardsResponse>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SiteBoardsResponse {
+    pub site_name: String,
+    pub boards: Vec<ChanBoard>
+}
+
+impl ServerSuccessResponse for GetBoardsResponse {
+
+}
+
+pub async fn handle(
+    _query: &str,
+    _: Incoming,
+    site_repository: &Arc<SiteRepository>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let sites = site_repository.get_boards()
+        .await
+        .into_iter()
+        .map(|(site_name, boards)| SiteBoardsResponse { site_name, boards })
+        .collect::<Vec<SiteBoardsResponse>>();
+
+    let response_json = success_response(GetBoardsResponse { sites })?;
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    return Ok(response);
+}
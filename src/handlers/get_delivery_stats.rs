@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, ServerSuccessResponse, collect_limited, error_response_str, max_request_body_size, success_response};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::{account_repository, post_reply_repository};
+use crate::model::repository::account_repository::AccountId;
+
+#[derive(Serialize, Deserialize)]
+pub struct GetDeliveryStatsRequest {
+    pub user_id: String
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetDeliveryStatsResponse {
+    pub total: i64,
+    pub delivered: i64,
+    pub pending: i64,
+    pub failed: i64
+}
+
+impl ServerSuccessResponse for GetDeliveryStatsResponse {
+
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: GetDeliveryStatsRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into GetDeliveryStatsRequest")?;
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+
+    let account = account_repository::get_account(&account_id, database).await?;
+    if account.is_none() {
+        error!(
+            "get_delivery_stats() account with account_id \'{}\' does not exist",
+            account_id.format_token()
+        );
+
+        let response_json = error_response_str(ErrorCode::AccountNotFound, "Account does not exist")?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let account_db_id = { account.unwrap().lock().await.id };
+
+    let delivery_stats = post_reply_repository::get_delivery_stats(account_db_id, database)
+        .await
+        .with_context(|| {
+            return format!(
+                "Failed to get delivery stats for account with account_id: \'{}\'",
+                account_id
+            );
+        })?;
+
+    let get_delivery_stats_response = GetDeliveryStatsResponse {
+        total: delivery_stats.total,
+        delivered: delivery_stats.delivered,
+        pending: delivery_stats.pending,
+        failed: delivery_stats.failed
+    };
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(success_response(get_delivery_stats_response)?)))?;
+
+    info!(
+        "get_delivery_stats() account_id: \'{}\', stats: {:?}",
+        account_id.format_token(),
+        delivery_stats
+    );
+
+    return Ok(response);
+}
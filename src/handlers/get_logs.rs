@@ -3,22 +3,37 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use chrono::{DateTime, Utc};
-use http_body_util::Full;
-use hyper::body::{Bytes, Incoming};
+use http_body_util::{BodyExt, Full, StreamBody};
+use hyper::body::{Bytes, Frame, Incoming};
 use hyper::Response;
 use serde::Serialize;
 
 use crate::{error, info};
-use crate::handlers::shared::{ContentType, error_response_str, ServerSuccessResponse, success_response};
+use crate::handlers::shared::{ContentType, ErrorCode, error_response_str, full_body, ResponseBody, ServerSuccessResponse, success_response};
 use crate::helpers::serde_helpers::serialize_datetime;
 use crate::model::database::db::Database;
 use crate::model::repository::logs_repository;
+use crate::model::repository::logs_repository::LogLine;
+
+// How many log lines are loaded from the database per chunk when streaming NDJSON
+const NDJSON_BATCH_SIZE: i64 = 500;
 
 #[derive(Serialize)]
 struct GetLogsResponse {
     log_lines: Vec<LogLineResponse>
 }
 
+enum LogsFormat {
+    Json,
+    Ndjson
+}
+
+struct NdjsonStreamState {
+    database: Arc<Database>,
+    last_id: i64,
+    remaining: i64
+}
+
 #[derive(Serialize)]
 struct LogLineResponse {
     id: i64,
@@ -33,14 +48,26 @@ impl ServerSuccessResponse for GetLogsResponse {
 
 }
 
+impl LogLineResponse {
+    fn from_log_line(log_line: &LogLine) -> LogLineResponse {
+        return LogLineResponse {
+            id: log_line.id,
+            log_time: log_line.log_time.clone(),
+            log_level: log_line.log_level.clone(),
+            target: log_line.target.clone(),
+            message: log_line.message.clone(),
+        }
+    }
+}
+
 pub async fn handle(
     query: &str,
     _: Incoming,
     database: &Arc<Database>
-) -> anyhow::Result<Response<Full<Bytes>>> {
+) -> anyhow::Result<Response<ResponseBody>> {
     let params = query
         .split('&')
-        .take(2)
+        .take(3)
         .filter_map(|parameter| {
             let key_value = parameter.split('=').take(2).collect::<Vec<&str>>();
 
@@ -57,17 +84,18 @@ pub async fn handle(
 
     let num_str = params.get("num").unwrap_or(&"");
     let last_id_str = params.get("last_id").unwrap_or(&"");
+    let format_str = params.get("format").unwrap_or(&"");
 
     if num_str.is_empty() {
         error!("get_logs() Num parameter not found");
 
-        let response_json = error_response_str("Num parameter not found")?;
+        let response_json = error_response_str(ErrorCode::InvalidParameter, "Num parameter not found")?;
         let response = Response::builder()
             .json()
             .status(200)
             .body(Full::new(Bytes::from(response_json)))?;
 
-        return Ok(response);
+        return Ok(full_body(response));
     }
 
     let num = i64::from_str(num_str);
@@ -75,29 +103,50 @@ pub async fn handle(
         let error_message = format!("Failed to convert num \'{}\' to number", num_str);
         error!("get_logs() {}", error_message);
 
-        let response_json = error_response_str(&error_message)?;
+        let response_json = error_response_str(ErrorCode::InvalidParameter, &error_message)?;
         let response = Response::builder()
             .json()
             .status(200)
             .body(Full::new(Bytes::from(response_json)))?;
 
-        return Ok(response);
+        return Ok(full_body(response));
     }
 
+    let format = match *format_str {
+        "" | "json" => LogsFormat::Json,
+        "ndjson" => LogsFormat::Ndjson,
+        _ => {
+            let error_message = format!("Unknown format \'{}\'", format_str);
+            error!("get_logs() {}", error_message);
+
+            let response_json = error_response_str(ErrorCode::InvalidParameter, &error_message)?;
+            let response = Response::builder()
+                .json()
+                .status(200)
+                .body(Full::new(Bytes::from(response_json)))?;
+
+            return Ok(full_body(response));
+        }
+    };
+
     let num = num.unwrap();
     let last_id = i64::from_str(last_id_str).unwrap_or(i64::MAX);
 
+    if let LogsFormat::Ndjson = format {
+        let response = Response::builder()
+            .ndjson()
+            .status(200)
+            .body(ndjson_body(num, last_id, database))?;
+
+        info!("get_logs() Streaming logs as NDJSON");
+        return Ok(response);
+    }
+
     let log_lines = logs_repository::get_logs(num, last_id, database).await?;
 
-    let log_lines_response = log_lines.iter().map(|log_line| {
-        return LogLineResponse {
-            id: log_line.id,
-            log_time: log_line.log_time.clone(),
-            log_level: log_line.log_level.clone(),
-            target: log_line.target.clone(),
-            message: log_line.message.clone(),
-        }
-    }).collect::<Vec<LogLineResponse>>();
+    let log_lines_response = log_lines.iter()
+        .map(|log_line| LogLineResponse::from_log_line(log_line))
+        .collect::<Vec<LogLineResponse>>();
 
     let get_logs_response = GetLogsResponse {
         log_lines: log_lines_response
@@ -109,5 +158,74 @@ pub async fn handle(
         .body(Full::new(Bytes::from(success_response(get_logs_response)?)))?;
 
     info!("get_logs() Success");
-    return Ok(response);
+    return Ok(full_body(response));
+}
+
+/// Loads the logs in batches of [NDJSON_BATCH_SIZE] lines and sends every batch as soon as it's
+/// loaded so that neither the whole log nor the whole response has to be kept in memory. Every
+/// line of the body is a separate JSON object. Since the status has already been sent by the time
+/// a batch fails to load, the error terminates the stream.
+fn ndjson_body(num: i64, last_id: i64, database: &Arc<Database>) -> ResponseBody {
+    let initial_state = NdjsonStreamState {
+        database: database.clone(),
+        last_id,
+        remaining: num
+    };
+
+    let stream = futures::stream::unfold(Some(initial_state), |state| async move {
+        if state.is_none() {
+            return None;
+        }
+
+        let mut state = state.unwrap();
+        if state.remaining <= 0 {
+            return None;
+        }
+
+        let batch_size = state.remaining.min(NDJSON_BATCH_SIZE);
+
+        let log_lines = logs_repository::get_logs(batch_size, state.last_id, &state.database).await;
+        if log_lines.is_err() {
+            let error = log_lines.err().unwrap();
+            error!("get_logs() Failed to load a batch of logs, error: {}", error);
+
+            return Some((Err(error), None));
+        }
+
+        let log_lines = log_lines.unwrap();
+        if log_lines.is_empty() {
+            return None;
+        }
+
+        let chunk = log_lines_to_ndjson(&log_lines);
+        if chunk.is_err() {
+            let error = chunk.err().unwrap();
+            error!("get_logs() Failed to serialize a batch of logs, error: {}", error);
+
+            return Some((Err(error), None));
+        }
+
+        state.remaining -= log_lines.len() as i64;
+        state.last_id = log_lines.last().unwrap().id;
+
+        // The database has no more logs to give us
+        if (log_lines.len() as i64) < batch_size {
+            state.remaining = 0;
+        }
+
+        return Some((Ok(Frame::data(chunk.unwrap())), Some(state)));
+    });
+
+    return StreamBody::new(stream).boxed_unsync();
+}
+
+fn log_lines_to_ndjson(log_lines: &Vec<LogLine>) -> anyhow::Result<Bytes> {
+    let mut chunk = Vec::with_capacity(log_lines.len() * 128);
+
+    for log_line in log_lines {
+        serde_json::to_writer(&mut chunk, &LogLineResponse::from_log_line(log_line))?;
+        chunk.push(b'\n');
+    }
+
+    return Ok(Bytes::from(chunk));
 }
\ No newline at end of file
@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, ServerSuccessResponse, collect_limited, error_response_str, error_response_string, max_request_body_size, success_response, throttle_account};
+use crate::helpers::serde_helpers::{deserialize_application_type, serialize_application_type};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::{account_repository, post_reply_repository};
+use crate::model::repository::account_repository::{AccountId, ApplicationType};
+use crate::model::repository::site_repository::SiteRepository;
+use crate::router::TestContext;
+
+#[derive(Serialize, Deserialize)]
+pub struct GetPendingRepliesRequest {
+    pub user_id: String,
+    #[serde(
+        serialize_with = "serialize_application_type",
+        deserialize_with = "deserialize_application_type"
+    )]
+    pub application_type: ApplicationType,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetPendingRepliesResponse {
+    pub replies: Vec<PendingReplyResponse>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PendingReplyResponse {
+    pub reply_id: u64,
+    pub new_reply_url: String,
+    pub watched_post_url: String,
+    pub thread_title: Option<String>
+}
+
+impl ServerSuccessResponse for GetPendingRepliesResponse {
+
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>,
+    test_context: Option<TestContext>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: GetPendingRepliesRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into GetPendingRepliesRequest")?;
+
+    let application_type = request.application_type;
+    if application_type == ApplicationType::Unknown {
+        let error_message = format!(
+            "Unsupported \'application_type\' parameter value: {}",
+            application_type as isize
+        );
+
+        error!("get_pending_replies() {}", error_message);
+
+        let response_json = error_response_string(ErrorCode::UnsupportedApplicationType, &error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/get_pending_replies").await?;
+
+    let account = account_repository::get_account(&account_id, database)
+        .await
+        .with_context(|| {
+            return format!(
+                "get_pending_replies() Failed to get account from repository with account_id \'{}\'",
+                account_id.format_token()
+            );
+        })?;
+
+    if account.is_none() {
+        error!(
+            "get_pending_replies() Account with id \'{}\' does not exist",
+            account_id.format_token()
+        );
+
+        let response_json = error_response_str(ErrorCode::AccountNotFound, "Account does not exist")?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let account = account.unwrap();
+
+    let is_valid = { account.lock().await.is_valid(&application_type) };
+    if !is_valid {
+        error!(
+            "get_pending_replies() Account with id \'{}\' is not valid",
+            account_id.format_token()
+        );
+
+        let response_json = error_response_str(ErrorCode::AccountExpired, "Account is not valid")?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let pending_replies = post_reply_repository::get_pending_replies(
+        &account_id,
+        &application_type,
+        database
+    )
+        .await
+        .with_context(|| {
+            return format!(
+                "get_pending_replies() Failed to get pending replies for account_id \'{}\'",
+                account_id.format_token()
+            );
+        })?;
+
+    let replies = pending_replies.iter()
+        .filter_map(|pending_reply| {
+            let new_reply_url = site_repository.to_url(&pending_reply.post_descriptor);
+            if new_reply_url.is_none() {
+                return None;
+            }
+
+            let watched_post_url = site_repository.to_url(&pending_reply.reply_to);
+            if watched_post_url.is_none() {
+                return None;
+            }
+
+            let pending_reply_response = PendingReplyResponse {
+                reply_id: pending_reply.post_reply_id as u64,
+                new_reply_url: new_reply_url.unwrap(),
+                watched_post_url: watched_post_url.unwrap(),
+                thread_title: pending_reply.thread_title.clone()
+            };
+
+            return Some(pending_reply_response);
+        })
+        .collect::<Vec<PendingReplyResponse>>();
+
+    let replies_count = replies.len();
+
+    let response_json = success_response(GetPendingRepliesResponse { replies })?;
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    info!(
+        "get_pending_replies() Success \'{}\', pending replies: {}",
+        account_id.format_token(),
+        replies_count
+    );
+
+    return Ok(response);
+}
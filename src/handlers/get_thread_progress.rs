@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, ServerSuccessResponse, collect_limited, error_response_str, error_response_string, max_request_body_size, success_response, throttle_account, validate_post_url};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::{account_repository, thread_repository};
+use crate::model::repository::account_repository::AccountId;
+use crate::model::repository::site_repository::SiteRepository;
+use crate::router::TestContext;
+
+#[derive(Serialize, Deserialize)]
+pub struct GetThreadProgressRequest {
+    pub user_id: String,
+    pub post_url: String
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetThreadProgressResponse {
+    pub last_processed_post_no: Option<u64>,
+    pub last_processed_post_sub_no: Option<u64>
+}
+
+impl ServerSuccessResponse for GetThreadProgressResponse {
+
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>,
+    test_context: Option<TestContext>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: GetThreadProgressRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into GetThreadProgressRequest")?;
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/get_thread_progress").await?;
+    let post_url = validate_post_url(&request.post_url)?;
+
+    let account = account_repository::get_account(&account_id, database).await?;
+    if account.is_none() {
+        error!(
+            "get_thread_progress() account with account_id \'{}\' does not exist",
+            account_id.format_token()
+        );
+
+        let response_json = error_response_str(ErrorCode::AccountNotFound, "Account does not exist")?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let imageboard = site_repository.by_url(post_url);
+    if imageboard.is_none() {
+        let full_error_message = format!("Site for url \'{}\' is not supported", post_url);
+
+        let response_json = error_response_string(ErrorCode::SiteNotSupported, &full_error_message)?;
+        error!("get_thread_progress() {}", full_error_message);
+
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let imageboard = imageboard.unwrap();
+
+    let post_descriptor = imageboard.post_url_to_post_descriptor(post_url);
+    if post_descriptor.is_none() {
+        let full_error_message = format!("Failed to parse \'{}\' url as post url", post_url);
+
+        let response_json = error_response_string(ErrorCode::InvalidPostUrl, &full_error_message)?;
+        error!("get_thread_progress() {}", full_error_message);
+
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let thread_descriptor = post_descriptor.unwrap().thread_descriptor;
+
+    let last_processed_post = thread_repository::get_last_processed_post(&thread_descriptor, database)
+        .await
+        .with_context(|| {
+            return format!("Failed to get last processed post for thread {}", thread_descriptor);
+        })?;
+
+    let get_thread_progress_response = GetThreadProgressResponse {
+        last_processed_post_no: last_processed_post.as_ref().map(|post_descriptor| post_descriptor.post_no),
+        last_processed_post_sub_no: last_processed_post.as_ref().map(|post_descriptor| post_descriptor.post_sub_no)
+    };
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(success_response(get_thread_progress_response)?)))?;
+
+    info!(
+        "get_thread_progress() account_id: \'{}\', thread: {}, last_processed_post: {:?}",
+        account_id.format_token(),
+        thread_descriptor,
+        last_processed_post
+    );
+
+    return Ok(response);
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, ServerSuccessResponse, collect_limited, error_response_string, max_request_body_size, success_response, validate_post_url};
+use crate::model::database::db::Database;
+use crate::model::repository::site_repository::SiteRepository;
+use crate::service::thread_watcher::{ThreadSnapshot, ThreadWatcher};
+
+#[derive(Serialize, Deserialize)]
+pub struct GetThreadSnapshotRequest {
+    pub post_url: String
+}
+
+impl ServerSuccessResponse for ThreadSnapshot {
+
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: GetThreadSnapshotRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into GetThreadSnapshotRequest")?;
+
+    let post_url = validate_post_url(&request.post_url)?;
+
+    let imageboard = site_repository.by_url(post_url);
+    if imageboard.is_none() {
+        let full_error_message = format!("Site for url \'{}\' is not supported", post_url);
+
+        let response_json = error_response_string(ErrorCode::SiteNotSupported, &full_error_message)?;
+        error!("get_thread_snapshot() {}", full_error_message);
+
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let post_descriptor = imageboard.unwrap().post_url_to_post_descriptor(post_url);
+    if post_descriptor.is_none() {
+        let full_error_message = format!("Failed to parse \'{}\' url as post url", post_url);
+
+        let response_json = error_response_string(ErrorCode::InvalidPostUrl, &full_error_message)?;
+        error!("get_thread_snapshot() {}", full_error_message);
+
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let thread_descriptor = post_descriptor.unwrap().thread_descriptor;
+
+    let snapshot = ThreadWatcher::load_thread_snapshot(&thread_descriptor, database, site_repository)
+        .await
+        .context(format!("Failed to load snapshot of thread {}", thread_descriptor))?;
+
+    info!(
+        "get_thread_snapshot({}) load_result: {}, posts: {}",
+        thread_descriptor,
+        snapshot.load_result,
+        snapshot.posts.len()
+    );
+
+    let response_json = success_response(snapshot)?;
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    return Ok(response);
+}
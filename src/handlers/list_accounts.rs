@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{constants, info};
+use crate::handlers::shared::{ContentType, ServerSuccessResponse, collect_limited, max_request_body_size, success_response};
+use crate::helpers::serde_helpers::{deserialize_application_type, deserialize_datetime, serialize_application_type, serialize_datetime_option};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository;
+use crate::model::repository::account_repository::ApplicationType;
+
+#[derive(Serialize, Deserialize)]
+pub struct ListAccountsRequest {
+    pub page: u64,
+    pub page_size: u64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ListAccountsResponse {
+    pub page: u64,
+    pub page_size: u64,
+    pub accounts: Vec<AccountSummaryResponse>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AccountSummaryResponse {
+    pub account_id: String,
+    #[serde(
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub valid_until: Option<DateTime<Utc>>,
+    pub tokens: Vec<ApplicationTokensCountResponse>,
+    pub watches_count: i64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ApplicationTokensCountResponse {
+    #[serde(
+        serialize_with = "serialize_application_type",
+        deserialize_with = "deserialize_application_type"
+    )]
+    pub application_type: ApplicationType,
+    pub count: i64
+}
+
+impl ServerSuccessResponse for ListAccountsResponse {
+
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: ListAccountsRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into ListAccountsRequest")?;
+
+    let page_size = request.page_size.clamp(1, constants::MAX_LIST_ACCOUNTS_PAGE_SIZE as u64);
+    let page = request.page.min((i64::MAX as u64) / page_size);
+
+    let account_summaries = account_repository::list_accounts(
+        page as i64,
+        page_size as i64,
+        database
+    )
+        .await
+        .with_context(|| {
+            return format!("Failed to list accounts, page: {}, page_size: {}", page, page_size);
+        })?;
+
+    let accounts = account_summaries.iter()
+        .map(|account_summary| {
+            let tokens = account_summary.tokens_count.iter()
+                .map(|(application_type, count)| {
+                    return ApplicationTokensCountResponse {
+                        application_type: application_type.clone(),
+                        count: *count
+                    };
+                })
+                .collect::<Vec<ApplicationTokensCountResponse>>();
+
+            return AccountSummaryResponse {
+                account_id: account_summary.account_id.format_token().to_string(),
+                valid_until: account_summary.valid_until,
+                tokens,
+                watches_count: account_summary.watches_count
+            };
+        })
+        .collect::<Vec<AccountSummaryResponse>>();
+
+    let accounts_count = accounts.len();
+    let list_accounts_response = ListAccountsResponse { page, page_size, accounts };
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(success_response(list_accounts_response)?)))?;
+
+    info!(
+        "list_accounts() page: {}, page_size: {}, accounts: {}",
+        page,
+        page_size,
+        accounts_count
+    );
+
+    return Ok(response);
+}
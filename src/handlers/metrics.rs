@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+
+use crate::handlers::shared::ContentType;
+use crate::model::database::db::Database;
+use crate::service::metrics;
+use crate::service::metrics::DatabasePoolState;
+
+pub async fn handle(
+    _query: &str,
+    _: Incoming,
+    database: &Arc<Database>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let pool_state = database.pool_state();
+
+    let database_pool_state = DatabasePoolState {
+        connections: pool_state.connections,
+        idle_connections: pool_state.idle_connections
+    };
+
+    let response = Response::builder()
+        .content_type("text/plain; version=0.0.4")
+        .status(200)
+        .body(Full::new(Bytes::from(metrics::encode(&database_pool_state))))?;
+
+    return Ok(response);
+}
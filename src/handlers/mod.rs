@@ -1,12 +1,35 @@
 pub mod index;
 pub mod create_account;
 pub mod update_account_expiry_date;
+pub mod extend_account_expiry;
 pub mod update_firebase_token;
 pub mod get_account_info;
+pub mod get_pending_replies;
 pub mod watch_post;
+pub mod watch_posts;
 pub mod unwatch_post;
+pub mod watch_catalog;
 pub mod update_message_delivered;
 pub mod get_logs;
 pub mod generate_invites;
 pub mod view_invite;
+pub mod delete_account;
+pub mod metrics;
+pub mod debug_process_thread;
+pub mod server_info;
+pub mod get_boards;
+pub mod get_delivery_stats;
+pub mod list_accounts;
+pub mod get_thread_progress;
+pub mod bulk_extend_expiry;
+pub mod remove_firebase_token;
+pub mod update_quiet_hours;
+pub mod replay_replies;
+pub mod mute_thread;
+pub mod ban_account;
+pub mod get_thread_snapshot;
+pub mod whoami;
+pub mod register_and_watch;
+pub mod send_test_notification;
+pub mod update_web_push_subscription;
 pub mod shared;
\ No newline at end of file
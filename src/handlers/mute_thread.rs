@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{constants, error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, collect_limited, empty_success_response, error_response_str, error_response_string, max_request_body_size, throttle_account, validate_post_url};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository::AccountId;
+use crate::model::repository::site_repository::SiteRepository;
+use crate::model::repository::thread_mute_repository;
+use crate::model::repository::thread_mute_repository::MuteThreadResult;
+use crate::router::TestContext;
+
+/// Mutes the thread for [minutes] minutes, 0 minutes unmutes it.
+#[derive(Serialize, Deserialize)]
+pub struct MuteThreadRequest {
+    pub user_id: String,
+    pub thread_url: String,
+    pub minutes: i64
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>,
+    test_context: Option<TestContext>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: MuteThreadRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into MuteThreadRequest")?;
+
+    if request.minutes < 0 || request.minutes > constants::MAX_THREAD_MUTE_MINUTES {
+        let error_message = format!(
+            "minutes must be in range 0..={}",
+            constants::MAX_THREAD_MUTE_MINUTES
+        );
+
+        error!("mute_thread() {}", error_message);
+
+        let response_json = error_response_string(ErrorCode::InvalidParameter, &error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/mute_thread").await?;
+    let thread_url = validate_post_url(&request.thread_url)?;
+
+    let imageboard = site_repository.by_url(thread_url);
+    if imageboard.is_none() {
+        let full_error_message = format!("Site for url \'{}\' is not supported", thread_url);
+
+        let response_json = error_response_string(ErrorCode::SiteNotSupported, &full_error_message)?;
+        error!("mute_thread() {}", full_error_message);
+
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let imageboard = imageboard.unwrap();
+
+    let post_descriptor = imageboard.post_url_to_post_descriptor(thread_url);
+    if post_descriptor.is_none() {
+        let full_error_message = format!("Failed to parse \'{}\' url as thread url", thread_url);
+
+        let response_json = error_response_string(ErrorCode::InvalidPostUrl, &full_error_message)?;
+        error!("mute_thread() {}", full_error_message);
+
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let thread_descriptor = post_descriptor.unwrap().thread_descriptor;
+
+    let result = thread_mute_repository::mute_thread(
+        database,
+        &account_id,
+        &thread_descriptor,
+        request.minutes
+    )
+        .await
+        .context(format!("Failed to mute thread {} for account with id \'{}\'", thread_descriptor, account_id))?;
+
+    if result != MuteThreadResult::Ok {
+        let (error_code, error_message) = match result {
+            MuteThreadResult::Ok => unreachable!(),
+            MuteThreadResult::AccountDoesNotExist => (ErrorCode::AccountNotFound, "Account does not exist")
+        };
+
+        error!(
+            "mute_thread() Failed to mute thread {} for account_id \'{}\': \"{}\"",
+            thread_descriptor,
+            account_id,
+            error_message
+        );
+
+        let response_json = error_response_str(error_code, error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let response_json = empty_success_response()?;
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    info!(
+        "mute_thread() Successfully muted thread {} for {} minutes. account_id: \'{}\'",
+        thread_descriptor,
+        request.minutes,
+        account_id.format_token()
+    );
+
+    return Ok(response);
+}
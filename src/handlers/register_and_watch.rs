@@ -0,0 +1,161 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{constants, error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, collect_limited, empty_success_response, error_response_str, error_response_string, max_request_body_size, throttle_account};
+use crate::handlers::watch_posts::{resolve_post_url, WATCH_POST_RESULT_OK};
+use crate::helpers::serde_helpers::{deserialize_application_type, serialize_application_type};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::data::chan::PostDescriptor;
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository::{AccountId, ApplicationType, FirebaseToken};
+use crate::model::repository::post_repository;
+use crate::model::repository::post_repository::StartWatchingPostResult;
+use crate::model::repository::site_repository::SiteRepository;
+use crate::router::TestContext;
+
+/// Stores the token and watches all the posts at once. Nothing is stored when any of the urls
+/// can't be watched.
+#[derive(Serialize, Deserialize)]
+pub struct RegisterAndWatchRequest {
+    pub user_id: String,
+    #[serde(
+        serialize_with = "serialize_application_type",
+        deserialize_with = "deserialize_application_type"
+    )]
+    pub application_type: ApplicationType,
+    pub firebase_token: String,
+    pub post_urls: Vec<String>
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>,
+    test_context: Option<TestContext>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: RegisterAndWatchRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into RegisterAndWatchRequest")?;
+
+    let application_type = request.application_type;
+    if application_type == ApplicationType::Unknown {
+        let error_message = format!(
+            "Unsupported \'application_type\' parameter value: {}",
+            application_type as isize
+        );
+
+        error!("register_and_watch() {}", error_message);
+
+        let response_json = error_response_string(ErrorCode::UnsupportedApplicationType, &error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    if request.post_urls.len() > constants::MAX_WATCH_POSTS_PER_REQUEST {
+        let error_message = format!(
+            "\'post_urls\' must contain at most {} urls",
+            constants::MAX_WATCH_POSTS_PER_REQUEST
+        );
+
+        error!("register_and_watch() {}", error_message);
+
+        let response_json = error_response_string(ErrorCode::InvalidParameter, &error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/register_and_watch").await?;
+    let firebase_token = FirebaseToken::from_str(&request.firebase_token)?;
+
+    let mut post_descriptors = Vec::<PostDescriptor>::with_capacity(request.post_urls.len());
+
+    for post_url in &request.post_urls {
+        let (result, post_descriptor) = resolve_post_url(post_url, site_repository);
+        if result != WATCH_POST_RESULT_OK {
+            let error_message = format!("Failed to watch post url \'{}\': {}", post_url, result);
+
+            error!("register_and_watch() {}", error_message);
+
+            let response_json = error_response_string(ErrorCode::InvalidPostUrl, &error_message)?;
+            let response = Response::builder()
+                .json()
+                .status(200)
+                .body(Full::new(Bytes::from(response_json)))?;
+
+            return Ok(response);
+        }
+
+        post_descriptors.push(post_descriptor.unwrap());
+    }
+
+    let result = post_repository::register_and_start_watching_posts(
+        database,
+        &account_id,
+        &application_type,
+        &firebase_token,
+        &post_descriptors
+    )
+        .await
+        .context(format!("Failed to register and start watching {} posts", post_descriptors.len()))?;
+
+    if result != StartWatchingPostResult::Ok {
+        let (error_code, error_message) = match result {
+            StartWatchingPostResult::Ok => unreachable!(),
+            StartWatchingPostResult::AccountDoesNotExist => (ErrorCode::AccountNotFound, "Account does not exist"),
+            StartWatchingPostResult::AccountHasNoToken => (ErrorCode::AccountHasNoToken, "Account has no token"),
+            StartWatchingPostResult::AccountIsNotValid => (ErrorCode::AccountExpired, "Account already expired"),
+            StartWatchingPostResult::AccountIsBanned => (ErrorCode::AccountBanned, "Account is banned"),
+            StartWatchingPostResult::WatchLimitReached => (ErrorCode::WatchLimitReached, "Too many watched posts"),
+        };
+
+        error!(
+            "register_and_watch() Failed to register account_id \'{}\': \"{}\"",
+            account_id.format_token(),
+            error_message
+        );
+
+        let response_json = error_response_str(error_code, error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let response_json = empty_success_response()?;
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    info!(
+        "register_and_watch() Successfully registered token \'{}\' and {} post watches for account_id \'{}\'",
+        firebase_token.format_token(),
+        post_descriptors.len(),
+        account_id.format_token()
+    );
+
+    return Ok(response);
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::{error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, collect_limited, empty_success_response, error_response_str, error_response_string, max_request_body_size, throttle_account};
+use crate::helpers::serde_helpers::{deserialize_application_type, serialize_application_type};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository;
+use crate::model::repository::account_repository::{AccountId, ApplicationType, FirebaseToken, RemoveFirebaseTokenResult};
+use crate::router::TestContext;
+
+#[derive(Serialize, Deserialize)]
+pub struct RemoveFirebaseTokenRequest {
+    pub user_id: String,
+    #[serde(serialize_with = "serialize_application_type", deserialize_with = "deserialize_application_type")]
+    pub application_type: ApplicationType,
+    /// When not set all the tokens of application_type are removed.
+    #[serde(default)]
+    pub firebase_token: Option<String>
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    test_context: Option<TestContext>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: RemoveFirebaseTokenRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into RemoveFirebaseTokenRequest")?;
+
+    let application_type = request.application_type;
+    if application_type == ApplicationType::Unknown {
+        let error_message = format!(
+            "Unsupported \'application_type\' parameter value: {}",
+            application_type as isize
+        );
+
+        error!("remove_firebase_token() {}", error_message);
+
+        let response_json = error_response_string(ErrorCode::UnsupportedApplicationType, &error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/remove_firebase_token").await?;
+    let firebase_token = FirebaseToken::from_opt_str(request.firebase_token.as_deref())?;
+
+    let result = account_repository::remove_firebase_token(
+        database,
+        &account_id,
+        &application_type,
+        firebase_token.as_ref()
+    )
+        .await
+        .context(format!("Failed to remove firebase token for account with id \'{}\'", account_id))?;
+
+    if result != RemoveFirebaseTokenResult::Ok {
+        let (error_code, error_message) = match result {
+            RemoveFirebaseTokenResult::Ok => unreachable!(),
+            RemoveFirebaseTokenResult::AccountDoesNotExist => (ErrorCode::AccountNotFound, "Account does not exist")
+        };
+
+        let full_error_message = format!(
+            "Failed to remove firebase token for account_id \'{}\': \"{}\"",
+            account_id,
+            error_message
+        );
+
+        error!("remove_firebase_token() {}", full_error_message);
+
+        let response_json = error_response_str(error_code, error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let response_json = empty_success_response()?;
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    info!(
+        "remove_firebase_token() Successfully removed firebase_token. account_id: \'{}\', application_type: {}",
+        account_id.format_token(),
+        application_type
+    );
+
+    return Ok(response);
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{constants, error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, ServerSuccessResponse, collect_limited, error_response_str, max_request_body_size, success_response};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::{account_repository, post_reply_repository};
+use crate::model::repository::account_repository::AccountId;
+
+#[derive(Serialize, Deserialize)]
+pub struct ReplayRepliesRequest {
+    pub user_id: String
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReplayRepliesResponse {
+    pub replayed_count: u64
+}
+
+impl ServerSuccessResponse for ReplayRepliesResponse {
+
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: ReplayRepliesRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into ReplayRepliesRequest")?;
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+
+    let account = account_repository::get_account(&account_id, database).await?;
+    if account.is_none() {
+        error!(
+            "replay_replies() account with account_id \'{}\' does not exist",
+            account_id.format_token()
+        );
+
+        let response_json = error_response_str(ErrorCode::AccountNotFound, "Account does not exist")?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let account_db_id = { account.unwrap().lock().await.id };
+
+    let replayed_count = post_reply_repository::replay_replies(
+        account_db_id,
+        constants::REPLAY_REPLIES_PERIOD_HOURS,
+        database
+    )
+        .await
+        .with_context(|| {
+            return format!(
+                "Failed to replay replies for account with account_id: \'{}\'",
+                account_id
+            );
+        })?;
+
+    let response_json = success_response(ReplayRepliesResponse { replayed_count })?;
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    info!(
+        "replay_replies() Successfully replayed {} replies of account with account_id \'{}\'",
+        replayed_count,
+        account_id.format_token()
+    );
+
+    return Ok(response);
+}
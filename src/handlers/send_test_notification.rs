@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, ServerSuccessResponse, collect_limited, error_response_str, error_response_string, max_request_body_size, success_response, throttle_account};
+use crate::helpers::serde_helpers::{deserialize_application_type, serialize_application_type};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository;
+use crate::model::repository::account_repository::{AccountId, AccountToken, ApplicationType};
+use crate::router::TestContext;
+use crate::service::fcm_sender::FcmSender;
+
+#[derive(Serialize, Deserialize)]
+pub struct SendTestNotificationRequest {
+    pub user_id: String,
+    #[serde(
+        serialize_with = "serialize_application_type",
+        deserialize_with = "deserialize_application_type"
+    )]
+    pub application_type: ApplicationType
+}
+
+/// [accepted] is only true when FCM accepted the message for every token of the application type.
+#[derive(Serialize, Deserialize)]
+pub struct SendTestNotificationResponse {
+    pub accepted: bool
+}
+
+impl ServerSuccessResponse for SendTestNotificationResponse {
+
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    fcm_sender: &Arc<FcmSender>,
+    test_context: Option<TestContext>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: SendTestNotificationRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into SendTestNotificationRequest")?;
+
+    let application_type = request.application_type;
+    if application_type == ApplicationType::Unknown {
+        let error_message = format!(
+            "Unsupported \'application_type\' parameter value: {}",
+            application_type as isize
+        );
+
+        error!("send_test_notification() {}", error_message);
+
+        let response_json = error_response_string(ErrorCode::UnsupportedApplicationType, &error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/send_test_notification").await?;
+
+    let account = account_repository::get_account(&account_id, database)
+        .await
+        .with_context(|| {
+            return format!(
+                "send_test_notification() Failed to get account from repository with account_id \'{}\'",
+                account_id.format_token()
+            );
+        })?;
+
+    if account.is_none() {
+        error!(
+            "send_test_notification() Account with id \'{}\' does not exist",
+            account_id.format_token()
+        );
+
+        let response_json = error_response_str(ErrorCode::AccountNotFound, "Account does not exist")?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let account = account.unwrap();
+
+    let (is_banned, account_tokens) = {
+        let acc = account.lock().await;
+
+        let account_tokens = acc.get_account_tokens(&application_type)
+            .into_iter()
+            .cloned()
+            .collect::<Vec<AccountToken>>();
+
+        (acc.is_banned(), account_tokens)
+    };
+
+    if is_banned || account_tokens.is_empty() {
+        let (error_code, error_message) = if is_banned {
+            (ErrorCode::AccountBanned, "Account is banned")
+        } else {
+            (ErrorCode::AccountHasNoToken, "Account has no token")
+        };
+
+        error!(
+            "send_test_notification() Can\'t send test notification to account_id \'{}\': \"{}\"",
+            account_id.format_token(),
+            error_message
+        );
+
+        let response_json = error_response_str(error_code, error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let mut accepted = true;
+
+    for account_token in &account_tokens {
+        let sent = fcm_sender.send_test_message(account_token)
+            .await
+            .with_context(|| {
+                return format!(
+                    "send_test_notification() Failed to send test message to token \'{}\'",
+                    account_token.token.format_token()
+                );
+            })?;
+
+        accepted = accepted && sent;
+    }
+
+    let response_json = success_response(SendTestNotificationResponse { accepted })?;
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    info!(
+        "send_test_notification() Sent test notification to {} tokens of account_id \'{}\', accepted: {}",
+        account_tokens.len(),
+        account_id.format_token(),
+        accepted
+    );
+
+    return Ok(response);
+}
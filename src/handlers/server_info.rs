@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+use crate::handlers::shared::{ContentType, ServerSuccessResponse, success_response};
+use crate::helpers::serde_helpers::{deserialize_datetime, serialize_datetime_option};
+use crate::model::repository::site_repository::SiteRepository;
+
+static SERVER_STARTED_AT: OnceCell<DateTime<Utc>> = OnceCell::new();
+
+#[derive(Serialize, Deserialize)]
+pub struct ServerInfoResponse {
+    pub version: String,
+    #[serde(
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub started_at: Option<DateTime<Utc>>,
+    pub uptime_seconds: u64,
+    pub supported_sites: Vec<String>
+}
+
+impl ServerSuccessResponse for ServerInfoResponse {
+
+}
+
+pub fn init_server_started_at() {
+    let _ = SERVER_STARTED_AT.set(chrono::offset::Utc::now());
+}
+
+fn server_started_at() -> DateTime<Utc> {
+    // Fallback to the time of the first request when init_server_started_at() was never called (tests)
+    return SERVER_STARTED_AT.get_or_init(|| chrono::offset::Utc::now()).clone();
+}
+
+pub async fn handle(
+    _query: &str,
+    _: Incoming,
+    site_repository: &Arc<SiteRepository>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let started_at = server_started_at();
+    let uptime_seconds = (chrono::offset::Utc::now() - started_at).num_seconds().max(0) as u64;
+
+    let server_info_response = ServerInfoResponse {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        started_at: Some(started_at),
+        uptime_seconds,
+        supported_sites: site_repository.supported_site_names()
+    };
+
+    let response_json = success_response(server_info_response)?;
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    return Ok(response);
+}
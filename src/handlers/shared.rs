@@ -1,8 +1,32 @@
-use anyhow::anyhow;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::Context;
+use http_body_util::{BodyExt, Full};
+use http_body_util::combinators::UnsyncBoxBody;
+use hyper::body::{Bytes, Incoming};
 use hyper::http::response::Builder;
+use hyper::Response;
 use serde::{Deserialize, Serialize};
 
 use crate::constants;
+use crate::helpers::throttler;
+use crate::model::data::chan::PostDescriptor;
+use crate::model::repository::account_repository::AccountId;
+use crate::model::repository::site_repository::{ImageboardSynced, SiteRepository};
+use crate::router::TestContext;
+
+pub const TOO_MANY_REQUESTS_MESSAGE: &str = "You are making too many requests, please wait a little bit.";
+
+static MAX_REQUEST_BODY_SIZE: AtomicUsize = AtomicUsize::new(constants::DEFAULT_MAX_REQUEST_BODY_SIZE);
+
+/// Body of every response sent by the router. Most of the handlers respond with Full<Bytes> which
+/// is converted via [full_body] but some of them stream their responses.
+pub type ResponseBody = UnsyncBoxBody<Bytes, anyhow::Error>;
+
+pub fn full_body(response: Response<Full<Bytes>>) -> Response<ResponseBody> {
+    return response.map(|body| body.map_err(|never| match never {}).boxed_unsync());
+}
 
 pub trait ServerSuccessResponse {
 
@@ -11,7 +35,76 @@ pub trait ServerSuccessResponse {
 #[derive(Serialize, Deserialize)]
 pub struct ServerResponse<T : ServerSuccessResponse> {
     pub data: Option<T>,
-    pub error: Option<String>
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ErrorCode>
+}
+
+/// Machine readable counterpart of ServerResponse.error. Clients should match on these instead of
+/// the error messages which are only meant for humans and may change.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    UnknownError,
+    BadRequest,
+    RequestBodyTooLarge,
+    TooManyRequests,
+    IncorrectMasterPassword,
+    InvalidParameter,
+    UnsupportedApplicationType,
+    AccountNotFound,
+    AccountAlreadyExists,
+    AccountExpired,
+    AccountHasNoToken,
+    AccountBanned,
+    InviteNotValid,
+    WatchLimitReached,
+    PostUrlEmpty,
+    PostUrlTooLong,
+    InvalidPostUrl,
+    /// The url is a valid thread url but has no post number.
+    PostUrlIsThreadUrl,
+    SiteNotSupported,
+    UnknownBoard,
+    InvalidFilterRegex
+}
+
+/// Handlers return this (via anyhow) when they bail out with "?" but the client still needs to
+/// know what went wrong, the router converts it into an error response with [code].
+#[derive(Debug)]
+pub struct ServerError {
+    pub code: ErrorCode,
+    pub message: String
+}
+
+impl ServerError {
+    pub fn new(code: ErrorCode, message: &str) -> anyhow::Error {
+        return anyhow::Error::new(ServerError { code, message: message.to_string() });
+    }
+}
+
+impl Display for ServerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", self.message);
+    }
+}
+
+impl std::error::Error for ServerError {
+
+}
+
+/// Picks the error code for an error returned by a handler.
+pub fn error_code_of(error: &anyhow::Error) -> ErrorCode {
+    let server_error = error.downcast_ref::<ServerError>();
+    if server_error.is_some() {
+        return server_error.unwrap().code;
+    }
+
+    if error.downcast_ref::<serde_json::Error>().is_some() {
+        return ErrorCode::BadRequest;
+    }
+
+    return ErrorCode::UnknownError;
 }
 
 #[derive(Serialize, Deserialize)]
@@ -35,7 +128,8 @@ impl ServerSuccessResponse for EmptyResponse {
 pub fn empty_success_response() -> anyhow::Result<String> {
     let response = ServerResponse {
         data: Some(DefaultSuccessResponse { success: true }),
-        error: None
+        error: None,
+        error_code: None
     };
 
     let json = serde_json::to_string(&response)?;
@@ -49,30 +143,39 @@ pub fn success_response<'a, T : ServerSuccessResponse>(
 {
     let response = ServerResponse {
         data: Some(data),
-        error: None
+        error: None,
+        error_code: None
     };
 
     let json = serde_json::to_string(&response)?;
     return Ok(json);
 }
 
-pub fn error_response_string(error: &String) -> anyhow::Result<String> {
-    return error_response_str(error.as_str());
+pub fn error_response_string(error_code: ErrorCode, error: &String) -> anyhow::Result<String> {
+    return error_response_str(error_code, error.as_str());
 }
 
-pub fn error_response_str(error: &str) -> anyhow::Result<String> {
+pub fn error_response_str(error_code: ErrorCode, error: &str) -> anyhow::Result<String> {
     let response: ServerResponse<EmptyResponse> = ServerResponse {
         data: None,
-        error: Some(error.to_string())
+        error: Some(error.to_string()),
+        error_code: Some(error_code)
     };
 
     let json = serde_json::to_string(&response)?;
     return Ok(json);
 }
 
+/// Attached (as a response extension) to the responses of JSON handlers that reject a request
+/// with an error in the body (e.g. a bad invite) so that the router reports it to the throttler.
+/// They respond with 200 so the status can't be used for that like with html handlers.
+#[derive(Clone, Copy)]
+pub struct RequestFailed;
+
 pub trait ContentType {
     fn content_type(self, value: &str) -> Builder;
     fn json(self) -> Builder;
+    fn ndjson(self) -> Builder;
     fn html(self) -> Builder;
 }
 
@@ -85,6 +188,10 @@ impl ContentType for Builder {
         return self.content_type("application/json")
     }
 
+    fn ndjson(self) -> Builder {
+        return self.content_type("application/x-ndjson")
+    }
+
     fn html(self) -> Builder {
         return self.content_type("text/html")
     }
@@ -92,12 +199,145 @@ impl ContentType for Builder {
 
 pub fn validate_post_url(post_url: &String) -> anyhow::Result<&String> {
     if post_url.is_empty() {
-        return Err(anyhow!("post_url is empty"));
+        return Err(ServerError::new(ErrorCode::PostUrlEmpty, "post_url is empty"));
     }
 
     if post_url.len() > constants::MAX_POST_URL_LENGTH {
-        return Err(anyhow!("post_url is too long"));
+        return Err(ServerError::new(ErrorCode::PostUrlTooLong, "post_url is too long"));
     }
 
     return Ok(post_url);
-}
\ No newline at end of file
+}
+
+/// Alternative to post_url for clients that already have a structured post descriptor and don't
+/// want to turn it into a site specific url just for us to parse it back.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PostDescriptorJson {
+    pub site_name: String,
+    pub board_code: String,
+    pub thread_no: u64,
+    pub post_no: u64,
+    #[serde(default)]
+    pub post_sub_no: u64
+}
+
+/// Converts [post_descriptor_json] into a PostDescriptor of one of the supported sites without
+/// going through the site url regexes.
+pub fn resolve_post_descriptor_json<'a>(
+    post_descriptor_json: &PostDescriptorJson,
+    site_repository: &'a SiteRepository
+) -> anyhow::Result<(&'a ImageboardSynced, PostDescriptor)> {
+    if post_descriptor_json.thread_no == 0 || post_descriptor_json.post_no < post_descriptor_json.thread_no {
+        let error_message = format!(
+            "Bad post_descriptor: thread_no {} and post_no {} must be positive and post_no must not be less than thread_no",
+            post_descriptor_json.thread_no,
+            post_descriptor_json.post_no
+        );
+
+        return Err(ServerError::new(ErrorCode::InvalidParameter, &error_message));
+    }
+
+    let post_descriptor = PostDescriptor::try_new(
+        post_descriptor_json.site_name.clone(),
+        post_descriptor_json.board_code.clone(),
+        post_descriptor_json.thread_no,
+        post_descriptor_json.post_no,
+        post_descriptor_json.post_sub_no
+    );
+
+    if post_descriptor.is_err() {
+        let error_message = format!("Bad post_descriptor: {}", post_descriptor.err().unwrap());
+        return Err(ServerError::new(ErrorCode::InvalidParameter, &error_message));
+    }
+
+    let post_descriptor = post_descriptor.unwrap();
+
+    let imageboard = site_repository.by_site_descriptor(post_descriptor.site_descriptor());
+    if imageboard.is_none() {
+        let error_message = format!("Site \'{}\' is not supported", post_descriptor.site_name());
+        return Err(ServerError::new(ErrorCode::SiteNotSupported, &error_message));
+    }
+
+    return Ok((imageboard.unwrap(), post_descriptor));
+}
+
+/// Fails with ErrorCode::TooManyRequests when the account made too many requests to [path].
+pub async fn throttle_account(
+    test_context: Option<TestContext>,
+    account_id: &AccountId,
+    path: &str
+) -> anyhow::Result<()> {
+    if !throttler::can_proceed_for_account(test_context, account_id, path).await? {
+        return Err(ServerError::new(ErrorCode::TooManyRequests, TOO_MANY_REQUESTS_MESSAGE));
+    }
+
+    return Ok(());
+}
+
+/// Accounts can be created or extended for 1..365 days at once.
+pub fn is_valid_days_count(days: i64) -> bool {
+    return days > 0 && days <= 365;
+}
+
+pub fn set_max_request_body_size(max_bytes: usize) {
+    MAX_REQUEST_BODY_SIZE.store(max_bytes, Ordering::Relaxed);
+}
+
+pub fn max_request_body_size() -> usize {
+    return MAX_REQUEST_BODY_SIZE.load(Ordering::Relaxed);
+}
+
+pub async fn collect_limited(mut body: Incoming, max_bytes: usize) -> anyhow::Result<Bytes> {
+    let mut collected = Vec::<u8>::new();
+
+    loop {
+        let frame = body.frame().await;
+        if frame.is_none() {
+            break;
+        }
+
+        let frame = frame.unwrap().context("Failed to collect body")?;
+        if !frame.is_data() {
+            continue;
+        }
+
+        let data = frame.into_data().unwrap();
+        if collected.len() + data.len() > max_bytes {
+            let error_message = format!("Request body is too large (max {} bytes)", max_bytes);
+            return Err(ServerError::new(ErrorCode::RequestBodyTooLarge, &error_message));
+        }
+
+        collected.extend_from_slice(&data);
+    }
+
+    return Ok(Bytes::from(collected));
+}
+
+#[test]
+fn test_error_response_contains_error_code() {
+    let json = error_response_str(ErrorCode::AccountNotFound, "Account does not exist").unwrap();
+    assert_eq!(r#"{"data":null,"error":"Account does not exist","error_code":"ACCOUNT_NOT_FOUND"}"#, json);
+
+    let json = empty_success_response().unwrap();
+    assert_eq!(r#"{"data":{"success":true},"error":null}"#, json);
+}
+
+#[test]
+fn test_error_code_of() {
+    let error = validate_post_url(&String::new()).err().unwrap();
+    assert_eq!(ErrorCode::PostUrlEmpty, error_code_of(&error));
+
+    let error = validate_post_url(&"1".repeat(constants::MAX_POST_URL_LENGTH + 1))
+        .context("Failed to validate post_url")
+        .err()
+        .unwrap();
+    assert_eq!(ErrorCode::PostUrlTooLong, error_code_of(&error));
+
+    let error = serde_json::from_str::<DefaultSuccessResponse>("{")
+        .context("Failed to convert body into DefaultSuccessResponse")
+        .err()
+        .unwrap();
+    assert_eq!(ErrorCode::BadRequest, error_code_of(&error));
+
+    assert_eq!(ErrorCode::UnknownError, error_code_of(&anyhow::anyhow!("Database is down")));
+}
@@ -1,13 +1,13 @@
 use std::sync::Arc;
 
 use anyhow::Context;
-use http_body_util::{BodyExt, Full};
+use http_body_util::Full;
 use hyper::body::{Bytes, Incoming};
 use hyper::Response;
 use serde::{Deserialize, Serialize};
 
 use crate::{error, info};
-use crate::handlers::shared::{ContentType, empty_success_response, error_response_str, error_response_string, validate_post_url};
+use crate::handlers::shared::{ContentType, ErrorCode, PostDescriptorJson, collect_limited, empty_success_response, error_response_str, error_response_string, max_request_body_size, resolve_post_descriptor_json, throttle_account, validate_post_url};
 use crate::helpers::serde_helpers::{deserialize_application_type, serialize_application_type};
 use crate::helpers::string_helpers::FormatToken;
 use crate::model::database::db::Database;
@@ -15,11 +15,16 @@ use crate::model::repository::account_repository::{AccountId, ApplicationType};
 use crate::model::repository::post_repository;
 use crate::model::repository::post_repository::StopWatchingPostResult;
 use crate::model::repository::site_repository::SiteRepository;
+use crate::router::TestContext;
 
 #[derive(Serialize, Deserialize)]
 pub struct UnwatchPostRequest {
     pub user_id: String,
+    #[serde(default)]
     pub post_url: String,
+    /// Used instead of [post_url] when set.
+    #[serde(default)]
+    pub post_descriptor: Option<PostDescriptorJson>,
     #[serde(
         serialize_with = "serialize_application_type",
         deserialize_with = "deserialize_application_type"
@@ -31,12 +36,10 @@ pub async fn handle(
     _query: &str,
     body: Incoming,
     database: &Arc<Database>,
-    site_repository: &Arc<SiteRepository>
+    site_repository: &Arc<SiteRepository>,
+    test_context: Option<TestContext>
 ) -> anyhow::Result<Response<Full<Bytes>>> {
-    let body_bytes = body.collect()
-        .await
-        .context("Failed to collect body")?
-        .to_bytes();
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
 
     let body_as_string = String::from_utf8(body_bytes.to_vec())
         .context("Failed to convert body into a string")?;
@@ -53,7 +56,7 @@ pub async fn handle(
 
         error!("unwatch_post() {}", error_message);
 
-        let response_json = error_response_string(&error_message)?;
+        let response_json = error_response_string(ErrorCode::UnsupportedApplicationType, &error_message)?;
         let response = Response::builder()
             .json()
             .status(200)
@@ -63,41 +66,48 @@ pub async fn handle(
     }
 
     let account_id = AccountId::from_user_id(&request.user_id)?;
-    let post_url = validate_post_url(&request.post_url)?;
+    throttle_account(test_context, &account_id, "/unwatch_post").await?;
 
-    let imageboard = site_repository.by_url(post_url);
-    if imageboard.is_none() {
-        let full_error_message = format!("Site for url \'{}\' is not supported", post_url);
+    let post_descriptor = if request.post_descriptor.is_some() {
+        resolve_post_descriptor_json(request.post_descriptor.as_ref().unwrap(), site_repository)?.1
+    } else {
+        let post_url = validate_post_url(&request.post_url)?;
 
-        let response_json = error_response_string(&full_error_message)?;
-        error!("unwatch_post() {}", full_error_message);
+        let imageboard = site_repository.by_url(post_url);
+        if imageboard.is_none() {
+            let full_error_message = format!("Site for url \'{}\' is not supported", post_url);
 
-        let response = Response::builder()
-            .json()
-            .status(200)
-            .body(Full::new(Bytes::from(response_json)))?;
+            let response_json = error_response_string(ErrorCode::SiteNotSupported, &full_error_message)?;
+            error!("unwatch_post() {}", full_error_message);
 
-        return Ok(response);
-    }
+            let response = Response::builder()
+                .json()
+                .status(200)
+                .body(Full::new(Bytes::from(response_json)))?;
 
-    let imageboard = imageboard.unwrap();
+            return Ok(response);
+        }
 
-    let post_descriptor = imageboard.post_url_to_post_descriptor(post_url);
-    if post_descriptor.is_none() {
-        let full_error_message = format!("Failed to parse \'{}\' url as post url", post_url);
+        let imageboard = imageboard.unwrap();
 
-        let response_json = error_response_string(&full_error_message)?;
-        error!("unwatch_post() {}", full_error_message);
+        let post_descriptor = imageboard.post_url_to_post_descriptor(post_url);
+        if post_descriptor.is_none() {
+            let full_error_message = format!("Failed to parse \'{}\' url as post url", post_url);
 
-        let response = Response::builder()
-            .json()
-            .status(200)
-            .body(Full::new(Bytes::from(response_json)))?;
+            let response_json = error_response_string(ErrorCode::InvalidPostUrl, &full_error_message)?;
+            error!("unwatch_post() {}", full_error_message);
 
-        return Ok(response);
-    }
+            let response = Response::builder()
+                .json()
+                .status(200)
+                .body(Full::new(Bytes::from(response_json)))?;
+
+            return Ok(response);
+        }
+
+        post_descriptor.unwrap()
+    };
 
-    let post_descriptor = post_descriptor.unwrap();
     info!("unwatch_post() post_descriptor: {}", post_descriptor);
 
     let post_watch_deleted_result = post_repository::stop_watching_post(
@@ -108,13 +118,13 @@ pub async fn handle(
     ).await.context(format!("Failed to unwatch post {}", post_descriptor))?;
 
     if post_watch_deleted_result != StopWatchingPostResult::Ok {
-        let error_message = match post_watch_deleted_result {
+        let (error_code, error_message) = match post_watch_deleted_result {
             StopWatchingPostResult::Ok => unreachable!(),
-            StopWatchingPostResult::AccountDoesNotExist => "Account does not exist",
-            StopWatchingPostResult::AccountIsNotValid => "Account already expired",
+            StopWatchingPostResult::AccountDoesNotExist => (ErrorCode::AccountNotFound, "Account does not exist"),
+            StopWatchingPostResult::AccountIsNotValid => (ErrorCode::AccountExpired, "Account already expired"),
         };
 
-        let response_json = error_response_str(error_message)?;
+        let response_json = error_response_str(error_code, error_message)?;
 
         let response = Response::builder()
             .json()
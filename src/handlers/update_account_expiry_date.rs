@@ -1,13 +1,15 @@
 use std::sync::Arc;
 
 use anyhow::Context;
-use http_body_util::{BodyExt, Full};
+use chrono::{DateTime, Utc};
+use http_body_util::Full;
 use hyper::body::{Bytes, Incoming};
 use hyper::Response;
 use serde::{Deserialize, Serialize};
 
 use crate::{error, info};
-use crate::handlers::shared::{ContentType, empty_success_response, error_response_str};
+use crate::handlers::shared::{ContentType, ErrorCode, ServerSuccessResponse, collect_limited, error_response_str, is_valid_days_count, max_request_body_size, success_response};
+use crate::helpers::serde_helpers::{deserialize_datetime, serialize_datetime_option};
 use crate::helpers::string_helpers::FormatToken;
 use crate::model::database::db::Database;
 use crate::model::repository::account_repository;
@@ -19,15 +21,26 @@ pub struct UpdateAccountExpiryDateRequest {
     pub valid_for_days: u64
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct UpdateAccountExpiryDateResponse {
+    pub account_id: String,
+    #[serde(
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub valid_until: Option<DateTime<Utc>>
+}
+
+impl ServerSuccessResponse for UpdateAccountExpiryDateResponse {
+
+}
+
 pub async fn handle(
     _query: &str,
     body: Incoming,
     database: &Arc<Database>
 ) -> anyhow::Result<Response<Full<Bytes>>> {
-    let body_bytes = body.collect()
-        .await
-        .context("Failed to collect body")?
-        .to_bytes();
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
 
     let body_as_string = String::from_utf8(body_bytes.to_vec())
         .context("Failed to convert body into a string")?;
@@ -38,10 +51,10 @@ pub async fn handle(
     let account_id = AccountId::from_user_id(&request.user_id)?;
     let valid_for_days = request.valid_for_days as i64;
 
-    if valid_for_days <= 0 || valid_for_days > 365 {
+    if !is_valid_days_count(valid_for_days) {
         error!("update_account_expiry_date() bad valid_for_days: {}", valid_for_days);
 
-        let response_json = error_response_str("valid_for_days must be in range 0..365")?;
+        let response_json = error_response_str(ErrorCode::InvalidParameter, "valid_for_days must be in range 0..365")?;
         let response = Response::builder()
             .json()
             .status(200)
@@ -65,30 +78,38 @@ pub async fn handle(
             );
         })?;
 
-    if result != UpdateAccountExpiryDateResult::Ok {
-        let error_message = match result {
-            UpdateAccountExpiryDateResult::Ok => unreachable!(),
-            UpdateAccountExpiryDateResult::AccountDoesNotExist => "Account does not exist"
-        };
+    let stored_valid_until = match result {
+        UpdateAccountExpiryDateResult::Ok(valid_until) => valid_until,
+        UpdateAccountExpiryDateResult::AccountDoesNotExist => {
+            let (error_code, error_message) = match result {
+                UpdateAccountExpiryDateResult::Ok(_) => unreachable!(),
+                UpdateAccountExpiryDateResult::AccountDoesNotExist => (ErrorCode::AccountNotFound, "Account does not exist")
+            };
+
+            let full_error_message = format!(
+                "Failed to update account expiry date for account_id \'{}\': \"{}\"",
+                account_id,
+                error_message
+            );
 
-        let full_error_message = format!(
-            "Failed to update account expiry date for account_id \'{}\': \"{}\"",
-            account_id,
-            error_message
-        );
+            error!("update_account_expiry_date() {}", full_error_message);
 
-        error!("update_account_expiry_date() {}", full_error_message);
+            let response_json = error_response_str(error_code, error_message)?;
+            let response = Response::builder()
+                .json()
+                .status(200)
+                .body(Full::new(Bytes::from(response_json)))?;
 
-        let response_json = error_response_str("Account does not exist")?;
-        let response = Response::builder()
-            .json()
-            .status(200)
-            .body(Full::new(Bytes::from(response_json)))?;
+            return Ok(response);
+        }
+    };
 
-        return Ok(response);
-    }
+    let response = UpdateAccountExpiryDateResponse {
+        account_id: account_id.format_token().to_string(),
+        valid_until: Some(stored_valid_until)
+    };
 
-    let response_json = empty_success_response()?;
+    let response_json = success_response(response)?;
 
     let response = Response::builder()
         .json()
@@ -99,7 +120,7 @@ pub async fn handle(
         "update_account_expiry_date() Successfully updated account expiry date. \
         account_id: \'{}\', valid_until: {:?}",
         account_id.format_token(),
-        valid_until
+        stored_valid_until
     );
 
     return Ok(response);
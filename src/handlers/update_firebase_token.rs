@@ -1,37 +1,38 @@
 use std::sync::Arc;
 
 use anyhow::Context;
-use http_body_util::{BodyExt, Full};
+use http_body_util::Full;
 use hyper::body::{Bytes, Incoming};
 use hyper::Response;
 use serde::Deserialize;
 use serde::Serialize;
 
 use crate::{error, info};
-use crate::handlers::shared::{ContentType, empty_success_response, error_response_str, error_response_string};
+use crate::handlers::shared::{ContentType, ErrorCode, collect_limited, empty_success_response, error_response_str, error_response_string, max_request_body_size, throttle_account};
 use crate::helpers::serde_helpers::{deserialize_application_type, serialize_application_type};
 use crate::helpers::string_helpers::FormatToken;
 use crate::model::database::db::Database;
 use crate::model::repository::account_repository;
 use crate::model::repository::account_repository::{AccountId, ApplicationType, FirebaseToken, UpdateFirebaseTokenResult};
+use crate::router::TestContext;
 
 #[derive(Serialize, Deserialize)]
 pub struct UpdateFirebaseTokenRequest {
     pub user_id: String,
     #[serde(serialize_with = "serialize_application_type", deserialize_with = "deserialize_application_type")]
     pub application_type: ApplicationType,
-    pub firebase_token: String
+    pub firebase_token: String,
+    #[serde(default)]
+    pub previous_token: Option<String>
 }
 
 pub async fn handle(
     _query: &str,
     body: Incoming,
-    database: &Arc<Database>
+    database: &Arc<Database>,
+    test_context: Option<TestContext>
 ) -> anyhow::Result<Response<Full<Bytes>>> {
-    let body_bytes = body.collect()
-        .await
-        .context("Failed to collect body")?
-        .to_bytes();
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
 
     let body_as_string = String::from_utf8(body_bytes.to_vec())
         .context("Failed to convert body into a string")?;
@@ -48,7 +49,7 @@ pub async fn handle(
 
         error!("update_firebase_token() {}", error_message);
 
-        let response_json = error_response_string(&error_message)?;
+        let response_json = error_response_string(ErrorCode::UnsupportedApplicationType, &error_message)?;
         let response = Response::builder()
             .json()
             .status(200)
@@ -58,21 +59,35 @@ pub async fn handle(
     }
 
     let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/update_firebase_token").await?;
     let firebase_token = FirebaseToken::from_str(&request.firebase_token)?;
-
-    let result = account_repository::update_firebase_token(
-        database,
-        &account_id,
-        &application_type,
-        &firebase_token
-    )
-        .await
-        .context(format!("Failed to update firebase token for account with id \'{}\'", account_id))?;
+    let previous_token = FirebaseToken::from_opt_str(request.previous_token.as_deref())?;
+
+    let result = if previous_token.is_some() {
+        account_repository::rotate_firebase_token(
+            database,
+            &account_id,
+            &application_type,
+            &previous_token.unwrap(),
+            &firebase_token
+        )
+            .await
+            .context(format!("Failed to rotate firebase token for account with id \'{}\'", account_id))?
+    } else {
+        account_repository::update_firebase_token(
+            database,
+            &account_id,
+            &application_type,
+            &firebase_token
+        )
+            .await
+            .context(format!("Failed to update firebase token for account with id \'{}\'", account_id))?
+    };
 
     if result != UpdateFirebaseTokenResult::Ok {
-        let error_message = match result {
+        let (error_code, error_message) = match result {
             UpdateFirebaseTokenResult::Ok => unreachable!(),
-            UpdateFirebaseTokenResult::AccountDoesNotExist => "Account does not exist"
+            UpdateFirebaseTokenResult::AccountDoesNotExist => (ErrorCode::AccountNotFound, "Account does not exist")
         };
 
         let full_error_message = format!(
@@ -83,7 +98,7 @@ pub async fn handle(
 
         error!("update_firebase_token() {}", full_error_message);
 
-        let response_json = error_response_str(error_message)?;
+        let response_json = error_response_str(error_code, error_message)?;
         let response = Response::builder()
             .json()
             .status(200)
@@ -2,18 +2,19 @@ use std::collections::HashSet;
 use std::sync::Arc;
 
 use anyhow::Context;
-use http_body_util::{BodyExt, Full};
+use http_body_util::Full;
 use hyper::body::{Bytes, Incoming};
 use hyper::Response;
 use serde::{Deserialize, Serialize};
 
 use crate::{error, info};
-use crate::handlers::shared::{ContentType, empty_success_response, error_response_string, validate_post_url};
+use crate::handlers::shared::{ContentType, collect_limited, empty_success_response, error_response_string, max_request_body_size, throttle_account, validate_post_url};
 use crate::helpers::string_helpers::FormatToken;
 use crate::model::database::db::Database;
 use crate::model::repository::post_watch_repository;
 use crate::model::repository::account_repository::AccountId;
 use crate::model::repository::site_repository::SiteRepository;
+use crate::router::TestContext;
 
 const MAX_REPLY_IDS_PER_REQUEST_COUNT: usize = 8192;
 
@@ -27,12 +28,10 @@ pub async fn handle(
     _query: &str,
     body: Incoming,
     database: &Arc<Database>,
-    site_repository: &Arc<SiteRepository>
+    site_repository: &Arc<SiteRepository>,
+    test_context: Option<TestContext>
 ) -> anyhow::Result<Response<Full<Bytes>>> {
-    let body_bytes = body.collect()
-        .await
-        .context("Failed to collect body")?
-        .to_bytes();
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
 
     let body_as_string = String::from_utf8(body_bytes.to_vec())
         .context("Failed to convert body into a string")?;
@@ -41,6 +40,7 @@ pub async fn handle(
         .context("Failed to convert body into MessageDelivered")?;
 
     let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/update_message_delivered").await?;
     let reply_ids = request.reply_ids
         .into_iter()
         .collect::<HashSet<u64>>()
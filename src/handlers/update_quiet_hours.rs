@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::{error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, collect_limited, empty_success_response, error_response_str, max_request_body_size, throttle_account};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository;
+use crate::model::repository::account_repository::{AccountId, QuietHours, UpdateQuietHoursResult};
+use crate::router::TestContext;
+
+const MINUTES_IN_DAY: i16 = 24 * 60;
+// UTC-12:00 .. UTC+14:00
+const MIN_TZ_OFFSET_MINUTES: i16 = -12 * 60;
+const MAX_TZ_OFFSET_MINUTES: i16 = 14 * 60;
+
+/// quiet_hours_start and quiet_hours_end are minutes since midnight in local time. When both are
+/// not set the quiet hours are disabled.
+#[derive(Serialize, Deserialize)]
+pub struct UpdateQuietHoursRequest {
+    pub user_id: String,
+    #[serde(default)]
+    pub quiet_hours_start: Option<i16>,
+    #[serde(default)]
+    pub quiet_hours_end: Option<i16>,
+    #[serde(default)]
+    pub tz_offset_minutes: i16
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    test_context: Option<TestContext>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: UpdateQuietHoursRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into UpdateQuietHoursRequest")?;
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/update_quiet_hours").await?;
+
+    let quiet_hours = validate_quiet_hours(&request);
+    if quiet_hours.is_err() {
+        let error_message = quiet_hours.err().unwrap();
+        error!("update_quiet_hours() {}", error_message);
+
+        let response_json = error_response_str(ErrorCode::InvalidParameter, error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let quiet_hours = quiet_hours.unwrap();
+
+    let result = account_repository::update_quiet_hours(database, &account_id, quiet_hours.as_ref())
+        .await
+        .context(format!("Failed to update quiet hours for account with id \'{}\'", account_id))?;
+
+    if result != UpdateQuietHoursResult::Ok {
+        let (error_code, error_message) = match result {
+            UpdateQuietHoursResult::Ok => unreachable!(),
+            UpdateQuietHoursResult::AccountDoesNotExist => (ErrorCode::AccountNotFound, "Account does not exist")
+        };
+
+        let full_error_message = format!(
+            "Failed to update quiet hours for account_id \'{}\': \"{}\"",
+            account_id,
+            error_message
+        );
+
+        error!("update_quiet_hours() {}", full_error_message);
+
+        let response_json = error_response_str(error_code, error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let response_json = empty_success_response()?;
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    info!(
+        "update_quiet_hours() Successfully updated quiet hours. account_id: \'{}\', quiet_hours: {:?}",
+        account_id.format_token(),
+        quiet_hours
+    );
+
+    return Ok(response);
+}
+
+fn validate_quiet_hours(request: &UpdateQuietHoursRequest) -> Result<Option<QuietHours>, &'static str> {
+    if request.quiet_hours_start.is_none() && request.quiet_hours_end.is_none() {
+        return Ok(None);
+    }
+
+    if request.quiet_hours_start.is_none() || request.quiet_hours_end.is_none() {
+        return Err("Both quiet_hours_start and quiet_hours_end must be set");
+    }
+
+    let start = request.quiet_hours_start.unwrap();
+    let end = request.quiet_hours_end.unwrap();
+
+    if start < 0 || start >= MINUTES_IN_DAY || end < 0 || end >= MINUTES_IN_DAY {
+        return Err("Quiet hours must be in range 0..1440 (minutes since midnight)");
+    }
+
+    if start == end {
+        return Err("quiet_hours_start must not be equal to quiet_hours_end");
+    }
+
+    if request.tz_offset_minutes < MIN_TZ_OFFSET_MINUTES || request.tz_offset_minutes > MAX_TZ_OFFSET_MINUTES {
+        return Err("tz_offset_minutes must be in range -720..=840");
+    }
+
+    let quiet_hours = QuietHours {
+        start,
+        end,
+        tz_offset_minutes: request.tz_offset_minutes
+    };
+
+    return Ok(Some(quiet_hours));
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::{error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, collect_limited, empty_success_response, error_response_str, error_response_string, max_request_body_size, throttle_account};
+use crate::helpers::serde_helpers::{deserialize_application_type, serialize_application_type};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository;
+use crate::model::repository::account_repository::{AccountId, ApplicationType, UpdateWebPushSubscriptionResult, WebPushSubscription};
+use crate::router::TestContext;
+
+#[derive(Serialize, Deserialize)]
+pub struct UpdateWebPushSubscriptionRequest {
+    pub user_id: String,
+    #[serde(serialize_with = "serialize_application_type", deserialize_with = "deserialize_application_type")]
+    pub application_type: ApplicationType,
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    test_context: Option<TestContext>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: UpdateWebPushSubscriptionRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into UpdateWebPushSubscriptionRequest")?;
+
+    let application_type = request.application_type;
+    if application_type == ApplicationType::Unknown {
+        let error_message = format!(
+            "Unsupported \'application_type\' parameter value: {}",
+            application_type as isize
+        );
+
+        error!("update_web_push_subscription() {}", error_message);
+
+        let response_json = error_response_string(ErrorCode::UnsupportedApplicationType, &error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/update_web_push_subscription").await?;
+    let web_push_subscription = WebPushSubscription::new(&request.endpoint, &request.p256dh, &request.auth)?;
+
+    let result = account_repository::update_web_push_subscription(
+        database,
+        &account_id,
+        &application_type,
+        &web_push_subscription
+    )
+        .await
+        .context(format!("Failed to update web push subscription for account with id \'{}\'", account_id))?;
+
+    if result != UpdateWebPushSubscriptionResult::Ok {
+        let (error_code, error_message) = match result {
+            UpdateWebPushSubscriptionResult::Ok => unreachable!(),
+            UpdateWebPushSubscriptionResult::AccountDoesNotExist => (ErrorCode::AccountNotFound, "Account does not exist")
+        };
+
+        let full_error_message = format!(
+            "Failed to update web push subscription for account_id \'{}\': \"{}\"",
+            account_id,
+            error_message
+        );
+
+        error!("update_web_push_subscription() {}", full_error_message);
+
+        let response_json = error_response_str(error_code, error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let response_json = empty_success_response()?;
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    info!(
+        "update_web_push_subscription() Successfully updated web push subscription. account_id: \'{}\', endpoint: \'{}\'",
+        account_id.format_token(),
+        web_push_subscription.endpoint.format_token()
+    );
+
+    return Ok(response);
+}
\ No newline at end of file
@@ -90,8 +90,9 @@ fn failed_to_accept_invite() -> anyhow::Result<Response<Full<Bytes>>> {
 </html>
     "#;
 
+    // Not 200 so that the router counts it as a failed request and locks out IPs guessing invites
     let response = Response::builder()
-        .status(200)
+        .status(404)
         .html()
         .body(Full::new(Bytes::from(html)))?;
 
@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{constants, error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, collect_limited, empty_success_response, error_response_str, error_response_string, max_request_body_size, throttle_account};
+use crate::helpers::serde_helpers::{deserialize_application_type, serialize_application_type};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::data::chan::{CatalogDescriptor, SiteDescriptor};
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository::{AccountId, ApplicationType};
+use crate::model::repository::catalog_watch_repository;
+use crate::model::repository::catalog_watch_repository::StartWatchingCatalogResult;
+use crate::model::repository::site_repository::SiteRepository;
+use crate::router::TestContext;
+
+#[derive(Serialize, Deserialize)]
+pub struct WatchCatalogRequest {
+    pub user_id: String,
+    pub site_name: String,
+    pub board_code: String,
+    pub filter: String,
+    #[serde(
+        serialize_with = "serialize_application_type",
+        deserialize_with = "deserialize_application_type"
+    )]
+    pub application_type: ApplicationType,
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>,
+    test_context: Option<TestContext>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: WatchCatalogRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into WatchCatalogRequest")?;
+
+    let application_type = request.application_type;
+    if application_type == ApplicationType::Unknown {
+        let error_message = format!(
+            "Unsupported \'application_type\' parameter value: {}",
+            application_type as isize
+        );
+
+        error!("watch_catalog() {}", error_message);
+
+        let response_json = error_response_string(ErrorCode::UnsupportedApplicationType, &error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/watch_catalog").await?;
+
+    let filter = request.filter.trim();
+    if filter.is_empty() || filter.len() > constants::MAX_CATALOG_FILTER_LENGTH {
+        let error_message = format!(
+            "\'filter\' must not be empty and must not be longer than {} characters",
+            constants::MAX_CATALOG_FILTER_LENGTH
+        );
+
+        error!("watch_catalog() {}", error_message);
+
+        let response_json = error_response_string(ErrorCode::InvalidParameter, &error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let site_descriptor = SiteDescriptor::from_string(&request.site_name);
+    let imageboard = site_repository.by_site_descriptor(&site_descriptor);
+    if imageboard.is_none() {
+        let full_error_message = format!("Site \'{}\' is not supported", request.site_name);
+
+        let response_json = error_response_string(ErrorCode::SiteNotSupported, &full_error_message)?;
+        error!("watch_catalog() {}", full_error_message);
+
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let imageboard = imageboard.unwrap();
+    if !imageboard.is_known_board(&request.board_code) {
+        let full_error_message = format!("Unknown board \'{}\'", request.board_code);
+
+        let response_json = error_response_string(ErrorCode::UnknownBoard, &full_error_message)?;
+        error!("watch_catalog() {}", full_error_message);
+
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let catalog_descriptor = CatalogDescriptor::from_site_descriptor(
+        site_descriptor,
+        request.board_code.clone()
+    );
+    info!("watch_catalog() catalog_descriptor: {}", catalog_descriptor);
+
+    let catalog_watch_created_result = catalog_watch_repository::start_watching_catalog(
+        database,
+        &account_id,
+        &application_type,
+        &catalog_descriptor,
+        filter
+    ).await.context(format!("Failed to start watching catalog {}", catalog_descriptor))?;
+
+    if catalog_watch_created_result != StartWatchingCatalogResult::Ok {
+        let (error_code, error_message) = match catalog_watch_created_result {
+            StartWatchingCatalogResult::Ok => unreachable!(),
+            StartWatchingCatalogResult::AccountDoesNotExist => (ErrorCode::AccountNotFound, "Account does not exist"),
+            StartWatchingCatalogResult::AccountHasNoToken => (ErrorCode::AccountHasNoToken, "Account has no token"),
+            StartWatchingCatalogResult::AccountIsNotValid => (ErrorCode::AccountExpired, "Account already expired"),
+            StartWatchingCatalogResult::AccountIsBanned => (ErrorCode::AccountBanned, "Account is banned"),
+        };
+
+        let response_json = error_response_str(error_code, error_message)?;
+
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        info!(
+            "Failed to start watching catalog {} for account {}, result: {:?}",
+            catalog_descriptor,
+            account_id,
+            catalog_watch_created_result
+        );
+
+        return Ok(response);
+    }
+
+    let response_json = empty_success_response()?;
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    info!(
+        "Catalog watch for catalog {} and account id {} was successfully created",
+        catalog_descriptor,
+        account_id.format_token()
+    );
+
+    return Ok(response);
+}
@@ -1,13 +1,14 @@
 use std::sync::Arc;
 
 use anyhow::Context;
-use http_body_util::{BodyExt, Full};
+use http_body_util::Full;
 use hyper::body::{Bytes, Incoming};
 use hyper::Response;
 use serde::{Deserialize, Serialize};
 
-use crate::{error, info};
-use crate::handlers::shared::{ContentType, empty_success_response, error_response_str, error_response_string, validate_post_url};
+use crate::{constants, error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, PostDescriptorJson, collect_limited, empty_success_response, error_response_str, error_response_string, max_request_body_size, resolve_post_descriptor_json, throttle_account, validate_post_url};
+use crate::helpers::regex_helpers;
 use crate::helpers::serde_helpers::{deserialize_application_type, serialize_application_type};
 use crate::helpers::string_helpers::FormatToken;
 use crate::model::database::db::Database;
@@ -15,28 +16,33 @@ use crate::model::repository::account_repository::{AccountId, ApplicationType};
 use crate::model::repository::post_repository;
 use crate::model::repository::post_repository::StartWatchingPostResult;
 use crate::model::repository::site_repository::SiteRepository;
+use crate::router::TestContext;
 
 #[derive(Serialize, Deserialize)]
 pub struct WatchPostRequest {
     pub user_id: String,
+    #[serde(default)]
     pub post_url: String,
+    /// Used instead of [post_url] when set.
+    #[serde(default)]
+    pub post_descriptor: Option<PostDescriptorJson>,
     #[serde(
         serialize_with = "serialize_application_type",
         deserialize_with = "deserialize_application_type"
     )]
     pub application_type: ApplicationType,
+    #[serde(default)]
+    pub filter_regex: Option<String>
 }
 
 pub async fn handle(
     _query: &str,
     body: Incoming,
     database: &Arc<Database>,
-    site_repository: &Arc<SiteRepository>
+    site_repository: &Arc<SiteRepository>,
+    test_context: Option<TestContext>
 ) -> anyhow::Result<Response<Full<Bytes>>> {
-    let body_bytes = body.collect()
-        .await
-        .context("Failed to collect body")?
-        .to_bytes();
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
 
     let body_as_string = String::from_utf8(body_bytes.to_vec())
         .context("Failed to convert body into a string")?;
@@ -53,7 +59,7 @@ pub async fn handle(
 
         error!("watch_post() {}", error_message);
 
-        let response_json = error_response_string(&error_message)?;
+        let response_json = error_response_string(ErrorCode::UnsupportedApplicationType, &error_message)?;
         let response = Response::builder()
             .json()
             .status(200)
@@ -63,30 +69,103 @@ pub async fn handle(
     }
 
     let account_id = AccountId::from_user_id(&request.user_id)?;
-    let post_url = validate_post_url(&request.post_url)?;
-
-    let imageboard = site_repository.by_url(post_url);
-    if imageboard.is_none() {
-        let full_error_message = format!("Site for url \'{}\' is not supported", post_url);
+    throttle_account(test_context, &account_id, "/watch_post").await?;
+
+    let filter_regex = request.filter_regex.as_ref()
+        .map(|filter_regex| filter_regex.trim())
+        .filter(|filter_regex| !filter_regex.is_empty());
+
+    if filter_regex.is_some() {
+        let filter_regex = filter_regex.unwrap();
+
+        let error_message = if filter_regex.len() > constants::MAX_FILTER_REGEX_LENGTH {
+            Some(format!(
+                "\'filter_regex\' must not be longer than {} characters",
+                constants::MAX_FILTER_REGEX_LENGTH
+            ))
+        } else {
+            regex_helpers::compile_filter_regex(filter_regex)
+                .err()
+                .map(|error| format!("Invalid \'filter_regex\': {}", error))
+        };
 
-        let response_json = error_response_string(&full_error_message)?;
-        error!("watch_post() {}", full_error_message);
+        if error_message.is_some() {
+            let error_message = error_message.unwrap();
+            error!("watch_post() {}", error_message);
 
-        let response = Response::builder()
-            .json()
-            .status(200)
-            .body(Full::new(Bytes::from(response_json)))?;
+            let response_json = error_response_string(ErrorCode::InvalidFilterRegex, &error_message)?;
+            let response = Response::builder()
+                .json()
+                .status(200)
+                .body(Full::new(Bytes::from(response_json)))?;
 
-        return Ok(response);
+            return Ok(response);
+        }
     }
 
-    let imageboard = imageboard.unwrap();
+    let (imageboard, post_descriptor) = if request.post_descriptor.is_some() {
+        resolve_post_descriptor_json(request.post_descriptor.as_ref().unwrap(), site_repository)?
+    } else {
+        let post_url = validate_post_url(&request.post_url)?;
+
+        let imageboard = site_repository.by_url(post_url);
+        if imageboard.is_none() {
+            let full_error_message = format!("Site for url \'{}\' is not supported", post_url);
+
+            let response_json = error_response_string(ErrorCode::SiteNotSupported, &full_error_message)?;
+            error!("watch_post() {}", full_error_message);
+
+            let response = Response::builder()
+                .json()
+                .status(200)
+                .body(Full::new(Bytes::from(response_json)))?;
+
+            return Ok(response);
+        }
+
+        let imageboard = imageboard.unwrap();
 
-    let post_descriptor = imageboard.post_url_to_post_descriptor(post_url);
-    if post_descriptor.is_none() {
-        let full_error_message = format!("Failed to parse \'{}\' url as post url", post_url);
+        let post_descriptor = imageboard.post_url_to_post_descriptor(post_url);
+        if post_descriptor.is_none() && imageboard.thread_url_to_thread_descriptor(post_url).is_some() {
+            let full_error_message = format!(
+                "URL \'{}\' points to a thread, not a post; did you mean to watch the whole thread?",
+                post_url
+            );
 
-        let response_json = error_response_string(&full_error_message)?;
+            let response_json = error_response_string(ErrorCode::PostUrlIsThreadUrl, &full_error_message)?;
+            error!("watch_post() {}", full_error_message);
+
+            let response = Response::builder()
+                .json()
+                .status(200)
+                .body(Full::new(Bytes::from(response_json)))?;
+
+            return Ok(response);
+        }
+
+        if post_descriptor.is_none() {
+            let full_error_message = format!("Failed to parse \'{}\' url as post url", post_url);
+
+            let response_json = error_response_string(ErrorCode::InvalidPostUrl, &full_error_message)?;
+            error!("watch_post() {}", full_error_message);
+
+            let response = Response::builder()
+                .json()
+                .status(200)
+                .body(Full::new(Bytes::from(response_json)))?;
+
+            return Ok(response);
+        }
+
+        (imageboard, post_descriptor.unwrap())
+    };
+
+    info!("watch_post() post_descriptor: {}", post_descriptor);
+
+    if !imageboard.is_known_board(post_descriptor.board_code()) {
+        let full_error_message = format!("Unknown board '{}'", post_descriptor.board_code());
+
+        let response_json = error_response_string(ErrorCode::UnknownBoard, &full_error_message)?;
         error!("watch_post() {}", full_error_message);
 
         let response = Response::builder()
@@ -97,25 +176,25 @@ pub async fn handle(
         return Ok(response);
     }
 
-    let post_descriptor = post_descriptor.unwrap();
-    info!("watch_post() post_descriptor: {}", post_descriptor);
-
-    let post_watch_created_result = post_repository::start_watching_post(
+    let post_watch_created_result = post_repository::start_watching_post_with_filter(
         database,
         &account_id,
         &application_type,
-        &post_descriptor
+        &post_descriptor,
+        filter_regex
     ).await.context(format!("Failed to start watching post {}", post_descriptor))?;
 
     if post_watch_created_result != StartWatchingPostResult::Ok {
-        let error_message = match post_watch_created_result {
+        let (error_code, error_message) = match post_watch_created_result {
             StartWatchingPostResult::Ok => unreachable!(),
-            StartWatchingPostResult::AccountDoesNotExist => "Account does not exist",
-            StartWatchingPostResult::AccountHasNoToken => "Account has no token",
-            StartWatchingPostResult::AccountIsNotValid => "Account already expired",
+            StartWatchingPostResult::AccountDoesNotExist => (ErrorCode::AccountNotFound, "Account does not exist"),
+            StartWatchingPostResult::AccountHasNoToken => (ErrorCode::AccountHasNoToken, "Account has no token"),
+            StartWatchingPostResult::AccountIsNotValid => (ErrorCode::AccountExpired, "Account already expired"),
+            StartWatchingPostResult::AccountIsBanned => (ErrorCode::AccountBanned, "Account is banned"),
+            StartWatchingPostResult::WatchLimitReached => (ErrorCode::WatchLimitReached, "Too many watched posts"),
         };
 
-        let response_json = error_response_str(error_message)?;
+        let response_json = error_response_str(error_code, error_message)?;
 
         let response = Response::builder()
             .json()
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{constants, error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, ServerSuccessResponse, collect_limited, error_response_str, error_response_string, max_request_body_size, success_response, throttle_account, validate_post_url};
+use crate::helpers::serde_helpers::{deserialize_application_type, serialize_application_type};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::data::chan::PostDescriptor;
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository::{AccountId, ApplicationType};
+use crate::model::repository::post_repository;
+use crate::model::repository::post_repository::StartWatchingPostResult;
+use crate::model::repository::site_repository::SiteRepository;
+use crate::router::TestContext;
+
+pub const WATCH_POST_RESULT_OK: &str = "ok";
+pub const WATCH_POST_RESULT_BAD_URL: &str = "bad_url";
+pub const WATCH_POST_RESULT_UNSUPPORTED_SITE: &str = "unsupported_site";
+pub const WATCH_POST_RESULT_UNPARSEABLE: &str = "unparseable";
+pub const WATCH_POST_RESULT_UNKNOWN_BOARD: &str = "unknown_board";
+
+#[derive(Serialize, Deserialize)]
+pub struct WatchPostsRequest {
+    pub user_id: String,
+    pub post_urls: Vec<String>,
+    #[serde(
+        serialize_with = "serialize_application_type",
+        deserialize_with = "deserialize_application_type"
+    )]
+    pub application_type: ApplicationType,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WatchPostsResponse {
+    pub results: Vec<WatchPostResult>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WatchPostResult {
+    pub post_url: String,
+    pub result: String
+}
+
+impl ServerSuccessResponse for WatchPostsResponse {
+
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>,
+    test_context: Option<TestContext>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: WatchPostsRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into WatchPostsRequest")?;
+
+    let application_type = request.application_type;
+    if application_type == ApplicationType::Unknown {
+        let error_message = format!(
+            "Unsupported \'application_type\' parameter value: {}",
+            application_type as isize
+        );
+
+        error!("watch_posts() {}", error_message);
+
+        let response_json = error_response_string(ErrorCode::UnsupportedApplicationType, &error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    if request.post_urls.is_empty() || request.post_urls.len() > constants::MAX_WATCH_POSTS_PER_REQUEST {
+        let error_message = format!(
+            "\'post_urls\' must contain from 1 to {} urls",
+            constants::MAX_WATCH_POSTS_PER_REQUEST
+        );
+
+        error!("watch_posts() {}", error_message);
+
+        let response_json = error_response_string(ErrorCode::InvalidParameter, &error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/watch_posts").await?;
+
+    let mut results = Vec::<WatchPostResult>::with_capacity(request.post_urls.len());
+    let mut post_descriptors = Vec::<PostDescriptor>::with_capacity(request.post_urls.len());
+
+    for post_url in &request.post_urls {
+        let (result, post_descriptor) = resolve_post_url(post_url, site_repository);
+        if post_descriptor.is_some() {
+            post_descriptors.push(post_descriptor.unwrap());
+        }
+
+        results.push(WatchPostResult { post_url: post_url.clone(), result: result.to_string() });
+    }
+
+    let post_watch_created_result = post_repository::start_watching_posts(
+        database,
+        &account_id,
+        &application_type,
+        &post_descriptors
+    ).await.context(format!("Failed to start watching {} posts", post_descriptors.len()))?;
+
+    if post_watch_created_result != StartWatchingPostResult::Ok {
+        let (error_code, error_message) = match post_watch_created_result {
+            StartWatchingPostResult::Ok => unreachable!(),
+            StartWatchingPostResult::AccountDoesNotExist => (ErrorCode::AccountNotFound, "Account does not exist"),
+            StartWatchingPostResult::AccountHasNoToken => (ErrorCode::AccountHasNoToken, "Account has no token"),
+            StartWatchingPostResult::AccountIsNotValid => (ErrorCode::AccountExpired, "Account already expired"),
+            StartWatchingPostResult::AccountIsBanned => (ErrorCode::AccountBanned, "Account is banned"),
+            StartWatchingPostResult::WatchLimitReached => (ErrorCode::WatchLimitReached, "Too many watched posts"),
+        };
+
+        let response_json = error_response_str(error_code, error_message)?;
+
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        info!(
+            "Failed to start watching posts for account {}, result: {:?}",
+            account_id,
+            post_watch_created_result
+        );
+
+        return Ok(response);
+    }
+
+    let response_json = success_response(WatchPostsResponse { results })?;
+
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    info!(
+        "Post watches for {} posts out of {} and account id {} were successfully created",
+        post_descriptors.len(),
+        request.post_urls.len(),
+        account_id.format_token()
+    );
+
+    return Ok(response);
+}
+
+pub fn resolve_post_url(
+    post_url: &String,
+    site_repository: &Arc<SiteRepository>
+) -> (&'static str, Option<PostDescriptor>) {
+    let post_url = validate_post_url(post_url);
+    if post_url.is_err() {
+        return (WATCH_POST_RESULT_BAD_URL, None);
+    }
+
+    let post_url = post_url.unwrap();
+
+    let imageboard = site_repository.by_url(post_url);
+    if imageboard.is_none() {
+        return (WATCH_POST_RESULT_UNSUPPORTED_SITE, None);
+    }
+
+    let imageboard = imageboard.unwrap();
+
+    let post_descriptor = imageboard.post_url_to_post_descriptor(post_url);
+    if post_descriptor.is_none() {
+        return (WATCH_POST_RESULT_UNPARSEABLE, None);
+    }
+
+    let post_descriptor = post_descriptor.unwrap();
+
+    if !imageboard.is_known_board(post_descriptor.board_code()) {
+        return (WATCH_POST_RESULT_UNKNOWN_BOARD, None);
+    }
+
+    return (WATCH_POST_RESULT_OK, Some(post_descriptor));
+}
@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::{error, info};
+use crate::handlers::shared::{ContentType, ErrorCode, ServerSuccessResponse, collect_limited, error_response_str, max_request_body_size, success_response, throttle_account};
+use crate::helpers::serde_helpers::{deserialize_application_type, deserialize_datetime, serialize_application_type, serialize_datetime_option};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::database::db::Database;
+use crate::model::repository::{account_repository, post_repository};
+use crate::model::repository::account_repository::{AccountId, ApplicationType};
+use crate::router::TestContext;
+
+#[derive(Serialize, Deserialize)]
+pub struct WhoAmIRequest {
+    pub user_id: String
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WhoAmIResponse {
+    pub account_id: String,
+    #[serde(
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub valid_until: Option<DateTime<Utc>>,
+    pub is_banned: bool,
+    pub applications: Vec<WhoAmIApplicationResponse>,
+    pub watches_count: i64
+}
+
+/// One entry per supported application type, [tokens] are formatted so that they can't be used.
+#[derive(Serialize, Deserialize)]
+pub struct WhoAmIApplicationResponse {
+    #[serde(
+        serialize_with = "serialize_application_type",
+        deserialize_with = "deserialize_application_type"
+    )]
+    pub application_type: ApplicationType,
+    pub is_valid: bool,
+    pub tokens: Vec<String>
+}
+
+impl ServerSuccessResponse for WhoAmIResponse {
+
+}
+
+pub async fn handle(
+    _query: &str,
+    body: Incoming,
+    database: &Arc<Database>,
+    test_context: Option<TestContext>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    let body_bytes = collect_limited(body, max_request_body_size()).await?;
+
+    let body_as_string = String::from_utf8(body_bytes.to_vec())
+        .context("Failed to convert body into a string")?;
+
+    let request: WhoAmIRequest = serde_json::from_str(body_as_string.as_str())
+        .context("Failed to convert body into WhoAmIRequest")?;
+
+    let account_id = AccountId::from_user_id(&request.user_id)?;
+    throttle_account(test_context, &account_id, "/whoami").await?;
+
+    let account = account_repository::get_account(&account_id, database)
+        .await
+        .with_context(|| {
+            return format!(
+                "whoami() Failed to get account from repository with account_id \'{}\'",
+                account_id.format_token()
+            );
+        })?;
+
+    if account.is_none() {
+        error!(
+            "whoami() Account with id \'{}\' does not exist",
+            account_id.format_token()
+        );
+
+        let response_json = error_response_str(ErrorCode::AccountNotFound, "Account does not exist")?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(response);
+    }
+
+    let account = account.unwrap();
+
+    let (account_db_id, valid_until, is_banned, applications) = {
+        let acc = account.lock().await;
+
+        let applications = ApplicationType::supported()
+            .into_iter()
+            .map(|application_type| {
+                let tokens = acc.get_account_tokens(&application_type)
+                    .iter()
+                    .map(|account_token| account_token.token.format_token().to_string())
+                    .collect::<Vec<String>>();
+
+                return WhoAmIApplicationResponse {
+                    is_valid: acc.is_valid(&application_type),
+                    application_type,
+                    tokens
+                };
+            })
+            .collect::<Vec<WhoAmIApplicationResponse>>();
+
+        (acc.id, acc.valid_until, acc.is_banned(), applications)
+    };
+
+    let watches_count = post_repository::count_account_watches(database, account_db_id)
+        .await
+        .with_context(|| {
+            return format!(
+                "whoami() Failed to count watches of account with account_id \'{}\'",
+                account_id.format_token()
+            );
+        })?;
+
+    let whoami_response = WhoAmIResponse {
+        account_id: account_id.format_token().to_string(),
+        valid_until,
+        is_banned,
+        applications,
+        watches_count
+    };
+
+    let response_json = success_response(whoami_response)?;
+    let response = Response::builder()
+        .json()
+        .status(200)
+        .body(Full::new(Bytes::from(response_json)))?;
+
+    info!("whoami() Success \'{}\'", account_id.format_token());
+    return Ok(response);
+}
@@ -27,4 +27,20 @@ fn sha3_512_internal(str: &str, iterations: usize) -> String {
     }
 
     return hash;
+}
+
+/// Compares [a] and [b] without bailing out on the first mismatch so that the time it takes doesn't
+/// depend on how many leading bytes match. The length of the inputs is not considered secret.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut diff = 0u8;
+
+    for (a_byte, b_byte) in a.iter().zip(b.iter()) {
+        diff |= a_byte ^ b_byte;
+    }
+
+    return diff == 0;
 }
\ No newline at end of file
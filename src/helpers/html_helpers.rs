@@ -0,0 +1,196 @@
+// Longest entity we decode is "&#x10FFFF;"
+const MAX_ENTITY_LENGTH: usize = 10;
+
+/// Converts a post comment (4chan/2ch html) into plain text. Tags are removed, <br> tags are
+/// converted into new lines and the common html entities are decoded. Entities are only decoded
+/// in text so "&lt;b&gt;" becomes "<b>" and not a tag. Anything that doesn't look like a tag or a
+/// known entity is kept as is.
+pub fn strip_html_to_text(html: &str) -> String {
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if rest.starts_with('<') {
+            let tag_length = tag_length(rest);
+            if tag_length.is_some() {
+                let tag_length = tag_length.unwrap();
+
+                if is_line_break_tag(&rest[..tag_length]) {
+                    result.push('\n');
+                }
+
+                rest = &rest[tag_length..];
+                continue;
+            }
+        }
+
+        if rest.starts_with('&') {
+            let entity = decode_entity(rest);
+            if entity.is_some() {
+                let (decoded, entity_length) = entity.unwrap();
+
+                result.push(decoded);
+                rest = &rest[entity_length..];
+                continue;
+            }
+        }
+
+        let ch = rest.chars().next().unwrap();
+        result.push(ch);
+        rest = &rest[ch.len_utf8()..];
+    }
+
+    return result;
+}
+
+/// [text] must start with '<'. Returns the length of the tag including the closing '>' or None
+/// when [text] doesn't start with a tag (e.g. "a < b" or an unclosed tag).
+fn tag_length(text: &str) -> Option<usize> {
+    let next_char = text[1..].chars().next();
+    if next_char.is_none() {
+        return None;
+    }
+
+    let next_char = next_char.unwrap();
+    if !next_char.is_ascii_alphabetic() && next_char != '/' && next_char != '!' {
+        return None;
+    }
+
+    return text.find('>').map(|index| index + 1);
+}
+
+fn is_line_break_tag(tag: &str) -> bool {
+    let tag_name = tag.trim_start_matches('<')
+        .trim_end_matches('>')
+        .split(|ch: char| ch.is_whitespace() || ch == '/')
+        .next()
+        .unwrap_or("");
+
+    return tag_name.eq_ignore_ascii_case("br");
+}
+
+/// [text] must start with '&'. Returns the decoded character and the length of the entity
+/// including the trailing ';'.
+fn decode_entity(text: &str) -> Option<(char, usize)> {
+    let end = text.char_indices()
+        .take(MAX_ENTITY_LENGTH + 1)
+        .find(|(_, ch)| *ch == ';')
+        .map(|(index, _)| index);
+
+    if end.is_none() {
+        return None;
+    }
+
+    let end = end.unwrap();
+    let name = &text[1..end];
+
+    let decoded = match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => decode_numeric_entity(name)
+    };
+
+    return decoded.map(|decoded| (decoded, end + 1));
+}
+
+fn decode_numeric_entity(name: &str) -> Option<char> {
+    if !name.starts_with('#') {
+        return None;
+    }
+
+    let number = &name[1..];
+
+    let code_point = if number.starts_with('x') || number.starts_with('X') {
+        u32::from_str_radix(&number[1..], 16).ok()
+    } else {
+        number.parse::<u32>().ok()
+    };
+
+    return code_point.and_then(|code_point| char::from_u32(code_point));
+}
+
+#[test]
+fn test_strip_html_to_text_quote_links() {
+    assert_eq!(
+        ">>251260223\nI agree",
+        strip_html_to_text("<a href=\"#p251260223\" class=\"quotelink\">&gt;&gt;251260223</a><br>I agree")
+    );
+
+    assert_eq!(
+        ">>92933496\n>>92933523\nWill look into them, it shouldn't be much work",
+        strip_html_to_text(
+            "<a href=\"#p92933496\" class=\"quotelink\">&gt;&gt;92933496</a><br>\
+            <a href=\"#p92933523\" class=\"quotelink\">&gt;&gt;92933523</a><br>\
+            Will look into them, it shouldn&#039;t be much work"
+        )
+    );
+
+    // 2ch doesn't escape the quote arrows
+    assert_eq!(
+        ">>197895\nhello",
+        strip_html_to_text(
+            "<a href=\"/test/res/197273.html#197895\" class=\"post-reply-link\" \
+            data-thread=\"197273\" data-num=\"197895\">>>197895</a><br>hello"
+        )
+    );
+
+    assert_eq!(
+        ">greentext",
+        strip_html_to_text("<span class=\"quote\">&gt;greentext</span>")
+    );
+}
+
+#[test]
+fn test_strip_html_to_text_entities() {
+    assert_eq!("& < > \" '  ", strip_html_to_text("&amp; &lt; &gt; &quot; &apos; &nbsp;"));
+    assert_eq!("it's", strip_html_to_text("it&#039;s"));
+    assert_eq!("it's", strip_html_to_text("it&#39;s"));
+    assert_eq!("it's", strip_html_to_text("it&#x27;s"));
+    assert_eq!("€", strip_html_to_text("&#8364;"));
+    assert_eq!("<b>not a tag</b>", strip_html_to_text("&lt;b&gt;not a tag&lt;/b&gt;"));
+    assert_eq!("&amp;", strip_html_to_text("&amp;amp;"));
+
+    // Unknown, broken or unterminated entities are kept as is
+    assert_eq!("&unknown;", strip_html_to_text("&unknown;"));
+    assert_eq!("&#xZZ;", strip_html_to_text("&#xZZ;"));
+    assert_eq!("&#1114112;", strip_html_to_text("&#1114112;"));
+    assert_eq!("fish & chips", strip_html_to_text("fish & chips"));
+    assert_eq!("&", strip_html_to_text("&"));
+}
+
+#[test]
+fn test_strip_html_to_text_nested_tags() {
+    assert_eq!(
+        "spoiler with bold and italic text",
+        strip_html_to_text("<s>spoiler with <b>bold and <i>italic</i></b> text</s>")
+    );
+
+    assert_eq!(
+        "first\nsecond\n\nthird",
+        strip_html_to_text("first<br>second<BR/><br />third")
+    );
+
+    assert_eq!(
+        "averylongword",
+        strip_html_to_text("avery<wbr>long<wbr>word")
+    );
+
+    assert_eq!(
+        "quote inside spoiler >>1",
+        strip_html_to_text("<span class=\"spoiler\">quote inside spoiler <a class=\"quotelink\">&gt;&gt;1</a></span>")
+    );
+}
+
+#[test]
+fn test_strip_html_to_text_keeps_text_that_is_not_a_tag() {
+    assert_eq!("", strip_html_to_text(""));
+    assert_eq!("plain text", strip_html_to_text("plain text"));
+    assert_eq!("1 < 2 and 3 > 2", strip_html_to_text("1 < 2 and 3 > 2"));
+    assert_eq!("unclosed <b tag", strip_html_to_text("unclosed <b tag"));
+    assert_eq!("ends with <", strip_html_to_text("ends with <"));
+    assert_eq!("юникод текст", strip_html_to_text("<b>юникод</b> текст"));
+}
@@ -0,0 +1,161 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use url::Url;
+
+use crate::{constants, info, warn};
+
+pub const MAX_REDIRECTS: usize = 3;
+
+/// Builds the client that is shared by the whole server (and its connection pool). It's passed to
+/// the SiteRepository and the ThreadWatcher, do not build other clients for outbound requests
+/// (except for create_web_push_http_client()).
+pub fn create_shared_http_client(
+    outbound_proxy: Option<String>,
+    request_timeout: Duration,
+    connect_timeout: Duration
+) -> Arc<reqwest::Client> {
+    if outbound_proxy.is_some() {
+        info!("create_shared_http_client() outbound proxy: \'{}\'", outbound_proxy.as_ref().unwrap());
+    }
+
+    info!(
+        "create_shared_http_client() request timeout: {}s, connect timeout: {}s",
+        request_timeout.as_secs(),
+        connect_timeout.as_secs()
+    );
+
+    let http_client = build_http_client(
+        outbound_proxy.as_ref().map(|proxy| proxy.as_str()),
+        request_timeout,
+        connect_timeout
+    );
+
+    return Arc::new(http_client);
+}
+
+pub fn build_http_client(
+    outbound_proxy: Option<&str>,
+    request_timeout: Duration,
+    connect_timeout: Duration
+) -> reqwest::Client {
+    // Some sites (and proxies) compress their responses, reqwest decompresses them transparently
+    // and strips the Content-Encoding header.
+    // The request timeout covers the whole request (including reading the body) so that a site
+    // that accepts the connection but never responds can't stall the thread watcher.
+    // The whole server shares this one client (and its connection pool). With high watcher
+    // concurrency most requests go to a handful of hosts, so keep enough idle connections around
+    // for them to be reused instead of opening new ones and running out of ephemeral ports.
+    let mut builder = reqwest::Client::builder()
+        .gzip(true)
+        .brotli(true)
+        .redirect(redirect_policy())
+        .timeout(request_timeout)
+        .connect_timeout(connect_timeout)
+        .pool_max_idle_per_host(constants::HTTP_POOL_MAX_IDLE_PER_HOST)
+        .tcp_keepalive(Duration::from_secs(constants::HTTP_TCP_KEEPALIVE_SECONDS));
+
+    if outbound_proxy.is_some() && !outbound_proxy.unwrap().is_empty() {
+        let outbound_proxy = outbound_proxy.unwrap();
+
+        match parse_outbound_proxy(outbound_proxy) {
+            Ok(proxy) => {
+                builder = builder.proxy(proxy);
+            }
+            Err(error) => {
+                warn!(
+                    "build_http_client() Failed to parse outbound proxy \'{}\', error: {}. \
+                    Falling back to direct connection.",
+                    outbound_proxy,
+                    error
+                );
+            }
+        }
+    }
+
+    let http_client = builder.build();
+    if http_client.is_err() {
+        warn!(
+            "build_http_client() Failed to build http client, error: {}. \
+            Falling back to default client.",
+            http_client.err().unwrap()
+        );
+
+        return reqwest::Client::new();
+    }
+
+    return http_client.unwrap();
+}
+
+/// Builds the client that delivers Web Push messages. The endpoints are provided by the users so
+/// it's separate from the shared client: it never goes through the outbound proxy (which may have
+/// access to hosts that the server itself doesn't) and doesn't follow redirects since a push
+/// service never redirects.
+pub fn create_web_push_http_client(
+    request_timeout: Duration,
+    connect_timeout: Duration
+) -> anyhow::Result<Arc<reqwest::Client>> {
+    let http_client = reqwest::Client::builder()
+        .no_proxy()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(request_timeout)
+        .connect_timeout(connect_timeout)
+        .build()?;
+
+    return Ok(Arc::new(http_client));
+}
+
+fn redirect_policy() -> reqwest::redirect::Policy {
+    return reqwest::redirect::Policy::custom(|attempt| {
+        if attempt.previous().len() > MAX_REDIRECTS {
+            return attempt.error(format!("Too many redirects (max {})", MAX_REDIRECTS));
+        }
+
+        if attempt.previous().contains(attempt.url()) {
+            return attempt.error(format!("Redirect loop detected at \'{}\'", attempt.url()));
+        }
+
+        return attempt.follow();
+    });
+}
+
+fn parse_outbound_proxy(outbound_proxy: &str) -> anyhow::Result<reqwest::Proxy> {
+    let url = Url::parse(outbound_proxy)?;
+
+    match url.scheme() {
+        "http" | "https" | "socks5" | "socks5h" => {}
+        scheme => return Err(anyhow!("Unsupported proxy scheme \'{}\'", scheme))
+    }
+
+    if url.host_str().is_none() {
+        return Err(anyhow!("Proxy url has no host"));
+    }
+
+    let proxy = reqwest::Proxy::all(outbound_proxy)?;
+    return Ok(proxy);
+}
+
+#[test]
+fn test_parse_outbound_proxy() {
+    assert!(parse_outbound_proxy("http://127.0.0.1:8080").is_ok());
+    assert!(parse_outbound_proxy("https://proxy.example.com:3128").is_ok());
+    assert!(parse_outbound_proxy("socks5://127.0.0.1:1080").is_ok());
+
+    assert!(parse_outbound_proxy("").is_err());
+    assert!(parse_outbound_proxy("127.0.0.1:8080").is_err());
+    assert!(parse_outbound_proxy("ftp://127.0.0.1:21").is_err());
+}
+
+#[test]
+fn test_build_http_client_with_proxy() {
+    let http_client = build_http_client(
+        Some("http://127.0.0.1:8080"),
+        Duration::from_secs(constants::DEFAULT_HTTP_REQUEST_TIMEOUT_SECONDS),
+        Duration::from_secs(constants::DEFAULT_HTTP_CONNECT_TIMEOUT_SECONDS)
+    );
+    let debug_string = format!("{:?}", http_client);
+
+    assert!(debug_string.contains("proxies"));
+    assert!(debug_string.contains("127.0.0.1:8080"));
+}
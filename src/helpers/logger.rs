@@ -1,36 +1,64 @@
 use std::fmt;
 use std::fmt::{Display, Formatter};
-use std::sync::Arc;
+use std::sync::{Arc, PoisonError, RwLock};
 use std::time::Duration;
 
-use chrono::{Datelike, DateTime, Local, Timelike, TimeZone, Utc};
+use chrono::{Datelike, DateTime, Local, SecondsFormat, Timelike, TimeZone, Utc};
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tokio::sync::Mutex;
 
 use crate::model::database::db::Database;
+use crate::model::repository::logs_repository;
+use crate::model::repository::logs_repository::NewLogLine;
 
 pub struct Logger {
     is_dev_build: bool,
     sender: UnboundedSender<LogLine>
 }
 
-static mut LOGGER: Option<Logger> = None;
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LogFormat {
+    Pretty,
+    Json
+}
+
+impl LogFormat {
+    pub fn from_str(value: &str) -> LogFormat {
+        return match value.to_lowercase().as_str() {
+            "json" => LogFormat::Json,
+            _ => LogFormat::Pretty
+        };
+    }
+}
+
+// Not a OnceCell because every test module runs in its own tokio runtime and has to init the
+// logger again (the log processing task dies together with the runtime it was spawned on).
+static LOGGER: RwLock<Option<Logger>> = RwLock::new(None);
 
-pub fn init_logger(is_dev_build: bool, database: Option<Arc<Database>>) {
-    // We init the logger only once at the very beginning so it should be fine
-    unsafe { LOGGER = Some(Logger::new(is_dev_build, database)); }
+pub fn init_logger(is_dev_build: bool, log_format: LogFormat, database: Option<Arc<Database>>) {
+    let logger = Logger::new(is_dev_build, log_format, database);
+
+    let mut logger_locked = LOGGER.write().unwrap_or_else(PoisonError::into_inner);
+    *logger_locked = Some(logger);
 }
 
-fn logger() -> &'static Logger {
-    return unsafe { LOGGER.as_ref().unwrap() };
+/// Logs sent before the logger is initialized are dropped. A poisoned lock is still usable since
+/// the logger is replaced atomically and can't be left in a broken state.
+fn send_log_line(logger: &RwLock<Option<Logger>>, log_line: LogLine) {
+    let logger_locked = logger.read().unwrap_or_else(PoisonError::into_inner);
+    if logger_locked.is_none() {
+        return;
+    }
+
+    let _ = logger_locked.as_ref().unwrap().sender.send(log_line);
 }
 
 impl Logger {
-    pub fn new(is_dev_build: bool, database: Option<Arc<Database>>) -> Logger {
+    pub fn new(is_dev_build: bool, log_format: LogFormat, database: Option<Arc<Database>>) -> Logger {
         let (sender, receiver) = tokio::sync::mpsc::unbounded_channel::<LogLine>();
 
         tokio::spawn(async move {
-            Self::process_logs(is_dev_build, database, receiver).await;
+            Self::process_logs(is_dev_build, log_format, database, receiver).await;
         });
 
         return Self { is_dev_build, sender };
@@ -38,6 +66,7 @@ impl Logger {
 
     async fn process_logs(
         is_dev_build: bool,
+        log_format: LogFormat,
         database: Option<Arc<Database>>,
         mut receiver: UnboundedReceiver<LogLine>
     ) {
@@ -58,30 +87,12 @@ impl Logger {
 
             let log_line = log_line.unwrap();
 
-            // Only print logs to console when is_dev_build is true. In production version only
-            // store logs into the database since we won't be able to see them anyway.
-            if is_dev_build {
-                let local_time: DateTime<Local> = DateTime::from(log_line.date_time);
-
-                let date_time = format!(
-                    "{}-{:02}-{:02} {:02}-{:02}-{:02}.{:03}",
-                    local_time.year(),
-                    local_time.month(),
-                    local_time.day(),
-                    local_time.hour(),
-                    local_time.minute(),
-                    local_time.second(),
-                    local_time.timestamp_millis() % 1000,
-                );
-
-                let formatted_log = format!(
-                    "{} [{}] {}@{} -- {}",
-                    log_line.log_level,
-                    date_time,
-                    log_line.target,
-                    log_line.thread_id,
-                    log_line.arguments
-                );
+            // Only print pretty logs to console when is_dev_build is true. In production version
+            // only store logs into the database since we won't be able to see them anyway, unless
+            // json format is requested, in which case the logs are expected to be collected from
+            // the console.
+            if is_dev_build || log_format == LogFormat::Json {
+                let formatted_log = format_console_log(log_format, &log_line);
 
                 if log_line.log_level == LogLevel::Info {
                     println!("{}", formatted_log);
@@ -173,37 +184,19 @@ impl Logger {
         database: &Arc<Database>,
         unsent_logs: &Vec<LogLine>
     ) -> anyhow::Result<()> {
-        if unsent_logs.is_empty() {
-            return Ok(());
-        }
-
-        let mut connection = database.connection().await?;
-        let transaction = connection.transaction().await?;
-
-        let query = r#"
-            INSERT INTO logs(
-                log_time,
-                log_level,
-                target,
-                message
-            )
-            VALUES ($1, $2, $3, $4)
-        "#;
-
-        for unsent_log in unsent_logs {
-            transaction.execute(
-                query,
-                &[
-                    &unsent_log.date_time,
-                    &Self::log_level_to_string(&unsent_log.log_level),
-                    &unsent_log.target,
-                    &unsent_log.arguments
-                ]
-            ).await?;
-        }
-
-        transaction.commit().await?;
-        return Ok(());
+        let new_log_lines = unsent_logs.iter()
+            .map(|unsent_log| {
+                return NewLogLine {
+                    log_time: unsent_log.date_time,
+                    log_level: Self::log_level_to_string(&unsent_log.log_level).to_string(),
+                    target: unsent_log.target.clone(),
+                    message: unsent_log.arguments.clone()
+                };
+            })
+            .collect::<Vec<NewLogLine>>();
+
+        // Large messages get compressed there if LOG_COMPRESSION_THRESHOLD is set
+        return logs_repository::store_logs(database, &new_log_lines).await;
     }
 
     fn log_level_to_string(log_level: &LogLevel) -> &str {
@@ -236,6 +229,48 @@ impl Display for LogLevel {
     }
 }
 
+fn format_console_log(log_format: LogFormat, log_line: &LogLine) -> String {
+    if log_format == LogFormat::Json {
+        let log_level = match log_line.log_level {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+        };
+
+        let json = serde_json::json!({
+            "level": log_level,
+            "ts": log_line.date_time.to_rfc3339_opts(SecondsFormat::Millis, true),
+            "target": log_line.target,
+            "thread_id": log_line.thread_id,
+            "message": log_line.arguments
+        });
+
+        return json.to_string();
+    }
+
+    let local_time: DateTime<Local> = DateTime::from(log_line.date_time);
+
+    let date_time = format!(
+        "{}-{:02}-{:02} {:02}-{:02}-{:02}.{:03}",
+        local_time.year(),
+        local_time.month(),
+        local_time.day(),
+        local_time.hour(),
+        local_time.minute(),
+        local_time.second(),
+        local_time.timestamp_millis() % 1000,
+    );
+
+    return format!(
+        "{} [{}] {}@{} -- {}",
+        log_line.log_level,
+        date_time,
+        log_line.target,
+        log_line.thread_id,
+        log_line.arguments
+    );
+}
+
 #[derive(Clone)]
 struct LogLine {
     date_time: DateTime<Utc>,
@@ -323,6 +358,61 @@ pub fn __private_api_log(
         thread_id: thread_id
     };
 
-    let logger = logger();
-    let _ = logger.sender.send(log_line);
-}
\ No newline at end of file
+    send_log_line(&LOGGER, log_line);
+}
+
+#[test]
+fn test_format_console_log_json() {
+    let log_line = LogLine {
+        date_time: Utc.timestamp_millis_opt(1_000).unwrap(),
+        log_level: LogLevel::Error,
+        target: "kpns::service".to_string(),
+        arguments: "Something \"bad\" happened".to_string(),
+        thread_id: 7
+    };
+
+    let formatted = format_console_log(LogFormat::Json, &log_line);
+    assert!(!formatted.contains('\n'));
+
+    let json: serde_json::Value = serde_json::from_str(&formatted).unwrap();
+    assert_eq!("error", json["level"]);
+    assert_eq!("1970-01-01T00:00:01.000Z", json["ts"]);
+    assert_eq!("kpns::service", json["target"]);
+    assert_eq!(7, json["thread_id"]);
+    assert_eq!("Something \"bad\" happened", json["message"]);
+}
+
+#[test]
+fn test_logging_before_init_does_not_panic() {
+    let log_line = LogLine {
+        date_time: Utc::now(),
+        log_level: LogLevel::Info,
+        target: "kpns::helpers::logger".to_string(),
+        arguments: "test".to_string(),
+        thread_id: 1
+    };
+
+    let logger = RwLock::new(None);
+    send_log_line(&logger, log_line.clone());
+
+    // Poison the lock
+    let _ = std::thread::scope(|scope| {
+        return scope.spawn(|| {
+            let _logger_locked = logger.write().unwrap();
+            panic!("poison the lock");
+        }).join();
+    });
+
+    assert!(logger.is_poisoned());
+    send_log_line(&logger, log_line);
+
+    crate::info!("logging through the macro never panics");
+}
+
+#[test]
+fn test_log_format_from_str() {
+    assert_eq!(LogFormat::Json, LogFormat::from_str("json"));
+    assert_eq!(LogFormat::Json, LogFormat::from_str("JSON"));
+    assert_eq!(LogFormat::Pretty, LogFormat::from_str("pretty"));
+    assert_eq!(LogFormat::Pretty, LogFormat::from_str(""));
+}
@@ -4,4 +4,7 @@ pub mod db_helpers;
 pub mod post_helpers;
 pub mod hashers;
 pub mod throttler;
-pub mod logger;
\ No newline at end of file
+pub mod logger;
+pub mod http_client;
+pub mod regex_helpers;
+pub mod html_helpers;
\ No newline at end of file
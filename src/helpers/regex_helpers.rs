@@ -0,0 +1,67 @@
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use regex::{Regex, RegexBuilder};
+
+use crate::error;
+
+// Limits the size of the compiled regex so that users can't make us compile something huge
+const FILTER_REGEX_SIZE_LIMIT: usize = 256 * 1024;
+
+lazy_static! {
+    static ref COMPILED_FILTER_REGEXES: Mutex<lru::LruCache<String, Arc<Regex>>> =
+        Mutex::new(lru::LruCache::new(NonZeroUsize::new(1024).unwrap()));
+}
+
+/// Filter regexes are case insensitive. Compiled regexes are cached by their pattern.
+pub fn compile_filter_regex(pattern: &str) -> anyhow::Result<Arc<Regex>> {
+    {
+        let mut compiled_filter_regexes = COMPILED_FILTER_REGEXES.lock().unwrap();
+
+        let compiled_filter_regex = compiled_filter_regexes.get(pattern);
+        if compiled_filter_regex.is_some() {
+            return Ok(compiled_filter_regex.unwrap().clone());
+        }
+    }
+
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .size_limit(FILTER_REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|error| anyhow!("Bad filter regex \'{}\': {}", pattern, error))?;
+
+    let regex = Arc::new(regex);
+    COMPILED_FILTER_REGEXES.lock().unwrap().put(pattern.to_string(), regex.clone());
+
+    return Ok(regex);
+}
+
+/// Watches without a filter match everything. Patterns that fail to compile match nothing.
+pub fn matches_filter_regex(filter_regex: &Option<String>, text: &str) -> bool {
+    if filter_regex.is_none() {
+        return true;
+    }
+
+    let filter_regex = filter_regex.as_ref().unwrap();
+
+    let compiled_filter_regex = compile_filter_regex(filter_regex);
+    if compiled_filter_regex.is_err() {
+        error!("matches_filter_regex() {}", compiled_filter_regex.err().unwrap());
+        return false;
+    }
+
+    return compiled_filter_regex.unwrap().is_match(text);
+}
+
+#[test]
+fn test_matches_filter_regex() {
+    assert!(matches_filter_regex(&None, "anything"));
+    assert!(matches_filter_regex(&Some("!!Tripcode".to_string()), "Posted by !!tRIPCODE"));
+    assert!(matches_filter_regex(&Some(r"\bpatch\s+\d+".to_string()), "new patch 123 is out"));
+    assert!(!matches_filter_regex(&Some(r"\bpatch\s+\d+".to_string()), "no patches yet"));
+    assert!(!matches_filter_regex(&Some("(unclosed".to_string()), "(unclosed"));
+
+    assert!(compile_filter_regex("(unclosed").is_err());
+}
@@ -54,6 +54,12 @@ pub fn serialize_application_type<S>(
         ApplicationType::KurobaExLiteProduction => {
             ApplicationType::KurobaExLiteProduction as isize
         }
+        ApplicationType::KurobaExDebug => {
+            ApplicationType::KurobaExDebug as isize
+        }
+        ApplicationType::KurobaExProduction => {
+            ApplicationType::KurobaExProduction as isize
+        }
         ApplicationType::Unknown => {
             ApplicationType::Unknown as isize
         }
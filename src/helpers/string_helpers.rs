@@ -119,6 +119,19 @@ pub fn insert_after_every_nth(input: &str, insert: &str, n: usize) -> String {
         .collect();
 }
 
+/// Random (version 4) UUID in the usual 8-4-4-4-12 hex format.
+pub fn random_uuid_v4() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = bytes.iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+
+    return format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32]);
+}
+
 #[test]
 fn test_format_token_internal() {
     let token = "";
@@ -153,4 +166,14 @@ fn test_format_token_internal() {
 fn test_extract_site_name_from_domain() {
     assert_eq!("2ch", extract_site_name_from_domain("2ch.hk"));
     assert_eq!("4chan", extract_site_name_from_domain("boards.4chan.org"));
+}
+
+#[test]
+fn test_random_uuid_v4() {
+    let uuid = random_uuid_v4();
+
+    assert_eq!(36, uuid.len());
+    assert_eq!(vec![8, 4, 4, 4, 12], uuid.split('-').map(|part| part.len()).collect::<Vec<usize>>());
+    assert_eq!(Some('4'), uuid.chars().nth(14));
+    assert_ne!(uuid, random_uuid_v4());
 }
\ No newline at end of file
@@ -1,18 +1,92 @@
 use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
 use std::num::NonZeroUsize;
-use std::time::Duration;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
+use anyhow::anyhow;
 use lazy_static::lazy_static;
 use tokio::sync::RwLock;
 
 use crate::{info, warn};
+use crate::model::repository::account_repository::AccountId;
 use crate::router::TestContext;
 
 lazy_static! {
     static ref VISITORS: RwLock<lru::LruCache<String, VisitorInfo>> =
         RwLock::new(lru::LruCache::new(NonZeroUsize::new(4096).unwrap()));
 
+    static ref ACCOUNT_VISITORS: RwLock<lru::LruCache<String, VisitorInfo>> =
+        RwLock::new(lru::LruCache::new(NonZeroUsize::new(4096).unwrap()));
+
     static ref REQUEST_LIMITS: RwLock<HashMap<String, usize>> = RwLock::new(init_request_limits());
+
+    static ref ALLOWLIST: RwLock<Vec<AllowlistEntry>> = RwLock::new(Vec::new());
+
+    static ref LOCKOUT_VISITORS: RwLock<lru::LruCache<String, LockoutInfo>> =
+        RwLock::new(lru::LruCache::new(NonZeroUsize::new(4096).unwrap()));
+
+    static ref LOCKOUT_CONFIGS: HashMap<String, LockoutConfig> = init_lockout_configs();
+}
+
+// Scraped periodically by monitoring so these must never be throttled.
+const UNTHROTTLED_PATHS: &[&str] = &["/metrics"];
+
+// Requests to these paths are additionally throttled per account by the handlers (see
+// can_proceed_for_account()) so the per IP limit is relaxed for them. Otherwise users sharing an
+// IP (NAT/CGNAT) would throttle each other.
+const ACCOUNT_SCOPED_PATHS: &[&str] = &[
+    "/update_firebase_token",
+    "/extend_account_expiry",
+    "/update_message_delivered",
+    "/get_account_info",
+    "/get_pending_replies",
+    "/watch_post",
+    "/watch_posts",
+    "/unwatch_post",
+    "/watch_catalog",
+    "/delete_account",
+    "/get_thread_progress",
+    "/remove_firebase_token",
+    "/update_quiet_hours",
+    "/mute_thread",
+    "/whoami",
+    "/register_and_watch",
+    "/send_test_notification",
+    "/update_web_push_subscription",
+];
+
+const ACCOUNT_SCOPED_PATH_IP_LIMIT_MULTIPLIER: usize = 10;
+
+/// An IP can send at most [max_requests] requests to a path within [request_window] (on top of the
+/// per minute limit). Once [max_failures] requests to a path fail within [failure_window] the IP
+/// they were sent from can't send requests to this path for [lockout_duration].
+struct LockoutConfig {
+    max_requests: usize,
+    request_window: Duration,
+    max_failures: usize,
+    failure_window: Duration,
+    lockout_duration: Duration
+}
+
+struct LockoutInfo {
+    requests: usize,
+    first_request_at: Instant,
+    failures: usize,
+    first_failure_at: Instant,
+    locked_until: Option<Instant>
+}
+
+impl LockoutInfo {
+    pub fn new(now: Instant) -> LockoutInfo {
+        return LockoutInfo {
+            requests: 0,
+            first_request_at: now,
+            failures: 0,
+            first_failure_at: now,
+            locked_until: None
+        }
+    }
 }
 
 struct VisitorInfo {
@@ -27,14 +101,109 @@ impl VisitorInfo {
     }
 }
 
+/// A single address or a CIDR range (e.g. "10.0.0.0/8") which is never throttled.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AllowlistEntry {
+    network: IpAddr,
+    prefix_len: u8
+}
+
+impl AllowlistEntry {
+    pub fn contains(&self, ip_address: &IpAddr) -> bool {
+        return match (&self.network, ip_address) {
+            (IpAddr::V4(network), IpAddr::V4(ip_address)) => {
+                prefix_matches(u32::from(*network) as u128, u32::from(*ip_address) as u128, self.prefix_len, 32)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip_address)) => {
+                prefix_matches(u128::from(*network), u128::from(*ip_address), self.prefix_len, 128)
+            }
+            (IpAddr::V4(_), IpAddr::V6(ip_address)) => {
+                // IPv4 clients connecting to a dual-stack listener show up as ::ffff:a.b.c.d
+                ip_address.to_ipv4_mapped()
+                    .map(|ip_address| self.contains(&IpAddr::V4(ip_address)))
+                    .unwrap_or(false)
+            }
+            (IpAddr::V6(_), IpAddr::V4(_)) => false
+        };
+    }
+}
+
+fn prefix_matches(network: u128, ip_address: u128, prefix_len: u8, bits: u8) -> bool {
+    if prefix_len == 0 {
+        return true;
+    }
+
+    let shift = (bits - prefix_len) as u32;
+    return (network ^ ip_address) >> shift == 0;
+}
+
+/// Parses a comma separated list of addresses and CIDR ranges, e.g. "127.0.0.1, 10.0.0.0/8, ::1".
+pub fn parse_throttler_allowlist(value: &str) -> anyhow::Result<Vec<AllowlistEntry>> {
+    let mut allowlist = Vec::<AllowlistEntry>::new();
+
+    for entry in value.split(',') {
+        let entry = entry.trim();
+        if entry.is_empty() {
+            continue;
+        }
+
+        let (address, prefix_len) = match entry.split_once('/') {
+            Some((address, prefix_len)) => (address.trim(), Some(prefix_len.trim())),
+            None => (entry, None)
+        };
+
+        let network = IpAddr::from_str(address)
+            .map_err(|_| anyhow!("Bad address in throttler allowlist entry \'{}\'", entry))?;
+
+        let max_prefix_len = if network.is_ipv4() { 32 } else { 128 };
+
+        let prefix_len = if prefix_len.is_none() {
+            max_prefix_len
+        } else {
+            let prefix_len = u8::from_str(prefix_len.unwrap())
+                .map_err(|_| anyhow!("Bad prefix length in throttler allowlist entry \'{}\'", entry))?;
+
+            if prefix_len > max_prefix_len {
+                return Err(anyhow!("Prefix length is too big in throttler allowlist entry \'{}\'", entry));
+            }
+
+            prefix_len
+        };
+
+        allowlist.push(AllowlistEntry { network, prefix_len });
+    }
+
+    return Ok(allowlist);
+}
+
+pub async fn set_throttler_allowlist(allowlist: Vec<AllowlistEntry>) {
+    *ALLOWLIST.write().await = allowlist;
+}
+
+async fn is_allowlisted(remote_address: &String) -> bool {
+    let ip_address = SocketAddr::from_str(remote_address)
+        .map(|socket_address| socket_address.ip())
+        .or_else(|_| IpAddr::from_str(remote_address));
+
+    if ip_address.is_err() {
+        return false;
+    }
+
+    let ip_address = ip_address.unwrap();
+
+    return ALLOWLIST.read().await
+        .iter()
+        .any(|allowlist_entry| allowlist_entry.contains(&ip_address));
+}
+
 pub async fn throttler_cleanup_task() {
     info!("throttler_cleanup_task() start");
 
     loop {
         info!("throttler_cleanup_task() cleaning up...");
 
-        {
-            let mut visitors_locked = VISITORS.write().await;
+        for visitors in [&VISITORS, &ACCOUNT_VISITORS] {
+            let mut visitors_locked = visitors.write().await;
             for (_, visitor_info) in visitors_locked.iter_mut() {
                 for (_, requests_count) in visitor_info.requests_counter.iter_mut() {
                     *requests_count = 0;
@@ -59,31 +228,196 @@ pub async fn can_proceed(
         return Ok(true);
     }
 
+    if UNTHROTTLED_PATHS.contains(&path.as_str()) {
+        return Ok(true);
+    }
+
+    if is_allowlisted(remote_address).await {
+        return Ok(true);
+    }
+
+    let ip_address = extract_ip_address(remote_address);
+
+    if is_locked_out(&ip_address, &path).await {
+        info!("can_proceed() '{}' is locked out of '{}'", ip_address, path);
+        return Ok(false);
+    }
+
+    if !count_lockout_window_request(&ip_address, &path).await {
+        info!("can_proceed() '{}' sent too many requests to '{}' within the request window", ip_address, path);
+        return Ok(false);
+    }
+
+    let limit_multiplier = if ACCOUNT_SCOPED_PATHS.contains(&path.as_str()) {
+        ACCOUNT_SCOPED_PATH_IP_LIMIT_MULTIPLIER
+    } else {
+        1
+    };
+
+    return Ok(count_request(&VISITORS, ip_address, &path, limit_multiplier).await);
+}
+
+/// Same as can_proceed() but the requests are counted per account instead of per IP. Used by the
+/// handlers of ACCOUNT_SCOPED_PATHS once they know the account so that a user can't evade the
+/// limits by switching IPs.
+pub async fn can_proceed_for_account(
+    test_context: Option<TestContext>,
+    account_id: &AccountId,
+    path: &str
+) -> anyhow::Result<bool> {
+    if test_context.is_some() && !test_context.unwrap().enable_throttler {
+        return Ok(true);
+    }
+
+    return Ok(count_request(&ACCOUNT_VISITORS, account_id.id.clone(), path, 1).await);
+}
+
+/// Must be called once a request to [path] was rejected (e.g. bad master password or invite) so
+/// that the IP it was sent from gets locked out after repeated failures. Does nothing for paths
+/// without a lockout config.
+pub async fn on_request_failed(
+    test_context: Option<TestContext>,
+    path: &str,
+    remote_address: &String
+) {
+    if test_context.is_some() && !test_context.unwrap().enable_throttler {
+        return;
+    }
+
+    let lockout_config = LOCKOUT_CONFIGS.get(path);
+    if lockout_config.is_none() || is_allowlisted(remote_address).await {
+        return;
+    }
+
+    let lockout_config = lockout_config.unwrap();
     let ip_address = extract_ip_address(remote_address);
+    let now = Instant::now();
+
+    let mut lockout_visitors_locked = LOCKOUT_VISITORS.write().await;
+    let lockout_info = lockout_visitors_locked.get_or_insert_mut(lockout_key(&ip_address, path), || {
+        return LockoutInfo::new(now);
+    });
 
+    if now.duration_since(lockout_info.first_failure_at) > lockout_config.failure_window {
+        lockout_info.failures = 0;
+        lockout_info.first_failure_at = now;
+    }
+
+    lockout_info.failures += 1;
+
+    if lockout_info.failures >= lockout_config.max_failures {
+        warn!(
+            "on_request_failed() '{}' failed {} requests to '{}', locking it out for {} seconds",
+            ip_address,
+            lockout_info.failures,
+            path,
+            lockout_config.lockout_duration.as_secs()
+        );
+
+        lockout_info.failures = 0;
+        lockout_info.first_failure_at = now;
+        lockout_info.locked_until = Some(now + lockout_config.lockout_duration);
+    }
+}
+
+/// Forgets the previous failures of the IP the successful request to [path] was sent from.
+pub async fn on_request_succeeded(
+    test_context: Option<TestContext>,
+    path: &str,
+    remote_address: &String
+) {
+    if test_context.is_some() && !test_context.unwrap().enable_throttler {
+        return;
+    }
+
+    if !LOCKOUT_CONFIGS.contains_key(path) {
+        return;
+    }
+
+    let ip_address = extract_ip_address(remote_address);
+
+    // Only the failures are forgotten, the requests still count towards the request window
+    let mut lockout_visitors_locked = LOCKOUT_VISITORS.write().await;
+    let lockout_info = lockout_visitors_locked.get_mut(&lockout_key(&ip_address, path));
+    if lockout_info.is_none() {
+        return;
+    }
+
+    let lockout_info = lockout_info.unwrap();
+    lockout_info.failures = 0;
+    lockout_info.first_failure_at = Instant::now();
+}
+
+async fn is_locked_out(ip_address: &String, path: &str) -> bool {
+    if !LOCKOUT_CONFIGS.contains_key(path) {
+        return false;
+    }
+
+    let lockout_visitors_locked = LOCKOUT_VISITORS.read().await;
+    let lockout_info = lockout_visitors_locked.peek(&lockout_key(ip_address, path));
+    if lockout_info.is_none() {
+        return false;
+    }
+
+    return lockout_info.unwrap().locked_until
+        .map(|locked_until| Instant::now() < locked_until)
+        .unwrap_or(false);
+}
+
+/// Returns false once the IP has sent more than [LockoutConfig::max_requests] requests to [path]
+/// within the current request window. Always returns true for paths without a lockout config.
+async fn count_lockout_window_request(ip_address: &String, path: &str) -> bool {
+    let lockout_config = LOCKOUT_CONFIGS.get(path);
+    if lockout_config.is_none() {
+        return true;
+    }
+
+    let lockout_config = lockout_config.unwrap();
+    let now = Instant::now();
+
+    let mut lockout_visitors_locked = LOCKOUT_VISITORS.write().await;
+    let lockout_info = lockout_visitors_locked.get_or_insert_mut(lockout_key(ip_address, path), || {
+        return LockoutInfo::new(now);
+    });
+
+    if now.duration_since(lockout_info.first_request_at) > lockout_config.request_window {
+        lockout_info.requests = 0;
+        lockout_info.first_request_at = now;
+    }
+
+    lockout_info.requests += 1;
+    return lockout_info.requests <= lockout_config.max_requests;
+}
+
+fn lockout_key(ip_address: &String, path: &str) -> String {
+    return format!("{}{}", ip_address, path);
+}
+
+async fn count_request(
+    visitors: &RwLock<lru::LruCache<String, VisitorInfo>>,
+    visitor_key: String,
+    path: &str,
+    limit_multiplier: usize
+) -> bool {
     let counter = {
-        let mut visitors_locked = VISITORS.write().await;
-        let visitor_info = visitors_locked.get_or_insert_mut(ip_address, || VisitorInfo::new());
-        let counter = visitor_info.requests_counter.entry(path.clone()).or_insert(0);
+        let mut visitors_locked = visitors.write().await;
+        let visitor_info = visitors_locked.get_or_insert_mut(visitor_key, || VisitorInfo::new());
+        let counter = visitor_info.requests_counter.entry(path.to_string()).or_insert(0);
 
         *counter += 1;
         counter.clone()
     };
 
-    let can_proceed = {
-        let request_limits_locked = REQUEST_LIMITS.write().await;
-        let limit_for_this_path = request_limits_locked.get(&path);
+    let request_limits_locked = REQUEST_LIMITS.read().await;
+    let limit_for_this_path = request_limits_locked.get(path);
 
-        if limit_for_this_path.is_none() {
-            warn!("Path \'{}\' has no request limit!!! Passing all requests!", path);
-            true
-        } else {
-            let limits = limit_for_this_path.unwrap();
-            counter <= *limits
-        }
-    };
+    if limit_for_this_path.is_none() {
+        warn!("Path \'{}\' has no request limit!!! Passing all requests!", path);
+        return true;
+    }
 
-    return Ok(can_proceed);
+    let limits = limit_for_this_path.unwrap();
+    return counter <= *limits * limit_multiplier;
 }
 
 fn init_request_limits() -> HashMap<String, usize> {
@@ -94,18 +428,68 @@ fn init_request_limits() -> HashMap<String, usize> {
     result_map.insert("/create_account".to_string(), 5);
     result_map.insert("/update_account_expiry_date".to_string(), 5);
     result_map.insert("/update_firebase_token".to_string(), 5);
+    result_map.insert("/update_web_push_subscription".to_string(), 5);
+    result_map.insert("/extend_account_expiry".to_string(), 5);
     result_map.insert("/update_message_delivered".to_string(), 15);
     result_map.insert("/get_account_info".to_string(), 15);
+    result_map.insert("/whoami".to_string(), 15);
+    result_map.insert("/get_pending_replies".to_string(), 15);
     result_map.insert("/watch_post".to_string(), 20);
+    result_map.insert("/watch_posts".to_string(), 5);
+    result_map.insert("/register_and_watch".to_string(), 5);
+    // Every request costs FCM quota
+    result_map.insert("/send_test_notification".to_string(), 2);
     result_map.insert("/unwatch_post".to_string(), 20);
+    result_map.insert("/watch_catalog".to_string(), 20);
     result_map.insert("/generate_invites".to_string(), 5);
     result_map.insert("/view_invite".to_string(), 5);
+    result_map.insert("/delete_account".to_string(), 5);
+    result_map.insert("/server_info".to_string(), 60);
+    result_map.insert("/get_boards".to_string(), 15);
+    result_map.insert("/get_delivery_stats".to_string(), 15);
+    result_map.insert("/list_accounts".to_string(), 15);
+    result_map.insert("/get_thread_progress".to_string(), 30);
+    result_map.insert("/bulk_extend_expiry".to_string(), 5);
+    result_map.insert("/remove_firebase_token".to_string(), 5);
+    result_map.insert("/update_quiet_hours".to_string(), 5);
+    result_map.insert("/replay_replies".to_string(), 5);
+    result_map.insert("/mute_thread".to_string(), 20);
+    result_map.insert("/ban_account".to_string(), 5);
+    result_map.insert("/debug/process_thread".to_string(), 15);
+    result_map.insert("/debug/get_thread_snapshot".to_string(), 15);
     result_map.insert("/".to_string(), 30);
     result_map.insert("/favicon.ico".to_string(), 30);
 
     return result_map;
 }
 
+// Paths that create accounts or redeem invites. Besides the per minute limits that every path has
+// they are limited per hour and on top of that an IP is locked out of them after a couple of
+// failed attempts.
+fn init_lockout_configs() -> HashMap<String, LockoutConfig> {
+    let mut result_map = HashMap::<String, LockoutConfig>::new();
+
+    let lockout_config = |max_requests_per_hour: usize| {
+        return LockoutConfig {
+            max_requests: max_requests_per_hour,
+            request_window: Duration::from_secs(60 * 60),
+            max_failures: 3,
+            failure_window: Duration::from_secs(10 * 60),
+            lockout_duration: Duration::from_secs(15 * 60)
+        };
+    };
+
+    // Master password protected so only the admin (or someone guessing the password) gets here
+    result_map.insert("/create_account".to_string(), lockout_config(30));
+    // Every accepted invite creates a new account
+    result_map.insert("/view_invite".to_string(), lockout_config(10));
+    // Redeems invites as well. Account scoped so the per minute IP limit is relaxed for it which
+    // would otherwise allow guessing invites a lot faster than through /view_invite
+    result_map.insert("/extend_account_expiry".to_string(), lockout_config(10));
+
+    return result_map;
+}
+
 fn extract_ip_address(remote_address: &String) -> String {
     let index = remote_address.find(":");
     if index.is_none() {
@@ -123,4 +507,150 @@ fn test() {
 
     let ip = extract_ip_address(&String::from("127.0.0.1"));
     assert_eq!("127.0.0.1", ip.as_str());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_parse_throttler_allowlist() {
+    let allowlist = parse_throttler_allowlist("127.0.0.1, 10.0.0.0/8,::1, fd00::/8").unwrap();
+    assert_eq!(4, allowlist.len());
+
+    let contains = |ip_address: &str| {
+        let ip_address = IpAddr::from_str(ip_address).unwrap();
+        return allowlist.iter().any(|allowlist_entry| allowlist_entry.contains(&ip_address));
+    };
+
+    assert!(contains("127.0.0.1"));
+    assert!(!contains("127.0.0.2"));
+    assert!(contains("10.0.0.0"));
+    assert!(contains("10.255.255.255"));
+    assert!(!contains("11.0.0.1"));
+    assert!(contains("::1"));
+    assert!(contains("fd12:3456::1"));
+    assert!(!contains("fe80::1"));
+    assert!(contains("::ffff:10.1.2.3"));
+
+    assert!(parse_throttler_allowlist("").unwrap().is_empty());
+    assert!(parse_throttler_allowlist("0.0.0.0/0").unwrap()[0].contains(&IpAddr::from_str("1.2.3.4").unwrap()));
+    assert!(parse_throttler_allowlist("localhost").is_err());
+    assert!(parse_throttler_allowlist("10.0.0.0/33").is_err());
+    assert!(parse_throttler_allowlist("10.0.0.0/abc").is_err());
+    assert!(parse_throttler_allowlist("::/129").is_err());
+}
+
+#[tokio::test]
+async fn test_allowlisted_addresses_are_never_throttled() {
+    set_throttler_allowlist(parse_throttler_allowlist("203.0.113.0/24").unwrap()).await;
+
+    let test_context = Some(TestContext { enable_throttler: true });
+    let path = "/create_account".to_string();
+    let limit = *REQUEST_LIMITS.read().await.get(&path).unwrap();
+
+    for _ in 0..(limit * 2) {
+        let allowed = can_proceed(test_context, path.clone(), &String::from("203.0.113.7:50016")).await;
+        assert!(allowed.unwrap());
+    }
+
+    for attempt in 0..(limit * 2) {
+        let allowed = can_proceed(test_context, path.clone(), &String::from("198.51.100.7:50016")).await;
+        assert_eq!(attempt < limit, allowed.unwrap());
+    }
+}
+
+#[tokio::test]
+async fn test_users_behind_one_ip_are_not_throttled_together() {
+    let test_context = Some(TestContext { enable_throttler: true });
+    let path = "/update_quiet_hours";
+    let limit = *REQUEST_LIMITS.read().await.get(path).unwrap();
+    let remote_address = String::from("192.0.2.1:50016");
+
+    let account_id1 = AccountId::from_user_id("444444444444444444444444444444444444").unwrap();
+    let account_id2 = AccountId::from_user_id("555555555555555555555555555555555555").unwrap();
+
+    for account_id in [&account_id1, &account_id2] {
+        for _ in 0..limit {
+            assert!(can_proceed(test_context, path.to_string(), &remote_address).await.unwrap());
+            assert!(can_proceed_for_account(test_context, account_id, path).await.unwrap());
+        }
+    }
+
+    // Both accounts used up their own limits but the shared IP is still fine
+    assert!(can_proceed(test_context, path.to_string(), &remote_address).await.unwrap());
+    assert!(!can_proceed_for_account(test_context, &account_id1, path).await.unwrap());
+    assert!(!can_proceed_for_account(test_context, &account_id2, path).await.unwrap());
+
+    // The same account is throttled from a different IP as well
+    let other_remote_address = String::from("192.0.2.2:50016");
+    assert!(can_proceed(test_context, path.to_string(), &other_remote_address).await.unwrap());
+    assert!(!can_proceed_for_account(test_context, &account_id1, path).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_account_throttling_is_disabled_by_test_context() {
+    let test_context = Some(TestContext { enable_throttler: false });
+    let path = "/watch_post";
+    let limit = *REQUEST_LIMITS.read().await.get(path).unwrap();
+
+    let account_id = AccountId::from_user_id("666666666666666666666666666666666666").unwrap();
+
+    for _ in 0..(limit * 2) {
+        assert!(can_proceed_for_account(test_context, &account_id, path).await.unwrap());
+    }
+}
+
+#[tokio::test]
+async fn test_repeated_account_creation_failures_lock_ip_out() {
+    let test_context = Some(TestContext { enable_throttler: true });
+    let path = "/create_account";
+    let max_failures = LOCKOUT_CONFIGS.get(path).unwrap().max_failures;
+    let remote_address = String::from("198.51.100.20:50016");
+
+    for _ in 0..(max_failures - 1) {
+        assert!(can_proceed(test_context, path.to_string(), &remote_address).await.unwrap());
+        on_request_failed(test_context, path, &remote_address).await;
+    }
+
+    assert!(can_proceed(test_context, path.to_string(), &remote_address).await.unwrap());
+    on_request_failed(test_context, path, &remote_address).await;
+
+    // Locked out even though the per minute request limit wasn't reached yet
+    assert!(max_failures < *REQUEST_LIMITS.read().await.get(path).unwrap());
+    assert!(!can_proceed(test_context, path.to_string(), &remote_address).await.unwrap());
+    assert!(!can_proceed(test_context, path.to_string(), &String::from("198.51.100.20:50017")).await.unwrap());
+
+    // Other IPs and paths are not affected
+    assert!(can_proceed(test_context, path.to_string(), &String::from("198.51.100.21:50016")).await.unwrap());
+    assert!(can_proceed(test_context, "/server_info".to_string(), &remote_address).await.unwrap());
+
+    // A success resets the failures
+    let other_remote_address = String::from("198.51.100.22:50016");
+
+    for _ in 0..(max_failures - 1) {
+        on_request_failed(test_context, path, &other_remote_address).await;
+    }
+
+    on_request_succeeded(test_context, path, &other_remote_address).await;
+    on_request_failed(test_context, path, &other_remote_address).await;
+    assert!(can_proceed(test_context, path.to_string(), &other_remote_address).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_account_creation_paths_are_limited_per_hour() {
+    let test_context = Some(TestContext { enable_throttler: true });
+    let path = "/view_invite";
+    let max_requests = LOCKOUT_CONFIGS.get(path).unwrap().max_requests;
+    let remote_address = String::from("198.51.100.30:50016");
+
+    // Stricter than the per minute limit
+    assert!(max_requests < *REQUEST_LIMITS.read().await.get(path).unwrap() * 60);
+
+    for _ in 0..max_requests {
+        assert!(can_proceed(test_context, path.to_string(), &remote_address).await.unwrap());
+        on_request_succeeded(test_context, path, &remote_address).await;
+
+        // Pretend that a minute has passed so that the per minute limit is not reached
+        VISITORS.write().await.pop(&extract_ip_address(&remote_address));
+    }
+
+    assert!(!can_proceed(test_context, path.to_string(), &remote_address).await.unwrap());
+    assert!(can_proceed(test_context, path.to_string(), &String::from("198.51.100.31:50016")).await.unwrap());
+}
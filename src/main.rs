@@ -6,21 +6,22 @@ use std::env;
 use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
-use anyhow::Context;
-use hyper::server::conn::http1;
-use hyper::service::service_fn;
+use anyhow::{anyhow, Context};
 use tokio::net::TcpListener;
 
-use crate::helpers::{logger, throttler};
+use crate::helpers::{http_client, logger, throttler};
+use crate::helpers::logger::LogFormat;
 use crate::model::database::db::Database;
+use crate::model::repository::{account_repository, invites_repository, logs_repository, migrations_repository, post_descriptor_id_repository, post_repository, site_repository};
 use crate::model::repository::migrations_repository::perform_migrations;
-use crate::model::repository::post_descriptor_id_repository;
 use crate::model::repository::site_repository::SiteRepository;
-use crate::router::{router, TestContext};
+use crate::router::{HttpProtocol, MasterPassword, TestContext};
 use crate::service::fcm_sender::FcmSender;
-use crate::service::invites_cleanup;
+use crate::service::{accounts_cache_verifier, dead_threads_cleanup, fcm_sender, invites_cleanup, thread_watcher};
 use crate::service::thread_watcher::ThreadWatcher;
+use crate::service::web_push_transport::VapidKeys;
 
 mod constants;
 mod model;
@@ -34,6 +35,8 @@ mod tests;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let create_account_valid_days = parse_create_account_arg(&env::args().collect::<Vec<String>>())?;
+
     let is_dev_build = i32::from_str(
         &env::var("DEVELOPMENT_BUILD")
             .context("Failed to read DEVELOPMENT_BUILD from Environment")?
@@ -45,95 +48,352 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         .context("Failed to read DATABASE_CONNECTION_STRING")?;
     let firebase_api_key = env::var("FIREBASE_API_KEY")
         .context("Failed to read FIREBASE_API_KEY from Environment")?;
-    let master_password = env::var("MASTER_PASSWORD")
-        .context("Failed to read MASTER_PASSWORD from Environment")?;
+    let master_password = read_master_password()?;
     let host_address = env::var("HOST_ADDRESS")
         .context("Failed to read HOST_ADDRESS from Environment")?;
+    let outbound_proxy = env::var("OUTBOUND_PROXY").ok();
+    let dead_threads_retention_days = env::var("DEAD_THREADS_RETENTION_DAYS")
+        .map(|value| u64::from_str(value.as_str()).unwrap())
+        .unwrap_or(constants::DEFAULT_DEAD_THREADS_RETENTION_DAYS);
+    let invites_cleanup_interval_minutes = env::var("INVITES_CLEANUP_INTERVAL_MINUTES")
+        .map(|value| u64::from_str(value.as_str()).unwrap())
+        .unwrap_or(constants::DEFAULT_INVITES_CLEANUP_INTERVAL_MINUTES);
+    let max_request_body_size = env::var("MAX_REQUEST_BODY_SIZE")
+        .map(|value| usize::from_str(value.as_str()).unwrap())
+        .unwrap_or(constants::DEFAULT_MAX_REQUEST_BODY_SIZE);
+    let force_http2 = env::var("FORCE_HTTP2")
+        .map(|value| i32::from_str(value.as_str()).unwrap() == 1)
+        .unwrap_or(false);
+    let server_bind_addr = parse_bind_address(
+        &env::var("SERVER_BIND_ADDR").unwrap_or(constants::DEFAULT_SERVER_BIND_ADDR.to_string())
+    )?;
+    let site_concurrency_limits = site_repository::parse_site_concurrency_limits(
+        &env::var("SITE_CONCURRENCY_LIMITS").unwrap_or(String::new())
+    )?;
+    let enabled_sites = site_repository::parse_enabled_sites(
+        &env::var("ENABLED_SITES").unwrap_or(String::new())
+    );
+    let max_watches_per_account = env::var("MAX_WATCHES_PER_ACCOUNT")
+        .map(|value| usize::from_str(value.as_str()).unwrap())
+        .unwrap_or(constants::DEFAULT_MAX_WATCHES_PER_ACCOUNT);
+    let max_replies_per_thread = env::var("MAX_REPLIES_PER_THREAD")
+        .map(|value| usize::from_str(value.as_str()).unwrap())
+        .unwrap_or(constants::DEFAULT_MAX_REPLIES_PER_THREAD);
+    let max_thread_age_days = env::var("MAX_THREAD_AGE_DAYS")
+        .map(|value| u64::from_str(value.as_str()).unwrap())
+        .unwrap_or(0);
+    let follow_successor_threads = env::var("FOLLOW_SUCCESSOR_THREADS")
+        .map(|value| i32::from_str(value.as_str()).unwrap() == 1)
+        .unwrap_or(false);
+    let suppress_self_replies = env::var("SUPPRESS_SELF_REPLIES")
+        .map(|value| i32::from_str(value.as_str()).unwrap() == 1)
+        .unwrap_or(false);
+    let log_format = env::var("LOG_FORMAT")
+        .map(|value| LogFormat::from_str(value.as_str()))
+        .unwrap_or(LogFormat::Pretty);
+    let http_request_timeout_seconds = env::var("HTTP_REQUEST_TIMEOUT_SECONDS")
+        .map(|value| u64::from_str(value.as_str()).unwrap())
+        .unwrap_or(constants::DEFAULT_HTTP_REQUEST_TIMEOUT_SECONDS);
+    let http_connect_timeout_seconds = env::var("HTTP_CONNECT_TIMEOUT_SECONDS")
+        .map(|value| u64::from_str(value.as_str()).unwrap())
+        .unwrap_or(constants::DEFAULT_HTTP_CONNECT_TIMEOUT_SECONDS);
+    let throttler_allowlist = throttler::parse_throttler_allowlist(
+        &env::var("THROTTLER_ALLOWLIST").unwrap_or(String::new())
+    )?;
+    let watcher_chunk_size = thread_watcher::parse_watcher_limit(
+        "WATCHER_CHUNK_SIZE",
+        env::var("WATCHER_CHUNK_SIZE").ok().as_deref()
+    )?;
+    let watcher_max_concurrency = thread_watcher::parse_watcher_limit(
+        "WATCHER_MAX_CONCURRENCY",
+        env::var("WATCHER_MAX_CONCURRENCY").ok().as_deref()
+    )?;
+    let send_watch_confirmations = env::var("SEND_WATCH_CONFIRMATIONS")
+        .map(|value| i32::from_str(value.as_str()).unwrap() == 1)
+        .unwrap_or(false);
+    let verify_cache_on_start = env::var("VERIFY_CACHE_ON_START")
+        .map(|value| i32::from_str(value.as_str()).unwrap() == 1)
+        .unwrap_or(false);
+    let user_id_hash_iterations = account_repository::parse_user_id_hash_iterations(
+        env::var("USER_ID_HASH_ITERATIONS").ok().as_deref()
+    )?;
+    let log_compression_threshold = env::var("LOG_COMPRESSION_THRESHOLD")
+        .map(|value| usize::from_str(value.as_str()).unwrap())
+        .unwrap_or(0);
+    let migrate_down_to = env::var("MIGRATE_DOWN_TO")
+        .ok()
+        .map(|value| u32::from_str(value.as_str()).unwrap());
+    let vapid_keys = read_vapid_keys()?;
 
     let num_cpus = num_cpus::get() as u32;
-    let database = Database::new(connection_string, num_cpus).await?;
+    let db_pool_max_size = env::var("DB_POOL_MAX_SIZE")
+        .map(|value| u32::from_str(value.as_str()).unwrap())
+        .unwrap_or(num_cpus * 2);
+    let db_connection_timeout = env::var("DB_CONNECTION_TIMEOUT_SECONDS")
+        .ok()
+        .map(|value| Duration::from_secs(u64::from_str(value.as_str()).unwrap()));
+
+    let database = Database::with_pool_config(
+        connection_string,
+        num_cpus,
+        db_pool_max_size,
+        db_connection_timeout
+    ).await?;
     let database = Arc::new(database);
-    init_logger(is_dev_build, Some(database.clone()));
+    logs_repository::set_log_compression_threshold(log_compression_threshold);
+    init_logger(is_dev_build, log_format, Some(database.clone()));
 
     info!("main() initializing the server");
     info!("main() detected cpu cores: {}", num_cpus);
 
+    let http_client = http_client::create_shared_http_client(
+        outbound_proxy,
+        Duration::from_secs(http_request_timeout_seconds),
+        Duration::from_secs(http_connect_timeout_seconds)
+    );
+    handlers::server_info::init_server_started_at();
+    handlers::shared::set_max_request_body_size(max_request_body_size);
+    post_repository::set_max_watches_per_account(max_watches_per_account);
+    account_repository::set_user_id_hash_iterations(user_id_hash_iterations);
+    fcm_sender::set_max_replies_per_thread(max_replies_per_thread);
+    fcm_sender::set_send_watch_confirmations(send_watch_confirmations);
+    thread_watcher::set_max_thread_age_days(max_thread_age_days);
+    thread_watcher::set_follow_successor_threads(follow_successor_threads);
+    thread_watcher::set_suppress_self_replies(suppress_self_replies);
+    thread_watcher::set_watcher_chunk_size(watcher_chunk_size);
+    thread_watcher::set_watcher_max_concurrency(watcher_max_concurrency);
+    throttler::set_throttler_allowlist(throttler_allowlist).await;
+
+    if migrate_down_to.is_some() {
+        let migrate_down_to = migrate_down_to.unwrap();
+
+        info!("main() rolling back migrations to version {}...", migrate_down_to);
+        migrations_repository::rollback_to(&database, migrate_down_to).await?;
+        info!("main() rolling back migrations to version {}... done, exiting", migrate_down_to);
+
+        return Ok(());
+    }
+
     info!("main() processing migrations...");
     perform_migrations(&database).await?;
     info!("main() processing migrations... done");
 
-    info!("main() starting up server...");
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
-    let listener = TcpListener::bind(addr).await?;
+    if create_account_valid_days.is_some() {
+        let valid_days = create_account_valid_days.unwrap();
+
+        let user_id = invites_repository::create_account_with_generated_id(&database, valid_days).await?;
+        info!("main() created a new account valid for {} days, exiting", valid_days);
 
-    let site_repository = Arc::new(SiteRepository::new());
+        println!("{}", user_id);
+        return Ok(());
+    }
+
+    info!("main() starting up server on {}...", server_bind_addr);
+    let listener = TcpListener::bind(server_bind_addr).await?;
+
+    let site_repository = SiteRepository::with_enabled_sites(
+        &http_client,
+        &site_concurrency_limits,
+        &enabled_sites
+    )?;
+    let site_repository = Arc::new(site_repository);
+    info!("main() enabled sites: {:?}", site_repository.supported_site_names());
     let database_cloned_for_watcher = database.clone();
     let site_repository_for_watcher = site_repository.clone();
+    let http_client_for_watcher = http_client.clone();
+
+    if vapid_keys.is_some() {
+        // Web clients must subscribe with this key as the applicationServerKey
+        info!("main() WebPush is enabled, VAPID public key: {}", vapid_keys.as_ref().unwrap().public_key());
+    }
+
+    let web_push_http_client = http_client::create_web_push_http_client(
+        Duration::from_secs(http_request_timeout_seconds),
+        Duration::from_secs(http_connect_timeout_seconds)
+    )?;
 
     let fcm_sender = FcmSender::new(
         is_dev_build,
         firebase_api_key,
+        vapid_keys,
+        &web_push_http_client,
         &database.clone(),
         &site_repository.clone()
     );
     let fcm_sender = Arc::new(fcm_sender);
+    let fcm_sender_for_watcher = fcm_sender.clone();
 
     post_descriptor_id_repository::init(&database)
         .await
         .context("Failed to init post_descriptor_id_repository")?;
 
     tokio::task::spawn(async move {
-        let mut thread_watcher = ThreadWatcher::new(num_cpus, timeout_seconds, is_dev_build);
+        let mut thread_watcher = ThreadWatcher::new(
+            num_cpus,
+            timeout_seconds,
+            is_dev_build,
+            &http_client_for_watcher
+        );
 
         thread_watcher.start(
             &database_cloned_for_watcher,
             &site_repository_for_watcher,
-            &fcm_sender
+            &fcm_sender_for_watcher
         ).await.unwrap();
     });
 
     let database_cloned_invites_cleanup = database.clone();
     tokio::task::spawn(async move {
-        invites_cleanup::invites_cleanup_task(&database_cloned_invites_cleanup).await;
+        invites_cleanup::invites_cleanup_task(
+            &database_cloned_invites_cleanup,
+            invites_cleanup_interval_minutes
+        ).await;
+    });
+
+    let database_cloned_dead_threads_cleanup = database.clone();
+    tokio::task::spawn(async move {
+        dead_threads_cleanup::dead_threads_cleanup_task(
+            &database_cloned_dead_threads_cleanup,
+            dead_threads_retention_days
+        ).await;
     });
 
     tokio::task::spawn(async move {
         throttler::throttler_cleanup_task().await;
     });
 
+    if verify_cache_on_start {
+        let database_cloned_cache_verification = database.clone();
+        tokio::task::spawn(async move {
+            accounts_cache_verifier::accounts_cache_verification_task(&database_cloned_cache_verification).await;
+        });
+    }
+
     info!("main() starting up server... done, waiting for connections...");
 
+    // There is no TLS listener so the protocol can't be negotiated, FORCE_HTTP2 enables plaintext
+    // HTTP/2 (h2c) instead.
+    let http_protocol = if force_http2 { HttpProtocol::Http2 } else { HttpProtocol::Http1 };
+    let master_password = Arc::new(master_password);
+    let host_address = Arc::new(host_address);
+
+    info!("main() using {:?}", http_protocol);
+
     loop {
         let (stream, sock_addr) = listener.accept().await?;
         let database_cloned_for_router = database.clone();
         let site_repository_cloned = site_repository.clone();
         let master_password_cloned = master_password.clone();
         let host_address_cloned = host_address.clone();
+        let fcm_sender_cloned = fcm_sender.clone();
 
         tokio::task::spawn(async move {
-            http1::Builder::new()
-                .serve_connection(
-                    stream,
-                    service_fn(|request| {
-                        let test_context: Option<TestContext> = None;
-
-                        return router(
-                            test_context,
-                            &master_password_cloned,
-                            &host_address_cloned,
-                            &sock_addr,
-                            request,
-                            &database_cloned_for_router,
-                            &site_repository_cloned
-                        );
-                    }),
-                )
-                .await
-                .unwrap();
+            let test_context: Option<TestContext> = None;
+
+            let result = router::serve_connection(
+                test_context,
+                http_protocol,
+                stream,
+                sock_addr,
+                master_password_cloned,
+                host_address_cloned,
+                database_cloned_for_router,
+                site_repository_cloned,
+                fcm_sender_cloned
+            ).await;
+
+            if result.is_err() {
+                error!("main() Failed to serve connection from {}: {:?}", sock_addr, result.err().unwrap());
+            }
         });
     }
 }
 
-pub fn init_logger(is_dev_build: bool, database: Option<Arc<Database>>) {
-    logger::init_logger(is_dev_build, database);
-}
\ No newline at end of file
+/// MASTER_PASSWORD_SHA3_512 is preferred so that the master password doesn't have to be stored in
+/// plaintext, MASTER_PASSWORD is only used when it's not set.
+fn read_master_password() -> anyhow::Result<MasterPassword> {
+    let master_password_hash = env::var("MASTER_PASSWORD_SHA3_512").ok();
+    if master_password_hash.is_some() {
+        return MasterPassword::from_hash(&master_password_hash.unwrap())
+            .context("Failed to read MASTER_PASSWORD_SHA3_512 from Environment");
+    }
+
+    let master_password = env::var("MASTER_PASSWORD")
+        .context("Failed to read MASTER_PASSWORD (or MASTER_PASSWORD_SHA3_512) from Environment")?;
+
+    return Ok(MasterPassword::from_plaintext(&master_password));
+}
+
+/// WebPush is optional, messages to WebPush tokens are not sent unless VAPID_PRIVATE_KEY is set.
+fn read_vapid_keys() -> anyhow::Result<Option<VapidKeys>> {
+    let vapid_private_key = env::var("VAPID_PRIVATE_KEY").ok();
+    if vapid_private_key.is_none() {
+        return Ok(None);
+    }
+
+    let vapid_subject = env::var("VAPID_SUBJECT")
+        .context("Failed to read VAPID_SUBJECT from Environment, it's required when VAPID_PRIVATE_KEY is set")?;
+
+    let vapid_keys = VapidKeys::from_base64(&vapid_private_key.unwrap(), &vapid_subject)
+        .context("Failed to read VAPID_PRIVATE_KEY from Environment")?;
+
+    return Ok(Some(vapid_keys));
+}
+
+pub fn init_logger(is_dev_build: bool, log_format: LogFormat, database: Option<Arc<Database>>) {
+    logger::init_logger(is_dev_build, log_format, database);
+}
+
+pub fn parse_bind_address(value: &str) -> anyhow::Result<SocketAddr> {
+    return SocketAddr::from_str(value.trim())
+        .with_context(|| format!("Failed to parse SERVER_BIND_ADDR \'{}\', expected ip:port", value));
+}
+
+/// Returns the days count of the "--create-account <valid_days>" argument, None when the argument
+/// is not present and the server should be started normally.
+pub fn parse_create_account_arg(args: &[String]) -> anyhow::Result<Option<i64>> {
+    let index = args.iter().position(|arg| arg == "--create-account");
+    if index.is_none() {
+        return Ok(None);
+    }
+
+    let valid_days = args.get(index.unwrap() + 1);
+    if valid_days.is_none() {
+        return Err(anyhow!("--create-account requires the <valid_days> argument"));
+    }
+
+    let valid_days = valid_days.unwrap();
+    let valid_days = i64::from_str(valid_days)
+        .with_context(|| format!("Failed to parse --create-account valid_days '{}'", valid_days))?;
+
+    if !handlers::shared::is_valid_days_count(valid_days) {
+        return Err(anyhow!("--create-account valid_days must be in range 1..=365"));
+    }
+
+    return Ok(Some(valid_days));
+}
+
+#[test]
+fn test_parse_create_account_arg() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>();
+
+    assert_eq!(None, parse_create_account_arg(&args(&["kpns"])).unwrap());
+    assert_eq!(Some(30), parse_create_account_arg(&args(&["kpns", "--create-account", "30"])).unwrap());
+
+    assert!(parse_create_account_arg(&args(&["kpns", "--create-account"])).is_err());
+    assert!(parse_create_account_arg(&args(&["kpns", "--create-account", "abc"])).is_err());
+    assert!(parse_create_account_arg(&args(&["kpns", "--create-account", "0"])).is_err());
+    assert!(parse_create_account_arg(&args(&["kpns", "--create-account", "366"])).is_err());
+}
+
+#[test]
+fn test_parse_bind_address() {
+    assert_eq!(SocketAddr::from(([0, 0, 0, 0], 3000)), parse_bind_address("0.0.0.0:3000").unwrap());
+    assert_eq!(SocketAddr::from(([127, 0, 0, 1], 8080)), parse_bind_address("127.0.0.1:8080").unwrap());
+    assert_eq!(SocketAddr::from(([0, 0, 0, 0, 0, 0, 0, 1], 8080)), parse_bind_address("[::1]:8080").unwrap());
+
+    assert!(parse_bind_address("").is_err());
+    assert!(parse_bind_address("127.0.0.1").is_err());
+    assert!(parse_bind_address("localhost:8080").is_err());
+    assert!(parse_bind_address("127.0.0.1:99999").is_err());
+}
@@ -2,10 +2,13 @@ use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::sync::RwLock;
 
+use anyhow::anyhow;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use tokio_postgres::Row;
 
+use crate::constants;
+
 lazy_static! {
     static ref DOMAINS: RwLock<HashMap<&'static str, &'static str>> = RwLock::new(create_domains_map());
 }
@@ -47,13 +50,38 @@ pub struct ChanPost {
     pub comment_unparsed: Option<String>
 }
 
+#[derive(Debug, Clone)]
+pub struct CatalogThread {
+    pub thread_no: u64,
+    pub subject: Option<String>,
+    pub comment: Option<String>
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ChanBoard {
+    pub board_code: String,
+    pub title: String
+}
+
 #[derive(Debug)]
 pub struct ChanThread {
     pub closed: bool,
     pub archived: bool,
+    pub subject: Option<String>,
     pub posts: Vec<ChanPost>
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ThreadActivityState {
+    /// The thread may still get new posts so we keep watching it.
+    Active,
+    /// The thread is read-only but still readable (4chan archives threads on some boards), its
+    /// posts are scanned one last time and then the thread stops being watched.
+    Archived,
+    /// The thread was closed, it's not watched anymore.
+    Closed
+}
+
 impl Display for SiteDescriptor {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.site_name)?;
@@ -85,15 +113,19 @@ impl SiteDescriptor {
     }
 
     pub fn from_str(site_name: &str) -> SiteDescriptor {
-        let domains_locked = DOMAINS.write().unwrap();
-        let site_name_mapped = domains_locked.get(site_name);
-        let mut site_name_actual = site_name;
+        let site_name = site_name.to_lowercase();
+
+        // DOMAINS is never modified after creation so even if the lock is poisoned the map is
+        // still safe to read.
+        let domains_locked = DOMAINS.read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
 
+        let site_name_mapped = domains_locked.get(site_name.as_str());
         if site_name_mapped.is_some() {
-            site_name_actual = *site_name_mapped.unwrap();
+            return SiteDescriptor { site_name: String::from(*site_name_mapped.unwrap()) };
         }
 
-        return SiteDescriptor { site_name: String::from(site_name_actual) };
+        return SiteDescriptor { site_name };
     }
 }
 
@@ -161,6 +193,18 @@ impl ThreadDescriptor {
         }
     }
 
+    /// Same as new() but validates [site_name] and [board_code], see PostDescriptor::try_new().
+    pub fn try_new(
+        site_name: String,
+        board_code: String,
+        thread_no: u64
+    ) -> anyhow::Result<ThreadDescriptor> {
+        validate_descriptor_part("site_name", &site_name, constants::MAX_SITE_NAME_LENGTH)?;
+        validate_descriptor_part("board_code", &board_code, constants::MAX_BOARD_CODE_LENGTH)?;
+
+        return Ok(ThreadDescriptor::new(site_name, board_code, thread_no));
+    }
+
     pub fn from_catalog_descriptor(
         catalog_descriptor: CatalogDescriptor,
         thread_no: u64
@@ -245,6 +289,21 @@ impl PostDescriptor {
         }
     }
 
+    /// Same as new() but for site names and board codes that come from the outside (e.g. parsed
+    /// from a post url) which must be validated before they end up in the caches and the database.
+    pub fn try_new(
+        site_name: String,
+        board_code: String,
+        thread_no: u64,
+        post_no: u64,
+        post_sub_no: u64
+    ) -> anyhow::Result<PostDescriptor> {
+        validate_descriptor_part("site_name", &site_name, constants::MAX_SITE_NAME_LENGTH)?;
+        validate_descriptor_part("board_code", &board_code, constants::MAX_BOARD_CODE_LENGTH)?;
+
+        return Ok(PostDescriptor::new(site_name, board_code, thread_no, post_no, post_sub_no));
+    }
+
     pub fn from_thread_descriptor(
         thread_descriptor: ThreadDescriptor,
         post_no: u64,
@@ -259,7 +318,118 @@ impl PostDescriptor {
 }
 
 impl ChanThread {
+    pub fn activity_state(&self) -> ThreadActivityState {
+        // Closed threads may be archived as well, closed takes precedence.
+        if self.closed {
+            return ThreadActivityState::Closed;
+        }
+
+        if self.archived {
+            return ThreadActivityState::Archived;
+        }
+
+        return ThreadActivityState::Active;
+    }
+
     pub fn is_not_active(&self) -> bool {
-        return self.closed || self.archived;
+        return self.activity_state() != ThreadActivityState::Active;
+    }
+}
+
+fn validate_descriptor_part(name: &str, value: &str, max_length: usize) -> anyhow::Result<()> {
+    if value.is_empty() {
+        return Err(anyhow!("{} is empty", name));
+    }
+
+    if value.len() > max_length {
+        return Err(anyhow!("{} is too long ({} > {})", name, value.len(), max_length));
     }
+
+    let has_bad_chars = value.chars().any(|ch| !ch.is_ascii_alphanumeric() && ch != '_');
+    if has_bad_chars {
+        return Err(anyhow!("{} \'{}\' contains not allowed characters", name, value));
+    }
+
+    return Ok(());
 }
+
+#[test]
+fn test_post_descriptor_from_thread_descriptor_keeps_post_sub_no() {
+    let thread_descriptor = ThreadDescriptor::new("test".to_string(), "g".to_string(), 1);
+
+    let post_descriptor = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 2, 3);
+    assert_eq!(thread_descriptor, post_descriptor.thread_descriptor);
+    assert_eq!(2, post_descriptor.post_no);
+    assert_eq!(3, post_descriptor.post_sub_no);
+
+    let post_descriptor_without_sub_no = PostDescriptor::from_thread_descriptor(thread_descriptor, 2, 0);
+    assert_ne!(post_descriptor, post_descriptor_without_sub_no);
+}
+
+#[test]
+fn test_site_descriptor_from_str_normalizes_case() {
+    assert_eq!("4chan", SiteDescriptor::from_str("4channel").site_name_str());
+    assert_eq!("4chan", SiteDescriptor::from_str("4CHANNEL").site_name_str());
+    assert_eq!("4chan", SiteDescriptor::from_str("4Channel").site_name_str());
+    assert_eq!("4chan", SiteDescriptor::from_str("4Chan").site_name_str());
+    assert_eq!("2ch", SiteDescriptor::from_str("2CH").site_name_str());
+}
+
+#[test]
+fn test_site_descriptor_from_str_does_not_panic_when_lock_is_poisoned() {
+    let _ = std::thread::spawn(|| {
+        let _domains_locked = DOMAINS.write().unwrap();
+        panic!("Poisoning DOMAINS lock");
+    }).join();
+
+    assert!(DOMAINS.is_poisoned());
+    assert_eq!("4chan", SiteDescriptor::from_str("4Channel").site_name_str());
+}
+
+#[test]
+fn test_post_descriptor_try_new() {
+    let post_descriptor = PostDescriptor::try_new("4chan".to_string(), "vg".to_string(), 1, 2, 0).unwrap();
+    assert_eq!(PostDescriptor::new("4chan".to_string(), "vg".to_string(), 1, 2, 0), post_descriptor);
+
+    assert!(PostDescriptor::try_new("2ch".to_string(), "b".to_string(), 1, 2, 0).is_ok());
+    assert!(PostDescriptor::try_new("4chan".to_string(), "a_b1".to_string(), 1, 2, 0).is_ok());
+
+    let max_length_board_code = "a".repeat(constants::MAX_BOARD_CODE_LENGTH);
+    assert!(PostDescriptor::try_new("4chan".to_string(), max_length_board_code, 1, 2, 0).is_ok());
+}
+
+#[test]
+fn test_post_descriptor_try_new_rejects_bad_board_codes() {
+    let error = PostDescriptor::try_new("4chan".to_string(), "".to_string(), 1, 2, 0).err().unwrap();
+    assert_eq!("board_code is empty", error.to_string());
+
+    let too_long_board_code = "a".repeat(constants::MAX_BOARD_CODE_LENGTH + 1);
+    let error = PostDescriptor::try_new("4chan".to_string(), too_long_board_code, 1, 2, 0).err().unwrap();
+    assert_eq!("board_code is too long (33 > 32)", error.to_string());
+
+    assert!(PostDescriptor::try_new("4chan".to_string(), "v g".to_string(), 1, 2, 0).is_err());
+    assert!(PostDescriptor::try_new("4chan".to_string(), "vg/../".to_string(), 1, 2, 0).is_err());
+    assert!(PostDescriptor::try_new("4chan".to_string(), "доска".to_string(), 1, 2, 0).is_err());
+}
+
+#[test]
+fn test_post_descriptor_try_new_rejects_bad_site_names() {
+    assert!(PostDescriptor::try_new("".to_string(), "vg".to_string(), 1, 2, 0).is_err());
+    assert!(PostDescriptor::try_new("a".repeat(constants::MAX_SITE_NAME_LENGTH + 1), "vg".to_string(), 1, 2, 0).is_err());
+    assert!(PostDescriptor::try_new("4chan.org".to_string(), "vg".to_string(), 1, 2, 0).is_err());
+}
+
+#[test]
+fn test_chan_thread_activity_state() {
+    let chan_thread = |closed: bool, archived: bool| {
+        return ChanThread { closed, archived, subject: None, posts: vec![] };
+    };
+
+    assert_eq!(ThreadActivityState::Active, chan_thread(false, false).activity_state());
+    assert_eq!(ThreadActivityState::Archived, chan_thread(false, true).activity_state());
+    assert_eq!(ThreadActivityState::Closed, chan_thread(true, false).activity_state());
+    assert_eq!(ThreadActivityState::Closed, chan_thread(true, true).activity_state());
+
+    assert!(!chan_thread(false, false).is_not_active());
+    assert!(chan_thread(false, true).is_not_active());
+}
\ No newline at end of file
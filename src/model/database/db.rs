@@ -1,42 +1,184 @@
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{anyhow, Context};
-use bb8::{Pool, PooledConnection};
+use bb8::{Pool, PooledConnection, RunError, State};
 use bb8_postgres::PostgresConnectionManager;
+use rand::Rng;
 use tokio_postgres::NoTls;
 
+use crate::error;
+
+const CONNECTION_MAX_RETRIES: u32 = 3;
+const CONNECTION_RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+const CONNECTION_RETRY_MAX_DELAY: Duration = Duration::from_secs(1);
+
 pub struct Database {
-    pool: Arc<Pool<PostgresConnectionManager<NoTls>>>
+    pool: Arc<Pool<PostgresConnectionManager<NoTls>>>,
+    connection_timeout: Option<Duration>
 }
 
 pub type PgPooledConnection<'a> = PooledConnection<'a, PostgresConnectionManager<NoTls>>;
 
+#[derive(Debug)]
+pub enum ConnectionError {
+    /// Pool timeouts, dropped connections, server restarts etc. Worth retrying.
+    Transient(anyhow::Error),
+    /// Bad credentials, missing database etc. Retrying won't help.
+    Permanent(anyhow::Error)
+}
+
+impl ConnectionError {
+    fn into_error(self) -> anyhow::Error {
+        return match self {
+            ConnectionError::Transient(error) => error,
+            ConnectionError::Permanent(error) => error
+        }
+    }
+}
+
 impl Database {
+    /// Uses a pool of twice the amount of cpu cores without a connection acquisition timeout.
     pub async fn new(connection_string: String, cpu_cores_count: u32) -> anyhow::Result<Database> {
+        return Database::with_pool_config(
+            connection_string,
+            cpu_cores_count,
+            cpu_cores_count * 2,
+            None
+        ).await;
+    }
+
+    pub async fn with_pool_config(
+        connection_string: String,
+        min_idle: u32,
+        max_size: u32,
+        connection_timeout: Option<Duration>
+    ) -> anyhow::Result<Database> {
+        if max_size == 0 {
+            return Err(anyhow!("Database pool max size must be greater than zero"));
+        }
+
         let manager = PostgresConnectionManager::new_from_stringlike(
             connection_string,
             NoTls
         ).context("Failed to connect to the database")?;
 
         let pool = Pool::builder()
-            .min_idle(Some(cpu_cores_count))
-            .max_size(cpu_cores_count * 2)
+            .min_idle(Some(min_idle.min(max_size)))
+            .max_size(max_size)
             .build(manager)
             .await
             .context("Failed to create connection pool")?;
 
         let database = Database {
-            pool: Arc::new(pool)
+            pool: Arc::new(pool),
+            connection_timeout
         };
 
         return Ok(database);
     }
 
     pub async fn connection(&self) -> anyhow::Result<PgPooledConnection<'_>> {
-        return match self.pool.get().await {
-            Ok(connection) => { Ok(connection) },
-            Err(error) => { Err(anyhow!(error.to_string())) }
+        return self.try_connection().await.map_err(|error| error.into_error());
+    }
+
+    /// Same as connection() but transient acquisition errors are retried a couple of times with
+    /// exponential backoff before giving up.
+    pub async fn connection_with_retry(&self) -> anyhow::Result<PgPooledConnection<'_>> {
+        return retry_with_backoff(
+            CONNECTION_MAX_RETRIES,
+            CONNECTION_RETRY_BASE_DELAY,
+            || self.try_connection()
+        ).await;
+    }
+
+    async fn try_connection(&self) -> Result<PgPooledConnection<'_>, ConnectionError> {
+        if self.connection_timeout.is_none() {
+            return self.pool.get().await.map_err(|error| classify_run_error(error));
         }
+
+        let connection_timeout = self.connection_timeout.unwrap();
+
+        return match tokio::time::timeout(connection_timeout, self.pool.get()).await {
+            Ok(result) => result.map_err(|error| classify_run_error(error)),
+            Err(_) => {
+                let pool_state = self.pool.state();
+
+                Err(ConnectionError::Transient(anyhow!(
+                    "Timed out after {:?} waiting for a database connection (connections: {}, idle: {})",
+                    connection_timeout,
+                    pool_state.connections,
+                    pool_state.idle_connections
+                )))
+            }
+        }
+    }
+
+    pub fn pool_state(&self) -> State {
+        return self.pool.state();
+    }
+
+}
+
+fn classify_run_error(error: RunError<tokio_postgres::Error>) -> ConnectionError {
+    let is_permanent = match &error {
+        RunError::User(postgres_error) => {
+            // Class 28 is "Invalid Authorization Specification", 3D000 is "Invalid Catalog Name"
+            // (the database does not exist).
+            postgres_error.code()
+                .map(|sql_state| sql_state.code().starts_with("28") || sql_state.code() == "3D000")
+                .unwrap_or(false)
+        }
+        RunError::TimedOut => false
+    };
+
+    if is_permanent {
+        return ConnectionError::Permanent(anyhow!(error.to_string()));
     }
 
+    return ConnectionError::Transient(anyhow!(error.to_string()));
+}
+
+/// Calls [operation] until it succeeds, fails with a permanent error or [max_retries] retries were
+/// made. The delay between retries doubles every time (capped at CONNECTION_RETRY_MAX_DELAY) and
+/// is randomized so that concurrent callers don't retry all at once.
+pub async fn retry_with_backoff<T, F, Fut>(
+    max_retries: u32,
+    base_delay: Duration,
+    mut operation: F
+) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ConnectionError>>
+{
+    let mut attempt: u32 = 0;
+
+    loop {
+        let error = match operation().await {
+            Ok(value) => return Ok(value),
+            Err(ConnectionError::Permanent(error)) => return Err(error),
+            Err(ConnectionError::Transient(error)) => error
+        };
+
+        if attempt >= max_retries {
+            return Err(error.context(format!("Giving up after {} retries", max_retries)));
+        }
+
+        let delay = base_delay.saturating_mul(2u32.saturating_pow(attempt))
+            .min(CONNECTION_RETRY_MAX_DELAY);
+        let jitter_millis = rand::thread_rng().gen_range(0..=(delay.as_millis() as u64 / 2));
+        let delay = delay / 2 + Duration::from_millis(jitter_millis);
+
+        error!(
+            "retry_with_backoff() attempt {}/{} failed, retrying in {:?}, error: {}",
+            attempt + 1,
+            max_retries + 1,
+            delay,
+            error
+        );
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
 }
\ No newline at end of file
@@ -1,15 +1,17 @@
 use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
 
-use anyhow::Context;
+use anyhow::{anyhow, Context};
 use async_recursion::async_recursion;
 use async_trait::async_trait;
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
 use regex::Regex;
+use reqwest::header::HeaderMap;
 use reqwest::Response;
 
-use crate::{error, info};
-use crate::model::data::chan::{ChanThread, PostDescriptor, SiteDescriptor, ThreadDescriptor};
+use crate::{error, info, warn};
+use crate::model::data::chan::{CatalogDescriptor, CatalogThread, ChanBoard, ChanThread, PostDescriptor, SiteDescriptor, ThreadDescriptor};
 use crate::model::database::db::Database;
 use crate::model::imageboards::parser::chan4_post_parser::ThreadParseResult;
 use crate::model::imageboards::parser::post_parser::PostParser;
@@ -22,8 +24,18 @@ pub trait Imageboard {
     fn matches(&self, site_descriptor: &SiteDescriptor) -> bool;
     fn url_matches(&self, url: &str) -> bool;
     fn post_url_to_post_descriptor(&self, post_url: &str) -> Option<PostDescriptor>;
+
+    /// Parses a url of a thread (with or without a post number). Used to tell thread urls apart from
+    /// the urls that can't be parsed at all. Sites that can't do that never find a thread.
+    fn thread_url_to_thread_descriptor(&self, _thread_url: &str) -> Option<ThreadDescriptor> {
+        return None;
+    }
+
     fn post_descriptor_to_url(&self, post_descriptor: &PostDescriptor) -> Option<String>;
-    fn post_quote_regex(&self) -> &'static Regex;
+    /// Candidate quote regexes, tried in order for every post comment, the first one that matches
+    /// anything is used. The first capture group must be the quoted post_no. Sites with sub
+    /// numbered posts may also capture the quoted post_sub_no as the second group.
+    fn post_quote_regexes(&self) -> &'static [Regex];
     fn post_parser(&self) -> &'static Box<dyn PostParser + Sync>;
     fn thread_json_endpoint(
         &self,
@@ -31,25 +43,157 @@ pub trait Imageboard {
         last_processed_post: &Option<PostDescriptor>
     ) -> Option<String>;
     fn supports_partial_load_head_request(&self) -> bool;
+    fn catalog_json_endpoint(&self, catalog_descriptor: &CatalogDescriptor) -> Option<String>;
+    fn boards_json_endpoint(&self) -> Option<String>;
+
+    /// Sites that don't have a list of known boards accept every board code.
+    fn is_known_board(&self, _board_code: &str) -> bool {
+        return true;
+    }
+
+    /// Finds the thread that replaced [dead_thread_descriptor] (e.g. the next thread of a general)
+    /// among the threads of its catalog. Sites that can't follow threads never find one.
+    fn find_successor_thread(
+        &self,
+        _dead_thread_descriptor: &ThreadDescriptor,
+        _dead_thread_subject: &str,
+        _catalog_threads: &Vec<CatalogThread>
+    ) -> Option<ThreadDescriptor> {
+        return None;
+    }
+
+    /// Loads the list of boards of this site. Sites without a boards endpoint have no boards.
+    async fn fetch_boards(&self, http_client: &reqwest::Client) -> anyhow::Result<Vec<ChanBoard>> {
+        let boards_json_endpoint = self.boards_json_endpoint();
+        if boards_json_endpoint.is_none() {
+            return Ok(vec![]);
+        }
+
+        let boards_json_endpoint = boards_json_endpoint.unwrap();
+
+        let request = http_client.get(boards_json_endpoint.clone()).build()?;
+        let response = http_client.execute(request)
+            .await
+            .with_context(|| {
+                return format!(
+                    "fetch_boards({}) Failed to execute GET request to \'{}\' endpoint",
+                    self.name(),
+                    boards_json_endpoint
+                );
+            })?;
+
+        let status_code = response.status().as_u16();
+        if status_code != 200 {
+            return Err(anyhow!("fetch_boards({}) GET status_code == {}", self.name(), status_code));
+        }
+
+        let response_text = response.text()
+            .await
+            .with_context(|| {
+                return format!("fetch_boards({}) Failed to extract text from response", self.name());
+            })?;
+
+        let boards = self.post_parser().parse_boards(&response_text)
+            .with_context(|| {
+                return format!("fetch_boards({}) Failed to parse boards", self.name());
+            })?;
+
+        info!("fetch_boards({}) success, boards: {}", self.name(), boards.len());
+        return Ok(boards);
+    }
 }
 
+// Includes the first page, loaded by load_thread() itself
+const MAX_THREAD_PAGES: usize = 32;
+
 pub enum ThreadLoadResult {
-    Success(ChanThread, Option<DateTime<FixedOffset>>),
+    Success(ChanThread, Option<DateTime<FixedOffset>>, Option<String>),
     ThreadWasNotModifiedSinceLastCheck,
     SiteNotSupported,
     HeadRequestBadStatusCode(u16),
     GetRequestBadStatusCode(u16),
     ThreadDeletedOrClosed,
     ThreadInaccessible,
+    /// The site didn't respond in time, the thread may still be alive so it must not be marked
+    /// as dead.
+    RequestTimedOut,
     FailedToReadChanThread(String),
     ServerSentIncorrectData(String),
     ServerError(i32, String)
 }
 
+pub enum CatalogLoadResult {
+    Success(Vec<CatalogThread>),
+    SiteNotSupported,
+    BadStatusCode(u16)
+}
+
+pub async fn load_catalog(
+    imageboard: &ImageboardSynced,
+    http_client: &reqwest::Client,
+    catalog_descriptor: &CatalogDescriptor
+) -> anyhow::Result<CatalogLoadResult> {
+    let catalog_json_endpoint = imageboard.catalog_json_endpoint(catalog_descriptor);
+    if catalog_json_endpoint.is_none() {
+        info!("load_catalog({}) site is not supported", catalog_descriptor);
+        return Ok(CatalogLoadResult::SiteNotSupported);
+    }
+
+    let catalog_json_endpoint = catalog_json_endpoint.unwrap();
+
+    let request = http_client.get(catalog_json_endpoint.clone()).build()?;
+    let response = http_client.execute(request)
+        .await
+        .with_context(|| {
+            return format!(
+                "load_catalog({}) Failed to execute GET request to \'{}\' endpoint",
+                catalog_descriptor,
+                catalog_json_endpoint
+            );
+        })?;
+
+    let status_code = response.status().as_u16();
+    if status_code != 200 {
+        error!("load_catalog({}) GET status_code == {}", catalog_descriptor, status_code);
+        return Ok(CatalogLoadResult::BadStatusCode(status_code));
+    }
+
+    let content_encoding = undecoded_content_encoding(response.headers());
+    if content_encoding.is_some() {
+        return Err(anyhow!(
+            "load_catalog({}) Response body is \'{}\'-encoded but the http client didn't decompress it",
+            catalog_descriptor,
+            content_encoding.unwrap()
+        ));
+    }
+
+    let response_text = response.text()
+        .await
+        .with_context(|| {
+            return format!(
+                "load_catalog({}) Failed to extract text from response",
+                catalog_descriptor
+            );
+        })?;
+
+    let catalog_threads = imageboard.post_parser().parse_catalog(catalog_descriptor, &response_text)
+        .with_context(|| {
+            return format!("load_catalog({}) Failed to parse catalog", catalog_descriptor);
+        })?;
+
+    info!(
+        "load_catalog({}) success, threads: {}",
+        catalog_descriptor,
+        catalog_threads.len()
+    );
+
+    return Ok(CatalogLoadResult::Success(catalog_threads));
+}
+
 #[async_recursion]
 pub async fn load_thread(
     imageboard: &ImageboardSynced,
-    http_client: &'static reqwest::Client,
+    http_client: &reqwest::Client,
     database: &Arc<Database>,
     thread_descriptor: &ThreadDescriptor,
     last_processed_post: &Option<PostDescriptor>
@@ -68,10 +212,29 @@ pub async fn load_thread(
 
     let thread_json_endpoint = thread_json_endpoint.unwrap();
 
-    let head_request = http_client.head(thread_json_endpoint.clone()).build()?;
-    let head_response = http_client.execute(head_request).await?;
+    let last_etag_local = thread_repository::get_last_etag(thread_descriptor, database).await?;
+
+    let mut head_request_builder = http_client.head(thread_json_endpoint.clone());
+    if last_etag_local.is_some() {
+        head_request_builder = head_request_builder.header("If-None-Match", last_etag_local.clone().unwrap());
+    }
+
+    let head_request = head_request_builder.build()?;
+    let head_response = http_client.execute(head_request).await;
+    if is_timeout_error(&head_response) {
+        error!("load_thread({}) HEAD request timed out", thread_descriptor);
+        return Ok(ThreadLoadResult::RequestTimedOut);
+    }
+
+    let head_response = head_response?;
+    log_redirect_if_needed(thread_descriptor, "HEAD", &thread_json_endpoint, &head_response);
 
     let status_code = head_response.status().as_u16();
+    if status_code == 304 {
+        info!("load_thread({}) HEAD status_code == 304, thread was not modified", thread_descriptor);
+        return Ok(ThreadLoadResult::ThreadWasNotModifiedSinceLastCheck);
+    }
+
     if status_code != 200 {
         // 2ch.hk will return 404 when sending a HEAD request to v2 API that supports partial thread
         // loading so we don't want to switch to full thread load in the case, just ignore this 404.
@@ -96,12 +259,25 @@ pub async fn load_thread(
         }
     }
 
+    let etag = parse_etag_header(head_response.headers());
+
     let last_modified = parse_last_modified_header(
         thread_descriptor,
         head_response
     ).await;
 
-    if last_modified.is_some() {
+    // Prefer ETag over Last-Modified when the server supplies one since it's more reliable
+    if etag.is_some() {
+        if last_etag_local.is_some() && last_etag_local == etag {
+            info!(
+                "load_thread({}) Thread was not updated since last check (etag: {})",
+                thread_descriptor,
+                etag.unwrap()
+            );
+
+            return Ok(ThreadLoadResult::ThreadWasNotModifiedSinceLastCheck);
+        }
+    } else if last_modified.is_some() {
         let thread_updated_since_last_check = was_content_modified_since_last_check(
             thread_descriptor,
             &last_modified,
@@ -114,9 +290,19 @@ pub async fn load_thread(
         }
     }
 
-    let request = http_client.get(thread_json_endpoint.clone()).build()?;
-    let response = http_client.execute(request)
-        .await
+    let mut request_builder = http_client.get(thread_json_endpoint.clone());
+    if last_etag_local.is_some() {
+        request_builder = request_builder.header("If-None-Match", last_etag_local.clone().unwrap());
+    }
+
+    let request = request_builder.build()?;
+    let response = http_client.execute(request).await;
+    if is_timeout_error(&response) {
+        error!("load_thread({}) GET request timed out", thread_descriptor);
+        return Ok(ThreadLoadResult::RequestTimedOut);
+    }
+
+    let response = response
         .with_context(|| {
             return format!(
                 "load_thread({}) Failed to execute GET request to \'{}\' endpoint",
@@ -125,7 +311,14 @@ pub async fn load_thread(
             );
         })?;
 
+    log_redirect_if_needed(thread_descriptor, "GET", &thread_json_endpoint, &response);
+
     let status_code = response.status().as_u16();
+    if status_code == 304 {
+        info!("load_thread({}) GET status_code == 304, thread was not modified", thread_descriptor);
+        return Ok(ThreadLoadResult::ThreadWasNotModifiedSinceLastCheck);
+    }
+
     if status_code != 200 {
         if last_processed_post.is_some() && status_code == 404 {
             info!("load_thread({}) GET status_code == 404, switching to full load", thread_descriptor);
@@ -142,8 +335,26 @@ pub async fn load_thread(
         return Ok(ThreadLoadResult::GetRequestBadStatusCode(status_code));
     }
 
-    let response_text = response.text()
-        .await
+    let etag = parse_etag_header(response.headers()).or(etag);
+
+    let content_encoding = undecoded_content_encoding(response.headers());
+    if content_encoding.is_some() {
+        let error_text = format!(
+            "Response body is \'{}\'-encoded but the http client didn't decompress it",
+            content_encoding.unwrap()
+        );
+
+        error!("load_thread({}) {}", thread_descriptor, error_text);
+        return Ok(ThreadLoadResult::FailedToReadChanThread(error_text));
+    }
+
+    let response_text = response.text().await;
+    if is_timeout_error(&response_text) {
+        error!("load_thread({}) timed out while reading response body", thread_descriptor);
+        return Ok(ThreadLoadResult::RequestTimedOut);
+    }
+
+    let response_text = response_text
         .with_context(|| {
             return format!(
                 "load_thread({}) Failed to extract text from response",
@@ -189,8 +400,17 @@ pub async fn load_thread(
         thread_parse_result.unwrap()
     };
 
-    let chan_thread = match thread_parse_result {
+    let mut has_more_pages = false;
+
+    let mut chan_thread = match thread_parse_result {
         ThreadParseResult::Ok(chan_thread) => { chan_thread }
+        ThreadParseResult::HasMorePages(chan_thread) => {
+            has_more_pages = true;
+            chan_thread
+        }
+        // Processed like any other archived thread: the posts are scanned one last time and then
+        // the thread is marked as dead.
+        ThreadParseResult::ThreadArchived(chan_thread) => { chan_thread }
         ThreadParseResult::PartialParseFailed => {
             info!(
                 "load_thread({}) Failed to parse thread partially, switching to full load",
@@ -230,16 +450,224 @@ pub async fn load_thread(
             last_processed_post.is_some()
         );
 
+        // The tail of a thread may legitimately contain no new posts so give the full load a try.
+        // The full load is never partial so this can't loop.
+        if last_processed_post.is_some() {
+            info!("load_thread({}) partial load returned no posts, switching to full load", thread_descriptor);
+
+            return load_thread(
+                imageboard,
+                http_client,
+                database,
+                thread_descriptor,
+                &None
+            ).await;
+        }
+
         return Ok(ThreadLoadResult::FailedToReadChanThread("Thread has no posts".to_string()));
     }
 
+    if has_more_pages {
+        load_remaining_thread_pages(imageboard, http_client, thread_descriptor, &mut chan_thread).await;
+    }
+
     info!(
         "load_thread({}) success, is partial load: {}",
         thread_descriptor,
         last_processed_post.is_some()
     );
 
-    return Ok(ThreadLoadResult::Success(chan_thread, last_modified));
+    return Ok(ThreadLoadResult::Success(chan_thread, last_modified, etag));
+}
+
+/// Keeps loading the posts after the last post of [chan_thread] until the site stops reporting that
+/// there are more of them. Errors are only logged, the posts that were loaded up to that point are
+/// processed and the rest of them will be loaded during the next check.
+async fn load_remaining_thread_pages(
+    imageboard: &ImageboardSynced,
+    http_client: &reqwest::Client,
+    thread_descriptor: &ThreadDescriptor,
+    chan_thread: &mut ChanThread
+) {
+    for page in 1..MAX_THREAD_PAGES {
+        let last_post = chan_thread.posts.last();
+        if last_post.is_none() {
+            return;
+        }
+
+        let last_post = last_post.unwrap();
+        let last_post_descriptor = Some(PostDescriptor::from_thread_descriptor(
+            thread_descriptor.clone(),
+            last_post.post_no,
+            last_post.post_sub_no.unwrap_or(0)
+        ));
+
+        let thread_json_endpoint = imageboard.thread_json_endpoint(thread_descriptor, &last_post_descriptor);
+        if thread_json_endpoint.is_none() {
+            return;
+        }
+
+        let thread_json_endpoint = thread_json_endpoint.unwrap();
+
+        let response_text = load_thread_page(http_client, &thread_json_endpoint).await;
+        if response_text.is_err() {
+            error!(
+                "load_remaining_thread_pages({}) Failed to load page {}, error: {}",
+                thread_descriptor,
+                page,
+                response_text.err().unwrap()
+            );
+
+            return;
+        }
+
+        let thread_parse_result = imageboard.post_parser().parse(
+            thread_descriptor,
+            &last_post_descriptor,
+            &response_text.unwrap()
+        );
+
+        if thread_parse_result.is_err() {
+            error!(
+                "load_remaining_thread_pages({}) Failed to parse page {}, error: {}",
+                thread_descriptor,
+                page,
+                thread_parse_result.err().unwrap()
+            );
+
+            return;
+        }
+
+        let (page_chan_thread, has_more_pages) = match thread_parse_result.unwrap() {
+            ThreadParseResult::Ok(page_chan_thread) => (page_chan_thread, false),
+            ThreadParseResult::HasMorePages(page_chan_thread) => (page_chan_thread, true),
+            _ => {
+                error!(
+                    "load_remaining_thread_pages({}) Page {} has no posts",
+                    thread_descriptor,
+                    page
+                );
+
+                return;
+            }
+        };
+
+        let last_post_no = last_post.post_no;
+        let posts_count_before = chan_thread.posts.len();
+
+        // Pages may repeat the original post so only the posts newer than the last one are taken
+        chan_thread.posts.extend(
+            page_chan_thread.posts
+                .into_iter()
+                .filter(|chan_post| chan_post.post_no > last_post_no)
+        );
+
+        let new_posts_count = chan_thread.posts.len() - posts_count_before;
+
+        info!(
+            "load_remaining_thread_pages({}) loaded page {}, new posts: {}, has more pages: {}",
+            thread_descriptor,
+            page,
+            new_posts_count,
+            has_more_pages
+        );
+
+        if !has_more_pages || new_posts_count == 0 {
+            return;
+        }
+    }
+
+    warn!(
+        "load_remaining_thread_pages({}) Reached the max amount of pages ({})",
+        thread_descriptor,
+        MAX_THREAD_PAGES
+    );
+}
+
+async fn load_thread_page(
+    http_client: &reqwest::Client,
+    thread_json_endpoint: &String
+) -> anyhow::Result<String> {
+    let request = http_client.get(thread_json_endpoint.clone()).build()?;
+    let response = http_client.execute(request)
+        .await
+        .with_context(|| {
+            return format!("Failed to execute GET request to '{}' endpoint", thread_json_endpoint);
+        })?;
+
+    let status_code = response.status().as_u16();
+    if status_code != 200 {
+        return Err(anyhow!("GET status_code == {}", status_code));
+    }
+
+    let content_encoding = undecoded_content_encoding(response.headers());
+    if content_encoding.is_some() {
+        return Err(anyhow!(
+            "Response body is '{}'-encoded but the http client didn't decompress it",
+            content_encoding.unwrap()
+        ));
+    }
+
+    let response_text = response.text()
+        .await
+        .context("Failed to extract text from response")?;
+
+    return Ok(response_text);
+}
+
+fn log_redirect_if_needed(
+    thread_descriptor: &ThreadDescriptor,
+    method: &str,
+    requested_url: &str,
+    response: &Response
+) {
+    let final_url = response.url().as_str();
+    if final_url == requested_url {
+        return;
+    }
+
+    info!(
+        "load_thread({}) {} request to \'{}\' was redirected to \'{}\'",
+        thread_descriptor,
+        method,
+        requested_url,
+        final_url
+    );
+}
+
+fn is_timeout_error<T>(result: &Result<T, reqwest::Error>) -> bool {
+    return match result {
+        Ok(_) => false,
+        Err(error) => error.is_timeout()
+    };
+}
+
+/// reqwest removes the Content-Encoding header after decompressing the body so if it's still there
+/// then the body is still compressed and can't be parsed.
+fn undecoded_content_encoding(headers: &HeaderMap) -> Option<String> {
+    let content_encoding = headers.get("Content-Encoding")
+        .map(|header_value| header_value.to_str().unwrap_or(""))
+        .unwrap_or("")
+        .trim()
+        .to_lowercase();
+
+    if content_encoding.is_empty() || content_encoding == "identity" {
+        return None;
+    }
+
+    return Some(content_encoding);
+}
+
+fn parse_etag_header(headers: &HeaderMap) -> Option<String> {
+    let etag = headers.get("ETag")
+        .map(|header_value| header_value.to_str().unwrap_or(""))
+        .unwrap_or("");
+
+    if etag.is_empty() {
+        return None;
+    }
+
+    return Some(etag.to_string());
 }
 
 async fn parse_last_modified_header(
@@ -256,8 +684,8 @@ async fn parse_last_modified_header(
         return None;
     }
 
-    let last_modified = DateTime::parse_from_rfc2822(last_modified_str);
-    if last_modified.is_err() {
+    let last_modified = parse_last_modified(last_modified_str);
+    if last_modified.is_none() {
         error!(
             "load_thread({}) Failed to parse \'{}\' as DateTime (last_modified)",
             thread_descriptor,
@@ -267,7 +695,63 @@ async fn parse_last_modified_header(
         return None;
     }
 
-    return Some(last_modified.unwrap());
+    return last_modified;
+}
+
+#[derive(Clone, Copy)]
+enum LastModifiedFormat {
+    Rfc2822,
+    Rfc3339,
+    // No time zone in the format, the value is always in UTC
+    Utc(&'static str)
+}
+
+// Tried in order. Some CDNs don't follow RFC2822 exactly (no leading zeroes, "UTC" instead of
+// "GMT", the obsolete RFC850 and asctime formats etc).
+const LAST_MODIFIED_FORMATS: &[(&str, LastModifiedFormat)] = &[
+    ("RFC2822", LastModifiedFormat::Rfc2822),
+    ("RFC1123", LastModifiedFormat::Utc("%a, %d %b %Y %H:%M:%S GMT")),
+    ("RFC1123 (UTC)", LastModifiedFormat::Utc("%a, %d %b %Y %H:%M:%S UTC")),
+    ("RFC1123 (no comma)", LastModifiedFormat::Utc("%a %d %b %Y %H:%M:%S GMT")),
+    ("RFC850", LastModifiedFormat::Utc("%A, %d-%b-%y %H:%M:%S GMT")),
+    ("asctime", LastModifiedFormat::Utc("%a %b %e %H:%M:%S %Y")),
+    ("RFC3339", LastModifiedFormat::Rfc3339),
+];
+
+// Bit per LAST_MODIFIED_FORMATS entry, set once a header was parsed with that format
+static LOGGED_LAST_MODIFIED_FORMATS: AtomicU32 = AtomicU32::new(0);
+
+pub fn parse_last_modified(last_modified_str: &str) -> Option<DateTime<FixedOffset>> {
+    let last_modified_str = last_modified_str.trim();
+
+    for (index, (format_name, format)) in LAST_MODIFIED_FORMATS.iter().enumerate() {
+        let last_modified = match format {
+            LastModifiedFormat::Rfc2822 => DateTime::parse_from_rfc2822(last_modified_str).ok(),
+            LastModifiedFormat::Rfc3339 => DateTime::parse_from_rfc3339(last_modified_str).ok(),
+            LastModifiedFormat::Utc(format) => {
+                NaiveDateTime::parse_from_str(last_modified_str, format)
+                    .ok()
+                    .map(|date_time| date_time.and_utc().fixed_offset())
+            }
+        };
+
+        if last_modified.is_none() {
+            continue;
+        }
+
+        let format_bit = 1u32 << index;
+        if LOGGED_LAST_MODIFIED_FORMATS.fetch_or(format_bit, Ordering::Relaxed) & format_bit == 0 {
+            info!(
+                "parse_last_modified() \'{}\' was parsed using {} format",
+                last_modified_str,
+                format_name
+            );
+        }
+
+        return last_modified;
+    }
+
+    return None;
 }
 
 pub async fn was_content_modified_since_last_check(
@@ -306,6 +790,52 @@ pub async fn was_content_modified_since_last_check(
     return Ok(content_was_modified);
 }
 
+/// The successor is the oldest thread that was created after the dead one and has the same subject
+/// once thread numbers and punctuation are ignored (e.g. "/vg/ - General #123" and "/vg/ - General
+/// #124" match).
+pub fn find_successor_thread_by_subject(
+    dead_thread_descriptor: &ThreadDescriptor,
+    dead_thread_subject: &str,
+    catalog_threads: &Vec<CatalogThread>
+) -> Option<ThreadDescriptor> {
+    let dead_thread_subject = normalize_thread_subject(dead_thread_subject);
+    if dead_thread_subject.is_empty() {
+        return None;
+    }
+
+    let successor_thread_no = catalog_threads.iter()
+        .filter(|catalog_thread| catalog_thread.thread_no > dead_thread_descriptor.thread_no)
+        .filter(|catalog_thread| {
+            return catalog_thread.subject.as_ref()
+                .map(|subject| normalize_thread_subject(subject) == dead_thread_subject)
+                .unwrap_or(false);
+        })
+        .map(|catalog_thread| catalog_thread.thread_no)
+        .min();
+
+    if successor_thread_no.is_none() {
+        return None;
+    }
+
+    let successor_thread_descriptor = ThreadDescriptor::new(
+        dead_thread_descriptor.site_name().clone(),
+        dead_thread_descriptor.board_code().clone(),
+        successor_thread_no.unwrap()
+    );
+
+    return Some(successor_thread_descriptor);
+}
+
+fn normalize_thread_subject(subject: &str) -> String {
+    let subject = subject.to_lowercase()
+        .chars()
+        .filter(|ch| !ch.is_ascii_digit())
+        .map(|ch| if ch.is_alphanumeric() || ch == '/' { ch } else { ' ' })
+        .collect::<String>();
+
+    return subject.split_whitespace().collect::<Vec<&str>>().join(" ");
+}
+
 pub fn post_url_to_post_descriptor(
     imageboard: &dyn Imageboard,
     post_url: &str,
@@ -323,14 +853,7 @@ pub fn post_url_to_post_descriptor(
     let captures = captures.unwrap();
 
     let site_name = captures.get(1)?.as_str();
-    if site_name.is_empty() {
-        return None;
-    }
-
     let board_code = captures.get(2)?.as_str();
-    if board_code.is_empty() {
-        return None
-    }
 
     let thread_no_raw = captures.get(3)?.as_str();
     let thread_no = u64::from_str(thread_no_raw);
@@ -353,7 +876,7 @@ pub fn post_url_to_post_descriptor(
     }
     let post_no = post_no.unwrap();
 
-    let post_descriptor = PostDescriptor::new(
+    let post_descriptor = PostDescriptor::try_new(
         String::from(site_name),
         String::from(board_code),
         thread_no,
@@ -361,5 +884,88 @@ pub fn post_url_to_post_descriptor(
         0
     );
 
-    return Some(post_descriptor);
+    if post_descriptor.is_err() {
+        warn!("post_url_to_post_descriptor() bad post url '{}': {}", post_url, post_descriptor.err().unwrap());
+        return None;
+    }
+
+    return Some(post_descriptor.unwrap());
+}
+
+/// Same as [post_url_to_post_descriptor] but the post number group of [post_url_regex] is ignored.
+pub fn thread_url_to_thread_descriptor(
+    imageboard: &dyn Imageboard,
+    thread_url: &str,
+    post_url_regex: &Regex
+) -> Option<ThreadDescriptor> {
+    if !imageboard.url_matches(thread_url) {
+        return None;
+    }
+
+    let captures = post_url_regex.captures(thread_url);
+    if captures.is_none() {
+        return None;
+    }
+
+    let captures = captures.unwrap();
+
+    let site_name = captures.get(1)?.as_str();
+    let board_code = captures.get(2)?.as_str();
+
+    let thread_no = u64::from_str(captures.get(3)?.as_str());
+    if thread_no.is_err() {
+        return None;
+    }
+
+    let thread_descriptor = ThreadDescriptor::try_new(
+        String::from(site_name),
+        String::from(board_code),
+        thread_no.unwrap()
+    );
+
+    if thread_descriptor.is_err() {
+        warn!(
+            "thread_url_to_thread_descriptor() bad thread url '{}': {}",
+            thread_url,
+            thread_descriptor.err().unwrap()
+        );
+
+        return None;
+    }
+
+    return Some(thread_descriptor.unwrap());
+}
+
+#[test]
+fn test_parse_last_modified() {
+    let expected = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z").unwrap();
+
+    let headers = [
+        "Wed, 21 Oct 2015 07:28:00 GMT",
+        "Wed, 21 Oct 2015 07:28:00 +0000",
+        " Wed, 21 Oct 2015 07:28:00 GMT ",
+        "Wed, 21 Oct 2015 7:28:00 GMT",
+        "Wed, 21 Oct 2015 07:28:00 UTC",
+        "Wed 21 Oct 2015 07:28:00 GMT",
+        "Wednesday, 21-Oct-15 07:28:00 GMT",
+        "Wed Oct 21 07:28:00 2015",
+        "2015-10-21T07:28:00Z",
+    ];
+
+    for header in headers {
+        assert_eq!(Some(expected), parse_last_modified(header), "header: \'{}\'", header);
+    }
+
+    let expected = DateTime::parse_from_rfc3339("2015-10-01T07:08:00Z").unwrap();
+    assert_eq!(Some(expected), parse_last_modified("Thu, 1 Oct 2015 7:08:00 GMT"));
+    assert_eq!(Some(expected), parse_last_modified("Thu Oct  1 07:08:00 2015"));
+}
+
+#[test]
+fn test_parse_last_modified_rejects_garbage() {
+    assert_eq!(None, parse_last_modified(""));
+    assert_eq!(None, parse_last_modified("yesterday"));
+    assert_eq!(None, parse_last_modified("1445412480"));
+    // Wrong day of the week
+    assert_eq!(None, parse_last_modified("Thu, 21 Oct 2015 07:28:00 GMT"));
 }
\ No newline at end of file
@@ -1,13 +1,17 @@
+use std::collections::HashSet;
+
 use async_trait::async_trait;
 use lazy_static::lazy_static;
 use regex::{Captures, Regex};
 use url::Url;
 
 use crate::helpers::string_helpers;
-use crate::model::data::chan::{PostDescriptor, SiteDescriptor, ThreadDescriptor};
+use crate::model::data::chan::{CatalogDescriptor, CatalogThread, PostDescriptor, SiteDescriptor, ThreadDescriptor};
 use crate::model::imageboards::base_imageboard::{
+    find_successor_thread_by_subject,
     Imageboard,
-    post_url_to_post_descriptor
+    post_url_to_post_descriptor,
+    thread_url_to_thread_descriptor
 };
 use crate::model::imageboards::parser::chan4_post_parser::Chan4PostParser;
 use crate::model::imageboards::parser::post_parser::PostParser;
@@ -15,10 +19,23 @@ use crate::model::imageboards::parser::post_parser::PostParser;
 lazy_static! {
     static ref POST_URL_REGEX: Regex =
         Regex::new(r"https://boards.(\w+).org/(\w+)/thread/(\d+)(?:#p(\d+))?").unwrap();
-    static ref POST_REPLY_QUOTE_REGEX: Regex =
-        Regex::new(r#"class="quotelink">&gt;&gt;(\d+)</a>"#).unwrap();
+    // The second regex is a fallback for markup where the class attribute is not the last one of
+    // the anchor (attributes reordered, extra data- attributes etc).
+    static ref POST_REPLY_QUOTE_REGEXES: Vec<Regex> = vec![
+        Regex::new(r#"class="quotelink">&gt;&gt;(\d+)</a>"#).unwrap(),
+        Regex::new(r#"<a\s[^>]*class="[^"]*\bquotelink\b[^"]*"[^>]*>&gt;&gt;(\d+)</a>"#).unwrap()
+    ];
 
     static ref CHAN4_POST_PARSER: Box<dyn PostParser + Sync> = Box::new(Chan4PostParser {});
+
+    static ref KNOWN_BOARDS: HashSet<&'static str> = HashSet::from([
+        "3", "a", "aco", "adv", "an", "b", "bant", "biz", "c", "cgl", "ck", "cm", "co", "d", "diy",
+        "e", "f", "fa", "fit", "g", "gd", "gif", "h", "hc", "his", "hm", "hr", "i", "ic", "int",
+        "jp", "k", "lgbt", "lit", "m", "mlp", "mu", "n", "news", "o", "out", "p", "po", "pol", "pw",
+        "qa", "qst", "r", "r9k", "s", "s4s", "sci", "soc", "sp", "t", "tg", "toy", "trash", "trv",
+        "tv", "u", "v", "vg", "vip", "vm", "vmg", "vp", "vr", "vrpg", "vst", "vt", "w", "wg", "wsg",
+        "wsr", "x", "xs", "y"
+    ]);
 }
 
 pub struct Chan4 {
@@ -61,6 +78,10 @@ impl Imageboard for Chan4 {
         return post_url_to_post_descriptor(self, post_url, &POST_URL_REGEX);
     }
 
+    fn thread_url_to_thread_descriptor(&self, thread_url: &str) -> Option<ThreadDescriptor> {
+        return thread_url_to_thread_descriptor(self, thread_url, &POST_URL_REGEX);
+    }
+
     fn post_descriptor_to_url(&self, post_descriptor: &PostDescriptor) -> Option<String> {
         let mut string_builder = string_builder::Builder::new(72);
 
@@ -84,8 +105,8 @@ impl Imageboard for Chan4 {
         return Some(string.unwrap());
     }
 
-    fn post_quote_regex(&self) -> &'static Regex {
-        return &POST_REPLY_QUOTE_REGEX;
+    fn post_quote_regexes(&self) -> &'static [Regex] {
+        return &POST_REPLY_QUOTE_REGEXES;
     }
 
     fn post_parser(&self) -> &'static Box<dyn PostParser + Sync> {
@@ -120,6 +141,32 @@ impl Imageboard for Chan4 {
         return Some(endpoint);
     }
 
+    fn catalog_json_endpoint(&self, catalog_descriptor: &CatalogDescriptor) -> Option<String> {
+        if !self.matches(&catalog_descriptor.site_descriptor) {
+            return None;
+        }
+
+        let endpoint = format!("https://a.4cdn.org/{}/catalog.json", catalog_descriptor.board_code());
+        return Some(endpoint);
+    }
+
+    fn boards_json_endpoint(&self) -> Option<String> {
+        return Some("https://a.4cdn.org/boards.json".to_string());
+    }
+
+    fn is_known_board(&self, board_code: &str) -> bool {
+        return KNOWN_BOARDS.contains(board_code.to_lowercase().as_str());
+    }
+
+    fn find_successor_thread(
+        &self,
+        dead_thread_descriptor: &ThreadDescriptor,
+        dead_thread_subject: &str,
+        catalog_threads: &Vec<CatalogThread>
+    ) -> Option<ThreadDescriptor> {
+        return find_successor_thread_by_subject(dead_thread_descriptor, dead_thread_subject, catalog_threads);
+    }
+
     fn supports_partial_load_head_request(&self) -> bool {
         return true;
     }
@@ -143,20 +190,108 @@ fn test_url_conversion() {
     );
 
     assert!(td1.is_none());
+
+    let td2 = chan4.thread_url_to_thread_descriptor(
+        "https://boards.4chan.org/a/thread/1234567890"
+    ).unwrap();
+
+    assert_eq!("4chan", td2.site_name().as_str());
+    assert_eq!("a", td2.board_code().as_str());
+    assert_eq!(1234567890, td2.thread_no);
+}
+
+#[test]
+fn test_url_conversion_rejects_too_long_board_code() {
+    let chan4 = Chan4 { };
+
+    let post_url = format!(
+        "https://boards.4chan.org/{}/thread/1234567890#p1234567891",
+        "a".repeat(crate::constants::MAX_BOARD_CODE_LENGTH + 1)
+    );
+
+    assert!(chan4.post_url_to_post_descriptor(&post_url).is_none());
 }
 
 #[test]
 fn test_post_quote_regex() {
     let test_string = "<a href=\"#p251260223\" class=\"quotelink\">&gt;&gt;251260223</a>";
-    let captures = POST_REPLY_QUOTE_REGEX.captures(test_string).unwrap();
+    let captures = POST_REPLY_QUOTE_REGEXES[0].captures(test_string).unwrap();
     assert_eq!(2, captures.len());
     assert_eq!("251260223", captures.get(1).unwrap().as_str());
 
     let test_string = "<a href=\"#p92933496\" class=\"quotelink\">&gt;&gt;92933496</a><br>\
     <a href=\"#p92933523\" class=\"quotelink\">&gt;&gt;92933523</a><br>\
     Will look into them, upon first look, it shouldn&#039;t be much work";
-    let captures = POST_REPLY_QUOTE_REGEX.captures_iter(test_string).collect::<Vec<Captures>>();
+    let captures = POST_REPLY_QUOTE_REGEXES[0].captures_iter(test_string).collect::<Vec<Captures>>();
     assert_eq!(2, captures.len());
     assert_eq!("92933496", captures.get(0).unwrap().get(1).unwrap().as_str());
     assert_eq!("92933523", captures.get(1).unwrap().get(1).unwrap().as_str());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_post_quote_regexes_match_different_markup() {
+    let chan4 = Chan4 { };
+
+    let test_strings = [
+        "<a href=\"#p251260223\" class=\"quotelink\">&gt;&gt;251260223</a>",
+        "<a class=\"quotelink\" href=\"#p251260223\" data-function=\"highlight\" \
+        data-backlink=\"true\" data-post=\"251260223\">&gt;&gt;251260223</a>"
+    ];
+
+    for test_string in test_strings {
+        let captures = chan4.post_quote_regexes()
+            .iter()
+            .find_map(|post_quote_regex| post_quote_regex.captures(test_string))
+            .unwrap();
+
+        assert_eq!("251260223", captures.get(1).unwrap().as_str());
+    }
+}
+
+#[test]
+fn test_is_known_board() {
+    let chan4 = Chan4 { };
+
+    assert!(chan4.is_known_board("vg"));
+    assert!(chan4.is_known_board("VG"));
+    assert!(!chan4.is_known_board("vgg"));
+}
+
+#[test]
+fn test_find_successor_thread() {
+    let chan4 = Chan4 { };
+    let dead_thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "vg".to_string(), 100);
+
+    let catalog_thread = |thread_no: u64, subject: Option<&str>| {
+        return CatalogThread {
+            thread_no,
+            subject: subject.map(|subject| subject.to_string()),
+            comment: None
+        };
+    };
+
+    let catalog_threads = vec![
+        catalog_thread(90, Some("/agdg/ - Amateur Game Dev General #123")),
+        catalog_thread(101, None),
+        catalog_thread(102, Some("/vrpg/ - Video Games RPG General")),
+        catalog_thread(104, Some("/AGDG/ - Amateur Game Dev General #125")),
+        catalog_thread(103, Some("/agdg/ - Amateur Game Dev General #124")),
+    ];
+
+    let successor = chan4.find_successor_thread(
+        &dead_thread_descriptor,
+        "/agdg/ - Amateur Game Dev General #123",
+        &catalog_threads
+    );
+    assert_eq!(Some(ThreadDescriptor::new("4chan".to_string(), "vg".to_string(), 103)), successor);
+
+    let successor = chan4.find_successor_thread(
+        &dead_thread_descriptor,
+        "/vg/ - Video Game Generals",
+        &catalog_threads
+    );
+    assert!(successor.is_none());
+
+    let successor = chan4.find_successor_thread(&dead_thread_descriptor, "#123", &catalog_threads);
+    assert!(successor.is_none());
+}
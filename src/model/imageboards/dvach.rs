@@ -4,16 +4,17 @@ use regex::{Captures, Regex};
 use url::Url;
 
 use crate::helpers::string_helpers;
-use crate::model::data::chan::{PostDescriptor, SiteDescriptor, ThreadDescriptor};
-use crate::model::imageboards::base_imageboard::{Imageboard, post_url_to_post_descriptor};
+use crate::model::data::chan::{CatalogDescriptor, PostDescriptor, SiteDescriptor, ThreadDescriptor};
+use crate::model::imageboards::base_imageboard::{Imageboard, post_url_to_post_descriptor, thread_url_to_thread_descriptor};
 use crate::model::imageboards::parser::dvach_post_parser::DvachPostParser;
 use crate::model::imageboards::parser::post_parser::PostParser;
 
 lazy_static! {
     static ref POST_URL_REGEX: Regex =
         Regex::new(r"https://(\w+).\w+/(\w+)/res/(\d+).html(?:#(\d+))?").unwrap();
-    static ref POST_REPLY_QUOTE_REGEX: Regex =
-        Regex::new(r##">>>(\d+)\s*</a>"##).unwrap();
+    static ref POST_REPLY_QUOTE_REGEXES: Vec<Regex> = vec![
+        Regex::new(r##">>>(\d+)\s*</a>"##).unwrap()
+    ];
 
     static ref DVACH_POST_PARSER: Box<dyn PostParser + Sync> = Box::new(DvachPostParser {});
 }
@@ -59,6 +60,10 @@ impl Imageboard for Dvach {
         return post_url_to_post_descriptor(self, post_url, &POST_URL_REGEX);
     }
 
+    fn thread_url_to_thread_descriptor(&self, thread_url: &str) -> Option<ThreadDescriptor> {
+        return thread_url_to_thread_descriptor(self, thread_url, &POST_URL_REGEX);
+    }
+
     fn post_descriptor_to_url(&self, post_descriptor: &PostDescriptor) -> Option<String> {
         let mut string_builder = string_builder::Builder::new(72);
 
@@ -83,8 +88,8 @@ impl Imageboard for Dvach {
         return Some(string.unwrap());
     }
 
-    fn post_quote_regex(&self) -> &'static Regex {
-        return &POST_REPLY_QUOTE_REGEX;
+    fn post_quote_regexes(&self) -> &'static [Regex] {
+        return &POST_REPLY_QUOTE_REGEXES;
     }
 
     fn post_parser(&self) -> &'static Box<dyn PostParser + Sync> {
@@ -122,6 +127,19 @@ impl Imageboard for Dvach {
         return Some(endpoint);
     }
 
+    fn catalog_json_endpoint(&self, catalog_descriptor: &CatalogDescriptor) -> Option<String> {
+        if !self.matches(&catalog_descriptor.site_descriptor) {
+            return None;
+        }
+
+        let endpoint = format!("https://2ch.hk/{}/catalog.json", catalog_descriptor.board_code());
+        return Some(endpoint);
+    }
+
+    fn boards_json_endpoint(&self) -> Option<String> {
+        return Some("https://2ch.hk/api/mobile/v2/boards".to_string());
+    }
+
     fn supports_partial_load_head_request(&self) -> bool {
         return false;
     }
@@ -145,6 +163,13 @@ fn test_url_conversion() {
     );
 
     assert!(td1.is_none());
+
+    let td2 = dvach.thread_url_to_thread_descriptor(
+        "https://2ch.hk/test/res/197273.html"
+    ).unwrap();
+
+    assert_eq!("2ch", td2.site_name().as_str());
+    assert_eq!(197273, td2.thread_no);
 }
 
 #[test]
@@ -153,7 +178,7 @@ fn test_post_quote_regex() {
     data-thread=\"197273\" data-num=\"197895\">>>197895</a><br><a href=\"/test/res/197273.html#197896\" \
     class=\"post-reply-link\" data-thread=\"197273\" data-num=\"197896\">>>197896</a><br>test reply 1";
 
-    let captures = POST_REPLY_QUOTE_REGEX.captures_iter(test_string).collect::<Vec<Captures>>();
+    let captures = POST_REPLY_QUOTE_REGEXES[0].captures_iter(test_string).collect::<Vec<Captures>>();
     assert_eq!(2, captures.len());
     assert_eq!("197895", captures.get(0).unwrap().get(1).unwrap().as_str());
     assert_eq!("197896", captures.get(1).unwrap().get(1).unwrap().as_str());
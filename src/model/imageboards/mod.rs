@@ -1,4 +1,4 @@
-mod parser;
+pub mod parser;
 pub mod base_imageboard;
 pub mod chan4;
 pub mod dvach;
\ No newline at end of file
@@ -4,11 +4,16 @@ use serde::Deserialize;
 
 use crate::{error, info};
 use crate::helpers::post_helpers::compare_post_descriptors;
-use crate::model::data::chan::{ChanPost, ChanThread, PostDescriptor, ThreadDescriptor};
+use crate::model::data::chan::{CatalogDescriptor, CatalogThread, ChanBoard, ChanPost, ChanThread, PostDescriptor, ThreadDescriptor};
 use crate::model::imageboards::parser::post_parser::PostParser;
 
 pub enum ThreadParseResult {
     Ok(ChanThread),
+    /// Only the first page of the posts after the last processed post was sent, the rest must be
+    /// loaded starting from the last post of this page.
+    HasMorePages(ChanThread),
+    /// The server reported the thread as archived but still sent its posts.
+    ThreadArchived(ChanThread),
     PartialParseFailed,
     FullParseFailed,
     ThreadDeletedOrClosed,
@@ -29,6 +34,7 @@ struct TailInfo {
     no: u64,
     tail_size: u16,
     tail_id: u64,
+    sub: Option<String>,
     closed: Option<i32>,
     archived: Option<i32>,
 }
@@ -44,6 +50,7 @@ struct TailPost {
 struct Chan4PostFull {
     no: u64,
     resto: u64,
+    sub: Option<String>,
     com: Option<String>,
     closed: Option<i32>,
     archived: Option<i32>,
@@ -54,6 +61,29 @@ struct Chan4ThreadFull {
     posts: Vec<Chan4PostFull>
 }
 
+#[derive(Debug, Deserialize)]
+struct Chan4CatalogThread {
+    no: u64,
+    sub: Option<String>,
+    com: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+struct Chan4CatalogPage {
+    threads: Vec<Chan4CatalogThread>
+}
+
+#[derive(Debug, Deserialize)]
+struct Chan4Board {
+    board: String,
+    title: String
+}
+
+#[derive(Debug, Deserialize)]
+struct Chan4Boards {
+    boards: Vec<Chan4Board>
+}
+
 pub struct Chan4PostParser {}
 
 impl PostParser for Chan4PostParser {
@@ -86,6 +116,60 @@ impl PostParser for Chan4PostParser {
 
         return parse_thread_full(thread_json);
     }
+
+    fn parse_catalog(
+        &self,
+        catalog_descriptor: &CatalogDescriptor,
+        catalog_json: &String
+    ) -> anyhow::Result<Vec<CatalogThread>> {
+        info!(
+            "parse_catalog({}) catalog_json_len: {}",
+            catalog_descriptor,
+            catalog_json.len()
+        );
+
+        return parse_catalog(catalog_json);
+    }
+
+    fn parse_boards(&self, boards_json: &String) -> anyhow::Result<Vec<ChanBoard>> {
+        info!("parse_boards() boards_json_len: {}", boards_json.len());
+
+        return parse_boards(boards_json);
+    }
+}
+
+fn parse_catalog(catalog_json: &String) -> anyhow::Result<Vec<CatalogThread>> {
+    let catalog_pages: Vec<Chan4CatalogPage> = serde_json::from_str(catalog_json)?;
+
+    let catalog_threads = catalog_pages
+        .into_iter()
+        .flat_map(|catalog_page| catalog_page.threads)
+        .map(|catalog_thread| {
+            return CatalogThread {
+                thread_no: catalog_thread.no,
+                subject: catalog_thread.sub,
+                comment: catalog_thread.com
+            };
+        })
+        .collect::<Vec<CatalogThread>>();
+
+    return Ok(catalog_threads);
+}
+
+fn parse_boards(boards_json: &String) -> anyhow::Result<Vec<ChanBoard>> {
+    let chan4_boards = serde_json::from_str::<Chan4Boards>(boards_json)?;
+
+    let boards = chan4_boards.boards
+        .into_iter()
+        .map(|chan4_board| {
+            return ChanBoard {
+                board_code: chan4_board.board,
+                title: chan4_board.title
+            };
+        })
+        .collect::<Vec<ChanBoard>>();
+
+    return Ok(boards);
 }
 
 fn parse_thread_full(thread_json: &String) -> anyhow::Result<ThreadParseResult> {
@@ -93,6 +177,7 @@ fn parse_thread_full(thread_json: &String) -> anyhow::Result<ThreadParseResult>
 
     let mut archived = false;
     let mut closed = false;
+    let mut subject: Option<String> = None;
 
     let chan4_thread_full: Chan4ThreadFull = serde_json::from_str(thread_json)?;
 
@@ -100,6 +185,7 @@ fn parse_thread_full(thread_json: &String) -> anyhow::Result<ThreadParseResult>
         if index == 0 {
             archived = chan4_post_full.archived.unwrap_or(0) == 1;
             closed = chan4_post_full.closed.unwrap_or(0) == 1;
+            subject = chan4_post_full.sub.clone();
         }
 
         let chan_post = ChanPost {
@@ -114,6 +200,7 @@ fn parse_thread_full(thread_json: &String) -> anyhow::Result<ThreadParseResult>
     let chan_thread = ChanThread {
         archived: archived,
         closed: closed,
+        subject: subject,
         posts: result_posts
     };
 
@@ -129,7 +216,9 @@ fn parse_thread_partial(
 
     let mut archived = false;
     let mut closed = false;
+    let mut subject: Option<String> = None;
     let mut op_post_found = false;
+    let mut tail_size: u16 = 0;
 
     let last_processed_post = last_processed_post.clone().unwrap();
     let parsed_data: serde_json::Value = serde_json::from_str(thread_json)?;
@@ -168,6 +257,8 @@ fn parse_thread_partial(
 
                 archived = tail_info.archived.unwrap_or(0) == 1;
                 closed = tail_info.closed.unwrap_or(0) == 1;
+                subject = tail_info.sub;
+                tail_size = tail_info.tail_size;
             }
             Chan4PostPartial::TailPost(tail_post) => {
                 if !op_post_found {
@@ -191,11 +282,214 @@ fn parse_thread_partial(
         return Ok(ThreadParseResult::PartialParseFailed);
     }
 
+    if !tail_covers_last_processed_post(&last_processed_post, tail_size, &result_posts) {
+        info!(
+            "parse_thread_partial({}) tail (tail_size: {}, posts: {}) does not reach last_processed_post ({}). \
+            Switching to full thread load.",
+            thread_descriptor,
+            tail_size,
+            result_posts.len(),
+            last_processed_post
+        );
+        return Ok(ThreadParseResult::PartialParseFailed);
+    }
+
     let chan_thread = ChanThread {
         archived: archived,
         closed: closed,
+        subject: subject,
         posts: result_posts
     };
 
     return Ok(ThreadParseResult::Ok(chan_thread));
-}
\ No newline at end of file
+}
+
+/// tail_id only tells where the tail window is supposed to start. When we got fewer posts than
+/// tail_size or the oldest post we got is newer than last_processed_post (e.g. a stale tail file
+/// served by a CDN) then the posts in between would be silently missed.
+fn tail_covers_last_processed_post(
+    last_processed_post: &PostDescriptor,
+    tail_size: u16,
+    tail_posts: &Vec<ChanPost>
+) -> bool {
+    if tail_posts.len() < tail_size as usize {
+        return false;
+    }
+
+    let oldest_tail_post_no = tail_posts.iter()
+        .map(|tail_post| tail_post.post_no)
+        .min();
+
+    if oldest_tail_post_no.is_none() {
+        return true;
+    }
+
+    return oldest_tail_post_no.unwrap() <= last_processed_post.post_no;
+}
+
+#[test]
+fn test_parse_thread_full_subject() {
+    let thread_json = r#"
+        {
+            "posts": [
+                { "no": 1, "resto": 0, "sub": "Thread subject", "com": "OP comment", "closed": 0 },
+                { "no": 2, "resto": 1, "com": "<a href=\"#p1\" class=\"quotelink\">&gt;&gt;1</a>" }
+            ]
+        }
+    "#.to_string();
+
+    let result = parse_thread_full(&thread_json).unwrap();
+    let chan_thread = match result {
+        ThreadParseResult::Ok(chan_thread) => chan_thread,
+        _ => panic!("Unexpected parse result")
+    };
+
+    assert_eq!(Some("Thread subject".to_string()), chan_thread.subject);
+    assert_eq!(2, chan_thread.posts.len());
+}
+
+#[test]
+fn test_parse_thread_partial_subject() {
+    let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+    let last_processed_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 3, 0);
+
+    let thread_json = r#"
+        {
+            "posts": [
+                { "no": 1, "resto": 0, "sub": "Thread subject", "tail_size": 2, "tail_id": 3 },
+                { "no": 3, "resto": 1, "com": "Old post" },
+                { "no": 4, "resto": 1, "com": "New post" }
+            ]
+        }
+    "#.to_string();
+
+    let result = parse_thread_partial(
+        &thread_descriptor,
+        &Some(last_processed_post),
+        &thread_json
+    ).unwrap();
+
+    let chan_thread = match result {
+        ThreadParseResult::Ok(chan_thread) => chan_thread,
+        _ => panic!("Unexpected parse result")
+    };
+
+    assert_eq!(Some("Thread subject".to_string()), chan_thread.subject);
+    assert_eq!(2, chan_thread.posts.len());
+}
+
+#[test]
+fn test_parse_thread_partial_without_subject() {
+    let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+    let last_processed_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 3, 0);
+
+    let thread_json = r#"
+        {
+            "posts": [
+                { "no": 1, "resto": 0, "tail_size": 1, "tail_id": 3 },
+                { "no": 3, "resto": 1, "com": "Old post" }
+            ]
+        }
+    "#.to_string();
+
+    let result = parse_thread_partial(
+        &thread_descriptor,
+        &Some(last_processed_post),
+        &thread_json
+    ).unwrap();
+
+    let chan_thread = match result {
+        ThreadParseResult::Ok(chan_thread) => chan_thread,
+        _ => panic!("Unexpected parse result")
+    };
+
+    assert!(chan_thread.subject.is_none());
+}
+
+#[test]
+fn test_parse_catalog() {
+    let catalog_json = r#"
+        [
+            {
+                "page": 1,
+                "threads": [
+                    { "no": 100, "sub": "/vg/ general", "com": "OP comment" },
+                    { "no": 101, "com": "No subject" }
+                ]
+            },
+            {
+                "page": 2,
+                "threads": [
+                    { "no": 50, "sub": "Old thread" }
+                ]
+            }
+        ]
+    "#.to_string();
+
+    let catalog_threads = parse_catalog(&catalog_json).unwrap();
+    assert_eq!(3, catalog_threads.len());
+
+    assert_eq!(100, catalog_threads[0].thread_no);
+    assert_eq!(Some("/vg/ general".to_string()), catalog_threads[0].subject);
+    assert_eq!(101, catalog_threads[1].thread_no);
+    assert_eq!(None, catalog_threads[1].subject);
+    assert_eq!(50, catalog_threads[2].thread_no);
+}
+
+#[test]
+fn test_parse_boards() {
+    let boards_json = r#"
+        {
+            "boards": [
+                { "board": "a", "title": "Anime & Manga", "ws_board": 1 },
+                { "board": "vg", "title": "Video Game Generals", "ws_board": 1 }
+            ]
+        }
+    "#.to_string();
+
+    let boards = parse_boards(&boards_json).unwrap();
+    assert_eq!(2, boards.len());
+
+    assert_eq!("a", boards[0].board_code);
+    assert_eq!("Anime & Manga", boards[0].title);
+    assert_eq!("vg", boards[1].board_code);
+    assert_eq!("Video Game Generals", boards[1].title);
+}
+
+#[test]
+fn test_parse_thread_partial_fails_when_tail_does_not_reach_last_processed_post() {
+    let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+    let last_processed_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 3, 0);
+
+    // tail_id claims the window starts at the last processed post but posts 3 and 4 are missing
+    let stale_tail_json = r#"
+        {
+            "posts": [
+                { "no": 1, "resto": 0, "tail_size": 2, "tail_id": 3 },
+                { "no": 5, "resto": 1, "com": "New post" },
+                { "no": 6, "resto": 1, "com": "New post" }
+            ]
+        }
+    "#.to_string();
+
+    // Fewer posts than tail_size
+    let truncated_tail_json = r#"
+        {
+            "posts": [
+                { "no": 1, "resto": 0, "tail_size": 3, "tail_id": 3 },
+                { "no": 3, "resto": 1, "com": "Old post" },
+                { "no": 4, "resto": 1, "com": "New post" }
+            ]
+        }
+    "#.to_string();
+
+    for thread_json in [stale_tail_json, truncated_tail_json] {
+        let result = parse_thread_partial(
+            &thread_descriptor,
+            &Some(last_processed_post.clone()),
+            &thread_json
+        ).unwrap();
+
+        assert!(matches!(result, ThreadParseResult::PartialParseFailed));
+    }
+}
@@ -1,7 +1,7 @@
 use serde::Deserialize;
 
 use crate::{error, info};
-use crate::model::data::chan::{ChanPost, ChanThread, PostDescriptor, ThreadDescriptor};
+use crate::model::data::chan::{CatalogDescriptor, CatalogThread, ChanBoard, ChanPost, ChanThread, PostDescriptor, ThreadDescriptor};
 use crate::model::imageboards::parser::chan4_post_parser::ThreadParseResult;
 use crate::model::imageboards::parser::post_parser::PostParser;
 
@@ -10,6 +10,7 @@ struct DvachPost {
     num: u64,
     op: u64,
     closed: Option<i32>,
+    subject: Option<String>,
     comment: Option<String>
 }
 
@@ -22,12 +23,35 @@ struct DvachError {
 #[derive(Debug, Deserialize)]
 struct DvachThread {
     posts: Option<Vec<DvachPost>>,
-    error: Option<DvachError>
+    error: Option<DvachError>,
+    // Set by the mobile v2 "after" endpoint when the response was capped and there are more posts
+    #[serde(default)]
+    has_more: bool
 }
 
 #[derive(Debug, Deserialize)]
 struct DvachThreads {
-    threads: Vec<DvachThread>
+    #[serde(default)]
+    threads: Vec<DvachThread>,
+    error: Option<DvachError>
+}
+
+#[derive(Debug, Deserialize)]
+struct DvachCatalogThread {
+    num: u64,
+    subject: Option<String>,
+    comment: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+struct DvachCatalog {
+    threads: Vec<DvachCatalogThread>
+}
+
+#[derive(Debug, Deserialize)]
+struct DvachBoard {
+    id: String,
+    name: String
 }
 
 pub struct DvachPostParser {}
@@ -35,7 +59,14 @@ pub struct DvachPostParser {}
 impl DvachError {
     fn is_thread_deleted_or_closed(&self) -> bool {
         return match self.code {
-            -2 | -3 | -41 | -7 => true,
+            -2 | -3 | -7 => true,
+            _ => false
+        }
+    }
+
+    fn is_thread_archived(&self) -> bool {
+        return match self.code {
+            -41 => true,
             _ => false
         }
     }
@@ -55,7 +86,6 @@ impl PostParser for DvachPostParser {
         last_processed_post: &Option<PostDescriptor>,
         thread_json: &String
     ) -> anyhow::Result<ThreadParseResult> {
-        // TODO: '{"error":{"code":-3,"message":"Тред не существует."},"result":0}'
         if last_processed_post.is_some() {
             info!(
                 "parse({}) parsing thread partially last_processed_post: {}, thread_json_len: {}",
@@ -81,6 +111,59 @@ impl PostParser for DvachPostParser {
             thread_json
         );
     }
+
+    fn parse_catalog(
+        &self,
+        catalog_descriptor: &CatalogDescriptor,
+        catalog_json: &String
+    ) -> anyhow::Result<Vec<CatalogThread>> {
+        info!(
+            "parse_catalog({}) catalog_json_len: {}",
+            catalog_descriptor,
+            catalog_json.len()
+        );
+
+        return parse_catalog(catalog_json);
+    }
+
+    fn parse_boards(&self, boards_json: &String) -> anyhow::Result<Vec<ChanBoard>> {
+        info!("parse_boards() boards_json_len: {}", boards_json.len());
+
+        return parse_boards(boards_json);
+    }
+}
+
+fn parse_catalog(catalog_json: &String) -> anyhow::Result<Vec<CatalogThread>> {
+    let dvach_catalog = serde_json::from_str::<DvachCatalog>(catalog_json)?;
+
+    let catalog_threads = dvach_catalog.threads
+        .into_iter()
+        .map(|catalog_thread| {
+            return CatalogThread {
+                thread_no: catalog_thread.num,
+                subject: catalog_thread.subject.filter(|subject| !subject.is_empty()),
+                comment: catalog_thread.comment
+            };
+        })
+        .collect::<Vec<CatalogThread>>();
+
+    return Ok(catalog_threads);
+}
+
+fn parse_boards(boards_json: &String) -> anyhow::Result<Vec<ChanBoard>> {
+    let dvach_boards = serde_json::from_str::<Vec<DvachBoard>>(boards_json)?;
+
+    let boards = dvach_boards
+        .into_iter()
+        .map(|dvach_board| {
+            return ChanBoard {
+                board_code: dvach_board.id,
+                title: dvach_board.name
+            };
+        })
+        .collect::<Vec<ChanBoard>>();
+
+    return Ok(boards);
 }
 
 fn parse_thread_partial(
@@ -88,7 +171,16 @@ fn parse_thread_partial(
     thread_json: &String
 ) -> anyhow::Result<ThreadParseResult> {
     let dvach_thread = serde_json::from_str::<DvachThread>(thread_json)?;
-    return parse_shared(thread_descriptor, &dvach_thread);
+    let thread_parse_result = parse_shared(thread_descriptor, dvach_thread.error, dvach_thread.posts.as_ref())?;
+
+    if !dvach_thread.has_more {
+        return Ok(thread_parse_result);
+    }
+
+    return match thread_parse_result {
+        ThreadParseResult::Ok(chan_thread) => Ok(ThreadParseResult::HasMorePages(chan_thread)),
+        thread_parse_result => Ok(thread_parse_result)
+    };
 }
 
 fn parse_thread_full(
@@ -96,20 +188,29 @@ fn parse_thread_full(
     thread_json: &String
 ) -> anyhow::Result<ThreadParseResult> {
     let dvach_threads = serde_json::from_str::<DvachThreads>(thread_json)?;
-    if dvach_threads.threads.is_empty() {
+
+    // Errors are sent either at the top level ('{"error":{"code":-3,"message":"..."},"result":0}')
+    // or inside of the thread object.
+    let dvach_thread = dvach_threads.threads.first();
+    if dvach_threads.error.is_some() {
+        let posts = dvach_thread.and_then(|dvach_thread| dvach_thread.posts.as_ref());
+        return parse_shared(thread_descriptor, dvach_threads.error.clone(), posts);
+    }
+
+    if dvach_thread.is_none() {
         error!("parse_thread_full({}) DvachThreads has no threads", thread_descriptor);
         return Ok(ThreadParseResult::FullParseFailed);
     }
 
-    let dvach_thread = dvach_threads.threads.first().unwrap();
-    return parse_shared(thread_descriptor, &dvach_thread);
+    let dvach_thread = dvach_thread.unwrap();
+    return parse_shared(thread_descriptor, dvach_thread.error.clone(), dvach_thread.posts.as_ref());
 }
 
 fn parse_shared(
     thread_descriptor: &ThreadDescriptor,
-    dvach_thread: &DvachThread
+    error: Option<DvachError>,
+    posts: Option<&Vec<DvachPost>>
 ) -> anyhow::Result<ThreadParseResult> {
-    let error = dvach_thread.error.clone();
     if error.is_some() {
         let error = error.unwrap();
         error!(
@@ -119,6 +220,20 @@ fn parse_shared(
             error.message
         );
 
+        if error.is_thread_archived() {
+            // Same as with archived 4chan threads, there may still be replies that we haven't seen
+            // yet so if the posts were sent along with the error they get processed one last time.
+            let posts = posts.filter(|posts| !posts.is_empty());
+            if posts.is_none() {
+                return Ok(ThreadParseResult::ThreadDeletedOrClosed);
+            }
+
+            let mut chan_thread = dvach_posts_to_chan_thread(posts.unwrap());
+            chan_thread.archived = true;
+
+            return Ok(ThreadParseResult::ThreadArchived(chan_thread));
+        }
+
         if error.is_thread_deleted_or_closed() {
             return Ok(ThreadParseResult::ThreadDeletedOrClosed);
         }
@@ -130,7 +245,6 @@ fn parse_shared(
         return Ok(ThreadParseResult::ServerError(error.code, error.message));
     }
 
-    let posts = &dvach_thread.posts;
     if posts.is_none() {
         error!(
             "parse_shared({}) Server didn't send \"posts\" json",
@@ -141,15 +255,18 @@ fn parse_shared(
         return Ok(ThreadParseResult::ServerSentIncorrectData(message));
     }
 
-    let posts: &Vec<DvachPost> = posts.as_ref().unwrap();
-
-    let original_post = posts.first();
-    if original_post.is_none() {
+    let posts: &Vec<DvachPost> = posts.unwrap();
+    if posts.is_empty() {
         error!("parse_shared({}) DvachThread has no posts", thread_descriptor);
         return Ok(ThreadParseResult::FullParseFailed);
     }
 
-    let original_post = original_post.unwrap();
+    return Ok(ThreadParseResult::Ok(dvach_posts_to_chan_thread(posts)));
+}
+
+/// [posts] must not be empty, the first post is the original post.
+fn dvach_posts_to_chan_thread(posts: &Vec<DvachPost>) -> ChanThread {
+    let original_post = posts.first().unwrap();
     let mut chan_posts = Vec::<ChanPost>::with_capacity(posts.len());
 
     for chan4_post in posts {
@@ -166,7 +283,158 @@ fn parse_shared(
         posts: chan_posts,
         closed: original_post.closed.unwrap_or(0) == 1,
         archived: false,
+        subject: original_post.subject.clone().filter(|subject| !subject.is_empty()),
+    };
+
+    return chan_thread;
+}
+
+#[test]
+fn test_parse_thread_full_subject() {
+    let thread_descriptor = ThreadDescriptor::new("2ch".to_string(), "b".to_string(), 1);
+
+    let thread_json = r#"
+        {
+            "threads": [
+                {
+                    "posts": [
+                        { "num": 1, "op": 1, "closed": 0, "subject": "Thread subject", "comment": "OP comment" },
+                        { "num": 2, "op": 0, "comment": "Reply" }
+                    ]
+                }
+            ]
+        }
+    "#.to_string();
+
+    let result = parse_thread_full(&thread_descriptor, &thread_json).unwrap();
+    let chan_thread = match result {
+        ThreadParseResult::Ok(chan_thread) => chan_thread,
+        _ => panic!("Unexpected parse result")
     };
 
-    return Ok(ThreadParseResult::Ok(chan_thread));
-}
\ No newline at end of file
+    assert_eq!(Some("Thread subject".to_string()), chan_thread.subject);
+    assert_eq!(2, chan_thread.posts.len());
+}
+
+#[test]
+fn test_parse_thread_partial_subject() {
+    let thread_descriptor = ThreadDescriptor::new("2ch".to_string(), "b".to_string(), 1);
+
+    let thread_json = r#"
+        {
+            "posts": [
+                { "num": 1, "op": 1, "subject": "Thread subject", "comment": "OP comment" },
+                { "num": 5, "op": 0, "comment": "Reply" }
+            ]
+        }
+    "#.to_string();
+
+    let result = parse_thread_partial(&thread_descriptor, &thread_json).unwrap();
+    let chan_thread = match result {
+        ThreadParseResult::Ok(chan_thread) => chan_thread,
+        _ => panic!("Unexpected parse result")
+    };
+
+    assert_eq!(Some("Thread subject".to_string()), chan_thread.subject);
+}
+
+#[test]
+fn test_parse_thread_partial_has_more_pages() {
+    let thread_descriptor = ThreadDescriptor::new("2ch".to_string(), "b".to_string(), 1);
+
+    let thread_json = r#"
+        {
+            "posts": [
+                { "num": 5, "op": 0, "comment": "Reply" },
+                { "num": 6, "op": 0, "comment": "Reply" }
+            ],
+            "has_more": true
+        }
+    "#.to_string();
+
+    let result = parse_thread_partial(&thread_descriptor, &thread_json).unwrap();
+    let chan_thread = match result {
+        ThreadParseResult::HasMorePages(chan_thread) => chan_thread,
+        _ => panic!("Unexpected parse result")
+    };
+
+    assert_eq!(2, chan_thread.posts.len());
+}
+
+#[test]
+fn test_parse_archived_thread_still_returns_posts() {
+    let thread_descriptor = ThreadDescriptor::new("2ch".to_string(), "b".to_string(), 1);
+
+    let thread_json = r#"
+        {
+            "error": { "code": -41, "message": "Тред в архиве." },
+            "threads": [
+                {
+                    "posts": [
+                        { "num": 1, "op": 1, "subject": "Thread subject", "comment": "OP comment" },
+                        { "num": 2, "op": 0, "comment": "Last reply" }
+                    ]
+                }
+            ]
+        }
+    "#.to_string();
+
+    let result = parse_thread_full(&thread_descriptor, &thread_json).unwrap();
+    let chan_thread = match result {
+        ThreadParseResult::ThreadArchived(chan_thread) => chan_thread,
+        _ => panic!("Unexpected parse result")
+    };
+
+    assert!(chan_thread.archived);
+    assert!(chan_thread.is_not_active());
+    assert_eq!(2, chan_thread.posts.len());
+    assert_eq!(Some("Last reply".to_string()), chan_thread.posts[1].comment_unparsed);
+
+    let thread_json = r#"{ "error": { "code": -41, "message": "Тред в архиве." }, "result": 0 }"#.to_string();
+
+    let result = parse_thread_partial(&thread_descriptor, &thread_json).unwrap();
+    assert!(matches!(result, ThreadParseResult::ThreadDeletedOrClosed));
+
+    let thread_json = r#"{ "error": { "code": -3, "message": "Тред не существует." }, "result": 0 }"#.to_string();
+
+    let result = parse_thread_full(&thread_descriptor, &thread_json).unwrap();
+    assert!(matches!(result, ThreadParseResult::ThreadDeletedOrClosed));
+}
+
+#[test]
+fn test_parse_catalog() {
+    let catalog_json = r#"
+        {
+            "threads": [
+                { "num": 100, "subject": "Thread subject", "comment": "OP comment" },
+                { "num": 101, "subject": "", "comment": "No subject" }
+            ]
+        }
+    "#.to_string();
+
+    let catalog_threads = parse_catalog(&catalog_json).unwrap();
+    assert_eq!(2, catalog_threads.len());
+
+    assert_eq!(100, catalog_threads[0].thread_no);
+    assert_eq!(Some("Thread subject".to_string()), catalog_threads[0].subject);
+    assert_eq!(101, catalog_threads[1].thread_no);
+    assert_eq!(None, catalog_threads[1].subject);
+}
+
+#[test]
+fn test_parse_boards() {
+    let boards_json = r#"
+        [
+            { "id": "b", "name": "Бред", "category": "Разное" },
+            { "id": "vg", "name": "Video Games General", "category": "Игры" }
+        ]
+    "#.to_string();
+
+    let boards = parse_boards(&boards_json).unwrap();
+    assert_eq!(2, boards.len());
+
+    assert_eq!("b", boards[0].board_code);
+    assert_eq!("Бред", boards[0].title);
+    assert_eq!("vg", boards[1].board_code);
+    assert_eq!("Video Games General", boards[1].title);
+}
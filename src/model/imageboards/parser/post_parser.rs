@@ -1,4 +1,4 @@
-use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
+use crate::model::data::chan::{CatalogDescriptor, ChanBoard, CatalogThread, PostDescriptor, ThreadDescriptor};
 use crate::model::imageboards::parser::chan4_post_parser::ThreadParseResult;
 
 pub trait PostParser {
@@ -8,4 +8,12 @@ pub trait PostParser {
         last_processed_post: &Option<PostDescriptor>,
         thread_json: &String
     ) -> anyhow::Result<ThreadParseResult>;
+
+    fn parse_catalog(
+        &self,
+        catalog_descriptor: &CatalogDescriptor,
+        catalog_json: &String
+    ) -> anyhow::Result<Vec<CatalogThread>>;
+
+    fn parse_boards(&self, boards_json: &String) -> anyhow::Result<Vec<ChanBoard>>;
 }
\ No newline at end of file
@@ -1,12 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use anyhow::{anyhow, Context};
 use chrono::{DateTime, Utc};
 use lazy_static::lazy_static;
 use tokio::sync::{Mutex, RwLock};
-use tokio_postgres::Row;
+use tokio_postgres::{Row, Transaction};
+use url::{Host, Url};
 
 use crate::{constants, info, warn};
 use crate::helpers::db_helpers;
@@ -14,6 +17,8 @@ use crate::helpers::hashers::Sha512Hashable;
 use crate::helpers::string_helpers::FormatToken;
 use crate::model::database::db::Database;
 
+static USER_ID_HASH_ITERATIONS: AtomicUsize = AtomicUsize::new(constants::DEFAULT_USER_ID_HASH_ITERATIONS);
+
 lazy_static! {
     static ref ACCOUNTS_CACHE: RwLock<HashMap<AccountId, Arc<Mutex<Account>>>> =
         RwLock::new(HashMap::with_capacity(1024));
@@ -24,7 +29,8 @@ pub struct Account {
     pub id: i64,
     pub account_id: AccountId,
     pub tokens: Vec<AccountToken>,
-    pub valid_until: Option<DateTime<Utc>>
+    pub valid_until: Option<DateTime<Utc>>,
+    pub banned_until: Option<DateTime<Utc>>
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -69,6 +75,8 @@ pub enum ApplicationType {
     Unknown = -1,
     KurobaExLiteDebug = 0,
     KurobaExLiteProduction = 1,
+    KurobaExDebug = 2,
+    KurobaExProduction = 3,
 }
 
 impl Display for ApplicationType {
@@ -80,6 +88,12 @@ impl Display for ApplicationType {
             ApplicationType::KurobaExLiteProduction => {
                 write!(f, "KurobaExLiteProduction")?;
             }
+            ApplicationType::KurobaExDebug => {
+                write!(f, "KurobaExDebug")?;
+            }
+            ApplicationType::KurobaExProduction => {
+                write!(f, "KurobaExProduction")?;
+            }
             ApplicationType::Unknown => {
                 write!(f, "Unknown")?;
             }
@@ -94,17 +108,32 @@ impl ApplicationType {
         let application_type = match value {
             0 => ApplicationType::KurobaExLiteDebug,
             1 => ApplicationType::KurobaExLiteProduction,
+            2 => ApplicationType::KurobaExDebug,
+            3 => ApplicationType::KurobaExProduction,
             _ => ApplicationType::Unknown
         };
 
         return application_type;
     }
+
+    /// Every application type except Unknown.
+    pub fn supported() -> Vec<ApplicationType> {
+        return vec![
+            ApplicationType::KurobaExLiteDebug,
+            ApplicationType::KurobaExLiteProduction,
+            ApplicationType::KurobaExDebug,
+            ApplicationType::KurobaExProduction
+        ];
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum TokenType {
     Unknown = -1,
-    Firebase = 0
+    Firebase = 0,
+    /// The token is the push service endpoint of a browser, the keys needed to encrypt the
+    /// messages are stored in web_push_subscriptions.
+    WebPush = 2
 }
 
 impl Display for TokenType {
@@ -113,6 +142,9 @@ impl Display for TokenType {
             TokenType::Firebase => {
                 write!(f, "Firebase")?;
             }
+            TokenType::WebPush => {
+                write!(f, "WebPush")?;
+            }
             TokenType::Unknown => {
                 write!(f, "Unknown")?;
             }
@@ -126,6 +158,7 @@ impl TokenType {
     pub fn from_i64(value: i64) -> TokenType {
         let token_type = match value {
             0 => TokenType::Firebase,
+            2 => TokenType::WebPush,
             _ => TokenType::Unknown
         };
 
@@ -134,22 +167,19 @@ impl TokenType {
 }
 
 impl Account {
-    pub fn get_account_token(
+    pub fn get_account_tokens(
         &self,
         application_type: &ApplicationType
-    ) -> Option<&AccountToken> {
-        for token in &self.tokens {
-            if token.application_type == *application_type {
-                return Some(token);
-            }
-        }
-
-        return None;
+    ) -> Vec<&AccountToken> {
+        return self.tokens
+            .iter()
+            .filter(|token| token.application_type == *application_type)
+            .collect::<Vec<&AccountToken>>();
     }
 
     pub fn is_valid(&self, application_type: &ApplicationType) -> bool {
-        let token = &self.get_account_token(application_type);
-        if token.is_none() {
+        let tokens = self.get_account_tokens(application_type);
+        if tokens.is_empty() {
             return false;
         }
 
@@ -164,9 +194,18 @@ impl Account {
         return valid_until >= now;
     }
 
+    /// Banned accounts can't create new watches and don't receive notifications until the ban ends.
+    pub fn is_banned(&self) -> bool {
+        if self.banned_until.is_none() {
+            return false;
+        }
+
+        return self.banned_until.unwrap() > chrono::Utc::now();
+    }
+
     pub fn validation_status(&self, application_type: &ApplicationType) -> Option<String> {
-        let token = &self.get_account_token(application_type);
-        if token.is_none() {
+        let tokens = self.get_account_tokens(application_type);
+        if tokens.is_empty() {
             return Some(format!("token for app_type \'{}\' is not set", application_type));
         }
 
@@ -200,7 +239,7 @@ impl Account {
     pub fn add_or_update_token(&mut self, new_token: AccountToken) {
         for (index, old_token) in self.tokens.iter().enumerate() {
             if old_token.token == new_token.token {
-                let mut updated_token = self.tokens[index].clone();
+                let updated_token = &mut self.tokens[index];
                 updated_token.token_type = new_token.token_type;
                 updated_token.application_type = new_token.application_type;
                 return;
@@ -210,8 +249,23 @@ impl Account {
         self.tokens.push(new_token)
     }
 
+    pub fn remove_token(&mut self, token: &str) {
+        self.tokens.retain(|account_token| account_token.token != token);
+    }
+
+    /// Removes all tokens of [application_type] or only [token] when it's set.
+    pub fn remove_application_tokens(&mut self, application_type: &ApplicationType, token: Option<&str>) {
+        self.tokens.retain(|account_token| {
+            if account_token.application_type != *application_type {
+                return true;
+            }
+
+            return token.is_some() && account_token.token != token.unwrap();
+        });
+    }
+
     pub fn account_token(&self, application_type: &ApplicationType) -> Option<&AccountToken> {
-        return self.get_account_token(application_type);
+        return self.get_account_tokens(application_type).first().cloned();
     }
 
     pub fn new(
@@ -224,7 +278,8 @@ impl Account {
             id,
             account_id,
             tokens,
-            valid_until
+            valid_until,
+            banned_until: None
         }
     }
 
@@ -232,12 +287,14 @@ impl Account {
         let id: i64 = row.try_get(0)?;
         let account_id: String = row.try_get(1)?;
         let valid_until: Option<DateTime<Utc>> = row.try_get(2)?;
+        let banned_until: Option<DateTime<Utc>> = row.try_get(3)?;
 
         let account = Account {
             id,
-            account_id: AccountId::new(account_id),
+            account_id: AccountId::new(account_id)?,
             tokens: Vec::with_capacity(4),
-            valid_until
+            valid_until,
+            banned_until
         };
 
         return Ok(account);
@@ -254,44 +311,157 @@ pub struct FirebaseToken {
     pub token: String
 }
 
+/// What the browser returns from PushManager.subscribe(), the keys are base64url encoded.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct WebPushSubscription {
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String
+}
+
 #[derive(Eq, PartialEq)]
 pub enum CreateAccountResult {
     Ok,
-    AccountAlreadyExists
+    AccountAlreadyExists,
+    AccountIsBanned
 }
 
 #[derive(Eq, PartialEq)]
-pub enum UpdateAccountExpiryDateResult {
+pub enum BanAccountResult {
     Ok,
     AccountDoesNotExist
 }
 
+#[derive(Eq, PartialEq)]
+pub enum UpdateAccountExpiryDateResult {
+    Ok(DateTime<Utc>),
+    AccountDoesNotExist
+}
+
 #[derive(Eq, PartialEq)]
 pub enum UpdateFirebaseTokenResult {
     Ok,
     AccountDoesNotExist
 }
 
-impl AccountId {
-    pub fn new(account_id: String) -> AccountId {
-        if account_id.len() != 128 {
-            panic!("Bad account_id len {}", account_id.len());
-        }
+#[derive(Eq, PartialEq)]
+pub enum UpdateWebPushSubscriptionResult {
+    Ok,
+    AccountDoesNotExist
+}
+
+#[derive(Eq, PartialEq)]
+pub enum RemoveFirebaseTokenResult {
+    Ok,
+    AccountDoesNotExist
+}
+
+#[derive(Eq, PartialEq)]
+pub enum UpdateQuietHoursResult {
+    Ok,
+    AccountDoesNotExist
+}
+
+/// [start] and [end] are minutes since midnight (0..1440) in the account's local time which is
+/// UTC + [tz_offset_minutes]. The window may wrap around midnight (e.g. 22:00 - 07:00).
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct QuietHours {
+    pub start: i16,
+    pub end: i16,
+    pub tz_offset_minutes: i16
+}
+
+pub struct AccountSummary {
+    pub account_id: AccountId,
+    pub valid_until: Option<DateTime<Utc>>,
+    pub tokens_count: Vec<(ApplicationType, i64)>,
+    pub watches_count: i64
+}
+
+#[derive(Eq, PartialEq)]
+pub enum DeleteAccountResult {
+    Ok(u64),
+    AccountDoesNotExist
+}
+
+/// Changing it changes the account_id of every user_id so existing accounts become unreachable.
+pub fn set_user_id_hash_iterations(iterations: usize) {
+    USER_ID_HASH_ITERATIONS.store(iterations, Ordering::Relaxed);
+}
+
+pub fn user_id_hash_iterations() -> usize {
+    return USER_ID_HASH_ITERATIONS.load(Ordering::Relaxed);
+}
 
-        return AccountId { id: account_id };
+pub fn parse_user_id_hash_iterations(value: Option<&str>) -> anyhow::Result<usize> {
+    if value.is_none() {
+        return Ok(constants::DEFAULT_USER_ID_HASH_ITERATIONS);
+    }
+
+    let value = value.unwrap().trim();
+
+    let iterations = usize::from_str(value)
+        .with_context(|| format!("Failed to parse USER_ID_HASH_ITERATIONS \'{}\'", value))?;
+
+    if iterations == 0 || iterations > constants::MAX_USER_ID_HASH_ITERATIONS {
+        return Err(
+            anyhow!("USER_ID_HASH_ITERATIONS must be in range 1..={}", constants::MAX_USER_ID_HASH_ITERATIONS)
+        );
+    }
+
+    return Ok(iterations);
+}
+
+pub fn validate_user_id(user_id: &str) -> anyhow::Result<()> {
+    if user_id.len() < constants::MIN_USER_ID_LENGTH || user_id.len() > constants::MAX_USER_ID_LENGTH {
+        return Err(
+            anyhow!(
+                "Bad user_id length {} must be within {}..{} symbols",
+                user_id.len(),
+                constants::MIN_USER_ID_LENGTH,
+                constants::MAX_USER_ID_LENGTH
+            )
+        );
+    }
+
+    return Ok(());
+}
+
+pub fn validate_account_id(account_id: &str) -> anyhow::Result<()> {
+    if account_id.len() != constants::ACCOUNT_ID_LENGTH {
+        return Err(
+            anyhow!(
+                "Bad account_id length {} must be {} symbols",
+                account_id.len(),
+                constants::ACCOUNT_ID_LENGTH
+            )
+        );
+    }
+
+    if !account_id.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        return Err(anyhow!("Bad account_id, must only contain hex digits"));
+    }
+
+    return Ok(());
+}
+
+impl AccountId {
+    pub fn new(account_id: String) -> anyhow::Result<AccountId> {
+        validate_account_id(&account_id)?;
+        return Ok(AccountId { id: account_id });
     }
 
     pub fn from_user_id(user_id: &str) -> anyhow::Result<AccountId> {
-        if user_id.len() < 32 || user_id.len() > 128 {
-            return Err(anyhow!("Bad user_id length {} must be within 32..128 symbols", user_id.len()));
-        }
+        validate_user_id(user_id)?;
 
-        let account_id = AccountId { id: user_id.sha3_512(constants::USER_ID_HASH_ITERATIONS) };
+        let account_id = AccountId { id: user_id.sha3_512(user_id_hash_iterations()) };
         return Ok(account_id);
     }
 
+    /// Skips the user_id validation so that tests can use any user_id.
+    #[cfg(test)]
     pub fn test_unsafe(user_id: &str) -> anyhow::Result<AccountId> {
-        let account_id = AccountId { id: user_id.sha3_512(constants::USER_ID_HASH_ITERATIONS) };
+        let account_id = AccountId { id: user_id.sha3_512(user_id_hash_iterations()) };
         return Ok(account_id);
     }
 }
@@ -323,6 +493,50 @@ impl FirebaseToken {
     }
 }
 
+impl WebPushSubscription {
+    pub fn new(endpoint: &str, p256dh: &str, auth: &str) -> anyhow::Result<WebPushSubscription> {
+        if endpoint.len() == 0 || endpoint.len() > 1024 {
+            return Err(anyhow!("Bad endpoint length {} must be within 1..1024", endpoint.len()));
+        }
+
+        let endpoint_url = Url::parse(endpoint)
+            .with_context(|| { return format!("Bad endpoint \'{}\'", endpoint); })?;
+
+        // The server sends requests to the endpoint so it must not be usable to reach the hosts
+        // of the server's own network. Push services are never addressed by ip.
+        if endpoint_url.scheme() != "https" {
+            return Err(anyhow!("Bad endpoint scheme \'{}\'", endpoint_url.scheme()));
+        }
+
+        let endpoint_host = match endpoint_url.host() {
+            Some(Host::Domain(domain)) => domain.trim_end_matches('.').to_lowercase(),
+            Some(Host::Ipv4(_)) | Some(Host::Ipv6(_)) | None => {
+                return Err(anyhow!("Bad endpoint host \'{}\'", endpoint_url.host_str().unwrap_or("")));
+            }
+        };
+
+        if endpoint_host == "localhost" || endpoint_host.ends_with(".localhost") {
+            return Err(anyhow!("Bad endpoint host \'{}\'", endpoint_host));
+        }
+
+        if p256dh.len() == 0 || p256dh.len() > 128 {
+            return Err(anyhow!("Bad p256dh length {} must be within 1..128", p256dh.len()));
+        }
+
+        if auth.len() == 0 || auth.len() > 64 {
+            return Err(anyhow!("Bad auth length {} must be within 1..64", auth.len()));
+        }
+
+        let web_push_subscription = WebPushSubscription {
+            endpoint: String::from(endpoint),
+            p256dh: String::from(p256dh),
+            auth: String::from(auth)
+        };
+
+        return Ok(web_push_subscription);
+    }
+}
+
 impl Display for FirebaseToken {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         return write!(f, "{}", self.token);
@@ -335,6 +549,7 @@ impl Display for Account {
         write!(f, "{}, ", self.account_id)?;
         write!(f, "{}, ", self.tokens.len())?;
         write!(f, "{:?}, ", self.valid_until)?;
+        write!(f, "{:?}, ", self.banned_until)?;
         write!(f, ")")?;
         return Ok(());
     }
@@ -378,6 +593,108 @@ pub async fn get_account(
     return Ok(Some(account));
 }
 
+/// Drops the cached account so that the next get_account() call re-reads it from the database.
+/// The write paths patch the cached account in place so this is only needed when the database
+/// ends up in a state that the patch doesn't reflect.
+pub async fn invalidate(account_id: &AccountId) {
+    let mut accounts_locked = ACCOUNTS_CACHE.write().await;
+    accounts_locked.remove(account_id);
+}
+
+/// Compares up to [sample_size] cached accounts (their tokens and valid_until) with the database,
+/// logs every discrepancy and invalidates the mismatching accounts. Returns the amount of
+/// mismatching accounts.
+pub async fn verify_accounts_cache(
+    database: &Arc<Database>,
+    sample_size: usize
+) -> anyhow::Result<usize> {
+    let cached_accounts = {
+        ACCOUNTS_CACHE.read()
+            .await
+            .iter()
+            .take(sample_size)
+            .map(|(account_id, account)| (account_id.clone(), account.clone()))
+            .collect::<Vec<(AccountId, Arc<Mutex<Account>>)>>()
+    };
+
+    let mut mismatched = 0;
+
+    for (account_id, cached_account) in &cached_accounts {
+        let (cached_valid_until, cached_tokens) = {
+            let cached_account = cached_account.lock().await;
+
+            let cached_tokens = cached_account.tokens.iter()
+                .cloned()
+                .collect::<HashSet<AccountToken>>();
+
+            (cached_account.valid_until.clone(), cached_tokens)
+        };
+
+        let account_from_database = get_account_from_database(account_id, database).await?;
+        if account_from_database.is_none() {
+            warn!(
+                "verify_accounts_cache() account {} is cached but does not exist in the database",
+                account_id.format_token()
+            );
+
+            invalidate(account_id).await;
+            mismatched += 1;
+            continue;
+        }
+
+        let account_from_database = account_from_database.unwrap();
+        let database_tokens = get_account_tokens_from_database(account_id, database).await?
+            .into_iter()
+            .collect::<HashSet<AccountToken>>();
+
+        let valid_until_matches = valid_until_matches(&cached_valid_until, &account_from_database.valid_until);
+        let tokens_match = cached_tokens == database_tokens;
+
+        if valid_until_matches && tokens_match {
+            continue;
+        }
+
+        if !valid_until_matches {
+            warn!(
+                "verify_accounts_cache() account {} valid_until mismatch, cache: {:?}, database: {:?}",
+                account_id.format_token(),
+                cached_valid_until,
+                account_from_database.valid_until
+            );
+        }
+
+        if !tokens_match {
+            warn!(
+                "verify_accounts_cache() account {} tokens mismatch, cache: {} tokens, database: {} tokens",
+                account_id.format_token(),
+                cached_tokens.len(),
+                database_tokens.len()
+            );
+        }
+
+        invalidate(account_id).await;
+        mismatched += 1;
+    }
+
+    info!(
+        "verify_accounts_cache() checked {} cached accounts, mismatched: {}",
+        cached_accounts.len(),
+        mismatched
+    );
+
+    return Ok(mismatched);
+}
+
+/// Postgres stores timestamps with microsecond precision while the cached ones may have nanoseconds.
+fn valid_until_matches(cached: &Option<DateTime<Utc>>, from_database: &Option<DateTime<Utc>>) -> bool {
+    if cached.is_none() || from_database.is_none() {
+        return cached.is_none() && from_database.is_none();
+    }
+
+    let difference = cached.unwrap() - from_database.unwrap();
+    return difference.num_microseconds().map(|micros| micros.abs() <= 1).unwrap_or(false);
+}
+
 pub async fn create_account(
     database: &Arc<Database>,
     account_id: &AccountId,
@@ -389,6 +706,13 @@ pub async fn create_account(
         return Err(anyhow!("Account already exists"));
     }
 
+    // Deleted accounts keep their ban so that a banned user can't get around it by re-creating
+    // the account.
+    if is_account_banned_in_database(account_id, database).await? {
+        warn!("create_account() account with id: {} is banned!", account_id.format_token());
+        return Ok(CreateAccountResult::AccountIsBanned);
+    }
+
     let query = r#"
         INSERT INTO accounts
         (
@@ -399,7 +723,7 @@ pub async fn create_account(
         RETURNING accounts.id
     "#;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let statement = connection.prepare(query).await?;
 
     let id: i64 = connection.query_one(
@@ -429,49 +753,47 @@ pub async fn create_account(
     return Ok(CreateAccountResult::Ok);
 }
 
-pub async fn update_firebase_token(
+/// Bans the account until [banned_until], None lifts the ban.
+pub async fn ban_account(
     database: &Arc<Database>,
     account_id: &AccountId,
-    application_type: &ApplicationType,
-    firebase_token: &FirebaseToken
-) -> anyhow::Result<UpdateFirebaseTokenResult> {
+    banned_until: Option<DateTime<Utc>>
+) -> anyhow::Result<BanAccountResult> {
     let existing_account = get_account(account_id, database).await?;
     if existing_account.is_none() {
         warn!(
-            "update_firebase_token() account with id: {} does not exist!",
+            "ban_account() account with id: {} does not exist!",
             account_id.format_token()
         );
 
-        return Ok(UpdateFirebaseTokenResult::AccountDoesNotExist);
+        return Ok(BanAccountResult::AccountDoesNotExist);
     }
 
-    let account_id_generated = { existing_account.unwrap().lock().await.id };
-
     let query = r#"
-        INSERT INTO account_tokens (
-            owner_account_id,
-            token,
-            application_type,
-            token_type
-        )
-        VALUES ($1, $2, $3, $4)
-        ON CONFLICT (token, application_type, token_type) DO NOTHING
+        UPDATE accounts
+        SET
+            banned_until = $1
+        WHERE
+            account_id = $2
+        RETURNING accounts.banned_until
     "#;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let statement = connection.prepare(query).await?;
 
-    connection.execute(
+    let row = connection.query_opt(
         &statement,
-        &[
-            &account_id_generated,
-            &firebase_token.token,
-            &(application_type.clone() as i64),
-            &(TokenType::Firebase as i64)
-        ]
+        &[&banned_until, &account_id.id]
     )
         .await
-        .context("update_firebase_token() Failed to update firebase_token in the database")?;
+        .context("ban_account() Failed to update banned_until in the database")?;
+
+    if row.is_none() {
+        invalidate(account_id).await;
+        return Ok(BanAccountResult::AccountDoesNotExist);
+    }
+
+    let stored_banned_until: Option<DateTime<Utc>> = row.unwrap().try_get(0)?;
 
     {
         let mut accounts_locked = ACCOUNTS_CACHE.write().await;
@@ -479,107 +801,767 @@ pub async fn update_firebase_token(
         let existing_account = accounts_locked.get_mut(account_id);
         if existing_account.is_some() {
             let mut existing_account = existing_account.unwrap().lock().await;
-
-            let account_token = AccountToken {
-                token: firebase_token.token.clone(),
-                application_type: application_type.clone(),
-                token_type: TokenType::Firebase
-            };
-
-            existing_account.add_or_update_token(account_token);
-        } else {
-            return Err(anyhow!("Account {} does not exist!", account_id));
+            existing_account.banned_until = stored_banned_until.clone();
         }
     }
 
     info!(
-        "update_firebase_token() success. account_id: {}, firebase_token: {}",
+        "ban_account() success. account_id: {}, banned_until: {:?}",
         account_id.format_token(),
-        firebase_token.format_token()
+        stored_banned_until
     );
 
-    return Ok(UpdateFirebaseTokenResult::Ok);
+    return Ok(BanAccountResult::Ok);
 }
 
-pub async fn update_account_expiry_date(
+pub async fn update_firebase_token(
     database: &Arc<Database>,
     account_id: &AccountId,
-    valid_until: &DateTime<Utc>
-) -> anyhow::Result<UpdateAccountExpiryDateResult> {
+    application_type: &ApplicationType,
+    firebase_token: &FirebaseToken
+) -> anyhow::Result<UpdateFirebaseTokenResult> {
     let existing_account = get_account(account_id, database).await?;
     if existing_account.is_none() {
         warn!(
-            "update_account_expiry_date() account with id: {} does not exist!",
+            "update_firebase_token() account with id: {} does not exist!",
             account_id.format_token()
         );
 
-        return Ok(UpdateAccountExpiryDateResult::AccountDoesNotExist);
+        return Ok(UpdateFirebaseTokenResult::AccountDoesNotExist);
     }
 
-    let query = r#"
-        UPDATE accounts
-        SET
-            valid_until = $1
-        WHERE
-            account_id = $2
-    "#;
+    let account_id_generated = { existing_account.unwrap().lock().await.id };
 
-    let connection = database.connection().await?;
-    let statement = connection.prepare(query).await?;
+    let mut connection = database.connection_with_retry().await?;
+    let transaction = connection.transaction().await?;
 
-    connection.execute(
-        &statement,
-        &[&valid_until, &account_id.id]
+    let inserted = insert_firebase_token(
+        account_id_generated,
+        application_type,
+        firebase_token,
+        &transaction
     )
         .await
-        .context("update_account_expiry_date() Failed to update valid_until in the database")?;
-
-    {
-        let mut accounts_locked = ACCOUNTS_CACHE.write().await;
+        .context("update_firebase_token() Failed to update firebase_token in the database")?;
 
-        let existing_account = accounts_locked.get_mut(account_id);
-        if existing_account.is_some() {
-            let mut existing_account = existing_account.unwrap().lock().await;
-            existing_account.valid_until = Some(valid_until.clone());
-        } else {
-            return Err(anyhow!("Account {} does not exist!", account_id));
-        }
-    }
+    transaction.commit().await?;
+    on_firebase_token_inserted(account_id, application_type, firebase_token, inserted).await;
 
     info!(
-        "update_account_expiry_date() success. account_id: {}, valid_until: {}",
+        "update_firebase_token() success. account_id: {}, firebase_token: {}",
         account_id.format_token(),
-        valid_until
+        firebase_token.format_token()
     );
 
-    return Ok(UpdateAccountExpiryDateResult::Ok);
+    return Ok(UpdateFirebaseTokenResult::Ok);
 }
 
-pub async fn retain_post_db_ids_belonging_to_account(
-    account_id: &AccountId,
-    reply_ids: &Vec<i64>,
-    database: &Arc<Database>
-) -> anyhow::Result<Vec<i64>> {
+/// Returns whether the token was inserted, it's not when it already exists (possibly belonging to
+/// another account). [on_firebase_token_inserted] must be called once the transaction is committed.
+pub async fn insert_firebase_token(
+    account_db_id: i64,
+    application_type: &ApplicationType,
+    firebase_token: &FirebaseToken,
+    transaction: &Transaction<'_>
+) -> anyhow::Result<bool> {
     let query = r#"
-        SELECT
-            post_replies.id
-        FROM post_replies
-        INNER JOIN accounts account on account.id = post_replies.owner_account_id
-        WHERE
-            account.account_id = $1
-        AND
-            post_replies.id IN ({QUERY_PARAMS})
+        INSERT INTO account_tokens (
+            owner_account_id,
+            token,
+            application_type,
+            token_type
+        )
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (token, application_type, token_type) DO NOTHING
     "#;
 
-    let connection = database.connection().await?;
-
-    let (query, mut db_params) = db_helpers::format_query_params_with_start_index(
+    let inserted = transaction.execute(
         query,
-        "{QUERY_PARAMS}",
-        1,
-        reply_ids
-    )?;
-
+        &[
+            &account_db_id,
+            &firebase_token.token,
+            &(application_type.clone() as i64),
+            &(TokenType::Firebase as i64)
+        ]
+    ).await?;
+
+    return Ok(inserted > 0);
+}
+
+pub async fn on_firebase_token_inserted(
+    account_id: &AccountId,
+    application_type: &ApplicationType,
+    firebase_token: &FirebaseToken,
+    inserted: bool
+) {
+    if !inserted {
+        // The database was not changed and patching the cached account would make it diverge
+        // from the database.
+        invalidate(account_id).await;
+        return;
+    }
+
+    let mut accounts_locked = ACCOUNTS_CACHE.write().await;
+
+    let existing_account = accounts_locked.get_mut(account_id);
+    if existing_account.is_some() {
+        let mut existing_account = existing_account.unwrap().lock().await;
+
+        let account_token = AccountToken {
+            token: firebase_token.token.clone(),
+            application_type: application_type.clone(),
+            token_type: TokenType::Firebase
+        };
+
+        existing_account.add_or_update_token(account_token);
+    }
+}
+
+pub async fn rotate_firebase_token(
+    database: &Arc<Database>,
+    account_id: &AccountId,
+    application_type: &ApplicationType,
+    previous_firebase_token: &FirebaseToken,
+    firebase_token: &FirebaseToken
+) -> anyhow::Result<UpdateFirebaseTokenResult> {
+    let existing_account = get_account(account_id, database).await?;
+    if existing_account.is_none() {
+        warn!(
+            "rotate_firebase_token() account with id: {} does not exist!",
+            account_id.format_token()
+        );
+
+        return Ok(UpdateFirebaseTokenResult::AccountDoesNotExist);
+    }
+
+    let account_id_generated = { existing_account.unwrap().lock().await.id };
+
+    // post_watches belong to the account and not to the token so they survive the rotation.
+    let delete_previous_token_query = r#"
+        DELETE FROM account_tokens
+        WHERE
+            account_tokens.owner_account_id = $1
+        AND
+            account_tokens.token = $2
+        AND
+            account_tokens.token_type = $3
+    "#;
+
+    let insert_new_token_query = r#"
+        INSERT INTO account_tokens (
+            owner_account_id,
+            token,
+            application_type,
+            token_type
+        )
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (token, application_type, token_type) DO NOTHING
+    "#;
+
+    let mut connection = database.connection_with_retry().await?;
+    let transaction = connection.transaction().await?;
+
+    transaction.execute(
+        delete_previous_token_query,
+        &[
+            &account_id_generated,
+            &previous_firebase_token.token,
+            &(TokenType::Firebase as i64)
+        ]
+    )
+        .await
+        .context("rotate_firebase_token() Failed to delete previous firebase_token from the database")?;
+
+    let inserted = transaction.execute(
+        insert_new_token_query,
+        &[
+            &account_id_generated,
+            &firebase_token.token,
+            &(application_type.clone() as i64),
+            &(TokenType::Firebase as i64)
+        ]
+    )
+        .await
+        .context("rotate_firebase_token() Failed to insert new firebase_token into the database")?;
+
+    transaction.commit().await?;
+
+    if inserted == 0 {
+        // Same as in update_firebase_token(), the new token already exists so just re-read the
+        // account from the database next time.
+        invalidate(account_id).await;
+    } else {
+        let mut accounts_locked = ACCOUNTS_CACHE.write().await;
+
+        let existing_account = accounts_locked.get_mut(account_id);
+        if existing_account.is_some() {
+            let mut existing_account = existing_account.unwrap().lock().await;
+
+            let account_token = AccountToken {
+                token: firebase_token.token.clone(),
+                application_type: application_type.clone(),
+                token_type: TokenType::Firebase
+            };
+
+            existing_account.remove_token(&previous_firebase_token.token);
+            existing_account.add_or_update_token(account_token);
+        }
+    }
+
+    info!(
+        "rotate_firebase_token() success. account_id: {}, previous_firebase_token: {}, firebase_token: {}",
+        account_id.format_token(),
+        previous_firebase_token.format_token(),
+        firebase_token.format_token()
+    );
+
+    return Ok(UpdateFirebaseTokenResult::Ok);
+}
+
+/// Stores the endpoint of the subscription as a WebPush token of the account and the keys needed to
+/// encrypt the messages in web_push_subscriptions. Just like with firebase tokens an endpoint that
+/// already belongs to another account is left untouched.
+pub async fn update_web_push_subscription(
+    database: &Arc<Database>,
+    account_id: &AccountId,
+    application_type: &ApplicationType,
+    web_push_subscription: &WebPushSubscription
+) -> anyhow::Result<UpdateWebPushSubscriptionResult> {
+    let existing_account = get_account(account_id, database).await?;
+    if existing_account.is_none() {
+        warn!(
+            "update_web_push_subscription() account with id: {} does not exist!",
+            account_id.format_token()
+        );
+
+        return Ok(UpdateWebPushSubscriptionResult::AccountDoesNotExist);
+    }
+
+    let account_id_generated = { existing_account.unwrap().lock().await.id };
+
+    let insert_token_query = r#"
+        INSERT INTO account_tokens (
+            owner_account_id,
+            token,
+            application_type,
+            token_type
+        )
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (token, application_type, token_type) DO NOTHING
+    "#;
+
+    let select_token_id_query = r#"
+        SELECT account_tokens.id
+        FROM account_tokens
+        WHERE
+            account_tokens.owner_account_id = $1
+        AND
+            account_tokens.token = $2
+        AND
+            account_tokens.application_type = $3
+        AND
+            account_tokens.token_type = $4
+    "#;
+
+    let upsert_subscription_query = r#"
+        INSERT INTO web_push_subscriptions (
+            owner_account_token_id,
+            p256dh,
+            auth
+        )
+        VALUES ($1, $2, $3)
+        ON CONFLICT (owner_account_token_id) DO UPDATE
+        SET
+            p256dh = EXCLUDED.p256dh,
+            auth = EXCLUDED.auth
+    "#;
+
+    let mut connection = database.connection_with_retry().await?;
+    let transaction = connection.transaction().await?;
+
+    let inserted = transaction.execute(
+        insert_token_query,
+        &[
+            &account_id_generated,
+            &web_push_subscription.endpoint,
+            &(application_type.clone() as i64),
+            &(TokenType::WebPush as i64)
+        ]
+    )
+        .await
+        .context("update_web_push_subscription() Failed to insert endpoint into the database")?;
+
+    let token_row = transaction.query_opt(
+        select_token_id_query,
+        &[
+            &account_id_generated,
+            &web_push_subscription.endpoint,
+            &(application_type.clone() as i64),
+            &(TokenType::WebPush as i64)
+        ]
+    )
+        .await
+        .context("update_web_push_subscription() Failed to select endpoint from the database")?;
+
+    if token_row.is_some() {
+        let account_token_id: i64 = token_row.unwrap().try_get(0)?;
+
+        transaction.execute(
+            upsert_subscription_query,
+            &[&account_token_id, &web_push_subscription.p256dh, &web_push_subscription.auth]
+        )
+            .await
+            .context("update_web_push_subscription() Failed to store subscription keys in the database")?;
+    }
+
+    transaction.commit().await?;
+
+    if inserted == 0 {
+        invalidate(account_id).await;
+    } else {
+        let mut accounts_locked = ACCOUNTS_CACHE.write().await;
+
+        let existing_account = accounts_locked.get_mut(account_id);
+        if existing_account.is_some() {
+            let mut existing_account = existing_account.unwrap().lock().await;
+
+            let account_token = AccountToken {
+                token: web_push_subscription.endpoint.clone(),
+                application_type: application_type.clone(),
+                token_type: TokenType::WebPush
+            };
+
+            existing_account.add_or_update_token(account_token);
+        }
+    }
+
+    info!(
+        "update_web_push_subscription() success. account_id: {}, endpoint: {}",
+        account_id.format_token(),
+        web_push_subscription.endpoint.format_token()
+    );
+
+    return Ok(UpdateWebPushSubscriptionResult::Ok);
+}
+
+pub async fn get_web_push_subscription(
+    endpoint: &str,
+    database: &Arc<Database>
+) -> anyhow::Result<Option<WebPushSubscription>> {
+    let query = r#"
+        SELECT
+            account_tokens.token,
+            web_push_subscriptions.p256dh,
+            web_push_subscriptions.auth
+        FROM web_push_subscriptions
+            INNER JOIN account_tokens
+                ON account_tokens.id = web_push_subscriptions.owner_account_token_id
+        WHERE
+            account_tokens.token = $1
+        AND
+            account_tokens.token_type = $2
+        ORDER BY web_push_subscriptions.id DESC
+        LIMIT 1
+    "#;
+
+    let connection = database.connection_with_retry().await?;
+    let row = connection.query_opt(query, &[&endpoint, &(TokenType::WebPush as i64)]).await?;
+    if row.is_none() {
+        return Ok(None);
+    }
+
+    let row = row.unwrap();
+
+    let web_push_subscription = WebPushSubscription {
+        endpoint: row.try_get(0)?,
+        p256dh: row.try_get(1)?,
+        auth: row.try_get(2)?
+    };
+
+    return Ok(Some(web_push_subscription));
+}
+
+/// Removes the firebase tokens of [application_type] (or only [firebase_token] when it's set) so
+/// that no more FCM messages are sent to them. The account and its post_watches are kept.
+pub async fn remove_firebase_token(
+    database: &Arc<Database>,
+    account_id: &AccountId,
+    application_type: &ApplicationType,
+    firebase_token: Option<&FirebaseToken>
+) -> anyhow::Result<RemoveFirebaseTokenResult> {
+    let existing_account = get_account(account_id, database).await?;
+    if existing_account.is_none() {
+        warn!(
+            "remove_firebase_token() account with id: {} does not exist!",
+            account_id.format_token()
+        );
+
+        return Ok(RemoveFirebaseTokenResult::AccountDoesNotExist);
+    }
+
+    let account_id_generated = { existing_account.unwrap().lock().await.id };
+
+    let query = r#"
+        DELETE FROM account_tokens
+        WHERE
+            account_tokens.owner_account_id = $1
+        AND
+            account_tokens.application_type = $2
+        AND
+            account_tokens.token_type = $3
+        AND
+            ($4::text IS NULL OR account_tokens.token = $4)
+    "#;
+
+    let token = firebase_token.map(|firebase_token| firebase_token.token.clone());
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    let deleted = connection.execute(
+        &statement,
+        &[
+            &account_id_generated,
+            &(application_type.clone() as i64),
+            &(TokenType::Firebase as i64),
+            &token
+        ]
+    )
+        .await
+        .context("remove_firebase_token() Failed to delete firebase_token from the database")?;
+
+    {
+        let mut accounts_locked = ACCOUNTS_CACHE.write().await;
+
+        let existing_account = accounts_locked.get_mut(account_id);
+        if existing_account.is_some() {
+            let mut existing_account = existing_account.unwrap().lock().await;
+            existing_account.remove_application_tokens(application_type, token.as_deref());
+        }
+    }
+
+    info!(
+        "remove_firebase_token() success. account_id: {}, application_type: {}, removed tokens: {}",
+        account_id.format_token(),
+        application_type,
+        deleted
+    );
+
+    return Ok(RemoveFirebaseTokenResult::Ok);
+}
+
+pub async fn update_account_expiry_date(
+    database: &Arc<Database>,
+    account_id: &AccountId,
+    valid_until: &DateTime<Utc>
+) -> anyhow::Result<UpdateAccountExpiryDateResult> {
+    let existing_account = get_account(account_id, database).await?;
+    if existing_account.is_none() {
+        warn!(
+            "update_account_expiry_date() account with id: {} does not exist!",
+            account_id.format_token()
+        );
+
+        return Ok(UpdateAccountExpiryDateResult::AccountDoesNotExist);
+    }
+
+    let query = r#"
+        UPDATE accounts
+        SET
+            valid_until = $1
+        WHERE
+            account_id = $2
+        RETURNING accounts.valid_until
+    "#;
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    let row = connection.query_opt(
+        &statement,
+        &[&valid_until, &account_id.id]
+    )
+        .await
+        .context("update_account_expiry_date() Failed to update valid_until in the database")?;
+
+    if row.is_none() {
+        // The account row was removed after we read it, don't keep it cached.
+        invalidate(account_id).await;
+        return Ok(UpdateAccountExpiryDateResult::AccountDoesNotExist);
+    }
+
+    // Postgres has microsecond precision so this may slightly differ from [valid_until]
+    let stored_valid_until: DateTime<Utc> = row.unwrap().try_get(0)?;
+
+    {
+        let mut accounts_locked = ACCOUNTS_CACHE.write().await;
+
+        let existing_account = accounts_locked.get_mut(account_id);
+        if existing_account.is_some() {
+            let mut existing_account = existing_account.unwrap().lock().await;
+            existing_account.valid_until = Some(stored_valid_until.clone());
+        }
+    }
+
+    info!(
+        "update_account_expiry_date() success. account_id: {}, valid_until: {}",
+        account_id.format_token(),
+        stored_valid_until
+    );
+
+    return Ok(UpdateAccountExpiryDateResult::Ok(stored_valid_until));
+}
+
+/// Extends valid_until of the account by [days] starting from whichever is later, the current
+/// valid_until or now, so that renewing early doesn't make the user lose the remaining time.
+/// Returns the new valid_until or None if the account does not exist.
+/// [on_account_expiry_date_extended] must be called once the transaction is committed.
+pub async fn extend_account_expiry_date(
+    account_id: &AccountId,
+    days: i32,
+    transaction: &Transaction<'_>
+) -> anyhow::Result<Option<DateTime<Utc>>> {
+    let query = r#"
+        UPDATE accounts
+        SET
+            valid_until = GREATEST(valid_until, now()) + make_interval(days => $2)
+        WHERE
+            account_id = $1
+        RETURNING accounts.valid_until
+    "#;
+
+    let statement = transaction.prepare(query).await?;
+
+    let row = transaction.query_opt(
+        &statement,
+        &[&account_id.id, &days]
+    )
+        .await
+        .context("extend_account_expiry_date() Failed to update valid_until in the database")?;
+
+    if row.is_none() {
+        return Ok(None);
+    }
+
+    let valid_until: DateTime<Utc> = row.unwrap().try_get(0)?;
+    return Ok(Some(valid_until));
+}
+
+pub async fn on_account_expiry_date_extended(
+    account_id: &AccountId,
+    valid_until: &DateTime<Utc>
+) {
+    let mut accounts_locked = ACCOUNTS_CACHE.write().await;
+
+    let existing_account = accounts_locked.get_mut(account_id);
+    if existing_account.is_some() {
+        let mut existing_account = existing_account.unwrap().lock().await;
+        existing_account.valid_until = Some(valid_until.clone());
+    }
+}
+
+/// Replies to accounts that are currently in their quiet hours are not sent until the quiet hours
+/// end (see post_reply_repository). Passing None disables quiet hours.
+pub async fn update_quiet_hours(
+    database: &Arc<Database>,
+    account_id: &AccountId,
+    quiet_hours: Option<&QuietHours>
+) -> anyhow::Result<UpdateQuietHoursResult> {
+    let existing_account = get_account(account_id, database).await?;
+    if existing_account.is_none() {
+        warn!(
+            "update_quiet_hours() account with id: {} does not exist!",
+            account_id.format_token()
+        );
+
+        return Ok(UpdateQuietHoursResult::AccountDoesNotExist);
+    }
+
+    let query = r#"
+        UPDATE accounts
+        SET
+            quiet_hours_start = $1,
+            quiet_hours_end = $2,
+            tz_offset_minutes = $3
+        WHERE
+            account_id = $4
+    "#;
+
+    let quiet_hours_start = quiet_hours.map(|quiet_hours| quiet_hours.start);
+    let quiet_hours_end = quiet_hours.map(|quiet_hours| quiet_hours.end);
+    let tz_offset_minutes = quiet_hours.map(|quiet_hours| quiet_hours.tz_offset_minutes).unwrap_or(0);
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    connection.execute(
+        &statement,
+        &[&quiet_hours_start, &quiet_hours_end, &tz_offset_minutes, &account_id.id]
+    )
+        .await
+        .context("update_quiet_hours() Failed to update quiet hours in the database")?;
+
+    info!(
+        "update_quiet_hours() success. account_id: {}, quiet_hours: {:?}",
+        account_id.format_token(),
+        quiet_hours
+    );
+
+    return Ok(UpdateQuietHoursResult::Ok);
+}
+
+/// Extends valid_until of every existing account by [extra_days] in a single transaction, expired
+/// accounts are extended starting from now. Returns the new valid_until of every extended account,
+/// accounts that do not exist (or were deleted) are not included.
+pub async fn bulk_extend_account_expiry(
+    database: &Arc<Database>,
+    account_ids: &Vec<AccountId>,
+    extra_days: i64
+) -> anyhow::Result<HashMap<AccountId, DateTime<Utc>>> {
+    if account_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let query = r#"
+        UPDATE accounts
+        SET
+            valid_until = GREATEST(COALESCE(accounts.valid_until, now()), now()) + make_interval(days => $1)
+        WHERE
+            accounts.account_id = ANY($2)
+        AND
+            accounts.deleted_on IS NULL
+        RETURNING
+            accounts.account_id,
+            accounts.valid_until
+    "#;
+
+    let account_id_strings = account_ids.iter()
+        .map(|account_id| account_id.id.clone())
+        .collect::<Vec<String>>();
+
+    let mut connection = database.connection_with_retry().await?;
+    let transaction = connection.transaction().await?;
+
+    let rows = transaction.query(query, &[&(extra_days as i32), &account_id_strings])
+        .await
+        .context("bulk_extend_account_expiry() Failed to update valid_until in the database")?;
+
+    transaction.commit().await?;
+
+    let mut extended_accounts = HashMap::<AccountId, DateTime<Utc>>::with_capacity(rows.len());
+
+    for row in rows {
+        let account_id = AccountId::new(row.try_get(0)?)?;
+        let valid_until: DateTime<Utc> = row.try_get(1)?;
+
+        extended_accounts.insert(account_id, valid_until);
+    }
+
+    {
+        let accounts_locked = ACCOUNTS_CACHE.read().await;
+
+        for (account_id, valid_until) in &extended_accounts {
+            let existing_account = accounts_locked.get(account_id);
+            if existing_account.is_some() {
+                let mut existing_account = existing_account.unwrap().lock().await;
+                existing_account.valid_until = Some(valid_until.clone());
+            }
+        }
+    }
+
+    info!(
+        "bulk_extend_account_expiry() success. extended {} out of {} accounts by {} days",
+        extended_accounts.len(),
+        account_ids.len(),
+        extra_days
+    );
+
+    return Ok(extended_accounts);
+}
+
+pub async fn delete_account(
+    database: &Arc<Database>,
+    account_id: &AccountId
+) -> anyhow::Result<DeleteAccountResult> {
+    let existing_account = get_account(account_id, database).await?;
+    if existing_account.is_none() {
+        warn!(
+            "delete_account() account with id: {} does not exist!",
+            account_id.format_token()
+        );
+
+        return Ok(DeleteAccountResult::AccountDoesNotExist);
+    }
+
+    let account_id_generated = { existing_account.unwrap().lock().await.id };
+
+    let mark_account_as_deleted_query = r#"
+        UPDATE accounts
+        SET
+            deleted_on = now()
+        WHERE
+            accounts.id = $1
+        AND
+            accounts.deleted_on IS NULL
+    "#;
+
+    let delete_post_watches_query = r#"
+        DELETE FROM post_watches
+        WHERE post_watches.owner_account_id = $1
+    "#;
+
+    let mut connection = database.connection_with_retry().await?;
+    let transaction = connection.transaction().await?;
+
+    transaction.execute(mark_account_as_deleted_query, &[&account_id_generated])
+        .await
+        .context("delete_account() Failed to mark account as deleted in the database")?;
+
+    let deleted_post_watches = transaction.execute(delete_post_watches_query, &[&account_id_generated])
+        .await
+        .context("delete_account() Failed to delete account post watches from the database")?;
+
+    transaction.commit().await?;
+    invalidate(account_id).await;
+
+    info!(
+        "delete_account() success. account_id: {}, deleted_post_watches: {}",
+        account_id.format_token(),
+        deleted_post_watches
+    );
+
+    return Ok(DeleteAccountResult::Ok(deleted_post_watches));
+}
+
+pub async fn retain_post_db_ids_belonging_to_account(
+    account_id: &AccountId,
+    reply_ids: &Vec<i64>,
+    database: &Arc<Database>
+) -> anyhow::Result<Vec<i64>> {
+    if reply_ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let query = r#"
+        SELECT
+            post_replies.id
+        FROM post_replies
+        INNER JOIN accounts account on account.id = post_replies.owner_account_id
+        WHERE
+            account.account_id = $1
+        AND
+            post_replies.id IN ({QUERY_PARAMS})
+    "#;
+
+    let connection = database.connection_with_retry().await?;
+
+    let (query, mut db_params) = db_helpers::format_query_params_with_start_index(
+        query,
+        "{QUERY_PARAMS}",
+        1,
+        reply_ids
+    )?;
+
     db_params.insert(0, &account_id.id);
 
     let statement = connection.prepare(&query).await?;
@@ -599,6 +1581,94 @@ pub async fn retain_post_db_ids_belonging_to_account(
     return Ok(result_vec);
 }
 
+/// Returns one page of non-deleted accounts ordered by the order they were created in.
+pub async fn list_accounts(
+    page: i64,
+    page_size: i64,
+    database: &Arc<Database>
+) -> anyhow::Result<Vec<AccountSummary>> {
+    // There is one row per (account, application_type) pair. Accounts without tokens have a single
+    // row with NULL application_type.
+    let query = r#"
+        WITH page_accounts AS (
+            SELECT
+                accounts.id,
+                accounts.account_id,
+                accounts.valid_until
+            FROM accounts
+            WHERE
+                accounts.deleted_on IS NULL
+            ORDER BY accounts.id
+            LIMIT $1
+            OFFSET $2
+        )
+        SELECT
+            page_account.id,
+            page_account.account_id,
+            page_account.valid_until,
+            (
+                SELECT COUNT(post_watch.id)
+                FROM post_watches post_watch
+                WHERE post_watch.owner_account_id = page_account.id
+            ),
+            account_token.application_type,
+            COUNT(account_token.token)
+        FROM page_accounts page_account
+        LEFT JOIN account_tokens account_token
+            ON account_token.owner_account_id = page_account.id
+        GROUP BY
+            page_account.id,
+            page_account.account_id,
+            page_account.valid_until,
+            account_token.application_type
+        ORDER BY page_account.id
+    "#;
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    let rows = connection.query(&statement, &[&page_size, &(page * page_size)]).await?;
+    if rows.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut result_vec = Vec::<AccountSummary>::with_capacity(rows.len());
+    let mut last_account_db_id: Option<i64> = None;
+
+    for row in rows {
+        let account_db_id: i64 = row.try_get(0)?;
+        let account_id: String = row.try_get(1)?;
+        let valid_until: Option<DateTime<Utc>> = row.try_get(2)?;
+        let watches_count: i64 = row.try_get(3)?;
+        let application_type: Option<i64> = row.try_get(4)?;
+        let tokens_count: i64 = row.try_get(5)?;
+
+        if last_account_db_id != Some(account_db_id) {
+            last_account_db_id = Some(account_db_id);
+
+            result_vec.push(AccountSummary {
+                account_id: AccountId::new(account_id)?,
+                valid_until,
+                tokens_count: Vec::with_capacity(4),
+                watches_count
+            });
+        }
+
+        if application_type.is_none() {
+            continue;
+        }
+
+        let application_type = ApplicationType::from_i64(application_type.unwrap());
+
+        result_vec.last_mut()
+            .unwrap()
+            .tokens_count
+            .push((application_type, tokens_count));
+    }
+
+    return Ok(result_vec);
+}
+
 pub async fn get_account_from_database(
     account_id: &AccountId,
     database: &Arc<Database>
@@ -607,7 +1677,8 @@ pub async fn get_account_from_database(
         SELECT
             accounts.id,
             accounts.account_id,
-            accounts.valid_until
+            accounts.valid_until,
+            accounts.banned_until
         FROM accounts
         WHERE
             accounts.account_id = $1
@@ -615,7 +1686,7 @@ pub async fn get_account_from_database(
             accounts.deleted_on IS NULL
     "#;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let statement = connection.prepare(query).await?;
 
     let row = connection.query_opt(&statement, &[&account_id.id]).await?;
@@ -631,6 +1702,27 @@ pub async fn get_account_from_database(
     return Ok(Some(account.unwrap()));
 }
 
+/// Unlike get_account_from_database() this also checks deleted accounts.
+async fn is_account_banned_in_database(
+    account_id: &AccountId,
+    database: &Arc<Database>
+) -> anyhow::Result<bool> {
+    let query = r#"
+        SELECT 1
+        FROM accounts
+        WHERE
+            accounts.account_id = $1
+        AND
+            accounts.banned_until > now()
+    "#;
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    let row = connection.query_opt(&statement, &[&account_id.id]).await?;
+    return Ok(row.is_some());
+}
+
 async fn get_account_tokens_from_database(
     account_id: &AccountId,
     database: &Arc<Database>
@@ -646,7 +1738,7 @@ async fn get_account_tokens_from_database(
         WHERE account_id = $1
     "#;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let statement = connection.prepare(query).await?;
 
     let rows = connection.query(&statement, &[&account_id.id]).await?;
@@ -715,7 +1807,7 @@ pub async fn test_put_account_into_database(
         ON CONFLICT (account_id) DO UPDATE SET valid_until = $2
 "#;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let statement = connection.prepare(query).await?;
 
     connection.execute(
@@ -735,7 +1827,7 @@ pub async fn test_count_accounts_in_database(database: &Arc<Database>) -> anyhow
         FROM accounts
     "#;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let statement = connection.prepare(query).await?;
 
     let accounts_count: i64 = connection.query_opt(&statement, &[]).await?.unwrap().get(0);
@@ -752,3 +1844,60 @@ pub async fn test_cleanup() {
     let mut accounts_cache_locked = ACCOUNTS_CACHE.write().await;
     accounts_cache_locked.clear();
 }
+
+#[test]
+fn test_user_id_length_policy() {
+    assert!(validate_user_id(&"a".repeat(31)).is_err());
+    assert!(validate_user_id(&"a".repeat(32)).is_ok());
+    assert!(validate_user_id(&"a".repeat(128)).is_ok());
+    assert!(validate_user_id(&"a".repeat(129)).is_err());
+
+    assert_eq!(
+        "Bad user_id length 31 must be within 32..128 symbols",
+        AccountId::from_user_id(&"a".repeat(31)).err().unwrap().to_string()
+    );
+    assert_eq!(128, AccountId::from_user_id(&"a".repeat(32)).unwrap().id.len());
+}
+
+#[test]
+fn test_account_id_new_does_not_panic() {
+    let account_id = AccountId::from_user_id(&"a".repeat(64)).unwrap();
+    assert!(account_id == AccountId::new(account_id.id.clone()).unwrap());
+
+    assert!(AccountId::new("a".repeat(127)).is_err());
+    assert!(AccountId::new("a".repeat(129)).is_err());
+    assert!(AccountId::new("z".repeat(128)).is_err());
+    assert!(AccountId::new(String::new()).is_err());
+}
+
+#[test]
+fn test_parse_user_id_hash_iterations() {
+    assert_eq!(constants::DEFAULT_USER_ID_HASH_ITERATIONS, parse_user_id_hash_iterations(None).unwrap());
+    assert_eq!(1, parse_user_id_hash_iterations(Some("1")).unwrap());
+    assert_eq!(32, parse_user_id_hash_iterations(Some(" 32 ")).unwrap());
+
+    assert!(parse_user_id_hash_iterations(Some("0")).is_err());
+    assert!(parse_user_id_hash_iterations(Some("1025")).is_err());
+    assert!(parse_user_id_hash_iterations(Some("abc")).is_err());
+}
+
+#[test]
+fn test_web_push_subscription_endpoint_policy() {
+    let new = |endpoint: &str| {
+        return WebPushSubscription::new(endpoint, "p256dh", "auth");
+    };
+
+    assert!(new("https://fcm.googleapis.com/fcm/send/abc").is_ok());
+    assert!(new("https://updates.push.services.mozilla.com/wpush/v2/abc").is_ok());
+
+    assert!(new("http://fcm.googleapis.com/fcm/send/abc").is_err());
+    assert!(new("https://127.0.0.1/push").is_err());
+    assert!(new("https://2130706433/push").is_err());
+    assert!(new("https://10.0.0.1:8080/push").is_err());
+    assert!(new("https://169.254.169.254/latest/meta-data").is_err());
+    assert!(new("https://[::1]/push").is_err());
+    assert!(new("https://[fe80::1]/push").is_err());
+    assert!(new("https://localhost/push").is_err());
+    assert!(new("https://LOCALHOST./push").is_err());
+    assert!(new("https://push.localhost/push").is_err());
+}
@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::helpers::db_helpers;
+use crate::helpers::string_helpers::FormatToken;
+use crate::info;
+use crate::model::data::chan::{CatalogDescriptor, CatalogThread};
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository;
+use crate::model::repository::account_repository::{AccountId, AccountToken, ApplicationType, TokenType};
+
+pub const MAX_CATALOG_NOTIFICATION_DELIVERY_ATTEMPTS: i16 = 25;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum StartWatchingCatalogResult {
+    Ok,
+    AccountDoesNotExist,
+    AccountHasNoToken,
+    AccountIsNotValid,
+    AccountIsBanned
+}
+
+#[derive(Debug, Clone)]
+pub struct CatalogWatch {
+    pub id: i64,
+    pub filter: String,
+    pub last_seen_thread_no: u64
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct UnsentCatalogNotification {
+    pub notification_id: i64,
+    pub catalog_descriptor: CatalogDescriptor,
+    pub thread_no: u64,
+    pub subject: Option<String>
+}
+
+pub async fn start_watching_catalog(
+    database: &Arc<Database>,
+    account_id: &AccountId,
+    application_type: &ApplicationType,
+    catalog_descriptor: &CatalogDescriptor,
+    filter: &str
+) -> anyhow::Result<StartWatchingCatalogResult> {
+    let account = account_repository::get_account(account_id, database).await?;
+    if account.is_none() {
+        info!(
+            "start_watching_catalog() account with id \'{}\' does not exist",
+            account_id.format_token()
+        );
+
+        return Ok(StartWatchingCatalogResult::AccountDoesNotExist);
+    }
+
+    let account = account.unwrap();
+
+    let is_banned = { account.lock().await.is_banned() };
+    if is_banned {
+        info!(
+            "start_watching_catalog() account with id \'{}\' is banned",
+            account_id.format_token()
+        );
+
+        return Ok(StartWatchingCatalogResult::AccountIsBanned);
+    }
+
+    let has_token = { !account.lock().await.get_account_tokens(application_type).is_empty() };
+    if !has_token {
+        info!(
+            "start_watching_catalog() account with id \'{}\' has no token",
+            account_id.format_token(),
+        );
+
+        return Ok(StartWatchingCatalogResult::AccountHasNoToken);
+    }
+
+    let is_valid = { account.lock().await.is_valid(application_type) };
+    if !is_valid {
+        let validation_status = { account.lock().await.validation_status(application_type) };
+
+        info!(
+            "start_watching_catalog() account with id \'{}\' is not valid (status: {})",
+            account_id.format_token(),
+            validation_status.unwrap()
+        );
+
+        return Ok(StartWatchingCatalogResult::AccountIsNotValid);
+    }
+
+    let query = r#"
+        INSERT INTO catalog_watches(
+            owner_account_id,
+            site_name,
+            board_code,
+            filter,
+            application_type
+        )
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (owner_account_id, site_name, board_code, filter) DO NOTHING
+    "#;
+
+    let account_db_id = { account.lock().await.id };
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    let inserted = connection.execute(
+        &statement,
+        &[
+            &account_db_id,
+            catalog_descriptor.site_name(),
+            catalog_descriptor.board_code(),
+            &filter,
+            &(application_type.clone() as i64)
+        ]
+    ).await?;
+
+    info!(
+        "start_watching_catalog() catalog watch for {} with filter \'{}\' created: {}",
+        catalog_descriptor,
+        filter,
+        inserted > 0
+    );
+
+    return Ok(StartWatchingCatalogResult::Ok);
+}
+
+pub async fn get_all_watched_catalogs(
+    database: &Arc<Database>
+) -> anyhow::Result<Vec<CatalogDescriptor>> {
+    let query = r#"
+        SELECT DISTINCT
+            catalog_watch.site_name,
+            catalog_watch.board_code
+        FROM catalog_watches catalog_watch
+            INNER JOIN accounts account
+                ON account.id = catalog_watch.owner_account_id
+        WHERE
+            account.deleted_on IS NULL
+        AND
+            account.valid_until > now()
+    "#;
+
+    let connection = database.connection_with_retry().await?;
+    let rows = connection.query(query, &[]).await?;
+
+    let catalog_descriptors = rows.iter()
+        .map(|row| {
+            let site_name: String = row.get(0);
+            let board_code: String = row.get(1);
+
+            return CatalogDescriptor::new(site_name, board_code);
+        })
+        .collect::<Vec<CatalogDescriptor>>();
+
+    return Ok(catalog_descriptors);
+}
+
+pub async fn get_catalog_watches(
+    database: &Arc<Database>,
+    catalog_descriptor: &CatalogDescriptor
+) -> anyhow::Result<Vec<CatalogWatch>> {
+    let query = r#"
+        SELECT
+            catalog_watch.id,
+            catalog_watch.filter,
+            catalog_watch.last_seen_thread_no
+        FROM catalog_watches catalog_watch
+        WHERE
+            catalog_watch.site_name = $1
+        AND
+            catalog_watch.board_code = $2
+    "#;
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    let rows = connection.query(
+        &statement,
+        &[catalog_descriptor.site_name(), catalog_descriptor.board_code()]
+    ).await?;
+
+    let catalog_watches = rows.iter()
+        .map(|row| {
+            let id: i64 = row.get(0);
+            let filter: String = row.get(1);
+            let last_seen_thread_no: i64 = row.get(2);
+
+            return CatalogWatch {
+                id,
+                filter,
+                last_seen_thread_no: last_seen_thread_no as u64
+            };
+        })
+        .collect::<Vec<CatalogWatch>>();
+
+    return Ok(catalog_watches);
+}
+
+pub async fn store_new_catalog_threads(
+    database: &Arc<Database>,
+    catalog_watch_id: i64,
+    last_seen_thread_no: u64,
+    new_catalog_threads: &Vec<&CatalogThread>
+) -> anyhow::Result<()> {
+    let mut connection = database.connection_with_retry().await?;
+    let transaction = connection.transaction().await?;
+
+    if !new_catalog_threads.is_empty() {
+        let query = r#"
+            INSERT INTO catalog_thread_notifications(
+                owner_catalog_watch_id,
+                thread_no,
+                subject
+            )
+            VALUES ($1, $2, $3)
+            ON CONFLICT (owner_catalog_watch_id, thread_no) DO NOTHING
+        "#;
+
+        let statement = transaction.prepare(query).await?;
+
+        for new_catalog_thread in new_catalog_threads {
+            transaction.execute(
+                &statement,
+                &[
+                    &catalog_watch_id,
+                    &(new_catalog_thread.thread_no as i64),
+                    &new_catalog_thread.subject
+                ]
+            ).await?;
+        }
+    }
+
+    transaction.execute(
+        "UPDATE catalog_watches SET last_seen_thread_no = $1 WHERE catalog_watches.id = $2",
+        &[&(last_seen_thread_no as i64), &catalog_watch_id]
+    ).await?;
+
+    transaction.commit().await?;
+    return Ok(());
+}
+
+pub async fn get_unsent_catalog_notifications(
+    database: &Arc<Database>
+) -> anyhow::Result<HashMap<AccountToken, Vec<UnsentCatalogNotification>>> {
+    // Same as with post replies, only send notifications to tokens whose application_type matches
+    // the application_type of the catalog watch.
+    let query = r#"
+        SELECT
+            notification.id,
+            catalog_watch.site_name,
+            catalog_watch.board_code,
+            notification.thread_no,
+            notification.subject,
+            account_token.token,
+            account_token.application_type,
+            account_token.token_type
+        FROM catalog_thread_notifications notification
+            INNER JOIN catalog_watches catalog_watch
+                ON catalog_watch.id = notification.owner_catalog_watch_id
+            INNER JOIN accounts account
+                ON account.id = catalog_watch.owner_account_id
+            INNER JOIN account_tokens account_token
+                ON account_token.owner_account_id = account.id
+                AND account_token.application_type = catalog_watch.application_type
+        WHERE
+            notification.notification_delivered_on IS NULL
+        AND
+            notification.notification_delivery_attempt < $1
+        AND
+            account.valid_until > now()
+        AND
+            account.deleted_on IS NULL
+        AND
+            (account.banned_until IS NULL OR account.banned_until <= now())
+    "#;
+
+    let connection = database.connection_with_retry().await?;
+    let rows = connection.query(query, &[&MAX_CATALOG_NOTIFICATION_DELIVERY_ATTEMPTS]).await?;
+
+    let mut result_map =
+        HashMap::<AccountToken, Vec<UnsentCatalogNotification>>::with_capacity(rows.len());
+
+    for row in rows {
+        let notification_id: i64 = row.try_get(0)?;
+        let site_name: String = row.try_get(1)?;
+        let board_code: String = row.try_get(2)?;
+        let thread_no: i64 = row.try_get(3)?;
+        let subject: Option<String> = row.try_get(4)?;
+        let token: String = row.try_get(5)?;
+        let application_type: i64 = row.try_get(6)?;
+        let token_type: i64 = row.try_get(7)?;
+
+        let account_token = AccountToken {
+            token,
+            application_type: ApplicationType::from_i64(application_type),
+            token_type: TokenType::from_i64(token_type)
+        };
+
+        let unsent_catalog_notification = UnsentCatalogNotification {
+            notification_id,
+            catalog_descriptor: CatalogDescriptor::new(site_name, board_code),
+            thread_no: thread_no as u64,
+            subject
+        };
+
+        if !result_map.contains_key(&account_token) {
+            result_map.insert(account_token.clone(), Vec::with_capacity(4));
+        }
+
+        result_map.get_mut(&account_token)
+            .unwrap()
+            .push(unsent_catalog_notification);
+    }
+
+    return Ok(result_map);
+}
+
+pub async fn mark_catalog_notifications_as_delivered(
+    notification_ids: &Vec<i64>,
+    database: &Arc<Database>
+) -> anyhow::Result<()> {
+    if notification_ids.is_empty() {
+        return Ok(());
+    }
+
+    let query = r#"
+        UPDATE catalog_thread_notifications
+        SET notification_delivered_on = now()
+        WHERE id IN ({QUERY_PARAMS})
+    "#;
+
+    let (query, db_params) = db_helpers::format_query_params(
+        query,
+        "{QUERY_PARAMS}",
+        notification_ids
+    )?;
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(&query).await?;
+    connection.execute(&statement, &db_params[..]).await?;
+
+    return Ok(());
+}
+
+pub async fn increment_catalog_notification_delivery_attempt(
+    notification_ids: &Vec<i64>,
+    database: &Arc<Database>
+) -> anyhow::Result<()> {
+    if notification_ids.is_empty() {
+        return Ok(());
+    }
+
+    let query = r#"
+        UPDATE catalog_thread_notifications
+        SET notification_delivery_attempt = notification_delivery_attempt + 1
+        WHERE id IN ({QUERY_PARAMS})
+    "#;
+
+    let (query, db_params) = db_helpers::format_query_params(
+        query,
+        "{QUERY_PARAMS}",
+        notification_ids
+    )?;
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(&query).await?;
+    connection.execute(&statement, &db_params[..]).await?;
+
+    return Ok(());
+}
@@ -1,16 +1,26 @@
 use std::sync::Arc;
 
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
 use rand::distributions::Alphanumeric;
 use rand::Rng;
 use tokio_postgres::Transaction;
 
 use crate::info;
+use crate::helpers::string_helpers::FormatToken;
 use crate::model::database::db::Database;
 use crate::model::repository::account_repository;
 use crate::model::repository::account_repository::{AccountId, CreateAccountResult};
 
 pub const NEW_ACCOUNT_TRIAL_PERIOD_DAYS: usize = 7;
 
+#[derive(Debug, Eq, PartialEq)]
+pub enum ExtendAccountExpiryResult {
+    Ok(DateTime<Utc>),
+    AccountDoesNotExist,
+    InviteIsNotValid
+}
+
 pub async fn cleanup(database: &Arc<Database>) -> anyhow::Result<u64> {
     let query = r#"
         DELETE
@@ -21,7 +31,7 @@ pub async fn cleanup(database: &Arc<Database>) -> anyhow::Result<u64> {
             now() > expires_on
     "#;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let deleted = connection.execute(query, &[]).await?;
 
     return Ok(deleted);
@@ -33,7 +43,7 @@ pub async fn generate_invites(
 ) -> anyhow::Result<Vec<String>> {
     let mut new_invites = Vec::<String>::with_capacity(amount_to_generate as usize);
 
-    let mut connection = database.connection().await?;
+    let mut connection = database.connection_with_retry().await?;
     let transaction = connection.transaction().await?;
 
     for _ in 0..amount_to_generate {
@@ -51,16 +61,15 @@ pub async fn accept_invite(
     invite: &String,
     database: &Arc<Database>,
 ) -> anyhow::Result<Option<String>> {
-    let mut connection = database.connection().await?;
+    let mut connection = database.connection_with_retry().await?;
     let transaction = connection.transaction().await?;
 
-    let exists_and_valid = invite_exists_and_valid(invite, &transaction).await?;
-    if !exists_and_valid {
+    let accepted = mark_invite_as_accepted(invite, &transaction).await?;
+    if !accepted {
         info!("accept_invite() invite does not exist or not valid, invite: {}", invite);
         return Ok(None);
     }
 
-    mark_invite_as_accepted(invite, &transaction).await?;
     transaction.commit().await?;
 
     let (user_id, account_id) = generate_account_id(&database).await?;
@@ -83,44 +92,100 @@ pub async fn accept_invite(
             info!("accept_invite() Account already exists, invite: {}", invite);
             Ok(None)
         }
+        CreateAccountResult::AccountIsBanned => {
+            info!("accept_invite() Account is banned, invite: {}", invite);
+            Ok(None)
+        }
     }
 }
 
-async fn mark_invite_as_accepted(
+/// Creates an account with a random user_id without going through invites. Used by the
+/// --create-account command line argument. Returns the user_id of the new account.
+pub async fn create_account_with_generated_id(
+    database: &Arc<Database>,
+    valid_days: i64
+) -> anyhow::Result<String> {
+    let (user_id, account_id) = generate_account_id(database).await?;
+    let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(valid_days);
+
+    let create_account_result = account_repository::create_account(
+        database,
+        &account_id,
+        Some(valid_until)
+    ).await?;
+
+    if create_account_result != CreateAccountResult::Ok {
+        return Err(anyhow!("Account {} already exists", account_id.format_token()));
+    }
+
+    info!("create_account_with_generated_id() success, valid_until: {}", valid_until);
+    return Ok(user_id);
+}
+
+pub async fn extend_account_expiry(
     invite: &String,
-    transaction: &Transaction<'_>,
-) -> anyhow::Result<()> {
-    let query = r#"
-        UPDATE invites
-        SET accepted_on = now()
-        WHERE invite_id = $1
-    "#;
+    account_id: &AccountId,
+    database: &Arc<Database>,
+) -> anyhow::Result<ExtendAccountExpiryResult> {
+    let account = account_repository::get_account(account_id, database).await?;
+    if account.is_none() {
+        info!("extend_account_expiry() account does not exist, invite: {}", invite);
+        return Ok(ExtendAccountExpiryResult::AccountDoesNotExist);
+    }
 
-    let statement = transaction.prepare(query).await?;
-    transaction.execute(&statement, &[&invite]).await?;
+    let mut connection = database.connection_with_retry().await?;
+    let transaction = connection.transaction().await?;
 
-    return Ok(());
+    let accepted = mark_invite_as_accepted(invite, &transaction).await?;
+    if !accepted {
+        info!("extend_account_expiry() invite does not exist or not valid, invite: {}", invite);
+        return Ok(ExtendAccountExpiryResult::InviteIsNotValid);
+    }
+
+    let valid_until = account_repository::extend_account_expiry_date(
+        account_id,
+        NEW_ACCOUNT_TRIAL_PERIOD_DAYS as i32,
+        &transaction
+    ).await?;
+
+    if valid_until.is_none() {
+        // The transaction is dropped without being committed so the invite is not used up
+        info!("extend_account_expiry() Account does not exist, invite: {}", invite);
+        return Ok(ExtendAccountExpiryResult::AccountDoesNotExist);
+    }
+
+    let valid_until = valid_until.unwrap();
+    transaction.commit().await?;
+
+    account_repository::on_account_expiry_date_extended(account_id, &valid_until).await;
+
+    info!("extend_account_expiry() success");
+    return Ok(ExtendAccountExpiryResult::Ok(valid_until));
 }
 
-async fn invite_exists_and_valid(
+/// Marks the invite as accepted unless it's already accepted or expired. Returns whether it was
+/// marked, the check and the update are a single statement so an invite can't be redeemed twice
+/// by concurrent requests.
+async fn mark_invite_as_accepted(
     invite: &String,
-    transaction: &Transaction<'_>
+    transaction: &Transaction<'_>,
 ) -> anyhow::Result<bool> {
     let query = r#"
-        SELECT invite_id
-        FROM invites
+        UPDATE invites
+        SET accepted_on = now()
         WHERE
             invite_id = $1
         AND
             accepted_on IS NULL
         AND
             now() < expires_on
+        RETURNING invite_id
     "#;
 
     let statement = transaction.prepare(query).await?;
-    let exists_and_valid = transaction.query_opt(&statement, &[&invite]).await?.is_some();
+    let accepted = transaction.query_opt(&statement, &[&invite]).await?.is_some();
 
-    return Ok(exists_and_valid);
+    return Ok(accepted);
 }
 
 async fn create_invite(
@@ -1,10 +1,19 @@
+use std::io::{Read, Write};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+use anyhow::Context;
 use chrono::{DateTime, Utc};
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
 
 use crate::info;
 use crate::model::database::db::Database;
 
+// 0 means compression is disabled
+static LOG_COMPRESSION_THRESHOLD: AtomicUsize = AtomicUsize::new(0);
+
 pub struct LogLine {
     pub id: i64,
     pub log_time: DateTime<Utc>,
@@ -13,6 +22,22 @@ pub struct LogLine {
     pub message: String
 }
 
+pub struct NewLogLine {
+    pub log_time: DateTime<Utc>,
+    pub log_level: String,
+    pub target: String,
+    pub message: String
+}
+
+/// Messages longer than [threshold] bytes are stored deflated, 0 disables the compression.
+pub fn set_log_compression_threshold(threshold: usize) {
+    LOG_COMPRESSION_THRESHOLD.store(threshold, Ordering::Relaxed);
+}
+
+pub fn log_compression_threshold() -> usize {
+    return LOG_COMPRESSION_THRESHOLD.load(Ordering::Relaxed);
+}
+
 pub async fn get_logs(
     num: i64,
     last_id: i64,
@@ -21,7 +46,14 @@ pub async fn get_logs(
     info!("get_logs() num: {}, last_id: {}", num, last_id);
 
     let query = r#"
-        SELECT *
+        SELECT
+            id,
+            log_time,
+            log_level,
+            target,
+            message,
+            message_compressed,
+            compressed
         FROM logs
         WHERE id < $1
         ORDER BY id DESC
@@ -43,7 +75,16 @@ pub async fn get_logs(
         let log_time: DateTime<Utc> = row.try_get(1)?;
         let log_level: String = row.try_get(2)?;
         let target: String = row.try_get(3)?;
-        let message: String = row.try_get(4)?;
+        let mut message: String = row.try_get(4)?;
+        let message_compressed: Option<Vec<u8>> = row.try_get(5)?;
+        let compressed: bool = row.try_get(6)?;
+
+        if compressed && message_compressed.is_some() {
+            message = decompress_log_message(&message_compressed.unwrap())
+                .with_context(|| {
+                    return format!("Failed to decompress log message with id {}", id);
+                })?;
+        }
 
         let log_line = LogLine {
             id,
@@ -57,4 +98,80 @@ pub async fn get_logs(
     }
 
     return Ok(result_vec);
-}
\ No newline at end of file
+}
+
+pub async fn store_logs(
+    database: &Arc<Database>,
+    new_log_lines: &Vec<NewLogLine>
+) -> anyhow::Result<()> {
+    if new_log_lines.is_empty() {
+        return Ok(());
+    }
+
+    let mut connection = database.connection().await?;
+    let transaction = connection.transaction().await?;
+
+    let query = r#"
+        INSERT INTO logs(
+            log_time,
+            log_level,
+            target,
+            message,
+            message_compressed,
+            compressed
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+    "#;
+
+    let compression_threshold = log_compression_threshold();
+
+    for new_log_line in new_log_lines {
+        let mut message = new_log_line.message.as_str();
+        let mut message_compressed: Option<Vec<u8>> = None;
+
+        if compression_threshold > 0 && new_log_line.message.len() > compression_threshold {
+            message_compressed = Some(compress_log_message(&new_log_line.message)?);
+            message = "";
+        }
+
+        transaction.execute(
+            query,
+            &[
+                &new_log_line.log_time,
+                &new_log_line.log_level,
+                &new_log_line.target,
+                &message,
+                &message_compressed,
+                &message_compressed.is_some()
+            ]
+        ).await?;
+    }
+
+    transaction.commit().await?;
+    return Ok(());
+}
+
+fn compress_log_message(message: &str) -> anyhow::Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(message.as_bytes())?;
+
+    return Ok(encoder.finish()?);
+}
+
+fn decompress_log_message(message_compressed: &[u8]) -> anyhow::Result<String> {
+    let mut decoder = DeflateDecoder::new(message_compressed);
+
+    let mut message = String::new();
+    decoder.read_to_string(&mut message)?;
+
+    return Ok(message);
+}
+
+#[test]
+fn test_log_message_compression_round_trip() {
+    let message = "SELECT * FROM logs WHERE id < $1 ".repeat(100);
+
+    let message_compressed = compress_log_message(&message).unwrap();
+    assert!(message_compressed.len() < message.len());
+    assert_eq!(message, decompress_log_message(&message_compressed).unwrap());
+}
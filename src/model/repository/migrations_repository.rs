@@ -16,6 +16,25 @@ mod embedded {
     embed_migrations!("migrations");
 }
 
+// Down scripts can't live in the "migrations" directory because refinery would try to embed them
+// as regular migrations so they are stored separately and must be registered here.
+static DOWN_MIGRATIONS: &[(u32, &str)] = &[
+    (2, include_str!("../../../migrations_down/V2__add_invites_table.sql")),
+    (3, include_str!("../../../migrations_down/V3__add_threads_died_on.sql")),
+    (4, include_str!("../../../migrations_down/V4__allow_multiple_account_tokens.sql")),
+    (5, include_str!("../../../migrations_down/V5__add_threads_title.sql")),
+    (6, include_str!("../../../migrations_down/V6__add_threads_last_etag.sql")),
+    (7, include_str!("../../../migrations_down/V7__add_catalog_watches.sql")),
+    (8, include_str!("../../../migrations_down/V8__add_post_replies_notification_claimed_until.sql")),
+    (9, include_str!("../../../migrations_down/V9__add_post_watches_filter_regex.sql")),
+    (10, include_str!("../../../migrations_down/V10__add_post_replies_owner_post_descriptor_id_index.sql")),
+    (11, include_str!("../../../migrations_down/V11__add_accounts_quiet_hours.sql")),
+    (12, include_str!("../../../migrations_down/V12__add_thread_mutes.sql")),
+    (13, include_str!("../../../migrations_down/V13__add_accounts_banned_until.sql")),
+    (14, include_str!("../../../migrations_down/V14__add_logs_message_compressed.sql")),
+    (15, include_str!("../../../migrations_down/V15__add_web_push_subscriptions.sql")),
+];
+
 struct AppliedMigration {
     version: u32,
     name: String,
@@ -116,6 +135,81 @@ pub async fn perform_migrations(database: &Arc<Database>) -> anyhow::Result<()>
     return Ok(());
 }
 
+pub async fn rollback_to(database: &Arc<Database>, target_version: u32) -> anyhow::Result<()> {
+    let mut connection = database.connection().await?;
+    let applied_migrations = collect_applied_migrations_as_map(&connection).await?;
+
+    let runner = embedded::migrations::runner();
+    let mut migrations = runner.get_migrations().clone();
+    migrations.sort_by(|a, b| b.version().cmp(&a.version()));
+
+    let migrations_to_rollback = migrations.into_iter()
+        .filter(|migration| {
+            return migration.version() > target_version
+                && applied_migrations.contains_key(&migration.version());
+        })
+        .collect::<Vec<Migration>>();
+
+    info!(
+        "Rolling back {} migrations to version {}...",
+        migrations_to_rollback.len(),
+        target_version
+    );
+
+    let transaction = connection.transaction()
+        .await
+        .context("Failed to start transaction")?;
+
+    for migration in &migrations_to_rollback {
+        let migrations_match = check_migration_checksum_match(&transaction, migration)
+            .await?;
+
+        if !migrations_match {
+            return Err(anyhow!(
+                "Applied migration does not match migration on disk! Version: {}",
+                migration.version()
+            ));
+        }
+
+        let down_migration_sql = DOWN_MIGRATIONS.iter()
+            .find(|(version, _)| *version == migration.version())
+            .map(|(_, sql)| *sql);
+
+        if down_migration_sql.is_none() {
+            return Err(anyhow!("Migration {} has no down script", migration));
+        }
+
+        let down_migration_sql = down_migration_sql.unwrap();
+
+        info!("Rolling back migration {}...", migration);
+
+        transaction.batch_execute(down_migration_sql)
+            .await
+            .context(format!("Failed to roll back migration {}", migration))?;
+
+        transaction.execute(
+            "DELETE FROM migrations WHERE migrations.version = $1",
+            &[&(migration.version() as i32)]
+        )
+            .await
+            .context("Failed to delete migration")?;
+
+        info!("Rolling back migration {}... success", migration);
+    }
+
+    transaction.commit()
+        .await
+        .context("Failed to commit transaction")?;
+
+    info!(
+        "Rolling back migrations to version {}... success, rolled back: {}",
+        target_version,
+        migrations_to_rollback.len()
+    );
+
+    return Ok(());
+}
+
 async fn check_migration_checksum_match(
     transaction: &Transaction<'_>,
     migration: &Migration
@@ -7,4 +7,6 @@ pub mod post_descriptor_id_repository;
 pub mod post_reply_repository;
 pub mod post_watch_repository;
 pub mod logs_repository;
-pub mod invites_repository;
\ No newline at end of file
+pub mod invites_repository;
+pub mod catalog_watch_repository;
+pub mod thread_mute_repository;
\ No newline at end of file
@@ -1,5 +1,7 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use lazy_static::lazy_static;
 use tokio::sync::{RwLock, RwLockWriteGuard};
@@ -24,6 +26,11 @@ lazy_static! {
         RwLock::new(HashMap::with_capacity(1024));
 }
 
+/// Lets the tests check that insert_descriptor_db_ids() inserts all the descriptors with a single
+/// statement instead of one statement per descriptor.
+#[cfg(test)]
+static POST_DESCRIPTOR_INSERT_STATEMENTS: AtomicUsize = AtomicUsize::new(0);
+
 #[derive(Debug, Clone)]
 struct ChanThread {
     thread_descriptor: ThreadDescriptor,
@@ -56,7 +63,7 @@ async fn populate_thread_descriptors_cache(database: &Arc<Database>) -> anyhow::
             thread.deleted_on IS NULL
     "#;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let rows = connection.query(query, &[]).await?;
 
     let mut loaded_thread_descriptors = 0;
@@ -132,7 +139,7 @@ async fn populate_post_descriptors_cache(database: &Arc<Database>) -> anyhow::Re
             thread.thread_no IN (SELECT thread_no FROM alive_threads)
     "#;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let rows = connection.query(query, &[]).await?;
 
     let mut loaded_post_descriptors = 0;
@@ -465,7 +472,7 @@ pub async fn insert_descriptor_db_ids<'a>(
     let mut result_map = HashMap::<&PostDescriptor, i64>::with_capacity(post_descriptors.len());
 
     let mut post_descriptors_to_insert =
-        Vec::<&PostDescriptor>::with_capacity(post_descriptors.len() / 2);
+        HashMap::<(i64, i64, i64), &'a PostDescriptor>::with_capacity(post_descriptors.len() / 2);
 
     {
         let pd_to_dbid_cache_locked = PD_TO_DBID_CACHE.read().await;
@@ -474,9 +481,22 @@ pub async fn insert_descriptor_db_ids<'a>(
             let id = pd_to_dbid_cache_locked.get(post_descriptor);
             if id.is_some() {
                 result_map.insert(post_descriptor, *id.unwrap());
-            } else {
-                post_descriptors_to_insert.push(post_descriptor);
+                continue;
+            }
+
+            let thread_db_id = thread_db_ids.get(&post_descriptor.thread_descriptor);
+            if thread_db_id.is_none() {
+                continue;
             }
+
+            // Duplicates must be filtered out here, otherwise "ON CONFLICT DO UPDATE" will fail
+            // with "command cannot affect row a second time"
+            let key = (
+                *thread_db_id.unwrap(),
+                post_descriptor.post_no as i64,
+                post_descriptor.post_sub_no as i64
+            );
+            post_descriptors_to_insert.insert(key, post_descriptor);
         }
     }
 
@@ -485,6 +505,16 @@ pub async fn insert_descriptor_db_ids<'a>(
         return Ok(result_map);
     }
 
+    let mut owner_thread_ids = Vec::<i64>::with_capacity(post_descriptors_to_insert.len());
+    let mut post_nos = Vec::<i64>::with_capacity(post_descriptors_to_insert.len());
+    let mut post_sub_nos = Vec::<i64>::with_capacity(post_descriptors_to_insert.len());
+
+    for (owner_thread_id, post_no, post_sub_no) in post_descriptors_to_insert.keys() {
+        owner_thread_ids.push(*owner_thread_id);
+        post_nos.push(*post_no);
+        post_sub_nos.push(*post_sub_no);
+    }
+
     let query = r#"
         INSERT INTO post_descriptors
         (
@@ -492,38 +522,44 @@ pub async fn insert_descriptor_db_ids<'a>(
             post_no,
             post_sub_no
         )
-        VALUES ($1, $2, $3)
+        SELECT * FROM UNNEST($1::bigint[], $2::bigint[], $3::bigint[])
         ON CONFLICT (owner_thread_id, post_no, post_sub_no)
             DO UPDATE SET post_no = post_descriptors.post_no
-        RETURNING id
+        RETURNING
+            id,
+            owner_thread_id,
+            post_no,
+            post_sub_no
     "#;
 
-    // TODO: this might be slow
-    for post_descriptor in post_descriptors_to_insert {
-        let thread_db_id = thread_db_ids.get(&post_descriptor.thread_descriptor);
-        if thread_db_id.is_none() {
-            continue;
-        }
+    let rows = transaction.query(
+        query,
+        &[&owner_thread_ids, &post_nos, &post_sub_nos]
+    ).await?;
 
-        let thread_db_id = thread_db_id.unwrap();
+    #[cfg(test)]
+    POST_DESCRIPTOR_INSERT_STATEMENTS.fetch_add(1, Ordering::SeqCst);
 
-        let id: i64 = transaction.query_one(
-            query,
-            &[
-                &thread_db_id,
-                &(post_descriptor.post_no as i64),
-                &(post_descriptor.post_sub_no as i64)
-            ],
-        ).await?.get(0);
+    let mut inserted_post_descriptors = Vec::<(&PostDescriptor, i64)>::with_capacity(rows.len());
 
-        insert_post_descriptor_into_cache(
-            post_descriptor,
-            id
-        ).await;
+    for row in rows {
+        let id: i64 = row.get(0);
+        let owner_thread_id: i64 = row.get(1);
+        let post_no: i64 = row.get(2);
+        let post_sub_no: i64 = row.get(3);
 
+        let post_descriptor = post_descriptors_to_insert.get(&(owner_thread_id, post_no, post_sub_no));
+        if post_descriptor.is_none() {
+            continue;
+        }
+
+        let post_descriptor = *post_descriptor.unwrap();
+        inserted_post_descriptors.push((post_descriptor, id));
         result_map.insert(post_descriptor, id);
     }
 
+    insert_post_descriptors_into_cache(&inserted_post_descriptors).await;
+
     return Ok(result_map);
 }
 
@@ -535,51 +571,80 @@ async fn insert_thread_descriptor_db_ids(
         return Ok(HashMap::new());
     }
 
-    let thread_descriptors_to_insert = {
+    let mut result_map =
+        HashMap::<ThreadDescriptor, i64>::with_capacity(thread_descriptors.len());
+
+    let mut thread_descriptors_to_insert =
+        HashMap::<(&String, &String, i64), &ThreadDescriptor>::with_capacity(thread_descriptors.len() / 2);
+
+    {
         let td_to_dbid_cache_locked = TD_TO_DBID_CACHE.read().await;
-        let mut thread_descriptors_to_insert =
-            Vec::<&ThreadDescriptor>::with_capacity(thread_descriptors.len() / 2);
 
         for thread_descriptor in thread_descriptors {
             let id = td_to_dbid_cache_locked.get(thread_descriptor);
             if id.is_some() {
-                thread_descriptors_to_insert.push(thread_descriptor);
+                result_map.insert((*thread_descriptor).clone(), *id.unwrap());
+                continue;
             }
-        }
 
-        thread_descriptors_to_insert
-    };
+            let key = (
+                thread_descriptor.site_name(),
+                thread_descriptor.board_code(),
+                thread_descriptor.thread_no as i64
+            );
+            thread_descriptors_to_insert.insert(key, *thread_descriptor);
+        }
+    }
 
     if thread_descriptors_to_insert.is_empty() {
-        return Ok(HashMap::new());
+        // All thread descriptors were already cached
+        return Ok(result_map);
     }
 
-    let mut result_map =
-        HashMap::<ThreadDescriptor, i64>::with_capacity(thread_descriptors_to_insert.len());
+    let mut site_names = Vec::<&str>::with_capacity(thread_descriptors_to_insert.len());
+    let mut board_codes = Vec::<&str>::with_capacity(thread_descriptors_to_insert.len());
+    let mut thread_nos = Vec::<i64>::with_capacity(thread_descriptors_to_insert.len());
 
-    // TODO: slow!!!
-    for thread_descriptor in thread_descriptors_to_insert {
-        let query = r#"
-            INSERT INTO threads
-            (
-                site_name,
-                board_code,
-                thread_no
-            )
-            VALUES ($1, $2, $3)
-            ON CONFLICT (site_name, board_code, thread_no)
-                DO UPDATE SET board_code = threads.board_code
-            RETURNING id
-        "#;
-
-        let id: i64 = transaction.query_one(
-            query,
-            &[
-                &thread_descriptor.site_name(),
-                &thread_descriptor.board_code(),
-                &(thread_descriptor.thread_no as i64)
-            ],
-        ).await?.get(0);
+    for (site_name, board_code, thread_no) in thread_descriptors_to_insert.keys() {
+        site_names.push(site_name.as_str());
+        board_codes.push(board_code.as_str());
+        thread_nos.push(*thread_no);
+    }
+
+    let query = r#"
+        INSERT INTO threads
+        (
+            site_name,
+            board_code,
+            thread_no
+        )
+        SELECT * FROM UNNEST($1::varchar[], $2::varchar[], $3::bigint[])
+        ON CONFLICT (site_name, board_code, thread_no)
+            DO UPDATE SET board_code = threads.board_code
+        RETURNING
+            id,
+            site_name,
+            board_code,
+            thread_no
+    "#;
+
+    let rows = transaction.query(
+        query,
+        &[&site_names, &board_codes, &thread_nos]
+    ).await?;
+
+    for row in rows {
+        let id: i64 = row.get(0);
+        let site_name: String = row.get(1);
+        let board_code: String = row.get(2);
+        let thread_no: i64 = row.get(3);
+
+        let thread_descriptor = thread_descriptors_to_insert.get(&(&site_name, &board_code, thread_no));
+        if thread_descriptor.is_none() {
+            continue;
+        }
+
+        let thread_descriptor = *thread_descriptor.unwrap();
 
         insert_thread_descriptor_into_cache(
             thread_descriptor,
@@ -665,6 +730,22 @@ async fn insert_thread_descriptor_into_cache(thread_descriptor: &ThreadDescripto
     dbid_to_ct_cache_locked.insert(id, chan_thread);
 }
 
+async fn insert_post_descriptors_into_cache(post_descriptors: &Vec<(&PostDescriptor, i64)>) {
+    if post_descriptors.is_empty() {
+        return;
+    }
+
+    let mut pd_to_dbid_cache_locked = PD_TO_DBID_CACHE.write().await;
+    let mut dbid_to_pd_cache_locked = DBID_TO_PD_CACHE.write().await;
+    let mut pd_to_td_cache_locked = PD_TO_TD_CACHE.write().await;
+
+    for (post_descriptor, id) in post_descriptors {
+        insert_pd_for_td(post_descriptor, &mut pd_to_td_cache_locked);
+        pd_to_dbid_cache_locked.insert((*post_descriptor).clone(), *id);
+        dbid_to_pd_cache_locked.insert(*id, (*post_descriptor).clone());
+    }
+}
+
 async fn insert_post_descriptor_into_cache(post_descriptor: &PostDescriptor, id: i64) {
     let mut pd_to_dbid_cache_locked = PD_TO_DBID_CACHE.write().await;
     let mut dbid_to_pd_cache_locked = DBID_TO_PD_CACHE.write().await;
@@ -675,7 +756,15 @@ async fn insert_post_descriptor_into_cache(post_descriptor: &PostDescriptor, id:
     dbid_to_pd_cache_locked.insert(id, post_descriptor.clone());
 }
 
+#[cfg(test)]
+pub fn test_get_post_descriptor_insert_statements() -> usize {
+    return POST_DESCRIPTOR_INSERT_STATEMENTS.load(Ordering::SeqCst);
+}
+
 pub async fn test_cleanup() {
+    #[cfg(test)]
+    POST_DESCRIPTOR_INSERT_STATEMENTS.store(0, Ordering::SeqCst);
+
     let mut dbid_to_ct_cache = DBID_TO_CT_CACHE.write().await;
     let mut dt_to_dbid_cache = TD_TO_DBID_CACHE.write().await;
 
@@ -4,26 +4,46 @@ use std::sync::Arc;
 use tokio_postgres::Row;
 
 use crate::{error, info};
-use crate::helpers::db_helpers;
-use crate::model::data::chan::PostDescriptor;
+use crate::helpers::{db_helpers, regex_helpers};
+use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
 use crate::model::database::db::Database;
-use crate::model::repository::account_repository::{AccountToken, ApplicationType, TokenType};
+use crate::model::repository::account_repository::{AccountId, AccountToken, ApplicationType, TokenType};
 use crate::model::repository::post_descriptor_id_repository;
 use crate::service::thread_watcher::FoundPostReply;
 
-const MAX_NOTIFICATION_DELIVERY_ATTEMPTS: i16 = 25;
+pub const MAX_NOTIFICATION_DELIVERY_ATTEMPTS: i16 = 25;
+pub const NOTIFICATION_CLAIM_TIMEOUT_SECONDS: u64 = 5 * 60;
 
 #[derive(Debug)]
 pub struct PostReply {
     pub owner_post_descriptor_id: i64,
     pub owner_account_id: i64,
+    pub filter_regex: Option<String>
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct UnsentReply {
     pub post_reply_id: i64,
     pub token: AccountToken,
-    pub post_descriptor: PostDescriptor
+    pub post_descriptor: PostDescriptor,
+    pub reply_to: PostDescriptor,
+    pub thread_title: Option<String>
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DeliveryStats {
+    pub total: i64,
+    pub delivered: i64,
+    pub pending: i64,
+    pub failed: i64
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct PendingReply {
+    pub post_reply_id: i64,
+    pub post_descriptor: PostDescriptor,
+    pub reply_to: PostDescriptor,
+    pub thread_title: Option<String>
 }
 
 impl UnsentReply {
@@ -37,6 +57,9 @@ impl UnsentReply {
         let token: String = row.try_get(7)?;
         let application_type: i64 = row.try_get(8)?;
         let token_type: i64 = row.try_get(9)?;
+        let thread_title: Option<String> = row.try_get(10)?;
+        let reply_to_post_no: i64 = row.try_get(11)?;
+        let reply_to_post_sub_no: i64 = row.try_get(12)?;
 
         let post_descriptor = PostDescriptor::new(
             site_name,
@@ -46,6 +69,14 @@ impl UnsentReply {
             post_sub_no as u64,
         );
 
+        // Replies are only searched for within the same thread so the watched post always belongs
+        // to the same thread as the reply.
+        let reply_to = PostDescriptor::from_thread_descriptor(
+            post_descriptor.thread_descriptor.clone(),
+            reply_to_post_no as u64,
+            reply_to_post_sub_no as u64
+        );
+
         let application_type = ApplicationType::from_i64(application_type);
         let token_type = TokenType::from_i64(token_type);
 
@@ -58,16 +89,22 @@ impl UnsentReply {
         let unsent_reply = UnsentReply {
             post_reply_id,
             token: account_token,
-            post_descriptor
+            post_descriptor,
+            reply_to,
+            thread_title
         };
 
         return Ok(unsent_reply);
     }
 }
 
+/// [post_comments] are the comments of the reply posts. They are matched against the filter_regex
+/// of the watches. Replies without a comment never match a filter.
 pub async fn store(
     post_replies: &Vec<PostReply>,
     post_descriptor_db_ids: &HashMap<i64, Vec<&FoundPostReply>>,
+    watched_origins_by_account: &HashMap<i64, HashSet<PostDescriptor>>,
+    post_comments: &HashMap<PostDescriptor, &str>,
     database: &Arc<Database>
 ) -> anyhow::Result<()> {
     if post_replies.is_empty() {
@@ -91,7 +128,7 @@ pub async fn store(
         ) DO NOTHING
     "#;
 
-    let mut connection = database.connection().await?;
+    let mut connection = database.connection_with_retry().await?;
     let transaction = connection.transaction().await?;
 
     for post_reply in post_replies {
@@ -103,23 +140,44 @@ pub async fn store(
             continue;
         }
 
-        let found_post_replies = post_descriptors_to_insert.unwrap();
+        let watched_origins = watched_origins_by_account.get(&post_reply.owner_account_id);
+
+        let found_post_replies = post_descriptors_to_insert.unwrap()
+            .iter()
+            .filter(|found_post_reply| {
+                // Most likely the account replied to its own post, see SUPPRESS_SELF_REPLIES
+                if watched_origins.is_none() {
+                    return true;
+                }
+
+                return !watched_origins.unwrap().contains(&found_post_reply.origin);
+            })
+            .filter(|found_post_reply| {
+                if post_reply.filter_regex.is_none() {
+                    return true;
+                }
+
+                let post_comment = post_comments.get(&found_post_reply.origin).cloned().unwrap_or("");
+                return regex_helpers::matches_filter_regex(&post_reply.filter_regex, post_comment);
+            })
+            .collect::<Vec<&&FoundPostReply>>();
+
+        if found_post_replies.is_empty() {
+            continue;
+        }
 
+        // All of found_post_replies reply to the watched post which already has a db id so only the
+        // origins need to be inserted.
         let origin_post_db_ids = post_descriptor_id_repository::insert_descriptor_db_ids(
             &found_post_replies.iter().map(|fpr| &fpr.origin).collect::<Vec<&PostDescriptor>>(),
             &transaction
         ).await?;
 
-        let reply_to_post_db_ids = post_descriptor_id_repository::insert_descriptor_db_ids(
-            &found_post_replies.iter().map(|fpr| &fpr.replies_to).collect::<Vec<&PostDescriptor>>(),
-            &transaction
-        ).await?;
-
+        let reply_to_post_db_id = post_reply.owner_post_descriptor_id;
         let statement = transaction.prepare(query).await?;
 
         for found_post_reply in found_post_replies {
             let origin_post_db_id = origin_post_db_ids.get(&found_post_reply.origin);
-            let reply_to_post_db_id = reply_to_post_db_ids.get(&found_post_reply.replies_to);
 
             transaction.execute(
                 &statement,
@@ -133,47 +191,87 @@ pub async fn store(
     return Ok(());
 }
 
-pub async fn get_unsent_replies(
-    is_dev_build: bool,
+pub async fn delete_unsent_replies_with_missing_origin(
+    thread_descriptor: &ThreadDescriptor,
+    min_post_no: u64,
+    existing_posts: &Vec<(u64, u64)>,
     database: &Arc<Database>
-) -> anyhow::Result<HashMap<AccountToken, HashSet<UnsentReply>>> {
-    // Damn, this motherfucker is kinda too complex but I have no idea how to simplify it.
-    // The idea here is to extract post_replies.id, account_token.token, thread.site_name,
-    // thread.board_code, thread.thread_no, post_descriptor.post_no, post_descriptor.post_sub_no
-    // but only for post_replies that match post_watches' account_token.application_type.
-    // In other words, accounts can have multiple tokens with different application types
-    // (for example for KurobaExLite there are two application types: Debug and Production, since
-    // the user can have both applications installed on their phone). When we start watching a post
-    // we send what application was it the created this post watch. So when a reply to this watch
-    // comes we only send the reply to the token that is associated with the original post watch.
+) -> anyhow::Result<u64> {
     let query = r#"
-        WITH
-            -- Associate post_reply with account_token.application_type
-            post_reply_application_type AS (
-                SELECT
-                    post_replies.id,
-                    post_replies.owner_account_id,
-                    account_token.application_type
-                FROM post_replies
-                         INNER JOIN accounts account
-                                    ON account.id = post_replies.owner_account_id
-                         INNER JOIN account_tokens account_token
-                                    ON account.id = account_token.owner_account_id
-            ),
-            -- Associate post_replies with post_watch.application_type
-            post_watch_application_type AS (
-                SELECT
-                    post_watch.id,
-                    post_watch.owner_account_id,
-                    post_watch.application_type
-                FROM post_replies
-                         INNER JOIN post_descriptors post_descriptor
-                                    ON post_replies.reply_to_post_descriptor_id = post_descriptor.id
-                         INNER JOIN post_watches post_watch
-                                    ON post_descriptor.id = post_watch.owner_post_descriptor_id
-            )
+        UPDATE post_replies
+        SET deleted_on = now()
+        WHERE post_replies.id IN (
+            SELECT
+                post_reply.id
+            FROM post_replies post_reply
+                INNER JOIN post_descriptors post_descriptor
+                    ON post_descriptor.id = post_reply.owner_post_descriptor_id
+                INNER JOIN threads thread
+                    ON thread.id = post_descriptor.owner_thread_id
+            WHERE
+                thread.site_name = $1
+            AND
+                thread.board_code = $2
+            AND
+                thread.thread_no = $3
+            AND
+                post_descriptor.post_no >= $4
+            AND
+                NOT EXISTS (
+                    SELECT 1
+                    FROM UNNEST($5::bigint[], $6::bigint[]) AS existing_post(post_no, post_sub_no)
+                    WHERE
+                        existing_post.post_no = post_descriptor.post_no
+                    AND
+                        existing_post.post_sub_no = post_descriptor.post_sub_no
+                )
+            AND
+                post_reply.deleted_on IS NULL
+            AND
+                post_reply.notification_delivered_on IS NULL
+        )
+    "#;
 
-        SELECT
+    let existing_post_nos = existing_posts
+        .iter()
+        .map(|(post_no, _)| *post_no as i64)
+        .collect::<Vec<i64>>();
+
+    let existing_post_sub_nos = existing_posts
+        .iter()
+        .map(|(_, post_sub_no)| *post_sub_no as i64)
+        .collect::<Vec<i64>>();
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    let deleted = connection.execute(
+        &statement,
+        &[
+            thread_descriptor.site_name(),
+            thread_descriptor.board_code(),
+            &(thread_descriptor.thread_no as i64),
+            &(min_post_no as i64),
+            &existing_post_nos,
+            &existing_post_sub_nos
+        ]
+    ).await?;
+
+    return Ok(deleted);
+}
+
+// The idea here is to extract post_replies.id, account_token.token, thread.site_name,
+// thread.board_code, thread.thread_no, post_descriptor.post_no, post_descriptor.post_sub_no
+// but only for account_tokens that match post_watches' application_type.
+// In other words, accounts can have multiple tokens with different application types
+// (for example for KurobaExLite there are two application types: Debug and Production, since
+// the user can have both applications installed on their phone) as well as multiple tokens
+// with the same application type (the same application installed on multiple devices).
+// When we start watching a post we send what application was it the created this post watch.
+// So when a reply to this watch comes we send the reply to every token that is associated
+// with the application type of the original post watch.
+const UNSENT_REPLIES_QUERY: &str = r#"
+        SELECT DISTINCT
             post_replies.id,
             account_token.token,
             thread.site_name,
@@ -183,28 +281,27 @@ pub async fn get_unsent_replies(
             post_descriptor.post_sub_no,
             account_token.token,
             account_token.application_type,
-            account_token.token_type
+            account_token.token_type,
+            thread.title,
+            reply_to_post_descriptor.post_no,
+            reply_to_post_descriptor.post_sub_no
         FROM post_replies
             INNER JOIN accounts account
                 ON post_replies.owner_account_id = account.id
+            INNER JOIN post_watches post_watch
+                ON post_watch.owner_post_descriptor_id = post_replies.reply_to_post_descriptor_id
+                AND post_watch.owner_account_id = account.id
             INNER JOIN account_tokens account_token
                 ON account_token.owner_account_id = account.id
+                -- Select only tokens that have the same application_type as post watches they reply to
+                AND account_token.application_type = post_watch.application_type
             INNER JOIN post_descriptors post_descriptor
                 ON post_replies.owner_post_descriptor_id = post_descriptor.id
+            INNER JOIN post_descriptors reply_to_post_descriptor
+                ON post_replies.reply_to_post_descriptor_id = reply_to_post_descriptor.id
             INNER JOIN threads thread
                 ON post_descriptor.owner_thread_id = thread.id
-            INNER JOIN post_watches post_watch
-                ON post_watch.owner_post_descriptor_id = post_replies.reply_to_post_descriptor_id
-            INNER JOIN post_reply_application_type prat
-                ON post_replies.id = prat.id
-            INNER JOIN post_watch_application_type pwat
-                ON post_watch.id = pwat.id
         WHERE
-            prat.owner_account_id = pwat.owner_account_id
-        AND
-            -- Select only post replies that have the same application_type as post watches they reply to
-            prat.application_type = pwat.application_type
-        AND
             post_replies.deleted_on IS NULL
         AND
             post_replies.notification_delivery_attempt < $1
@@ -214,14 +311,152 @@ pub async fn get_unsent_replies(
             account.valid_until > now()
         AND
             account.deleted_on IS NULL
+        AND
+            (account.banned_until IS NULL OR account.banned_until <= now())
+        -- Replies to accounts in their quiet hours are held back (not claimed, so the delivery
+        -- attempts are not incremented) until the quiet hours end
+        AND NOT (
+            account.quiet_hours_start IS NOT NULL
+            AND
+            account.quiet_hours_end IS NOT NULL
+            AND
+            CASE
+                WHEN account.quiet_hours_start <= account.quiet_hours_end THEN
+                    {LOCAL_MINUTE} >= account.quiet_hours_start AND {LOCAL_MINUTE} < account.quiet_hours_end
+                ELSE
+                    {LOCAL_MINUTE} >= account.quiet_hours_start OR {LOCAL_MINUTE} < account.quiet_hours_end
+            END
+        )
+        -- Replies to muted threads are held back the same way until the mute ends
+        AND NOT EXISTS (
+            SELECT 1
+            FROM thread_mutes thread_mute
+            WHERE
+                thread_mute.owner_account_id = account.id
+            AND
+                thread_mute.site_name = thread.site_name
+            AND
+                thread_mute.board_code = thread.board_code
+            AND
+                thread_mute.thread_no = thread.thread_no
+            AND
+                thread_mute.muted_until > now()
+        )
+        {CLAIMED_REPLIES_FILTER}
+"#;
+
+// Current minute of the day in the account's local time
+const LOCAL_MINUTE: &str = r#"
+    MOD(
+        MOD(
+            CAST(EXTRACT(HOUR FROM now() AT TIME ZONE 'UTC') * 60 + EXTRACT(MINUTE FROM now() AT TIME ZONE 'UTC') AS integer)
+                + account.tz_offset_minutes,
+            1440
+        ) + 1440,
+        1440
+    )
+"#;
+
+fn unsent_replies_query(claimed_replies_filter: &str) -> String {
+    return UNSENT_REPLIES_QUERY
+        .replace("{LOCAL_MINUTE}", LOCAL_MINUTE)
+        .replace("{CLAIMED_REPLIES_FILTER}", claimed_replies_filter);
+}
+
+/// Returns the unsent replies that haven't reached MAX_NOTIFICATION_DELIVERY_ATTEMPTS yet. Every
+/// returned reply is claimed by the caller first so that multiple server instances running against
+/// the same database (e.g. during a deploy) never send the same reply twice. Claiming a reply
+/// counts as a delivery attempt. The caller must either mark the claimed replies as notified or
+/// release them via release_claimed_replies(). Claims of senders that died mid way expire after
+/// NOTIFICATION_CLAIM_TIMEOUT_SECONDS.
+pub async fn claim_unsent_replies(
+    is_dev_build: bool,
+    database: &Arc<Database>
+) -> anyhow::Result<HashMap<AccountToken, HashSet<UnsentReply>>> {
+    let claim_query = format!(
+        r#"
+        WITH claimable AS (
+            SELECT post_replies.id
+            FROM post_replies
+            WHERE
+                post_replies.id IN (SELECT unsent_reply.id FROM ({}) AS unsent_reply)
+            AND
+                (post_replies.notification_claimed_until IS NULL
+                    OR post_replies.notification_claimed_until < now())
+            FOR UPDATE SKIP LOCKED
+        )
+        UPDATE post_replies
+        SET
+            notification_delivery_attempt = post_replies.notification_delivery_attempt + 1,
+            notification_claimed_until = now() + make_interval(secs => $2)
+        FROM claimable
+        WHERE post_replies.id = claimable.id
+        RETURNING post_replies.id
+    "#,
+        unsent_replies_query("")
+    );
+
+    let mut connection = database.connection_with_retry().await?;
+    let transaction = connection.transaction().await?;
+
+    let claimed_rows = transaction.query(
+        &claim_query,
+        &[&MAX_NOTIFICATION_DELIVERY_ATTEMPTS, &(NOTIFICATION_CLAIM_TIMEOUT_SECONDS as f64)]
+    ).await?;
+
+    let claimed_post_reply_ids = claimed_rows.iter()
+        .map(|row| row.try_get(0))
+        .collect::<Result<Vec<i64>, tokio_postgres::Error>>()?;
+
+    if claimed_post_reply_ids.is_empty() {
+        transaction.commit().await?;
+
+        info!("No unsent replies to claim found");
+        return Ok(HashMap::new());
+    }
+
+    // The claim bumped notification_delivery_attempt so compare against the value before the claim
+    let query = unsent_replies_query("AND post_replies.id = ANY($2)");
+
+    let rows = transaction.query(
+        &query,
+        &[&(MAX_NOTIFICATION_DELIVERY_ATTEMPTS + 1), &claimed_post_reply_ids]
+    ).await?;
+
+    transaction.commit().await?;
+
+    info!("claim_unsent_replies() claimed {} replies", claimed_post_reply_ids.len());
+    return Ok(map_unsent_replies(is_dev_build, rows));
+}
+
+/// Makes claimed but not sent replies available for the next send attempt right away.
+pub async fn release_claimed_replies(
+    post_reply_ids: &Vec<i64>,
+    database: &Arc<Database>
+) -> anyhow::Result<()> {
+    if post_reply_ids.is_empty() {
+        return Ok(());
+    }
+
+    let query = r#"
+        UPDATE post_replies
+        SET notification_claimed_until = NULL
+        WHERE id = ANY($1)
     "#;
 
-    let connection = database.connection().await?;
-    let rows = connection.query(query, &[&MAX_NOTIFICATION_DELIVERY_ATTEMPTS]).await?;
+    let connection = database.connection_with_retry().await?;
+    connection.execute(query, &[post_reply_ids]).await?;
 
+    return Ok(());
+}
+
+fn map_unsent_replies(
+    is_dev_build: bool,
+    rows: Vec<Row>
+) -> HashMap<AccountToken, HashSet<UnsentReply>> {
     if rows.is_empty() {
         info!("No unsent replies found");
-        return Ok(HashMap::new());
+        return HashMap::new();
     }
 
     let mut unsent_replies = HashMap::<AccountToken, HashSet<UnsentReply>>::with_capacity(rows.len());
@@ -253,36 +488,134 @@ pub async fn get_unsent_replies(
             .insert(unsent_reply.clone());
     }
 
-    return Ok(unsent_replies);
+    return unsent_replies;
 }
 
-pub async fn increment_notification_delivery_attempt(
-    sent_post_reply_ids: &Vec<i64>,
+/// Unlike claim_unsent_replies() this one is not gated on the delivery attempts so that the clients
+/// that missed the push messages (offline, Doze mode etc) can still poll for them.
+pub async fn get_pending_replies(
+    account_id: &AccountId,
+    application_type: &ApplicationType,
     database: &Arc<Database>
-) -> anyhow::Result<()> {
-    info!("increment_notification_delivery_attempt() Got {} sent_post_reply_ids", sent_post_reply_ids.len());
+) -> anyhow::Result<Vec<PendingReply>> {
+    let query = r#"
+        SELECT DISTINCT
+            post_replies.id,
+            thread.site_name,
+            thread.board_code,
+            thread.thread_no,
+            post_descriptor.post_no,
+            post_descriptor.post_sub_no,
+            thread.title,
+            reply_to_post_descriptor.post_no,
+            reply_to_post_descriptor.post_sub_no
+        FROM post_replies
+            INNER JOIN accounts account
+                ON post_replies.owner_account_id = account.id
+            INNER JOIN post_watches post_watch
+                ON post_watch.owner_post_descriptor_id = post_replies.reply_to_post_descriptor_id
+                AND post_watch.owner_account_id = account.id
+            INNER JOIN post_descriptors post_descriptor
+                ON post_replies.owner_post_descriptor_id = post_descriptor.id
+            INNER JOIN post_descriptors reply_to_post_descriptor
+                ON post_replies.reply_to_post_descriptor_id = reply_to_post_descriptor.id
+            INNER JOIN threads thread
+                ON post_descriptor.owner_thread_id = thread.id
+        WHERE
+            account.account_id = $1
+        AND
+            post_watch.application_type = $2
+        AND
+            post_replies.deleted_on IS NULL
+        AND
+            post_replies.notification_delivered_on IS NULL
+        AND
+            account.deleted_on IS NULL
+        ORDER BY post_replies.id
+    "#;
 
-    if sent_post_reply_ids.is_empty() {
-        return Ok(());
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    let rows = connection.query(
+        &statement,
+        &[&account_id.id, &(application_type.clone() as i64)]
+    ).await?;
+
+    let mut pending_replies = Vec::<PendingReply>::with_capacity(rows.len());
+
+    for row in rows {
+        let post_reply_id: i64 = row.try_get(0)?;
+        let site_name: String = row.try_get(1)?;
+        let board_code: String = row.try_get(2)?;
+        let thread_no: i64 = row.try_get(3)?;
+        let post_no: i64 = row.try_get(4)?;
+        let post_sub_no: i64 = row.try_get(5)?;
+        let thread_title: Option<String> = row.try_get(6)?;
+        let reply_to_post_no: i64 = row.try_get(7)?;
+        let reply_to_post_sub_no: i64 = row.try_get(8)?;
+
+        let post_descriptor = PostDescriptor::new(
+            site_name,
+            board_code,
+            thread_no as u64,
+            post_no as u64,
+            post_sub_no as u64,
+        );
+
+        let reply_to = PostDescriptor::from_thread_descriptor(
+            post_descriptor.thread_descriptor.clone(),
+            reply_to_post_no as u64,
+            reply_to_post_sub_no as u64
+        );
+
+        let pending_reply = PendingReply {
+            post_reply_id,
+            post_descriptor,
+            reply_to,
+            thread_title
+        };
+
+        pending_replies.push(pending_reply);
     }
 
+    return Ok(pending_replies);
+}
+
+/// Failed replies are the undelivered ones that reached MAX_NOTIFICATION_DELIVERY_ATTEMPTS and
+/// won't be sent anymore. Deleted replies are not counted.
+pub async fn get_delivery_stats(
+    account_db_id: i64,
+    database: &Arc<Database>
+) -> anyhow::Result<DeliveryStats> {
     let query = r#"
-        UPDATE post_replies
-        SET notification_delivery_attempt = notification_delivery_attempt + 1
-        WHERE id IN ({QUERY_PARAMS})
+        SELECT
+            COUNT(*),
+            COUNT(*) FILTER (WHERE notification_delivered_on IS NOT NULL),
+            COUNT(*) FILTER (
+                WHERE notification_delivered_on IS NULL AND notification_delivery_attempt < $2
+            ),
+            COUNT(*) FILTER (
+                WHERE notification_delivered_on IS NULL AND notification_delivery_attempt >= $2
+            )
+        FROM post_replies
+        WHERE
+            owner_account_id = $1
+        AND
+            deleted_on IS NULL
     "#;
 
-    let (query, db_params) = db_helpers::format_query_params(
-        query,
-        "{QUERY_PARAMS}",
-        &sent_post_reply_ids
-    )?;
+    let connection = database.connection_with_retry().await?;
+    let row = connection.query_one(query, &[&account_db_id, &MAX_NOTIFICATION_DELIVERY_ATTEMPTS]).await?;
 
-    let connection = database.connection().await?;
-    let statement = connection.prepare(&query).await?;
-    connection.execute(&statement, &db_params[..]).await?;
+    let delivery_stats = DeliveryStats {
+        total: row.try_get(0)?,
+        delivered: row.try_get(1)?,
+        pending: row.try_get(2)?,
+        failed: row.try_get(3)?
+    };
 
-    return Ok(());
+    return Ok(delivery_stats);
 }
 
 pub async fn mark_post_replies_as_notified(
@@ -291,6 +624,10 @@ pub async fn mark_post_replies_as_notified(
 ) -> anyhow::Result<()> {
     info!("mark_post_replies_as_notified() Got {} sent_post_reply_ids", sent_post_reply_ids.len());
 
+    if sent_post_reply_ids.is_empty() {
+        return Ok(());
+    }
+
     let query = r#"
         UPDATE post_replies
         SET notification_delivered_on = now()
@@ -303,9 +640,42 @@ pub async fn mark_post_replies_as_notified(
         &sent_post_reply_ids
     )?;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let statement = connection.prepare(&query).await?;
     connection.execute(&statement, &db_params[..]).await?;
 
     return Ok(());
+}
+
+/// Makes the account's replies created during the last [hours] hours available for sending again,
+/// including the ones that were already delivered or ran out of delivery attempts. Replies that are
+/// currently being sent by somebody else are skipped. Returns the amount of replayed replies.
+pub async fn replay_replies(
+    account_db_id: i64,
+    hours: i32,
+    database: &Arc<Database>
+) -> anyhow::Result<u64> {
+    let query = r#"
+        UPDATE post_replies
+        SET
+            notification_delivered_on = NULL,
+            notification_delivery_attempt = 0,
+            notification_claimed_until = NULL
+        WHERE
+            owner_account_id = $1
+        AND
+            deleted_on IS NULL
+        AND
+            created_on > now() - make_interval(hours => $2)
+        AND
+            (notification_delivered_on IS NOT NULL
+                OR notification_claimed_until IS NULL
+                OR notification_claimed_until < now())
+    "#;
+
+    let connection = database.connection_with_retry().await?;
+    let replayed = connection.execute(query, &[&account_db_id, &hours]).await?;
+
+    info!("replay_replies() replayed {} replies of account {}", replayed, account_db_id);
+    return Ok(replayed);
 }
\ No newline at end of file
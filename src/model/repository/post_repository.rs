@@ -1,23 +1,32 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use anyhow::Context;
+use tokio::sync::Mutex;
+use tokio_postgres::Transaction;
 
+use crate::constants;
 use crate::helpers::db_helpers;
 use crate::helpers::string_helpers::FormatToken;
 use crate::info;
 use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
 use crate::model::database::db::Database;
 use crate::model::repository::{account_repository, post_descriptor_id_repository};
-use crate::model::repository::account_repository::{AccountId, ApplicationType};
+use crate::model::repository::account_repository::{Account, AccountId, AccountToken, ApplicationType, FirebaseToken, TokenType};
 use crate::model::repository::post_reply_repository::PostReply;
+use crate::service::fcm_sender;
+
+static MAX_WATCHES_PER_ACCOUNT: AtomicUsize = AtomicUsize::new(constants::DEFAULT_MAX_WATCHES_PER_ACCOUNT);
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum StartWatchingPostResult {
     Ok,
     AccountDoesNotExist,
     AccountHasNoToken,
-    AccountIsNotValid
+    AccountIsNotValid,
+    AccountIsBanned,
+    WatchLimitReached
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -32,11 +41,243 @@ pub async fn start_watching_post(
     account_id: &AccountId,
     application_type: &ApplicationType,
     post_descriptor: &PostDescriptor
+) -> anyhow::Result<StartWatchingPostResult> {
+    return start_watching_post_with_filter(
+        database,
+        account_id,
+        application_type,
+        post_descriptor,
+        None
+    ).await;
+}
+
+/// Replies to a watch with a [filter_regex] are only stored when the reply comment matches the
+/// filter. Watching an already watched post replaces its filter.
+pub async fn start_watching_post_with_filter(
+    database: &Arc<Database>,
+    account_id: &AccountId,
+    application_type: &ApplicationType,
+    post_descriptor: &PostDescriptor,
+    filter_regex: Option<&str>
+) -> anyhow::Result<StartWatchingPostResult> {
+    let account = get_account_for_watching(
+        "start_watching_post()",
+        database,
+        account_id,
+        application_type
+    ).await?;
+
+    if account.is_err() {
+        return Ok(account.err().unwrap());
+    }
+
+    let account = account.unwrap();
+
+    let mut connection = database.connection_with_retry().await?;
+
+    // Committed separately for the same reason as in register_and_start_watching_posts()
+    let transaction = connection.transaction().await?;
+    let owner_post_descriptor_id = post_descriptor_id_repository::insert_post_descriptor_db_id(
+        post_descriptor,
+        &transaction
+    ).await?;
+    transaction.commit().await?;
+
+    let transaction = connection.transaction().await?;
+
+    let query = r#"
+        INSERT INTO post_watches(
+            owner_account_id,
+            owner_post_descriptor_id,
+            application_type,
+            filter_regex
+        )
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (owner_account_id, owner_post_descriptor_id) DO NOTHING
+        RETURNING id
+    "#;
+
+    let account_id = { account.lock().await.id };
+
+    let new_watch_inserted = transaction.query_opt(
+        query,
+        &[
+            &account_id,
+            &owner_post_descriptor_id,
+            &(application_type.clone() as i64),
+            &filter_regex
+        ]
+    ).await?.is_some();
+
+    if !new_watch_inserted {
+        transaction.execute(
+            r#"
+                UPDATE post_watches
+                SET filter_regex = $3
+                WHERE owner_account_id = $1 AND owner_post_descriptor_id = $2
+            "#,
+            &[&account_id, &owner_post_descriptor_id, &filter_regex]
+        ).await?;
+
+        transaction.commit().await?;
+
+        info!("start_watching_post() Post watch {} already exists in the database", post_descriptor);
+        return Ok(StartWatchingPostResult::Ok);
+    }
+
+    if watch_limit_exceeded(account_id, &transaction).await? {
+        transaction.rollback().await?;
+
+        info!(
+            "start_watching_post() Post watch {} was not created because the account has too many watches",
+            post_descriptor
+        );
+        return Ok(StartWatchingPostResult::WatchLimitReached);
+    }
+
+    transaction.commit().await?;
+
+    let account_tokens = {
+        account.lock().await
+            .get_account_tokens(application_type)
+            .into_iter()
+            .cloned()
+            .collect::<Vec<AccountToken>>()
+    };
+
+    fcm_sender::enqueue_watch_confirmations(&account_tokens, &vec![post_descriptor.clone()]).await;
+
+    info!(
+        "start_watching_post() Created new post watch for post {} for user with {} tokens",
+        post_descriptor,
+        account_tokens.len()
+    );
+
+    return Ok(StartWatchingPostResult::Ok);
+}
+
+/// Same as [start_watching_post] but validates the account only once and inserts all the post
+/// watches in a single transaction.
+pub async fn start_watching_posts(
+    database: &Arc<Database>,
+    account_id: &AccountId,
+    application_type: &ApplicationType,
+    post_descriptors: &Vec<PostDescriptor>
+) -> anyhow::Result<StartWatchingPostResult> {
+    let account = get_account_for_watching(
+        "start_watching_posts()",
+        database,
+        account_id,
+        application_type
+    ).await?;
+
+    if account.is_err() {
+        return Ok(account.err().unwrap());
+    }
+
+    let account = account.unwrap();
+
+    if post_descriptors.is_empty() {
+        return Ok(StartWatchingPostResult::Ok);
+    }
+
+    let mut connection = database.connection_with_retry().await?;
+
+    // Committed separately for the same reason as in register_and_start_watching_posts()
+    let post_descriptor_refs = post_descriptors.iter().collect::<Vec<&PostDescriptor>>();
+
+    let transaction = connection.transaction().await?;
+    let post_descriptor_db_ids = post_descriptor_id_repository::insert_descriptor_db_ids(
+        &post_descriptor_refs,
+        &transaction
+    ).await?;
+    transaction.commit().await?;
+
+    let transaction = connection.transaction().await?;
+
+    let owner_post_descriptor_ids = post_descriptor_db_ids.values()
+        .cloned()
+        .collect::<HashSet<i64>>()
+        .into_iter()
+        .collect::<Vec<i64>>();
+
+    let query = r#"
+        INSERT INTO post_watches(
+            owner_account_id,
+            owner_post_descriptor_id,
+            application_type
+        )
+        SELECT $1::bigint, owner_post_descriptor_id, $3::bigint
+        FROM UNNEST($2::bigint[]) AS owner_post_descriptor_id
+        ON CONFLICT (owner_account_id, owner_post_descriptor_id) DO NOTHING
+        RETURNING owner_post_descriptor_id
+    "#;
+
+    let account_db_id = { account.lock().await.id };
+
+    let inserted_post_descriptor_ids = transaction.query(
+        query,
+        &[
+            &account_db_id,
+            &owner_post_descriptor_ids,
+            &(application_type.clone() as i64)
+        ]
+    )
+        .await?
+        .iter()
+        .map(|row| row.try_get::<usize, i64>(0))
+        .collect::<Result<HashSet<i64>, tokio_postgres::Error>>()?;
+
+    let inserted = inserted_post_descriptor_ids.len();
+
+    if inserted > 0 && watch_limit_exceeded(account_db_id, &transaction).await? {
+        transaction.rollback().await?;
+
+        info!(
+            "start_watching_posts() Post watches were not created because account \'{}\' has too many watches",
+            account_id.format_token()
+        );
+        return Ok(StartWatchingPostResult::WatchLimitReached);
+    }
+
+    transaction.commit().await?;
+
+    let account_tokens = {
+        account.lock().await
+            .get_account_tokens(application_type)
+            .into_iter()
+            .cloned()
+            .collect::<Vec<AccountToken>>()
+    };
+
+    fcm_sender::enqueue_watch_confirmations(
+        &account_tokens,
+        &newly_watched_post_descriptors(&post_descriptor_db_ids, &inserted_post_descriptor_ids)
+    ).await;
+
+    info!(
+        "start_watching_posts() Created {} new post watches out of {} for account \'{}\'",
+        inserted,
+        post_descriptors.len(),
+        account_id.format_token()
+    );
+
+    return Ok(StartWatchingPostResult::Ok);
+}
+
+/// Stores [firebase_token] and starts watching [post_descriptors] in a single transaction so
+/// either both are stored or neither is. Calling it again with the same arguments changes nothing.
+pub async fn register_and_start_watching_posts(
+    database: &Arc<Database>,
+    account_id: &AccountId,
+    application_type: &ApplicationType,
+    firebase_token: &FirebaseToken,
+    post_descriptors: &Vec<PostDescriptor>
 ) -> anyhow::Result<StartWatchingPostResult> {
     let account = account_repository::get_account(account_id, database).await?;
     if account.is_none() {
         info!(
-            "start_watching_post() account with id \'{}\' does not exist",
+            "register_and_start_watching_posts() account with id \'{}\' does not exist",
             account_id.format_token()
         );
 
@@ -45,82 +286,236 @@ pub async fn start_watching_post(
 
     let account = account.unwrap();
 
-    let has_token = { account.lock().await.account_token(application_type).is_some() };
-    if !has_token {
+    // The token is not checked here since it's about to be stored
+    let (account_db_id, is_banned, valid_until) = {
+        let account_locked = account.lock().await;
+        (account_locked.id, account_locked.is_banned(), account_locked.valid_until)
+    };
+
+    if is_banned {
         info!(
-            "start_watching_post() account with id \'{}\' has no token",
-            account_id.format_token(),
+            "register_and_start_watching_posts() account with id \'{}\' is banned",
+            account_id.format_token()
         );
 
-        return Ok(StartWatchingPostResult::AccountHasNoToken);
+        return Ok(StartWatchingPostResult::AccountIsBanned);
     }
 
-    let is_valid = { account.lock().await.is_valid(application_type) };
-    if !is_valid {
-        let validation_status = { account.lock().await.validation_status(application_type) };
-
+    if valid_until.is_none() || valid_until.unwrap() < chrono::Utc::now() {
         info!(
-            "start_watching_post() account with id \'{}\' is not valid (status: {})",
+            "register_and_start_watching_posts() account with id \'{}\' is not valid (valid_until: {:?})",
             account_id.format_token(),
-            validation_status.unwrap()
+            valid_until
         );
 
         return Ok(StartWatchingPostResult::AccountIsNotValid);
     }
 
-    let mut connection = database.connection().await?;
+    let mut connection = database.connection_with_retry().await?;
+
+    // Post descriptors are not owned by the account and their ids are cached as soon as they are
+    // inserted so they are committed separately, otherwise a rollback would leave the cache
+    // pointing to rows that don't exist.
+    let post_descriptor_refs = post_descriptors.iter().collect::<Vec<&PostDescriptor>>();
+
     let transaction = connection.transaction().await?;
+    let post_descriptor_db_ids = post_descriptor_id_repository::insert_descriptor_db_ids(
+        &post_descriptor_refs,
+        &transaction
+    ).await?;
+    transaction.commit().await?;
 
-    let owner_post_descriptor_id = post_descriptor_id_repository::insert_post_descriptor_db_id(
-        post_descriptor,
+    let transaction = connection.transaction().await?;
+
+    let token_inserted = account_repository::insert_firebase_token(
+        account_db_id,
+        application_type,
+        firebase_token,
         &transaction
     ).await?;
 
+    let owner_post_descriptor_ids = post_descriptor_db_ids.values()
+        .cloned()
+        .collect::<HashSet<i64>>()
+        .into_iter()
+        .collect::<Vec<i64>>();
+
     let query = r#"
         INSERT INTO post_watches(
             owner_account_id,
             owner_post_descriptor_id,
             application_type
         )
-        VALUES ($1, $2, $3)
+        SELECT $1::bigint, owner_post_descriptor_id, $3::bigint
+        FROM UNNEST($2::bigint[]) AS owner_post_descriptor_id
         ON CONFLICT (owner_account_id, owner_post_descriptor_id) DO NOTHING
-        RETURNING id
+        RETURNING owner_post_descriptor_id
     "#;
 
-    let account_id = { account.lock().await.id };
-
-    let new_watch_inserted = transaction.query_opt(
+    let inserted_post_descriptor_ids = transaction.query(
         query,
         &[
-            &account_id,
-            &owner_post_descriptor_id,
+            &account_db_id,
+            &owner_post_descriptor_ids,
             &(application_type.clone() as i64)
         ]
-    ).await?.is_some();
+    )
+        .await?
+        .iter()
+        .map(|row| row.try_get::<usize, i64>(0))
+        .collect::<Result<HashSet<i64>, tokio_postgres::Error>>()?;
 
-    if !new_watch_inserted {
+    let inserted = inserted_post_descriptor_ids.len();
+
+    if inserted > 0 && watch_limit_exceeded(account_db_id, &transaction).await? {
         transaction.rollback().await?;
 
-        info!("start_watching_post() Post watch {} already exists in the database", post_descriptor);
-        return Ok(StartWatchingPostResult::Ok);
+        info!(
+            "register_and_start_watching_posts() Nothing was stored because account \'{}\' has too many watches",
+            account_id.format_token()
+        );
+        return Ok(StartWatchingPostResult::WatchLimitReached);
     }
 
     transaction.commit().await?;
 
-    let token = {
-        let acc = account.lock().await;
-        acc.get_account_token(application_type).unwrap().clone()
+    account_repository::on_firebase_token_inserted(
+        account_id,
+        application_type,
+        firebase_token,
+        token_inserted
+    ).await;
+
+    let account_token = AccountToken {
+        token: firebase_token.token.clone(),
+        application_type: application_type.clone(),
+        token_type: TokenType::Firebase
     };
 
+    fcm_sender::enqueue_watch_confirmations(
+        &vec![account_token],
+        &newly_watched_post_descriptors(&post_descriptor_db_ids, &inserted_post_descriptor_ids)
+    ).await;
+
     info!(
-        "start_watching_post() Created new post watch for post {} for user with token {}",
-        post_descriptor,
-        token
+        "register_and_start_watching_posts() Stored token \'{}\' and created {} new post watches out of {} for account \'{}\'",
+        firebase_token.format_token(),
+        inserted,
+        post_descriptors.len(),
+        account_id.format_token()
     );
 
     return Ok(StartWatchingPostResult::Ok);
 }
 
+pub fn set_max_watches_per_account(max_watches: usize) {
+    MAX_WATCHES_PER_ACCOUNT.store(max_watches, Ordering::Relaxed);
+}
+
+pub fn max_watches_per_account() -> usize {
+    return MAX_WATCHES_PER_ACCOUNT.load(Ordering::Relaxed);
+}
+
+pub async fn count_account_watches(
+    database: &Arc<Database>,
+    account_db_id: i64
+) -> anyhow::Result<i64> {
+    let query = r#"
+        SELECT COUNT(post_watch.id)
+        FROM post_watches post_watch
+        WHERE post_watch.owner_account_id = $1
+    "#;
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    let watches_count: i64 = connection.query_one(&statement, &[&account_db_id]).await?.try_get(0)?;
+    return Ok(watches_count);
+}
+
+fn newly_watched_post_descriptors(
+    post_descriptor_db_ids: &HashMap<&PostDescriptor, i64>,
+    inserted_post_descriptor_ids: &HashSet<i64>
+) -> Vec<PostDescriptor> {
+    return post_descriptor_db_ids.iter()
+        .filter(|(_, post_descriptor_db_id)| inserted_post_descriptor_ids.contains(*post_descriptor_db_id))
+        .map(|(post_descriptor, _)| (*post_descriptor).clone())
+        .collect::<Vec<PostDescriptor>>();
+}
+
+/// Must be called after the new watches were inserted but before the transaction is committed.
+async fn watch_limit_exceeded(
+    account_db_id: i64,
+    transaction: &Transaction<'_>
+) -> anyhow::Result<bool> {
+    let query = r#"
+        SELECT COUNT(post_watch.id)
+        FROM post_watches post_watch
+        WHERE post_watch.owner_account_id = $1
+    "#;
+
+    let watches_count: i64 = transaction.query_one(query, &[&account_db_id]).await?.get(0);
+    return Ok(watches_count as usize > max_watches_per_account());
+}
+
+async fn get_account_for_watching(
+    caller: &str,
+    database: &Arc<Database>,
+    account_id: &AccountId,
+    application_type: &ApplicationType
+) -> anyhow::Result<Result<Arc<Mutex<Account>>, StartWatchingPostResult>> {
+    let account = account_repository::get_account(account_id, database).await?;
+    if account.is_none() {
+        info!(
+            "{} account with id \'{}\' does not exist",
+            caller,
+            account_id.format_token()
+        );
+
+        return Ok(Err(StartWatchingPostResult::AccountDoesNotExist));
+    }
+
+    let account = account.unwrap();
+
+    let is_banned = { account.lock().await.is_banned() };
+    if is_banned {
+        info!(
+            "{} account with id \'{}\' is banned",
+            caller,
+            account_id.format_token()
+        );
+
+        return Ok(Err(StartWatchingPostResult::AccountIsBanned));
+    }
+
+    let has_token = { !account.lock().await.get_account_tokens(application_type).is_empty() };
+    if !has_token {
+        info!(
+            "{} account with id \'{}\' has no token",
+            caller,
+            account_id.format_token(),
+        );
+
+        return Ok(Err(StartWatchingPostResult::AccountHasNoToken));
+    }
+
+    let is_valid = { account.lock().await.is_valid(application_type) };
+    if !is_valid {
+        let validation_status = { account.lock().await.validation_status(application_type) };
+
+        info!(
+            "{} account with id \'{}\' is not valid (status: {})",
+            caller,
+            account_id.format_token(),
+            validation_status.unwrap()
+        );
+
+        return Ok(Err(StartWatchingPostResult::AccountIsNotValid));
+    }
+
+    return Ok(Ok(account));
+}
+
 pub async fn stop_watching_post(
     database: &Arc<Database>,
     account_id: &AccountId,
@@ -152,7 +547,7 @@ pub async fn stop_watching_post(
         return Ok(StopWatchingPostResult::AccountIsNotValid);
     }
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
 
     let owner_post_descriptor_id = post_descriptor_id_repository::get_post_descriptor_db_id(
         post_descriptor
@@ -197,15 +592,12 @@ pub async fn stop_watching_post(
         ]
     ).await?;
 
-    let token = {
-        let acc = account.lock().await;
-        acc.get_account_token(application_type).unwrap().clone()
-    };
+    let tokens_count = { account.lock().await.get_account_tokens(application_type).len() };
 
     info!(
-        "stop_watching_post() Deleted {} post watches for user with token {}",
+        "stop_watching_post() Deleted {} post watches for user with {} tokens",
         deleted,
-        token
+        tokens_count
     );
 
     return Ok(StopWatchingPostResult::Ok);
@@ -214,19 +606,30 @@ pub async fn stop_watching_post(
 pub async fn get_all_watched_threads(
     database: &Arc<Database>
 ) -> anyhow::Result<Vec<ThreadDescriptor>> {
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
 
+    // Only threads that have at least one watcher that can actually receive notifications (the
+    // same account filter claim_unsent_replies() uses), otherwise we'd keep polling threads nobody
+    // will ever get a reply from.
     let query = r#"
-        SELECT
+        SELECT DISTINCT
             post_descriptor.id
         FROM
             threads AS thread
         INNER JOIN post_descriptors post_descriptor
             ON thread.id = post_descriptor.owner_thread_id
+        INNER JOIN post_watches post_watch
+            ON post_watch.owner_post_descriptor_id = post_descriptor.id
+        INNER JOIN accounts account
+            ON account.id = post_watch.owner_account_id
         WHERE
             thread.is_dead IS NOT TRUE
         AND
             thread.deleted_on is NULL
+        AND
+            account.valid_until > now()
+        AND
+            account.deleted_on IS NULL
     "#;
 
     let rows = connection.query(query, &[]).await?;
@@ -273,11 +676,13 @@ pub async fn mark_thread_as_dead(
 
     let query = r#"
         UPDATE threads
-        SET is_dead = TRUE
+        SET
+            is_dead = TRUE,
+            died_on = COALESCE(threads.died_on, now())
         WHERE threads.id = $1
     "#;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let statement = connection.prepare(query).await?;
 
     connection.execute(&statement, &[&thread_db_id])
@@ -293,30 +698,181 @@ pub async fn mark_thread_as_dead(
     return Ok(());
 }
 
+/// Makes every account that watches any post of [from_thread_descriptor] watch [to_post_descriptor]
+/// (one watch per account, the filter of the oldest watch is kept). The old watches are kept so
+/// that replies that were not sent yet are still delivered, they are removed together with the
+/// dead thread. Accounts that are at the watch limit are skipped. Returns the amount of created
+/// watches.
+pub async fn migrate_thread_watches(
+    database: &Arc<Database>,
+    from_thread_descriptor: &ThreadDescriptor,
+    to_post_descriptor: &PostDescriptor
+) -> anyhow::Result<u64> {
+    let mut connection = database.connection_with_retry().await?;
+
+    // Committed separately for the same reason as in register_and_start_watching_posts()
+    let transaction = connection.transaction().await?;
+    let to_post_descriptor_id = post_descriptor_id_repository::insert_post_descriptor_db_id(
+        to_post_descriptor,
+        &transaction
+    ).await?;
+    transaction.commit().await?;
+
+    let transaction = connection.transaction().await?;
+
+    let query = r#"
+        INSERT INTO post_watches(
+            owner_account_id,
+            owner_post_descriptor_id,
+            application_type,
+            filter_regex
+        )
+        SELECT DISTINCT ON (post_watch.owner_account_id)
+            post_watch.owner_account_id,
+            $1::bigint,
+            post_watch.application_type,
+            post_watch.filter_regex
+        FROM threads thread
+            INNER JOIN post_descriptors post_descriptor
+                ON post_descriptor.owner_thread_id = thread.id
+            INNER JOIN post_watches post_watch
+                ON post_watch.owner_post_descriptor_id = post_descriptor.id
+        WHERE
+            thread.site_name = $2
+        AND
+            thread.board_code = $3
+        AND
+            thread.thread_no = $4
+        AND
+            (
+                SELECT COUNT(account_watch.id)
+                FROM post_watches account_watch
+                WHERE account_watch.owner_account_id = post_watch.owner_account_id
+            ) < $5
+        ORDER BY post_watch.owner_account_id, post_watch.id
+        ON CONFLICT (owner_account_id, owner_post_descriptor_id) DO NOTHING
+    "#;
+
+    let migrated = transaction.execute(
+        query,
+        &[
+            &to_post_descriptor_id,
+            from_thread_descriptor.site_name(),
+            from_thread_descriptor.board_code(),
+            &(from_thread_descriptor.thread_no as i64),
+            &(max_watches_per_account() as i64)
+        ]
+    )
+        .await
+        .with_context(|| {
+            return format!(
+                "Failed to migrate watches of thread {} to post {}",
+                from_thread_descriptor,
+                to_post_descriptor
+            );
+        })?;
+
+    transaction.commit().await?;
+
+    info!(
+        "migrate_thread_watches() migrated {} watches from thread {} to post {}",
+        migrated,
+        from_thread_descriptor,
+        to_post_descriptor
+    );
+
+    return Ok(migrated);
+}
+
 pub async fn delete_all_dead_threads() -> usize {
     return post_descriptor_id_repository::delete_all_dead_threads().await;
 }
 
+pub async fn purge_dead_threads(
+    database: &Arc<Database>,
+    retention_days: u64
+) -> anyhow::Result<usize> {
+    // post_descriptors, post_replies and post_watches are removed by the "on delete cascade"
+    // foreign keys. Threads that are still watched by an existing and valid account are kept.
+    let query = r#"
+        DELETE FROM threads
+        WHERE
+            threads.is_dead = TRUE
+        AND
+            threads.died_on IS NOT NULL
+        AND
+            threads.died_on < now() - make_interval(days => $1)
+        AND NOT EXISTS (
+            SELECT 1
+            FROM post_descriptors post_descriptor
+                INNER JOIN post_watches watch
+                    ON watch.owner_post_descriptor_id = post_descriptor.id
+                INNER JOIN accounts account
+                    ON account.id = watch.owner_account_id
+            WHERE
+                post_descriptor.owner_thread_id = threads.id
+            AND
+                account.deleted_on IS NULL
+            AND
+                (account.valid_until IS NULL OR account.valid_until > now())
+        )
+        RETURNING
+            threads.site_name,
+            threads.board_code,
+            threads.thread_no
+    "#;
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    let rows = connection.query(&statement, &[&(retention_days as i32)])
+        .await
+        .context("purge_dead_threads() Failed to delete dead threads")?;
+
+    for row in &rows {
+        let site_name: String = row.get(0);
+        let board_code: String = row.get(1);
+        let thread_no: i64 = row.get(2);
+
+        let thread_descriptor = ThreadDescriptor::new(site_name, board_code, thread_no as u64);
+        post_descriptor_id_repository::delete_all_thread_posts(&thread_descriptor).await;
+    }
+
+    return Ok(rows.len());
+}
+
 pub async fn find_new_replies(
     thread_descriptor: &ThreadDescriptor,
     database: &Arc<Database>,
     post_descriptor_db_ids: &Vec<i64>
 ) -> anyhow::Result<Vec<PostReply>> {
+    // Starts from post_watches (post_watches_owner_post_descriptor_id_idx) so that only the
+    // watches of the given posts are looked at. A watched post whose replies were all deleted is
+    // skipped, replies are only looked up to check that and are not returned.
     let query = r#"
         SELECT
-            post_descriptor.id,
-            account.id
-        FROM threads
-            LEFT JOIN post_descriptors post_descriptor on post_descriptor.owner_thread_id = threads.id
-            LEFT JOIN post_watches watch on watch.owner_post_descriptor_id = post_descriptor.id
-            LEFT JOIN accounts account on watch.owner_account_id = account.id
-            LEFT JOIN post_replies post_reply on post_descriptor.id = post_reply.owner_post_descriptor_id
+            watch.owner_post_descriptor_id,
+            account.id,
+            watch.filter_regex
+        FROM post_watches watch
+            INNER JOIN accounts account ON account.id = watch.owner_account_id
         WHERE
-            post_descriptor.id IN ({QUERY_PARAMS})
-        AND
-            post_reply.deleted_on IS NULL
-        AND
-            account.id IS NOT NULL
+            watch.owner_post_descriptor_id IN ({QUERY_PARAMS})
+        AND (
+            NOT EXISTS (
+                SELECT 1
+                FROM post_replies post_reply
+                WHERE post_reply.owner_post_descriptor_id = watch.owner_post_descriptor_id
+            )
+            OR EXISTS (
+                SELECT 1
+                FROM post_replies post_reply
+                WHERE
+                    post_reply.owner_post_descriptor_id = watch.owner_post_descriptor_id
+                AND
+                    post_reply.deleted_on IS NULL
+            )
+        )
     "#;
 
     let (query, query_params) = db_helpers::format_query_params(
@@ -325,7 +881,7 @@ pub async fn find_new_replies(
         &post_descriptor_db_ids
     )?;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let statement = connection.prepare(query.as_str()).await?;
 
     let rows = connection.query(&statement, &query_params[..]).await?;
@@ -339,14 +895,58 @@ pub async fn find_new_replies(
     for row in rows {
         let post_descriptor_id: i64 = row.get(0);
         let account_id: i64 = row.get(1);
+        let filter_regex: Option<String> = row.get(2);
 
         let post_reply = PostReply {
             owner_post_descriptor_id: post_descriptor_id,
-            owner_account_id: account_id
+            owner_account_id: account_id,
+            filter_regex
         };
 
         post_replies.push(post_reply);
     }
 
     return Ok(post_replies);
+}
+
+/// Returns the ids of the accounts watching each of [post_descriptor_db_ids], posts that nobody
+/// watches are not included.
+pub async fn find_watching_accounts(
+    post_descriptor_db_ids: &Vec<i64>,
+    database: &Arc<Database>
+) -> anyhow::Result<HashMap<i64, Vec<i64>>> {
+    if post_descriptor_db_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let query = r#"
+        SELECT
+            watch.owner_post_descriptor_id,
+            watch.owner_account_id
+        FROM post_watches watch
+        WHERE watch.owner_post_descriptor_id IN ({QUERY_PARAMS})
+    "#;
+
+    let (query, query_params) = db_helpers::format_query_params(
+        query,
+        "{QUERY_PARAMS}",
+        post_descriptor_db_ids
+    )?;
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query.as_str()).await?;
+
+    let rows = connection.query(&statement, &query_params[..]).await?;
+    let mut watching_accounts = HashMap::<i64, Vec<i64>>::with_capacity(rows.len());
+
+    for row in rows {
+        let post_descriptor_id: i64 = row.get(0);
+        let account_id: i64 = row.get(1);
+
+        watching_accounts.entry(post_descriptor_id)
+            .or_insert_with(|| Vec::new())
+            .push(account_id);
+    }
+
+    return Ok(watching_accounts);
 }
\ No newline at end of file
@@ -1,30 +1,205 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::model::data::chan::{PostDescriptor, SiteDescriptor, ThreadDescriptor};
+use anyhow::anyhow;
+use tokio::sync::{OwnedSemaphorePermit, RwLock, Semaphore};
+
+use crate::{constants, error};
+use crate::model::data::chan::{CatalogDescriptor, ChanBoard, PostDescriptor, SiteDescriptor, ThreadDescriptor};
 use crate::model::database::db::Database;
 use crate::model::imageboards::base_imageboard;
-use crate::model::imageboards::base_imageboard::{Imageboard, ThreadLoadResult};
+use crate::model::imageboards::base_imageboard::{CatalogLoadResult, Imageboard, ThreadLoadResult};
 use crate::model::imageboards::chan4::Chan4;
 use crate::model::imageboards::dvach::Dvach;
 
 pub type ImageboardSynced = Arc<dyn Imageboard + Sync + Send>;
 
 pub struct SiteRepository {
-    sites: HashMap<String, ImageboardSynced>
+    http_client: Arc<reqwest::Client>,
+    sites: HashMap<String, ImageboardSynced>,
+    disabled_sites: HashSet<String>,
+    site_semaphores: HashMap<String, Arc<Semaphore>>,
+    boards_cache: RwLock<HashMap<String, CachedBoards>>,
+    boards_cache_ttl: Duration
+}
+
+struct CachedBoards {
+    boards: Vec<ChanBoard>,
+    fetched_at: Instant
 }
 
 impl SiteRepository {
-    pub fn new() -> SiteRepository {
-        let mut sites = HashMap::<String, ImageboardSynced>::new();
+    pub fn new(http_client: &Arc<reqwest::Client>) -> SiteRepository {
+        return SiteRepository::with_concurrency_limits(http_client, &HashMap::new());
+    }
+
+    /// [site_concurrency_limits] is the max amount of concurrent requests per site name. Sites
+    /// that are not in the map use DEFAULT_SITE_CONCURRENCY_LIMIT.
+    pub fn with_concurrency_limits(
+        http_client: &Arc<reqwest::Client>,
+        site_concurrency_limits: &HashMap<String, usize>
+    ) -> SiteRepository {
+        return SiteRepository::with_imageboards(
+            http_client,
+            all_imageboards(),
+            site_concurrency_limits,
+            Duration::from_secs(constants::DEFAULT_BOARDS_CACHE_TTL_SECONDS)
+        );
+    }
+
+    /// Same as with_concurrency_limits() but only the sites from [enabled_sites] are registered,
+    /// the rest is treated as not supported. An empty [enabled_sites] enables every site.
+    pub fn with_enabled_sites(
+        http_client: &Arc<reqwest::Client>,
+        site_concurrency_limits: &HashMap<String, usize>,
+        enabled_sites: &Vec<String>
+    ) -> anyhow::Result<SiteRepository> {
+        let imageboards = all_imageboards();
+
+        for enabled_site in enabled_sites {
+            let is_known_site = imageboards.iter()
+                .any(|imageboard| imageboard.name() == enabled_site);
+
+            if !is_known_site {
+                return Err(anyhow!("Unknown site \'{}\' in enabled sites", enabled_site));
+            }
+        }
+
+        if enabled_sites.is_empty() {
+            return Ok(SiteRepository::with_concurrency_limits(http_client, site_concurrency_limits));
+        }
+
+        let (imageboards, disabled_imageboards): (Vec<ImageboardSynced>, Vec<ImageboardSynced>) = imageboards
+            .into_iter()
+            .partition(|imageboard| enabled_sites.iter().any(|enabled_site| enabled_site == imageboard.name()));
+
+        let mut site_repository = SiteRepository::with_imageboards(
+            http_client,
+            imageboards,
+            site_concurrency_limits,
+            Duration::from_secs(constants::DEFAULT_BOARDS_CACHE_TTL_SECONDS)
+        );
+
+        site_repository.disabled_sites = disabled_imageboards.iter()
+            .map(|imageboard| imageboard.name().to_string())
+            .collect::<HashSet<String>>();
+
+        return Ok(site_repository);
+    }
+
+    pub fn with_imageboards(
+        http_client: &Arc<reqwest::Client>,
+        imageboards: Vec<ImageboardSynced>,
+        site_concurrency_limits: &HashMap<String, usize>,
+        boards_cache_ttl: Duration
+    ) -> SiteRepository {
+        let sites = imageboards.into_iter()
+            .map(|imageboard| (imageboard.name().to_string(), imageboard))
+            .collect::<HashMap<String, ImageboardSynced>>();
+
+        let site_semaphores = sites.keys()
+            .map(|site_name| {
+                let permits = site_concurrency_limits.get(site_name)
+                    .cloned()
+                    .unwrap_or(constants::DEFAULT_SITE_CONCURRENCY_LIMIT);
+
+                return (site_name.clone(), Arc::new(Semaphore::new(permits)));
+            })
+            .collect::<HashMap<String, Arc<Semaphore>>>();
+
+        return SiteRepository {
+            http_client: http_client.clone(),
+            sites,
+            disabled_sites: HashSet::new(),
+            site_semaphores,
+            boards_cache: RwLock::new(HashMap::new()),
+            boards_cache_ttl
+        };
+    }
+
+    /// The client shared by the whole server, the ThreadWatcher is given the same one.
+    pub fn http_client(&self) -> &Arc<reqwest::Client> {
+        return &self.http_client;
+    }
+
+    /// Waits until a request to the site can be made. The request may be made for as long as the
+    /// returned permit is alive. Returns None for unsupported sites.
+    pub async fn acquire_site_permit(&self, site_descriptor: &SiteDescriptor) -> Option<OwnedSemaphorePermit> {
+        let semaphore = self.site_semaphores.get(site_descriptor.site_name());
+        if semaphore.is_none() {
+            return None;
+        }
+
+        return semaphore.unwrap().clone().acquire_owned().await.ok();
+    }
+
+    /// Disabled sites are supported by the server but were turned off by the operator, unlike
+    /// unsupported ones their threads must be kept alive.
+    pub fn is_site_disabled(&self, site_descriptor: &SiteDescriptor) -> bool {
+        return self.disabled_sites.contains(site_descriptor.site_name());
+    }
+
+    pub fn supported_site_names(&self) -> Vec<String> {
+        let mut site_names = self.sites.keys()
+            .cloned()
+            .collect::<Vec<String>>();
+
+        site_names.sort();
+        return site_names;
+    }
+
+    /// Returns the boards of every supported site sorted by site name. Board lists are cached for
+    /// [boards_cache_ttl] and when a site fails to return its boards the last cached list is used.
+    pub async fn get_boards(&self) -> Vec<(String, Vec<ChanBoard>)> {
+        let mut result = Vec::<(String, Vec<ChanBoard>)>::with_capacity(self.sites.len());
+
+        for site_name in self.supported_site_names() {
+            let boards = self.get_site_boards(&site_name).await;
+            result.push((site_name, boards));
+        }
+
+        return result;
+    }
 
-        let chan4 = Chan4 {};
-        sites.insert(chan4.name().to_string(), Arc::new(chan4));
+    async fn get_site_boards(&self, site_name: &String) -> Vec<ChanBoard> {
+        {
+            let boards_cache = self.boards_cache.read().await;
+            let cached_boards = boards_cache.get(site_name);
 
-        let dvach = Dvach {};
-        sites.insert(dvach.name().to_string(), Arc::new(dvach));
+            if cached_boards.is_some() {
+                let cached_boards = cached_boards.unwrap();
+                if cached_boards.fetched_at.elapsed() < self.boards_cache_ttl {
+                    return cached_boards.boards.clone();
+                }
+            }
+        }
+
+        let imageboard = self.sites.get(site_name).unwrap();
+        let fetch_result = imageboard.fetch_boards(&self.http_client).await;
 
-        return SiteRepository { sites };
+        let mut boards_cache = self.boards_cache.write().await;
+
+        if fetch_result.is_err() {
+            error!(
+                "get_site_boards({}) failed to fetch boards, error: {}",
+                site_name,
+                fetch_result.err().unwrap()
+            );
+
+            return boards_cache.get(site_name)
+                .map(|cached_boards| cached_boards.boards.clone())
+                .unwrap_or(vec![]);
+        }
+
+        let boards = fetch_result.unwrap();
+        boards_cache.insert(
+            site_name.clone(),
+            CachedBoards { boards: boards.clone(), fetched_at: Instant::now() }
+        );
+
+        return boards;
     }
 
     pub fn by_url(&self, post_url: &str) -> Option<&ImageboardSynced> {
@@ -55,7 +230,7 @@ impl SiteRepository {
 
     pub async fn load_thread(
         &self,
-        http_client: &'static reqwest::Client,
+        http_client: &reqwest::Client,
         database: &Arc<Database>,
         last_processed_post: &Option<PostDescriptor>,
         thread_descriptor: &ThreadDescriptor
@@ -76,4 +251,88 @@ impl SiteRepository {
         ).await;
     }
 
-}
\ No newline at end of file
+    pub async fn load_catalog(
+        &self,
+        http_client: &reqwest::Client,
+        catalog_descriptor: &CatalogDescriptor
+    ) -> anyhow::Result<CatalogLoadResult> {
+        let imageboard = self.by_site_descriptor(&catalog_descriptor.site_descriptor);
+        if imageboard.is_none() {
+            return Ok(CatalogLoadResult::SiteNotSupported);
+        }
+
+        let imageboard = imageboard.unwrap();
+
+        return base_imageboard::load_catalog(
+            &imageboard,
+            http_client,
+            catalog_descriptor
+        ).await;
+    }
+
+}
+
+fn all_imageboards() -> Vec<ImageboardSynced> {
+    return vec![
+        Arc::new(Chan4 {}),
+        Arc::new(Dvach {})
+    ];
+}
+
+/// Parses a comma separated list of site names (e.g. "4chan,2ch").
+pub fn parse_enabled_sites(value: &str) -> Vec<String> {
+    return value.split(',')
+        .map(|site_name| site_name.trim())
+        .filter(|site_name| !site_name.is_empty())
+        .map(|site_name| site_name.to_string())
+        .collect::<Vec<String>>();
+}
+
+/// Parses a comma separated list of site_name=permits pairs (e.g. "4chan=8,2ch=4").
+pub fn parse_site_concurrency_limits(value: &str) -> anyhow::Result<HashMap<String, usize>> {
+    let mut site_concurrency_limits = HashMap::<String, usize>::new();
+
+    for pair in value.split(',') {
+        let pair = pair.trim();
+        if pair.is_empty() {
+            continue;
+        }
+
+        let split = pair.split_once('=');
+        if split.is_none() {
+            return Err(anyhow!("Bad site concurrency limit \'{}\', expected site_name=permits", pair));
+        }
+
+        let (site_name, permits) = split.unwrap();
+
+        let permits = usize::from_str(permits.trim())
+            .map_err(|_| anyhow!("Bad permits value in site concurrency limit \'{}\'", pair))?;
+        if permits == 0 {
+            return Err(anyhow!("Site concurrency limit must be greater than zero: \'{}\'", pair));
+        }
+
+        site_concurrency_limits.insert(site_name.trim().to_string(), permits);
+    }
+
+    return Ok(site_concurrency_limits);
+}
+
+#[test]
+fn test_parse_site_concurrency_limits() {
+    let limits = parse_site_concurrency_limits("4chan=8, 2ch=4").unwrap();
+    assert_eq!(2, limits.len());
+    assert_eq!(Some(&8), limits.get("4chan"));
+    assert_eq!(Some(&4), limits.get("2ch"));
+
+    assert!(parse_site_concurrency_limits("").unwrap().is_empty());
+    assert!(parse_site_concurrency_limits("4chan").is_err());
+    assert!(parse_site_concurrency_limits("4chan=abc").is_err());
+    assert!(parse_site_concurrency_limits("4chan=0").is_err());
+}
+
+#[test]
+fn test_parse_enabled_sites() {
+    assert_eq!(vec!["4chan".to_string(), "2ch".to_string()], parse_enabled_sites("4chan, 2ch,"));
+    assert!(parse_enabled_sites("").is_empty());
+    assert!(parse_enabled_sites(" , ").is_empty());
+}
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+
+use crate::{info, warn};
+use crate::helpers::string_helpers::FormatToken;
+use crate::model::data::chan::ThreadDescriptor;
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository;
+use crate::model::repository::account_repository::AccountId;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum MuteThreadResult {
+    Ok,
+    AccountDoesNotExist
+}
+
+/// Mutes notifications about replies in [thread_descriptor] for [minutes] minutes. The replies are
+/// still stored and get sent once the mute ends. Passing 0 minutes unmutes the thread right away.
+pub async fn mute_thread(
+    database: &Arc<Database>,
+    account_id: &AccountId,
+    thread_descriptor: &ThreadDescriptor,
+    minutes: i64
+) -> anyhow::Result<MuteThreadResult> {
+    let account = account_repository::get_account(account_id, database).await?;
+    if account.is_none() {
+        warn!(
+            "mute_thread() account with id: {} does not exist!",
+            account_id.format_token()
+        );
+
+        return Ok(MuteThreadResult::AccountDoesNotExist);
+    }
+
+    let account_db_id = { account.unwrap().lock().await.id };
+
+    let mut connection = database.connection_with_retry().await?;
+    let transaction = connection.transaction().await?;
+
+    // Expired mutes of this account are useless so get rid of them while we are at it
+    let delete_query = r#"
+        DELETE FROM thread_mutes
+        WHERE
+            owner_account_id = $1
+        AND
+            (muted_until <= now() OR (site_name = $2 AND board_code = $3 AND thread_no = $4))
+    "#;
+
+    transaction.execute(
+        delete_query,
+        &[
+            &account_db_id,
+            thread_descriptor.site_name(),
+            thread_descriptor.board_code(),
+            &(thread_descriptor.thread_no as i64)
+        ]
+    )
+        .await
+        .context("mute_thread() Failed to delete old thread mutes")?;
+
+    if minutes > 0 {
+        let insert_query = r#"
+            INSERT INTO thread_mutes(
+                owner_account_id,
+                site_name,
+                board_code,
+                thread_no,
+                muted_until
+            )
+            VALUES ($1, $2, $3, $4, now() + make_interval(mins => $5))
+        "#;
+
+        transaction.execute(
+            insert_query,
+            &[
+                &account_db_id,
+                thread_descriptor.site_name(),
+                thread_descriptor.board_code(),
+                &(thread_descriptor.thread_no as i64),
+                &(minutes as i32)
+            ]
+        )
+            .await
+            .context("mute_thread() Failed to insert thread mute")?;
+    }
+
+    transaction.commit().await?;
+
+    info!(
+        "mute_thread() success. account_id: {}, thread: {}, minutes: {}",
+        account_id.format_token(),
+        thread_descriptor,
+        minutes
+    );
+
+    return Ok(MuteThreadResult::Ok);
+}
@@ -19,7 +19,7 @@ pub async fn get_last_processed_post(
           AND threads.last_processed_post_no > 0
 "#;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let statement = connection.prepare(query).await?;
 
     let row_maybe = connection.query_opt(
@@ -49,6 +49,9 @@ pub async fn get_last_processed_post(
     return Ok(Some(last_processed_post_descriptor));
 }
 
+/// The last processed post only ever moves forward. Overlapping watcher passes (or a full load
+/// racing a partial one) may try to store an older post, which would make us process (and notify
+/// about) the same posts again.
 pub async fn store_last_processed_post(
     post_descriptor: &PostDescriptor,
     database: &Arc<Database>
@@ -63,9 +66,10 @@ pub async fn store_last_processed_post(
         ON CONFLICT (site_name, board_code, thread_no)
             DO UPDATE SET last_processed_post_no     = $4,
                           last_processed_post_sub_no = $5
+            WHERE (threads.last_processed_post_no, threads.last_processed_post_sub_no) < ($4, $5)
 "#;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let statement = connection.prepare(query).await?;
 
     connection.execute(
@@ -94,7 +98,7 @@ pub async fn get_last_modified(
           AND threads.thread_no = $3
     "#;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let statement = connection.prepare(query).await?;
 
     let row_maybe = connection.query_opt(
@@ -120,22 +124,55 @@ pub async fn store_last_modified(
     last_modified: &DateTime<FixedOffset>,
     thread_descriptor: &ThreadDescriptor,
     database: &Arc<Database>
+) -> anyhow::Result<()> {
+    // Upsert so that the timestamp is never silently lost when the thread row doesn't exist yet,
+    // otherwise the thread would be considered modified on every check.
+    let query = r#"
+        INSERT INTO threads(site_name,
+                            board_code,
+                            thread_no,
+                            last_modified)
+        VALUES ($2, $3, $4, $1)
+        ON CONFLICT (site_name, board_code, thread_no)
+            DO UPDATE SET last_modified = $1
+"#;
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    connection.execute(
+        &statement,
+        &[
+            last_modified,
+            thread_descriptor.site_name(),
+            thread_descriptor.board_code(),
+            &(thread_descriptor.thread_no as i64)
+        ]
+    ).await?;
+
+    return Ok(());
+}
+
+pub async fn store_thread_title(
+    title: &String,
+    thread_descriptor: &ThreadDescriptor,
+    database: &Arc<Database>
 ) -> anyhow::Result<()> {
     let query = r#"
         UPDATE threads
-        SET last_modified = $1
+        SET title = $1
         WHERE threads.site_name = $2
           AND threads.board_code = $3
           AND threads.thread_no = $4
-"#;
+    "#;
 
-    let connection = database.connection().await?;
+    let connection = database.connection_with_retry().await?;
     let statement = connection.prepare(query).await?;
 
     connection.execute(
         &statement,
         &[
-            last_modified,
+            title,
             thread_descriptor.site_name(),
             thread_descriptor.board_code(),
             &(thread_descriptor.thread_no as i64)
@@ -143,4 +180,101 @@ pub async fn store_last_modified(
     ).await?;
 
     return Ok(());
-}
\ No newline at end of file
+}
+
+pub async fn get_thread_title(
+    thread_descriptor: &ThreadDescriptor,
+    database: &Arc<Database>
+) -> anyhow::Result<Option<String>> {
+    let query = r#"
+        SELECT title
+        FROM threads
+        WHERE threads.site_name = $1
+          AND threads.board_code = $2
+          AND threads.thread_no = $3
+    "#;
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    let row_maybe = connection.query_opt(
+        &statement,
+        &[
+            thread_descriptor.site_name(),
+            thread_descriptor.board_code(),
+            &(thread_descriptor.thread_no as i64)
+        ]
+    ).await?;
+
+    if row_maybe.is_none() {
+        return Ok(None);
+    }
+
+    let row = row_maybe.unwrap();
+    let title: Option<String> = row.try_get(0)?;
+
+    return Ok(title);
+}
+
+pub async fn get_last_etag(
+    thread_descriptor: &ThreadDescriptor,
+    database: &Arc<Database>
+) -> anyhow::Result<Option<String>> {
+    let query = r#"
+        SELECT last_etag
+        FROM threads
+        WHERE threads.site_name = $1
+          AND threads.board_code = $2
+          AND threads.thread_no = $3
+    "#;
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    let row_maybe = connection.query_opt(
+        &statement,
+        &[
+            thread_descriptor.site_name(),
+            thread_descriptor.board_code(),
+            &(thread_descriptor.thread_no as i64)
+        ]
+    ).await?;
+
+    if row_maybe.is_none() {
+        return Ok(None);
+    }
+
+    let row = row_maybe.unwrap();
+    let last_etag: Option<String> = row.try_get(0)?;
+
+    return Ok(last_etag);
+}
+
+pub async fn store_last_etag(
+    last_etag: &String,
+    thread_descriptor: &ThreadDescriptor,
+    database: &Arc<Database>
+) -> anyhow::Result<()> {
+    let query = r#"
+        UPDATE threads
+        SET last_etag = $1
+        WHERE threads.site_name = $2
+          AND threads.board_code = $3
+          AND threads.thread_no = $4
+    "#;
+
+    let connection = database.connection_with_retry().await?;
+    let statement = connection.prepare(query).await?;
+
+    connection.execute(
+        &statement,
+        &[
+            last_etag,
+            thread_descriptor.site_name(),
+            thread_descriptor.board_code(),
+            &(thread_descriptor.thread_no as i64)
+        ]
+    ).await?;
+
+    return Ok(());
+}
@@ -1,29 +1,166 @@
+use std::future::Future;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use anyhow::anyhow;
 use http_body_util::Full;
 use hyper::{Request, Response};
 use hyper::body::Bytes;
+use hyper::header::HeaderValue;
+use hyper::server::conn::{http1, http2};
+use hyper::service::service_fn;
+use tokio::net::TcpStream;
 
 use crate::{error, handlers, info};
-use crate::handlers::shared::ContentType;
-use crate::helpers::throttler;
+use crate::handlers::shared::{ContentType, ErrorCode, RequestFailed, ResponseBody};
+use crate::helpers::{string_helpers, throttler};
+use crate::helpers::hashers::{constant_time_eq, Sha512Hashable};
 use crate::model::database::db::Database;
 use crate::model::repository::site_repository::SiteRepository;
+use crate::service::fcm_sender::FcmSender;
+use crate::service::metrics;
 
+#[derive(Clone, Copy)]
 pub struct TestContext {
     pub enable_throttler: bool
 }
 
+/// Only the SHA3-512 hash of the master password is kept around. The password sent along with a
+/// request is hashed as well so that the hashes can be compared in constant time.
+pub struct MasterPassword {
+    hash: String
+}
+
+impl MasterPassword {
+    pub fn from_plaintext(master_password: &str) -> MasterPassword {
+        return MasterPassword { hash: master_password.sha3_512(1) };
+    }
+
+    /// [hash] is a hex encoded SHA3-512 hash (e.g. "echo -n password | openssl dgst -sha3-512").
+    pub fn from_hash(hash: &str) -> anyhow::Result<MasterPassword> {
+        let hash = hash.trim().to_lowercase();
+
+        if hash.len() != 128 || !hash.chars().all(|ch| ch.is_ascii_hexdigit()) {
+            return Err(anyhow!("Master password hash must be a hex encoded SHA3-512 hash (128 symbols)"));
+        }
+
+        return Ok(MasterPassword { hash });
+    }
+
+    pub fn matches(&self, master_password_from_request: &str) -> bool {
+        let hash_from_request = master_password_from_request.sha3_512(1);
+        return constant_time_eq(self.hash.as_bytes(), hash_from_request.as_bytes());
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HttpProtocol {
+    Http1,
+    Http2
+}
+
+#[derive(Clone)]
+struct TokioExecutor;
+
+impl<F> hyper::rt::Executor<F> for TokioExecutor
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static
+{
+    fn execute(&self, future: F) {
+        tokio::task::spawn(future);
+    }
+}
+
+pub async fn serve_connection(
+    test_context: Option<TestContext>,
+    http_protocol: HttpProtocol,
+    stream: TcpStream,
+    sock_addr: SocketAddr,
+    master_password: Arc<MasterPassword>,
+    host_address: Arc<String>,
+    database: Arc<Database>,
+    site_repository: Arc<SiteRepository>,
+    fcm_sender: Arc<FcmSender>
+) -> anyhow::Result<()> {
+    let service = service_fn(move |request| {
+        let master_password = master_password.clone();
+        let host_address = host_address.clone();
+        let database = database.clone();
+        let site_repository = site_repository.clone();
+        let fcm_sender = fcm_sender.clone();
+
+        return async move {
+            return router(
+                test_context,
+                &master_password,
+                &host_address,
+                &sock_addr,
+                request,
+                &database,
+                &site_repository,
+                &fcm_sender
+            ).await;
+        };
+    });
+
+    match http_protocol {
+        HttpProtocol::Http1 => {
+            http1::Builder::new()
+                .serve_connection(stream, service)
+                .await?;
+        }
+        HttpProtocol::Http2 => {
+            http2::Builder::new(TokioExecutor)
+                .serve_connection(stream, service)
+                .await?;
+        }
+    }
+
+    return Ok(());
+}
+
 pub async fn router(
     test_context: Option<TestContext>,
-    master_password: &String,
+    master_password: &MasterPassword,
     host_address: &String,
     sock_addr: &SocketAddr,
     request: Request<hyper::body::Incoming>,
     database: &Arc<Database>,
     site_repository: &Arc<SiteRepository>,
-) -> anyhow::Result<Response<Full<Bytes>>> {
+    fcm_sender: &Arc<FcmSender>,
+) -> anyhow::Result<Response<ResponseBody>> {
+    // Included into every log line of this request so that the lines of concurrent requests can
+    // be told apart. Also sent back to the client so that it can be reported along with errors.
+    let request_id = string_helpers::random_uuid_v4();
+
+    let mut response = route_request(
+        &request_id,
+        test_context,
+        master_password,
+        host_address,
+        sock_addr,
+        request,
+        database,
+        site_repository,
+        fcm_sender
+    ).await?;
+
+    response.headers_mut().insert("X-Request-Id", HeaderValue::from_str(&request_id)?);
+    return Ok(response);
+}
+
+async fn route_request(
+    request_id: &str,
+    test_context: Option<TestContext>,
+    master_password: &MasterPassword,
+    host_address: &String,
+    sock_addr: &SocketAddr,
+    request: Request<hyper::body::Incoming>,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>,
+    fcm_sender: &Arc<FcmSender>,
+) -> anyhow::Result<Response<ResponseBody>> {
     let remote_address = sock_addr.to_string();
     let (parts, body) = request.into_parts();
 
@@ -33,35 +170,42 @@ pub async fn router(
 
     let path_and_query = parts.uri.path_and_query();
     if path_and_query.is_none() {
-        error!("router() path_and_query not found");
+        error!("router() [{}] path_and_query not found", request_id);
 
         let error_message = "path_and_query not found";
-        let response_json = handlers::shared::error_response_str(error_message)?;
+        let response_json = handlers::shared::error_response_str(ErrorCode::BadRequest, error_message)?;
         let response = Response::builder()
             .json()
             .status(200)
             .body(Full::new(Bytes::from(response_json)))?;
 
-        return Ok(response);
+        return Ok(handlers::shared::full_body(response));
     }
 
     let path_and_query = path_and_query.unwrap();
     let mut path = path_and_query.path();
 
-    info!("router() New request to \'{}\' from \'{}\'", path, remote_address);
+    info!(
+        "router() [{}] New request to \'{}\' from \'{}\'",
+        request_id,
+        path,
+        remote_address
+    );
 
     let can_proceed = throttler::can_proceed(test_context, path.to_string(), &remote_address).await?;
     if !can_proceed {
-        info!("router() Client {} has been throttled", remote_address);
+        info!("router() [{}] Client {} has been throttled", request_id, remote_address);
 
-        let error_message = "You are making too many requests, please wait a little bit.";
-        let response_json = handlers::shared::error_response_str(error_message)?;
+        let response_json = handlers::shared::error_response_str(
+            ErrorCode::TooManyRequests,
+            handlers::shared::TOO_MANY_REQUESTS_MESSAGE
+        )?;
         let response = Response::builder()
             .json()
             .status(200)
             .body(Full::new(Bytes::from(response_json)))?;
 
-        return Ok(response);
+        return Ok(handlers::shared::full_body(response));
     }
 
     let start = chrono::offset::Utc::now();
@@ -69,24 +213,37 @@ pub async fn router(
 
     match path {
         "/get_logs" |
+        "/get_delivery_stats" |
+        "/list_accounts" |
+        "/bulk_extend_expiry" |
+        "/replay_replies" |
+        "/ban_account" |
+        "/debug/process_thread" |
+        "/debug/get_thread_snapshot" |
         "/create_account" |
         "/update_account_expiry_date" |
         "/generate_invites" => {
-            if master_password != master_password_from_request {
+            if !master_password.matches(master_password_from_request) {
+                // Never log the password itself, it could be the real one with a typo
                 info!(
-                    "router() Client {} sent incorrect master password: \'{}\'",
-                    remote_address,
-                    master_password_from_request
+                    "router() [{}] Client {} sent incorrect master password",
+                    request_id,
+                    remote_address
                 );
 
+                throttler::on_request_failed(test_context, path, &remote_address).await;
+
                 let error_message = "Incorrect master password";
-                let response_json = handlers::shared::error_response_str(error_message)?;
+                let response_json = handlers::shared::error_response_str(
+                    ErrorCode::IncorrectMasterPassword,
+                    error_message
+                )?;
                 let response = Response::builder()
                     .json()
                     .status(403)
                     .body(Full::new(Bytes::from(response_json)))?;
 
-                return Ok(response);
+                return Ok(handlers::shared::full_body(response));
             }
         },
         _ => {
@@ -95,7 +252,87 @@ pub async fn router(
     };
 
     // Do not forget to update throttler as well when changing paths here.
-    let handler_result = match path {
+    let handler_result = if path == "/get_logs" {
+        // May stream its response so it has its own body type
+        handlers::get_logs::handle(query, body, database).await
+    } else {
+        route_to_handler(
+            path,
+            query,
+            body,
+            test_context,
+            host_address,
+            database,
+            site_repository,
+            fcm_sender
+        ).await.map(handlers::shared::full_body)
+    };
+
+    let delta = chrono::offset::Utc::now() - start;
+    metrics::on_http_request(handler_result.is_err());
+
+    // Handlers rendering html (e.g. /view_invite) report failures with a client error status
+    // instead of an error so that the user still gets a page, JSON handlers mark the response
+    // with RequestFailed
+    let request_failed = handler_result.as_ref()
+        .map(|response| {
+            return response.status().is_client_error() ||
+                response.extensions().get::<RequestFailed>().is_some();
+        })
+        .unwrap_or(true);
+
+    if request_failed {
+        throttler::on_request_failed(test_context, path, &remote_address).await;
+    } else {
+        throttler::on_request_succeeded(test_context, path, &remote_address).await;
+    }
+
+    if handler_result.is_err() {
+        let handler_error = handler_result
+            .as_ref()
+            .err();
+
+        let handler_error_message = handler_error
+            .map(|err| err.to_string())
+            .unwrap_or(String::from("Unknown error"));
+
+        let handler_error_code = handler_error
+            .map(|err| handlers::shared::error_code_of(err))
+            .unwrap_or(ErrorCode::UnknownError);
+
+        error!("router() [{}] Request to {} error: {:?}", request_id, path, handler_error);
+
+        let response_json = handlers::shared::error_response_string(handler_error_code, &handler_error_message)?;
+        let response = Response::builder()
+            .json()
+            .status(200)
+            .body(Full::new(Bytes::from(response_json)))?;
+
+        return Ok(handlers::shared::full_body(response));
+    } else {
+        info!(
+            "router() [{}] Request to \'{}\' from \'{}\' success, took {} ms",
+            request_id,
+            path,
+            remote_address,
+            delta.num_milliseconds()
+        );
+    }
+
+    return handler_result
+}
+
+async fn route_to_handler(
+    path: &str,
+    query: &str,
+    body: hyper::body::Incoming,
+    test_context: Option<TestContext>,
+    host_address: &String,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>,
+    fcm_sender: &Arc<FcmSender>
+) -> anyhow::Result<Response<Full<Bytes>>> {
+    return match path {
         "/create_account" => {
             handlers::create_account::handle(query, body, database).await
         },
@@ -103,22 +340,43 @@ pub async fn router(
             handlers::update_account_expiry_date::handle(query, body, database).await
         },
         "/update_firebase_token" => {
-            handlers::update_firebase_token::handle(query, body, database).await
+            handlers::update_firebase_token::handle(query, body, database, test_context).await
+        },
+        "/update_web_push_subscription" => {
+            handlers::update_web_push_subscription::handle(query, body, database, test_context).await
+        },
+        "/extend_account_expiry" => {
+            handlers::extend_account_expiry::handle(query, body, database, test_context).await
         },
         "/update_message_delivered" => {
-            handlers::update_message_delivered::handle(query, body, database, site_repository).await
+            handlers::update_message_delivered::handle(query, body, database, site_repository, test_context).await
         }
         "/get_account_info" => {
-            handlers::get_account_info::handle(query, body, database).await
+            handlers::get_account_info::handle(query, body, database, test_context).await
+        },
+        "/whoami" => {
+            handlers::whoami::handle(query, body, database, test_context).await
+        },
+        "/get_pending_replies" => {
+            handlers::get_pending_replies::handle(query, body, database, site_repository, test_context).await
         },
-        "/get_logs" => {
-            handlers::get_logs::handle(query, body, database).await
-        }
         "/watch_post" => {
-            handlers::watch_post::handle(query, body, database, site_repository).await
+            handlers::watch_post::handle(query, body, database, site_repository, test_context).await
+        },
+        "/watch_posts" => {
+            handlers::watch_posts::handle(query, body, database, site_repository, test_context).await
+        },
+        "/register_and_watch" => {
+            handlers::register_and_watch::handle(query, body, database, site_repository, test_context).await
+        },
+        "/send_test_notification" => {
+            handlers::send_test_notification::handle(query, body, database, fcm_sender, test_context).await
         },
         "/unwatch_post" => {
-            handlers::unwatch_post::handle(query, body, database, site_repository).await
+            handlers::unwatch_post::handle(query, body, database, site_repository, test_context).await
+        },
+        "/watch_catalog" => {
+            handlers::watch_catalog::handle(query, body, database, site_repository, test_context).await
         },
         "/generate_invites" => {
             handlers::generate_invites::handle(query, body, database, host_address).await
@@ -126,39 +384,74 @@ pub async fn router(
         "/view_invite" => {
             handlers::view_invite::handle(query, body, database, host_address).await
         }
+        "/delete_account" => {
+            handlers::delete_account::handle(query, body, database, test_context).await
+        }
+        "/metrics" => {
+            handlers::metrics::handle(query, body, database).await
+        }
+        "/debug/process_thread" => {
+            handlers::debug_process_thread::handle(query, body, database, site_repository).await
+        }
+        "/debug/get_thread_snapshot" => {
+            handlers::get_thread_snapshot::handle(query, body, database, site_repository).await
+        }
+        "/server_info" => {
+            handlers::server_info::handle(query, body, site_repository).await
+        }
+        "/get_boards" => {
+            handlers::get_boards::handle(query, body, site_repository).await
+        }
+        "/get_delivery_stats" => {
+            handlers::get_delivery_stats::handle(query, body, database).await
+        }
+        "/list_accounts" => {
+            handlers::list_accounts::handle(query, body, database).await
+        }
+        "/get_thread_progress" => {
+            handlers::get_thread_progress::handle(query, body, database, site_repository, test_context).await
+        }
+        "/bulk_extend_expiry" => {
+            handlers::bulk_extend_expiry::handle(query, body, database).await
+        }
+        "/remove_firebase_token" => {
+            handlers::remove_firebase_token::handle(query, body, database, test_context).await
+        }
+        "/update_quiet_hours" => {
+            handlers::update_quiet_hours::handle(query, body, database, test_context).await
+        }
+        "/replay_replies" => {
+            handlers::replay_replies::handle(query, body, database).await
+        }
+        "/mute_thread" => {
+            handlers::mute_thread::handle(query, body, database, site_repository, test_context).await
+        }
+        "/ban_account" => {
+            handlers::ban_account::handle(query, body, database).await
+        }
         _ => {
             handlers::index::handle(query, body).await
         }
     };
+}
 
-    let delta = chrono::offset::Utc::now() - start;
-
-    if handler_result.is_err() {
-        let handler_error = handler_result
-            .as_ref()
-            .err();
-
-        let handler_error_message = handler_error
-            .map(|err| err.to_string())
-            .unwrap_or(String::from("Unknown error"));
-
-        error!("router() Request to {} error: {:?}", path, handler_error);
+#[test]
+fn test_master_password_matches() {
+    let master_password = MasterPassword::from_plaintext("test123");
+    assert!(master_password.matches("test123"));
+    assert!(!master_password.matches("test1234"));
+    assert!(!master_password.matches("Test123"));
+    assert!(!master_password.matches(""));
 
-        let response_json = handlers::shared::error_response_string(&handler_error_message)?;
-        let response = Response::builder()
-            .json()
-            .status(200)
-            .body(Full::new(Bytes::from(response_json)))?;
+    let hash = "test123".sha3_512(1);
 
-        return Ok(response);
-    } else {
-        info!(
-            "router() Request to \'{}\' from \'{}\' success, took {} ms",
-            path,
-            remote_address,
-            delta.num_milliseconds()
-        );
-    }
+    let master_password = MasterPassword::from_hash(&hash.to_uppercase()).unwrap();
+    assert!(master_password.matches("test123"));
+    assert!(!master_password.matches("test1234"));
+    // The hash itself is not the password
+    assert!(!master_password.matches(&hash));
 
-    return handler_result
+    assert!(MasterPassword::from_hash("test123").is_err());
+    assert!(MasterPassword::from_hash(&hash[1..]).is_err());
+    assert!(MasterPassword::from_hash(&format!("{}z", &hash[1..])).is_err());
 }
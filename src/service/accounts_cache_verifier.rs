@@ -0,0 +1,34 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{constants, error, info};
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository;
+
+/// The accounts cache is filled lazily so there is nothing to verify right after the start. Instead
+/// we periodically compare a sample of the cached accounts with the database once the server is up.
+pub async fn accounts_cache_verification_task(database: &Arc<Database>) {
+    info!("accounts_cache_verification_task() start");
+
+    loop {
+        info!("accounts_cache_verification_task() waiting...");
+        tokio::time::sleep(Duration::from_secs(30 * 60)).await;
+        info!("accounts_cache_verification_task() waiting... done, verifying...");
+
+        let result = account_repository::verify_accounts_cache(
+            database,
+            constants::ACCOUNTS_CACHE_VERIFICATION_SAMPLE_SIZE
+        ).await;
+
+        let mismatched = if result.is_err() {
+            error!("accounts_cache_verification_task::verify_accounts_cache() error: {}", result.err().unwrap());
+            0
+        } else {
+            result.unwrap()
+        };
+
+        info!("accounts_cache_verification_task() verifying... done, mismatched: {}", mismatched);
+    }
+
+    info!("accounts_cache_verification_task() end");
+}
\ No newline at end of file
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+
+use crate::{error, info};
+use crate::model::data::chan::{CatalogDescriptor, CatalogThread};
+use crate::model::database::db::Database;
+use crate::model::imageboards::base_imageboard::CatalogLoadResult;
+use crate::model::repository::catalog_watch_repository;
+use crate::model::repository::site_repository::SiteRepository;
+
+pub async fn process_watched_catalogs(
+    http_client: &Arc<reqwest::Client>,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>
+) -> anyhow::Result<usize> {
+    let all_watched_catalogs = catalog_watch_repository::get_all_watched_catalogs(database)
+        .await
+        .context("process_watched_catalogs() Failed to get all watched catalogs")?;
+
+    if all_watched_catalogs.is_empty() {
+        info!("process_watched_catalogs() no watched catalogs to process");
+        return Ok(0);
+    }
+
+    let mut new_threads_total: usize = 0;
+
+    for catalog_descriptor in &all_watched_catalogs {
+        let site_permit = site_repository.acquire_site_permit(&catalog_descriptor.site_descriptor).await;
+
+        let catalog_load_result = site_repository.load_catalog(
+            http_client,
+            catalog_descriptor
+        ).await;
+
+        drop(site_permit);
+
+        if catalog_load_result.is_err() {
+            error!(
+                "process_watched_catalogs({}) failed to load catalog, error: {}",
+                catalog_descriptor,
+                catalog_load_result.err().unwrap()
+            );
+
+            continue;
+        }
+
+        let catalog_threads = match catalog_load_result.unwrap() {
+            CatalogLoadResult::Success(catalog_threads) => catalog_threads,
+            CatalogLoadResult::SiteNotSupported => {
+                info!("process_watched_catalogs({}) site is not supported", catalog_descriptor);
+                continue;
+            }
+            CatalogLoadResult::BadStatusCode(status_code) => {
+                error!(
+                    "process_watched_catalogs({}) bad status code: {}",
+                    catalog_descriptor,
+                    status_code
+                );
+
+                continue;
+            }
+        };
+
+        new_threads_total += process_catalog(catalog_descriptor, &catalog_threads, database).await?;
+    }
+
+    info!(
+        "process_watched_catalogs() processed {} catalogs, found {} new matching threads",
+        all_watched_catalogs.len(),
+        new_threads_total
+    );
+
+    return Ok(new_threads_total);
+}
+
+/// Diffs the catalog threads against the last seen thread number of every watch of this catalog
+/// and stores a notification for every new thread that matches the watch filter. The first time a
+/// watch is processed we only remember the newest thread so that the user isn't notified about
+/// every thread that was already in the catalog when they started watching it.
+pub async fn process_catalog(
+    catalog_descriptor: &CatalogDescriptor,
+    catalog_threads: &Vec<CatalogThread>,
+    database: &Arc<Database>
+) -> anyhow::Result<usize> {
+    let max_thread_no = catalog_threads.iter()
+        .map(|catalog_thread| catalog_thread.thread_no)
+        .max();
+
+    if max_thread_no.is_none() {
+        return Ok(0);
+    }
+
+    let max_thread_no = max_thread_no.unwrap();
+
+    let catalog_watches = catalog_watch_repository::get_catalog_watches(database, catalog_descriptor)
+        .await
+        .context(format!("process_catalog({}) Failed to get catalog watches", catalog_descriptor))?;
+
+    let mut new_threads_total: usize = 0;
+
+    for catalog_watch in &catalog_watches {
+        if max_thread_no <= catalog_watch.last_seen_thread_no {
+            continue;
+        }
+
+        let mut new_catalog_threads = Vec::<&CatalogThread>::new();
+
+        if catalog_watch.last_seen_thread_no > 0 {
+            new_catalog_threads = catalog_threads.iter()
+                .filter(|catalog_thread| catalog_thread.thread_no > catalog_watch.last_seen_thread_no)
+                .filter(|catalog_thread| matches_filter(catalog_thread, &catalog_watch.filter))
+                .collect::<Vec<&CatalogThread>>();
+        }
+
+        catalog_watch_repository::store_new_catalog_threads(
+            database,
+            catalog_watch.id,
+            max_thread_no,
+            &new_catalog_threads
+        )
+            .await
+            .context(format!("process_catalog({}) Failed to store new catalog threads", catalog_descriptor))?;
+
+        new_threads_total += new_catalog_threads.len();
+    }
+
+    return Ok(new_threads_total);
+}
+
+fn matches_filter(catalog_thread: &CatalogThread, filter: &String) -> bool {
+    let filter = filter.to_lowercase();
+
+    let subject_matches = catalog_thread.subject.as_ref()
+        .map(|subject| subject.to_lowercase().contains(&filter))
+        .unwrap_or(false);
+
+    if subject_matches {
+        return true;
+    }
+
+    return catalog_thread.comment.as_ref()
+        .map(|comment| comment.to_lowercase().contains(&filter))
+        .unwrap_or(false);
+}
+
+#[test]
+fn test_matches_filter() {
+    let catalog_thread = CatalogThread {
+        thread_no: 1,
+        subject: Some("/vg/ General".to_string()),
+        comment: Some("Welcome to the thread".to_string())
+    };
+
+    assert!(matches_filter(&catalog_thread, &"general".to_string()));
+    assert!(matches_filter(&catalog_thread, &"WELCOME".to_string()));
+    assert!(!matches_filter(&catalog_thread, &"other".to_string()));
+}
@@ -0,0 +1,28 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{error, info};
+use crate::model::database::db::Database;
+use crate::model::repository::post_repository;
+
+pub async fn dead_threads_cleanup_task(database: &Arc<Database>, retention_days: u64) {
+    info!("dead_threads_cleanup_task() start, retention_days: {}", retention_days);
+
+    loop {
+        info!("dead_threads_cleanup_task() cleaning up...");
+
+        let result = post_repository::purge_dead_threads(database, retention_days).await;
+        let deleted = if result.is_err() {
+            error!("dead_threads_cleanup_task::purge_dead_threads() error: {}", result.err().unwrap());
+            0
+        } else {
+            result.unwrap()
+        };
+
+        info!("dead_threads_cleanup_task() cleaning up... done, deleted: {}, waiting...", deleted);
+        tokio::time::sleep(Duration::from_secs(60 * 60)).await;
+        info!("dead_threads_cleanup_task() waiting... done");
+    }
+
+    info!("dead_threads_cleanup_task() end");
+}
@@ -1,69 +1,204 @@
 use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
 
 use anyhow::Context;
-use fcm::Priority;
+use chrono::{DateTime, Utc};
 use lazy_static::lazy_static;
-use serde::Serialize;
-use tokio::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, RwLock};
 use tokio::task::JoinHandle;
 
-use crate::{error, info};
+use crate::{constants, error, info, warn};
+use crate::helpers::hashers::Sha512Hashable;
 use crate::model::database::db::Database;
-use crate::model::repository::{post_reply_repository, post_repository};
-use crate::model::repository::account_repository::AccountToken;
+use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
+use crate::model::repository::{catalog_watch_repository, post_reply_repository, post_repository};
+use crate::model::repository::account_repository::{AccountToken, TokenType};
+use crate::model::repository::catalog_watch_repository::UnsentCatalogNotification;
 use crate::model::repository::post_reply_repository::UnsentReply;
 use crate::model::repository::site_repository::SiteRepository;
+use crate::service::fcm_transport::{FcmMessagePriority, FcmTransport, FirebaseFcmTransport};
+use crate::service::metrics;
+use crate::service::web_push_transport::{VapidKeys, WebPushTransport};
+
+const FCM_SEND_MAX_ATTEMPTS: u32 = 3;
+const FCM_SEND_RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Must be bumped every time the shape of NewFcmRepliesMessage changes so that the clients can
+/// tell which version of the payload they are parsing.
+pub const FCM_REPLIES_PAYLOAD_VERSION: u32 = 2;
+
+static MAX_REPLIES_PER_THREAD: AtomicUsize = AtomicUsize::new(constants::DEFAULT_MAX_REPLIES_PER_THREAD);
+
+/// When enabled, every newly created post watch is confirmed to the account's tokens with a low
+/// priority FCM message.
+static SEND_WATCH_CONFIRMATIONS: AtomicBool = AtomicBool::new(false);
 
 lazy_static! {
-    static ref FCM_CLIENT: fcm::Client = fcm::Client::new();
+    static ref PENDING_WATCH_CONFIRMATIONS: Mutex<Vec<PendingWatchConfirmation>> =
+        Mutex::new(Vec::with_capacity(64));
 }
 
 pub struct FcmSender {
     is_dev_build: bool,
-    firebase_api_key: String,
+    fcm_transport: Arc<dyn FcmTransport>,
+    /// Only set when the VAPID keys are configured, messages to WebPush tokens can't be sent
+    /// otherwise.
+    web_push_transport: Option<Arc<dyn FcmTransport>>,
     database: Arc<Database>,
     site_repository: Arc<SiteRepository>
 }
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FcmSendAttemptResult {
+    Sent,
+    TransientError(String),
+    PermanentError(String)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewFcmRepliesMessage {
+    pub payload_version: u32,
+    /// Lets the clients account for the difference between their clock and the server clock.
+    pub server_time: DateTime<Utc>,
+    pub new_reply_messages: Vec<FcmReplyMessage>,
+    /// Added in payload_version 2, replies that didn't fit into MAX_REPLIES_PER_THREAD.
+    pub coalesced_reply_messages: Vec<FcmCoalescedRepliesMessage>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FcmReplyMessage {
+    pub reply_id: u64,
+    pub new_reply_url: String,
+    pub watched_post_url: String,
+    pub thread_title: Option<String>,
+    pub group_key: String
+}
+
+/// "N more replies" summary of a thread which got too many replies during one cycle.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FcmCoalescedRepliesMessage {
+    pub replies_count: u64,
+    pub last_reply_url: String,
+    pub thread_title: Option<String>,
+    pub group_key: String
+}
+
+/// Sent (batched per token) once the watches of [watched_post_urls] were created.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FcmWatchConfirmedMessage {
+    pub watched_post_urls: Vec<String>
+}
+
+struct PendingWatchConfirmation {
+    account_token: AccountToken,
+    post_descriptor: PostDescriptor
+}
+
 #[derive(Debug, Serialize)]
-struct NewFcmRepliesMessage {
-    new_reply_messages: Vec<FcmReplyMessage>
+struct NewFcmCatalogThreadsMessage {
+    new_catalog_thread_messages: Vec<FcmCatalogThreadMessage>
 }
 
 #[derive(Debug, Serialize)]
-struct FcmReplyMessage {
-    reply_id: u64,
-    new_reply_url: String
+struct FcmCatalogThreadMessage {
+    notification_id: u64,
+    thread_url: String,
+    thread_subject: Option<String>
 }
 
 impl FcmSender {
     pub fn new(
         is_dev_build: bool,
         firebase_api_key: String,
+        vapid_keys: Option<VapidKeys>,
+        web_push_http_client: &Arc<reqwest::Client>,
+        database: &Arc<Database>,
+        site_repository: &Arc<SiteRepository>
+    ) -> FcmSender {
+        let web_push_transport = vapid_keys.map(|vapid_keys| {
+            let web_push_transport = WebPushTransport::new(
+                vapid_keys,
+                database,
+                web_push_http_client
+            );
+
+            return Arc::new(web_push_transport) as Arc<dyn FcmTransport>;
+        });
+
+        return FcmSender::with_transports(
+            is_dev_build,
+            Arc::new(FirebaseFcmTransport::new(firebase_api_key)),
+            web_push_transport,
+            database,
+            site_repository
+        );
+    }
+
+    pub fn with_transport(
+        is_dev_build: bool,
+        fcm_transport: Arc<dyn FcmTransport>,
+        database: &Arc<Database>,
+        site_repository: &Arc<SiteRepository>
+    ) -> FcmSender {
+        return FcmSender::with_transports(
+            is_dev_build,
+            fcm_transport,
+            None,
+            database,
+            site_repository
+        );
+    }
+
+    pub fn with_transports(
+        is_dev_build: bool,
+        fcm_transport: Arc<dyn FcmTransport>,
+        web_push_transport: Option<Arc<dyn FcmTransport>>,
         database: &Arc<Database>,
         site_repository: &Arc<SiteRepository>
     ) -> FcmSender {
         return FcmSender {
             is_dev_build,
-            firebase_api_key,
+            fcm_transport,
+            web_push_transport,
             database: database.clone(),
             site_repository: site_repository.clone()
         };
     }
 
+    /// WebPush tokens are browser endpoints so they can't be sent to via FCM.
+    fn transport_for(&self, account_token: &AccountToken) -> Option<Arc<dyn FcmTransport>> {
+        if account_token.token_type == TokenType::WebPush {
+            if self.web_push_transport.is_none() {
+                warn!("transport_for({}) VAPID keys are not configured", account_token);
+            }
+
+            return self.web_push_transport.clone();
+        }
+
+        return Some(self.fcm_transport.clone());
+    }
+
     pub async fn send_fcm_messages(&self, chunk_size: usize) -> anyhow::Result<u64> {
-        let unsent_replies = post_reply_repository::get_unsent_replies(
+        let unsent_replies = post_reply_repository::claim_unsent_replies(
             self.is_dev_build,
             &self.database
-        ).await.context("send_fcm_messages() Failed to get unsent replies")?;
+        ).await.context("send_fcm_messages() Failed to claim unsent replies")?;
+
+        let unsent_replies_count = unsent_replies.values()
+            .fold(0, |acc, unsent_replies_for_token| acc + unsent_replies_for_token.len());
+        metrics::set_unsent_replies_backlog(unsent_replies_count);
 
         if unsent_replies.is_empty() {
             info!("send_fcm_messages() No unsent replies found");
             return Ok(0);
         }
 
+        let unsent_replies = merge_unsent_replies_by_token(unsent_replies);
+
         for (firebase_token, unsent_replies_for_token) in &unsent_replies {
             info!(
                 "send_fcm_messages() Got {} unsent replies for user with token {}",
@@ -72,7 +207,6 @@ impl FcmSender {
             );
         }
 
-        let firebase_api_key = Arc::new(self.firebase_api_key.clone());
         let capacity = unsent_replies.len() / 2;
         let sent_post_reply_ids_set =
             Arc::new(RwLock::new(HashSet::<i64>::with_capacity(capacity)));
@@ -88,10 +222,22 @@ impl FcmSender {
                 continue;
             }
 
+            let fcm_transport = self.transport_for(&account_token);
+            if fcm_transport.is_none() {
+                let mut failed_to_send_post_reply_ids_locked = failed_to_send_post_reply_ids_set.write().await;
+                unsent_replies
+                    .iter()
+                    .for_each(|unsent_reply| {
+                        failed_to_send_post_reply_ids_locked.insert(unsent_reply.post_reply_id);
+                    });
+
+                continue;
+            }
+
             let semaphore_permit = semaphore.clone().acquire_owned().await?;
             let successfully_sent_cloned = sent_post_reply_ids_set.clone();
             let failed_to_send_post_reply_ids_cloned = failed_to_send_post_reply_ids_set.clone();
-            let firebase_api_key_cloned = firebase_api_key.clone();
+            let fcm_transport_cloned = fcm_transport.unwrap();
             let account_token_cloned = account_token.clone();
             let site_repository_cloned = self.site_repository.clone();
             let sent_replies_cloned = sent_replies.clone();
@@ -99,8 +245,7 @@ impl FcmSender {
             let join_handle = tokio::task::spawn(async move {
                 let result = send_unsent_reply(
                     is_dev_build,
-                    &FCM_CLIENT,
-                    &firebase_api_key_cloned,
+                    &fcm_transport_cloned,
                     &account_token_cloned,
                     &unsent_replies,
                     &successfully_sent_cloned,
@@ -118,28 +263,30 @@ impl FcmSender {
 
         futures::future::join_all(join_handles).await;
 
-        let sent_post_reply_ids = {
+        let (sent_post_reply_ids, failed_to_send_post_reply_ids) = {
             let sent_post_reply_ids_locked = sent_post_reply_ids_set.read().await;
-            let mut result_vec = Vec::<i64>::with_capacity(sent_post_reply_ids_locked.len());
+            let failed_to_send_post_reply_ids_locked = failed_to_send_post_reply_ids_set.read().await;
 
-            sent_post_reply_ids_locked
+            let sent_post_reply_ids = sent_post_reply_ids_locked
                 .iter()
-                .for_each(|reply_id| result_vec.push(*reply_id));
+                .cloned()
+                .collect::<Vec<i64>>();
 
-            result_vec
+            // A reply that was sent to at least one of the account's tokens is considered delivered
+            let failed_to_send_post_reply_ids = failed_to_send_post_reply_ids_locked
+                .iter()
+                .filter(|reply_id| !sent_post_reply_ids_locked.contains(*reply_id))
+                .cloned()
+                .collect::<Vec<i64>>();
+
+            (sent_post_reply_ids, failed_to_send_post_reply_ids)
         };
 
-        if sent_post_reply_ids.len() > 0 {
-            post_reply_repository::increment_notification_delivery_attempt(
-                &sent_post_reply_ids,
-                &self.database
-            )
-                .await
-                .with_context(|| {
-                    return "send_fcm_messages() Failed to increment notification \
-                        delivery attempt counter";
-                })?;
-        }
+        update_claimed_delivery_state(
+            &sent_post_reply_ids,
+            &failed_to_send_post_reply_ids,
+            &self.database
+        ).await?;
 
         {
             let sent_post_reply_ids_set = sent_post_reply_ids_set.read().await;
@@ -161,12 +308,279 @@ impl FcmSender {
 
         return Ok(sent_replies.load(Ordering::Relaxed));
     }
+
+    pub async fn send_catalog_fcm_messages(&self) -> anyhow::Result<u64> {
+        let unsent_catalog_notifications =
+            catalog_watch_repository::get_unsent_catalog_notifications(&self.database)
+                .await
+                .context("send_catalog_fcm_messages() Failed to get unsent catalog notifications")?;
+
+        if unsent_catalog_notifications.is_empty() {
+            info!("send_catalog_fcm_messages() No unsent catalog notifications found");
+            return Ok(0);
+        }
+
+        let mut sent_notification_ids = HashSet::<i64>::new();
+        let mut failed_to_send_notification_ids = HashSet::<i64>::new();
+        let mut sent_messages: u64 = 0;
+
+        for (account_token, unsent_notifications) in &unsent_catalog_notifications {
+            let fcm_transport = self.transport_for(account_token);
+
+            let sent = if fcm_transport.is_some() {
+                send_unsent_catalog_notifications(
+                    &fcm_transport.unwrap(),
+                    account_token,
+                    unsent_notifications,
+                    &self.site_repository
+                ).await?
+            } else {
+                false
+            };
+
+            let notification_ids = unsent_notifications.iter()
+                .map(|unsent_notification| unsent_notification.notification_id);
+
+            if sent {
+                sent_messages += 1;
+                sent_notification_ids.extend(notification_ids);
+            } else {
+                failed_to_send_notification_ids.extend(notification_ids);
+            }
+        }
+
+        let sent_notification_ids = sent_notification_ids.iter()
+            .cloned()
+            .collect::<Vec<i64>>();
+
+        // Same as with replies, a notification that was sent to at least one of the account's
+        // tokens is considered delivered
+        let failed_to_send_notification_ids = failed_to_send_notification_ids.iter()
+            .filter(|notification_id| !sent_notification_ids.contains(*notification_id))
+            .cloned()
+            .collect::<Vec<i64>>();
+
+        catalog_watch_repository::mark_catalog_notifications_as_delivered(
+            &sent_notification_ids,
+            &self.database
+        )
+            .await
+            .context("send_catalog_fcm_messages() Failed to mark catalog notifications as delivered")?;
+
+        catalog_watch_repository::increment_catalog_notification_delivery_attempt(
+            &failed_to_send_notification_ids,
+            &self.database
+        )
+            .await
+            .context("send_catalog_fcm_messages() Failed to increment notification delivery attempt counter")?;
+
+        info!(
+            "send_catalog_fcm_messages() Done! Sent: {}, Not sent: {}",
+            sent_notification_ids.len(),
+            failed_to_send_notification_ids.len()
+        );
+
+        return Ok(sent_messages);
+    }
+
+    /// Best effort, confirmations that failed to be sent are not retried on the next cycle.
+    pub async fn send_watch_confirmation_messages(&self) -> anyhow::Result<u64> {
+        let pending_watch_confirmations = {
+            let mut pending_watch_confirmations_locked = PENDING_WATCH_CONFIRMATIONS.lock().await;
+            std::mem::take(&mut *pending_watch_confirmations_locked)
+        };
+
+        if pending_watch_confirmations.is_empty() {
+            info!("send_watch_confirmation_messages() No pending watch confirmations found");
+            return Ok(0);
+        }
+
+        let mut post_descriptors_by_token =
+            HashMap::<AccountToken, Vec<PostDescriptor>>::with_capacity(pending_watch_confirmations.len());
+
+        for pending_watch_confirmation in pending_watch_confirmations {
+            post_descriptors_by_token.entry(pending_watch_confirmation.account_token)
+                .or_insert_with(|| Vec::with_capacity(1))
+                .push(pending_watch_confirmation.post_descriptor);
+        }
+
+        let mut sent_messages: u64 = 0;
+
+        for (account_token, post_descriptors) in &post_descriptors_by_token {
+            let watched_post_urls = post_descriptors.iter()
+                .filter_map(|post_descriptor| self.site_repository.to_url(post_descriptor))
+                .collect::<Vec<String>>();
+
+            let fcm_transport = self.transport_for(account_token);
+            if watched_post_urls.is_empty() || fcm_transport.is_none() {
+                continue;
+            }
+
+            let sent = send_watch_confirmation(
+                &fcm_transport.unwrap(),
+                account_token,
+                watched_post_urls
+            ).await?;
+
+            if sent {
+                sent_messages += 1;
+            }
+        }
+
+        info!(
+            "send_watch_confirmation_messages() Done! Sent: {}, Not sent: {}",
+            sent_messages,
+            post_descriptors_by_token.len() as u64 - sent_messages
+        );
+
+        return Ok(sent_messages);
+    }
+
+    /// Sends a single message with the "test" type so that users can check whether pushes reach
+    /// their devices. Not retried since every attempt costs FCM quota. Returns whether FCM accepted
+    /// the message.
+    pub async fn send_test_message(&self, account_token: &AccountToken) -> anyhow::Result<bool> {
+        let fcm_transport = self.transport_for(account_token);
+        if fcm_transport.is_none() {
+            return Ok(false);
+        }
+
+        let mut map = HashMap::new();
+        map.insert("type", String::from("test"));
+
+        let send_result = fcm_transport.unwrap().send(
+            account_token.token.as_str(),
+            &map,
+            FcmMessagePriority::High
+        ).await;
+
+        let send_result = match send_result {
+            Ok(send_result) => send_result,
+            Err(error) => FcmSendAttemptResult::PermanentError(error.to_string())
+        };
+
+        if send_result != FcmSendAttemptResult::Sent {
+            metrics::on_fcm_messages_failed(1);
+
+            error!(
+                "send_test_message({}) Failed to send FCM message because of error: {:?}",
+                account_token,
+                send_result
+            );
+
+            return Ok(false);
+        }
+
+        metrics::on_fcm_messages_sent(1);
+        info!("send_test_message({}) Success", account_token);
+
+        return Ok(true);
+    }
+}
+
+pub fn set_send_watch_confirmations(send_watch_confirmations: bool) {
+    SEND_WATCH_CONFIRMATIONS.store(send_watch_confirmations, Ordering::Relaxed);
+}
+
+pub fn send_watch_confirmations() -> bool {
+    return SEND_WATCH_CONFIRMATIONS.load(Ordering::Relaxed);
+}
+
+/// Must only be called for watches that were just created, does nothing unless
+/// SEND_WATCH_CONFIRMATIONS is enabled.
+pub async fn enqueue_watch_confirmations(
+    account_tokens: &Vec<AccountToken>,
+    post_descriptors: &Vec<PostDescriptor>
+) {
+    if !send_watch_confirmations() || account_tokens.is_empty() || post_descriptors.is_empty() {
+        return;
+    }
+
+    let mut pending_watch_confirmations_locked = PENDING_WATCH_CONFIRMATIONS.lock().await;
+
+    for account_token in account_tokens {
+        for post_descriptor in post_descriptors {
+            let pending_watch_confirmation = PendingWatchConfirmation {
+                account_token: account_token.clone(),
+                post_descriptor: post_descriptor.clone()
+            };
+
+            pending_watch_confirmations_locked.push(pending_watch_confirmation);
+        }
+    }
+}
+
+/// The same firebase token may be registered for multiple application types (e.g. when it gets
+/// re-registered after switching between the debug and production builds) in which case the user
+/// would receive the same reply once per application type. This collapses all replies destined for
+/// the same physical token into one group so that only one FCM message is sent per token while
+/// still keeping every contributing post_reply so that all of them get marked as delivered.
+pub fn merge_unsent_replies_by_token(
+    unsent_replies: HashMap<AccountToken, HashSet<UnsentReply>>
+) -> HashMap<AccountToken, HashSet<UnsentReply>> {
+    let mut account_token_by_token = HashMap::<String, AccountToken>::with_capacity(unsent_replies.len());
+    let mut merged = HashMap::<AccountToken, HashSet<UnsentReply>>::with_capacity(unsent_replies.len());
+
+    for (account_token, unsent_replies_for_token) in unsent_replies {
+        let merged_account_token = account_token_by_token
+            .entry(account_token.token.clone())
+            .or_insert(account_token)
+            .clone();
+
+        merged.entry(merged_account_token)
+            .or_insert_with(|| HashSet::with_capacity(unsent_replies_for_token.len()))
+            .extend(unsent_replies_for_token);
+    }
+
+    return merged;
+}
+
+/// Stable key that is the same for all replies of one thread so that the client can group the
+/// notifications by thread. We don't use FCM's collapse_key for that because it makes FCM drop all
+/// but the latest undelivered message which would make us lose replies.
+pub fn thread_group_key(thread_descriptor: &ThreadDescriptor) -> String {
+    let thread_descriptor_string = thread_descriptor.to_string();
+    let hash = thread_descriptor_string.as_str().sha3_512(1);
+
+    return hash[..32].to_string();
+}
+
+/// Marks successfully sent replies as delivered and releases the ones we failed to send so that
+/// they are retried right away. Both must be claimed via claim_unsent_replies() first, the claim
+/// already counted as a delivery attempt.
+pub async fn update_claimed_delivery_state(
+    sent_post_reply_ids: &Vec<i64>,
+    failed_to_send_post_reply_ids: &Vec<i64>,
+    database: &Arc<Database>
+) -> anyhow::Result<()> {
+    if sent_post_reply_ids.len() > 0 {
+        post_reply_repository::mark_post_replies_as_notified(
+            sent_post_reply_ids,
+            database
+        )
+            .await
+            .with_context(|| {
+                return "update_claimed_delivery_state() Failed to mark post replies as notified";
+            })?;
+    }
+
+    if failed_to_send_post_reply_ids.len() > 0 {
+        post_reply_repository::release_claimed_replies(
+            failed_to_send_post_reply_ids,
+            database
+        )
+            .await
+            .with_context(|| {
+                return "update_claimed_delivery_state() Failed to release claimed post replies";
+            })?;
+    }
+
+    return Ok(());
 }
 
 async fn send_unsent_reply(
     is_dev_build: bool,
-    client: &fcm::Client,
-    firebase_api_key: &String,
+    fcm_transport: &Arc<dyn FcmTransport>,
     account_token: &AccountToken,
     unsent_replies: &HashSet<UnsentReply>,
     successfully_sent: &Arc<RwLock<HashSet<i64>>>,
@@ -187,14 +601,23 @@ async fn send_unsent_reply(
         return Ok(());
     }
 
+    let (new_reply_messages, coalesced_reply_messages) = coalesce_fcm_reply_messages(
+        new_reply_messages,
+        max_replies_per_thread()
+    );
+
     let new_fcm_replies_message = NewFcmRepliesMessage {
-        new_reply_messages
+        payload_version: FCM_REPLIES_PAYLOAD_VERSION,
+        server_time: chrono::offset::Utc::now(),
+        new_reply_messages,
+        coalesced_reply_messages
     };
 
     info!(
-        "send_unsent_reply({}) new_reply_messages: {}",
+        "send_unsent_reply({}) new_reply_messages: {}, coalesced_reply_messages: {}",
         account_token,
-        new_fcm_replies_message.new_reply_messages.len()
+        new_fcm_replies_message.new_reply_messages.len(),
+        new_fcm_replies_message.coalesced_reply_messages.len()
     );
 
     if is_dev_build {
@@ -213,15 +636,29 @@ async fn send_unsent_reply(
     let mut map = HashMap::new();
     map.insert("message_body", new_fcm_replies_message_json);
 
-    let mut builder = fcm::MessageBuilder::new(firebase_api_key.as_str(), account_token.token.as_str());
-    builder
-        .priority(Priority::High)
-        .data(&map)?;
+    let map_ref = &map;
+
+    let send_result = send_with_retries(
+        FCM_SEND_MAX_ATTEMPTS,
+        FCM_SEND_RETRY_BASE_DELAY,
+        move || async move {
+            let result = fcm_transport.send(
+                account_token.token.as_str(),
+                map_ref,
+                FcmMessagePriority::High
+            ).await;
+
+            if result.is_err() {
+                return FcmSendAttemptResult::PermanentError(result.err().unwrap().to_string());
+            }
+
+            return result.unwrap();
+        }
+    ).await;
 
-    let response = client.send(builder.finalize()).await?;
+    if send_result != FcmSendAttemptResult::Sent {
+        metrics::on_fcm_messages_failed(1);
 
-    let error = response.error;
-    if error.is_some() {
         {
             let mut failed_to_send_locked = failed_to_send.write().await;
             unsent_replies
@@ -231,13 +668,14 @@ async fn send_unsent_reply(
                 });
         }
 
-        let error = error.unwrap();
         error!(
             "send_unsent_reply({}) Failed to send FCM messages because of error: {:?}",
             account_token,
-            error
+            send_result
         );
     } else {
+        metrics::on_fcm_messages_sent(1);
+
         {
             let mut successfully_sent_locked = successfully_sent.write().await;
             unsent_replies
@@ -257,13 +695,271 @@ async fn send_unsent_reply(
     return Ok(());
 }
 
-fn convert_unsent_replies_to_fcm_messages(
+async fn send_unsent_catalog_notifications(
+    fcm_transport: &Arc<dyn FcmTransport>,
+    account_token: &AccountToken,
+    unsent_notifications: &Vec<UnsentCatalogNotification>,
+    site_repository: &Arc<SiteRepository>
+) -> anyhow::Result<bool> {
+    let new_catalog_thread_messages = unsent_notifications.iter()
+        .filter_map(|unsent_notification| {
+            let thread_descriptor = ThreadDescriptor::from_catalog_descriptor(
+                unsent_notification.catalog_descriptor.clone(),
+                unsent_notification.thread_no
+            );
+
+            let post_descriptor = PostDescriptor::from_thread_descriptor(
+                thread_descriptor,
+                unsent_notification.thread_no,
+                0
+            );
+
+            let thread_url = site_repository.to_url(&post_descriptor);
+            if thread_url.is_none() {
+                return None;
+            }
+
+            let fcm_catalog_thread_message = FcmCatalogThreadMessage {
+                notification_id: unsent_notification.notification_id as u64,
+                thread_url: thread_url.unwrap(),
+                thread_subject: unsent_notification.subject.clone()
+            };
+
+            return Some(fcm_catalog_thread_message);
+        })
+        .collect::<Vec<FcmCatalogThreadMessage>>();
+
+    if new_catalog_thread_messages.is_empty() {
+        info!(
+            "send_unsent_catalog_notifications({}) new_catalog_thread_messages is empty",
+            account_token
+        );
+
+        return Ok(false);
+    }
+
+    let new_fcm_catalog_threads_message = NewFcmCatalogThreadsMessage {
+        new_catalog_thread_messages
+    };
+
+    let message_json = serde_json::to_string(&new_fcm_catalog_threads_message)?;
+
+    let mut map = HashMap::new();
+    map.insert("catalog_threads_message_body", message_json);
+
+    let map_ref = &map;
+
+    let send_result = send_with_retries(
+        FCM_SEND_MAX_ATTEMPTS,
+        FCM_SEND_RETRY_BASE_DELAY,
+        move || async move {
+            let result = fcm_transport.send(
+                account_token.token.as_str(),
+                map_ref,
+                FcmMessagePriority::High
+            ).await;
+
+            if result.is_err() {
+                return FcmSendAttemptResult::PermanentError(result.err().unwrap().to_string());
+            }
+
+            return result.unwrap();
+        }
+    ).await;
+
+    if send_result != FcmSendAttemptResult::Sent {
+        metrics::on_fcm_messages_failed(1);
+
+        error!(
+            "send_unsent_catalog_notifications({}) Failed to send FCM message because of error: {:?}",
+            account_token,
+            send_result
+        );
+
+        return Ok(false);
+    }
+
+    metrics::on_fcm_messages_sent(1);
+
+    info!(
+        "send_unsent_catalog_notifications({}) Successfully sent a batch of {} new catalog threads",
+        account_token,
+        unsent_notifications.len()
+    );
+
+    return Ok(true);
+}
+
+async fn send_watch_confirmation(
+    fcm_transport: &Arc<dyn FcmTransport>,
+    account_token: &AccountToken,
+    watched_post_urls: Vec<String>
+) -> anyhow::Result<bool> {
+    let watched_post_urls_count = watched_post_urls.len();
+    let fcm_watch_confirmed_message = FcmWatchConfirmedMessage { watched_post_urls };
+    let message_json = serde_json::to_string(&fcm_watch_confirmed_message)?;
+
+    let mut map = HashMap::new();
+    map.insert("watch_confirmed_message_body", message_json);
+
+    let map_ref = &map;
+
+    let send_result = send_with_retries(
+        FCM_SEND_MAX_ATTEMPTS,
+        FCM_SEND_RETRY_BASE_DELAY,
+        move || async move {
+            let result = fcm_transport.send(
+                account_token.token.as_str(),
+                map_ref,
+                FcmMessagePriority::Normal
+            ).await;
+
+            if result.is_err() {
+                return FcmSendAttemptResult::PermanentError(result.err().unwrap().to_string());
+            }
+
+            return result.unwrap();
+        }
+    ).await;
+
+    if send_result != FcmSendAttemptResult::Sent {
+        metrics::on_fcm_messages_failed(1);
+
+        error!(
+            "send_watch_confirmation({}) Failed to send FCM message because of error: {:?}",
+            account_token,
+            send_result
+        );
+
+        return Ok(false);
+    }
+
+    metrics::on_fcm_messages_sent(1);
+
+    info!(
+        "send_watch_confirmation({}) Successfully confirmed {} watches",
+        account_token,
+        watched_post_urls_count
+    );
+
+    return Ok(true);
+}
+
+/// Sends a message using [send] retrying up to [max_attempts] times with exponential backoff
+/// when the error is transient. Permanent errors are returned right away.
+pub async fn send_with_retries<F, Fut>(
+    max_attempts: u32,
+    base_delay: Duration,
+    send: F
+) -> FcmSendAttemptResult
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = FcmSendAttemptResult>
+{
+    let mut attempt = 1;
+
+    loop {
+        let result = send().await;
+
+        let reason = match &result {
+            FcmSendAttemptResult::Sent => return result,
+            FcmSendAttemptResult::PermanentError(_) => return result,
+            FcmSendAttemptResult::TransientError(reason) => reason.clone()
+        };
+
+        if attempt >= max_attempts {
+            return result;
+        }
+
+        let delay = base_delay * 2u32.pow(attempt - 1);
+
+        info!(
+            "send_with_retries() attempt {}/{} failed with transient error: {}, retrying in {} ms",
+            attempt,
+            max_attempts,
+            reason,
+            delay.as_millis()
+        );
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// 0 disables the limit.
+pub fn set_max_replies_per_thread(max_replies_per_thread: usize) {
+    MAX_REPLIES_PER_THREAD.store(max_replies_per_thread, Ordering::Relaxed);
+}
+
+pub fn max_replies_per_thread() -> usize {
+    return MAX_REPLIES_PER_THREAD.load(Ordering::Relaxed);
+}
+
+/// Keeps at most [max_replies_per_thread] (the oldest) replies of every thread and collapses the
+/// rest into one "N more replies" message per thread so that a raid on a watched post doesn't
+/// flood the user and FCM. The collapsed replies are still marked as delivered.
+pub fn coalesce_fcm_reply_messages(
+    fcm_reply_messages: Vec<FcmReplyMessage>,
+    max_replies_per_thread: usize
+) -> (Vec<FcmReplyMessage>, Vec<FcmCoalescedRepliesMessage>) {
+    if max_replies_per_thread == 0 {
+        return (fcm_reply_messages, vec![]);
+    }
+
+    let mut fcm_reply_messages = fcm_reply_messages;
+    fcm_reply_messages.sort_by_key(|fcm_reply_message| fcm_reply_message.reply_id);
+
+    let mut kept_reply_messages = Vec::<FcmReplyMessage>::with_capacity(fcm_reply_messages.len());
+    let mut replies_per_thread = HashMap::<String, usize>::new();
+    let mut coalesced_per_thread = HashMap::<String, FcmCoalescedRepliesMessage>::new();
+
+    for fcm_reply_message in fcm_reply_messages {
+        let thread_replies = replies_per_thread.entry(fcm_reply_message.group_key.clone()).or_insert(0);
+        *thread_replies += 1;
+
+        if *thread_replies <= max_replies_per_thread {
+            kept_reply_messages.push(fcm_reply_message);
+            continue;
+        }
+
+        let coalesced = coalesced_per_thread.entry(fcm_reply_message.group_key.clone())
+            .or_insert_with(|| {
+                return FcmCoalescedRepliesMessage {
+                    replies_count: 0,
+                    last_reply_url: String::new(),
+                    thread_title: fcm_reply_message.thread_title.clone(),
+                    group_key: fcm_reply_message.group_key.clone()
+                };
+            });
+
+        // Sorted by reply_id so the last one is the newest reply
+        coalesced.replies_count += 1;
+        coalesced.last_reply_url = fcm_reply_message.new_reply_url;
+    }
+
+    let mut coalesced_reply_messages = coalesced_per_thread.into_values()
+        .collect::<Vec<FcmCoalescedRepliesMessage>>();
+    coalesced_reply_messages.sort_by(|first, second| first.group_key.cmp(&second.group_key));
+
+    return (kept_reply_messages, coalesced_reply_messages);
+}
+
+pub fn convert_unsent_replies_to_fcm_messages(
     unsent_replies: &HashSet<UnsentReply>,
     site_repository: &Arc<SiteRepository>
 ) -> Vec<FcmReplyMessage> {
+    let mut processed_post_descriptors =
+        HashSet::<(&PostDescriptor, &PostDescriptor)>::with_capacity(unsent_replies.len());
+
     return unsent_replies
         .into_iter()
         .filter_map(|unsent_reply| {
+            // Merged replies may contain the same reply multiple times (once per application type)
+            let key = (&unsent_reply.post_descriptor, &unsent_reply.reply_to);
+            if !processed_post_descriptors.insert(key) {
+                return None;
+            }
+
             let post_url = site_repository.to_url(&unsent_reply.post_descriptor);
             if post_url.is_none() {
                 return None;
@@ -271,9 +967,19 @@ fn convert_unsent_replies_to_fcm_messages(
 
             let post_url = post_url.unwrap();
 
+            let watched_post_url = site_repository.to_url(&unsent_reply.reply_to);
+            if watched_post_url.is_none() {
+                return None;
+            }
+
+            let watched_post_url = watched_post_url.unwrap();
+
             let fcm_reply_message = FcmReplyMessage {
                 reply_id: unsent_reply.post_reply_id as u64,
-                new_reply_url: post_url
+                new_reply_url: post_url,
+                watched_post_url,
+                thread_title: unsent_reply.thread_title.clone(),
+                group_key: thread_group_key(&unsent_reply.post_descriptor.thread_descriptor)
             };
 
             return Some(fcm_reply_message);
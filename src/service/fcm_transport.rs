@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use fcm::{ErrorReason, FcmError, FcmResponse, Priority};
+
+use crate::service::fcm_sender::FcmSendAttemptResult;
+
+/// Low priority messages may be delayed by FCM to save the device's battery.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FcmMessagePriority {
+    High,
+    Normal
+}
+
+/// Delivers a single data message to one token (an FCM token or a WebPush endpoint). Exists so that
+/// FcmSender can be run against something other than the real FCM servers.
+#[async_trait]
+pub trait FcmTransport : Send + Sync {
+    /// Returns an error only when the message could not even be built, errors returned by FCM
+    /// itself are classified into FcmSendAttemptResult.
+    async fn send(
+        &self,
+        token: &str,
+        data: &HashMap<&str, String>,
+        priority: FcmMessagePriority
+    ) -> anyhow::Result<FcmSendAttemptResult>;
+}
+
+pub struct FirebaseFcmTransport {
+    firebase_api_key: String,
+    client: fcm::Client
+}
+
+impl FirebaseFcmTransport {
+    pub fn new(firebase_api_key: String) -> FirebaseFcmTransport {
+        return FirebaseFcmTransport {
+            firebase_api_key,
+            client: fcm::Client::new()
+        };
+    }
+}
+
+#[async_trait]
+impl FcmTransport for FirebaseFcmTransport {
+    async fn send(
+        &self,
+        token: &str,
+        data: &HashMap<&str, String>,
+        priority: FcmMessagePriority
+    ) -> anyhow::Result<FcmSendAttemptResult> {
+        let mut builder = fcm::MessageBuilder::new(self.firebase_api_key.as_str(), token);
+
+        let priority = match priority {
+            FcmMessagePriority::High => Priority::High,
+            FcmMessagePriority::Normal => Priority::Normal
+        };
+
+        builder.priority(priority)
+            .data(data)
+            .context("Failed to serialize message data")?;
+
+        let result = self.client.send(builder.finalize()).await;
+        return Ok(classify_fcm_send_result(result));
+    }
+}
+
+fn classify_fcm_send_result(result: Result<FcmResponse, FcmError>) -> FcmSendAttemptResult {
+    if result.is_err() {
+        let error = result.err().unwrap();
+
+        return match error {
+            FcmError::Unauthorized | FcmError::InvalidMessage(_) => {
+                FcmSendAttemptResult::PermanentError(format!("{:?}", error))
+            }
+            _ => FcmSendAttemptResult::TransientError(format!("{:?}", error))
+        };
+    }
+
+    let response = result.unwrap();
+    if response.error.is_none() {
+        return FcmSendAttemptResult::Sent;
+    }
+
+    let error_reason = response.error.unwrap();
+
+    return match error_reason {
+        ErrorReason::Unavailable |
+        ErrorReason::InternalServerError |
+        ErrorReason::DeviceMessageRateExceeded |
+        ErrorReason::TopicsMessageRateExceeded => {
+            FcmSendAttemptResult::TransientError(format!("{:?}", error_reason))
+        }
+        _ => FcmSendAttemptResult::PermanentError(format!("{:?}", error_reason))
+    };
+}
@@ -5,24 +5,32 @@ use crate::{error, info};
 use crate::model::database::db::Database;
 use crate::model::repository::invites_repository;
 
-pub async fn invites_cleanup_task(database: &Arc<Database>) {
-    info!("invites_cleanup_task() start");
+pub async fn invites_cleanup_task(database: &Arc<Database>, interval_minutes: u64) {
+    // 0 would turn this into a busy loop hammering the database
+    let interval_minutes = interval_minutes.max(1);
+    info!("invites_cleanup_task() start, interval_minutes: {}", interval_minutes);
 
     loop {
         info!("invites_cleanup_task() cleaning up...");
 
-        let result = invites_repository::cleanup(database).await;
-        let deleted = if result.is_err() {
-            error!("invites_cleanup_task::cleanup() error: {}", anyhow::anyhow!(result.err().unwrap()));
-            0
-        } else {
-            result.unwrap()
-        };
+        let deleted = cleanup_invites(database).await;
 
         info!("invites_cleanup_task() cleaning up... done, deleted: {}, waiting...", deleted);
-        tokio::time::sleep(Duration::from_secs(30 * 60)).await;
+        tokio::time::sleep(Duration::from_secs(interval_minutes * 60)).await;
         info!("invites_cleanup_task() waiting... done");
     }
 
     info!("invites_cleanup_task() end");
+}
+
+/// Deletes the expired invites that were never accepted. Errors are only logged so that the task
+/// keeps running and tries again next time, returns the amount of deleted invites.
+pub async fn cleanup_invites(database: &Arc<Database>) -> u64 {
+    let result = invites_repository::cleanup(database).await;
+    if result.is_err() {
+        error!("cleanup_invites() error: {}", result.err().unwrap());
+        return 0;
+    }
+
+    return result.unwrap();
 }
\ No newline at end of file
@@ -0,0 +1,139 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static THREADS_PROCESSED_PER_CYCLE: AtomicU64 = AtomicU64::new(0);
+static WATCHER_ITERATION_DURATION_MS: AtomicU64 = AtomicU64::new(0);
+static WATCHER_ITERATIONS_TOTAL: AtomicU64 = AtomicU64::new(0);
+static FCM_MESSAGES_SENT_TOTAL: AtomicU64 = AtomicU64::new(0);
+static FCM_MESSAGES_FAILED_TOTAL: AtomicU64 = AtomicU64::new(0);
+static UNSENT_REPLIES_BACKLOG: AtomicU64 = AtomicU64::new(0);
+static HTTP_REQUESTS_TOTAL: AtomicU64 = AtomicU64::new(0);
+static HTTP_REQUEST_ERRORS_TOTAL: AtomicU64 = AtomicU64::new(0);
+
+pub struct DatabasePoolState {
+    pub connections: u32,
+    pub idle_connections: u32
+}
+
+pub fn on_watcher_iteration_finished(processed_threads: usize, duration_ms: i64) {
+    THREADS_PROCESSED_PER_CYCLE.store(processed_threads as u64, Ordering::Relaxed);
+    WATCHER_ITERATION_DURATION_MS.store(duration_ms.max(0) as u64, Ordering::Relaxed);
+    WATCHER_ITERATIONS_TOTAL.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn on_fcm_messages_sent(count: usize) {
+    FCM_MESSAGES_SENT_TOTAL.fetch_add(count as u64, Ordering::Relaxed);
+}
+
+pub fn on_fcm_messages_failed(count: usize) {
+    FCM_MESSAGES_FAILED_TOTAL.fetch_add(count as u64, Ordering::Relaxed);
+}
+
+pub fn set_unsent_replies_backlog(count: usize) {
+    UNSENT_REPLIES_BACKLOG.store(count as u64, Ordering::Relaxed);
+}
+
+pub fn on_http_request(is_error: bool) {
+    HTTP_REQUESTS_TOTAL.fetch_add(1, Ordering::Relaxed);
+
+    if is_error {
+        HTTP_REQUEST_ERRORS_TOTAL.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub fn encode(database_pool_state: &DatabasePoolState) -> String {
+    let mut result = String::with_capacity(2048);
+
+    let in_use_connections = database_pool_state.connections
+        .saturating_sub(database_pool_state.idle_connections);
+
+    write_metric(
+        &mut result,
+        "kpns_threads_processed_per_cycle",
+        "gauge",
+        "Amount of watched threads processed during the last watcher iteration",
+        THREADS_PROCESSED_PER_CYCLE.load(Ordering::Relaxed)
+    );
+    write_metric(
+        &mut result,
+        "kpns_watcher_iteration_duration_milliseconds",
+        "gauge",
+        "Duration of the last watcher iteration",
+        WATCHER_ITERATION_DURATION_MS.load(Ordering::Relaxed)
+    );
+    write_metric(
+        &mut result,
+        "kpns_watcher_iterations_total",
+        "counter",
+        "Amount of finished watcher iterations",
+        WATCHER_ITERATIONS_TOTAL.load(Ordering::Relaxed)
+    );
+    write_metric(
+        &mut result,
+        "kpns_fcm_messages_sent_total",
+        "counter",
+        "Amount of FCM messages successfully sent",
+        FCM_MESSAGES_SENT_TOTAL.load(Ordering::Relaxed)
+    );
+    write_metric(
+        &mut result,
+        "kpns_fcm_messages_failed_total",
+        "counter",
+        "Amount of FCM messages that failed to be sent",
+        FCM_MESSAGES_FAILED_TOTAL.load(Ordering::Relaxed)
+    );
+    write_metric(
+        &mut result,
+        "kpns_unsent_replies_backlog",
+        "gauge",
+        "Amount of unsent replies found during the last FCM sending pass",
+        UNSENT_REPLIES_BACKLOG.load(Ordering::Relaxed)
+    );
+    write_metric(
+        &mut result,
+        "kpns_db_pool_connections_in_use",
+        "gauge",
+        "Amount of database pool connections currently in use",
+        in_use_connections as u64
+    );
+    write_metric(
+        &mut result,
+        "kpns_db_pool_connections",
+        "gauge",
+        "Amount of database pool connections currently open",
+        database_pool_state.connections as u64
+    );
+    write_metric(
+        &mut result,
+        "kpns_http_requests_total",
+        "counter",
+        "Amount of handled http requests",
+        HTTP_REQUESTS_TOTAL.load(Ordering::Relaxed)
+    );
+    write_metric(
+        &mut result,
+        "kpns_http_request_errors_total",
+        "counter",
+        "Amount of http requests which handlers returned an error",
+        HTTP_REQUEST_ERRORS_TOTAL.load(Ordering::Relaxed)
+    );
+
+    return result;
+}
+
+fn write_metric(result: &mut String, name: &str, metric_type: &str, help: &str, value: u64) {
+    let _ = writeln!(result, "# HELP {} {}", name, help);
+    let _ = writeln!(result, "# TYPE {} {}", name, metric_type);
+    let _ = writeln!(result, "{} {}", name, value);
+}
+
+#[test]
+fn test_encode() {
+    let database_pool_state = DatabasePoolState { connections: 8, idle_connections: 6 };
+    let encoded = encode(&database_pool_state);
+
+    assert!(encoded.contains("# TYPE kpns_threads_processed_per_cycle gauge\n"));
+    assert!(encoded.contains("# TYPE kpns_fcm_messages_sent_total counter\n"));
+    assert!(encoded.contains("\nkpns_db_pool_connections_in_use 2\n"));
+    assert!(encoded.contains("\nkpns_db_pool_connections 8\n"));
+}
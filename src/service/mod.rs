@@ -1,3 +1,9 @@
 pub mod thread_watcher;
+pub mod catalog_watcher;
 pub mod fcm_sender;
-pub mod invites_cleanup;
\ No newline at end of file
+pub mod fcm_transport;
+pub mod invites_cleanup;
+pub mod accounts_cache_verifier;
+pub mod dead_threads_cleanup;
+pub mod metrics;
+pub mod web_push_transport;
\ No newline at end of file
@@ -1,32 +1,75 @@
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering as AtomicOrdering};
 use std::time::Duration;
 
 use anyhow::{anyhow, Context};
+use chrono::{DateTime, FixedOffset, Utc};
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
 
-use crate::{error, info};
-use crate::helpers::post_helpers;
-use crate::model::data::chan::{ChanThread, PostDescriptor, ThreadDescriptor};
+use crate::{constants, error, info};
+use crate::helpers::{post_helpers, regex_helpers};
+use crate::helpers::serde_helpers::{deserialize_datetime, serialize_datetime_option};
+use crate::model::data::chan::{CatalogThread, ChanThread, PostDescriptor, ThreadActivityState, ThreadDescriptor};
 use crate::model::database::db::Database;
-use crate::model::imageboards::base_imageboard::ThreadLoadResult;
+use crate::model::imageboards::base_imageboard::{CatalogLoadResult, ThreadLoadResult};
 use crate::model::repository::{post_descriptor_id_repository, post_reply_repository, post_repository, thread_repository};
 use crate::model::repository::site_repository::SiteRepository;
+use crate::service::catalog_watcher;
 use crate::service::fcm_sender::FcmSender;
+use crate::service::metrics;
+
+const MAX_QUOTE_POST_NO_DISTANCE: u64 = 1_000_000;
+
+/// Comments of thread snapshots are cut to this many characters.
+const THREAD_SNAPSHOT_MAX_COMMENT_LENGTH: usize = 256;
+
+/// A single 404 may be transient (e.g. a cdn hiccup) so a thread is only marked as dead once it
+/// 404'd this many times in a row.
+const THREAD_NOT_FOUND_THRESHOLD: u32 = 2;
 
 lazy_static! {
-    static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::new();
+    static ref THREAD_NOT_FOUND_COUNTERS: Mutex<HashMap<ThreadDescriptor, u32>> =
+        Mutex::new(HashMap::with_capacity(128));
 }
 
+/// Threads without new posts for this many days are considered to have fallen off the board and
+/// are marked as dead without waiting for them to 404. 0 means disabled.
+static MAX_THREAD_AGE_DAYS: AtomicU64 = AtomicU64::new(0);
+
+/// When enabled, the watches of a thread that died are moved to the thread that replaced it (the
+/// next thread of a general) on sites that support it.
+static FOLLOW_SUCCESSOR_THREADS: AtomicBool = AtomicBool::new(false);
+
+/// When enabled, a reply is not sent to an account that also watches the post the reply was made
+/// from. Post authorship is not tracked, so watching the reply is used as a proxy for having written
+/// it (the clients watch the posts the user makes). This means that replies made from a post that
+/// the user watches for any other reason are suppressed as well.
+static SUPPRESS_SELF_REPLIES: AtomicBool = AtomicBool::new(false);
+
+/// Amount of threads processed concurrently (and FCM messages sent concurrently) per chunk. 0 means
+/// it's computed from the amount of cpu cores.
+static WATCHER_CHUNK_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+/// Upper bound for the chunk size, be it computed or overridden.
+static WATCHER_MAX_CONCURRENCY: AtomicUsize = AtomicUsize::new(0);
+
+// Thread processing is network bound so we can go way above the amount of cpu cores
+const WATCHER_CHUNK_SIZE_PER_CPU: usize = 16;
+
 pub struct ThreadWatcher {
     num_cpus: u32,
     timeout_seconds: u64,
     is_dev_build: bool,
+    http_client: Arc<reqwest::Client>,
     working: bool
 }
 
@@ -36,16 +79,213 @@ pub struct FoundPostReply {
     pub replies_to: PostDescriptor
 }
 
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ThreadProcessingReport {
+    pub load_result: String,
+    pub posts_fetched: usize,
+    pub new_posts: usize,
+    pub quotes_found: usize,
+    pub matched_watches: usize,
+    pub replies_to_send: usize
+}
+
+/// What the server sees when it loads a thread, used for debugging missed notifications.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ThreadSnapshot {
+    pub load_result: String,
+    pub closed: bool,
+    pub archived: bool,
+    pub subject: Option<String>,
+    pub posts: Vec<ThreadSnapshotPost>,
+    pub last_processed_post: Option<PostDescriptor>,
+    #[serde(
+        serialize_with = "serialize_datetime_option",
+        deserialize_with = "deserialize_datetime"
+    )]
+    pub last_modified: Option<DateTime<Utc>>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ThreadSnapshotPost {
+    pub post_no: u64,
+    pub post_sub_no: Option<u64>,
+    pub comment: Option<String>
+}
+
+/// How processing of a single thread ended, tallied per site to see which sites are failing.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ThreadProcessOutcome {
+    Success,
+    NotModified,
+    Dead,
+    BadStatus,
+    Inaccessible
+}
+
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct SiteOutcomeTallies {
+    pub success: u64,
+    pub not_modified: u64,
+    pub dead: u64,
+    pub bad_status: u64,
+    pub inaccessible: u64
+}
+
+/// Accumulated over one watcher iteration and logged once it's done.
+#[derive(Debug, Default)]
+pub struct WatcherCycleStats {
+    tallies: BTreeMap<String, SiteOutcomeTallies>
+}
+
+impl WatcherCycleStats {
+    pub fn record(&mut self, site_name: &str, outcome: ThreadProcessOutcome) {
+        if !self.tallies.contains_key(site_name) {
+            self.tallies.insert(site_name.to_string(), SiteOutcomeTallies::default());
+        }
+
+        let tallies = self.tallies.get_mut(site_name).unwrap();
+
+        match outcome {
+            ThreadProcessOutcome::Success => tallies.success += 1,
+            ThreadProcessOutcome::NotModified => tallies.not_modified += 1,
+            ThreadProcessOutcome::Dead => tallies.dead += 1,
+            ThreadProcessOutcome::BadStatus => tallies.bad_status += 1,
+            ThreadProcessOutcome::Inaccessible => tallies.inaccessible += 1,
+        }
+    }
+
+    pub fn tallies(&self, site_name: &str) -> Option<&SiteOutcomeTallies> {
+        return self.tallies.get(site_name);
+    }
+}
+
+impl Display for WatcherCycleStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.tallies.is_empty() {
+            return write!(f, "<empty>");
+        }
+
+        for (index, (site_name, tallies)) in self.tallies.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(
+                f,
+                "{}[ok: {}, not_modified: {}, dead: {}, bad_status: {}, inaccessible: {}]",
+                site_name,
+                tallies.success,
+                tallies.not_modified,
+                tallies.dead,
+                tallies.bad_status,
+                tallies.inaccessible
+            )?;
+        }
+
+        return Ok(());
+    }
+}
+
+pub fn set_max_thread_age_days(max_thread_age_days: u64) {
+    MAX_THREAD_AGE_DAYS.store(max_thread_age_days, AtomicOrdering::Relaxed);
+}
+
+pub fn max_thread_age_days() -> u64 {
+    return MAX_THREAD_AGE_DAYS.load(AtomicOrdering::Relaxed);
+}
+
+pub fn set_follow_successor_threads(follow_successor_threads: bool) {
+    FOLLOW_SUCCESSOR_THREADS.store(follow_successor_threads, AtomicOrdering::Relaxed);
+}
+
+pub fn follow_successor_threads() -> bool {
+    return FOLLOW_SUCCESSOR_THREADS.load(AtomicOrdering::Relaxed);
+}
+
+pub fn set_suppress_self_replies(suppress_self_replies: bool) {
+    SUPPRESS_SELF_REPLIES.store(suppress_self_replies, AtomicOrdering::Relaxed);
+}
+
+pub fn suppress_self_replies() -> bool {
+    return SUPPRESS_SELF_REPLIES.load(AtomicOrdering::Relaxed);
+}
+
+pub fn set_watcher_chunk_size(chunk_size: Option<usize>) {
+    WATCHER_CHUNK_SIZE.store(chunk_size.unwrap_or(0), AtomicOrdering::Relaxed);
+}
+
+pub fn set_watcher_max_concurrency(max_concurrency: Option<usize>) {
+    WATCHER_MAX_CONCURRENCY.store(max_concurrency.unwrap_or(0), AtomicOrdering::Relaxed);
+}
+
+/// Parses the value of WATCHER_CHUNK_SIZE or WATCHER_MAX_CONCURRENCY, None when the variable is not
+/// set.
+pub fn parse_watcher_limit(name: &str, value: Option<&str>) -> anyhow::Result<Option<usize>> {
+    if value.is_none() {
+        return Ok(None);
+    }
+
+    let value = value.unwrap().trim();
+
+    let limit = usize::from_str(value)
+        .with_context(|| format!("Failed to parse {} \'{}\'", name, value))?;
+
+    if limit == 0 || limit > constants::MAX_WATCHER_CONCURRENCY {
+        return Err(anyhow!("{} must be in range 1..={}", name, constants::MAX_WATCHER_CONCURRENCY));
+    }
+
+    return Ok(Some(limit));
+}
+
+/// Uses the chunk size override when there is one, otherwise the chunk size is computed from the
+/// amount of cpu cores and clamped to MIN_WATCHER_CHUNK_SIZE..=max_concurrency. Either way the
+/// result never exceeds max_concurrency.
+pub fn watcher_chunk_size(
+    num_cpus: u32,
+    chunk_size_override: Option<usize>,
+    max_concurrency: Option<usize>
+) -> usize {
+    let max_concurrency = max_concurrency.unwrap_or(constants::DEFAULT_WATCHER_MAX_CONCURRENCY);
+
+    if chunk_size_override.is_some() {
+        return chunk_size_override.unwrap().min(max_concurrency);
+    }
+
+    let chunk_size = (num_cpus as usize) * WATCHER_CHUNK_SIZE_PER_CPU;
+    return chunk_size.max(constants::MIN_WATCHER_CHUNK_SIZE).min(max_concurrency);
+}
+
+fn current_watcher_chunk_size(num_cpus: u32) -> usize {
+    let chunk_size_override = Some(WATCHER_CHUNK_SIZE.load(AtomicOrdering::Relaxed))
+        .filter(|chunk_size| *chunk_size > 0);
+    let max_concurrency = Some(WATCHER_MAX_CONCURRENCY.load(AtomicOrdering::Relaxed))
+        .filter(|max_concurrency| *max_concurrency > 0);
+
+    return watcher_chunk_size(num_cpus, chunk_size_override, max_concurrency);
+}
+
 impl ThreadWatcher {
-    pub fn new(num_cpus: u32, timeout_seconds: u64, is_dev_build: bool) -> ThreadWatcher {
+    /// [http_client] must be the same client the SiteRepository was created with so that they
+    /// share one connection pool.
+    pub fn new(
+        num_cpus: u32,
+        timeout_seconds: u64,
+        is_dev_build: bool,
+        http_client: &Arc<reqwest::Client>
+    ) -> ThreadWatcher {
         return ThreadWatcher {
             num_cpus,
             timeout_seconds,
             is_dev_build,
+            http_client: http_client.clone(),
             working: false
         };
     }
 
+    pub fn http_client(&self) -> &Arc<reqwest::Client> {
+        return &self.http_client;
+    }
+
     pub async fn start(
         &mut self,
         database: &Arc<Database>,
@@ -65,13 +305,18 @@ impl ThreadWatcher {
                 break;
             }
 
+            let iteration_start = chrono::offset::Utc::now();
+
             let result = process_watched_threads(
                 self.num_cpus,
+                &self.http_client,
                 database,
                 site_repository,
                 fcm_sender
             ).await;
 
+            let iteration_delta = chrono::offset::Utc::now() - iteration_start;
+
             if self.is_dev_build && result.is_err() {
                 result.unwrap();
                 unreachable!();
@@ -93,6 +338,11 @@ impl ThreadWatcher {
                 }
             };
 
+            metrics::on_watcher_iteration_finished(
+                processed_threads,
+                iteration_delta.num_milliseconds()
+            );
+
             let timeout_seconds = match processed_threads {
                 0..=255 => default_timeout_seconds,
                 256..=1023 => default_timeout_seconds * 2,
@@ -109,53 +359,367 @@ impl ThreadWatcher {
         return Ok(());
     }
 
+    /// Loads the whole thread (ignoring the last processed post) without storing anything and
+    /// returns the parsed posts together with what is stored about the thread. The stored
+    /// ETag/Last-Modified are still honored so an unchanged thread comes without posts.
+    pub async fn load_thread_snapshot(
+        thread_descriptor: &ThreadDescriptor,
+        database: &Arc<Database>,
+        site_repository: &Arc<SiteRepository>
+    ) -> anyhow::Result<ThreadSnapshot> {
+        let last_processed_post = thread_repository::get_last_processed_post(
+            thread_descriptor,
+            database
+        ).await?;
+
+        let last_modified = thread_repository::get_last_modified(thread_descriptor, database)
+            .await?
+            .map(|last_modified| last_modified.with_timezone(&Utc));
+
+        let site_permit = site_repository.acquire_site_permit(thread_descriptor.site_descriptor()).await;
+
+        let thread_load_result = site_repository.load_thread(
+            site_repository.http_client(),
+            database,
+            &None,
+            thread_descriptor,
+        ).await;
+
+        drop(site_permit);
+
+        let thread_load_result = thread_load_result?;
+        let load_result = describe_thread_load_result(&thread_load_result);
+
+        let chan_thread = match thread_load_result {
+            ThreadLoadResult::Success(chan_thread, _, _) => chan_thread,
+            _ => {
+                let snapshot = ThreadSnapshot {
+                    load_result,
+                    last_processed_post,
+                    last_modified,
+                    ..ThreadSnapshot::default()
+                };
+
+                return Ok(snapshot);
+            }
+        };
+
+        let posts = chan_thread.posts.iter()
+            .map(|chan_post| {
+                let comment = chan_post.comment_unparsed.as_ref()
+                    .map(|comment| comment.chars().take(THREAD_SNAPSHOT_MAX_COMMENT_LENGTH).collect::<String>());
+
+                return ThreadSnapshotPost {
+                    post_no: chan_post.post_no,
+                    post_sub_no: chan_post.post_sub_no,
+                    comment
+                };
+            })
+            .collect::<Vec<ThreadSnapshotPost>>();
+
+        let snapshot = ThreadSnapshot {
+            load_result,
+            closed: chan_thread.closed,
+            archived: chan_thread.archived,
+            subject: chan_thread.subject,
+            posts,
+            last_processed_post,
+            last_modified
+        };
+
+        return Ok(snapshot);
+    }
+
+    /// Runs the load + parse + find replies pipeline for one thread without storing anything and
+    /// without sending any FCM messages. Used for debugging threads that don't produce notifications.
+    pub async fn process_single_thread(
+        thread_descriptor: &ThreadDescriptor,
+        database: &Arc<Database>,
+        site_repository: &Arc<SiteRepository>
+    ) -> anyhow::Result<ThreadProcessingReport> {
+        let last_processed_post = thread_repository::get_last_processed_post(
+            thread_descriptor,
+            database
+        ).await?;
+
+        let site_permit = site_repository.acquire_site_permit(thread_descriptor.site_descriptor()).await;
+
+        let thread_load_result = site_repository.load_thread(
+            site_repository.http_client(),
+            database,
+            &last_processed_post,
+            thread_descriptor,
+        ).await;
+
+        drop(site_permit);
+
+        let chan_thread = match thread_load_result? {
+            ThreadLoadResult::Success(chan_thread, _, _) => chan_thread,
+            thread_load_result => {
+                let report = ThreadProcessingReport {
+                    load_result: describe_thread_load_result(&thread_load_result),
+                    ..ThreadProcessingReport::default()
+                };
+
+                return Ok(report);
+            }
+        };
+
+        return preview_thread_processing(
+            site_repository,
+            &last_processed_post,
+            thread_descriptor,
+            &chan_thread,
+            database
+        ).await;
+    }
+
+}
+
+pub async fn preview_thread_processing(
+    site_repository: &Arc<SiteRepository>,
+    last_processed_post: &Option<PostDescriptor>,
+    thread_descriptor: &ThreadDescriptor,
+    chan_thread: &ChanThread,
+    database: &Arc<Database>
+) -> anyhow::Result<ThreadProcessingReport> {
+    let mut report = ThreadProcessingReport {
+        load_result: "Success".to_string(),
+        posts_fetched: chan_thread.posts.len(),
+        ..ThreadProcessingReport::default()
+    };
+
+    let imageboard = site_repository.by_site_descriptor(thread_descriptor.site_descriptor());
+    if imageboard.is_none() {
+        report.load_result = describe_thread_load_result(&ThreadLoadResult::SiteNotSupported);
+        return Ok(report);
+    }
+
+    let imageboard = imageboard.unwrap();
+
+    let mut found_post_replies_set =
+        HashSet::<FoundPostReply>::with_capacity(chan_thread.posts.len());
+    let mut new_posts_count = 0;
+
+    find_post_replies(
+        thread_descriptor,
+        chan_thread,
+        last_processed_post,
+        &mut found_post_replies_set,
+        &mut new_posts_count,
+        imageboard.post_quote_regexes()
+    );
+
+    report.new_posts = new_posts_count as usize;
+    report.quotes_found = found_post_replies_set.len();
+
+    if found_post_replies_set.is_empty() {
+        return Ok(report);
+    }
+
+    let found_post_replies = found_post_replies_set.iter().collect::<Vec<&FoundPostReply>>();
+
+    let post_descriptor_db_ids = post_descriptor_id_repository::get_many_found_post_reply_db_ids(
+        &found_post_replies
+    ).await;
+
+    if post_descriptor_db_ids.is_empty() {
+        return Ok(report);
+    }
+
+    let post_replies = post_repository::find_new_replies(
+        thread_descriptor,
+        database,
+        &post_descriptor_db_ids_to_vec_of_unique_keys(&post_descriptor_db_ids)
+    ).await?;
+
+    let post_comments = post_comments_by_descriptor(thread_descriptor, chan_thread);
+
+    report.matched_watches = post_replies.len();
+    report.replies_to_send = post_replies.iter()
+        .map(|post_reply| {
+            return post_descriptor_db_ids.get(&post_reply.owner_post_descriptor_id)
+                .map(|found_post_replies| {
+                    return found_post_replies.iter()
+                        .filter(|found_post_reply| {
+                            let post_comment = post_comments.get(&found_post_reply.origin)
+                                .cloned()
+                                .unwrap_or("");
+
+                            return regex_helpers::matches_filter_regex(&post_reply.filter_regex, post_comment);
+                        })
+                        .count();
+                })
+                .unwrap_or(0);
+        })
+        .sum();
+
+    return Ok(report);
+}
+
+fn describe_thread_load_result(thread_load_result: &ThreadLoadResult) -> String {
+    return match thread_load_result {
+        ThreadLoadResult::Success(_, _, _) => "Success".to_string(),
+        ThreadLoadResult::ThreadWasNotModifiedSinceLastCheck => "ThreadWasNotModifiedSinceLastCheck".to_string(),
+        ThreadLoadResult::SiteNotSupported => "SiteNotSupported".to_string(),
+        ThreadLoadResult::HeadRequestBadStatusCode(status_code) => {
+            format!("HeadRequestBadStatusCode({})", status_code)
+        }
+        ThreadLoadResult::GetRequestBadStatusCode(status_code) => {
+            format!("GetRequestBadStatusCode({})", status_code)
+        }
+        ThreadLoadResult::ThreadDeletedOrClosed => "ThreadDeletedOrClosed".to_string(),
+        ThreadLoadResult::ThreadInaccessible => "ThreadInaccessible".to_string(),
+        ThreadLoadResult::RequestTimedOut => "RequestTimedOut".to_string(),
+        ThreadLoadResult::FailedToReadChanThread(body_text_part) => {
+            format!("FailedToReadChanThread({})", body_text_part)
+        }
+        ThreadLoadResult::ServerSentIncorrectData(reason) => {
+            format!("ServerSentIncorrectData({})", reason)
+        }
+        ThreadLoadResult::ServerError(code, message) => {
+            format!("ServerError({}, {})", code, message)
+        }
+    };
 }
 
 async fn process_watched_threads(
     num_cpus: u32,
+    http_client: &Arc<reqwest::Client>,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>,
+    fcm_sender: &Arc<FcmSender>,
+) -> anyhow::Result<usize> {
+    // Watch confirmations are best effort, failing to send them must not stop the watcher
+    let watch_confirmations_result = fcm_sender.send_watch_confirmation_messages().await;
+    match watch_confirmations_result {
+        Ok(sent_watch_confirmations) => {
+            if sent_watch_confirmations > 0 {
+                info!(
+                    "process_watched_threads() sent out {} watch confirmation FCM messages",
+                    sent_watch_confirmations
+                );
+            }
+        }
+        Err(error) => {
+            error!(
+                "process_watched_threads() failed to send out watch confirmation FCM messages, error: {}",
+                error
+            );
+        }
+    }
+
+    let process_threads_result = process_threads_and_send_replies(
+        num_cpus,
+        http_client,
+        database,
+        site_repository,
+        fcm_sender
+    ).await;
+
+    // Catalogs are loaded one by one so they are processed after the replies were sent out,
+    // otherwise slow catalogs would delay the reply notifications.
+    process_catalogs_and_send_notifications(http_client, database, site_repository, fcm_sender).await;
+
+    return process_threads_result;
+}
+
+async fn process_catalogs_and_send_notifications(
+    http_client: &Arc<reqwest::Client>,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>,
+    fcm_sender: &Arc<FcmSender>
+) {
+    let catalog_watcher_result = catalog_watcher::process_watched_catalogs(
+        http_client,
+        database,
+        site_repository
+    ).await;
+
+    if catalog_watcher_result.is_err() {
+        error!(
+            "process_catalogs_and_send_notifications() failed to process watched catalogs, error: {}",
+            catalog_watcher_result.err().unwrap()
+        );
+    }
+
+    // Same as with the catalogs, one failed send must not fail the whole watcher iteration
+    let catalog_fcm_messages_result = fcm_sender.send_catalog_fcm_messages().await;
+    match catalog_fcm_messages_result {
+        Ok(sent_catalog_fcm_messages) => {
+            info!(
+                "process_catalogs_and_send_notifications() sent out {} catalog FCM messages",
+                sent_catalog_fcm_messages
+            );
+        }
+        Err(error) => {
+            error!(
+                "process_catalogs_and_send_notifications() failed to send out catalog FCM messages, error: {}",
+                error
+            );
+        }
+    }
+}
+
+async fn process_threads_and_send_replies(
+    num_cpus: u32,
+    http_client: &Arc<reqwest::Client>,
     database: &Arc<Database>,
     site_repository: &Arc<SiteRepository>,
     fcm_sender: &Arc<FcmSender>,
 ) -> anyhow::Result<usize> {
     let all_watched_threads = post_repository::get_all_watched_threads(database)
         .await
-        .context("process_watched_threads() Failed to get all watched threads")?;
+        .context("process_threads_and_send_replies() Failed to get all watched threads")?;
 
     if all_watched_threads.is_empty() {
-        info!("process_watched_threads() no watched threads to process");
+        info!("process_threads_and_send_replies() no watched threads to process");
         return Ok(0);
     }
 
-    let mut chunk_size: usize = (num_cpus * 4) as usize;
-    if chunk_size < 16 {
-        chunk_size = 16;
-    }
-    if chunk_size > 128 {
-        chunk_size = 128;
-    }
+    let chunk_size = current_watcher_chunk_size(num_cpus);
 
     info!(
-        "process_watched_threads() found {} watched threads, processing with chunk size {}",
+        "process_threads_and_send_replies() found {} watched threads, processing with chunk size {}",
         all_watched_threads.len(),
         chunk_size
     );
 
     let process_threads_start = chrono::offset::Utc::now();
+    let cycle_stats = Arc::new(Mutex::new(WatcherCycleStats::default()));
 
     for thread_descriptors in all_watched_threads.chunks(chunk_size) {
         let mut join_handles: Vec<JoinHandle<()>> = Vec::with_capacity(chunk_size);
 
         for thread_descriptor in thread_descriptors {
             let thread_descriptor_cloned = thread_descriptor.clone();
+            let http_client_cloned = http_client.clone();
             let database_cloned = database.clone();
             let site_repository_cloned = site_repository.clone();
+            let cycle_stats_cloned = cycle_stats.clone();
 
             let join_handle = tokio::task::spawn(async move {
-                process_thread(
+                let result = process_thread(
                     &thread_descriptor_cloned,
+                    &http_client_cloned,
                     &database_cloned,
                     &site_repository_cloned,
-                ).await.unwrap();
+                ).await;
+
+                // Errors are mostly the site sending garbage (or an error) instead of a thread
+                let outcome = match &result {
+                    Ok(outcome) => *outcome,
+                    Err(_) => Some(ThreadProcessOutcome::BadStatus)
+                };
+
+                if outcome.is_some() {
+                    cycle_stats_cloned.lock().await.record(
+                        thread_descriptor_cloned.site_name_str(),
+                        outcome.unwrap()
+                    );
+                }
+
+                result.unwrap();
             });
 
             join_handles.push(join_handle);
@@ -167,9 +731,10 @@ async fn process_watched_threads(
     let delta = chrono::offset::Utc::now() - process_threads_start;
     let send_fcm_messages_start = chrono::offset::Utc::now();
     info!(
-        "process_watched_threads() processing done, took {} ms, sending out FCM messages...",
+        "process_threads_and_send_replies() processing done, took {} ms, sending out FCM messages...",
         delta.num_milliseconds()
     );
+    info!("process_threads_and_send_replies() outcomes per site: {}", cycle_stats.lock().await);
 
     let sent_fcm_messages = fcm_sender.send_fcm_messages(chunk_size)
         .await
@@ -177,7 +742,7 @@ async fn process_watched_threads(
 
     let delta = chrono::offset::Utc::now() - send_fcm_messages_start;
     info!(
-        "process_watched_threads() sending out FCM messages done ({} messages sent), \
+        "process_threads_and_send_replies() sending out FCM messages done ({} messages sent), \
         took {} ms, success!",
         sent_fcm_messages,
         delta.num_milliseconds()
@@ -188,9 +753,17 @@ async fn process_watched_threads(
 
 async fn process_thread(
     thread_descriptor: &ThreadDescriptor,
+    http_client: &Arc<reqwest::Client>,
     database: &Arc<Database>,
     site_repository: &Arc<SiteRepository>
-) -> anyhow::Result<()> {
+) -> anyhow::Result<Option<ThreadProcessOutcome>> {
+    // Otherwise the threads of a disabled site would be marked as dead because the site is not
+    // supported.
+    if site_repository.is_site_disabled(thread_descriptor.site_descriptor()) {
+        info!("process_thread({}) skipping because the site is disabled", thread_descriptor);
+        return Ok(None);
+    }
+
     let last_processed_post = thread_repository::get_last_processed_post(
         thread_descriptor,
         database
@@ -209,15 +782,26 @@ async fn process_thread(
         );
     }
 
+    if mark_thread_as_dead_if_stale(thread_descriptor, max_thread_age_days(), database).await? {
+        return Ok(Some(ThreadProcessOutcome::Dead));
+    }
+
+    // Limit the amount of concurrent requests per site so that one site with lots of watched
+    // threads doesn't get us rate limited while threads of other sites are processed independently.
+    let site_permit = site_repository.acquire_site_permit(thread_descriptor.site_descriptor()).await;
+
     let thread_load_result = site_repository.load_thread(
-        &HTTP_CLIENT,
+        http_client,
         database,
         &last_processed_post,
         thread_descriptor,
-    ).await?;
+    ).await;
+
+    drop(site_permit);
+    let thread_load_result = thread_load_result?;
 
-    let (chan_thread, last_modified) = match thread_load_result {
-        ThreadLoadResult::Success(chan_thread, last_modified) => { (chan_thread, last_modified) }
+    let (chan_thread, last_modified, etag) = match thread_load_result {
+        ThreadLoadResult::Success(chan_thread, last_modified, etag) => { (chan_thread, last_modified, etag) }
         ThreadLoadResult::SiteNotSupported => {
             error!(
                 "process_thread({}) marking thread as dead because the site is not supported",
@@ -225,45 +809,44 @@ async fn process_thread(
             );
 
             post_repository::mark_thread_as_dead(database, thread_descriptor, true).await?;
-            return Ok(());
+            return Ok(Some(ThreadProcessOutcome::Dead));
         }
         ThreadLoadResult::HeadRequestBadStatusCode(status_code) => {
             error!("process_thread({}) (HEAD) bad status code {}", thread_descriptor, status_code);
 
-            if status_code == 404 {
-                error!(
-                    "process_thread({}) (HEAD) marking thread as dead because status code is 404",
-                    thread_descriptor
-                );
-
-                post_repository::mark_thread_as_dead(database, thread_descriptor, true).await?;
+            if status_code == 404 && on_thread_not_found(thread_descriptor, database).await? {
+                follow_successor_thread(thread_descriptor, http_client, database, site_repository).await;
+                return Ok(Some(ThreadProcessOutcome::Dead));
             }
 
-            return Ok(());
+            return Ok(Some(ThreadProcessOutcome::BadStatus));
         }
         ThreadLoadResult::GetRequestBadStatusCode(status_code) => {
             error!("process_thread({}) bad status code {}", thread_descriptor, status_code);
 
-            if status_code == 404 {
-                error!(
-                    "process_thread({}) marking thread as dead because status code is 404",
-                    thread_descriptor
-                );
-
-                post_repository::mark_thread_as_dead(database, thread_descriptor, true).await?;
+            if status_code == 404 && on_thread_not_found(thread_descriptor, database).await? {
+                follow_successor_thread(thread_descriptor, http_client, database, site_repository).await;
+                return Ok(Some(ThreadProcessOutcome::Dead));
             }
 
-            return Ok(());
+            return Ok(Some(ThreadProcessOutcome::BadStatus));
         }
         ThreadLoadResult::ThreadDeletedOrClosed => {
             error!("process_thread({}) thread is deleted or closed", thread_descriptor);
 
+            forget_thread_not_found(thread_descriptor).await;
             post_repository::mark_thread_as_dead(database, thread_descriptor, true).await?;
-            return Ok(());
+            follow_successor_thread(thread_descriptor, http_client, database, site_repository).await;
+
+            return Ok(Some(ThreadProcessOutcome::Dead));
         }
         ThreadLoadResult::ThreadInaccessible => {
             error!("process_thread({}) thread is inaccessible", thread_descriptor);
-            return Ok(());
+            return Ok(Some(ThreadProcessOutcome::Inaccessible));
+        }
+        ThreadLoadResult::RequestTimedOut => {
+            error!("process_thread({}) request timed out, will retry next time", thread_descriptor);
+            return Ok(Some(ThreadProcessOutcome::Inaccessible));
         }
         ThreadLoadResult::ServerSentIncorrectData(message) => {
             error!(
@@ -272,7 +855,7 @@ async fn process_thread(
                 message
             );
 
-            return Ok(());
+            return Ok(Some(ThreadProcessOutcome::BadStatus));
         }
         ThreadLoadResult::ThreadWasNotModifiedSinceLastCheck => {
             info!(
@@ -280,7 +863,8 @@ async fn process_thread(
                 thread_descriptor
             );
 
-            return Ok(())
+            forget_thread_not_found(thread_descriptor).await;
+            return Ok(Some(ThreadProcessOutcome::NotModified));
         }
         ThreadLoadResult::FailedToReadChanThread(body_text_part) => {
             error!(
@@ -304,22 +888,16 @@ async fn process_thread(
         }
     };
 
-    if chan_thread.is_not_active() {
-        info!(
-            "process_thread({}) marking thread as dead it's either archived or closed \
-            (archived: {}, closed: {})",
-            thread_descriptor,
-            chan_thread.archived,
-            chan_thread.closed,
-        );
+    let should_process_posts = on_thread_loaded(thread_descriptor, &chan_thread, database).await?;
+    let mut outcome = ThreadProcessOutcome::Success;
 
-        // Do not delete the cached posts here, we still want to process them.
-        // Only mark the threads as dead
-        post_repository::mark_thread_as_dead(database, thread_descriptor, false).await?;
+    if chan_thread.is_not_active() {
+        outcome = ThreadProcessOutcome::Dead;
+        follow_successor_thread(thread_descriptor, http_client, database, site_repository).await;
+    }
 
-        // Fall through. We still want to send the last batch of messages if there are new replies
-        // to watched posts. We won't be processing this thread on the next iteration, though,
-        // because it will be filtered out during the database query.
+    if !should_process_posts {
+        return Ok(Some(outcome));
     }
 
     info!(
@@ -336,6 +914,264 @@ async fn process_thread(
         database
     ).await?;
 
+    // Store these as soon as the posts were processed so that failing to store the title doesn't
+    // make us load an unchanged thread again on the next check.
+    store_thread_cache_headers(thread_descriptor, &last_modified, &etag, database).await?;
+
+    // Partial loads may not include the subject, in this case the previously stored one is kept.
+    if chan_thread.subject.is_some() {
+        thread_repository::store_thread_title(
+            chan_thread.subject.as_ref().unwrap(),
+            thread_descriptor,
+            database
+        ).await?;
+    }
+
+    return Ok(Some(outcome));
+}
+
+/// Called every time [thread_descriptor] 404s. Marks the thread as dead once it 404'd
+/// THREAD_NOT_FOUND_THRESHOLD times in a row. Returns whether the thread was marked as dead.
+pub async fn on_thread_not_found(
+    thread_descriptor: &ThreadDescriptor,
+    database: &Arc<Database>
+) -> anyhow::Result<bool> {
+    let not_found_count = {
+        let mut counters = THREAD_NOT_FOUND_COUNTERS.lock().await;
+        let counter = counters.entry(thread_descriptor.clone()).or_insert(0);
+        *counter += 1;
+        *counter
+    };
+
+    if not_found_count < THREAD_NOT_FOUND_THRESHOLD {
+        info!(
+            "on_thread_not_found({}) thread 404'd {} time(s) in a row, will check again next time",
+            thread_descriptor,
+            not_found_count
+        );
+
+        return Ok(false);
+    }
+
+    error!(
+        "on_thread_not_found({}) marking thread as dead because it 404'd {} times in a row",
+        thread_descriptor,
+        not_found_count
+    );
+
+    forget_thread_not_found(thread_descriptor).await;
+    post_repository::mark_thread_as_dead(database, thread_descriptor, true).await?;
+
+    return Ok(true);
+}
+
+/// Called every time [chan_thread] was loaded successfully. Closed threads stop being watched right
+/// away while archived threads are stopped being watched after their posts are scanned one last
+/// time. Returns whether the posts of [chan_thread] should be processed.
+pub async fn on_thread_loaded(
+    thread_descriptor: &ThreadDescriptor,
+    chan_thread: &ChanThread,
+    database: &Arc<Database>
+) -> anyhow::Result<bool> {
+    forget_thread_not_found(thread_descriptor).await;
+
+    return match chan_thread.activity_state() {
+        ThreadActivityState::Active => Ok(true),
+        ThreadActivityState::Archived => {
+            info!(
+                "on_thread_loaded({}) marking thread as dead because it's archived",
+                thread_descriptor
+            );
+
+            // Do not delete the cached posts here, we still want to process them. We won't be
+            // processing this thread on the next iteration, though, because it will be filtered
+            // out during the database query.
+            post_repository::mark_thread_as_dead(database, thread_descriptor, false).await?;
+            Ok(true)
+        }
+        ThreadActivityState::Closed => {
+            info!(
+                "on_thread_loaded({}) marking thread as dead because it's closed",
+                thread_descriptor
+            );
+
+            post_repository::mark_thread_as_dead(database, thread_descriptor, true).await?;
+            Ok(false)
+        }
+    };
+}
+
+async fn forget_thread_not_found(thread_descriptor: &ThreadDescriptor) {
+    THREAD_NOT_FOUND_COUNTERS.lock().await.remove(thread_descriptor);
+}
+
+/// Moves the watches of a thread that just died to its successor thread when
+/// FOLLOW_SUCCESSOR_THREADS is enabled. Failing to do so must not fail the thread processing.
+async fn follow_successor_thread(
+    thread_descriptor: &ThreadDescriptor,
+    http_client: &Arc<reqwest::Client>,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>
+) {
+    if !follow_successor_threads() {
+        return;
+    }
+
+    let site_permit = site_repository.acquire_site_permit(thread_descriptor.site_descriptor()).await;
+
+    let catalog_load_result = site_repository.load_catalog(
+        http_client,
+        &thread_descriptor.catalog_descriptor
+    ).await;
+
+    drop(site_permit);
+
+    let catalog_threads = match catalog_load_result {
+        Ok(CatalogLoadResult::Success(catalog_threads)) => catalog_threads,
+        Ok(CatalogLoadResult::SiteNotSupported) => return,
+        Ok(CatalogLoadResult::BadStatusCode(status_code)) => {
+            error!(
+                "follow_successor_thread({}) failed to load catalog, bad status code {}",
+                thread_descriptor,
+                status_code
+            );
+
+            return;
+        }
+        Err(error) => {
+            error!(
+                "follow_successor_thread({}) failed to load catalog, error: {}",
+                thread_descriptor,
+                error
+            );
+
+            return;
+        }
+    };
+
+    let result = migrate_watches_to_successor_thread(
+        thread_descriptor,
+        &catalog_threads,
+        database,
+        site_repository
+    ).await;
+
+    if result.is_err() {
+        error!(
+            "follow_successor_thread({}) failed to migrate watches, error: {}",
+            thread_descriptor,
+            result.err().unwrap()
+        );
+    }
+}
+
+/// Looks up the successor of the dead [thread_descriptor] among [catalog_threads] using the stored
+/// thread title and makes its watchers watch the successor's OP. Returns the successor thread.
+pub async fn migrate_watches_to_successor_thread(
+    thread_descriptor: &ThreadDescriptor,
+    catalog_threads: &Vec<CatalogThread>,
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>
+) -> anyhow::Result<Option<ThreadDescriptor>> {
+    let imageboard = site_repository.by_site_descriptor(thread_descriptor.site_descriptor());
+    if imageboard.is_none() {
+        return Ok(None);
+    }
+
+    let imageboard = imageboard.unwrap();
+
+    let thread_title = thread_repository::get_thread_title(thread_descriptor, database).await?;
+    if thread_title.is_none() {
+        info!(
+            "migrate_watches_to_successor_thread({}) thread has no title, can't find successor",
+            thread_descriptor
+        );
+
+        return Ok(None);
+    }
+
+    let thread_title = thread_title.unwrap();
+
+    let successor_thread_descriptor = imageboard.find_successor_thread(
+        thread_descriptor,
+        &thread_title,
+        catalog_threads
+    );
+
+    if successor_thread_descriptor.is_none() {
+        info!(
+            "migrate_watches_to_successor_thread({}) no successor thread found for '{}'",
+            thread_descriptor,
+            thread_title
+        );
+
+        return Ok(None);
+    }
+
+    let successor_thread_descriptor = successor_thread_descriptor.unwrap();
+    let successor_original_post = PostDescriptor::from_thread_descriptor(
+        successor_thread_descriptor.clone(),
+        successor_thread_descriptor.thread_no,
+        0
+    );
+
+    post_repository::migrate_thread_watches(
+        database,
+        thread_descriptor,
+        &successor_original_post
+    ).await?;
+
+    info!(
+        "migrate_watches_to_successor_thread({}) successor thread: {}",
+        thread_descriptor,
+        successor_thread_descriptor
+    );
+
+    return Ok(Some(successor_thread_descriptor));
+}
+
+/// Marks the thread as dead when its last_modified is older than [max_thread_age_days]. Threads
+/// that were never loaded (no last_modified yet) are never considered stale. Returns true when the
+/// thread was marked as dead.
+pub async fn mark_thread_as_dead_if_stale(
+    thread_descriptor: &ThreadDescriptor,
+    max_thread_age_days: u64,
+    database: &Arc<Database>
+) -> anyhow::Result<bool> {
+    if max_thread_age_days == 0 {
+        return Ok(false);
+    }
+
+    let last_modified = thread_repository::get_last_modified(thread_descriptor, database).await?;
+    if last_modified.is_none() {
+        return Ok(false);
+    }
+
+    let last_modified = last_modified.unwrap().with_timezone(&Utc);
+    let max_thread_age = chrono::Duration::days(max_thread_age_days.min(i32::MAX as u64) as i64);
+
+    if last_modified >= chrono::offset::Utc::now() - max_thread_age {
+        return Ok(false);
+    }
+
+    info!(
+        "process_thread({}) marking thread as dead because it had no new posts since {} \
+        (max_thread_age_days: {})",
+        thread_descriptor,
+        last_modified,
+        max_thread_age_days
+    );
+
+    post_repository::mark_thread_as_dead(database, thread_descriptor, true).await?;
+    return Ok(true);
+}
+
+pub async fn store_thread_cache_headers(
+    thread_descriptor: &ThreadDescriptor,
+    last_modified: &Option<DateTime<FixedOffset>>,
+    etag: &Option<String>,
+    database: &Arc<Database>
+) -> anyhow::Result<()> {
     if last_modified.is_some() {
         let last_modified = last_modified.unwrap();
 
@@ -352,10 +1188,26 @@ async fn process_thread(
         ).await?;
     }
 
+    if etag.is_some() {
+        let etag = etag.as_ref().unwrap();
+
+        info!(
+            "process_thread({}) updating last_etag: {}",
+            thread_descriptor,
+            etag
+        );
+
+        thread_repository::store_last_etag(
+            etag,
+            thread_descriptor,
+            database
+        ).await?;
+    }
+
     return Ok(());
 }
 
-async fn process_posts(
+pub async fn process_posts(
     site_repository: &Arc<SiteRepository>,
     last_processed_post: &Option<PostDescriptor>,
     thread_descriptor: &ThreadDescriptor,
@@ -380,7 +1232,7 @@ async fn process_posts(
     let mut found_post_replies_set =
         HashSet::<FoundPostReply>::with_capacity(chan_thread.posts.len());
     let mut new_posts_count = 0;
-    let post_quote_regex = imageboard.post_quote_regex();
+    let post_quote_regexes = imageboard.post_quote_regexes();
 
     find_post_replies(
         thread_descriptor,
@@ -388,11 +1240,26 @@ async fn process_posts(
         last_processed_post,
         &mut found_post_replies_set,
         &mut new_posts_count,
-        post_quote_regex
+        post_quote_regexes
     );
 
     info!("process_posts({}) new_posts_count: {}", thread_descriptor, new_posts_count);
 
+    let deleted_replies_count = delete_unsent_replies_with_missing_origin(
+        thread_descriptor,
+        last_processed_post,
+        chan_thread,
+        database
+    ).await?;
+
+    if deleted_replies_count > 0 {
+        info!(
+            "process_posts({}) deleted {} unsent replies whose origin post no longer exists",
+            thread_descriptor,
+            deleted_replies_count
+        );
+    }
+
     let last_post = chan_thread.posts.last();
     if last_post.is_none() {
         return Ok(());
@@ -424,9 +1291,12 @@ async fn process_posts(
 
     info!("process_posts({}) found {} quotes", thread_descriptor, found_post_replies_set.len());
 
+    let post_comments = post_comments_by_descriptor(thread_descriptor, chan_thread);
+
     find_and_store_new_post_replies(
         thread_descriptor,
         &mut found_post_replies_set,
+        &post_comments,
         database,
     ).await?;
 
@@ -437,6 +1307,7 @@ async fn process_posts(
 pub async fn find_and_store_new_post_replies(
     thread_descriptor: &ThreadDescriptor,
     found_post_replies_set: &mut HashSet<FoundPostReply>,
+    post_comments: &HashMap<PostDescriptor, &str>,
     database: &Arc<Database>,
 ) -> anyhow::Result<()> {
     let found_post_replies = found_post_replies_set.iter().collect::<Vec<&FoundPostReply>>();
@@ -456,6 +1327,22 @@ pub async fn find_and_store_new_post_replies(
         &post_descriptor_db_ids_to_vec_of_unique_keys(&post_descriptor_db_ids)
     ).await?;
 
+    // Quotes may point to posts that were cached for some other reason (e.g. they are the origins
+    // of older replies) but are not watched, those must not end up in the database.
+    let watched_post_descriptor_db_ids = post_replies.iter()
+        .map(|post_reply| post_reply.owner_post_descriptor_id)
+        .collect::<HashSet<i64>>();
+
+    let post_descriptor_db_ids = post_descriptor_db_ids.into_iter()
+        .filter(|(post_descriptor_db_id, _)| watched_post_descriptor_db_ids.contains(post_descriptor_db_id))
+        .collect::<HashMap<i64, Vec<&FoundPostReply>>>();
+
+    let mut watched_origins_by_account = HashMap::<i64, HashSet<PostDescriptor>>::new();
+    if suppress_self_replies() && post_replies.len() > 0 {
+        watched_origins_by_account = find_watched_origins_by_account(&post_descriptor_db_ids, database)
+            .await?;
+    }
+
     if post_replies.len() > 0 {
         info!(
             "process_posts({}) storing {} post replies into the database",
@@ -463,7 +1350,13 @@ pub async fn find_and_store_new_post_replies(
             post_replies.len()
         );
 
-        post_reply_repository::store(&post_replies, &post_descriptor_db_ids, database)
+        post_reply_repository::store(
+            &post_replies,
+            &post_descriptor_db_ids,
+            &watched_origins_by_account,
+            post_comments,
+            database
+        )
             .await
             .context(format!("Failed to store post {} replies", post_replies.len()))?;
     }
@@ -471,14 +1364,84 @@ pub async fn find_and_store_new_post_replies(
     return Ok(());
 }
 
+/// Returns the origins of [post_descriptor_db_ids] watched by each account. A watched origin is
+/// considered to be the account's own post (see [SUPPRESS_SELF_REPLIES]). Only the origins that
+/// already have a db id can be watched so the rest are not looked up.
+async fn find_watched_origins_by_account(
+    post_descriptor_db_ids: &HashMap<i64, Vec<&FoundPostReply>>,
+    database: &Arc<Database>
+) -> anyhow::Result<HashMap<i64, HashSet<PostDescriptor>>> {
+    let origins = post_descriptor_db_ids.values()
+        .flat_map(|found_post_replies| found_post_replies.iter().map(|fpr| fpr.origin.clone()))
+        .collect::<HashSet<PostDescriptor>>()
+        .into_iter()
+        .collect::<Vec<PostDescriptor>>();
+
+    let mut origins_by_db_id = HashMap::<i64, PostDescriptor>::with_capacity(origins.len());
+    for origin in origins {
+        let origin_db_id = post_descriptor_id_repository::get_post_descriptor_db_id(&origin).await;
+        if origin_db_id.is_some() {
+            origins_by_db_id.insert(origin_db_id.unwrap(), origin);
+        }
+    }
+
+    if origins_by_db_id.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let watching_accounts = post_repository::find_watching_accounts(
+        &origins_by_db_id.keys().cloned().collect::<Vec<i64>>(),
+        database
+    ).await?;
+
+    let mut watched_origins_by_account = HashMap::<i64, HashSet<PostDescriptor>>::new();
+
+    for (origin_db_id, account_ids) in watching_accounts {
+        let origin = origins_by_db_id.get(&origin_db_id);
+        if origin.is_none() {
+            continue;
+        }
+
+        let origin = origin.unwrap();
+
+        for account_id in account_ids {
+            watched_origins_by_account.entry(account_id)
+                .or_insert_with(|| HashSet::new())
+                .insert(origin.clone());
+        }
+    }
+
+    return Ok(watched_origins_by_account);
+}
+
+fn post_comments_by_descriptor<'a>(
+    thread_descriptor: &ThreadDescriptor,
+    chan_thread: &'a ChanThread
+) -> HashMap<PostDescriptor, &'a str> {
+    return chan_thread.posts.iter()
+        .filter(|post| post.comment_unparsed.is_some())
+        .map(|post| {
+            let post_descriptor = PostDescriptor::from_thread_descriptor(
+                thread_descriptor.clone(),
+                post.post_no,
+                post.post_sub_no.unwrap_or(0)
+            );
+
+            return (post_descriptor, post.comment_unparsed.as_ref().unwrap().as_str());
+        })
+        .collect::<HashMap<PostDescriptor, &'a str>>();
+}
+
 fn find_post_replies(
     thread_descriptor: &ThreadDescriptor,
     chan_thread: &ChanThread,
     last_processed_post: &Option<PostDescriptor>,
     found_post_replies_set: &mut HashSet<FoundPostReply>,
     new_posts_count: &mut i32,
-    post_quote_regex: &Regex
+    post_quote_regexes: &[Regex]
 ) {
+    let max_quote_post_no = max_plausible_quote_post_no(chan_thread);
+
     for post in &chan_thread.posts {
         let origin = PostDescriptor::from_thread_descriptor(
             thread_descriptor.clone(),
@@ -505,7 +1468,16 @@ fn find_post_replies(
             continue;
         }
 
-        let captures_iter = post_quote_regex.captures_iter(post_comment);
+        // Markup may change over time so try the regexes in order and use the first one that
+        // matches anything in this comment.
+        let post_quote_regex = post_quote_regexes.iter()
+            .find(|post_quote_regex| post_quote_regex.is_match(post_comment));
+
+        if post_quote_regex.is_none() {
+            continue;
+        }
+
+        let captures_iter = post_quote_regex.unwrap().captures_iter(post_comment);
         for captures in captures_iter {
             let quote_post_no_str = captures
                 .get(1)
@@ -516,15 +1488,24 @@ fn find_post_replies(
                 continue;
             }
 
+            // Malformed html may contain garbage that looks like a quote, so skip quotes that
+            // can't possibly point to a real post to avoid polluting the caches with them.
             let quote_post_no = u64::from_str(quote_post_no_str).unwrap_or(0);
-            if quote_post_no == 0 {
+            if quote_post_no == 0 || quote_post_no > max_quote_post_no {
                 continue;
             }
 
+            // Sites that have sub numbered posts supply the quote post_sub_no as the second capture
+            // group of the quote regex.
+            let quote_post_sub_no = captures
+                .get(2)
+                .map(|capture| u64::from_str(capture.as_str()).unwrap_or(0))
+                .unwrap_or(0);
+
             let replies_to = PostDescriptor::from_thread_descriptor(
                 thread_descriptor.clone(),
                 quote_post_no,
-                0
+                quote_post_sub_no
             );
 
             let post_reply = FoundPostReply {
@@ -537,6 +1518,58 @@ fn find_post_replies(
     }
 }
 
+/// Quotes can only point to posts that already exist so a quote post_no can't be much greater than
+/// the greatest post_no of the thread (it can still be a little greater when quoting a post from
+/// a newer thread of the same board).
+fn max_plausible_quote_post_no(chan_thread: &ChanThread) -> u64 {
+    let max_post_no = chan_thread.posts
+        .iter()
+        .map(|post| post.post_no)
+        .max()
+        .unwrap_or(0);
+
+    return max_post_no.saturating_add(MAX_QUOTE_POST_NO_DISTANCE);
+}
+
+async fn delete_unsent_replies_with_missing_origin(
+    thread_descriptor: &ThreadDescriptor,
+    last_processed_post: &Option<PostDescriptor>,
+    chan_thread: &ChanThread,
+    database: &Arc<Database>
+) -> anyhow::Result<u64> {
+    // When the thread was loaded partially we only know about the posts of the loaded tail
+    // (the first post is the OP) so we can only tell that a post was deleted if it is newer than
+    // the oldest post of the tail.
+    let min_post_no = if last_processed_post.is_some() {
+        let min_post_no = chan_thread.posts
+            .iter()
+            .skip(1)
+            .map(|post| post.post_no)
+            .min();
+
+        if min_post_no.is_none() {
+            return Ok(0);
+        }
+
+        min_post_no.unwrap()
+    } else {
+        0
+    };
+
+    // Sub numbered posts share the post_no so a post is only identified by both numbers
+    let existing_posts = chan_thread.posts
+        .iter()
+        .map(|post| (post.post_no, post.post_sub_no.unwrap_or(0)))
+        .collect::<Vec<(u64, u64)>>();
+
+    return post_reply_repository::delete_unsent_replies_with_missing_origin(
+        thread_descriptor,
+        min_post_no,
+        &existing_posts,
+        database
+    ).await;
+}
+
 fn post_descriptor_db_ids_to_vec_of_unique_keys(
     post_descriptor_db_ids: &HashMap<i64, Vec<&FoundPostReply>>
 ) -> Vec<i64> {
@@ -559,4 +1592,216 @@ fn post_descriptor_db_ids_to_vec_of_unique_keys(
     }
 
     return result_vec;
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_find_post_replies_skips_implausible_quote_post_numbers() {
+    use crate::model::data::chan::ChanPost;
+    use crate::model::imageboards::base_imageboard::Imageboard;
+    use crate::model::imageboards::chan4::Chan4;
+
+    let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 100);
+
+    let comment = "<a href=\"#p100\" class=\"quotelink\">&gt;&gt;100</a>\
+        <a href=\"#p0\" class=\"quotelink\">&gt;&gt;0</a>\
+        <a href=\"#p1\" class=\"quotelink\">&gt;&gt;99999999999999</a>\
+        <a href=\"#p1\" class=\"quotelink\">&gt;&gt;99999999999999999999</a>";
+
+    let chan_thread = ChanThread {
+        closed: false,
+        archived: false,
+        subject: None,
+        posts: vec![
+            ChanPost { post_no: 100, post_sub_no: None, comment_unparsed: None },
+            ChanPost { post_no: 101, post_sub_no: None, comment_unparsed: Some(comment.to_string()) },
+        ]
+    };
+
+    let mut found_post_replies_set = HashSet::<FoundPostReply>::new();
+    let mut new_posts_count = 0;
+
+    find_post_replies(
+        &thread_descriptor,
+        &chan_thread,
+        &None,
+        &mut found_post_replies_set,
+        &mut new_posts_count,
+        Chan4 {}.post_quote_regexes()
+    );
+
+    assert_eq!(2, new_posts_count);
+    assert_eq!(1, found_post_replies_set.len());
+
+    let found_post_reply = found_post_replies_set.iter().next().unwrap();
+    assert_eq!(101, found_post_reply.origin.post_no);
+    assert_eq!(100, found_post_reply.replies_to.post_no);
+}
+
+#[test]
+fn test_find_post_replies_tries_every_quote_regex() {
+    use crate::model::data::chan::ChanPost;
+    use crate::model::imageboards::base_imageboard::Imageboard;
+    use crate::model::imageboards::chan4::Chan4;
+
+    let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 100);
+
+    let chan_thread = ChanThread {
+        closed: false,
+        archived: false,
+        subject: None,
+        posts: vec![
+            ChanPost { post_no: 100, post_sub_no: None, comment_unparsed: None },
+            ChanPost {
+                post_no: 101,
+                post_sub_no: None,
+                comment_unparsed: Some("<a href=\"#p100\" class=\"quotelink\">&gt;&gt;100</a>".to_string())
+            },
+            ChanPost {
+                post_no: 102,
+                post_sub_no: None,
+                comment_unparsed: Some(
+                    "<a class=\"quotelink\" href=\"#p100\" data-function=\"highlight\">&gt;&gt;100</a>".to_string()
+                )
+            },
+        ]
+    };
+
+    let mut found_post_replies_set = HashSet::<FoundPostReply>::new();
+    let mut new_posts_count = 0;
+
+    find_post_replies(
+        &thread_descriptor,
+        &chan_thread,
+        &None,
+        &mut found_post_replies_set,
+        &mut new_posts_count,
+        Chan4 {}.post_quote_regexes()
+    );
+
+    assert_eq!(3, new_posts_count);
+    assert_eq!(2, found_post_replies_set.len());
+
+    for origin_post_no in [101, 102] {
+        let found_post_reply = FoundPostReply {
+            origin: PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), origin_post_no, 0),
+            replies_to: PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 100, 0)
+        };
+
+        assert!(found_post_replies_set.contains(&found_post_reply));
+    }
+}
+
+#[test]
+fn test_find_post_replies_uses_quote_post_sub_no() {
+    use crate::model::data::chan::ChanPost;
+
+    let thread_descriptor = ThreadDescriptor::new("test".to_string(), "g".to_string(), 100);
+    let post_quote_regex = Regex::new(r#"&gt;&gt;(\d+)(?:,(\d+))?"#).unwrap();
+
+    let chan_thread = ChanThread {
+        closed: false,
+        archived: false,
+        subject: None,
+        posts: vec![
+            ChanPost { post_no: 100, post_sub_no: None, comment_unparsed: None },
+            ChanPost { post_no: 100, post_sub_no: Some(1), comment_unparsed: None },
+            ChanPost { post_no: 101, post_sub_no: None, comment_unparsed: Some("&gt;&gt;100,1".to_string()) },
+            ChanPost { post_no: 102, post_sub_no: None, comment_unparsed: Some("&gt;&gt;100".to_string()) },
+        ]
+    };
+
+    let mut found_post_replies_set = HashSet::<FoundPostReply>::new();
+    let mut new_posts_count = 0;
+
+    find_post_replies(
+        &thread_descriptor,
+        &chan_thread,
+        &None,
+        &mut found_post_replies_set,
+        &mut new_posts_count,
+        &[post_quote_regex]
+    );
+
+    assert_eq!(4, new_posts_count);
+    assert_eq!(2, found_post_replies_set.len());
+
+    let sub_numbered_reply = FoundPostReply {
+        origin: PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 101, 0),
+        replies_to: PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 100, 1)
+    };
+    let reply = FoundPostReply {
+        origin: PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 102, 0),
+        replies_to: PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 100, 0)
+    };
+
+    assert!(found_post_replies_set.contains(&sub_numbered_reply));
+    assert!(found_post_replies_set.contains(&reply));
+}
+
+#[test]
+fn test_watcher_cycle_stats() {
+    let mut cycle_stats = WatcherCycleStats::default();
+    assert_eq!("<empty>", cycle_stats.to_string());
+
+    cycle_stats.record("4chan", ThreadProcessOutcome::Success);
+    cycle_stats.record("4chan", ThreadProcessOutcome::Success);
+    cycle_stats.record("4chan", ThreadProcessOutcome::NotModified);
+    cycle_stats.record("2ch", ThreadProcessOutcome::BadStatus);
+    cycle_stats.record("2ch", ThreadProcessOutcome::BadStatus);
+    cycle_stats.record("2ch", ThreadProcessOutcome::Inaccessible);
+    cycle_stats.record("2ch", ThreadProcessOutcome::Dead);
+
+    let chan4_tallies = SiteOutcomeTallies {
+        success: 2,
+        not_modified: 1,
+        ..SiteOutcomeTallies::default()
+    };
+    let dvach_tallies = SiteOutcomeTallies {
+        dead: 1,
+        bad_status: 2,
+        inaccessible: 1,
+        ..SiteOutcomeTallies::default()
+    };
+
+    assert_eq!(Some(&chan4_tallies), cycle_stats.tallies("4chan"));
+    assert_eq!(Some(&dvach_tallies), cycle_stats.tallies("2ch"));
+    assert_eq!(None, cycle_stats.tallies("lainchan"));
+
+    assert_eq!(
+        "2ch[ok: 0, not_modified: 0, dead: 1, bad_status: 2, inaccessible: 1], \
+        4chan[ok: 2, not_modified: 1, dead: 0, bad_status: 0, inaccessible: 0]",
+        cycle_stats.to_string()
+    );
+}
+
+#[test]
+fn test_watcher_chunk_size() {
+    // Computed from the amount of cpu cores
+    assert_eq!(64, watcher_chunk_size(4, None, None));
+    assert_eq!(16 * 16, watcher_chunk_size(16, None, None));
+
+    // Clamped to MIN_WATCHER_CHUNK_SIZE..=max_concurrency
+    assert_eq!(constants::MIN_WATCHER_CHUNK_SIZE, watcher_chunk_size(0, None, None));
+    assert_eq!(constants::DEFAULT_WATCHER_MAX_CONCURRENCY, watcher_chunk_size(128, None, None));
+    assert_eq!(100, watcher_chunk_size(128, None, Some(100)));
+    assert_eq!(8, watcher_chunk_size(4, None, Some(8)));
+
+    // The override wins over the computed value but still respects max_concurrency
+    assert_eq!(4, watcher_chunk_size(64, Some(4), None));
+    assert_eq!(512, watcher_chunk_size(1, Some(512), Some(1024)));
+    assert_eq!(constants::DEFAULT_WATCHER_MAX_CONCURRENCY, watcher_chunk_size(1, Some(512), None));
+    assert_eq!(32, watcher_chunk_size(1, Some(512), Some(32)));
+}
+
+#[test]
+fn test_parse_watcher_limit() {
+    assert_eq!(None, parse_watcher_limit("WATCHER_CHUNK_SIZE", None).unwrap());
+    assert_eq!(Some(64), parse_watcher_limit("WATCHER_CHUNK_SIZE", Some("64")).unwrap());
+    assert_eq!(Some(1024), parse_watcher_limit("WATCHER_CHUNK_SIZE", Some(" 1024 ")).unwrap());
+
+    assert!(parse_watcher_limit("WATCHER_CHUNK_SIZE", Some("0")).is_err());
+    assert!(parse_watcher_limit("WATCHER_CHUNK_SIZE", Some("1025")).is_err());
+    assert!(parse_watcher_limit("WATCHER_CHUNK_SIZE", Some("-1")).is_err());
+    assert!(parse_watcher_limit("WATCHER_CHUNK_SIZE", Some("abc")).is_err());
+    assert!(parse_watcher_limit("WATCHER_CHUNK_SIZE", Some("")).is_err());
+}
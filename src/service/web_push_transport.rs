@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use jwt_simple::algorithms::{ECDSAP256KeyPairLike, ECDSAP256PublicKeyLike, ES256KeyPair};
+use jwt_simple::claims::Claims;
+use jwt_simple::reexports::coarsetime::Duration;
+use jwt_simple::reexports::ct_codecs::{Base64UrlSafeNoPadding, Decoder, Encoder};
+use reqwest::StatusCode;
+use url::Url;
+
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository;
+use crate::service::fcm_sender::FcmSendAttemptResult;
+use crate::service::fcm_transport::{FcmMessagePriority, FcmTransport};
+
+/// How long the push service keeps a message for a browser that is currently offline.
+const WEB_PUSH_TTL_SECONDS: u64 = 24 * 60 * 60;
+
+/// Push services reject VAPID tokens that are valid for longer than 24 hours.
+const VAPID_TOKEN_VALID_FOR_HOURS: u64 = 12;
+
+/// Identifies this server to the push services. The private key is the raw P-256 scalar encoded
+/// with base64url, the format most VAPID key generators output.
+pub struct VapidKeys {
+    key_pair: ES256KeyPair,
+    public_key: String,
+    subject: String
+}
+
+impl VapidKeys {
+    pub fn from_base64(private_key: &str, subject: &str) -> anyhow::Result<VapidKeys> {
+        if !subject.starts_with("mailto:") && !subject.starts_with("https:") {
+            return Err(anyhow!("Bad VAPID subject \'{}\' must be a mailto: or an https: url", subject));
+        }
+
+        let private_key = decode_base64_url(private_key)
+            .context("Failed to decode VAPID private key")?;
+        let key_pair = ES256KeyPair::from_bytes(&private_key)
+            .context("Bad VAPID private key")?;
+        let public_key = Base64UrlSafeNoPadding::encode_to_string(
+            key_pair.public_key().public_key().to_bytes_uncompressed()
+        )?;
+
+        let vapid_keys = VapidKeys {
+            key_pair,
+            public_key,
+            subject: String::from(subject)
+        };
+
+        return Ok(vapid_keys);
+    }
+
+    /// The applicationServerKey the web clients must subscribe with.
+    pub fn public_key(&self) -> &str {
+        return &self.public_key;
+    }
+}
+
+/// Delivers the messages to browsers via the Web Push protocol. The payload is the same JSON object
+/// that is sent as FCM message data, encrypted with the keys of the browser's subscription.
+pub struct WebPushTransport {
+    vapid_keys: VapidKeys,
+    database: Arc<Database>,
+    http_client: Arc<reqwest::Client>
+}
+
+impl WebPushTransport {
+    pub fn new(
+        vapid_keys: VapidKeys,
+        database: &Arc<Database>,
+        http_client: &Arc<reqwest::Client>
+    ) -> WebPushTransport {
+        return WebPushTransport {
+            vapid_keys,
+            database: database.clone(),
+            http_client: http_client.clone()
+        };
+    }
+}
+
+#[async_trait]
+impl FcmTransport for WebPushTransport {
+    async fn send(
+        &self,
+        token: &str,
+        data: &HashMap<&str, String>,
+        priority: FcmMessagePriority
+    ) -> anyhow::Result<FcmSendAttemptResult> {
+        let web_push_subscription = account_repository::get_web_push_subscription(token, &self.database)
+            .await
+            .context("Failed to get web push subscription")?;
+
+        if web_push_subscription.is_none() {
+            return Ok(FcmSendAttemptResult::PermanentError(String::from("NoWebPushSubscription")));
+        }
+
+        let web_push_subscription = web_push_subscription.unwrap();
+
+        let p256dh = decode_base64_url(&web_push_subscription.p256dh)
+            .context("Failed to decode p256dh")?;
+        let auth = decode_base64_url(&web_push_subscription.auth)
+            .context("Failed to decode auth")?;
+
+        let payload = serde_json::to_vec(data)
+            .context("Failed to serialize message data")?;
+        let encrypted_payload = ece::encrypt(&p256dh, &auth, &payload)
+            .context("Failed to encrypt message data")?;
+
+        let authorization = vapid_authorization(&self.vapid_keys, &web_push_subscription.endpoint)?;
+
+        let urgency = match priority {
+            FcmMessagePriority::High => "high",
+            FcmMessagePriority::Normal => "normal"
+        };
+
+        let result = self.http_client.post(&web_push_subscription.endpoint)
+            .header("TTL", WEB_PUSH_TTL_SECONDS.to_string())
+            .header("Urgency", urgency)
+            .header("Content-Encoding", "aes128gcm")
+            .header("Content-Type", "application/octet-stream")
+            .header("Authorization", authorization)
+            .body(encrypted_payload)
+            .send()
+            .await;
+
+        return Ok(classify_web_push_send_result(result));
+    }
+}
+
+fn vapid_authorization(vapid_keys: &VapidKeys, endpoint: &str) -> anyhow::Result<String> {
+    let endpoint_url = Url::parse(endpoint)
+        .with_context(|| { return format!("Bad endpoint \'{}\'", endpoint); })?;
+
+    let claims = Claims::create(Duration::from_hours(VAPID_TOKEN_VALID_FOR_HOURS))
+        .with_audience(endpoint_url.origin().ascii_serialization())
+        .with_subject(&vapid_keys.subject);
+
+    let token = vapid_keys.key_pair.sign(claims)
+        .context("Failed to sign VAPID token")?;
+
+    return Ok(format!("vapid t={}, k={}", token, vapid_keys.public_key));
+}
+
+fn classify_web_push_send_result(result: reqwest::Result<reqwest::Response>) -> FcmSendAttemptResult {
+    if result.is_err() {
+        let error = result.err().unwrap();
+        return FcmSendAttemptResult::TransientError(format!("{:?}", error));
+    }
+
+    let status = result.unwrap().status();
+    if status.is_success() {
+        return FcmSendAttemptResult::Sent;
+    }
+
+    if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        return FcmSendAttemptResult::TransientError(status.to_string());
+    }
+
+    // 404 and 410 mean that the subscription has expired or the user unsubscribed
+    return FcmSendAttemptResult::PermanentError(status.to_string());
+}
+
+/// Browsers encode the keys without padding but some client libraries add it anyway.
+fn decode_base64_url(value: &str) -> anyhow::Result<Vec<u8>> {
+    return Base64UrlSafeNoPadding::decode_to_vec(value.trim_end_matches('='), None)
+        .map_err(|error| anyhow!("Failed to decode base64url value: {:?}", error));
+}
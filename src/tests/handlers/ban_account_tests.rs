@@ -0,0 +1,266 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::handlers::shared::{EmptyResponse, ErrorCode};
+    use crate::model::data::chan::{CatalogDescriptor, CatalogThread, PostDescriptor, ThreadDescriptor};
+    use crate::model::repository::account_repository::{AccountId, ApplicationType};
+    use crate::model::repository::catalog_watch_repository;
+    use crate::model::repository::catalog_watch_repository::StartWatchingCatalogResult;
+    use crate::service::{catalog_watcher, thread_watcher};
+    use crate::service::thread_watcher::FoundPostReply;
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared, post_reply_repository_shared, watch_post_repository_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    const WATCHED_POST_URL: &'static str = "https://boards.4channel.org/vg/thread/426895061#p426901491";
+    const OTHER_POST_URL: &'static str = "https://boards.4channel.org/vg/thread/426895061#p426901490";
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_ban_account_with_incorrect_master_password),
+            test_case!(should_not_ban_account_if_account_does_not_exist),
+            test_case!(should_not_ban_account_for_bad_days),
+            test_case!(banned_account_should_not_watch_posts_nor_receive_notifications_until_unbanned),
+            test_case!(banned_account_should_not_be_recreated_after_deletion),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_ban_account_with_incorrect_master_password() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let result = account_repository_shared::ban_account::<EmptyResponse>(
+            "incorrect master password",
+            user_id1,
+            30
+        ).await;
+
+        assert!(result.is_err());
+        assert_eq!("Bad response status: 403", result.err().unwrap().to_string());
+    }
+
+    async fn should_not_ban_account_if_account_does_not_exist() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = account_repository_shared::ban_account::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            30
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+        assert_eq!(Some(ErrorCode::AccountNotFound), server_response.error_code);
+    }
+
+    async fn should_not_ban_account_for_bad_days() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        for days in [-1, 10 * 365 + 1] {
+            let server_response = account_repository_shared::ban_account::<EmptyResponse>(
+                TEST_MASTER_PASSWORD,
+                user_id1,
+                days
+            ).await.unwrap();
+
+            assert!(server_response.data.is_none());
+            assert_eq!("days must be in range 0..=3650", server_response.error.unwrap());
+            assert_eq!(Some(ErrorCode::InvalidParameter), server_response.error_code);
+        }
+    }
+
+    async fn banned_account_should_not_watch_posts_nor_receive_notifications_until_unbanned() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        store_test_replies().await;
+        store_test_catalog_notifications().await;
+
+        assert_eq!(3, unsent_replies_count().await);
+        assert_eq!(1, unsent_catalog_notifications_count().await);
+
+        let server_response = account_repository_shared::ban_account::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            30
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        assert_eq!(0, unsent_replies_count().await);
+        assert_eq!(0, unsent_catalog_notifications_count().await);
+
+        let account = account_repository_shared::get_account_from_database(
+            user_id1,
+            database_shared::database()
+        ).await.unwrap().unwrap();
+
+        assert!(account.is_banned());
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            OTHER_POST_URL,
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account is banned", server_response.error.unwrap());
+        assert_eq!(Some(ErrorCode::AccountBanned), server_response.error_code);
+
+        let server_response = account_repository_shared::ban_account::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            0
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        assert_eq!(3, unsent_replies_count().await);
+        assert_eq!(1, unsent_catalog_notifications_count().await);
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            OTHER_POST_URL,
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+    }
+
+    async fn banned_account_should_not_be_recreated_after_deletion() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        let server_response = account_repository_shared::ban_account::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            30
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+
+        let server_response = account_repository_shared::delete_account::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+
+        let server_response = account_repository_shared::create_account::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            30
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account is banned", server_response.error.unwrap());
+        assert_eq!(Some(ErrorCode::AccountBanned), server_response.error_code);
+    }
+
+    async fn store_test_replies() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        account_repository_shared::update_token_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+            &application_type
+        ).await;
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            WATCHED_POST_URL,
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "vg".to_string(), 426895061);
+        let watched_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 426901491, 0);
+
+        let mut found_post_replies_set = (426901492..426901495)
+            .map(|post_no| {
+                return FoundPostReply {
+                    origin: PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), post_no, 0),
+                    replies_to: watched_post.clone()
+                };
+            })
+            .collect::<HashSet<FoundPostReply>>();
+
+        thread_watcher::find_and_store_new_post_replies(
+            &thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database_shared::database(),
+        ).await.unwrap();
+    }
+
+    async fn store_test_catalog_notifications() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let account_id = AccountId::from_user_id(&account_repository_shared::TEST_GOOD_USER_ID1).unwrap();
+        let catalog_descriptor = CatalogDescriptor::new("4chan".to_string(), "vg".to_string());
+        let database = database_shared::database();
+
+        let result = catalog_watch_repository::start_watching_catalog(
+            database,
+            &account_id,
+            &application_type,
+            &catalog_descriptor,
+            "general"
+        ).await.unwrap();
+        assert_eq!(StartWatchingCatalogResult::Ok, result);
+
+        // The first pass only remembers the newest thread, the second one reports the new thread
+        let initial_catalog = vec![catalog_thread(100, "/vg/ general")];
+        catalog_watcher::process_catalog(&catalog_descriptor, &initial_catalog, database)
+            .await
+            .unwrap();
+
+        let updated_catalog = vec![
+            catalog_thread(100, "/vg/ general"),
+            catalog_thread(101, "/agdg/ - Amateur Game Dev General"),
+        ];
+
+        let new_threads = catalog_watcher::process_catalog(&catalog_descriptor, &updated_catalog, database)
+            .await
+            .unwrap();
+        assert_eq!(1, new_threads);
+    }
+
+    fn catalog_thread(thread_no: u64, subject: &str) -> CatalogThread {
+        return CatalogThread {
+            thread_no,
+            subject: Some(subject.to_string()),
+            comment: None
+        };
+    }
+
+    async fn unsent_catalog_notifications_count() -> usize {
+        return catalog_watch_repository::get_unsent_catalog_notifications(database_shared::database())
+            .await
+            .unwrap()
+            .values()
+            .map(|unsent_notifications| unsent_notifications.len())
+            .sum();
+    }
+
+    async fn unsent_replies_count() -> usize {
+        return post_reply_repository_shared::peek_unsent_replies_count(database_shared::database()).await;
+    }
+}
@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::bulk_extend_expiry::BulkExtendExpiryResponse;
+    use crate::handlers::shared::EmptyResponse;
+    use crate::helpers::string_helpers::FormatToken;
+    use crate::model::repository::account_repository::AccountId;
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_extend_expiry_with_incorrect_master_password),
+            test_case!(should_not_extend_expiry_with_bad_extra_days),
+            test_case!(should_extend_expiry_of_all_accounts),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_extend_expiry_with_incorrect_master_password() {
+        let server_response = account_repository_shared::bulk_extend_expiry::<EmptyResponse>(
+            "incorrect_password",
+            &vec!["1".repeat(35)],
+            10
+        ).await;
+
+        assert!(server_response.is_err());
+        assert_eq!("Bad response status: 403", server_response.err().unwrap().to_string());
+    }
+
+    async fn should_not_extend_expiry_with_bad_extra_days() {
+        let user_id = "1".repeat(35);
+        account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, &user_id).await;
+
+        for extra_days in [0, 366] {
+            let server_response = account_repository_shared::bulk_extend_expiry::<EmptyResponse>(
+                TEST_MASTER_PASSWORD,
+                &vec![user_id.clone()],
+                extra_days
+            ).await.unwrap();
+
+            assert!(server_response.data.is_none());
+            assert_eq!("extra_days must be in range 0..365", server_response.error.unwrap());
+        }
+    }
+
+    async fn should_extend_expiry_of_all_accounts() {
+        let database = database_shared::database();
+        let extra_days = 10;
+
+        let user_ids = (1..=3)
+            .map(|index| index.to_string().repeat(35))
+            .collect::<Vec<String>>();
+
+        let mut valid_until_before = Vec::new();
+
+        for user_id in &user_ids {
+            account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id).await;
+
+            let account = account_repository_shared::get_account_from_database(user_id, database)
+                .await
+                .unwrap()
+                .unwrap();
+
+            valid_until_before.push(account.valid_until.unwrap());
+        }
+
+        let not_existing_user_id = "4".repeat(35);
+
+        let mut account_ids = user_ids.clone();
+        account_ids.push(not_existing_user_id.clone());
+
+        let server_response = account_repository_shared::bulk_extend_expiry::<BulkExtendExpiryResponse>(
+            TEST_MASTER_PASSWORD,
+            &account_ids,
+            extra_days
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        let bulk_extend_expiry_response = server_response.data.unwrap();
+
+        assert_eq!(3, bulk_extend_expiry_response.extended.len());
+        assert_eq!(vec![formatted_account_id(&not_existing_user_id)], bulk_extend_expiry_response.not_found);
+
+        for (index, user_id) in user_ids.iter().enumerate() {
+            let from_database = account_repository_shared::get_account_from_database(user_id, database)
+                .await
+                .unwrap()
+                .unwrap();
+
+            let from_cache = account_repository_shared::get_account_from_cache(user_id)
+                .await
+                .unwrap()
+                .unwrap();
+
+            let valid_until = from_database.valid_until.unwrap();
+
+            assert_eq!(valid_until_before[index] + chrono::Duration::days(extra_days as i64), valid_until);
+            assert_eq!(Some(valid_until), from_cache.valid_until);
+
+            let extended_account = &bulk_extend_expiry_response.extended[index];
+            assert_eq!(formatted_account_id(user_id), extended_account.account_id);
+            assert_eq!(valid_until.timestamp(), extended_account.valid_until.unwrap().timestamp());
+        }
+    }
+
+    fn formatted_account_id(user_id: &str) -> String {
+        return AccountId::test_unsafe(user_id).unwrap().format_token().to_string();
+    }
+}
@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::delete_account::DeleteAccountResponse;
+    use crate::handlers::shared::EmptyResponse;
+    use crate::model::repository::account_repository;
+    use crate::model::repository::account_repository::{AccountId, ApplicationType};
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared, watch_post_repository_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_delete_account_if_it_does_not_exist),
+            test_case!(should_delete_account_and_its_post_watches),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_delete_account_if_it_does_not_exist() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = account_repository_shared::delete_account::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert!(server_response.error.is_some());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+    }
+
+    async fn should_delete_account_and_its_post_watches() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let account_id1 = AccountId::test_unsafe(user_id1).unwrap();
+        let database = database_shared::database();
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        account_repository_shared::update_token_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+            &application_type
+        ).await;
+
+        let post_urls = vec![
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            "https://boards.4channel.org/vg/thread/426895061#p426901492"
+        ];
+
+        for post_url in post_urls {
+            let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+                user_id1,
+                post_url,
+                &application_type
+            ).await.unwrap();
+
+            assert!(server_response.data.is_some());
+            assert!(server_response.error.is_none());
+        }
+
+        let server_response = account_repository_shared::delete_account::<DeleteAccountResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await.unwrap();
+
+        assert!(server_response.data.is_some());
+        assert!(server_response.error.is_none());
+        assert_eq!(2, server_response.data.unwrap().deleted_post_watches);
+
+        let account = account_repository::get_account(&account_id1, database).await.unwrap();
+        assert!(account.is_none());
+
+        let account_from_cache = account_repository_shared::get_account_from_cache(user_id1)
+            .await
+            .unwrap();
+        assert!(account_from_cache.is_none());
+
+        let test_post_watches = watch_post_repository_shared::get_post_watches_from_database(
+            &account_id1,
+            database
+        )
+            .await
+            .unwrap();
+
+        assert!(test_post_watches.is_empty());
+
+        let server_response = account_repository_shared::delete_account::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+    }
+}
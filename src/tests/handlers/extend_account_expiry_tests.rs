@@ -0,0 +1,212 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::extend_account_expiry::ExtendAccountExpiryResponse;
+    use crate::handlers::extend_account_expiry::ExtendAccountExpiryRequest;
+    use crate::handlers::shared::{EmptyResponse, ErrorCode, ServerResponse, TOO_MANY_REQUESTS_MESSAGE};
+    use crate::model::repository::invites_repository;
+    use crate::model::repository::invites_repository::NEW_ACCOUNT_TRIAL_PERIOD_DAYS;
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared, server_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    // Same as the max_failures of the /extend_account_expiry lockout config
+    const MAX_FAILED_REDEMPTIONS: usize = 3;
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_extend_expiry_if_account_does_not_exist),
+            test_case!(should_not_extend_expiry_if_invite_does_not_exist),
+            test_case!(should_extend_expiry_and_accept_invite),
+            test_case!(should_extend_expiry_only_once_when_invite_is_redeemed_concurrently),
+            test_case!(should_lock_ip_out_after_rapid_failed_invite_redemptions),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_extend_expiry_if_account_does_not_exist() {
+        let database = database_shared::database();
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let invite = invites_repository::generate_invites(database, 1).await.unwrap().remove(0);
+
+        let server_response = account_repository_shared::extend_account_expiry::<EmptyResponse>(
+            user_id1,
+            &invite
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+        assert!(!is_invite_accepted(&invite).await);
+    }
+
+    async fn should_not_extend_expiry_if_invite_does_not_exist() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id1).await;
+
+        let server_response = account_repository_shared::extend_account_expiry::<EmptyResponse>(
+            user_id1,
+            "bad_invite"
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Invite does not exist or already expired", server_response.error.unwrap());
+    }
+
+    async fn should_extend_expiry_and_accept_invite() {
+        let database = database_shared::database();
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id1).await;
+
+        let valid_until_before = account_repository_shared::get_account_from_database(user_id1, database)
+            .await
+            .unwrap()
+            .unwrap()
+            .valid_until
+            .unwrap();
+
+        let invite = invites_repository::generate_invites(database, 1).await.unwrap().remove(0);
+
+        let server_response = account_repository_shared::extend_account_expiry::<ExtendAccountExpiryResponse>(
+            user_id1,
+            &invite
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        assert!(server_response.data.is_some());
+
+        let valid_until_after = account_repository_shared::get_account_from_database(user_id1, database)
+            .await
+            .unwrap()
+            .unwrap()
+            .valid_until
+            .unwrap();
+
+        // The trial period is stacked on top of the remaining time
+        let expected_valid_until = valid_until_before +
+            chrono::Duration::days(NEW_ACCOUNT_TRIAL_PERIOD_DAYS as i64);
+        let delta = (valid_until_after - expected_valid_until).num_seconds().abs();
+        assert!(delta <= 1);
+
+        assert!(is_invite_accepted(&invite).await);
+
+        // The same invite can't be used twice
+        let server_response = account_repository_shared::extend_account_expiry::<EmptyResponse>(
+            user_id1,
+            &invite
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Invite does not exist or already expired", server_response.error.unwrap());
+    }
+
+    async fn should_extend_expiry_only_once_when_invite_is_redeemed_concurrently() {
+        let database = database_shared::database();
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id1).await;
+
+        let valid_until_before = account_repository_shared::get_account_from_database(user_id1, database)
+            .await
+            .unwrap()
+            .unwrap()
+            .valid_until
+            .unwrap();
+
+        let invite = invites_repository::generate_invites(database, 1).await.unwrap().remove(0);
+
+        let (server_response1, server_response2) = tokio::join!(
+            account_repository_shared::extend_account_expiry::<ExtendAccountExpiryResponse>(user_id1, &invite),
+            account_repository_shared::extend_account_expiry::<ExtendAccountExpiryResponse>(user_id1, &invite)
+        );
+
+        let server_response1 = server_response1.unwrap();
+        let server_response2 = server_response2.unwrap();
+
+        let succeeded = [&server_response1, &server_response2]
+            .iter()
+            .filter(|server_response| server_response.data.is_some())
+            .count();
+
+        assert_eq!(1, succeeded);
+
+        let from_database = account_repository_shared::get_account_from_database(user_id1, database)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let expected_valid_until = valid_until_before +
+            chrono::Duration::days(NEW_ACCOUNT_TRIAL_PERIOD_DAYS as i64);
+        let delta = (from_database.valid_until.unwrap() - expected_valid_until).num_seconds().abs();
+        assert!(delta <= 1);
+
+        let from_cache = account_repository_shared::get_account_from_cache(user_id1)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(from_database.valid_until, from_cache.valid_until);
+    }
+
+    async fn should_lock_ip_out_after_rapid_failed_invite_redemptions() {
+        let database = database_shared::database();
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id1).await;
+
+        let (server_address, server_handle) = server_shared::start_throttled_server().await;
+
+        let http_client = reqwest::Client::new();
+        let extend_account_expiry = |invite: String| {
+            let request = ExtendAccountExpiryRequest {
+                user_id: user_id1.to_string(),
+                invite
+            };
+
+            return http_client.post(format!("http://{}/extend_account_expiry", server_address))
+                .body(serde_json::to_string(&request).unwrap())
+                .send();
+        };
+
+        for index in 0..MAX_FAILED_REDEMPTIONS {
+            let text = extend_account_expiry(format!("bad_invite_{}", index))
+                .await
+                .unwrap()
+                .text()
+                .await
+                .unwrap();
+
+            let server_response: ServerResponse<EmptyResponse> = serde_json::from_str(&text).unwrap();
+            assert_eq!("Invite does not exist or already expired", server_response.error.unwrap());
+        }
+
+        // Even a valid invite can't be redeemed until the lockout ends
+        let invite = invites_repository::generate_invites(database, 1).await.unwrap().remove(0);
+
+        let text = extend_account_expiry(invite.clone())
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        let server_response: ServerResponse<EmptyResponse> = serde_json::from_str(&text).unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!(TOO_MANY_REQUESTS_MESSAGE, server_response.error.unwrap());
+        assert_eq!(Some(ErrorCode::TooManyRequests), server_response.error_code);
+        assert!(!is_invite_accepted(&invite).await);
+
+        server_handle.abort();
+    }
+
+    async fn is_invite_accepted(invite: &String) -> bool {
+        let connection = database_shared::database().connection().await.unwrap();
+
+        let row = connection.query_one(
+            "SELECT accepted_on IS NOT NULL FROM invites WHERE invite_id = $1",
+            &[invite]
+        ).await.unwrap();
+
+        return row.get(0);
+    }
+}
@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::handlers::get_delivery_stats::GetDeliveryStatsResponse;
+    use crate::handlers::shared::EmptyResponse;
+    use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
+    use crate::model::repository::account_repository::ApplicationType;
+    use crate::model::repository::post_reply_repository;
+    use crate::service::thread_watcher;
+    use crate::service::thread_watcher::FoundPostReply;
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared, post_reply_repository_shared, watch_post_repository_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_get_delivery_stats_if_account_does_not_exist),
+            test_case!(should_count_delivered_pending_and_failed_replies),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_get_delivery_stats_if_account_does_not_exist() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = account_repository_shared::get_delivery_stats::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert!(server_response.error.is_some());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+    }
+
+    async fn should_count_delivered_pending_and_failed_replies() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let database = database_shared::database();
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        account_repository_shared::update_firebase_token::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+            &application_type
+        ).await.unwrap();
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "vg".to_string(), 426895061);
+        let watched_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 426901491, 0);
+
+        let mut found_post_replies_set = (426901492..426901495)
+            .map(|post_no| {
+                return FoundPostReply {
+                    origin: PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), post_no, 0),
+                    replies_to: watched_post.clone()
+                };
+            })
+            .collect::<HashSet<FoundPostReply>>();
+
+        thread_watcher::find_and_store_new_post_replies(
+            &thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database,
+        ).await.unwrap();
+
+        let mut post_reply_ids = post_reply_repository_shared::peek_unsent_replies(database)
+            .await
+            .values()
+            .flat_map(|unsent_replies| unsent_replies.iter())
+            .map(|unsent_reply| unsent_reply.post_reply_id)
+            .collect::<Vec<i64>>();
+
+        post_reply_ids.sort();
+        assert_eq!(3, post_reply_ids.len());
+
+        let delivered_reply_id = post_reply_ids[0];
+        let failed_reply_id = post_reply_ids[1];
+
+        post_reply_repository::mark_post_replies_as_notified(&vec![delivered_reply_id], database)
+            .await
+            .unwrap();
+
+        post_reply_repository_shared::exhaust_delivery_attempts(failed_reply_id, database).await;
+
+        let server_response = account_repository_shared::get_delivery_stats::<GetDeliveryStatsResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        let delivery_stats = server_response.data.unwrap();
+
+        assert_eq!(3, delivery_stats.total);
+        assert_eq!(1, delivery_stats.delivered);
+        assert_eq!(1, delivery_stats.pending);
+        assert_eq!(1, delivery_stats.failed);
+    }
+}
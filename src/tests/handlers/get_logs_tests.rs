@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use crate::model::repository::logs_repository;
+    use crate::model::repository::logs_repository::NewLogLine;
+    use crate::test_case;
+    use crate::tests::shared::{database_shared, http_client_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_return_logs_as_json_array_by_default),
+            test_case!(should_stream_one_json_object_per_line_in_ndjson_format),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn store_test_logs(count: usize) {
+        let database = database_shared::database();
+
+        let new_log_lines = (0..count)
+            .map(|index| {
+                return NewLogLine {
+                    log_time: Utc::now(),
+                    log_level: "INFO".to_string(),
+                    target: "get_logs_tests".to_string(),
+                    message: format!("Test log line {}\nwith a line break", index),
+                };
+            })
+            .collect::<Vec<NewLogLine>>();
+
+        logs_repository::store_logs(database, &new_log_lines).await.unwrap();
+    }
+
+    async fn should_return_logs_as_json_array_by_default() {
+        store_test_logs(20).await;
+
+        let text = http_client_shared::get_request_text_with_master_password(
+            "get_logs?num=100&last_id=11",
+            TEST_MASTER_PASSWORD
+        ).await.unwrap();
+
+        let json = serde_json::from_str::<serde_json::Value>(&text).unwrap();
+        let log_lines = json["data"]["log_lines"].as_array().unwrap();
+
+        assert_eq!(10, log_lines.len());
+    }
+
+    async fn should_stream_one_json_object_per_line_in_ndjson_format() {
+        store_test_logs(1200).await;
+
+        let text = http_client_shared::get_request_text_with_master_password(
+            "get_logs?num=2000&last_id=1101&format=ndjson",
+            TEST_MASTER_PASSWORD
+        ).await.unwrap();
+
+        assert!(text.ends_with('\n'));
+
+        let ids = text.lines()
+            .map(|line| {
+                let json = serde_json::from_str::<serde_json::Value>(line).unwrap();
+                assert!(json.is_object());
+                assert_eq!("get_logs_tests", json["target"].as_str().unwrap());
+
+                return json["id"].as_i64().unwrap();
+            })
+            .collect::<Vec<i64>>();
+
+        // Spans multiple batches and comes back newest first
+        assert_eq!((1..=1100).rev().collect::<Vec<i64>>(), ids);
+    }
+}
@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::handlers::get_pending_replies::GetPendingRepliesResponse;
+    use crate::handlers::shared::EmptyResponse;
+    use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
+    use crate::model::repository::account_repository::ApplicationType;
+    use crate::model::repository::post_reply_repository;
+    use crate::service::thread_watcher;
+    use crate::service::thread_watcher::FoundPostReply;
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared, watch_post_repository_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_get_pending_replies_if_account_does_not_exist),
+            test_case!(should_return_only_undelivered_replies),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_get_pending_replies_if_account_does_not_exist() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = watch_post_repository_shared::get_pending_replies::<GetPendingRepliesResponse>(
+            user_id1,
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert!(server_response.error.is_some());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+    }
+
+    async fn should_return_only_undelivered_replies() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let database = database_shared::database();
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        account_repository_shared::update_firebase_token::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+            &application_type
+        ).await.unwrap();
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "vg".to_string(), 426895061);
+        let watched_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 426901491, 0);
+        let reply1 = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 426901492, 0);
+        let reply2 = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 426901493, 0);
+
+        let mut found_post_replies_set = HashSet::from(
+            [
+                FoundPostReply { origin: reply1.clone(), replies_to: watched_post.clone() },
+                FoundPostReply { origin: reply2.clone(), replies_to: watched_post.clone() }
+            ]
+        );
+
+        thread_watcher::find_and_store_new_post_replies(
+            &thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database,
+        ).await.unwrap();
+
+        let server_response = watch_post_repository_shared::get_pending_replies::<GetPendingRepliesResponse>(
+            user_id1,
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        let pending_replies = server_response.data.unwrap().replies;
+        assert_eq!(2, pending_replies.len());
+
+        let delivered_reply = pending_replies.iter()
+            .find(|pending_reply| pending_reply.new_reply_url.ends_with("#p426901492"))
+            .unwrap();
+
+        assert_eq!(
+            "https://boards.4chan.org/vg/thread/426895061#p426901491",
+            delivered_reply.watched_post_url
+        );
+
+        post_reply_repository::mark_post_replies_as_notified(
+            &vec![delivered_reply.reply_id as i64],
+            database
+        ).await.unwrap();
+
+        let server_response = watch_post_repository_shared::get_pending_replies::<GetPendingRepliesResponse>(
+            user_id1,
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        let pending_replies = server_response.data.unwrap().replies;
+        assert_eq!(1, pending_replies.len());
+        assert_eq!(
+            "https://boards.4chan.org/vg/thread/426895061#p426901493",
+            pending_replies.first().unwrap().new_reply_url
+        );
+    }
+}
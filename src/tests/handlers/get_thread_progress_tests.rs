@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::get_thread_progress::GetThreadProgressResponse;
+    use crate::handlers::shared::EmptyResponse;
+    use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
+    use crate::model::repository::thread_repository;
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared, watch_post_repository_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_get_thread_progress_if_account_does_not_exist),
+            test_case!(should_return_none_for_unseen_thread),
+            test_case!(should_return_last_processed_post),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_get_thread_progress_if_account_does_not_exist() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = watch_post_repository_shared::get_thread_progress::<EmptyResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061#p426901491"
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+    }
+
+    async fn should_return_none_for_unseen_thread() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id1).await;
+
+        let server_response = watch_post_repository_shared::get_thread_progress::<GetThreadProgressResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061#p426901491"
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        let thread_progress = server_response.data.unwrap();
+
+        assert!(thread_progress.last_processed_post_no.is_none());
+        assert!(thread_progress.last_processed_post_sub_no.is_none());
+    }
+
+    async fn should_return_last_processed_post() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let database = database_shared::database();
+        account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id1).await;
+
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "vg".to_string(), 426895061);
+        let last_processed_post = PostDescriptor::from_thread_descriptor(thread_descriptor, 426901500, 0);
+
+        thread_repository::store_last_processed_post(&last_processed_post, database)
+            .await
+            .unwrap();
+
+        // The post part of the url doesn't matter, only the thread is used
+        let server_response = watch_post_repository_shared::get_thread_progress::<GetThreadProgressResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061#p426901491"
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        let thread_progress = server_response.data.unwrap();
+
+        assert_eq!(Some(426901500), thread_progress.last_processed_post_no);
+        assert_eq!(Some(0), thread_progress.last_processed_post_sub_no);
+    }
+}
@@ -0,0 +1,237 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use http_body_util::Full;
+    use hyper::{Request, Response};
+    use hyper::body::{Bytes, Incoming};
+    use hyper::server::conn::http1;
+    use hyper::service::service_fn;
+    use lazy_static::lazy_static;
+    use regex::Regex;
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    use crate::handlers::get_thread_snapshot::GetThreadSnapshotRequest;
+    use crate::handlers::shared::{ErrorCode, ServerResponse};
+    use crate::model::data::chan::{CatalogDescriptor, PostDescriptor, SiteDescriptor, ThreadDescriptor};
+    use crate::model::imageboards::base_imageboard::Imageboard;
+    use crate::model::imageboards::parser::chan4_post_parser::Chan4PostParser;
+    use crate::model::imageboards::parser::post_parser::PostParser;
+    use crate::model::repository::site_repository::{ImageboardSynced, SiteRepository};
+    use crate::model::repository::thread_repository;
+    use crate::service::thread_watcher::{ThreadSnapshot, ThreadWatcher};
+    use crate::test_case;
+    use crate::tests::shared::{database_shared, http_client_shared, site_repository_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    lazy_static! {
+        static ref POST_REPLY_QUOTE_REGEXES: Vec<Regex> = vec![
+            Regex::new(r#"class="quotelink">&gt;&gt;(\d+)</a>"#).unwrap()
+        ];
+        static ref POST_PARSER: Box<dyn PostParser + Sync> = Box::new(Chan4PostParser {});
+        static ref THREAD_JSON: String = format!(
+            r#"
+            {{
+                "posts": [
+                    {{ "no": 1, "resto": 0, "sub": "Thread subject", "com": "OP comment", "closed": 0 }},
+                    {{ "no": 2, "resto": 1, "com": "<a href=\"#p1\" class=\"quotelink\">&gt;&gt;1</a>" }},
+                    {{ "no": 3, "resto": 1, "com": "{}" }}
+                ]
+            }}
+            "#,
+            "a".repeat(1000)
+        );
+    }
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_get_thread_snapshot_with_incorrect_master_password),
+            test_case!(should_not_get_thread_snapshot_of_unsupported_site),
+            test_case!(should_return_parsed_posts_and_stored_thread_state),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_get_thread_snapshot_with_incorrect_master_password() {
+        let result = get_thread_snapshot(
+            "incorrect master password",
+            "https://boards.4channel.org/vg/thread/426895061"
+        ).await;
+
+        assert!(result.is_err());
+        assert_eq!("Bad response status: 403", result.err().unwrap().to_string());
+    }
+
+    async fn should_not_get_thread_snapshot_of_unsupported_site() {
+        let server_response = get_thread_snapshot(
+            TEST_MASTER_PASSWORD,
+            "https://example.com/vg/thread/426895061"
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!(Some(ErrorCode::SiteNotSupported), server_response.error_code);
+    }
+
+    async fn should_return_parsed_posts_and_stored_thread_state() {
+        let database = database_shared::database();
+        let (server_address, server_handle) = start_mock_server().await;
+
+        let imageboard: ImageboardSynced = Arc::new(MockImageboard { server_address });
+        let site_repository = Arc::new(SiteRepository::with_imageboards(
+            site_repository_shared::site_repository().http_client(),
+            vec![imageboard],
+            &HashMap::new(),
+            Duration::from_secs(60)
+        ));
+
+        let thread_descriptor = ThreadDescriptor::new("mock".to_string(), "g".to_string(), 1);
+        let last_processed_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 2, 0);
+        thread_repository::store_last_processed_post(&last_processed_post, database).await.unwrap();
+
+        let last_modified = chrono::DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+        thread_repository::store_last_modified(&last_modified, &thread_descriptor, database).await.unwrap();
+
+        let snapshot = ThreadWatcher::load_thread_snapshot(&thread_descriptor, database, &site_repository)
+            .await
+            .unwrap();
+        server_handle.abort();
+
+        assert_eq!("Success", snapshot.load_result);
+        assert!(!snapshot.closed);
+        assert!(!snapshot.archived);
+        assert_eq!(Some("Thread subject".to_string()), snapshot.subject);
+        assert_eq!(Some(last_processed_post), snapshot.last_processed_post);
+        assert_eq!(Some(last_modified.timestamp()), snapshot.last_modified.map(|date_time| date_time.timestamp()));
+
+        // The whole thread is loaded even though some posts were already processed
+        let post_nos = snapshot.posts.iter().map(|post| post.post_no).collect::<Vec<u64>>();
+        assert_eq!(vec![1, 2, 3], post_nos);
+
+        assert_eq!(Some("OP comment".to_string()), snapshot.posts[0].comment);
+        assert_eq!(256, snapshot.posts[2].comment.as_ref().unwrap().chars().count());
+    }
+
+    async fn get_thread_snapshot(
+        master_password: &str,
+        post_url: &str
+    ) -> anyhow::Result<ServerResponse<ThreadSnapshot>> {
+        let request = GetThreadSnapshotRequest {
+            post_url: post_url.to_string()
+        };
+
+        let body = serde_json::to_string(&request).unwrap();
+
+        return http_client_shared::post_request::<ServerResponse<ThreadSnapshot>>(
+            "debug/get_thread_snapshot",
+            &body,
+            master_password
+        ).await;
+    }
+
+    async fn start_mock_server() -> (SocketAddr, JoinHandle<()>) {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await.unwrap();
+        let server_address = listener.local_addr().unwrap();
+
+        let join_handle = tokio::task::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+
+                tokio::task::spawn(async move {
+                    let _ = http1::Builder::new()
+                        .serve_connection(stream, service_fn(mock_handler))
+                        .await;
+                });
+            }
+        });
+
+        return (server_address, join_handle);
+    }
+
+    async fn mock_handler(request: Request<Incoming>) -> Result<Response<Full<Bytes>>, Infallible> {
+        let response = if request.uri().path() == "/g/thread/1.json" {
+            Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .body(Full::new(Bytes::from(THREAD_JSON.as_str())))
+                .unwrap()
+        } else {
+            Response::builder()
+                .status(404)
+                .body(Full::new(Bytes::new()))
+                .unwrap()
+        };
+
+        return Ok(response);
+    }
+
+    struct MockImageboard {
+        server_address: SocketAddr
+    }
+
+    #[async_trait]
+    impl Imageboard for MockImageboard {
+        fn name(&self) -> &'static str {
+            return "mock";
+        }
+
+        fn matches(&self, site_descriptor: &SiteDescriptor) -> bool {
+            return site_descriptor.site_name_str() == "mock";
+        }
+
+        fn url_matches(&self, _url: &str) -> bool {
+            return false;
+        }
+
+        fn post_url_to_post_descriptor(&self, _post_url: &str) -> Option<PostDescriptor> {
+            return None;
+        }
+
+        fn post_descriptor_to_url(&self, _post_descriptor: &PostDescriptor) -> Option<String> {
+            return None;
+        }
+
+        fn post_quote_regexes(&self) -> &'static [Regex] {
+            return &POST_REPLY_QUOTE_REGEXES;
+        }
+
+        fn post_parser(&self) -> &'static Box<dyn PostParser + Sync> {
+            return &POST_PARSER;
+        }
+
+        fn thread_json_endpoint(
+            &self,
+            thread_descriptor: &ThreadDescriptor,
+            _last_processed_post: &Option<PostDescriptor>
+        ) -> Option<String> {
+            let endpoint = format!(
+                "http://{}/{}/thread/{}.json",
+                self.server_address,
+                thread_descriptor.board_code(),
+                thread_descriptor.thread_no
+            );
+
+            return Some(endpoint);
+        }
+
+        fn supports_partial_load_head_request(&self) -> bool {
+            return true;
+        }
+
+        fn catalog_json_endpoint(&self, _catalog_descriptor: &CatalogDescriptor) -> Option<String> {
+            return None;
+        }
+
+        fn boards_json_endpoint(&self) -> Option<String> {
+            return None;
+        }
+    }
+}
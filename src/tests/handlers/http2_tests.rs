@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    use tokio::net::TcpListener;
+
+    use crate::router;
+    use crate::router::{HttpProtocol, MasterPassword, TestContext};
+    use crate::test_case;
+    use crate::tests::shared::{database_shared, site_repository_shared};
+    use crate::tests::shared::server_shared;
+    use crate::tests::shared::server_shared::{TEST_HOST_ADDRESS, TEST_MASTER_PASSWORD};
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_serve_requests_over_http2),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_serve_requests_over_http2() {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await.unwrap();
+        let server_address = listener.local_addr().unwrap();
+
+        let server_handle = tokio::task::spawn(async move {
+            let (stream, sock_addr) = listener.accept().await.unwrap();
+            let test_context = TestContext { enable_throttler: false };
+
+            router::serve_connection(
+                Some(test_context),
+                HttpProtocol::Http2,
+                stream,
+                sock_addr,
+                Arc::new(MasterPassword::from_plaintext(TEST_MASTER_PASSWORD)),
+                Arc::new(TEST_HOST_ADDRESS.to_string()),
+                database_shared::database().clone(),
+                site_repository_shared::site_repository().clone(),
+                server_shared::test_server_fcm_sender(
+                    database_shared::database(),
+                    site_repository_shared::site_repository()
+                )
+            ).await.unwrap();
+        });
+
+        let http_client = reqwest::Client::builder()
+            .http2_prior_knowledge()
+            .build()
+            .unwrap();
+
+        let response = http_client.get(format!("http://{}/metrics", server_address))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(reqwest::Version::HTTP_2, response.version());
+        assert_eq!(200, response.status().as_u16());
+
+        let text = response.text().await.unwrap();
+        assert!(text.contains("kpns_http_requests_total"));
+
+        server_handle.abort();
+    }
+}
@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod tests {
+    use crate::constants;
+    use crate::handlers::list_accounts::ListAccountsResponse;
+    use crate::handlers::shared::EmptyResponse;
+    use crate::helpers::string_helpers::FormatToken;
+    use crate::model::repository::account_repository::{AccountId, ApplicationType};
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, watch_post_repository_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_list_accounts_with_incorrect_master_password),
+            test_case!(should_list_accounts_page_by_page),
+            test_case!(should_cap_page_size),
+            test_case!(should_list_tokens_and_watches_count),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn create_accounts(count: usize) -> Vec<String> {
+        let mut user_ids = Vec::<String>::with_capacity(count);
+
+        for index in 1..=count {
+            let user_id = index.to_string().repeat(35);
+            account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, &user_id).await;
+            user_ids.push(user_id);
+        }
+
+        return user_ids;
+    }
+
+    fn formatted_account_id(user_id: &str) -> String {
+        return AccountId::test_unsafe(user_id).unwrap().format_token().to_string();
+    }
+
+    async fn should_not_list_accounts_with_incorrect_master_password() {
+        let server_response = account_repository_shared::list_accounts::<EmptyResponse>(
+            "incorrect_password",
+            0,
+            10
+        ).await;
+
+        assert!(server_response.is_err());
+        assert_eq!("Bad response status: 403", server_response.err().unwrap().to_string());
+    }
+
+    async fn should_list_accounts_page_by_page() {
+        let user_ids = create_accounts(5).await;
+
+        let expected_pages = vec![
+            vec![&user_ids[0], &user_ids[1]],
+            vec![&user_ids[2], &user_ids[3]],
+            vec![&user_ids[4]],
+            vec![],
+        ];
+
+        for (page, expected_user_ids) in expected_pages.iter().enumerate() {
+            let server_response = account_repository_shared::list_accounts::<ListAccountsResponse>(
+                TEST_MASTER_PASSWORD,
+                page as u64,
+                2
+            ).await.unwrap();
+
+            assert!(server_response.error.is_none());
+            let list_accounts_response = server_response.data.unwrap();
+
+            assert_eq!(page as u64, list_accounts_response.page);
+            assert_eq!(2, list_accounts_response.page_size);
+
+            let account_ids = list_accounts_response.accounts.iter()
+                .map(|account| account.account_id.clone())
+                .collect::<Vec<String>>();
+
+            let expected_account_ids = expected_user_ids.iter()
+                .map(|user_id| formatted_account_id(user_id))
+                .collect::<Vec<String>>();
+
+            assert_eq!(expected_account_ids, account_ids);
+        }
+    }
+
+    async fn should_cap_page_size() {
+        create_accounts(3).await;
+
+        let server_response = account_repository_shared::list_accounts::<ListAccountsResponse>(
+            TEST_MASTER_PASSWORD,
+            0,
+            u64::MAX
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        let list_accounts_response = server_response.data.unwrap();
+
+        assert_eq!(constants::MAX_LIST_ACCOUNTS_PAGE_SIZE as u64, list_accounts_response.page_size);
+        assert_eq!(3, list_accounts_response.accounts.len());
+
+        let server_response = account_repository_shared::list_accounts::<ListAccountsResponse>(
+            TEST_MASTER_PASSWORD,
+            0,
+            0
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        let list_accounts_response = server_response.data.unwrap();
+
+        assert_eq!(1, list_accounts_response.page_size);
+        assert_eq!(1, list_accounts_response.accounts.len());
+    }
+
+    async fn should_list_tokens_and_watches_count() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_ids = create_accounts(2).await;
+
+        account_repository_shared::update_firebase_token::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            &user_ids[0],
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+            &application_type
+        ).await.unwrap();
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            &user_ids[0],
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+
+        let server_response = account_repository_shared::list_accounts::<ListAccountsResponse>(
+            TEST_MASTER_PASSWORD,
+            0,
+            10
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        let accounts = server_response.data.unwrap().accounts;
+        assert_eq!(2, accounts.len());
+
+        assert_eq!(formatted_account_id(&user_ids[0]), accounts[0].account_id);
+        assert!(accounts[0].valid_until.is_some());
+        assert_eq!(1, accounts[0].watches_count);
+        assert_eq!(1, accounts[0].tokens.len());
+        assert_eq!(application_type, accounts[0].tokens[0].application_type);
+        assert_eq!(1, accounts[0].tokens[0].count);
+
+        assert_eq!(formatted_account_id(&user_ids[1]), accounts[1].account_id);
+        assert_eq!(0, accounts[1].watches_count);
+        assert!(accounts[1].tokens.is_empty());
+    }
+}
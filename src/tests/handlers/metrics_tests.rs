@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod tests {
+    use crate::test_case;
+    use crate::tests::shared::http_client_shared;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_expose_all_metrics),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_expose_all_metrics() {
+        let expected_metric_names = vec![
+            "kpns_threads_processed_per_cycle",
+            "kpns_watcher_iteration_duration_milliseconds",
+            "kpns_watcher_iterations_total",
+            "kpns_fcm_messages_sent_total",
+            "kpns_fcm_messages_failed_total",
+            "kpns_unsent_replies_backlog",
+            "kpns_db_pool_connections_in_use",
+            "kpns_db_pool_connections",
+            "kpns_http_requests_total",
+            "kpns_http_request_errors_total",
+        ];
+
+        let metrics_text = http_client_shared::get_request_text("metrics").await.unwrap();
+
+        for expected_metric_name in expected_metric_names {
+            assert!(
+                metrics_text.contains(&format!("# TYPE {} ", expected_metric_name)),
+                "metric {} not found",
+                expected_metric_name
+            );
+        }
+    }
+}
@@ -1,4 +1,30 @@
+pub mod ban_account_tests;
+pub mod bulk_extend_expiry_tests;
 pub mod create_account_tests;
+pub mod delete_account_tests;
+pub mod extend_account_expiry_tests;
 pub mod get_account_info_tests;
+pub mod get_delivery_stats_tests;
+pub mod get_logs_tests;
+pub mod get_pending_replies_tests;
+pub mod get_thread_progress_tests;
+pub mod get_thread_snapshot_tests;
+pub mod http2_tests;
+pub mod list_accounts_tests;
+pub mod metrics_tests;
+pub mod mute_thread_tests;
+pub mod register_and_watch_tests;
+pub mod remove_firebase_token_tests;
+pub mod replay_replies_tests;
+pub mod request_body_limit_tests;
+pub mod request_id_tests;
+pub mod send_test_notification_tests;
+pub mod server_info_tests;
+pub mod update_account_expiry_date_tests;
 pub mod update_firebase_token_tests;
-pub mod watch_post_tests;
\ No newline at end of file
+pub mod update_quiet_hours_tests;
+pub mod update_web_push_subscription_tests;
+pub mod view_invite_tests;
+pub mod watch_post_tests;
+pub mod watch_posts_tests;
+pub mod whoami_tests;
\ No newline at end of file
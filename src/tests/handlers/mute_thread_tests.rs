@@ -0,0 +1,167 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::handlers::shared::{EmptyResponse, ErrorCode};
+    use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
+    use crate::model::repository::account_repository::ApplicationType;
+    use crate::service::thread_watcher;
+    use crate::service::thread_watcher::FoundPostReply;
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared, post_reply_repository_shared, watch_post_repository_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    const THREAD_URL: &'static str = "https://boards.4channel.org/vg/thread/426895061";
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_mute_thread_if_account_does_not_exist),
+            test_case!(should_not_mute_thread_for_bad_minutes),
+            test_case!(muting_should_suppress_delivery_and_unmuting_should_resume_it),
+            test_case!(delivery_should_resume_once_mute_expires),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_mute_thread_if_account_does_not_exist() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = watch_post_repository_shared::mute_thread::<EmptyResponse>(
+            user_id1,
+            THREAD_URL,
+            60
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+        assert_eq!(Some(ErrorCode::AccountNotFound), server_response.error_code);
+    }
+
+    async fn should_not_mute_thread_for_bad_minutes() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        for minutes in [-1, 7 * 24 * 60 + 1] {
+            let server_response = watch_post_repository_shared::mute_thread::<EmptyResponse>(
+                user_id1,
+                THREAD_URL,
+                minutes
+            ).await.unwrap();
+
+            assert!(server_response.data.is_none());
+            assert_eq!("minutes must be in range 0..=10080", server_response.error.unwrap());
+            assert_eq!(Some(ErrorCode::InvalidParameter), server_response.error_code);
+        }
+    }
+
+    async fn muting_should_suppress_delivery_and_unmuting_should_resume_it() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        store_test_replies().await;
+
+        assert_eq!(3, unsent_replies_count().await);
+
+        let server_response = watch_post_repository_shared::mute_thread::<EmptyResponse>(
+            user_id1,
+            THREAD_URL,
+            60
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        assert_eq!(0, unsent_replies_count().await);
+
+        // Muting again just prolongs the mute
+        let server_response = watch_post_repository_shared::mute_thread::<EmptyResponse>(
+            user_id1,
+            THREAD_URL,
+            120
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        assert_eq!(0, unsent_replies_count().await);
+
+        let server_response = watch_post_repository_shared::mute_thread::<EmptyResponse>(
+            user_id1,
+            THREAD_URL,
+            0
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        assert_eq!(3, unsent_replies_count().await);
+    }
+
+    async fn delivery_should_resume_once_mute_expires() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        store_test_replies().await;
+
+        let server_response = watch_post_repository_shared::mute_thread::<EmptyResponse>(
+            user_id1,
+            THREAD_URL,
+            60
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        assert_eq!(0, unsent_replies_count().await);
+
+        let connection = database_shared::database().connection().await.unwrap();
+        connection.execute("UPDATE thread_mutes SET muted_until = now() - interval '1 minute'", &[])
+            .await
+            .unwrap();
+
+        assert_eq!(3, unsent_replies_count().await);
+    }
+
+    async fn store_test_replies() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        account_repository_shared::update_token_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+            &application_type
+        ).await;
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "vg".to_string(), 426895061);
+        let watched_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 426901491, 0);
+
+        let mut found_post_replies_set = (426901492..426901495)
+            .map(|post_no| {
+                return FoundPostReply {
+                    origin: PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), post_no, 0),
+                    replies_to: watched_post.clone()
+                };
+            })
+            .collect::<HashSet<FoundPostReply>>();
+
+        thread_watcher::find_and_store_new_post_replies(
+            &thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database_shared::database(),
+        ).await.unwrap();
+    }
+
+    async fn unsent_replies_count() -> usize {
+        return post_reply_repository_shared::peek_unsent_replies_count(database_shared::database()).await;
+    }
+}
@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod tests {
+    use crate::constants;
+    use crate::handlers::shared::{EmptyResponse, ErrorCode};
+    use crate::model::repository::account_repository::{AccountId, ApplicationType};
+    use crate::model::repository::post_repository;
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared, watch_post_repository_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    const POST_URL1: &'static str = "https://boards.4channel.org/vg/thread/426895061#p426901491";
+    const POST_URL2: &'static str = "https://boards.4channel.org/vg/thread/426895061#p426901492";
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_register_if_account_does_not_exist),
+            test_case!(should_register_token_and_watch_posts_idempotently),
+            test_case!(should_store_nothing_if_one_of_post_urls_is_invalid),
+            test_case!(should_store_nothing_if_watch_limit_is_reached),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_register_if_account_does_not_exist() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = watch_post_repository_shared::register_and_watch::<EmptyResponse>(
+            user_id1,
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+            &[POST_URL1],
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+        assert_eq!(Some(ErrorCode::AccountNotFound), server_response.error_code);
+    }
+
+    async fn should_register_token_and_watch_posts_idempotently() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let account_id1 = AccountId::test_unsafe(user_id1).unwrap();
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        for _ in 0..2 {
+            let server_response = watch_post_repository_shared::register_and_watch::<EmptyResponse>(
+                user_id1,
+                &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+                &[POST_URL1, POST_URL2],
+                &application_type
+            ).await.unwrap();
+
+            assert!(server_response.data.is_some());
+            assert!(server_response.error.is_none());
+
+            let account = account_repository_shared::get_account_from_database(
+                user_id1,
+                database_shared::database()
+            ).await.unwrap().unwrap();
+
+            assert_eq!(1, account.tokens.len());
+            assert_eq!(account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1.as_str(), account.tokens[0].token);
+            assert!(account.is_valid(&application_type));
+
+            let test_post_watches = watch_post_repository_shared::get_post_watches_from_database(
+                &account_id1,
+                database_shared::database()
+            ).await.unwrap();
+
+            assert_eq!(2, test_post_watches.len());
+        }
+
+        // The cached account must have the token too
+        let account = account_repository_shared::get_account_from_cache(user_id1)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(account.is_valid(&application_type));
+    }
+
+    async fn should_store_nothing_if_one_of_post_urls_is_invalid() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let account_id1 = AccountId::test_unsafe(user_id1).unwrap();
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        let server_response = watch_post_repository_shared::register_and_watch::<EmptyResponse>(
+            user_id1,
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+            &[POST_URL1, "https://example.com/vg/thread/426895061#p426901492"],
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!(Some(ErrorCode::InvalidPostUrl), server_response.error_code);
+
+        assert_nothing_stored(user_id1, &account_id1).await;
+    }
+
+    async fn should_store_nothing_if_watch_limit_is_reached() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let account_id1 = AccountId::test_unsafe(user_id1).unwrap();
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        post_repository::set_max_watches_per_account(1);
+
+        let server_response = watch_post_repository_shared::register_and_watch::<EmptyResponse>(
+            user_id1,
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+            &[POST_URL1, POST_URL2],
+            &application_type
+        ).await.unwrap();
+
+        post_repository::set_max_watches_per_account(constants::DEFAULT_MAX_WATCHES_PER_ACCOUNT);
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Too many watched posts", server_response.error.unwrap());
+        assert_eq!(Some(ErrorCode::WatchLimitReached), server_response.error_code);
+
+        // The token was inserted before the watches so it must have been rolled back with them
+        assert_nothing_stored(user_id1, &account_id1).await;
+    }
+
+    async fn assert_nothing_stored(user_id: &String, account_id: &AccountId) {
+        let account = account_repository_shared::get_account_from_database(
+            user_id,
+            database_shared::database()
+        ).await.unwrap().unwrap();
+
+        assert!(account.tokens.is_empty());
+
+        let test_post_watches = watch_post_repository_shared::get_post_watches_from_database(
+            account_id,
+            database_shared::database()
+        ).await.unwrap();
+
+        assert!(test_post_watches.is_empty());
+    }
+}
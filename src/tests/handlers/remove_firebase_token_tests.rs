@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::shared::EmptyResponse;
+    use crate::model::repository::account_repository::{AccountId, ApplicationType};
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared, watch_post_repository_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_remove_firebase_token_if_account_does_not_exist),
+            test_case!(should_remove_all_tokens_and_keep_watches),
+            test_case!(should_remove_only_the_specified_token),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_remove_firebase_token_if_account_does_not_exist() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let application_type = ApplicationType::KurobaExLiteDebug;
+
+        let server_response = account_repository_shared::remove_firebase_token::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            None,
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+    }
+
+    async fn should_remove_all_tokens_and_keep_watches() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let other_application_type = ApplicationType::KurobaExLiteProduction;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let account_id1 = AccountId::test_unsafe(user_id1).unwrap();
+        let database = database_shared::database();
+
+        account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id1).await;
+
+        for (token, application_type) in [
+            ("good token 1", &application_type),
+            ("good token 2", &application_type),
+            ("good token 3", &other_application_type)
+        ] {
+            account_repository_shared::update_token_actual(
+                TEST_MASTER_PASSWORD,
+                user_id1,
+                &token.to_string(),
+                application_type
+            ).await;
+        }
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+
+        let server_response = account_repository_shared::remove_firebase_token::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            None,
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_some());
+        assert!(server_response.error.is_none());
+
+        let from_cache = account_repository_shared::get_account_from_cache(user_id1)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(!from_cache.is_valid(&application_type));
+        assert!(from_cache.is_valid(&other_application_type));
+
+        let from_database = account_repository_shared::get_account_from_database(user_id1, database)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(!from_database.is_valid(&application_type));
+        assert!(from_database.is_valid(&other_application_type));
+
+        let post_watches = watch_post_repository_shared::get_post_watches_from_database(&account_id1, database)
+            .await
+            .unwrap();
+
+        assert_eq!(1, post_watches.len());
+        assert_eq!(426901491, post_watches[0].post_descriptor.post_no);
+    }
+
+    async fn should_remove_only_the_specified_token() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let database = database_shared::database();
+
+        account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id1).await;
+
+        for token in ["good token 1", "good token 2"] {
+            account_repository_shared::update_token_actual(
+                TEST_MASTER_PASSWORD,
+                user_id1,
+                &token.to_string(),
+                &application_type
+            ).await;
+        }
+
+        let server_response = account_repository_shared::remove_firebase_token::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            Some("good token 1"),
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+
+        let from_cache = account_repository_shared::get_account_from_cache(user_id1)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let from_database = account_repository_shared::get_account_from_database(user_id1, database)
+            .await
+            .unwrap()
+            .unwrap();
+
+        for account in [from_cache, from_database] {
+            let tokens = account.get_account_tokens(&application_type);
+
+            assert_eq!(1, tokens.len());
+            assert_eq!("good token 2", tokens[0].token);
+            assert!(account.is_valid(&application_type));
+        }
+    }
+}
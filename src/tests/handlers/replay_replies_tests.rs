@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::handlers::replay_replies::ReplayRepliesResponse;
+    use crate::handlers::shared::{EmptyResponse, ErrorCode};
+    use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
+    use crate::model::repository::account_repository::ApplicationType;
+    use crate::model::repository::post_reply_repository;
+    use crate::service::thread_watcher;
+    use crate::service::thread_watcher::FoundPostReply;
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared, post_reply_repository_shared, watch_post_repository_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_replay_replies_with_incorrect_master_password),
+            test_case!(should_not_replay_replies_if_account_does_not_exist),
+            test_case!(should_make_delivered_and_failed_replies_eligible_again),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_replay_replies_with_incorrect_master_password() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let result = account_repository_shared::replay_replies::<EmptyResponse>(
+            "incorrect master password",
+            user_id1
+        ).await;
+
+        assert!(result.is_err());
+        assert_eq!("Bad response status: 403", result.err().unwrap().to_string());
+    }
+
+    async fn should_not_replay_replies_if_account_does_not_exist() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = account_repository_shared::replay_replies::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+        assert_eq!(Some(ErrorCode::AccountNotFound), server_response.error_code);
+    }
+
+    async fn should_make_delivered_and_failed_replies_eligible_again() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let database = database_shared::database();
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        account_repository_shared::update_token_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+            &application_type
+        ).await;
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "vg".to_string(), 426895061);
+        let watched_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 426901491, 0);
+
+        let mut found_post_replies_set = (426901492..426901495)
+            .map(|post_no| {
+                return FoundPostReply {
+                    origin: PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), post_no, 0),
+                    replies_to: watched_post.clone()
+                };
+            })
+            .collect::<HashSet<FoundPostReply>>();
+
+        thread_watcher::find_and_store_new_post_replies(
+            &thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database,
+        ).await.unwrap();
+
+        let mut post_reply_ids = unsent_reply_ids().await;
+        post_reply_ids.sort();
+        assert_eq!(3, post_reply_ids.len());
+
+        let delivered_reply_id = post_reply_ids[0];
+        let failed_reply_id = post_reply_ids[1];
+
+        post_reply_repository::mark_post_replies_as_notified(&vec![delivered_reply_id], database)
+            .await
+            .unwrap();
+
+        post_reply_repository_shared::exhaust_delivery_attempts(failed_reply_id, database).await;
+
+        let mut unsent_reply_ids_before_replay = unsent_reply_ids().await;
+        unsent_reply_ids_before_replay.sort();
+        assert_eq!(vec![post_reply_ids[2]], unsent_reply_ids_before_replay);
+
+        let server_response = account_repository_shared::replay_replies::<ReplayRepliesResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        assert_eq!(3, server_response.data.unwrap().replayed_count);
+
+        let mut unsent_reply_ids_after_replay = unsent_reply_ids().await;
+        unsent_reply_ids_after_replay.sort();
+        assert_eq!(post_reply_ids, unsent_reply_ids_after_replay);
+    }
+
+    async fn unsent_reply_ids() -> Vec<i64> {
+        return post_reply_repository_shared::peek_unsent_replies(database_shared::database())
+            .await
+            .values()
+            .flat_map(|unsent_replies| unsent_replies.iter())
+            .map(|unsent_reply| unsent_reply.post_reply_id)
+            .collect::<Vec<i64>>();
+    }
+}
@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod tests {
+    use crate::constants;
+    use crate::handlers::shared::{EmptyResponse, ServerResponse};
+    use crate::test_case;
+    use crate::tests::shared::http_client_shared;
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_reject_oversized_request_body),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_reject_oversized_request_body() {
+        let body = "a".repeat(constants::DEFAULT_MAX_REQUEST_BODY_SIZE + 1024);
+
+        let server_response = http_client_shared::post_request::<ServerResponse<EmptyResponse>>(
+            "watch_post",
+            &body,
+            TEST_MASTER_PASSWORD,
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert!(server_response.error.is_some());
+        assert_eq!(
+            format!("Request body is too large (max {} bytes)", constants::DEFAULT_MAX_REQUEST_BODY_SIZE),
+            server_response.error.unwrap()
+        );
+    }
+}
@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod tests {
+    use crate::test_case;
+    use crate::tests::shared::http_client_shared;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_return_unique_request_id_header),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_return_unique_request_id_header() {
+        let mut request_ids = Vec::<String>::new();
+
+        for _ in 0..2 {
+            let response = http_client_shared::get_request("server_info").await.unwrap();
+            assert_eq!(200, response.status().as_u16());
+
+            let request_id = response.headers()
+                .get("X-Request-Id")
+                .map(|header_value| header_value.to_str().unwrap().to_string());
+
+            assert!(request_id.is_some());
+            assert_eq!(36, request_id.as_ref().unwrap().len());
+
+            request_ids.push(request_id.unwrap());
+        }
+
+        assert_ne!(request_ids[0], request_ids[1]);
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::send_test_notification::SendTestNotificationResponse;
+    use crate::handlers::shared::{EmptyResponse, ErrorCode};
+    use crate::model::repository::account_repository::ApplicationType;
+    use crate::service::fcm_transport::FcmMessagePriority;
+    use crate::test_case;
+    use crate::tests::shared::account_repository_shared;
+    use crate::tests::shared::fcm_transport_shared::test_server_fcm_transport;
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_send_test_notification_if_account_does_not_exist),
+            test_case!(should_not_send_test_notification_if_account_has_no_token),
+            test_case!(should_send_exactly_one_test_notification),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_send_test_notification_if_account_does_not_exist() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = account_repository_shared::send_test_notification::<EmptyResponse>(
+            user_id1,
+            &ApplicationType::KurobaExLiteDebug
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+        assert_eq!(Some(ErrorCode::AccountNotFound), server_response.error_code);
+    }
+
+    async fn should_not_send_test_notification_if_account_has_no_token() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        let server_response = account_repository_shared::send_test_notification::<EmptyResponse>(
+            user_id1,
+            &ApplicationType::KurobaExLiteDebug
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account has no token", server_response.error.unwrap());
+        assert_eq!(Some(ErrorCode::AccountHasNoToken), server_response.error_code);
+    }
+
+    async fn should_send_exactly_one_test_notification() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let firebase_token = &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1;
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        account_repository_shared::update_token_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            firebase_token,
+            &application_type
+        ).await;
+
+        let fcm_transport = test_server_fcm_transport();
+        fcm_transport.clear();
+
+        let server_response = account_repository_shared::send_test_notification::<SendTestNotificationResponse>(
+            user_id1,
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        assert!(server_response.data.unwrap().accepted);
+
+        let sent_messages = fcm_transport.sent_messages();
+        assert_eq!(1, sent_messages.len());
+
+        let sent_message = &sent_messages[0];
+        assert_eq!(firebase_token.as_str(), sent_message.token.as_str());
+        assert_eq!(FcmMessagePriority::High, sent_message.priority);
+        assert_eq!(Some(&"test".to_string()), sent_message.data.get("type"));
+    }
+}
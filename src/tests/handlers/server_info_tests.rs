@@ -0,0 +1,31 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::server_info::ServerInfoResponse;
+    use crate::handlers::shared::ServerResponse;
+    use crate::test_case;
+    use crate::tests::shared::http_client_shared;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_return_server_info_with_supported_sites),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_return_server_info_with_supported_sites() {
+        let response_text = http_client_shared::get_request_text("server_info").await.unwrap();
+        let server_response = serde_json::from_str::<ServerResponse<ServerInfoResponse>>(&response_text)
+            .unwrap();
+
+        assert!(server_response.error.is_none());
+
+        let server_info = server_response.data.unwrap();
+        assert_eq!(env!("CARGO_PKG_VERSION"), server_info.version);
+        assert!(server_info.started_at.is_some());
+        assert!(server_info.supported_sites.contains(&"4chan".to_string()));
+        assert!(server_info.supported_sites.contains(&"2ch".to_string()));
+    }
+}
@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::shared::{EmptyResponse, ErrorCode};
+    use crate::handlers::update_account_expiry_date::UpdateAccountExpiryDateResponse;
+    use crate::helpers::string_helpers::FormatToken;
+    use crate::model::repository::account_repository::AccountId;
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_update_account_expiry_date_with_incorrect_master_password),
+            test_case!(should_not_update_account_expiry_date_if_account_does_not_exist),
+            test_case!(should_return_new_valid_until_after_update),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_update_account_expiry_date_with_incorrect_master_password() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let result = account_repository_shared::update_account_expiry_date::<EmptyResponse>(
+            "incorrect master password",
+            user_id1,
+            10
+        ).await;
+
+        assert!(result.is_err());
+        assert_eq!("Bad response status: 403", result.err().unwrap().to_string());
+    }
+
+    async fn should_not_update_account_expiry_date_if_account_does_not_exist() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = account_repository_shared::update_account_expiry_date::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            10
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+        assert_eq!(Some(ErrorCode::AccountNotFound), server_response.error_code);
+    }
+
+    async fn should_return_new_valid_until_after_update() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let account_id1 = AccountId::test_unsafe(user_id1).unwrap();
+        let database = database_shared::database();
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        let time_before_update = chrono::offset::Utc::now();
+
+        let server_response = account_repository_shared::update_account_expiry_date::<UpdateAccountExpiryDateResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            10
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+
+        let response = server_response.data.unwrap();
+        assert_eq!(account_id1.format_token(), response.account_id);
+
+        let valid_until = response.valid_until.unwrap();
+        assert!(valid_until >= time_before_update + chrono::Duration::days(10) - chrono::Duration::seconds(1));
+        assert!(valid_until <= chrono::offset::Utc::now() + chrono::Duration::days(10));
+
+        let from_database = account_repository_shared::get_account_from_database(user_id1, database)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(from_database.valid_until.unwrap().timestamp(), valid_until.timestamp());
+    }
+}
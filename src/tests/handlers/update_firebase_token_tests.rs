@@ -3,7 +3,7 @@ mod tests {
     use crate::handlers::shared::EmptyResponse;
     use crate::model::repository::account_repository::{AccountId, ApplicationType};
     use crate::test_case;
-    use crate::tests::shared::{account_repository_shared, database_shared};
+    use crate::tests::shared::{account_repository_shared, database_shared, watch_post_repository_shared};
     use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
     use crate::tests::shared::shared::{run_test, TestCase};
 
@@ -14,6 +14,7 @@ mod tests {
             test_case!(should_not_update_firebase_token_if_token_is_too_short),
             test_case!(should_not_update_firebase_token_if_token_is_too_long),
             test_case!(should_update_token_if_params_are_good),
+            test_case!(should_replace_previous_token_when_rotating),
         ];
 
         run_test(tests).await;
@@ -160,4 +161,74 @@ mod tests {
             assert!(&from_database.valid_until.is_some());
         }
     }
+
+    async fn should_replace_previous_token_when_rotating() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let account_id1 = AccountId::test_unsafe(user_id1).unwrap();
+        let database = database_shared::database();
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        account_repository_shared::update_token_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            &"good token 1".to_string(),
+            &application_type
+        ).await;
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_some());
+        assert!(server_response.error.is_none());
+
+        let server_response = account_repository_shared::rotate_firebase_token::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            "good token 1",
+            "good token 2",
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_some());
+        assert!(server_response.error.is_none());
+
+        {
+            let from_cache = account_repository_shared::get_account_from_cache(user_id1)
+                .await
+                .unwrap()
+                .unwrap();
+
+            let tokens = from_cache.get_account_tokens(&application_type);
+            assert_eq!(1, tokens.len());
+            assert_eq!("good token 2", tokens.first().unwrap().token);
+        }
+
+        {
+            let from_database = account_repository_shared::get_account_from_database(user_id1, database)
+                .await
+                .unwrap()
+                .unwrap();
+
+            let tokens = from_database.get_account_tokens(&application_type);
+            assert_eq!(1, tokens.len());
+            assert_eq!("good token 2", tokens.first().unwrap().token);
+        }
+
+        let test_post_watches = watch_post_repository_shared::get_post_watches_from_database(
+            &account_id1,
+            database
+        )
+            .await
+            .unwrap();
+
+        assert_eq!(1, test_post_watches.len());
+    }
 }
\ No newline at end of file
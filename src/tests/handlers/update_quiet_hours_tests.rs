@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::shared::EmptyResponse;
+    use crate::model::repository::account_repository::AccountId;
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_update_quiet_hours_if_account_does_not_exist),
+            test_case!(should_not_update_quiet_hours_with_bad_parameters),
+            test_case!(should_update_and_disable_quiet_hours),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_update_quiet_hours_if_account_does_not_exist() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = account_repository_shared::update_quiet_hours::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            Some(22 * 60),
+            Some(7 * 60),
+            0
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+    }
+
+    async fn should_not_update_quiet_hours_with_bad_parameters() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id1).await;
+
+        let bad_parameters = vec![
+            (Some(22 * 60), None, 0, "Both quiet_hours_start and quiet_hours_end must be set"),
+            (None, Some(7 * 60), 0, "Both quiet_hours_start and quiet_hours_end must be set"),
+            (Some(-1), Some(7 * 60), 0, "Quiet hours must be in range 0..1440 (minutes since midnight)"),
+            (Some(22 * 60), Some(1440), 0, "Quiet hours must be in range 0..1440 (minutes since midnight)"),
+            (Some(60), Some(60), 0, "quiet_hours_start must not be equal to quiet_hours_end"),
+            (Some(22 * 60), Some(7 * 60), 15 * 60, "tz_offset_minutes must be in range -720..=840"),
+            (Some(22 * 60), Some(7 * 60), -13 * 60, "tz_offset_minutes must be in range -720..=840"),
+        ];
+
+        for (quiet_hours_start, quiet_hours_end, tz_offset_minutes, expected_error) in bad_parameters {
+            let server_response = account_repository_shared::update_quiet_hours::<EmptyResponse>(
+                TEST_MASTER_PASSWORD,
+                user_id1,
+                quiet_hours_start,
+                quiet_hours_end,
+                tz_offset_minutes
+            ).await.unwrap();
+
+            assert!(server_response.data.is_none());
+            assert_eq!(expected_error, server_response.error.unwrap());
+        }
+    }
+
+    async fn should_update_and_disable_quiet_hours() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let account_id1 = AccountId::test_unsafe(user_id1).unwrap();
+        account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id1).await;
+
+        let server_response = account_repository_shared::update_quiet_hours::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            Some(22 * 60),
+            Some(7 * 60),
+            -5 * 60
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        assert_eq!(Some((Some(22 * 60), Some(7 * 60), -5 * 60)), get_quiet_hours(&account_id1).await);
+
+        let server_response = account_repository_shared::update_quiet_hours::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            None,
+            None,
+            0
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        assert_eq!(Some((None, None, 0)), get_quiet_hours(&account_id1).await);
+    }
+
+    async fn get_quiet_hours(account_id: &AccountId) -> Option<(Option<i16>, Option<i16>, i16)> {
+        let connection = database_shared::database().connection().await.unwrap();
+
+        let row = connection.query_opt(
+            "SELECT quiet_hours_start, quiet_hours_end, tz_offset_minutes FROM accounts WHERE account_id = $1",
+            &[&account_id.id]
+        ).await.unwrap();
+
+        return row.map(|row| (row.get(0), row.get(1), row.get(2)));
+    }
+}
@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::shared::EmptyResponse;
+    use crate::model::repository::account_repository;
+    use crate::model::repository::account_repository::{ApplicationType, TokenType};
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    const TEST_ENDPOINT: &str = "https://push.example.com/push/1";
+    const TEST_P256DH: &str = "BNcRdreALRFXTkOOUHK1EtK2wtaz5Ry4YfYCA_0QTpQtUbVlUls0VJXg7A8u-Ts1XbjhazAkj7I99e8QcYP7DkM";
+    const TEST_AUTH: &str = "tBHItJI5svbpez7KI4CCXg";
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_update_web_push_subscription_if_account_does_not_exist),
+            test_case!(should_not_update_web_push_subscription_if_endpoint_is_not_a_url),
+            test_case!(should_store_web_push_subscription_next_to_firebase_token),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_update_web_push_subscription_if_account_does_not_exist() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let application_type = ApplicationType::KurobaExLiteDebug;
+
+        let server_response = account_repository_shared::update_web_push_subscription::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            TEST_ENDPOINT,
+            TEST_P256DH,
+            TEST_AUTH,
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert!(server_response.error.is_some());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+
+        let web_push_subscription = account_repository::get_web_push_subscription(
+            TEST_ENDPOINT,
+            database_shared::database()
+        ).await.unwrap();
+
+        assert!(web_push_subscription.is_none());
+    }
+
+    async fn should_not_update_web_push_subscription_if_endpoint_is_not_a_url() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let application_type = ApplicationType::KurobaExLiteDebug;
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        let server_response = account_repository_shared::update_web_push_subscription::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            "not a url",
+            TEST_P256DH,
+            TEST_AUTH,
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert!(server_response.error.is_some());
+        assert_eq!("Bad endpoint \'not a url\'", server_response.error.unwrap());
+
+        let from_database = account_repository_shared::get_account_from_database(user_id1, database_shared::database())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(from_database.get_account_tokens(&application_type).is_empty());
+    }
+
+    async fn should_store_web_push_subscription_next_to_firebase_token() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        account_repository_shared::update_firebase_token::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            "good token 1",
+            &application_type
+        ).await.unwrap();
+
+        let server_response = account_repository_shared::update_web_push_subscription::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            TEST_ENDPOINT,
+            TEST_P256DH,
+            TEST_AUTH,
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_some());
+        assert!(server_response.error.is_none());
+
+        {
+            let from_cache = account_repository_shared::get_account_from_cache(user_id1)
+                .await
+                .unwrap()
+                .unwrap();
+
+            let tokens = from_cache.get_account_tokens(&application_type);
+            assert_eq!(2, tokens.len());
+            assert!(tokens.iter().any(|token| token.token == "good token 1" && token.token_type == TokenType::Firebase));
+            assert!(tokens.iter().any(|token| token.token == TEST_ENDPOINT && token.token_type == TokenType::WebPush));
+        }
+
+        {
+            let from_database = account_repository_shared::get_account_from_database(user_id1, database)
+                .await
+                .unwrap()
+                .unwrap();
+
+            let tokens = from_database.get_account_tokens(&application_type);
+            assert_eq!(2, tokens.len());
+            assert!(tokens.iter().any(|token| token.token == "good token 1" && token.token_type == TokenType::Firebase));
+            assert!(tokens.iter().any(|token| token.token == TEST_ENDPOINT && token.token_type == TokenType::WebPush));
+        }
+
+        let web_push_subscription = account_repository::get_web_push_subscription(TEST_ENDPOINT, database)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(TEST_ENDPOINT, web_push_subscription.endpoint);
+        assert_eq!(TEST_P256DH, web_push_subscription.p256dh);
+        assert_eq!(TEST_AUTH, web_push_subscription.auth);
+    }
+}
@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::shared::{EmptyResponse, ErrorCode, ServerResponse, TOO_MANY_REQUESTS_MESSAGE};
+    use crate::model::repository::invites_repository;
+    use crate::test_case;
+    use crate::tests::shared::{database_shared, server_shared};
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    // Same as the max_failures of the /view_invite lockout config
+    const MAX_FAILED_REDEMPTIONS: usize = 3;
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_lock_ip_out_after_rapid_failed_invite_redemptions),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_lock_ip_out_after_rapid_failed_invite_redemptions() {
+        let database = database_shared::database();
+        let (server_address, server_handle) = server_shared::start_throttled_server().await;
+
+        let http_client = reqwest::Client::new();
+        let view_invite_url = |invite: &str| {
+            return format!("http://{}/view_invite?invite={}", server_address, invite);
+        };
+
+        for index in 0..MAX_FAILED_REDEMPTIONS {
+            let response = http_client.get(view_invite_url(&format!("bad_invite_{}", index)))
+                .send()
+                .await
+                .unwrap();
+
+            assert_eq!(404, response.status().as_u16());
+
+            let text = response.text().await.unwrap();
+            assert!(text.contains("Failed to accept invite"));
+        }
+
+        // Even a valid invite can't be redeemed until the lockout ends
+        let invite = invites_repository::generate_invites(database, 1)
+            .await
+            .unwrap()
+            .first()
+            .unwrap()
+            .clone();
+
+        let text = http_client.get(view_invite_url(&invite))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        let server_response: ServerResponse<EmptyResponse> = serde_json::from_str(&text).unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!(TOO_MANY_REQUESTS_MESSAGE, server_response.error.unwrap());
+        assert_eq!(Some(ErrorCode::TooManyRequests), server_response.error_code);
+
+        // The invite was not used up by the rejected request
+        let user_id = invites_repository::accept_invite(&invite, database).await.unwrap();
+        assert!(user_id.is_some());
+
+        server_handle.abort();
+    }
+}
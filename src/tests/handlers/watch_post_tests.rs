@@ -1,7 +1,9 @@
 #[cfg(test)]
 mod tests {
-    use crate::handlers::shared::EmptyResponse;
+    use crate::handlers::shared::{EmptyResponse, ErrorCode, PostDescriptorJson};
+    use crate::constants;
     use crate::model::repository::account_repository::{AccountId, ApplicationType};
+    use crate::model::repository::post_repository;
     use crate::test_case;
     use crate::tests::shared::{account_repository_shared, database_shared, watch_post_repository_shared};
     use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
@@ -11,13 +13,21 @@ mod tests {
     async fn run_tests() {
         let tests: Vec<TestCase> = vec![
             test_case!(should_not_watch_post_if_account_does_not_exist),
+            test_case!(should_not_watch_post_if_application_type_is_unknown),
             test_case!(should_not_watch_post_if_account_is_expired),
             test_case!(should_not_watch_post_if_site_is_not_supported),
             test_case!(should_not_watch_post_if_link_is_unparseable),
+            test_case!(should_not_watch_post_if_link_points_to_thread),
+            test_case!(should_not_watch_post_if_board_is_unknown),
             test_case!(should_not_watch_post_if_link_is_too_short),
             test_case!(should_not_watch_post_if_link_is_too_long),
             test_case!(should_start_watching_post_if_params_are_good),
             test_case!(should_not_create_duplicates_when_one_post_is_watched_multiple_times),
+            test_case!(should_not_watch_post_if_watch_limit_is_reached),
+            test_case!(should_watch_post_that_was_rejected_for_another_account),
+            test_case!(should_not_watch_post_if_filter_regex_is_invalid),
+            test_case!(should_watch_and_unwatch_post_by_descriptor_same_as_by_url),
+            test_case!(should_not_watch_post_if_post_descriptor_is_bad),
         ];
 
         run_test(tests).await;
@@ -35,9 +45,33 @@ mod tests {
 
         assert!(server_response.data.is_none());
         assert!(server_response.error.is_some());
+        assert_eq!(Some(ErrorCode::AccountNotFound), server_response.error_code);
         assert_eq!("Account does not exist", server_response.error.unwrap());
     }
 
+    async fn should_not_watch_post_if_application_type_is_unknown() {
+        let application_type = ApplicationType::Unknown;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert!(server_response.error.is_some());
+        assert_eq!(
+            "Unsupported \'application_type\' parameter value: -1",
+            server_response.error.unwrap()
+        );
+    }
+
     async fn should_not_watch_post_if_account_is_expired() {
         let application_type = ApplicationType::KurobaExLiteDebug;
         let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
@@ -74,6 +108,7 @@ mod tests {
 
         assert!(server_response.data.is_none());
         assert!(server_response.error.is_some());
+        assert_eq!(Some(ErrorCode::SiteNotSupported), server_response.error_code);
         assert_eq!(
             "Site for url 'https://imageboard.com/vg/thread/426895061#p426901491' is not supported",
             server_response.error.unwrap()
@@ -92,12 +127,49 @@ mod tests {
 
         assert!(server_response.data.is_none());
         assert!(server_response.error.is_some());
+        assert_eq!(Some(ErrorCode::InvalidPostUrl), server_response.error_code);
         assert_eq!(
             "Failed to parse \'https://boards.4channel.org/vg/thread/4268<BAM>95061#p426901491\' url as post url",
             server_response.error.unwrap()
         );
     }
 
+    async fn should_not_watch_post_if_link_points_to_thread() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061",
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert!(server_response.error.is_some());
+        assert_eq!(Some(ErrorCode::PostUrlIsThreadUrl), server_response.error_code);
+        assert_eq!(
+            "URL \'https://boards.4channel.org/vg/thread/426895061\' points to a thread, not a post; \
+            did you mean to watch the whole thread?",
+            server_response.error.unwrap()
+        );
+    }
+
+    async fn should_not_watch_post_if_board_is_unknown() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            "https://boards.4channel.org/vgg/thread/426895061#p426901491",
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert!(server_response.error.is_some());
+        assert_eq!(Some(ErrorCode::UnknownBoard), server_response.error_code);
+        assert_eq!("Unknown board \'vgg\'", server_response.error.unwrap());
+    }
+
     async fn should_not_watch_post_if_link_is_too_short() {
         let application_type = ApplicationType::KurobaExLiteDebug;
         let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
@@ -110,6 +182,7 @@ mod tests {
 
         assert!(server_response.data.is_none());
         assert!(server_response.error.is_some());
+        assert_eq!(Some(ErrorCode::PostUrlEmpty), server_response.error_code);
         assert_eq!(
             "post_url is empty",
             server_response.error.unwrap()
@@ -131,6 +204,7 @@ mod tests {
 
         assert!(server_response.data.is_none());
         assert!(server_response.error.is_some());
+        assert_eq!(Some(ErrorCode::PostUrlTooLong), server_response.error_code);
         assert_eq!(
             "post_url is too long",
             server_response.error.unwrap()
@@ -325,4 +399,303 @@ mod tests {
         }
     }
 
-}
\ No newline at end of file
+    async fn should_not_watch_post_if_watch_limit_is_reached() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let account_id1 = AccountId::test_unsafe(user_id1).unwrap();
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        account_repository_shared::update_firebase_token::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+            &application_type
+        ).await.unwrap();
+
+        post_repository::set_max_watches_per_account(2);
+
+        let post_urls = vec![
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            "https://boards.4channel.org/vg/thread/426895061#p426901492",
+            // Watching an already watched post must not count towards the limit
+            "https://boards.4channel.org/vg/thread/426895061#p426901492",
+        ];
+
+        for post_url in post_urls {
+            let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+                user_id1,
+                post_url,
+                &application_type
+            ).await.unwrap();
+
+            assert!(server_response.data.is_some());
+            assert!(server_response.error.is_none());
+        }
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061#p426901493",
+            &application_type
+        ).await.unwrap();
+
+        post_repository::set_max_watches_per_account(constants::DEFAULT_MAX_WATCHES_PER_ACCOUNT);
+
+        assert!(server_response.data.is_none());
+        assert!(server_response.error.is_some());
+        assert_eq!("Too many watched posts", server_response.error.unwrap());
+
+        let test_post_watches = watch_post_repository_shared::get_post_watches_from_database(
+            &account_id1,
+            database_shared::database()
+        )
+            .await
+            .unwrap();
+
+        assert_eq!(2, test_post_watches.len());
+    }
+
+    async fn should_watch_post_that_was_rejected_for_another_account() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let user_id2 = &account_repository_shared::TEST_GOOD_USER_ID2;
+        let account_id2 = AccountId::test_unsafe(user_id2).unwrap();
+
+        account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id1).await;
+        account_repository_shared::update_token_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+            &application_type
+        ).await;
+
+        account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id2).await;
+        account_repository_shared::update_token_actual(
+            TEST_MASTER_PASSWORD,
+            user_id2,
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN2,
+            &application_type
+        ).await;
+
+        // Both posts are in threads that nobody watched yet so their descriptors are inserted by
+        // the requests that get rejected
+        let rejected_post_url1 = "https://boards.4channel.org/vg/thread/426895062#p426895063";
+        let rejected_post_url2 = "https://boards.4channel.org/vg/thread/426895064#p426895065";
+
+        post_repository::set_max_watches_per_account(1);
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            &application_type
+        ).await.unwrap();
+        assert!(server_response.error.is_none());
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            rejected_post_url1,
+            &application_type
+        ).await.unwrap();
+        assert_eq!(Some(ErrorCode::WatchLimitReached), server_response.error_code);
+
+        let server_response = watch_post_repository_shared::watch_posts::<EmptyResponse>(
+            user_id1,
+            &[rejected_post_url2],
+            &application_type
+        ).await.unwrap();
+        assert_eq!(Some(ErrorCode::WatchLimitReached), server_response.error_code);
+
+        post_repository::set_max_watches_per_account(constants::DEFAULT_MAX_WATCHES_PER_ACCOUNT);
+
+        for post_url in [rejected_post_url1, rejected_post_url2] {
+            let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+                user_id2,
+                post_url,
+                &application_type
+            ).await.unwrap();
+
+            assert!(server_response.data.is_some());
+            assert!(server_response.error.is_none());
+        }
+
+        let test_post_watches = watch_post_repository_shared::get_post_watches_from_database(
+            &account_id2,
+            database_shared::database()
+        )
+            .await
+            .unwrap();
+
+        assert_eq!(2, test_post_watches.len());
+    }
+
+    async fn should_not_watch_post_if_filter_regex_is_invalid() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let account_id1 = AccountId::test_unsafe(user_id1).unwrap();
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        account_repository_shared::update_firebase_token::<EmptyResponse>(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+            &application_type
+        ).await.unwrap();
+
+        let server_response = watch_post_repository_shared::watch_post_with_filter::<EmptyResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            &application_type,
+            Some("(unclosed")
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert!(server_response.error.is_some());
+        assert!(server_response.error.unwrap().starts_with("Invalid \'filter_regex\'"));
+
+        let test_post_watches = watch_post_repository_shared::get_post_watches_from_database(
+            &account_id1,
+            database_shared::database()
+        ).await.unwrap();
+
+        assert!(test_post_watches.is_empty());
+    }
+
+    async fn should_watch_and_unwatch_post_by_descriptor_same_as_by_url() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let user_id2 = &account_repository_shared::TEST_GOOD_USER_ID2;
+
+        let account_id1 = AccountId::test_unsafe(user_id1).unwrap();
+        let account_id2 = AccountId::test_unsafe(user_id2).unwrap();
+
+        let database = database_shared::database();
+
+        for (user_id, firebase_token) in [
+            (user_id1, &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1),
+            (user_id2, &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN2)
+        ] {
+            account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id).await;
+            account_repository_shared::update_token_actual(
+                TEST_MASTER_PASSWORD,
+                user_id,
+                firebase_token,
+                &application_type
+            ).await;
+        }
+
+        let post_descriptor_json = PostDescriptorJson {
+            site_name: "4chan".to_string(),
+            board_code: "vg".to_string(),
+            thread_no: 426895061,
+            post_no: 426901491,
+            post_sub_no: 0
+        };
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            &application_type
+        ).await.unwrap();
+        assert!(server_response.error.is_none());
+
+        let server_response = watch_post_repository_shared::watch_post_by_descriptor::<EmptyResponse>(
+            user_id2,
+            &post_descriptor_json,
+            &application_type
+        ).await.unwrap();
+        assert!(server_response.error.is_none());
+
+        let post_watches1 = watch_post_repository_shared::get_post_watches_from_database(&account_id1, database)
+            .await
+            .unwrap();
+        let post_watches2 = watch_post_repository_shared::get_post_watches_from_database(&account_id2, database)
+            .await
+            .unwrap();
+
+        assert_eq!(1, post_watches1.len());
+        assert_eq!(1, post_watches2.len());
+        assert_eq!(post_watches1[0].post_descriptor, post_watches2[0].post_descriptor);
+
+        // Watching the same post by url must not create a second watch
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id2,
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            &application_type
+        ).await.unwrap();
+        assert!(server_response.error.is_none());
+
+        let post_watches2 = watch_post_repository_shared::get_post_watches_from_database(&account_id2, database)
+            .await
+            .unwrap();
+        assert_eq!(1, post_watches2.len());
+
+        let server_response = watch_post_repository_shared::unwatch_post_by_descriptor::<EmptyResponse>(
+            user_id2,
+            &post_descriptor_json,
+            &application_type
+        ).await.unwrap();
+        assert!(server_response.error.is_none());
+
+        let post_watches2 = watch_post_repository_shared::get_post_watches_from_database(&account_id2, database)
+            .await
+            .unwrap();
+        assert!(post_watches2.is_empty());
+    }
+
+    async fn should_not_watch_post_if_post_descriptor_is_bad() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let post_descriptor_json = |site_name: &str, board_code: &str, post_no: u64| {
+            return PostDescriptorJson {
+                site_name: site_name.to_string(),
+                board_code: board_code.to_string(),
+                thread_no: 426895061,
+                post_no,
+                post_sub_no: 0
+            };
+        };
+
+        let test_cases = [
+            (
+                post_descriptor_json("imageboard", "vg", 426901491),
+                ErrorCode::SiteNotSupported,
+                "Site 'imageboard' is not supported"
+            ),
+            (
+                post_descriptor_json("4chan", "v g", 426901491),
+                ErrorCode::InvalidParameter,
+                "Bad post_descriptor: board_code 'v g' contains not allowed characters"
+            ),
+            (
+                post_descriptor_json("4chan", "vg", 1),
+                ErrorCode::InvalidParameter,
+                "Bad post_descriptor: thread_no 426895061 and post_no 1 must be positive and post_no must not be less than thread_no"
+            ),
+            (
+                post_descriptor_json("4chan", "vgg", 426901491),
+                ErrorCode::UnknownBoard,
+                "Unknown board 'vgg'"
+            ),
+        ];
+
+        for (post_descriptor_json, expected_error_code, expected_error) in test_cases {
+            let server_response = watch_post_repository_shared::watch_post_by_descriptor::<EmptyResponse>(
+                user_id1,
+                &post_descriptor_json,
+                &application_type
+            ).await.unwrap();
+
+            assert!(server_response.data.is_none());
+            assert_eq!(Some(expected_error_code), server_response.error_code);
+            assert_eq!(expected_error, server_response.error.unwrap());
+        }
+    }
+}
@@ -0,0 +1,96 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::shared::EmptyResponse;
+    use crate::handlers::watch_posts::{WATCH_POST_RESULT_OK, WATCH_POST_RESULT_UNKNOWN_BOARD, WATCH_POST_RESULT_UNPARSEABLE, WATCH_POST_RESULT_UNSUPPORTED_SITE, WatchPostsResponse};
+    use crate::model::repository::account_repository::{AccountId, ApplicationType};
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, database_shared, watch_post_repository_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_not_watch_posts_if_account_does_not_exist),
+            test_case!(should_watch_valid_posts_and_report_invalid_ones),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_not_watch_posts_if_account_does_not_exist() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = watch_post_repository_shared::watch_posts::<EmptyResponse>(
+            user_id1,
+            &["https://boards.4channel.org/vg/thread/426895061#p426901491"],
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+    }
+
+    async fn should_watch_valid_posts_and_report_invalid_ones() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let account_id1 = AccountId::test_unsafe(user_id1).unwrap();
+        let database = database_shared::database();
+
+        account_repository_shared::create_account_actual(TEST_MASTER_PASSWORD, user_id1).await;
+        account_repository_shared::update_token_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1,
+            &application_type
+        ).await;
+
+        let post_urls = [
+            "https://boards.4channel.org/vg/thread/426895061#p426901491",
+            "https://imageboard.com/vg/thread/426895061#p426901491",
+            "https://boards.4channel.org/vg/thread/4268<BAM>95061#p426901491",
+            "https://boards.4channel.org/vgg/thread/426895061#p426901491",
+            "https://boards.4channel.org/vg/thread/426895061#p426901492",
+            "https://boards.4channel.org/vg/thread/426895061#p426901492",
+        ];
+
+        let server_response = watch_post_repository_shared::watch_posts::<WatchPostsResponse>(
+            user_id1,
+            &post_urls,
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+
+        let results = server_response.data.unwrap().results;
+        assert_eq!(post_urls.len(), results.len());
+
+        let expected_results = [
+            WATCH_POST_RESULT_OK,
+            WATCH_POST_RESULT_UNSUPPORTED_SITE,
+            WATCH_POST_RESULT_UNPARSEABLE,
+            WATCH_POST_RESULT_UNKNOWN_BOARD,
+            WATCH_POST_RESULT_OK,
+            WATCH_POST_RESULT_OK,
+        ];
+
+        for (index, expected_result) in expected_results.iter().enumerate() {
+            assert_eq!(post_urls[index], results[index].post_url);
+            assert_eq!(*expected_result, results[index].result);
+        }
+
+        let mut test_post_watches = watch_post_repository_shared::get_post_watches_from_database(
+            &account_id1,
+            database
+        )
+            .await
+            .unwrap();
+
+        test_post_watches.sort_by_key(|test_post_watch| test_post_watch.post_descriptor.post_no);
+
+        assert_eq!(2, test_post_watches.len());
+        assert_eq!(426901491, test_post_watches[0].post_descriptor.post_no);
+        assert_eq!(426901492, test_post_watches[1].post_descriptor.post_no);
+    }
+}
@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::shared::{EmptyResponse, ErrorCode};
+    use crate::handlers::whoami::WhoAmIResponse;
+    use crate::helpers::string_helpers::FormatToken;
+    use crate::model::repository::account_repository::ApplicationType;
+    use crate::test_case;
+    use crate::tests::shared::{account_repository_shared, watch_post_repository_shared};
+    use crate::tests::shared::server_shared::TEST_MASTER_PASSWORD;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    const POST_URL: &'static str = "https://boards.4channel.org/vg/thread/426895061#p426901491";
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_return_nothing_if_account_does_not_exist),
+            test_case!(should_return_token_presence_per_application_type),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_return_nothing_if_account_does_not_exist() {
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+
+        let server_response = account_repository_shared::whoami::<EmptyResponse>(user_id1)
+            .await
+            .unwrap();
+
+        assert!(server_response.data.is_none());
+        assert_eq!("Account does not exist", server_response.error.unwrap());
+        assert_eq!(Some(ErrorCode::AccountNotFound), server_response.error_code);
+    }
+
+    async fn should_return_token_presence_per_application_type() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let user_id1 = &account_repository_shared::TEST_GOOD_USER_ID1;
+        let firebase_token = &account_repository_shared::TEST_GOOD_FIREBASE_TOKEN1;
+
+        account_repository_shared::create_account_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1
+        ).await;
+
+        account_repository_shared::update_token_actual(
+            TEST_MASTER_PASSWORD,
+            user_id1,
+            firebase_token,
+            &application_type
+        ).await;
+
+        let whoami_response = whoami(user_id1).await;
+        assert!(!whoami_response.is_banned);
+        assert!(whoami_response.valid_until.is_some());
+        assert_eq!(0, whoami_response.watches_count);
+        assert_eq!(ApplicationType::supported().len(), whoami_response.applications.len());
+
+        let lite_debug = whoami_response.applications
+            .iter()
+            .find(|application| application.application_type == ApplicationType::KurobaExLiteDebug)
+            .unwrap();
+
+        assert!(lite_debug.is_valid);
+        assert_eq!(vec![firebase_token.format_token().to_string()], lite_debug.tokens);
+        assert_ne!(firebase_token.as_str(), lite_debug.tokens[0].as_str());
+
+        let lite_production = whoami_response.applications
+            .iter()
+            .find(|application| application.application_type == ApplicationType::KurobaExLiteProduction)
+            .unwrap();
+
+        assert!(!lite_production.is_valid);
+        assert!(lite_production.tokens.is_empty());
+
+        let server_response = watch_post_repository_shared::watch_post::<EmptyResponse>(
+            user_id1,
+            POST_URL,
+            &application_type
+        ).await.unwrap();
+
+        assert!(server_response.error.is_none());
+        assert_eq!(1, whoami(user_id1).await.watches_count);
+    }
+
+    async fn whoami(user_id: &str) -> WhoAmIResponse {
+        let server_response = account_repository_shared::whoami::<WhoAmIResponse>(user_id)
+            .await
+            .unwrap();
+
+        assert!(server_response.error.is_none());
+        return server_response.data.unwrap();
+    }
+}
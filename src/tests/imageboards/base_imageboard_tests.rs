@@ -0,0 +1,628 @@
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::io::Write;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    use async_trait::async_trait;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use http_body_util::Full;
+    use hyper::{Request, Response};
+    use hyper::body::{Bytes, Incoming};
+    use hyper::server::conn::http1;
+    use hyper::service::service_fn;
+    use lazy_static::lazy_static;
+    use regex::Regex;
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    use crate::helpers::http_client;
+    use crate::model::data::chan::{CatalogDescriptor, PostDescriptor, SiteDescriptor, ThreadDescriptor};
+    use crate::model::imageboards::base_imageboard;
+    use crate::model::imageboards::base_imageboard::{Imageboard, ThreadLoadResult};
+    use crate::model::imageboards::parser::chan4_post_parser::Chan4PostParser;
+    use crate::model::imageboards::parser::dvach_post_parser::DvachPostParser;
+    use crate::model::imageboards::parser::post_parser::PostParser;
+    use crate::model::repository::site_repository::ImageboardSynced;
+    use crate::model::repository::thread_repository;
+    use crate::service::thread_watcher;
+    use crate::test_case;
+    use crate::tests::shared::{database_shared, site_repository_shared};
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    lazy_static! {
+        static ref POST_REPLY_QUOTE_REGEXES: Vec<Regex> = vec![
+            Regex::new(r#"class="quotelink">&gt;&gt;(\d+)</a>"#).unwrap()
+        ];
+        static ref POST_PARSER: Box<dyn PostParser + Sync> = Box::new(Chan4PostParser {});
+        static ref DVACH_POST_PARSER: Box<dyn PostParser + Sync> = Box::new(DvachPostParser {});
+    }
+
+    const THREAD_ETAG: &'static str = "\"v1\"";
+
+    const THREAD_LAST_MODIFIED: &'static str = "Wed, 21 Oct 2015 07:28:00 GMT";
+
+    const SLOW_RESPONSE_DELAY: Duration = Duration::from_secs(5);
+
+    static ETAG_GET_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+    static LAST_MODIFIED_GET_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+    static TAIL_GET_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+    static FULL_AFTER_TAIL_GET_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+    const THREAD_JSON: &'static str = r#"
+        {
+            "posts": [
+                { "no": 1, "resto": 0, "sub": "Thread subject", "com": "OP comment", "closed": 0 },
+                { "no": 2, "resto": 1, "com": "<a href=\"#p1\" class=\"quotelink\">&gt;&gt;1</a>" }
+            ]
+        }
+    "#;
+
+    // Tail of a thread that has no posts newer than the last processed post (2)
+    const EMPTY_THREAD_TAIL_JSON: &'static str = r#"
+        {
+            "posts": [
+                { "no": 1, "tail_size": 0, "tail_id": 2, "sub": "Thread subject", "closed": 0 }
+            ]
+        }
+    "#;
+
+    // First page of the posts after the last processed post (2), more posts remain
+    const DVACH_AFTER_FIRST_PAGE_JSON: &'static str = r#"
+        {
+            "posts": [
+                { "num": 3, "op": 0, "comment": "Reply 3" },
+                { "num": 4, "op": 0, "comment": "Reply 4" }
+            ],
+            "has_more": true
+        }
+    "#;
+
+    const DVACH_AFTER_LAST_PAGE_JSON: &'static str = r#"
+        {
+            "posts": [
+                { "num": 5, "op": 0, "comment": "Reply 5" }
+            ]
+        }
+    "#;
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_follow_redirect_and_parse_thread),
+            test_case!(should_fail_on_redirect_loop),
+            test_case!(should_fail_when_redirect_cap_is_exceeded),
+            test_case!(should_not_load_thread_when_etag_matches),
+            test_case!(should_return_etag_when_thread_was_modified),
+            test_case!(should_not_load_unchanged_thread_again_once_last_modified_is_stored),
+            test_case!(should_decompress_gzip_encoded_thread),
+            test_case!(should_fail_with_clear_error_when_body_is_not_decompressed),
+            test_case!(should_time_out_when_site_does_not_respond),
+            test_case!(should_fall_back_to_full_load_once_when_partial_load_has_no_posts),
+            test_case!(should_gather_posts_from_every_page_of_partial_load),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_follow_redirect_and_parse_thread() {
+        let (server_address, server_handle) = start_mock_server().await;
+
+        let result = load_test_thread(server_address, "redirect").await.unwrap();
+        server_handle.abort();
+
+        let chan_thread = match result {
+            ThreadLoadResult::Success(chan_thread, _, _) => chan_thread,
+            _ => panic!("Unexpected thread load result")
+        };
+
+        assert_eq!(Some("Thread subject".to_string()), chan_thread.subject);
+        assert_eq!(2, chan_thread.posts.len());
+    }
+
+    async fn should_fail_on_redirect_loop() {
+        let (server_address, server_handle) = start_mock_server().await;
+
+        let result = load_test_thread(server_address, "loop").await;
+        server_handle.abort();
+
+        assert!(result.is_err());
+    }
+
+    async fn should_fail_when_redirect_cap_is_exceeded() {
+        let (server_address, server_handle) = start_mock_server().await;
+
+        let result = load_test_thread(server_address, "chain0").await;
+        server_handle.abort();
+
+        assert!(result.is_err());
+    }
+
+    async fn should_not_load_thread_when_etag_matches() {
+        let (server_address, server_handle) = start_mock_server().await;
+        let database = database_shared::database();
+        ETAG_GET_REQUESTS.store(0, Ordering::SeqCst);
+
+        let thread_descriptor = ThreadDescriptor::new("mock".to_string(), "etag".to_string(), 1);
+        let last_processed_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0);
+        thread_repository::store_last_processed_post(&last_processed_post, database).await.unwrap();
+        thread_repository::store_last_etag(&THREAD_ETAG.to_string(), &thread_descriptor, database).await.unwrap();
+
+        let result = load_test_thread(server_address, "etag").await.unwrap();
+        server_handle.abort();
+
+        assert!(matches!(result, ThreadLoadResult::ThreadWasNotModifiedSinceLastCheck));
+        assert_eq!(0, ETAG_GET_REQUESTS.load(Ordering::SeqCst));
+    }
+
+    async fn should_return_etag_when_thread_was_modified() {
+        let (server_address, server_handle) = start_mock_server().await;
+        ETAG_GET_REQUESTS.store(0, Ordering::SeqCst);
+
+        let result = load_test_thread(server_address, "etag").await.unwrap();
+        server_handle.abort();
+
+        let etag = match result {
+            ThreadLoadResult::Success(_, _, etag) => etag,
+            _ => panic!("Unexpected thread load result")
+        };
+
+        assert_eq!(Some(THREAD_ETAG.to_string()), etag);
+        assert_eq!(1, ETAG_GET_REQUESTS.load(Ordering::SeqCst));
+    }
+
+    async fn should_not_load_unchanged_thread_again_once_last_modified_is_stored() {
+        let (server_address, server_handle) = start_mock_server().await;
+        let database = database_shared::database();
+        LAST_MODIFIED_GET_REQUESTS.store(0, Ordering::SeqCst);
+
+        let thread_descriptor = ThreadDescriptor::new("mock".to_string(), "last_modified".to_string(), 1);
+        assert!(thread_repository::get_last_modified(&thread_descriptor, database).await.unwrap().is_none());
+
+        let result = load_test_thread(server_address, "last_modified").await.unwrap();
+
+        let (last_modified, etag) = match result {
+            ThreadLoadResult::Success(_, last_modified, etag) => (last_modified, etag),
+            _ => panic!("Unexpected thread load result")
+        };
+
+        assert!(last_modified.is_some());
+
+        // The thread row doesn't exist yet, the timestamp must still be persisted
+        thread_watcher::store_thread_cache_headers(&thread_descriptor, &last_modified, &etag, database)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            last_modified,
+            thread_repository::get_last_modified(&thread_descriptor, database).await.unwrap()
+        );
+
+        let result = load_test_thread(server_address, "last_modified").await.unwrap();
+        server_handle.abort();
+
+        assert!(matches!(result, ThreadLoadResult::ThreadWasNotModifiedSinceLastCheck));
+        assert_eq!(1, LAST_MODIFIED_GET_REQUESTS.load(Ordering::SeqCst));
+    }
+
+    async fn should_decompress_gzip_encoded_thread() {
+        let (server_address, server_handle) = start_mock_server().await;
+
+        let result = load_test_thread(server_address, "gzip").await.unwrap();
+        server_handle.abort();
+
+        let chan_thread = match result {
+            ThreadLoadResult::Success(chan_thread, _, _) => chan_thread,
+            _ => panic!("Unexpected thread load result")
+        };
+
+        assert_eq!(Some("Thread subject".to_string()), chan_thread.subject);
+        assert_eq!(2, chan_thread.posts.len());
+    }
+
+    async fn should_fail_with_clear_error_when_body_is_not_decompressed() {
+        let (server_address, server_handle) = start_mock_server().await;
+
+        let result = load_test_thread(server_address, "compress").await.unwrap();
+        server_handle.abort();
+
+        let error_text = match result {
+            ThreadLoadResult::FailedToReadChanThread(error_text) => error_text,
+            _ => panic!("Unexpected thread load result")
+        };
+
+        assert_eq!(
+            "Response body is \'compress\'-encoded but the http client didn't decompress it",
+            error_text
+        );
+    }
+
+    async fn should_time_out_when_site_does_not_respond() {
+        let (server_address, server_handle) = start_mock_server().await;
+
+        let http_client = http_client::build_http_client(
+            None,
+            Duration::from_millis(300),
+            Duration::from_millis(300)
+        );
+
+        let started_at = Instant::now();
+        let result = load_test_thread_with_client(server_address, "slow", &http_client, &None).await;
+        let elapsed = started_at.elapsed();
+        server_handle.abort();
+
+        assert!(matches!(result.unwrap(), ThreadLoadResult::RequestTimedOut));
+        assert!(elapsed < SLOW_RESPONSE_DELAY);
+    }
+
+    async fn should_fall_back_to_full_load_once_when_partial_load_has_no_posts() {
+        let (server_address, server_handle) = start_mock_server().await;
+        TAIL_GET_REQUESTS.store(0, Ordering::SeqCst);
+        FULL_AFTER_TAIL_GET_REQUESTS.store(0, Ordering::SeqCst);
+
+        let thread_descriptor = ThreadDescriptor::new("mock".to_string(), "tail".to_string(), 1);
+        let last_processed_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 2, 0);
+
+        let result = load_test_thread_with_client(
+            server_address,
+            "tail",
+            site_repository_shared::site_repository().http_client(),
+            &Some(last_processed_post)
+        ).await.unwrap();
+        server_handle.abort();
+
+        let chan_thread = match result {
+            ThreadLoadResult::Success(chan_thread, _, _) => chan_thread,
+            _ => panic!("Unexpected thread load result")
+        };
+
+        assert_eq!(Some("Thread subject".to_string()), chan_thread.subject);
+        assert_eq!(2, chan_thread.posts.len());
+        assert_eq!(1, TAIL_GET_REQUESTS.load(Ordering::SeqCst));
+        assert_eq!(1, FULL_AFTER_TAIL_GET_REQUESTS.load(Ordering::SeqCst));
+    }
+
+    async fn should_gather_posts_from_every_page_of_partial_load() {
+        let (server_address, server_handle) = start_mock_server().await;
+
+        let imageboard: ImageboardSynced = Arc::new(DvachMockImageboard { server_address });
+        let thread_descriptor = ThreadDescriptor::new("mock".to_string(), "b".to_string(), 1);
+        let last_processed_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 2, 0);
+
+        let result = base_imageboard::load_thread(
+            &imageboard,
+            site_repository_shared::site_repository().http_client(),
+            database_shared::database(),
+            &thread_descriptor,
+            &Some(last_processed_post)
+        ).await.unwrap();
+        server_handle.abort();
+
+        let chan_thread = match result {
+            ThreadLoadResult::Success(chan_thread, _, _) => chan_thread,
+            _ => panic!("Unexpected thread load result")
+        };
+
+        let post_nos = chan_thread.posts.iter().map(|post| post.post_no).collect::<Vec<u64>>();
+        assert_eq!(vec![3, 4, 5], post_nos);
+        assert_eq!(Some("Reply 5".to_string()), chan_thread.posts[2].comment_unparsed);
+    }
+
+    async fn load_test_thread(
+        server_address: SocketAddr,
+        board_code: &str
+    ) -> anyhow::Result<ThreadLoadResult> {
+        return load_test_thread_with_client(
+            server_address,
+            board_code,
+            site_repository_shared::site_repository().http_client(),
+            &None
+        ).await;
+    }
+
+    async fn load_test_thread_with_client(
+        server_address: SocketAddr,
+        board_code: &str,
+        http_client: &reqwest::Client,
+        last_processed_post: &Option<PostDescriptor>
+    ) -> anyhow::Result<ThreadLoadResult> {
+        let imageboard: ImageboardSynced = Arc::new(MockImageboard { server_address });
+        let thread_descriptor = ThreadDescriptor::new("mock".to_string(), board_code.to_string(), 1);
+
+        return base_imageboard::load_thread(
+            &imageboard,
+            http_client,
+            database_shared::database(),
+            &thread_descriptor,
+            last_processed_post
+        ).await;
+    }
+
+    async fn start_mock_server() -> (SocketAddr, JoinHandle<()>) {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await.unwrap();
+        let server_address = listener.local_addr().unwrap();
+
+        let join_handle = tokio::task::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+
+                tokio::task::spawn(async move {
+                    let _ = http1::Builder::new()
+                        .serve_connection(stream, service_fn(mock_handler))
+                        .await;
+                });
+            }
+        });
+
+        return (server_address, join_handle);
+    }
+
+    async fn mock_handler(request: Request<Incoming>) -> Result<Response<Full<Bytes>>, Infallible> {
+        let path = request.uri().path();
+
+        let response = if path == "/etag/thread/1.json" {
+            etag_response(&request)
+        } else if path == "/last_modified/thread/1.json" {
+            if request.method() == hyper::Method::GET {
+                LAST_MODIFIED_GET_REQUESTS.fetch_add(1, Ordering::SeqCst);
+            }
+
+            Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .header("Last-Modified", THREAD_LAST_MODIFIED)
+                .body(Full::new(Bytes::from(THREAD_JSON)))
+                .unwrap()
+        } else if path == "/redirect/thread/1.json" {
+            redirect_response("/g/thread/1.json")
+        } else if path == "/loop/thread/1.json" {
+            redirect_response("/loop/thread/1.json")
+        } else if path.starts_with("/chain") {
+            let hop = path.trim_start_matches("/chain")
+                .trim_end_matches("/thread/1.json")
+                .parse::<u32>()
+                .unwrap();
+
+            redirect_response(&format!("/chain{}/thread/1.json", hop + 1))
+        } else if path == "/gzip/thread/1.json" {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(THREAD_JSON.as_bytes()).unwrap();
+
+            Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .header("Content-Encoding", "gzip")
+                .body(Full::new(Bytes::from(encoder.finish().unwrap())))
+                .unwrap()
+        } else if path == "/compress/thread/1.json" {
+            // reqwest doesn't support this encoding so the body is returned as is
+            Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .header("Content-Encoding", "compress")
+                .body(Full::new(Bytes::from(THREAD_JSON)))
+                .unwrap()
+        } else if path == "/slow/thread/1.json" {
+            tokio::time::sleep(SLOW_RESPONSE_DELAY).await;
+
+            Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .body(Full::new(Bytes::from(THREAD_JSON)))
+                .unwrap()
+        } else if path == "/tail/thread/1-tail.json" {
+            if request.method() == hyper::Method::GET {
+                TAIL_GET_REQUESTS.fetch_add(1, Ordering::SeqCst);
+            }
+
+            Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .body(Full::new(Bytes::from(EMPTY_THREAD_TAIL_JSON)))
+                .unwrap()
+        } else if path == "/tail/thread/1.json" {
+            if request.method() == hyper::Method::GET {
+                FULL_AFTER_TAIL_GET_REQUESTS.fetch_add(1, Ordering::SeqCst);
+            }
+
+            Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .body(Full::new(Bytes::from(THREAD_JSON)))
+                .unwrap()
+        } else if path == "/dvach/after/b/1/2" {
+            Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .body(Full::new(Bytes::from(DVACH_AFTER_FIRST_PAGE_JSON)))
+                .unwrap()
+        } else if path == "/dvach/after/b/1/4" {
+            Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .body(Full::new(Bytes::from(DVACH_AFTER_LAST_PAGE_JSON)))
+                .unwrap()
+        } else if path == "/g/thread/1.json" {
+            Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .body(Full::new(Bytes::from(THREAD_JSON)))
+                .unwrap()
+        } else {
+            Response::builder()
+                .status(404)
+                .body(Full::new(Bytes::new()))
+                .unwrap()
+        };
+
+        return Ok(response);
+    }
+
+    fn etag_response(request: &Request<Incoming>) -> Response<Full<Bytes>> {
+        let if_none_match = request.headers()
+            .get("If-None-Match")
+            .map(|header_value| header_value.to_str().unwrap_or(""))
+            .unwrap_or("");
+
+        if if_none_match == THREAD_ETAG {
+            return Response::builder()
+                .status(304)
+                .header("ETag", THREAD_ETAG)
+                .body(Full::new(Bytes::new()))
+                .unwrap();
+        }
+
+        if request.method() == hyper::Method::GET {
+            ETAG_GET_REQUESTS.fetch_add(1, Ordering::SeqCst);
+        }
+
+        return Response::builder()
+            .status(200)
+            .header("Content-Type", "application/json")
+            .header("ETag", THREAD_ETAG)
+            .body(Full::new(Bytes::from(THREAD_JSON)))
+            .unwrap();
+    }
+
+    fn redirect_response(location: &str) -> Response<Full<Bytes>> {
+        return Response::builder()
+            .status(302)
+            .header("Location", location)
+            .body(Full::new(Bytes::new()))
+            .unwrap();
+    }
+
+    struct MockImageboard {
+        server_address: SocketAddr
+    }
+
+    #[async_trait]
+    impl Imageboard for MockImageboard {
+        fn name(&self) -> &'static str {
+            return "mock";
+        }
+
+        fn matches(&self, site_descriptor: &SiteDescriptor) -> bool {
+            return site_descriptor.site_name_str() == "mock";
+        }
+
+        fn url_matches(&self, _url: &str) -> bool {
+            return false;
+        }
+
+        fn post_url_to_post_descriptor(&self, _post_url: &str) -> Option<PostDescriptor> {
+            return None;
+        }
+
+        fn post_descriptor_to_url(&self, _post_descriptor: &PostDescriptor) -> Option<String> {
+            return None;
+        }
+
+        fn post_quote_regexes(&self) -> &'static [Regex] {
+            return &POST_REPLY_QUOTE_REGEXES;
+        }
+
+        fn post_parser(&self) -> &'static Box<dyn PostParser + Sync> {
+            return &POST_PARSER;
+        }
+
+        fn thread_json_endpoint(
+            &self,
+            thread_descriptor: &ThreadDescriptor,
+            last_processed_post: &Option<PostDescriptor>
+        ) -> Option<String> {
+            // Same as 4chan, the tail of a thread is loaded when some posts were already processed
+            let endpoint = format!(
+                "http://{}/{}/thread/{}{}.json",
+                self.server_address,
+                thread_descriptor.board_code(),
+                thread_descriptor.thread_no,
+                if last_processed_post.is_some() { "-tail" } else { "" }
+            );
+
+            return Some(endpoint);
+        }
+
+        fn supports_partial_load_head_request(&self) -> bool {
+            return true;
+        }
+
+        fn catalog_json_endpoint(&self, _catalog_descriptor: &CatalogDescriptor) -> Option<String> {
+            return None;
+        }
+
+        fn boards_json_endpoint(&self) -> Option<String> {
+            return None;
+        }
+    }
+
+    /// Loads threads the same way 2ch does, through the "after" endpoint of the mobile API.
+    struct DvachMockImageboard {
+        server_address: SocketAddr
+    }
+
+    #[async_trait]
+    impl Imageboard for DvachMockImageboard {
+        fn name(&self) -> &'static str {
+            return "mock";
+        }
+
+        fn matches(&self, site_descriptor: &SiteDescriptor) -> bool {
+            return site_descriptor.site_name_str() == "mock";
+        }
+
+        fn url_matches(&self, _url: &str) -> bool {
+            return false;
+        }
+
+        fn post_url_to_post_descriptor(&self, _post_url: &str) -> Option<PostDescriptor> {
+            return None;
+        }
+
+        fn post_descriptor_to_url(&self, _post_descriptor: &PostDescriptor) -> Option<String> {
+            return None;
+        }
+
+        fn post_quote_regexes(&self) -> &'static [Regex] {
+            return &POST_REPLY_QUOTE_REGEXES;
+        }
+
+        fn post_parser(&self) -> &'static Box<dyn PostParser + Sync> {
+            return &DVACH_POST_PARSER;
+        }
+
+        fn thread_json_endpoint(
+            &self,
+            thread_descriptor: &ThreadDescriptor,
+            last_processed_post: &Option<PostDescriptor>
+        ) -> Option<String> {
+            let last_processed_post = last_processed_post.as_ref()?;
+
+            let endpoint = format!(
+                "http://{}/dvach/after/{}/{}/{}",
+                self.server_address,
+                thread_descriptor.board_code(),
+                thread_descriptor.thread_no,
+                last_processed_post.post_no
+            );
+
+            return Some(endpoint);
+        }
+
+        fn supports_partial_load_head_request(&self) -> bool {
+            return false;
+        }
+
+        fn catalog_json_endpoint(&self, _catalog_descriptor: &CatalogDescriptor) -> Option<String> {
+            return None;
+        }
+
+        fn boards_json_endpoint(&self) -> Option<String> {
+            return None;
+        }
+    }
+}
@@ -0,0 +1 @@
+pub mod base_imageboard_tests;
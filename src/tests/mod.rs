@@ -1,3 +1,5 @@
 pub mod handlers;
+pub mod imageboards;
+pub mod repository;
 pub mod service;
 mod shared;
\ No newline at end of file
@@ -0,0 +1,185 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
+    use crate::model::repository::{account_repository, post_repository};
+    use crate::model::repository::account_repository::{Account, AccountId, ApplicationType, FirebaseToken};
+    use crate::service::thread_watcher;
+    use crate::service::thread_watcher::FoundPostReply;
+    use crate::test_case;
+    use crate::tests::shared::{database_shared, post_reply_repository_shared};
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_retain_only_reply_ids_belonging_to_account),
+            test_case!(should_retain_nothing_when_reply_ids_are_empty),
+            test_case!(should_re_read_account_from_database_after_invalidation),
+            test_case!(should_invalidate_accounts_that_do_not_match_database_when_verifying_cache),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_retain_only_reply_ids_belonging_to_account() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        let account_id1 = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let account_id2 = AccountId::from_user_id("222222222222222222222222222222222222").unwrap();
+        let firebase_token1 = FirebaseToken::from_str("1234567890").unwrap();
+        let firebase_token2 = FirebaseToken::from_str("0987654321").unwrap();
+        let thread_descriptor = ThreadDescriptor::new("test".to_string(), "test".to_string(), 1);
+        let watched_post1 = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0);
+        let watched_post2 = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 2, 0);
+        let reply = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 3, 0);
+        let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+        for (account_id, firebase_token, watched_post) in [
+            (&account_id1, &firebase_token1, &watched_post1),
+            (&account_id2, &firebase_token2, &watched_post2)
+        ] {
+            account_repository::create_account(database, account_id, Some(valid_until)).await.unwrap();
+            account_repository::update_firebase_token(database, account_id, &application_type, firebase_token)
+                .await
+                .unwrap();
+            post_repository::start_watching_post(database, account_id, &application_type, watched_post)
+                .await
+                .unwrap();
+        }
+
+        let mut found_post_replies_set = HashSet::from(
+            [
+                FoundPostReply { origin: reply.clone(), replies_to: watched_post1.clone() },
+                FoundPostReply { origin: reply.clone(), replies_to: watched_post2.clone() }
+            ]
+        );
+
+        thread_watcher::find_and_store_new_post_replies(
+            &thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database,
+        ).await.unwrap();
+
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
+        assert_eq!(2, unsent_replies.len());
+
+        let reply_ids_of = |firebase_token: &FirebaseToken| {
+            return unsent_replies.iter()
+                .find(|(account_token, _)| account_token.token == firebase_token.token)
+                .unwrap()
+                .1
+                .iter()
+                .map(|unsent_reply| unsent_reply.post_reply_id)
+                .collect::<Vec<i64>>();
+        };
+
+        let reply_ids1 = reply_ids_of(&firebase_token1);
+        let reply_ids2 = reply_ids_of(&firebase_token2);
+        assert_eq!(1, reply_ids1.len());
+        assert_eq!(1, reply_ids2.len());
+
+        let all_reply_ids = reply_ids1.iter()
+            .chain(reply_ids2.iter())
+            .cloned()
+            .collect::<Vec<i64>>();
+
+        let retained_reply_ids1 = account_repository::retain_post_db_ids_belonging_to_account(
+            &account_id1,
+            &all_reply_ids,
+            database
+        ).await.unwrap();
+        assert_eq!(reply_ids1, retained_reply_ids1);
+
+        let retained_reply_ids2 = account_repository::retain_post_db_ids_belonging_to_account(
+            &account_id2,
+            &all_reply_ids,
+            database
+        ).await.unwrap();
+        assert_eq!(reply_ids2, retained_reply_ids2);
+    }
+
+    async fn should_retain_nothing_when_reply_ids_are_empty() {
+        let database = database_shared::database();
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+
+        let retained_reply_ids = account_repository::retain_post_db_ids_belonging_to_account(
+            &account_id,
+            &vec![],
+            database
+        ).await.unwrap();
+
+        assert!(retained_reply_ids.is_empty());
+    }
+
+    async fn should_re_read_account_from_database_after_invalidation() {
+        let database = database_shared::database();
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+        let new_valid_until = valid_until + chrono::Duration::days(10);
+
+        account_repository::create_account(database, &account_id, Some(valid_until)).await.unwrap();
+
+        let connection = database.connection().await.unwrap();
+        connection.execute(
+            "UPDATE accounts SET valid_until = $1 WHERE account_id = $2",
+            &[&new_valid_until, &account_id.id]
+        ).await.unwrap();
+
+        // The cached account doesn't know about the change yet
+        let account = account_repository::get_account(&account_id, database).await.unwrap();
+        assert_eq!(valid_until.timestamp(), valid_until_timestamp(account).await);
+
+        account_repository::invalidate(&account_id).await;
+        assert!(account_repository::test_get_account_from_cache(&account_id).await.is_none());
+
+        let account = account_repository::get_account(&account_id, database).await.unwrap();
+        assert_eq!(new_valid_until.timestamp(), valid_until_timestamp(account).await);
+    }
+
+    async fn should_invalidate_accounts_that_do_not_match_database_when_verifying_cache() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+        let account_id1 = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let account_id2 = AccountId::from_user_id("222222222222222222222222222222222222").unwrap();
+        let firebase_token1 = FirebaseToken::from_str("1234567890").unwrap();
+        let firebase_token2 = FirebaseToken::from_str("0987654321").unwrap();
+        let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+        for (account_id, firebase_token) in [(&account_id1, &firebase_token1), (&account_id2, &firebase_token2)] {
+            account_repository::create_account(database, account_id, Some(valid_until)).await.unwrap();
+            account_repository::update_firebase_token(database, account_id, &application_type, firebase_token)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(0, account_repository::verify_accounts_cache(database, 100).await.unwrap());
+
+        let connection = database.connection().await.unwrap();
+        connection.execute("DELETE FROM account_tokens WHERE token = $1", &[&firebase_token2.token])
+            .await
+            .unwrap();
+
+        assert_eq!(1, account_repository::verify_accounts_cache(database, 100).await.unwrap());
+        assert!(account_repository::test_get_account_from_cache(&account_id1).await.is_some());
+        assert!(account_repository::test_get_account_from_cache(&account_id2).await.is_none());
+
+        let account2 = account_repository::get_account(&account_id2, database).await.unwrap().unwrap();
+        assert!(account2.lock().await.tokens.is_empty());
+
+        assert_eq!(0, account_repository::verify_accounts_cache(database, 100).await.unwrap());
+    }
+
+    async fn valid_until_timestamp(account: Option<Arc<Mutex<Account>>>) -> i64 {
+        let account = account.unwrap();
+        let account = account.lock().await;
+
+        return account.valid_until.unwrap().timestamp();
+    }
+}
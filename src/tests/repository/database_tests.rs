@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use anyhow::anyhow;
+
+    use crate::model::database::db;
+    use crate::model::database::db::{ConnectionError, Database};
+    use crate::test_case;
+    use crate::tests::shared::database_shared;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_fail_with_timeout_when_pool_is_exhausted),
+            test_case!(should_retry_transient_connection_errors),
+            test_case!(should_not_retry_permanent_connection_errors),
+            test_case!(should_give_up_after_max_retries),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_fail_with_timeout_when_pool_is_exhausted() {
+        let database = Database::with_pool_config(
+            database_shared::connection_string(),
+            1,
+            1,
+            Some(Duration::from_millis(500))
+        ).await.unwrap();
+
+        let connection = database.connection().await.unwrap();
+
+        let error = database.connection().await.err().unwrap();
+        assert!(error.to_string().contains("Timed out"));
+
+        drop(connection);
+        assert!(database.connection().await.is_ok());
+    }
+
+    async fn should_retry_transient_connection_errors() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+
+        let result = db::retry_with_backoff(3, Duration::from_millis(10), || {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+
+            async move {
+                if attempt <= 2 {
+                    return Err(ConnectionError::Transient(anyhow!("Connection reset")));
+                }
+
+                return Ok(attempt);
+            }
+        }).await;
+
+        assert_eq!(3, result.unwrap());
+        assert_eq!(3, attempts.load(Ordering::SeqCst));
+    }
+
+    async fn should_not_retry_permanent_connection_errors() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+
+        let result = db::retry_with_backoff(3, Duration::from_millis(10), || {
+            attempts.fetch_add(1, Ordering::SeqCst);
+
+            async move {
+                return Err::<(), ConnectionError>(
+                    ConnectionError::Permanent(anyhow!("password authentication failed"))
+                );
+            }
+        }).await;
+
+        assert!(result.is_err());
+        assert_eq!(1, attempts.load(Ordering::SeqCst));
+    }
+
+    async fn should_give_up_after_max_retries() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+
+        let result = db::retry_with_backoff(2, Duration::from_millis(10), || {
+            attempts.fetch_add(1, Ordering::SeqCst);
+
+            async move {
+                return Err::<(), ConnectionError>(ConnectionError::Transient(anyhow!("Connection reset")));
+            }
+        }).await;
+
+        assert!(result.is_err());
+        assert_eq!(3, attempts.load(Ordering::SeqCst));
+    }
+}
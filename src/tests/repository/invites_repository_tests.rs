@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod tests {
+    use crate::model::repository::{account_repository, invites_repository};
+    use crate::model::repository::account_repository::AccountId;
+    use crate::test_case;
+    use crate::tests::shared::database_shared;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_create_account_with_generated_id),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_create_account_with_generated_id() {
+        let database = database_shared::database();
+        let started_at = chrono::offset::Utc::now();
+
+        let user_id = invites_repository::create_account_with_generated_id(database, 30).await.unwrap();
+        let account_id = AccountId::from_user_id(&user_id).unwrap();
+
+        let account = account_repository::get_account_from_database(&account_id, database)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(account_id == account.account_id);
+        assert!(account.tokens.is_empty());
+
+        let valid_until = account.valid_until.unwrap();
+        assert!(valid_until >= started_at + chrono::Duration::days(30));
+        assert!(valid_until <= chrono::offset::Utc::now() + chrono::Duration::days(30));
+
+        // Every call creates a new account
+        let other_user_id = invites_repository::create_account_with_generated_id(database, 1).await.unwrap();
+        assert_ne!(user_id, other_user_id);
+    }
+}
@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use crate::model::repository::logs_repository;
+    use crate::model::repository::logs_repository::NewLogLine;
+    use crate::test_case;
+    use crate::tests::shared::database_shared;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(large_log_message_should_be_compressed_on_write_and_decompressed_on_read),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn large_log_message_should_be_compressed_on_write_and_decompressed_on_read() {
+        let database = database_shared::database();
+        let small_message = "small message".to_string();
+        let large_message = "INSERT INTO logs(log_time, log_level, target, message) ".repeat(100);
+
+        let new_log_lines = vec![small_message.clone(), large_message.clone()]
+            .into_iter()
+            .map(|message| {
+                return NewLogLine {
+                    log_time: Utc::now(),
+                    log_level: "I".to_string(),
+                    target: "kpns::tests".to_string(),
+                    message
+                };
+            })
+            .collect::<Vec<NewLogLine>>();
+
+        logs_repository::set_log_compression_threshold(1024);
+        let result = logs_repository::store_logs(database, &new_log_lines).await;
+        logs_repository::set_log_compression_threshold(0);
+        result.unwrap();
+
+        let connection = database.connection().await.unwrap();
+        let rows = connection.query(
+            "SELECT message, message_compressed, compressed FROM logs ORDER BY id ASC",
+            &[]
+        ).await.unwrap();
+
+        assert_eq!(2, rows.len());
+
+        let message: String = rows[0].get(0);
+        let message_compressed: Option<Vec<u8>> = rows[0].get(1);
+        let compressed: bool = rows[0].get(2);
+
+        assert_eq!(small_message, message);
+        assert!(message_compressed.is_none());
+        assert!(!compressed);
+
+        let message: String = rows[1].get(0);
+        let message_compressed: Option<Vec<u8>> = rows[1].get(1);
+        let compressed: bool = rows[1].get(2);
+
+        assert!(message.is_empty());
+        assert!(message_compressed.unwrap().len() < large_message.len());
+        assert!(compressed);
+
+        let log_lines = logs_repository::get_logs(10, i64::MAX, database).await.unwrap();
+        assert_eq!(2, log_lines.len());
+
+        // Newest first
+        assert_eq!(large_message, log_lines[0].message);
+        assert_eq!(small_message, log_lines[1].message);
+    }
+}
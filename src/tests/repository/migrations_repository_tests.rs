@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::model::database::db::Database;
+    use crate::model::repository::migrations_repository;
+    use crate::test_case;
+    use crate::tests::shared::database_shared;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_rollback_and_reapply_migrations),
+            test_case!(should_not_rollback_when_checksum_does_not_match),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_rollback_and_reapply_migrations() {
+        let database = database_shared::database();
+        reset_schema(database).await;
+
+        assert!(get_applied_versions(database).await.contains(&6));
+        assert!(column_exists(database, "threads", "last_etag").await);
+        assert!(column_exists(database, "threads", "title").await);
+
+        migrations_repository::rollback_to(database, 4).await.unwrap();
+
+        let applied_versions = get_applied_versions(database).await;
+        assert_eq!(vec![1, 2, 3, 4], applied_versions);
+        assert!(!column_exists(database, "threads", "last_etag").await);
+        assert!(!column_exists(database, "threads", "title").await);
+        assert!(column_exists(database, "threads", "died_on").await);
+
+        migrations_repository::perform_migrations(database).await.unwrap();
+
+        assert!(get_applied_versions(database).await.contains(&6));
+        assert!(column_exists(database, "threads", "last_etag").await);
+        assert!(column_exists(database, "threads", "title").await);
+    }
+
+    async fn should_not_rollback_when_checksum_does_not_match() {
+        let database = database_shared::database();
+        reset_schema(database).await;
+        let applied_versions_before = get_applied_versions(database).await;
+
+        {
+            let connection = database.connection().await.unwrap();
+            connection.execute(
+                "UPDATE migrations SET checksum = 'bad' WHERE migrations.version = 6",
+                &[]
+            ).await.unwrap();
+        }
+
+        let result = migrations_repository::rollback_to(database, 4).await;
+        assert!(result.is_err());
+
+        let applied_versions = get_applied_versions(database).await;
+        assert_eq!(applied_versions_before, applied_versions);
+        assert!(column_exists(database, "threads", "last_etag").await);
+
+        reset_schema(database).await;
+    }
+
+    async fn reset_schema(database: &Arc<Database>) {
+        database_shared::drop_all_tables().await;
+        migrations_repository::perform_migrations(database).await.unwrap();
+    }
+
+    async fn get_applied_versions(database: &Arc<Database>) -> Vec<i32> {
+        let connection = database.connection().await.unwrap();
+
+        return connection.query("SELECT version FROM migrations ORDER BY version", &[])
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get::<usize, i32>(0))
+            .collect::<Vec<i32>>();
+    }
+
+    async fn column_exists(database: &Arc<Database>, table_name: &str, column_name: &str) -> bool {
+        let query = r#"
+            SELECT COUNT(*)
+            FROM information_schema.columns
+            WHERE table_name = $1
+              AND column_name = $2
+        "#;
+
+        let connection = database.connection().await.unwrap();
+        let count: i64 = connection.query_one(query, &[&table_name, &column_name])
+            .await
+            .unwrap()
+            .get(0);
+
+        return count > 0;
+    }
+}
@@ -0,0 +1,9 @@
+pub mod account_repository_tests;
+pub mod database_tests;
+pub mod invites_repository_tests;
+pub mod logs_repository_tests;
+pub mod migrations_repository_tests;
+pub mod post_descriptor_id_repository_tests;
+pub mod post_repository_tests;
+pub mod site_repository_tests;
+pub mod thread_repository_tests;
\ No newline at end of file
@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::model::data::chan::PostDescriptor;
+    use crate::model::repository::post_descriptor_id_repository;
+    use crate::test_case;
+    use crate::tests::shared::database_shared;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_insert_many_post_descriptors_with_one_statement),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_insert_many_post_descriptors_with_one_statement() {
+        let database = database_shared::database();
+
+        let post_descriptors = (0..500u64)
+            .map(|index| {
+                return PostDescriptor::new(
+                    String::from("4chan"),
+                    String::from("g"),
+                    1 + (index % 5),
+                    1000 + index,
+                    0
+                );
+            })
+            .collect::<Vec<PostDescriptor>>();
+
+        let post_descriptor_refs = post_descriptors.iter().collect::<Vec<&PostDescriptor>>();
+
+        let db_ids = {
+            let mut connection = database.connection().await.unwrap();
+            let transaction = connection.transaction().await.unwrap();
+
+            let db_ids = post_descriptor_id_repository::insert_descriptor_db_ids(
+                &post_descriptor_refs,
+                &transaction
+            ).await.unwrap();
+
+            transaction.commit().await.unwrap();
+            db_ids
+        };
+
+        assert_eq!(1, post_descriptor_id_repository::test_get_post_descriptor_insert_statements());
+        assert_eq!(500, db_ids.len());
+
+        let query = r#"
+            SELECT
+                pd.id,
+                thread.site_name,
+                thread.board_code,
+                thread.thread_no,
+                pd.post_no,
+                pd.post_sub_no
+            FROM post_descriptors pd
+                INNER JOIN threads thread on thread.id = pd.owner_thread_id
+        "#;
+
+        let connection = database.connection().await.unwrap();
+        let rows = connection.query(query, &[]).await.unwrap();
+        assert_eq!(500, rows.len());
+
+        let mut post_descriptors_from_database = HashMap::<i64, PostDescriptor>::with_capacity(rows.len());
+
+        for row in rows {
+            let id: i64 = row.get(0);
+            let site_name: String = row.get(1);
+            let board_code: String = row.get(2);
+            let thread_no: i64 = row.get(3);
+            let post_no: i64 = row.get(4);
+            let post_sub_no: i64 = row.get(5);
+
+            let post_descriptor = PostDescriptor::new(
+                site_name,
+                board_code,
+                thread_no as u64,
+                post_no as u64,
+                post_sub_no as u64
+            );
+
+            post_descriptors_from_database.insert(id, post_descriptor);
+        }
+
+        for post_descriptor in &post_descriptors {
+            let db_id = *db_ids.get(post_descriptor).unwrap();
+
+            assert_eq!(post_descriptor, post_descriptors_from_database.get(&db_id).unwrap());
+            assert_eq!(
+                Some(db_id),
+                post_descriptor_id_repository::get_post_descriptor_db_id(post_descriptor).await
+            );
+        }
+
+        // Everything is cached now so nothing should be inserted
+        let post_descriptor_refs = post_descriptors.iter().collect::<Vec<&PostDescriptor>>();
+
+        {
+            let mut connection = database.connection().await.unwrap();
+            let transaction = connection.transaction().await.unwrap();
+
+            let cached_db_ids = post_descriptor_id_repository::insert_descriptor_db_ids(
+                &post_descriptor_refs,
+                &transaction
+            ).await.unwrap();
+
+            transaction.commit().await.unwrap();
+            assert_eq!(db_ids, cached_db_ids);
+        }
+
+        // Nothing to insert so no statement was executed
+        assert_eq!(1, post_descriptor_id_repository::test_get_post_descriptor_insert_statements());
+
+        let count_query = "SELECT COUNT(*) FROM post_descriptors";
+        let post_descriptors_count: i64 = connection.query_one(count_query, &[]).await.unwrap().get(0);
+        assert_eq!(500, post_descriptors_count);
+    }
+}
@@ -0,0 +1,204 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::helpers::db_helpers;
+    use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
+    use crate::model::repository::{account_repository, post_descriptor_id_repository, post_repository};
+    use crate::model::repository::account_repository::{AccountId, ApplicationType, FirebaseToken};
+    use crate::test_case;
+    use crate::tests::shared::database_shared;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    // The query find_new_replies() used before it was rewritten to start from post_watches
+    const LEGACY_FIND_NEW_REPLIES_QUERY: &'static str = r#"
+        SELECT
+            post_descriptor.id,
+            account.id,
+            watch.filter_regex
+        FROM threads
+            LEFT JOIN post_descriptors post_descriptor on post_descriptor.owner_thread_id = threads.id
+            LEFT JOIN post_watches watch on watch.owner_post_descriptor_id = post_descriptor.id
+            LEFT JOIN accounts account on watch.owner_account_id = account.id
+            LEFT JOIN post_replies post_reply on post_descriptor.id = post_reply.owner_post_descriptor_id
+        WHERE
+            post_descriptor.id IN ({QUERY_PARAMS})
+        AND
+            post_reply.deleted_on IS NULL
+        AND
+            account.id IS NOT NULL
+    "#;
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(find_new_replies_returns_the_same_watches_as_the_legacy_query),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn find_new_replies_returns_the_same_watches_as_the_legacy_query() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        let thread_descriptor = ThreadDescriptor::new("test".to_string(), "test".to_string(), 1);
+        let other_thread_descriptor = ThreadDescriptor::new("test".to_string(), "test".to_string(), 2);
+        let post = |post_no: u64| PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), post_no, 0);
+
+        let account_id1 = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let account_id2 = AccountId::from_user_id("222222222222222222222222222222222222").unwrap();
+        let account_id3 = AccountId::from_user_id("333333333333333333333333333333333333").unwrap();
+
+        for (index, account_id) in [&account_id1, &account_id2, &account_id3].iter().enumerate() {
+            let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+            let firebase_token = FirebaseToken::from_str(&format!("token{}", index)).unwrap();
+
+            account_repository::create_account(database, account_id, Some(valid_until)).await.unwrap();
+            account_repository::update_firebase_token(database, account_id, &application_type, &firebase_token)
+                .await
+                .unwrap();
+        }
+
+        // account3 doesn't watch anything in this thread
+        let watches = vec![
+            (&account_id1, post(1), None),
+            (&account_id1, post(2), None),
+            (&account_id1, post(3), None),
+            (&account_id1, post(4), None),
+            (&account_id2, post(1), None),
+            (&account_id2, post(3), None),
+            (&account_id2, post(5), Some("filter")),
+            (&account_id3, PostDescriptor::from_thread_descriptor(other_thread_descriptor.clone(), 1, 0), None),
+        ];
+
+        for (account_id, post_descriptor, filter_regex) in &watches {
+            post_repository::start_watching_post_with_filter(
+                database,
+                account_id,
+                &application_type,
+                post_descriptor,
+                *filter_regex
+            ).await.unwrap();
+        }
+
+        // post 1: no replies
+        // post 2: one reply
+        // post 3: only deleted replies
+        // post 4: one deleted and one not deleted reply
+        // post 5: two replies
+        // post 6: not watched
+        let replies = vec![
+            (&account_id1, post(2), post(10), false),
+            (&account_id1, post(3), post(11), true),
+            (&account_id2, post(3), post(11), true),
+            (&account_id1, post(4), post(12), true),
+            (&account_id1, post(4), post(13), false),
+            (&account_id2, post(5), post(14), false),
+            (&account_id2, post(5), post(15), false),
+        ];
+
+        for (account_id, owner_post_descriptor, reply_to_post_descriptor, deleted) in &replies {
+            insert_post_reply(account_id, owner_post_descriptor, reply_to_post_descriptor, *deleted).await;
+        }
+
+        let mut post_descriptor_db_ids = Vec::<i64>::new();
+        for post_no in 1..=6 {
+            post_descriptor_db_ids.push(insert_post_descriptor(&post(post_no)).await);
+        }
+
+        let actual = post_repository::find_new_replies(&thread_descriptor, database, &post_descriptor_db_ids)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|post_reply| {
+                return (post_reply.owner_post_descriptor_id, post_reply.owner_account_id, post_reply.filter_regex);
+            })
+            .collect::<Vec<(i64, i64, Option<String>)>>();
+
+        // The legacy query returned a row per reply, the new one returns a row per watch
+        let actual_set = actual.iter().cloned().collect::<HashSet<(i64, i64, Option<String>)>>();
+        assert_eq!(actual.len(), actual_set.len());
+        assert_eq!(run_legacy_query(&post_descriptor_db_ids).await, actual_set);
+
+        let account1_db_id = account_db_id(&account_id1).await;
+        let account2_db_id = account_db_id(&account_id2).await;
+
+        let expected = HashSet::from([
+            (post_descriptor_db_ids[0], account1_db_id, None),
+            (post_descriptor_db_ids[0], account2_db_id, None),
+            (post_descriptor_db_ids[1], account1_db_id, None),
+            (post_descriptor_db_ids[3], account1_db_id, None),
+            (post_descriptor_db_ids[4], account2_db_id, Some("filter".to_string())),
+        ]);
+
+        assert_eq!(expected, actual_set);
+    }
+
+    async fn run_legacy_query(post_descriptor_db_ids: &Vec<i64>) -> HashSet<(i64, i64, Option<String>)> {
+        let (query, query_params) = db_helpers::format_query_params(
+            LEGACY_FIND_NEW_REPLIES_QUERY,
+            "{QUERY_PARAMS}",
+            post_descriptor_db_ids
+        ).unwrap();
+
+        let connection = database_shared::database().connection().await.unwrap();
+
+        return connection.query(query.as_str(), &query_params[..])
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| (row.get::<usize, i64>(0), row.get::<usize, i64>(1), row.get::<usize, Option<String>>(2)))
+            .collect::<HashSet<(i64, i64, Option<String>)>>();
+    }
+
+    async fn insert_post_descriptor(post_descriptor: &PostDescriptor) -> i64 {
+        let mut connection = database_shared::database().connection().await.unwrap();
+        let transaction = connection.transaction().await.unwrap();
+
+        let post_descriptor_db_id = post_descriptor_id_repository::insert_post_descriptor_db_id(
+            post_descriptor,
+            &transaction
+        ).await.unwrap();
+
+        transaction.commit().await.unwrap();
+        return post_descriptor_db_id;
+    }
+
+    async fn insert_post_reply(
+        account_id: &AccountId,
+        owner_post_descriptor: &PostDescriptor,
+        reply_to_post_descriptor: &PostDescriptor,
+        deleted: bool
+    ) {
+        let owner_account_id = account_db_id(account_id).await;
+        let owner_post_descriptor_id = insert_post_descriptor(owner_post_descriptor).await;
+        let reply_to_post_descriptor_id = insert_post_descriptor(reply_to_post_descriptor).await;
+
+        let query = r#"
+            INSERT INTO post_replies(
+                owner_account_id,
+                owner_post_descriptor_id,
+                reply_to_post_descriptor_id,
+                deleted_on
+            )
+            VALUES ($1, $2, $3, CASE WHEN $4 THEN now() ELSE NULL END)
+        "#;
+
+        let connection = database_shared::database().connection().await.unwrap();
+        connection.execute(
+            query,
+            &[&owner_account_id, &owner_post_descriptor_id, &reply_to_post_descriptor_id, &deleted]
+        ).await.unwrap();
+    }
+
+    async fn account_db_id(account_id: &AccountId) -> i64 {
+        let account = account_repository::get_account(account_id, database_shared::database())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let account_db_id = account.lock().await.id;
+        return account_db_id;
+    }
+}
@@ -0,0 +1,263 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use lazy_static::lazy_static;
+    use regex::Regex;
+
+    use crate::model::data::chan::{CatalogDescriptor, ChanBoard, PostDescriptor, SiteDescriptor, ThreadDescriptor};
+    use crate::model::imageboards::base_imageboard::Imageboard;
+    use crate::model::imageboards::parser::chan4_post_parser::Chan4PostParser;
+    use crate::model::imageboards::parser::post_parser::PostParser;
+    use crate::model::repository::site_repository::{ImageboardSynced, SiteRepository};
+    use crate::test_case;
+    use crate::tests::shared::site_repository_shared;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    lazy_static! {
+        static ref POST_REPLY_QUOTE_REGEXES: Vec<Regex> = vec![
+            Regex::new(r#"class="quotelink">&gt;&gt;(\d+)</a>"#).unwrap()
+        ];
+        static ref POST_PARSER: Box<dyn PostParser + Sync> = Box::new(Chan4PostParser {});
+    }
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_fetch_threads_of_same_site_sequentially_with_one_permit),
+            test_case!(should_not_limit_other_sites),
+            test_case!(should_return_cached_boards_until_ttl_expires),
+            test_case!(should_return_last_cached_boards_when_fetch_fails),
+            test_case!(should_reject_urls_of_disabled_sites),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_fetch_threads_of_same_site_sequentially_with_one_permit() {
+        let site_repository = site_repository_with_limits(&[("2ch", 1)]);
+        let site_descriptor = SiteDescriptor::from_str("2ch");
+
+        let max_concurrent_fetches = run_fetches(&site_repository, &[&site_descriptor, &site_descriptor]).await;
+        assert_eq!(1, max_concurrent_fetches);
+    }
+
+    async fn should_not_limit_other_sites() {
+        let site_repository = site_repository_with_limits(&[("2ch", 1)]);
+        let dvach = SiteDescriptor::from_str("2ch");
+        let chan4 = SiteDescriptor::from_str("4chan");
+
+        let max_concurrent_fetches = run_fetches(&site_repository, &[&dvach, &chan4]).await;
+        assert_eq!(2, max_concurrent_fetches);
+    }
+
+    async fn should_return_cached_boards_until_ttl_expires() {
+        let mock_imageboard = Arc::new(MockImageboard::new());
+        let site_repository = site_repository_with_mock_imageboard(&mock_imageboard, Duration::from_millis(200));
+
+        let boards = site_repository.get_boards().await;
+        assert_eq!(1, boards.len());
+        assert_eq!("mock", boards[0].0);
+        assert_eq!("Boards #1", boards[0].1[0].title);
+
+        let boards = site_repository.get_boards().await;
+        assert_eq!("Boards #1", boards[0].1[0].title);
+        assert_eq!(1, mock_imageboard.fetch_count.load(Ordering::SeqCst));
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let boards = site_repository.get_boards().await;
+        assert_eq!("Boards #2", boards[0].1[0].title);
+        assert_eq!(2, mock_imageboard.fetch_count.load(Ordering::SeqCst));
+    }
+
+    async fn should_return_last_cached_boards_when_fetch_fails() {
+        let mock_imageboard = Arc::new(MockImageboard::new());
+        mock_imageboard.should_fail.store(true, Ordering::SeqCst);
+
+        // Zero ttl means the boards are fetched every time
+        let site_repository = site_repository_with_mock_imageboard(&mock_imageboard, Duration::ZERO);
+
+        let boards = site_repository.get_boards().await;
+        assert_eq!(1, boards.len());
+        assert!(boards[0].1.is_empty());
+
+        mock_imageboard.should_fail.store(false, Ordering::SeqCst);
+        let boards = site_repository.get_boards().await;
+        assert_eq!("Boards #2", boards[0].1[0].title);
+
+        mock_imageboard.should_fail.store(true, Ordering::SeqCst);
+        let boards = site_repository.get_boards().await;
+        assert_eq!("Boards #2", boards[0].1[0].title);
+        assert_eq!(3, mock_imageboard.fetch_count.load(Ordering::SeqCst));
+    }
+
+    async fn should_reject_urls_of_disabled_sites() {
+        let site_repository = SiteRepository::with_enabled_sites(
+            http_client(),
+            &HashMap::new(),
+            &vec!["4chan".to_string()]
+        ).unwrap();
+
+        assert_eq!(vec!["4chan".to_string()], site_repository.supported_site_names());
+        assert!(site_repository.by_url("https://boards.4channel.org/vg/thread/426895061#p426901491").is_some());
+        assert!(site_repository.by_url("https://2ch.hk/test/res/197273.html#197871").is_none());
+        assert!(site_repository.by_site_descriptor(&SiteDescriptor::from_str("2ch")).is_none());
+
+        assert!(site_repository.is_site_disabled(&SiteDescriptor::from_str("2ch")));
+        assert!(!site_repository.is_site_disabled(&SiteDescriptor::from_str("4chan")));
+        assert!(!site_repository.is_site_disabled(&SiteDescriptor::from_str("unknown")));
+
+        let site_repository = SiteRepository::with_enabled_sites(http_client(), &HashMap::new(), &vec![]).unwrap();
+        assert_eq!(vec!["2ch".to_string(), "4chan".to_string()], site_repository.supported_site_names());
+        assert!(site_repository.by_url("https://2ch.hk/test/res/197273.html#197871").is_some());
+
+        let result = SiteRepository::with_enabled_sites(http_client(), &HashMap::new(), &vec!["4chn".to_string()]);
+        assert_eq!("Unknown site '4chn' in enabled sites", result.err().unwrap().to_string());
+    }
+
+    fn site_repository_with_mock_imageboard(
+        mock_imageboard: &Arc<MockImageboard>,
+        boards_cache_ttl: Duration
+    ) -> Arc<SiteRepository> {
+        let imageboards: Vec<ImageboardSynced> = vec![mock_imageboard.clone()];
+
+        return Arc::new(SiteRepository::with_imageboards(
+            http_client(),
+            imageboards,
+            &HashMap::new(),
+            boards_cache_ttl
+        ));
+    }
+
+    fn site_repository_with_limits(limits: &[(&str, usize)]) -> Arc<SiteRepository> {
+        let site_concurrency_limits = limits.iter()
+            .map(|(site_name, permits)| (site_name.to_string(), *permits))
+            .collect::<HashMap<String, usize>>();
+
+        return Arc::new(SiteRepository::with_concurrency_limits(http_client(), &site_concurrency_limits));
+    }
+
+    fn http_client() -> &'static Arc<reqwest::Client> {
+        return site_repository_shared::site_repository().http_client();
+    }
+
+    /// Simulates fetching a thread for every site descriptor concurrently and returns the max
+    /// amount of fetches that were in flight at the same time.
+    async fn run_fetches(
+        site_repository: &Arc<SiteRepository>,
+        site_descriptors: &[&SiteDescriptor]
+    ) -> usize {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let mut join_handles = Vec::new();
+
+        for site_descriptor in site_descriptors {
+            let site_descriptor = (*site_descriptor).clone();
+            let site_repository = site_repository.clone();
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+
+            let join_handle = tokio::task::spawn(async move {
+                let site_permit = site_repository.acquire_site_permit(&site_descriptor).await;
+                assert!(site_permit.is_some());
+
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+
+                tokio::time::sleep(Duration::from_millis(100)).await;
+
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                drop(site_permit);
+            });
+
+            join_handles.push(join_handle);
+        }
+
+        for join_handle in join_handles {
+            join_handle.await.unwrap();
+        }
+
+        return max_in_flight.load(Ordering::SeqCst);
+    }
+
+    struct MockImageboard {
+        fetch_count: AtomicUsize,
+        should_fail: AtomicBool
+    }
+
+    impl MockImageboard {
+        fn new() -> MockImageboard {
+            return MockImageboard { fetch_count: AtomicUsize::new(0), should_fail: AtomicBool::new(false) };
+        }
+    }
+
+    #[async_trait]
+    impl Imageboard for MockImageboard {
+        fn name(&self) -> &'static str {
+            return "mock";
+        }
+
+        fn matches(&self, site_descriptor: &SiteDescriptor) -> bool {
+            return site_descriptor.site_name_str() == "mock";
+        }
+
+        fn url_matches(&self, _url: &str) -> bool {
+            return false;
+        }
+
+        fn post_url_to_post_descriptor(&self, _post_url: &str) -> Option<PostDescriptor> {
+            return None;
+        }
+
+        fn post_descriptor_to_url(&self, _post_descriptor: &PostDescriptor) -> Option<String> {
+            return None;
+        }
+
+        fn post_quote_regexes(&self) -> &'static [Regex] {
+            return &POST_REPLY_QUOTE_REGEXES;
+        }
+
+        fn post_parser(&self) -> &'static Box<dyn PostParser + Sync> {
+            return &POST_PARSER;
+        }
+
+        fn thread_json_endpoint(
+            &self,
+            _thread_descriptor: &ThreadDescriptor,
+            _last_processed_post: &Option<PostDescriptor>
+        ) -> Option<String> {
+            return None;
+        }
+
+        fn supports_partial_load_head_request(&self) -> bool {
+            return false;
+        }
+
+        fn catalog_json_endpoint(&self, _catalog_descriptor: &CatalogDescriptor) -> Option<String> {
+            return None;
+        }
+
+        fn boards_json_endpoint(&self) -> Option<String> {
+            return None;
+        }
+
+        async fn fetch_boards(&self, _http_client: &reqwest::Client) -> anyhow::Result<Vec<ChanBoard>> {
+            let fetch_count = self.fetch_count.fetch_add(1, Ordering::SeqCst) + 1;
+            if self.should_fail.load(Ordering::SeqCst) {
+                return Err(anyhow::anyhow!("Failed to fetch boards"));
+            }
+
+            let board = ChanBoard {
+                board_code: "a".to_string(),
+                title: format!("Boards #{}", fetch_count)
+            };
+
+            return Ok(vec![board]);
+        }
+    }
+}
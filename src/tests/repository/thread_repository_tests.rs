@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod tests {
+    use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
+    use crate::model::repository::thread_repository;
+    use crate::test_case;
+    use crate::tests::shared::database_shared;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(last_processed_post_should_only_move_forward),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn last_processed_post_should_only_move_forward() {
+        let database = database_shared::database();
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+
+        let post_descriptor = |post_no: u64, post_sub_no: u64| {
+            return PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), post_no, post_sub_no);
+        };
+
+        thread_repository::store_last_processed_post(&post_descriptor(10, 0), database).await.unwrap();
+        assert_eq!(
+            Some(post_descriptor(10, 0)),
+            thread_repository::get_last_processed_post(&thread_descriptor, database).await.unwrap()
+        );
+
+        // A stale pass must not lower the stored value
+        thread_repository::store_last_processed_post(&post_descriptor(5, 0), database).await.unwrap();
+        assert_eq!(
+            Some(post_descriptor(10, 0)),
+            thread_repository::get_last_processed_post(&thread_descriptor, database).await.unwrap()
+        );
+
+        thread_repository::store_last_processed_post(&post_descriptor(10, 2), database).await.unwrap();
+        assert_eq!(
+            Some(post_descriptor(10, 2)),
+            thread_repository::get_last_processed_post(&thread_descriptor, database).await.unwrap()
+        );
+
+        thread_repository::store_last_processed_post(&post_descriptor(10, 1), database).await.unwrap();
+        assert_eq!(
+            Some(post_descriptor(10, 2)),
+            thread_repository::get_last_processed_post(&thread_descriptor, database).await.unwrap()
+        );
+
+        thread_repository::store_last_processed_post(&post_descriptor(11, 0), database).await.unwrap();
+        assert_eq!(
+            Some(post_descriptor(11, 0)),
+            thread_repository::get_last_processed_post(&thread_descriptor, database).await.unwrap()
+        );
+    }
+}
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests {
+    use crate::model::data::chan::{CatalogDescriptor, CatalogThread};
+    use crate::model::repository::{account_repository, catalog_watch_repository};
+    use crate::model::repository::account_repository::{AccountId, ApplicationType, FirebaseToken};
+    use crate::model::repository::catalog_watch_repository::StartWatchingCatalogResult;
+    use crate::service::catalog_watcher;
+    use crate::test_case;
+    use crate::tests::shared::database_shared;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_store_notification_when_catalog_gains_matching_thread),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_store_notification_when_catalog_gains_matching_thread() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+        let catalog_descriptor = CatalogDescriptor::new("4chan".to_string(), "vg".to_string());
+        let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+        account_repository::create_account(database, &account_id, Some(valid_until)).await.unwrap();
+        account_repository::update_firebase_token(database, &account_id, &application_type, &firebase_token)
+            .await
+            .unwrap();
+
+        let result = catalog_watch_repository::start_watching_catalog(
+            database,
+            &account_id,
+            &application_type,
+            &catalog_descriptor,
+            "general"
+        ).await.unwrap();
+        assert_eq!(StartWatchingCatalogResult::Ok, result);
+
+        let initial_catalog = vec![
+            catalog_thread(100, Some("/vg/ general"), None),
+            catalog_thread(101, None, Some("Some thread")),
+        ];
+
+        // The first pass only remembers the newest thread, existing threads are not reported
+        let new_threads = catalog_watcher::process_catalog(&catalog_descriptor, &initial_catalog, database)
+            .await
+            .unwrap();
+        assert_eq!(0, new_threads);
+
+        let unsent_notifications = catalog_watch_repository::get_unsent_catalog_notifications(database)
+            .await
+            .unwrap();
+        assert!(unsent_notifications.is_empty());
+
+        let updated_catalog = vec![
+            catalog_thread(100, Some("/vg/ general"), None),
+            catalog_thread(101, None, Some("Some thread")),
+            catalog_thread(102, Some("/agdg/ - Amateur Game Dev General"), None),
+            catalog_thread(103, Some("Other thread"), Some("Nothing to see here")),
+        ];
+
+        let new_threads = catalog_watcher::process_catalog(&catalog_descriptor, &updated_catalog, database)
+            .await
+            .unwrap();
+        assert_eq!(1, new_threads);
+
+        let unsent_notifications = catalog_watch_repository::get_unsent_catalog_notifications(database)
+            .await
+            .unwrap();
+        assert_eq!(1, unsent_notifications.len());
+
+        let unsent_notifications_for_token = unsent_notifications.values().next().unwrap();
+        assert_eq!(1, unsent_notifications_for_token.len());
+
+        let unsent_notification = unsent_notifications_for_token.first().unwrap();
+        assert_eq!(catalog_descriptor, unsent_notification.catalog_descriptor);
+        assert_eq!(102, unsent_notification.thread_no);
+        assert_eq!(
+            Some("/agdg/ - Amateur Game Dev General".to_string()),
+            unsent_notification.subject
+        );
+
+        // Processing the same catalog again must not produce duplicate notifications
+        let new_threads = catalog_watcher::process_catalog(&catalog_descriptor, &updated_catalog, database)
+            .await
+            .unwrap();
+        assert_eq!(0, new_threads);
+
+        catalog_watch_repository::mark_catalog_notifications_as_delivered(
+            &vec![unsent_notification.notification_id],
+            database
+        ).await.unwrap();
+
+        let unsent_notifications = catalog_watch_repository::get_unsent_catalog_notifications(database)
+            .await
+            .unwrap();
+        assert!(unsent_notifications.is_empty());
+    }
+
+    fn catalog_thread(thread_no: u64, subject: Option<&str>, comment: Option<&str>) -> CatalogThread {
+        return CatalogThread {
+            thread_no,
+            subject: subject.map(|subject| subject.to_string()),
+            comment: comment.map(|comment| comment.to_string())
+        };
+    }
+}
@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
+    use crate::model::database::db::Database;
+    use crate::model::repository::{account_repository, post_descriptor_id_repository, post_repository};
+    use crate::model::repository::account_repository::{AccountId, ApplicationType, FirebaseToken};
+    use crate::test_case;
+    use crate::tests::shared::database_shared;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_purge_dead_thread_without_watches),
+            test_case!(should_not_purge_recently_dead_thread),
+            test_case!(should_not_purge_dead_thread_with_live_watches),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_purge_dead_thread_without_watches() {
+        let database = database_shared::database();
+        let thread_descriptor = ThreadDescriptor::new("test".to_string(), "test".to_string(), 1);
+        let post_descriptors = insert_thread_posts(&thread_descriptor, database).await;
+
+        post_repository::mark_thread_as_dead(database, &thread_descriptor, false).await.unwrap();
+        age_dead_threads(10, database).await;
+
+        let purged = post_repository::purge_dead_threads(database, 7).await.unwrap();
+        assert_eq!(1, purged);
+
+        assert!(post_descriptor_id_repository::get_thread_db_id(&thread_descriptor).await.is_none());
+        assert!(post_descriptor_id_repository::get_thread_post_descriptors(&thread_descriptor).await.is_empty());
+
+        for post_descriptor in &post_descriptors {
+            assert!(post_descriptor_id_repository::get_post_descriptor_db_id(post_descriptor).await.is_none());
+        }
+
+        assert_eq!(0, count_rows("threads", database).await);
+        assert_eq!(0, count_rows("post_descriptors", database).await);
+        assert_eq!(0, count_rows("post_replies", database).await);
+    }
+
+    async fn should_not_purge_recently_dead_thread() {
+        let database = database_shared::database();
+        let thread_descriptor = ThreadDescriptor::new("test".to_string(), "test".to_string(), 1);
+        let post_descriptors = insert_thread_posts(&thread_descriptor, database).await;
+
+        post_repository::mark_thread_as_dead(database, &thread_descriptor, false).await.unwrap();
+        age_dead_threads(3, database).await;
+
+        let purged = post_repository::purge_dead_threads(database, 7).await.unwrap();
+        assert_eq!(0, purged);
+
+        assert!(post_descriptor_id_repository::get_thread_db_id(&thread_descriptor).await.is_some());
+        assert_eq!(
+            post_descriptors.len(),
+            post_descriptor_id_repository::get_thread_post_descriptors(&thread_descriptor).await.len()
+        );
+        assert_eq!(1, count_rows("threads", database).await);
+        assert_eq!(post_descriptors.len() as i64, count_rows("post_descriptors", database).await);
+    }
+
+    async fn should_not_purge_dead_thread_with_live_watches() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+        let thread_descriptor = ThreadDescriptor::new("test".to_string(), "test".to_string(), 1);
+        let watched_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0);
+
+        let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+        account_repository::create_account(
+            database,
+            &account_id,
+            Some(valid_until)
+        ).await.unwrap();
+
+        account_repository::update_firebase_token(
+            database,
+            &account_id,
+            &application_type,
+            &firebase_token
+        ).await.unwrap();
+
+        post_repository::start_watching_post(
+            database,
+            &account_id,
+            &application_type,
+            &watched_post
+        ).await.unwrap();
+
+        post_repository::mark_thread_as_dead(database, &thread_descriptor, false).await.unwrap();
+        age_dead_threads(10, database).await;
+
+        let purged = post_repository::purge_dead_threads(database, 7).await.unwrap();
+        assert_eq!(0, purged);
+
+        assert!(post_descriptor_id_repository::get_post_descriptor_db_id(&watched_post).await.is_some());
+        assert_eq!(1, count_rows("threads", database).await);
+        assert_eq!(1, count_rows("post_watches", database).await);
+    }
+
+    async fn insert_thread_posts(
+        thread_descriptor: &ThreadDescriptor,
+        database: &Arc<Database>
+    ) -> Vec<PostDescriptor> {
+        let post_descriptors = (1..=10u64)
+            .map(|post_no| PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), post_no, 0))
+            .collect::<Vec<PostDescriptor>>();
+
+        let mut connection = database.connection().await.unwrap();
+        let transaction = connection.transaction().await.unwrap();
+
+        post_descriptor_id_repository::insert_descriptor_db_ids(
+            &post_descriptors.iter().collect::<Vec<&PostDescriptor>>(),
+            &transaction
+        ).await.unwrap();
+
+        transaction.commit().await.unwrap();
+        return post_descriptors;
+    }
+
+    async fn age_dead_threads(days: i32, database: &Arc<Database>) {
+        let query = r#"
+            UPDATE threads
+            SET died_on = now() - make_interval(days => $1)
+            WHERE threads.is_dead = TRUE
+        "#;
+
+        let connection = database.connection().await.unwrap();
+        connection.execute(query, &[&days]).await.unwrap();
+    }
+
+    async fn count_rows(table_name: &str, database: &Arc<Database>) -> i64 {
+        let query = format!("SELECT COUNT(*) FROM {}", table_name);
+
+        let connection = database.connection().await.unwrap();
+        return connection.query_one(query.as_str(), &[]).await.unwrap().get(0);
+    }
+}
@@ -0,0 +1,831 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    use chrono::Timelike;
+    use http_body_util::Full;
+    use hyper::{HeaderMap, Request, Response};
+    use hyper::body::{Bytes, Incoming};
+    use hyper::server::conn::http1;
+    use hyper::service::service_fn;
+    use jwt_simple::algorithms::{ECDSAP256PublicKeyLike, ES256PublicKey};
+    use jwt_simple::claims::NoCustomClaims;
+    use jwt_simple::common::VerificationOptions;
+    use jwt_simple::reexports::ct_codecs::{Base64UrlSafeNoPadding, Decoder, Encoder};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    use crate::handlers::shared::collect_limited;
+    use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
+    use crate::model::repository::{account_repository, post_reply_repository, post_repository};
+    use crate::model::repository::account_repository::{AccountId, ApplicationType, FirebaseToken, QuietHours, WebPushSubscription};
+    use crate::service::{fcm_sender, thread_watcher};
+    use crate::service::fcm_sender::{FcmSendAttemptResult, FcmSender, FcmWatchConfirmedMessage, NewFcmRepliesMessage};
+    use crate::service::fcm_transport::FcmMessagePriority;
+    use crate::service::thread_watcher::FoundPostReply;
+    use crate::service::web_push_transport::{VapidKeys, WebPushTransport};
+    use crate::test_case;
+    use crate::tests::shared::{database_shared, post_reply_repository_shared, site_repository_shared};
+    use crate::tests::shared::fcm_transport_shared::InMemoryFcmTransport;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_retry_transient_errors_until_sent),
+            test_case!(should_not_retry_permanent_errors),
+            test_case!(should_give_up_after_max_attempts),
+            test_case!(should_increment_delivery_attempt_of_failed_replies_until_cap),
+            test_case!(should_mark_sent_replies_as_delivered),
+            test_case!(should_merge_replies_for_same_token_across_application_types),
+            test_case!(should_use_same_group_key_only_for_replies_from_same_thread),
+            test_case!(should_include_reply_url_and_watched_post_url),
+            test_case!(should_isolate_kuroba_ex_replies_from_kuroba_ex_lite_tokens),
+            test_case!(should_claim_every_unsent_reply_only_once_across_concurrent_senders),
+            test_case!(should_make_released_claimed_replies_available_again),
+            test_case!(should_send_unsent_replies_through_transport_and_mark_them_delivered),
+            test_case!(should_include_payload_version_and_server_time_in_replies_payload),
+            test_case!(should_coalesce_replies_above_per_thread_cap_into_summary),
+            test_case!(should_defer_replies_of_accounts_in_quiet_hours),
+            test_case!(should_send_low_priority_confirmation_only_for_new_watches),
+            test_case!(should_send_encrypted_web_push_request_to_web_push_token),
+        ];
+
+        run_test(tests).await;
+    }
+
+    const TEST_VAPID_PRIVATE_KEY: &'static str = "5rHxvv6ofs25Kh8HVJm-X2hLzcszOK8uL61T90CNlxY";
+
+    const TEST_VAPID_SUBJECT: &'static str = "mailto:admin@example.com";
+
+    struct RecordedWebPushRequest {
+        path: String,
+        headers: HeaderMap,
+        body: Bytes
+    }
+
+    async fn should_retry_transient_errors_until_sent() {
+        let attempts = AtomicU32::new(0);
+        let attempts_ref = &attempts;
+
+        let result = fcm_sender::send_with_retries(3, Duration::from_millis(1), move || async move {
+            let attempt = attempts_ref.fetch_add(1, Ordering::SeqCst) + 1;
+            if attempt <= 2 {
+                return FcmSendAttemptResult::TransientError(String::from("Unavailable"));
+            }
+
+            return FcmSendAttemptResult::Sent;
+        }).await;
+
+        assert_eq!(FcmSendAttemptResult::Sent, result);
+        assert_eq!(3, attempts.load(Ordering::SeqCst));
+    }
+
+    async fn should_not_retry_permanent_errors() {
+        let attempts = AtomicU32::new(0);
+        let attempts_ref = &attempts;
+
+        let result = fcm_sender::send_with_retries(3, Duration::from_millis(1), move || async move {
+            attempts_ref.fetch_add(1, Ordering::SeqCst);
+            return FcmSendAttemptResult::PermanentError(String::from("NotRegistered"));
+        }).await;
+
+        assert_eq!(FcmSendAttemptResult::PermanentError(String::from("NotRegistered")), result);
+        assert_eq!(1, attempts.load(Ordering::SeqCst));
+    }
+
+    async fn should_give_up_after_max_attempts() {
+        let attempts = AtomicU32::new(0);
+        let attempts_ref = &attempts;
+
+        let result = fcm_sender::send_with_retries(3, Duration::from_millis(1), move || async move {
+            attempts_ref.fetch_add(1, Ordering::SeqCst);
+            return FcmSendAttemptResult::TransientError(String::from("InternalServerError"));
+        }).await;
+
+        assert_eq!(FcmSendAttemptResult::TransientError(String::from("InternalServerError")), result);
+        assert_eq!(3, attempts.load(Ordering::SeqCst));
+    }
+
+    async fn should_increment_delivery_attempt_of_failed_replies_until_cap() {
+        let database = database_shared::database();
+        let post_reply_id = create_unsent_reply().await;
+
+        for _ in 0..post_reply_repository::MAX_NOTIFICATION_DELIVERY_ATTEMPTS {
+            let claimed_replies = post_reply_repository::claim_unsent_replies(true, database).await.unwrap();
+            assert_eq!(1, claimed_replies.len());
+
+            fcm_sender::update_claimed_delivery_state(&vec![], &vec![post_reply_id], database).await.unwrap();
+        }
+
+        let claimed_replies = post_reply_repository::claim_unsent_replies(true, database).await.unwrap();
+        assert!(claimed_replies.is_empty());
+    }
+
+    async fn should_mark_sent_replies_as_delivered() {
+        let database = database_shared::database();
+        let post_reply_id = create_unsent_reply().await;
+
+        let claimed_replies = post_reply_repository::claim_unsent_replies(true, database).await.unwrap();
+        assert_eq!(1, claimed_replies.len());
+
+        fcm_sender::update_claimed_delivery_state(&vec![post_reply_id], &vec![], database).await.unwrap();
+
+        let claimed_replies = post_reply_repository::claim_unsent_replies(true, database).await.unwrap();
+        assert!(claimed_replies.is_empty());
+    }
+
+    async fn should_merge_replies_for_same_token_across_application_types() {
+        let database = database_shared::database();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+        let thread_descriptor = ThreadDescriptor::new("test".to_string(), "test".to_string(), 1);
+        let watched_post_debug = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0);
+        let watched_post_production = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 3, 0);
+        let reply = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 4, 0);
+        let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+        account_repository::create_account(database, &account_id, Some(valid_until)).await.unwrap();
+
+        for (application_type, watched_post) in [
+            (ApplicationType::KurobaExLiteDebug, &watched_post_debug),
+            (ApplicationType::KurobaExLiteProduction, &watched_post_production)
+        ] {
+            account_repository::update_firebase_token(database, &account_id, &application_type, &firebase_token)
+                .await
+                .unwrap();
+            post_repository::start_watching_post(database, &account_id, &application_type, watched_post)
+                .await
+                .unwrap();
+        }
+
+        let mut found_post_replies_set = HashSet::from(
+            [
+                FoundPostReply { origin: reply.clone(), replies_to: watched_post_debug.clone() },
+                FoundPostReply { origin: reply.clone(), replies_to: watched_post_production.clone() }
+            ]
+        );
+
+        thread_watcher::find_and_store_new_post_replies(
+            &thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database,
+        ).await.unwrap();
+
+        let claimed_replies = post_reply_repository::claim_unsent_replies(true, database).await.unwrap();
+        assert_eq!(2, claimed_replies.len());
+
+        // Both application types share the same token so only one FCM message must be sent
+        let merged_unsent_replies = fcm_sender::merge_unsent_replies_by_token(claimed_replies);
+        assert_eq!(1, merged_unsent_replies.len());
+
+        let (account_token, merged_replies) = merged_unsent_replies.iter().next().unwrap();
+        assert_eq!(firebase_token.token, account_token.token);
+        assert_eq!(2, merged_replies.len());
+
+        let post_reply_ids = merged_replies.iter()
+            .map(|unsent_reply| unsent_reply.post_reply_id)
+            .collect::<Vec<i64>>();
+
+        fcm_sender::update_claimed_delivery_state(&post_reply_ids, &vec![], database).await.unwrap();
+
+        let claimed_replies = post_reply_repository::claim_unsent_replies(true, database).await.unwrap();
+        assert!(claimed_replies.is_empty());
+    }
+
+    async fn should_use_same_group_key_only_for_replies_from_same_thread() {
+        let thread_descriptor1 = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+        let thread_descriptor2 = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 2);
+        let thread_descriptor3 = ThreadDescriptor::new("2ch".to_string(), "g".to_string(), 1);
+
+        let reply1 = PostDescriptor::from_thread_descriptor(thread_descriptor1.clone(), 10, 0);
+        let reply2 = PostDescriptor::from_thread_descriptor(thread_descriptor1.clone(), 11, 0);
+        let reply3 = PostDescriptor::from_thread_descriptor(thread_descriptor2.clone(), 12, 0);
+        let reply4 = PostDescriptor::from_thread_descriptor(thread_descriptor3.clone(), 10, 0);
+
+        let group_key1 = fcm_sender::thread_group_key(&reply1.thread_descriptor);
+        let group_key2 = fcm_sender::thread_group_key(&reply2.thread_descriptor);
+        let group_key3 = fcm_sender::thread_group_key(&reply3.thread_descriptor);
+        let group_key4 = fcm_sender::thread_group_key(&reply4.thread_descriptor);
+
+        assert_eq!(group_key1, group_key2);
+        assert_ne!(group_key1, group_key3);
+        assert_ne!(group_key1, group_key4);
+        assert_ne!(group_key3, group_key4);
+
+        assert_eq!(group_key1, fcm_sender::thread_group_key(&thread_descriptor1));
+        assert_eq!(32, group_key1.len());
+    }
+
+    async fn should_include_reply_url_and_watched_post_url() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+        let watched_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0);
+        let reply = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 2, 0);
+        let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+        account_repository::create_account(database, &account_id, Some(valid_until)).await.unwrap();
+        account_repository::update_firebase_token(database, &account_id, &application_type, &firebase_token)
+            .await
+            .unwrap();
+        post_repository::start_watching_post(database, &account_id, &application_type, &watched_post)
+            .await
+            .unwrap();
+
+        let mut found_post_replies_set = HashSet::from(
+            [FoundPostReply { origin: reply.clone(), replies_to: watched_post.clone() }]
+        );
+
+        thread_watcher::find_and_store_new_post_replies(
+            &thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database,
+        ).await.unwrap();
+
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
+        let unsent_replies_for_token = unsent_replies.values().next().unwrap();
+
+        let fcm_reply_messages = fcm_sender::convert_unsent_replies_to_fcm_messages(
+            unsent_replies_for_token,
+            site_repository_shared::site_repository()
+        );
+
+        assert_eq!(1, fcm_reply_messages.len());
+
+        let fcm_reply_message = fcm_reply_messages.first().unwrap();
+        assert_eq!("https://boards.4chan.org/g/thread/1#p2", fcm_reply_message.new_reply_url);
+        assert_eq!("https://boards.4chan.org/g/thread/1#p1", fcm_reply_message.watched_post_url);
+    }
+
+    async fn should_isolate_kuroba_ex_replies_from_kuroba_ex_lite_tokens() {
+        let database = database_shared::database();
+
+        assert_eq!(ApplicationType::KurobaExDebug, ApplicationType::from_i64(2));
+        assert_eq!(ApplicationType::KurobaExProduction, ApplicationType::from_i64(3));
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let kuroba_ex_lite_token = FirebaseToken::from_str("1234567890").unwrap();
+        let kuroba_ex_token = FirebaseToken::from_str("0987654321").unwrap();
+        let thread_descriptor = ThreadDescriptor::new("test".to_string(), "test".to_string(), 1);
+        let watched_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0);
+        let reply = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 2, 0);
+        let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+        account_repository::create_account(database, &account_id, Some(valid_until)).await.unwrap();
+
+        for (application_type, firebase_token) in [
+            (ApplicationType::KurobaExLiteDebug, &kuroba_ex_lite_token),
+            (ApplicationType::KurobaExDebug, &kuroba_ex_token)
+        ] {
+            account_repository::update_firebase_token(database, &account_id, &application_type, firebase_token)
+                .await
+                .unwrap();
+        }
+
+        // Only the KurobaEx app watches the post
+        post_repository::start_watching_post(database, &account_id, &ApplicationType::KurobaExDebug, &watched_post)
+            .await
+            .unwrap();
+
+        let mut found_post_replies_set = HashSet::from(
+            [FoundPostReply { origin: reply.clone(), replies_to: watched_post.clone() }]
+        );
+
+        thread_watcher::find_and_store_new_post_replies(
+            &thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database,
+        ).await.unwrap();
+
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
+        assert_eq!(1, unsent_replies.len());
+
+        let (account_token, unsent_replies_for_token) = unsent_replies.iter().next().unwrap();
+        assert_eq!(kuroba_ex_token.token, account_token.token);
+        assert_eq!(ApplicationType::KurobaExDebug, account_token.application_type);
+        assert_eq!(1, unsent_replies_for_token.len());
+    }
+
+    async fn should_claim_every_unsent_reply_only_once_across_concurrent_senders() {
+        let post_reply_ids = create_unsent_replies(10).await;
+
+        let mut join_handles = Vec::new();
+
+        for _ in 0..2 {
+            let join_handle = tokio::task::spawn(async move {
+                let database = database_shared::database();
+
+                let claimed_replies = post_reply_repository::claim_unsent_replies(true, database)
+                    .await
+                    .unwrap();
+
+                let claimed_post_reply_ids = claimed_replies.values()
+                    .flat_map(|unsent_replies| unsent_replies.iter())
+                    .map(|unsent_reply| unsent_reply.post_reply_id)
+                    .collect::<HashSet<i64>>()
+                    .into_iter()
+                    .collect::<Vec<i64>>();
+
+                fcm_sender::update_claimed_delivery_state(&claimed_post_reply_ids, &vec![], database)
+                    .await
+                    .unwrap();
+
+                return claimed_post_reply_ids;
+            });
+
+            join_handles.push(join_handle);
+        }
+
+        let mut all_claimed_post_reply_ids = Vec::<i64>::new();
+        for join_handle in join_handles {
+            all_claimed_post_reply_ids.extend(join_handle.await.unwrap());
+        }
+
+        all_claimed_post_reply_ids.sort();
+
+        let mut expected_post_reply_ids = post_reply_ids.clone();
+        expected_post_reply_ids.sort();
+
+        assert_eq!(expected_post_reply_ids, all_claimed_post_reply_ids);
+
+        let claimed_replies = post_reply_repository::claim_unsent_replies(true, database_shared::database())
+            .await
+            .unwrap();
+        assert!(claimed_replies.is_empty());
+    }
+
+    async fn should_make_released_claimed_replies_available_again() {
+        let database = database_shared::database();
+        let post_reply_id = create_unsent_reply().await;
+
+        let claimed_replies = post_reply_repository::claim_unsent_replies(true, database).await.unwrap();
+        assert_eq!(1, claimed_replies.len());
+
+        // Already claimed by the previous call
+        let claimed_replies = post_reply_repository::claim_unsent_replies(true, database).await.unwrap();
+        assert!(claimed_replies.is_empty());
+
+        fcm_sender::update_claimed_delivery_state(&vec![], &vec![post_reply_id], database).await.unwrap();
+
+        let claimed_replies = post_reply_repository::claim_unsent_replies(true, database).await.unwrap();
+        assert_eq!(1, claimed_replies.len());
+    }
+
+    async fn should_send_unsent_replies_through_transport_and_mark_them_delivered() {
+        let database = database_shared::database();
+        let site_repository = site_repository_shared::site_repository();
+
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+        let mut post_reply_ids = create_unsent_replies_in_thread(&thread_descriptor, 3).await;
+        post_reply_ids.sort();
+
+        let fcm_transport = Arc::new(InMemoryFcmTransport::new());
+        let fcm_sender = FcmSender::with_transport(true, fcm_transport.clone(), database, site_repository);
+
+        let sent_messages_count = fcm_sender.send_fcm_messages(4).await.unwrap();
+        assert_eq!(1, sent_messages_count);
+
+        let sent_messages = fcm_transport.sent_messages();
+        assert_eq!(1, sent_messages.len());
+
+        let sent_message = &sent_messages[0];
+        assert_eq!("1234567890", sent_message.token);
+
+        let message_body = serde_json::from_str::<serde_json::Value>(
+            sent_message.data.get("message_body").unwrap()
+        ).unwrap();
+
+        let mut sent_reply_ids = message_body["new_reply_messages"].as_array()
+            .unwrap()
+            .iter()
+            .map(|new_reply_message| new_reply_message["reply_id"].as_i64().unwrap())
+            .collect::<Vec<i64>>();
+        sent_reply_ids.sort();
+
+        assert_eq!(post_reply_ids, sent_reply_ids);
+
+        let claimed_replies = post_reply_repository::claim_unsent_replies(true, database).await.unwrap();
+        assert!(claimed_replies.is_empty());
+    }
+
+    async fn should_include_payload_version_and_server_time_in_replies_payload() {
+        let database = database_shared::database();
+        let site_repository = site_repository_shared::site_repository();
+
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+        create_unsent_replies_in_thread(&thread_descriptor, 2).await;
+
+        let fcm_transport = Arc::new(InMemoryFcmTransport::new());
+        let fcm_sender = FcmSender::with_transport(true, fcm_transport.clone(), database, site_repository);
+
+        let time_before_sending = chrono::offset::Utc::now();
+        fcm_sender.send_fcm_messages(4).await.unwrap();
+
+        let sent_messages = fcm_transport.sent_messages();
+        assert_eq!(1, sent_messages.len());
+
+        let message_body_json = sent_messages[0].data.get("message_body").unwrap();
+        let message_body = serde_json::from_str::<serde_json::Value>(message_body_json).unwrap();
+        assert_eq!(2, message_body["payload_version"].as_u64().unwrap());
+        assert!(message_body["server_time"].is_string());
+
+        let new_fcm_replies_message = serde_json::from_str::<NewFcmRepliesMessage>(message_body_json).unwrap();
+        assert_eq!(fcm_sender::FCM_REPLIES_PAYLOAD_VERSION, new_fcm_replies_message.payload_version);
+        assert!(new_fcm_replies_message.server_time >= time_before_sending);
+        assert_eq!(2, new_fcm_replies_message.new_reply_messages.len());
+
+        assert_eq!(message_body_json, &serde_json::to_string(&new_fcm_replies_message).unwrap());
+    }
+
+    async fn should_coalesce_replies_above_per_thread_cap_into_summary() {
+        let database = database_shared::database();
+        let site_repository = site_repository_shared::site_repository();
+
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+        let mut post_reply_ids = create_unsent_replies_in_thread(&thread_descriptor, 50).await;
+        post_reply_ids.sort();
+
+        let fcm_transport = Arc::new(InMemoryFcmTransport::new());
+        let fcm_sender = FcmSender::with_transport(true, fcm_transport.clone(), database, site_repository);
+
+        let sent_messages_count = fcm_sender.send_fcm_messages(4).await.unwrap();
+        assert_eq!(1, sent_messages_count);
+
+        let sent_messages = fcm_transport.sent_messages();
+        assert_eq!(1, sent_messages.len());
+
+        let new_fcm_replies_message = serde_json::from_str::<NewFcmRepliesMessage>(
+            sent_messages[0].data.get("message_body").unwrap()
+        ).unwrap();
+
+        // The oldest replies are sent as is
+        let max_replies_per_thread = fcm_sender::max_replies_per_thread();
+        assert_eq!(20, max_replies_per_thread);
+
+        let sent_reply_ids = new_fcm_replies_message.new_reply_messages.iter()
+            .map(|new_reply_message| new_reply_message.reply_id as i64)
+            .collect::<Vec<i64>>();
+        assert_eq!(post_reply_ids[..max_replies_per_thread].to_vec(), sent_reply_ids);
+
+        // The rest is collapsed into one summary pointing at the newest reply
+        assert_eq!(1, new_fcm_replies_message.coalesced_reply_messages.len());
+
+        let coalesced_reply_message = &new_fcm_replies_message.coalesced_reply_messages[0];
+        assert_eq!(30, coalesced_reply_message.replies_count);
+        assert!(coalesced_reply_message.last_reply_url.starts_with("https://boards.4chan.org/g/thread/1#p"));
+
+        let last_reply_url_was_sent = new_fcm_replies_message.new_reply_messages.iter()
+            .any(|new_reply_message| new_reply_message.new_reply_url == coalesced_reply_message.last_reply_url);
+        assert!(!last_reply_url_was_sent);
+        assert_eq!(fcm_sender::thread_group_key(&thread_descriptor), coalesced_reply_message.group_key);
+
+        // Coalesced replies are considered delivered too
+        let claimed_replies = post_reply_repository::claim_unsent_replies(true, database).await.unwrap();
+        assert!(claimed_replies.is_empty());
+
+        // No cap means no summary
+        let (reply_messages, coalesced_reply_messages) = fcm_sender::coalesce_fcm_reply_messages(
+            new_fcm_replies_message.new_reply_messages,
+            0
+        );
+        assert_eq!(20, reply_messages.len());
+        assert!(coalesced_reply_messages.is_empty());
+    }
+
+    async fn should_defer_replies_of_accounts_in_quiet_hours() {
+        let database = database_shared::database();
+        let site_repository = site_repository_shared::site_repository();
+
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+        let post_reply_ids = create_unsent_replies_in_thread(&thread_descriptor, 2).await;
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+
+        // A two hours long window around the current time in UTC+03:00
+        let now = chrono::offset::Utc::now();
+        let tz_offset_minutes = 3 * 60;
+        let local_minute = (now.hour() * 60 + now.minute()) as i16 + tz_offset_minutes;
+
+        let quiet_hours = QuietHours {
+            start: (local_minute - 60).rem_euclid(1440),
+            end: (local_minute + 60).rem_euclid(1440),
+            tz_offset_minutes
+        };
+
+        account_repository::update_quiet_hours(database, &account_id, Some(&quiet_hours)).await.unwrap();
+
+        let fcm_transport = Arc::new(InMemoryFcmTransport::new());
+        let fcm_sender = FcmSender::with_transport(true, fcm_transport.clone(), database, site_repository);
+
+        let sent_messages_count = fcm_sender.send_fcm_messages(4).await.unwrap();
+        assert_eq!(0, sent_messages_count);
+        assert!(fcm_transport.sent_messages().is_empty());
+
+        // Held back replies are neither claimed nor counted as failed
+        let account_db_id = account_repository::get_account(&account_id, database)
+            .await
+            .unwrap()
+            .unwrap()
+            .lock()
+            .await
+            .id;
+
+        let delivery_stats = post_reply_repository::get_delivery_stats(account_db_id, database).await.unwrap();
+        assert_eq!(2, delivery_stats.pending);
+        assert_eq!(0, delivery_stats.failed);
+
+        let connection = database.connection().await.unwrap();
+        let rows = connection.query(
+            "SELECT notification_delivery_attempt FROM post_replies WHERE id = ANY($1)",
+            &[&post_reply_ids]
+        ).await.unwrap();
+
+        assert_eq!(2, rows.len());
+        for row in rows {
+            assert_eq!(0, row.get::<usize, i16>(0));
+        }
+
+        // The quiet hours are over
+        account_repository::update_quiet_hours(database, &account_id, None).await.unwrap();
+
+        let sent_messages_count = fcm_sender.send_fcm_messages(4).await.unwrap();
+        assert_eq!(1, sent_messages_count);
+        assert_eq!(1, fcm_transport.sent_messages().len());
+
+        let claimed_replies = post_reply_repository::claim_unsent_replies(true, database).await.unwrap();
+        assert!(claimed_replies.is_empty());
+    }
+
+    async fn should_send_low_priority_confirmation_only_for_new_watches() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+        let site_repository = site_repository_shared::site_repository();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+        let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+        account_repository::create_account(database, &account_id, Some(valid_until)).await.unwrap();
+        account_repository::update_firebase_token(database, &account_id, &application_type, &firebase_token)
+            .await
+            .unwrap();
+
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+        let first_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0);
+        let second_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 2, 0);
+
+        fcm_sender::set_send_watch_confirmations(true);
+
+        for post_descriptor in vec![&first_post, &first_post, &second_post] {
+            post_repository::start_watching_post(database, &account_id, &application_type, post_descriptor)
+                .await
+                .unwrap();
+        }
+
+        fcm_sender::set_send_watch_confirmations(false);
+
+        let fcm_transport = Arc::new(InMemoryFcmTransport::new());
+        let fcm_sender = FcmSender::with_transport(true, fcm_transport.clone(), database, site_repository);
+
+        let sent_messages_count = fcm_sender.send_watch_confirmation_messages().await.unwrap();
+        assert_eq!(1, sent_messages_count);
+
+        let sent_messages = fcm_transport.sent_messages();
+        assert_eq!(1, sent_messages.len());
+
+        let sent_message = &sent_messages[0];
+        assert_eq!("1234567890", sent_message.token);
+        assert_eq!(FcmMessagePriority::Normal, sent_message.priority);
+
+        let fcm_watch_confirmed_message = serde_json::from_str::<FcmWatchConfirmedMessage>(
+            sent_message.data.get("watch_confirmed_message_body").unwrap()
+        ).unwrap();
+
+        let mut watched_post_urls = fcm_watch_confirmed_message.watched_post_urls;
+        watched_post_urls.sort();
+
+        let mut expected_post_urls = vec![
+            site_repository.to_url(&first_post).unwrap(),
+            site_repository.to_url(&second_post).unwrap()
+        ];
+        expected_post_urls.sort();
+
+        assert_eq!(expected_post_urls, watched_post_urls);
+
+        // The queue is drained after sending
+        assert_eq!(0, fcm_sender.send_watch_confirmation_messages().await.unwrap());
+    }
+
+    async fn should_send_encrypted_web_push_request_to_web_push_token() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+        let site_repository = site_repository_shared::site_repository();
+
+        // The account also has a firebase token which must keep getting FCM messages
+        let post_reply_id = create_unsent_reply().await;
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+
+        let recorded_requests = Arc::new(Mutex::new(Vec::<RecordedWebPushRequest>::new()));
+        let (server_address, server_handle) = start_mock_push_service(recorded_requests.clone()).await;
+
+        let (subscription_key_pair, auth_secret) = ece::generate_keypair_and_auth_secret().unwrap();
+        // Not created with WebPushSubscription::new() since it rejects the mock's local http endpoint
+        let web_push_subscription = WebPushSubscription {
+            endpoint: format!("http://{}/push/1", server_address),
+            p256dh: Base64UrlSafeNoPadding::encode_to_string(subscription_key_pair.pub_as_raw().unwrap()).unwrap(),
+            auth: Base64UrlSafeNoPadding::encode_to_string(auth_secret).unwrap()
+        };
+
+        account_repository::update_web_push_subscription(database, &account_id, &application_type, &web_push_subscription)
+            .await
+            .unwrap();
+
+        let vapid_keys = VapidKeys::from_base64(TEST_VAPID_PRIVATE_KEY, TEST_VAPID_SUBJECT).unwrap();
+        let vapid_public_key = vapid_keys.public_key().to_string();
+        let web_push_transport = Arc::new(WebPushTransport::new(
+            vapid_keys,
+            database,
+            site_repository_shared::site_repository().http_client()
+        ));
+
+        let fcm_transport = Arc::new(InMemoryFcmTransport::new());
+        let fcm_sender = FcmSender::with_transports(
+            true,
+            fcm_transport.clone(),
+            Some(web_push_transport),
+            database,
+            site_repository
+        );
+
+        let sent_messages_count = fcm_sender.send_fcm_messages(4).await.unwrap();
+        server_handle.abort();
+        assert_eq!(2, sent_messages_count);
+
+        let sent_fcm_messages = fcm_transport.sent_messages();
+        assert_eq!(1, sent_fcm_messages.len());
+        assert_eq!("1234567890", sent_fcm_messages[0].token);
+
+        let recorded_requests = recorded_requests.lock().unwrap();
+        assert_eq!(1, recorded_requests.len());
+
+        let recorded_request = &recorded_requests[0];
+        let header = |name: &str| {
+            return recorded_request.headers.get(name).unwrap().to_str().unwrap().to_string();
+        };
+
+        assert_eq!("/push/1", recorded_request.path);
+        assert_eq!("86400", header("TTL"));
+        assert_eq!("high", header("Urgency"));
+        assert_eq!("aes128gcm", header("Content-Encoding"));
+        assert_eq!("application/octet-stream", header("Content-Type"));
+
+        let authorization = header("Authorization");
+        let (vapid_token, vapid_key) = authorization.strip_prefix("vapid t=")
+            .unwrap()
+            .split_once(", k=")
+            .unwrap();
+        assert_eq!(vapid_public_key, vapid_key);
+
+        let vapid_key = ES256PublicKey::from_bytes(&Base64UrlSafeNoPadding::decode_to_vec(vapid_key, None).unwrap())
+            .unwrap();
+        let verification_options = VerificationOptions {
+            allowed_audiences: Some(HashSet::from([format!("http://{}", server_address)])),
+            ..Default::default()
+        };
+
+        let claims = vapid_key.verify_token::<NoCustomClaims>(vapid_token, Some(verification_options)).unwrap();
+        assert_eq!(Some(TEST_VAPID_SUBJECT.to_string()), claims.subject);
+
+        // Only the browser that owns the subscription keys can decrypt the payload
+        let payload = ece::decrypt(
+            &subscription_key_pair.raw_components().unwrap(),
+            &auth_secret,
+            &recorded_request.body
+        ).unwrap();
+
+        // Same JSON object as the data of the FCM message
+        let payload = serde_json::from_slice::<HashMap<String, String>>(&payload).unwrap();
+        assert_eq!(
+            sent_fcm_messages[0].data.keys().collect::<HashSet<&String>>(),
+            payload.keys().collect::<HashSet<&String>>()
+        );
+
+        let new_fcm_replies_message = serde_json::from_str::<NewFcmRepliesMessage>(
+            payload.get("message_body").unwrap()
+        ).unwrap();
+
+        assert_eq!(1, new_fcm_replies_message.new_reply_messages.len());
+        assert_eq!(post_reply_id as u64, new_fcm_replies_message.new_reply_messages[0].reply_id);
+
+        let claimed_replies = post_reply_repository::claim_unsent_replies(true, database).await.unwrap();
+        assert!(claimed_replies.is_empty());
+    }
+
+    async fn start_mock_push_service(
+        recorded_requests: Arc<Mutex<Vec<RecordedWebPushRequest>>>
+    ) -> (SocketAddr, JoinHandle<()>) {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await.unwrap();
+        let server_address = listener.local_addr().unwrap();
+
+        let join_handle = tokio::task::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let recorded_requests = recorded_requests.clone();
+
+                let service = service_fn(move |request: Request<Incoming>| {
+                    let recorded_requests = recorded_requests.clone();
+
+                    return async move {
+                        let path = request.uri().path().to_string();
+                        let headers = request.headers().clone();
+                        let body = collect_limited(request.into_body(), usize::MAX).await.unwrap();
+
+                        let recorded_request = RecordedWebPushRequest { path, headers, body };
+                        recorded_requests.lock().unwrap().push(recorded_request);
+
+                        // Push services respond with 201 once the message is queued for delivery
+                        let response = Response::builder()
+                            .status(201)
+                            .body(Full::new(Bytes::new()))
+                            .unwrap();
+
+                        return Ok::<Response<Full<Bytes>>, Infallible>(response);
+                    };
+                });
+
+                tokio::task::spawn(async move {
+                    let _ = http1::Builder::new()
+                        .serve_connection(stream, service)
+                        .await;
+                });
+            }
+        });
+
+        return (server_address, join_handle);
+    }
+
+    async fn create_unsent_reply() -> i64 {
+        return create_unsent_replies(1).await[0];
+    }
+
+    async fn create_unsent_replies(count: u64) -> Vec<i64> {
+        let thread_descriptor = ThreadDescriptor::new("test".to_string(), "test".to_string(), 1);
+        return create_unsent_replies_in_thread(&thread_descriptor, count).await;
+    }
+
+    async fn create_unsent_replies_in_thread(thread_descriptor: &ThreadDescriptor, count: u64) -> Vec<i64> {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+        let watched_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0);
+
+        let mut found_post_replies_set = (0..count)
+            .map(|index| {
+                return FoundPostReply {
+                    origin: PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 2 + index, 0),
+                    replies_to: watched_post.clone(),
+                };
+            })
+            .collect::<HashSet<FoundPostReply>>();
+
+        let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+        account_repository::create_account(database, &account_id, Some(valid_until)).await.unwrap();
+        account_repository::update_firebase_token(database, &account_id, &application_type, &firebase_token)
+            .await
+            .unwrap();
+        post_repository::start_watching_post(database, &account_id, &application_type, &watched_post)
+            .await
+            .unwrap();
+
+        thread_watcher::find_and_store_new_post_replies(
+            thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database,
+        ).await.unwrap();
+
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
+        let post_reply_ids = unsent_replies.values()
+            .flat_map(|unsent_replies| unsent_replies.iter())
+            .map(|unsent_reply| unsent_reply.post_reply_id)
+            .collect::<Vec<i64>>();
+
+        assert_eq!(count as usize, post_reply_ids.len());
+        return post_reply_ids;
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::model::database::db::Database;
+    use crate::model::repository::invites_repository;
+    use crate::service::invites_cleanup;
+    use crate::test_case;
+    use crate::tests::shared::database_shared;
+    use crate::tests::shared::shared::{run_test, TestCase};
+
+    #[tokio::test]
+    async fn run_tests() {
+        let tests: Vec<TestCase> = vec![
+            test_case!(should_delete_expired_invites_and_keep_valid_ones),
+        ];
+
+        run_test(tests).await;
+    }
+
+    async fn should_delete_expired_invites_and_keep_valid_ones() {
+        let database = database_shared::database();
+
+        let invites = invites_repository::generate_invites(database, 2).await.unwrap();
+        let expired_invite = &invites[0];
+        let valid_invite = &invites[1];
+
+        expire_invite(expired_invite, database).await;
+
+        let deleted = invites_cleanup::cleanup_invites(database).await;
+        assert_eq!(1, deleted);
+
+        assert_eq!(vec![valid_invite.clone()], get_invite_ids(database).await);
+
+        // Nothing left to delete
+        let deleted = invites_cleanup::cleanup_invites(database).await;
+        assert_eq!(0, deleted);
+    }
+
+    async fn expire_invite(invite_id: &String, database: &Arc<Database>) {
+        let query = r#"
+            UPDATE invites
+            SET expires_on = now() - interval '1 hours'
+            WHERE invite_id = $1
+        "#;
+
+        let connection = database.connection().await.unwrap();
+        connection.execute(query, &[invite_id]).await.unwrap();
+    }
+
+    async fn get_invite_ids(database: &Arc<Database>) -> Vec<String> {
+        let query = r#"
+            SELECT invite_id
+            FROM invites
+        "#;
+
+        let connection = database.connection().await.unwrap();
+
+        return connection.query(query, &[]).await
+            .unwrap()
+            .iter()
+            .map(|row| row.get::<usize, String>(0))
+            .collect::<Vec<String>>();
+    }
+}
@@ -1 +1,5 @@
-pub mod thread_watcher_tests;
\ No newline at end of file
+pub mod thread_watcher_tests;
+pub mod dead_threads_cleanup_tests;
+pub mod fcm_sender_tests;
+pub mod catalog_watcher_tests;
+pub mod invites_cleanup_tests;
\ No newline at end of file
@@ -1,14 +1,16 @@
 #[cfg(test)]
 mod tests {
-    use std::collections::HashSet;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Arc;
 
-    use crate::model::data::chan::{PostDescriptor, ThreadDescriptor};
-    use crate::model::repository::{account_repository, post_reply_repository, post_repository};
+    use crate::constants;
+    use crate::model::data::chan::{CatalogThread, ChanPost, ChanThread, PostDescriptor, ThreadDescriptor};
+    use crate::model::repository::{account_repository, post_repository, thread_repository};
     use crate::model::repository::account_repository::{AccountId, AccountToken, ApplicationType, FirebaseToken, TokenType};
     use crate::service::thread_watcher;
-    use crate::service::thread_watcher::FoundPostReply;
+    use crate::service::thread_watcher::{FoundPostReply, ThreadWatcher};
     use crate::test_case;
-    use crate::tests::shared::database_shared;
+    use crate::tests::shared::{database_shared, post_reply_repository_shared, site_repository_shared};
     use crate::tests::shared::shared::{run_test, TestCase};
 
     #[tokio::test]
@@ -17,6 +19,22 @@ mod tests {
             test_case!(test_one_account_watches_one_post),
             test_case!(test_two_accounts_watch_two_posts),
             test_case!(test_two_accounts_watch_the_same_post),
+            test_case!(test_one_account_with_two_tokens_watches_one_post),
+            test_case!(test_reply_is_not_stored_when_origin_post_was_deleted),
+            test_case!(test_reply_from_deleted_sub_post_is_deleted_when_sibling_exists),
+            test_case!(test_preview_thread_processing_reports_without_storing_replies),
+            test_case!(test_only_replies_matching_watch_filter_regex_are_stored),
+            test_case!(test_unmatched_quotes_do_not_create_post_descriptors),
+            test_case!(test_replies_from_posts_watched_by_the_same_account_are_suppressed),
+            test_case!(test_threads_watched_only_by_expired_accounts_are_not_watched),
+            test_case!(test_stale_thread_is_marked_as_dead),
+            test_case!(test_watches_migrate_to_successor_thread),
+            test_case!(test_watches_do_not_migrate_for_accounts_at_watch_limit),
+            test_case!(test_thread_is_marked_as_dead_after_two_consecutive_404s),
+            test_case!(test_successful_load_resets_404_counter),
+            test_case!(test_archived_thread_is_scanned_one_last_time),
+            test_case!(test_closed_thread_is_not_scanned),
+            test_case!(test_watcher_shares_http_client_with_site_repository),
         ];
 
         run_test(tests).await;
@@ -67,13 +85,11 @@ mod tests {
         thread_watcher::find_and_store_new_post_replies(
             &thread_descriptor,
             &mut found_post_replies_set,
+            &HashMap::new(),
             database,
         ).await.unwrap();
 
-        let unsent_replies = post_reply_repository::get_unsent_replies(
-            true,
-            database
-        ).await.unwrap();
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
 
         assert_eq!(1, unsent_replies.len());
 
@@ -165,13 +181,11 @@ mod tests {
         thread_watcher::find_and_store_new_post_replies(
             &thread_descriptor,
             &mut found_post_replies_set,
+            &HashMap::new(),
             database,
         ).await.unwrap();
 
-        let unsent_replies = post_reply_repository::get_unsent_replies(
-            true,
-            database
-        ).await.unwrap();
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
 
         assert_eq!(2, unsent_replies.len());
 
@@ -281,13 +295,11 @@ mod tests {
         thread_watcher::find_and_store_new_post_replies(
             &thread_descriptor,
             &mut found_post_replies_set,
+            &HashMap::new(),
             database,
         ).await.unwrap();
 
-        let unsent_replies = post_reply_repository::get_unsent_replies(
-            true,
-            database
-        ).await.unwrap();
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
 
         assert_eq!(2, unsent_replies.len());
 
@@ -330,4 +342,918 @@ mod tests {
         }
     }
 
-}
\ No newline at end of file
+    async fn test_one_account_with_two_tokens_watches_one_post() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token1 = FirebaseToken::from_str("1234567890").unwrap();
+        let firebase_token2 = FirebaseToken::from_str("0987654321").unwrap();
+        let thread_descriptor = ThreadDescriptor::new("test".to_string(), "test".to_string(), 1);
+        let watched_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0);
+
+        let mut found_post_replies_set = HashSet::from(
+            [
+                FoundPostReply {
+                    origin: PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 2, 0),
+                    replies_to: PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0),
+                }
+            ]
+        );
+
+        {
+            let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+            account_repository::create_account(
+                database,
+                &account_id,
+                Some(valid_until)
+            ).await.unwrap();
+
+            account_repository::update_firebase_token(
+                database,
+                &account_id,
+                &application_type,
+                &firebase_token1
+            ).await.unwrap();
+
+            account_repository::update_firebase_token(
+                database,
+                &account_id,
+                &application_type,
+                &firebase_token2
+            ).await.unwrap();
+
+            post_repository::start_watching_post(
+                database,
+                &account_id,
+                &application_type,
+                &watched_post
+            ).await.unwrap();
+        }
+
+        thread_watcher::find_and_store_new_post_replies(
+            &thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database,
+        ).await.unwrap();
+
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
+
+        assert_eq!(2, unsent_replies.len());
+
+        let tokens = unsent_replies.keys()
+            .map(|account_token| account_token.token.clone())
+            .collect::<HashSet<String>>();
+
+        assert!(tokens.contains(&firebase_token1.token));
+        assert!(tokens.contains(&firebase_token2.token));
+
+        for (account_token, unsent_replies_set) in &unsent_replies {
+            assert_eq!(application_type, account_token.application_type);
+            assert_eq!(TokenType::Firebase, account_token.token_type);
+
+            assert_eq!(1, unsent_replies_set.len());
+            let unsent_reply = unsent_replies_set.iter().next().unwrap();
+
+            assert_eq!(1, unsent_reply.post_reply_id);
+            assert_eq!(2, unsent_reply.post_descriptor.post_no);
+        }
+    }
+
+    async fn test_reply_is_not_stored_when_origin_post_was_deleted() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+        let site_repository = site_repository_shared::site_repository();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+        let watched_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0);
+
+        {
+            let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+            account_repository::create_account(
+                database,
+                &account_id,
+                Some(valid_until)
+            ).await.unwrap();
+
+            account_repository::update_firebase_token(
+                database,
+                &account_id,
+                &application_type,
+                &firebase_token
+            ).await.unwrap();
+
+            post_repository::start_watching_post(
+                database,
+                &account_id,
+                &application_type,
+                &watched_post
+            ).await.unwrap();
+        }
+
+        let quote = "<a href=\"#p1\" class=\"quotelink\">&gt;&gt;1</a>".to_string();
+
+        // Post 2 replies to the watched post
+        let chan_thread = ChanThread {
+            closed: false,
+            archived: false,
+            subject: None,
+            posts: vec![
+                ChanPost { post_no: 1, post_sub_no: None, comment_unparsed: None },
+                ChanPost { post_no: 2, post_sub_no: None, comment_unparsed: Some(quote) },
+            ]
+        };
+
+        thread_watcher::process_posts(site_repository, &None, &thread_descriptor, &chan_thread, database)
+            .await
+            .unwrap();
+
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
+        assert_eq!(1, unsent_replies.len());
+
+        // Post 2 was deleted before we could send the notification
+        let chan_thread = ChanThread {
+            closed: false,
+            archived: false,
+            subject: None,
+            posts: vec![
+                ChanPost { post_no: 1, post_sub_no: None, comment_unparsed: None },
+                ChanPost { post_no: 3, post_sub_no: None, comment_unparsed: None },
+            ]
+        };
+
+        thread_watcher::process_posts(site_repository, &None, &thread_descriptor, &chan_thread, database)
+            .await
+            .unwrap();
+
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
+        assert!(unsent_replies.is_empty());
+    }
+
+    async fn test_reply_from_deleted_sub_post_is_deleted_when_sibling_exists() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+        let site_repository = site_repository_shared::site_repository();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+        let post = |post_no: u64, post_sub_no: u64| {
+            return PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), post_no, post_sub_no);
+        };
+
+        {
+            let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+            account_repository::create_account(
+                database,
+                &account_id,
+                Some(valid_until)
+            ).await.unwrap();
+
+            account_repository::update_firebase_token(
+                database,
+                &account_id,
+                &application_type,
+                &firebase_token
+            ).await.unwrap();
+
+            post_repository::start_watching_post(
+                database,
+                &account_id,
+                &application_type,
+                &post(1, 0)
+            ).await.unwrap();
+        }
+
+        // Sub posts 2.1 and 2.2 both reply to the watched post
+        let mut found_post_replies_set = HashSet::from([
+            FoundPostReply { origin: post(2, 1), replies_to: post(1, 0) },
+            FoundPostReply { origin: post(2, 2), replies_to: post(1, 0) },
+        ]);
+
+        thread_watcher::find_and_store_new_post_replies(
+            &thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database
+        ).await.unwrap();
+
+        // Sub post 2.2 was deleted but its sibling with the same post_no is still there
+        let chan_thread = ChanThread {
+            closed: false,
+            archived: false,
+            subject: None,
+            posts: vec![
+                ChanPost { post_no: 1, post_sub_no: None, comment_unparsed: None },
+                ChanPost { post_no: 2, post_sub_no: Some(1), comment_unparsed: None },
+            ]
+        };
+
+        thread_watcher::process_posts(site_repository, &None, &thread_descriptor, &chan_thread, database)
+            .await
+            .unwrap();
+
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
+        assert_eq!(1, unsent_replies.len());
+
+        let unsent_replies_set = unsent_replies.values().next().unwrap();
+        assert_eq!(1, unsent_replies_set.len());
+
+        let unsent_reply = unsent_replies_set.iter().next().unwrap();
+        assert_eq!(2, unsent_reply.post_descriptor.post_no);
+        assert_eq!(1, unsent_reply.post_descriptor.post_sub_no);
+    }
+
+    async fn test_preview_thread_processing_reports_without_storing_replies() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+        let watched_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0);
+        let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+        account_repository::create_account(database, &account_id, Some(valid_until)).await.unwrap();
+        account_repository::update_firebase_token(database, &account_id, &application_type, &firebase_token)
+            .await
+            .unwrap();
+        post_repository::start_watching_post(database, &account_id, &application_type, &watched_post)
+            .await
+            .unwrap();
+
+        let quote = |post_no: u64| {
+            return format!("<a href=\"#p{0}\" class=\"quotelink\">&gt;&gt;{0}</a>", post_no);
+        };
+
+        let chan_thread = ChanThread {
+            closed: false,
+            archived: false,
+            subject: None,
+            posts: vec![
+                ChanPost { post_no: 1, post_sub_no: None, comment_unparsed: None },
+                ChanPost { post_no: 2, post_sub_no: None, comment_unparsed: Some(quote(1)) },
+                ChanPost { post_no: 3, post_sub_no: None, comment_unparsed: Some(format!("{}{}", quote(1), quote(2))) },
+            ]
+        };
+
+        let report = thread_watcher::preview_thread_processing(
+            site_repository_shared::site_repository(),
+            &None,
+            &thread_descriptor,
+            &chan_thread,
+            database
+        ).await.unwrap();
+
+        assert_eq!("Success", report.load_result);
+        assert_eq!(3, report.posts_fetched);
+        assert_eq!(3, report.new_posts);
+        assert_eq!(3, report.quotes_found);
+        assert_eq!(1, report.matched_watches);
+        assert_eq!(2, report.replies_to_send);
+
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
+        assert!(unsent_replies.is_empty());
+    }
+
+    async fn test_only_replies_matching_watch_filter_regex_are_stored() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+        let site_repository = site_repository_shared::site_repository();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+        let watched_post = PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0);
+
+        {
+            let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+            account_repository::create_account(
+                database,
+                &account_id,
+                Some(valid_until)
+            ).await.unwrap();
+
+            account_repository::update_firebase_token(
+                database,
+                &account_id,
+                &application_type,
+                &firebase_token
+            ).await.unwrap();
+
+            post_repository::start_watching_post_with_filter(
+                database,
+                &account_id,
+                &application_type,
+                &watched_post,
+                Some("!!tripcode")
+            ).await.unwrap();
+        }
+
+        let quote = "<a href=\"#p1\" class=\"quotelink\">&gt;&gt;1</a>";
+
+        // Both posts reply to the watched post but only post 2 matches the filter
+        let chan_thread = ChanThread {
+            closed: false,
+            archived: false,
+            subject: None,
+            posts: vec![
+                ChanPost { post_no: 1, post_sub_no: None, comment_unparsed: None },
+                ChanPost {
+                    post_no: 2,
+                    post_sub_no: None,
+                    comment_unparsed: Some(format!("{}<br>Update from !!TripCode", quote))
+                },
+                ChanPost {
+                    post_no: 3,
+                    post_sub_no: None,
+                    comment_unparsed: Some(format!("{}<br>Unrelated reply", quote))
+                },
+            ]
+        };
+
+        thread_watcher::process_posts(site_repository, &None, &thread_descriptor, &chan_thread, database)
+            .await
+            .unwrap();
+
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
+        assert_eq!(1, unsent_replies.len());
+
+        let unsent_replies_set = unsent_replies.values().next().unwrap();
+        assert_eq!(1, unsent_replies_set.len());
+        assert_eq!(2, unsent_replies_set.iter().next().unwrap().post_descriptor.post_no);
+    }
+
+    async fn test_unmatched_quotes_do_not_create_post_descriptors() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+        let post = |post_no: u64| PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), post_no, 0);
+
+        {
+            let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+            account_repository::create_account(
+                database,
+                &account_id,
+                Some(valid_until)
+            ).await.unwrap();
+
+            account_repository::update_firebase_token(
+                database,
+                &account_id,
+                &application_type,
+                &firebase_token
+            ).await.unwrap();
+
+            post_repository::start_watching_post(
+                database,
+                &account_id,
+                &application_type,
+                &post(1)
+            ).await.unwrap();
+        }
+
+        // Post 3 quotes a post that doesn't exist in this thread (e.g. a crossthread quote)
+        let mut found_post_replies_set = HashSet::from([
+            FoundPostReply { origin: post(2), replies_to: post(1) },
+            FoundPostReply { origin: post(3), replies_to: post(100) },
+        ]);
+
+        thread_watcher::find_and_store_new_post_replies(
+            &thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database
+        ).await.unwrap();
+
+        // Post 2 has a db id now but it's not watched
+        let mut found_post_replies_set = HashSet::from([
+            FoundPostReply { origin: post(4), replies_to: post(2) },
+        ]);
+
+        thread_watcher::find_and_store_new_post_replies(
+            &thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database
+        ).await.unwrap();
+
+        let query = r#"
+            SELECT pd.post_no
+            FROM post_descriptors pd
+                INNER JOIN threads thread on thread.id = pd.owner_thread_id
+            WHERE thread.thread_no = $1
+            ORDER BY pd.post_no
+        "#;
+
+        let connection = database.connection().await.unwrap();
+        let post_nos = connection.query(query, &[&(thread_descriptor.thread_no as i64)])
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get::<usize, i64>(0))
+            .collect::<Vec<i64>>();
+
+        assert_eq!(vec![1, 2], post_nos);
+
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
+        assert_eq!(1, unsent_replies.len());
+
+        let unsent_replies_set = unsent_replies.values().next().unwrap();
+        assert_eq!(1, unsent_replies_set.len());
+        assert_eq!(2, unsent_replies_set.iter().next().unwrap().post_descriptor.post_no);
+    }
+
+    async fn test_replies_from_posts_watched_by_the_same_account_are_suppressed() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        let account_id1 = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let account_id2 = AccountId::from_user_id("222222222222222222222222222222222222").unwrap();
+        let firebase_token1 = FirebaseToken::from_str("1234567890").unwrap();
+        let firebase_token2 = FirebaseToken::from_str("0987654321").unwrap();
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 1);
+        let post = |post_no: u64| PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), post_no, 0);
+
+        {
+            let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+            for (account_id, firebase_token) in [(&account_id1, &firebase_token1), (&account_id2, &firebase_token2)] {
+                account_repository::create_account(
+                    database,
+                    account_id,
+                    Some(valid_until)
+                ).await.unwrap();
+
+                account_repository::update_firebase_token(
+                    database,
+                    account_id,
+                    &application_type,
+                    firebase_token
+                ).await.unwrap();
+
+                post_repository::start_watching_post(
+                    database,
+                    account_id,
+                    &application_type,
+                    &post(1)
+                ).await.unwrap();
+            }
+
+            // Account 1 made post 2 (the clients watch the posts the user makes)
+            post_repository::start_watching_post(
+                database,
+                &account_id1,
+                &application_type,
+                &post(2)
+            ).await.unwrap();
+        }
+
+        let mut found_post_replies_set = HashSet::from([
+            FoundPostReply { origin: post(2), replies_to: post(1) },
+            FoundPostReply { origin: post(3), replies_to: post(1) },
+        ]);
+
+        thread_watcher::set_suppress_self_replies(true);
+        let result = thread_watcher::find_and_store_new_post_replies(
+            &thread_descriptor,
+            &mut found_post_replies_set,
+            &HashMap::new(),
+            database
+        ).await;
+        thread_watcher::set_suppress_self_replies(false);
+        result.unwrap();
+
+        let unsent_replies = post_reply_repository_shared::peek_unsent_replies(database).await;
+        assert_eq!(2, unsent_replies.len());
+
+        let reply_post_nos_of = |firebase_token: &FirebaseToken| {
+            let mut post_nos = unsent_replies.iter()
+                .find(|(token, _)| token.token == firebase_token.token)
+                .unwrap()
+                .1
+                .iter()
+                .map(|unsent_reply| unsent_reply.post_descriptor.post_no)
+                .collect::<Vec<u64>>();
+
+            post_nos.sort();
+            return post_nos;
+        };
+
+        // Account 1 is not notified about its own reply but the other watcher is
+        assert_eq!(vec![3], reply_post_nos_of(&firebase_token1));
+        assert_eq!(vec![2, 3], reply_post_nos_of(&firebase_token2));
+    }
+
+    async fn test_threads_watched_only_by_expired_accounts_are_not_watched() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        let valid_account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let expired_account_id = AccountId::from_user_id("222222222222222222222222222222222222").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+
+        let valid_thread_descriptor = ThreadDescriptor::new("test".to_string(), "test".to_string(), 1);
+        let expired_thread_descriptor = ThreadDescriptor::new("test".to_string(), "test".to_string(), 2);
+
+        {
+            let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+            for (account_id, thread_descriptor) in [
+                (&valid_account_id, &valid_thread_descriptor),
+                (&expired_account_id, &expired_thread_descriptor)
+            ] {
+                account_repository::create_account(
+                    database,
+                    account_id,
+                    Some(valid_until)
+                ).await.unwrap();
+
+                account_repository::update_firebase_token(
+                    database,
+                    account_id,
+                    &application_type,
+                    &firebase_token
+                ).await.unwrap();
+
+                post_repository::start_watching_post(
+                    database,
+                    account_id,
+                    &application_type,
+                    &PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0)
+                ).await.unwrap();
+            }
+        }
+
+        {
+            // Expire the second account after it started watching the post
+            let account_mutex = account_repository::test_get_account_from_cache(&expired_account_id)
+                .await
+                .unwrap();
+            let mut account = account_mutex.lock().await;
+
+            account.valid_until = Some(chrono::offset::Utc::now() - chrono::Duration::days(1));
+            account_repository::test_put_account_into_database(&account, database).await.unwrap();
+        }
+
+        let watched_threads = post_repository::get_all_watched_threads(database).await.unwrap();
+
+        assert_eq!(1, watched_threads.len());
+        assert_eq!(valid_thread_descriptor, watched_threads[0]);
+    }
+
+    async fn test_stale_thread_is_marked_as_dead() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+        let stale_thread_descriptor = ThreadDescriptor::new("test".to_string(), "test".to_string(), 1);
+        let active_thread_descriptor = ThreadDescriptor::new("test".to_string(), "test".to_string(), 2);
+
+        {
+            let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+            account_repository::create_account(
+                database,
+                &account_id,
+                Some(valid_until)
+            ).await.unwrap();
+
+            account_repository::update_firebase_token(
+                database,
+                &account_id,
+                &application_type,
+                &firebase_token
+            ).await.unwrap();
+
+            for (thread_descriptor, days_since_last_modified) in [
+                (&stale_thread_descriptor, 10),
+                (&active_thread_descriptor, 1)
+            ] {
+                post_repository::start_watching_post(
+                    database,
+                    &account_id,
+                    &application_type,
+                    &PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), 1, 0)
+                ).await.unwrap();
+
+                let last_modified = chrono::offset::Utc::now() - chrono::Duration::days(days_since_last_modified);
+                let last_modified = chrono::DateTime::<chrono::FixedOffset>::from(last_modified);
+
+                thread_repository::store_last_modified(
+                    &last_modified,
+                    thread_descriptor,
+                    database
+                ).await.unwrap();
+            }
+        }
+
+        // Disabled
+        let marked_as_dead = thread_watcher::mark_thread_as_dead_if_stale(&stale_thread_descriptor, 0, database)
+            .await
+            .unwrap();
+        assert!(!marked_as_dead);
+
+        let marked_as_dead = thread_watcher::mark_thread_as_dead_if_stale(&stale_thread_descriptor, 7, database)
+            .await
+            .unwrap();
+        assert!(marked_as_dead);
+
+        let marked_as_dead = thread_watcher::mark_thread_as_dead_if_stale(&active_thread_descriptor, 7, database)
+            .await
+            .unwrap();
+        assert!(!marked_as_dead);
+
+        // The stale thread must not be polled on the next cycle
+        let watched_threads = post_repository::get_all_watched_threads(database).await.unwrap();
+
+        assert_eq!(1, watched_threads.len());
+        assert_eq!(active_thread_descriptor, watched_threads[0]);
+    }
+
+    async fn test_watches_migrate_to_successor_thread() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+        let site_repository = site_repository_shared::site_repository();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+        let dead_thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "vg".to_string(), 100);
+        let successor_thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "vg".to_string(), 105);
+
+        {
+            let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+            account_repository::create_account(
+                database,
+                &account_id,
+                Some(valid_until)
+            ).await.unwrap();
+
+            account_repository::update_firebase_token(
+                database,
+                &account_id,
+                &application_type,
+                &firebase_token
+            ).await.unwrap();
+
+            // Two watched posts in the same thread must result in a single migrated watch
+            for post_no in [100, 101] {
+                post_repository::start_watching_post(
+                    database,
+                    &account_id,
+                    &application_type,
+                    &PostDescriptor::from_thread_descriptor(dead_thread_descriptor.clone(), post_no, 0)
+                ).await.unwrap();
+            }
+
+            thread_repository::store_thread_title(
+                &"/agdg/ - Amateur Game Dev General #123".to_string(),
+                &dead_thread_descriptor,
+                database
+            ).await.unwrap();
+
+            post_repository::mark_thread_as_dead(database, &dead_thread_descriptor, true).await.unwrap();
+        }
+
+        let watched_threads = post_repository::get_all_watched_threads(database).await.unwrap();
+        assert!(watched_threads.is_empty());
+
+        let catalog_threads = vec![
+            CatalogThread {
+                thread_no: 103,
+                subject: Some("/vg/ - Video Game Generals".to_string()),
+                comment: None
+            },
+            CatalogThread {
+                thread_no: 105,
+                subject: Some("/agdg/ - Amateur Game Dev General #124".to_string()),
+                comment: None
+            },
+        ];
+
+        let successor = thread_watcher::migrate_watches_to_successor_thread(
+            &dead_thread_descriptor,
+            &catalog_threads,
+            database,
+            site_repository
+        ).await.unwrap();
+
+        assert_eq!(Some(successor_thread_descriptor.clone()), successor);
+
+        let watched_threads = post_repository::get_all_watched_threads(database).await.unwrap();
+        assert_eq!(1, watched_threads.len());
+        assert_eq!(successor_thread_descriptor, watched_threads[0]);
+
+        // Migrating again must not create duplicate watches
+        let migrated = post_repository::migrate_thread_watches(
+            database,
+            &dead_thread_descriptor,
+            &PostDescriptor::from_thread_descriptor(successor_thread_descriptor.clone(), 105, 0)
+        ).await.unwrap();
+
+        assert_eq!(0, migrated);
+    }
+
+    async fn test_watches_do_not_migrate_for_accounts_at_watch_limit() {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        let account_id1 = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let account_id2 = AccountId::from_user_id("222222222222222222222222222222222222").unwrap();
+        let dead_thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "vg".to_string(), 100);
+        let successor_post_descriptor = PostDescriptor::from_thread_descriptor(
+            ThreadDescriptor::new("4chan".to_string(), "vg".to_string(), 105),
+            105,
+            0
+        );
+
+        let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+        // account1 watches two posts of the dead thread and account2 only one
+        let accounts = [
+            (&account_id1, "1234567890", vec![100, 101]),
+            (&account_id2, "0987654321", vec![100])
+        ];
+
+        for (account_id, firebase_token, post_nos) in accounts {
+            account_repository::create_account(database, account_id, Some(valid_until)).await.unwrap();
+
+            account_repository::update_firebase_token(
+                database,
+                account_id,
+                &application_type,
+                &FirebaseToken::from_str(firebase_token).unwrap()
+            ).await.unwrap();
+
+            for post_no in post_nos {
+                post_repository::start_watching_post(
+                    database,
+                    account_id,
+                    &application_type,
+                    &PostDescriptor::from_thread_descriptor(dead_thread_descriptor.clone(), post_no, 0)
+                ).await.unwrap();
+            }
+        }
+
+        post_repository::set_max_watches_per_account(2);
+
+        let migrated = post_repository::migrate_thread_watches(
+            database,
+            &dead_thread_descriptor,
+            &successor_post_descriptor
+        ).await;
+
+        post_repository::set_max_watches_per_account(constants::DEFAULT_MAX_WATCHES_PER_ACCOUNT);
+
+        // Only account2 was below the limit
+        assert_eq!(1, migrated.unwrap());
+
+        let account1 = account_repository::get_account(&account_id1, database).await.unwrap().unwrap();
+        let account2 = account_repository::get_account(&account_id2, database).await.unwrap().unwrap();
+        let account1_db_id = { account1.lock().await.id };
+        let account2_db_id = { account2.lock().await.id };
+
+        assert_eq!(2, post_repository::count_account_watches(database, account1_db_id).await.unwrap());
+        assert_eq!(2, post_repository::count_account_watches(database, account2_db_id).await.unwrap());
+    }
+
+    async fn test_thread_is_marked_as_dead_after_two_consecutive_404s() {
+        let database = database_shared::database();
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 200);
+        watch_thread(&thread_descriptor).await;
+
+        let marked_as_dead = thread_watcher::on_thread_not_found(&thread_descriptor, database)
+            .await
+            .unwrap();
+        assert!(!marked_as_dead);
+
+        let watched_threads = post_repository::get_all_watched_threads(database).await.unwrap();
+        assert_eq!(vec![thread_descriptor.clone()], watched_threads);
+
+        let marked_as_dead = thread_watcher::on_thread_not_found(&thread_descriptor, database)
+            .await
+            .unwrap();
+        assert!(marked_as_dead);
+
+        let watched_threads = post_repository::get_all_watched_threads(database).await.unwrap();
+        assert!(watched_threads.is_empty());
+    }
+
+    async fn test_successful_load_resets_404_counter() {
+        let database = database_shared::database();
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 201);
+        watch_thread(&thread_descriptor).await;
+
+        let marked_as_dead = thread_watcher::on_thread_not_found(&thread_descriptor, database)
+            .await
+            .unwrap();
+        assert!(!marked_as_dead);
+
+        let should_process_posts = thread_watcher::on_thread_loaded(
+            &thread_descriptor,
+            &chan_thread(false, false),
+            database
+        ).await.unwrap();
+        assert!(should_process_posts);
+
+        // Not consecutive anymore
+        let marked_as_dead = thread_watcher::on_thread_not_found(&thread_descriptor, database)
+            .await
+            .unwrap();
+        assert!(!marked_as_dead);
+
+        let watched_threads = post_repository::get_all_watched_threads(database).await.unwrap();
+        assert_eq!(vec![thread_descriptor.clone()], watched_threads);
+    }
+
+    async fn test_archived_thread_is_scanned_one_last_time() {
+        let database = database_shared::database();
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 202);
+        watch_thread(&thread_descriptor).await;
+
+        let should_process_posts = thread_watcher::on_thread_loaded(
+            &thread_descriptor,
+            &chan_thread(false, true),
+            database
+        ).await.unwrap();
+        assert!(should_process_posts);
+
+        let watched_threads = post_repository::get_all_watched_threads(database).await.unwrap();
+        assert!(watched_threads.is_empty());
+    }
+
+    async fn test_closed_thread_is_not_scanned() {
+        let database = database_shared::database();
+        let thread_descriptor = ThreadDescriptor::new("4chan".to_string(), "g".to_string(), 203);
+        watch_thread(&thread_descriptor).await;
+
+        let should_process_posts = thread_watcher::on_thread_loaded(
+            &thread_descriptor,
+            &chan_thread(true, false),
+            database
+        ).await.unwrap();
+        assert!(!should_process_posts);
+
+        let watched_threads = post_repository::get_all_watched_threads(database).await.unwrap();
+        assert!(watched_threads.is_empty());
+    }
+
+    async fn watch_thread(thread_descriptor: &ThreadDescriptor) {
+        let application_type = ApplicationType::KurobaExLiteDebug;
+        let database = database_shared::database();
+
+        let account_id = AccountId::from_user_id("111111111111111111111111111111111111").unwrap();
+        let firebase_token = FirebaseToken::from_str("1234567890").unwrap();
+        let valid_until = chrono::offset::Utc::now() + chrono::Duration::days(1);
+
+        account_repository::create_account(
+            database,
+            &account_id,
+            Some(valid_until)
+        ).await.unwrap();
+
+        account_repository::update_firebase_token(
+            database,
+            &account_id,
+            &application_type,
+            &firebase_token
+        ).await.unwrap();
+
+        post_repository::start_watching_post(
+            database,
+            &account_id,
+            &application_type,
+            &PostDescriptor::from_thread_descriptor(thread_descriptor.clone(), thread_descriptor.thread_no, 0)
+        ).await.unwrap();
+    }
+
+    async fn test_watcher_shares_http_client_with_site_repository() {
+        // The test server routes the requests with the shared site repository
+        let site_repository = site_repository_shared::site_repository();
+        let thread_watcher = ThreadWatcher::new(1, 1, true, site_repository.http_client());
+
+        assert!(Arc::ptr_eq(site_repository.http_client(), thread_watcher.http_client()));
+    }
+
+    fn chan_thread(closed: bool, archived: bool) -> ChanThread {
+        return ChanThread {
+            closed,
+            archived,
+            subject: None,
+            posts: vec![ChanPost { post_no: 1, post_sub_no: None, comment_unparsed: None }]
+        };
+    }
+}
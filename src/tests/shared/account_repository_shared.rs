@@ -4,10 +4,23 @@ use fcm::Duration;
 use lazy_static::lazy_static;
 use serde::de::DeserializeOwned;
 
+use crate::handlers::ban_account::BanAccountRequest;
+use crate::handlers::bulk_extend_expiry::BulkExtendExpiryRequest;
 use crate::handlers::create_account::CreateNewAccountRequest;
+use crate::handlers::delete_account::DeleteAccountRequest;
+use crate::handlers::extend_account_expiry::ExtendAccountExpiryRequest;
+use crate::handlers::get_delivery_stats::GetDeliveryStatsRequest;
 use crate::handlers::get_account_info::AccountInfoRequest;
+use crate::handlers::list_accounts::ListAccountsRequest;
+use crate::handlers::remove_firebase_token::RemoveFirebaseTokenRequest;
+use crate::handlers::replay_replies::ReplayRepliesRequest;
+use crate::handlers::send_test_notification::SendTestNotificationRequest;
 use crate::handlers::shared::{EmptyResponse, ServerResponse, ServerSuccessResponse};
+use crate::handlers::update_account_expiry_date::UpdateAccountExpiryDateRequest;
 use crate::handlers::update_firebase_token::UpdateFirebaseTokenRequest;
+use crate::handlers::update_quiet_hours::UpdateQuietHoursRequest;
+use crate::handlers::update_web_push_subscription::UpdateWebPushSubscriptionRequest;
+use crate::handlers::whoami::WhoAmIRequest;
 use crate::model::database::db::Database;
 use crate::model::repository::account_repository;
 use crate::model::repository::account_repository::{Account, AccountId, ApplicationType};
@@ -99,6 +112,44 @@ pub async fn get_account_info<'a, T : DeserializeOwned + ServerSuccessResponse>(
     return Ok(response);
 }
 
+pub async fn whoami<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    user_id: &str
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = WhoAmIRequest {
+        user_id: user_id.to_string()
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "whoami",
+        &body,
+        ""
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn send_test_notification<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    user_id: &str,
+    application_type: &ApplicationType
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = SendTestNotificationRequest {
+        user_id: user_id.to_string(),
+        application_type: application_type.clone()
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "send_test_notification",
+        &body,
+        ""
+    ).await?;
+
+    return Ok(response);
+}
+
 pub async fn update_firebase_token<'a, T : DeserializeOwned + ServerSuccessResponse>(
     master_password: &str,
     user_id: &str,
@@ -108,7 +159,8 @@ pub async fn update_firebase_token<'a, T : DeserializeOwned + ServerSuccessRespo
     let request = UpdateFirebaseTokenRequest {
         user_id: user_id.to_string(),
         firebase_token: firebase_token.to_string(),
-        application_type: application_type.clone()
+        application_type: application_type.clone(),
+        previous_token: None
     };
 
     let body = serde_json::to_string(&request).unwrap();
@@ -122,6 +174,267 @@ pub async fn update_firebase_token<'a, T : DeserializeOwned + ServerSuccessRespo
     return Ok(response);
 }
 
+pub async fn update_web_push_subscription<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    master_password: &str,
+    user_id: &str,
+    endpoint: &str,
+    p256dh: &str,
+    auth: &str,
+    application_type: &ApplicationType
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = UpdateWebPushSubscriptionRequest {
+        user_id: user_id.to_string(),
+        application_type: application_type.clone(),
+        endpoint: endpoint.to_string(),
+        p256dh: p256dh.to_string(),
+        auth: auth.to_string()
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "update_web_push_subscription",
+        &body,
+        master_password
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn rotate_firebase_token<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    master_password: &str,
+    user_id: &str,
+    previous_token: &str,
+    firebase_token: &str,
+    application_type: &ApplicationType
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = UpdateFirebaseTokenRequest {
+        user_id: user_id.to_string(),
+        firebase_token: firebase_token.to_string(),
+        application_type: application_type.clone(),
+        previous_token: Some(previous_token.to_string())
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "update_firebase_token",
+        &body,
+        master_password
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn remove_firebase_token<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    master_password: &str,
+    user_id: &str,
+    firebase_token: Option<&str>,
+    application_type: &ApplicationType
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = RemoveFirebaseTokenRequest {
+        user_id: user_id.to_string(),
+        application_type: application_type.clone(),
+        firebase_token: firebase_token.map(|firebase_token| firebase_token.to_string())
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "remove_firebase_token",
+        &body,
+        master_password
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn update_quiet_hours<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    master_password: &str,
+    user_id: &str,
+    quiet_hours_start: Option<i16>,
+    quiet_hours_end: Option<i16>,
+    tz_offset_minutes: i16
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = UpdateQuietHoursRequest {
+        user_id: user_id.to_string(),
+        quiet_hours_start,
+        quiet_hours_end,
+        tz_offset_minutes
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "update_quiet_hours",
+        &body,
+        master_password
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn delete_account<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    master_password: &str,
+    user_id: &str
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = DeleteAccountRequest {
+        user_id: user_id.to_string()
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "delete_account",
+        &body,
+        master_password
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn get_delivery_stats<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    master_password: &str,
+    user_id: &str
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = GetDeliveryStatsRequest {
+        user_id: user_id.to_string()
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "get_delivery_stats",
+        &body,
+        master_password
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn replay_replies<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    master_password: &str,
+    user_id: &str
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = ReplayRepliesRequest {
+        user_id: user_id.to_string()
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "replay_replies",
+        &body,
+        master_password
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn ban_account<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    master_password: &str,
+    user_id: &str,
+    days: i64
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = BanAccountRequest {
+        user_id: user_id.to_string(),
+        days
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "ban_account",
+        &body,
+        master_password
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn update_account_expiry_date<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    master_password: &str,
+    user_id: &str,
+    valid_for_days: u64
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = UpdateAccountExpiryDateRequest {
+        user_id: user_id.to_string(),
+        valid_for_days
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "update_account_expiry_date",
+        &body,
+        master_password
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn list_accounts<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    master_password: &str,
+    page: u64,
+    page_size: u64
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = ListAccountsRequest {
+        page,
+        page_size
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "list_accounts",
+        &body,
+        master_password
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn bulk_extend_expiry<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    master_password: &str,
+    account_ids: &Vec<String>,
+    extra_days: u64
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = BulkExtendExpiryRequest {
+        account_ids: account_ids.clone(),
+        extra_days
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "bulk_extend_expiry",
+        &body,
+        master_password
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn extend_account_expiry<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    user_id: &str,
+    invite: &str
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = ExtendAccountExpiryRequest {
+        user_id: user_id.to_string(),
+        invite: invite.to_string()
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "extend_account_expiry",
+        &body,
+        ""
+    ).await?;
+
+    return Ok(response);
+}
+
 pub async fn get_account_from_cache(user_id: &str) -> anyhow::Result<Option<Account>> {
     let account_id = AccountId::test_unsafe(user_id)?;
 
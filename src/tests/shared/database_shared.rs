@@ -11,28 +11,15 @@ pub fn database() -> &'static Arc<Database> {
     return DATABASE.get().unwrap();
 }
 
+pub fn connection_string() -> String {
+    return "postgresql://localhost/test?user=postgres&password=test123".to_string();
+}
+
 pub async fn ctor() {
-    let connection_string = "postgresql://localhost/test?user=postgres&password=test123".to_string();
-    let database = Database::new(connection_string, 4).await.unwrap();
+    let database = Database::new(connection_string(), 4).await.unwrap();
     let _ = DATABASE.set(Arc::new(database));
 
-    {
-        let database = DATABASE.get().unwrap();
-        let connection = database.connection().await.unwrap();
-
-        let query = r#"
-            DROP TABLE IF EXISTS public.account_tokens CASCADE;
-            DROP TABLE IF EXISTS public.accounts CASCADE;
-            DROP TABLE IF EXISTS public.logs CASCADE;
-            DROP TABLE IF EXISTS public.migrations CASCADE;
-            DROP TABLE IF EXISTS public.post_descriptors CASCADE;
-            DROP TABLE IF EXISTS public.post_replies CASCADE;
-            DROP TABLE IF EXISTS public.post_watches CASCADE;
-            DROP TABLE IF EXISTS public.threads CASCADE;
-        "#;
-
-        connection.batch_execute(query).await.unwrap();
-    }
+    drop_all_tables().await;
 }
 
 pub async fn cleanup() {
@@ -42,38 +29,56 @@ pub async fn cleanup() {
     let query = r#"
         DELETE FROM public.account_tokens;
         DELETE FROM public.accounts;
+        DELETE FROM public.catalog_thread_notifications;
+        DELETE FROM public.catalog_watches;
+        DELETE FROM public.invites;
         DELETE FROM public.logs;
         DELETE FROM public.migrations;
         DELETE FROM public.post_descriptors;
         DELETE FROM public.post_replies;
         DELETE FROM public.post_watches;
+        DELETE FROM public.thread_mutes;
         DELETE FROM public.threads;
+        DELETE FROM public.web_push_subscriptions;
 
         ALTER SEQUENCE account_tokens_id_seq RESTART;
         ALTER SEQUENCE accounts_id_seq RESTART;
+        ALTER SEQUENCE catalog_thread_notifications_id_seq RESTART;
+        ALTER SEQUENCE catalog_watches_id_seq RESTART;
         ALTER SEQUENCE logs_id_seq RESTART;
         ALTER SEQUENCE post_descriptors_id_seq RESTART;
         ALTER SEQUENCE post_replies_id_seq RESTART;
         ALTER SEQUENCE post_watches_id_seq RESTART;
+        ALTER SEQUENCE thread_mutes_id_seq RESTART;
         ALTER SEQUENCE threads_id_seq RESTART;
+        ALTER SEQUENCE web_push_subscriptions_id_seq RESTART;
     "#;
 
     connection.batch_execute(query).await.unwrap();
 }
 
 pub async fn dtor() {
+    drop_all_tables().await;
+}
+
+pub async fn drop_all_tables() {
     let database = DATABASE.get().unwrap();
     let connection = database.connection().await.unwrap();
 
     let query = r#"
         DROP TABLE IF EXISTS public.account_tokens CASCADE;
         DROP TABLE IF EXISTS public.accounts CASCADE;
+        DROP TABLE IF EXISTS public.catalog_thread_notifications CASCADE;
+        DROP TABLE IF EXISTS public.catalog_watches CASCADE;
+        DROP TABLE IF EXISTS public.invites CASCADE;
         DROP TABLE IF EXISTS public.logs CASCADE;
         DROP TABLE IF EXISTS public.migrations CASCADE;
         DROP TABLE IF EXISTS public.post_descriptors CASCADE;
         DROP TABLE IF EXISTS public.post_replies CASCADE;
         DROP TABLE IF EXISTS public.post_watches CASCADE;
+        DROP TABLE IF EXISTS public.thread_mutes CASCADE;
         DROP TABLE IF EXISTS public.threads CASCADE;
+        DROP TABLE IF EXISTS public.web_push_subscriptions CASCADE;
     "#;
 
     connection.batch_execute(query).await.unwrap();
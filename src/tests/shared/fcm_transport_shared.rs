@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+
+use crate::service::fcm_sender::FcmSendAttemptResult;
+use crate::service::fcm_transport::{FcmMessagePriority, FcmTransport};
+
+lazy_static! {
+    static ref TEST_SERVER_FCM_TRANSPORT: Arc<InMemoryFcmTransport> = Arc::new(InMemoryFcmTransport::new());
+}
+
+/// The transport of the FcmSender used by the test server.
+pub fn test_server_fcm_transport() -> &'static Arc<InMemoryFcmTransport> {
+    return &TEST_SERVER_FCM_TRANSPORT;
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordedFcmMessage {
+    pub token: String,
+    pub data: HashMap<String, String>,
+    pub priority: FcmMessagePriority
+}
+
+/// Records every message instead of sending it and reports all of them as sent.
+pub struct InMemoryFcmTransport {
+    sent_messages: Mutex<Vec<RecordedFcmMessage>>
+}
+
+impl InMemoryFcmTransport {
+    pub fn new() -> InMemoryFcmTransport {
+        return InMemoryFcmTransport {
+            sent_messages: Mutex::new(vec![])
+        };
+    }
+
+    pub fn sent_messages(&self) -> Vec<RecordedFcmMessage> {
+        return self.sent_messages.lock().unwrap().clone();
+    }
+
+    pub fn clear(&self) {
+        self.sent_messages.lock().unwrap().clear();
+    }
+}
+
+#[async_trait]
+impl FcmTransport for InMemoryFcmTransport {
+    async fn send(
+        &self,
+        token: &str,
+        data: &HashMap<&str, String>,
+        priority: FcmMessagePriority
+    ) -> anyhow::Result<FcmSendAttemptResult> {
+        let data = data.iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect::<HashMap<String, String>>();
+
+        let recorded_fcm_message = RecordedFcmMessage {
+            token: token.to_string(),
+            data,
+            priority
+        };
+
+        self.sent_messages.lock().unwrap().push(recorded_fcm_message);
+        return Ok(FcmSendAttemptResult::Sent);
+    }
+}
@@ -30,4 +30,48 @@ pub async fn post_request<'a, Response : DeserializeOwned>(
     let response_data = serde_json::from_str::<Response>(&text)?;
 
     return Ok(response_data);
-}
\ No newline at end of file
+}
+pub async fn get_request(endpoint: &str) -> anyhow::Result<reqwest::Response> {
+    let full_url = format!("{}/{}", *BASE_URL, endpoint);
+
+    let request = HTTP_CLIENT.get(full_url).build()?;
+    let response = HTTP_CLIENT.execute(request).await?;
+
+    return Ok(response);
+}
+
+pub async fn get_request_text(endpoint: &str) -> anyhow::Result<String> {
+    let full_url = format!("{}/{}", *BASE_URL, endpoint);
+
+    let request = HTTP_CLIENT.get(full_url).build()?;
+    let response = HTTP_CLIENT.execute(request).await.unwrap();
+
+    let status = response.status().as_u16();
+    if status != 200 {
+        return Err(anyhow!("Bad response status: {}", status))
+    }
+
+    let text = response.text().await?;
+    return Ok(text);
+}
+
+pub async fn get_request_text_with_master_password(
+    endpoint: &str,
+    master_password: &str
+) -> anyhow::Result<String> {
+    let full_url = format!("{}/{}", *BASE_URL, endpoint);
+
+    let request = HTTP_CLIENT.get(full_url)
+        .header("X-Master-Password", master_password.to_string())
+        .build()?;
+
+    let response = HTTP_CLIENT.execute(request).await.unwrap();
+
+    let status = response.status().as_u16();
+    if status != 200 {
+        return Err(anyhow!("Bad response status: {}", status))
+    }
+
+    let text = response.text().await?;
+    return Ok(text);
+}
@@ -4,4 +4,6 @@ pub mod server_shared;
 pub mod http_client_shared;
 pub mod account_repository_shared;
 pub mod watch_post_repository_shared;
-pub mod site_repository_shared;
\ No newline at end of file
+pub mod site_repository_shared;
+pub mod fcm_transport_shared;
+pub mod post_reply_repository_shared;
\ No newline at end of file
@@ -0,0 +1,46 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::model::database::db::Database;
+use crate::model::repository::account_repository::AccountToken;
+use crate::model::repository::post_reply_repository;
+use crate::model::repository::post_reply_repository::UnsentReply;
+
+/// Reads the unsent replies through the same claim path the sender uses and releases the claims
+/// right away so that the replies stay unsent. Just like a failed send this counts as a delivery
+/// attempt.
+pub async fn peek_unsent_replies(database: &Arc<Database>) -> HashMap<AccountToken, HashSet<UnsentReply>> {
+    let unsent_replies = post_reply_repository::claim_unsent_replies(true, database)
+        .await
+        .unwrap();
+
+    let post_reply_ids = unsent_replies.values()
+        .flat_map(|unsent_replies| unsent_replies.iter())
+        .map(|unsent_reply| unsent_reply.post_reply_id)
+        .collect::<HashSet<i64>>()
+        .into_iter()
+        .collect::<Vec<i64>>();
+
+    post_reply_repository::release_claimed_replies(&post_reply_ids, database)
+        .await
+        .unwrap();
+
+    return unsent_replies;
+}
+
+pub async fn peek_unsent_replies_count(database: &Arc<Database>) -> usize {
+    return peek_unsent_replies(database)
+        .await
+        .values()
+        .map(|unsent_replies| unsent_replies.len())
+        .sum();
+}
+
+/// Makes the reply look like every delivery attempt has failed without touching the other replies.
+pub async fn exhaust_delivery_attempts(post_reply_id: i64, database: &Arc<Database>) {
+    let connection = database.connection().await.unwrap();
+    connection.execute(
+        "UPDATE post_replies SET notification_delivery_attempt = $1 WHERE id = $2",
+        &[&post_reply_repository::MAX_NOTIFICATION_DELIVERY_ATTEMPTS, &post_reply_id]
+    ).await.unwrap();
+}
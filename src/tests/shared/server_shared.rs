@@ -2,8 +2,6 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use hyper::server::conn::http1;
-use hyper::service::service_fn;
 use lazy_static::lazy_static;
 use tokio::net::TcpListener;
 use tokio::sync::Mutex;
@@ -11,15 +9,33 @@ use tokio::task::JoinHandle;
 
 use crate::model::database::db::Database;
 use crate::model::repository::site_repository::SiteRepository;
-use crate::router::{router, TestContext};
+use crate::router;
+use crate::router::{HttpProtocol, MasterPassword, TestContext};
+use crate::service::fcm_sender::FcmSender;
+use crate::tests::shared::{database_shared, fcm_transport_shared, site_repository_shared};
 
 static SERVER_WORKING_FLAG: AtomicBool = AtomicBool::new(false);
 pub static TEST_MASTER_PASSWORD: &'static str = "test123";
+pub static TEST_HOST_ADDRESS: &'static str = "http://127.0.0.1:3000";
 
 lazy_static! {
     static ref SERVER_HANDLE: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
 }
 
+pub fn test_server_fcm_sender(
+    database: &Arc<Database>,
+    site_repository: &Arc<SiteRepository>
+) -> Arc<FcmSender> {
+    let fcm_sender = FcmSender::with_transport(
+        true,
+        fcm_transport_shared::test_server_fcm_transport().clone(),
+        database,
+        site_repository
+    );
+
+    return Arc::new(fcm_sender);
+}
+
 pub async fn ctor(
     site_repository: &Arc<SiteRepository>,
     database: &Arc<Database>
@@ -27,10 +43,12 @@ pub async fn ctor(
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
     let listener = TcpListener::bind(addr).await.unwrap();
     SERVER_WORKING_FLAG.store(true, Ordering::SeqCst);
-    let master_password = TEST_MASTER_PASSWORD.to_string();
+    let master_password = Arc::new(MasterPassword::from_plaintext(TEST_MASTER_PASSWORD));
+    let host_address = Arc::new(TEST_HOST_ADDRESS.to_string());
 
     let database_cloned_for_router = database.clone();
     let site_repository_cloned = site_repository.clone();
+    let fcm_sender = test_server_fcm_sender(database, site_repository);
 
     let join_handle: JoinHandle<()> = tokio::task::spawn(async move {
         loop {
@@ -42,27 +60,23 @@ pub async fn ctor(
             let database_cloned_for_router = database_cloned_for_router.clone();
             let site_repository_cloned = site_repository_cloned.clone();
             let master_password_cloned = master_password.clone();
+            let host_address_cloned = host_address.clone();
+            let fcm_sender_cloned = fcm_sender.clone();
 
             tokio::task::spawn(async move {
-                http1::Builder::new()
-                    .serve_connection(
-                        stream,
-                        service_fn(|request| {
-                            let test_context = TestContext { enable_throttler: false };
-                            let test_context = Some(test_context);
-
-                            return router(
-                                test_context,
-                                &master_password_cloned,
-                                &sock_addr,
-                                request,
-                                &database_cloned_for_router,
-                                &site_repository_cloned
-                            );
-                        }),
-                    )
-                    .await
-                    .unwrap();
+                let test_context = TestContext { enable_throttler: false };
+
+                router::serve_connection(
+                    Some(test_context),
+                    HttpProtocol::Http1,
+                    stream,
+                    sock_addr,
+                    master_password_cloned,
+                    host_address_cloned,
+                    database_cloned_for_router,
+                    site_repository_cloned,
+                    fcm_sender_cloned
+                ).await.unwrap();
             });
         }
 
@@ -82,4 +96,38 @@ pub async fn dtor() {
     let server_handle = server_handle_locked.take().unwrap();
     server_handle.abort();
     let _ = server_handle.await;
-}
\ No newline at end of file
+}
+
+/// Starts a server with the throttler enabled on a random port since the shared test server
+/// doesn't throttle. Abort the returned handle once the test is done.
+pub async fn start_throttled_server() -> (SocketAddr, JoinHandle<()>) {
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await.unwrap();
+    let server_address = listener.local_addr().unwrap();
+
+    let server_handle = tokio::task::spawn(async move {
+        loop {
+            let (stream, sock_addr) = listener.accept().await.unwrap();
+
+            tokio::task::spawn(async move {
+                let test_context = TestContext { enable_throttler: true };
+
+                router::serve_connection(
+                    Some(test_context),
+                    HttpProtocol::Http1,
+                    stream,
+                    sock_addr,
+                    Arc::new(MasterPassword::from_plaintext(TEST_MASTER_PASSWORD)),
+                    Arc::new(TEST_HOST_ADDRESS.to_string()),
+                    database_shared::database().clone(),
+                    site_repository_shared::site_repository().clone(),
+                    test_server_fcm_sender(
+                        database_shared::database(),
+                        site_repository_shared::site_repository()
+                    )
+                ).await.unwrap();
+            });
+        }
+    });
+
+    return (server_address, server_handle);
+}
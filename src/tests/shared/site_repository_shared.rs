@@ -1,7 +1,10 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use once_cell::sync::OnceCell;
 
+use crate::constants;
+use crate::helpers::http_client;
 use crate::model::repository::site_repository::SiteRepository;
 
 static SITE_REPOSITORY: OnceCell<Arc<SiteRepository>> = OnceCell::new();
@@ -11,7 +14,13 @@ pub fn site_repository() -> &'static Arc<SiteRepository> {
 }
 
 pub async fn ctor() {
-    let _ = SITE_REPOSITORY.set(Arc::new(SiteRepository::new()));
+    let http_client = Arc::new(http_client::build_http_client(
+        None,
+        Duration::from_secs(constants::DEFAULT_HTTP_REQUEST_TIMEOUT_SECONDS),
+        Duration::from_secs(constants::DEFAULT_HTTP_CONNECT_TIMEOUT_SECONDS)
+    ));
+
+    let _ = SITE_REPOSITORY.set(Arc::new(SiteRepository::new(&http_client)));
 }
 
 pub async fn dtor() {
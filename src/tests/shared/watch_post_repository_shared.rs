@@ -2,8 +2,14 @@ use std::sync::Arc;
 
 use serde::de::DeserializeOwned;
 
-use crate::handlers::shared::{ServerResponse, ServerSuccessResponse};
+use crate::handlers::get_pending_replies::GetPendingRepliesRequest;
+use crate::handlers::get_thread_progress::GetThreadProgressRequest;
+use crate::handlers::mute_thread::MuteThreadRequest;
+use crate::handlers::register_and_watch::RegisterAndWatchRequest;
+use crate::handlers::shared::{PostDescriptorJson, ServerResponse, ServerSuccessResponse};
+use crate::handlers::unwatch_post::UnwatchPostRequest;
 use crate::handlers::watch_post::WatchPostRequest;
+use crate::handlers::watch_posts::WatchPostsRequest;
 use crate::model::data::chan::PostDescriptor;
 use crate::model::database::db::Database;
 use crate::model::repository::account_repository::{AccountId, ApplicationType};
@@ -19,11 +25,46 @@ pub async fn watch_post<'a, T : DeserializeOwned + ServerSuccessResponse>(
     user_id: &str,
     post_url: &str,
     application_type: &ApplicationType
+) -> anyhow::Result<ServerResponse<T>> {
+    return watch_post_with_filter::<T>(user_id, post_url, application_type, None).await;
+}
+
+pub async fn watch_post_with_filter<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    user_id: &str,
+    post_url: &str,
+    application_type: &ApplicationType,
+    filter_regex: Option<&str>
 ) -> anyhow::Result<ServerResponse<T>> {
     let request = WatchPostRequest {
         user_id: user_id.to_string(),
         post_url: post_url.to_string(),
-        application_type: application_type.clone()
+        post_descriptor: None,
+        application_type: application_type.clone(),
+        filter_regex: filter_regex.map(|filter_regex| filter_regex.to_string())
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "watch_post",
+        &body,
+        TEST_MASTER_PASSWORD,
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn watch_post_by_descriptor<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    user_id: &str,
+    post_descriptor: &PostDescriptorJson,
+    application_type: &ApplicationType
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = WatchPostRequest {
+        user_id: user_id.to_string(),
+        post_url: String::new(),
+        post_descriptor: Some(post_descriptor.clone()),
+        application_type: application_type.clone(),
+        filter_regex: None
     };
 
     let body = serde_json::to_string(&request).unwrap();
@@ -37,6 +78,137 @@ pub async fn watch_post<'a, T : DeserializeOwned + ServerSuccessResponse>(
     return Ok(response);
 }
 
+pub async fn unwatch_post_by_descriptor<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    user_id: &str,
+    post_descriptor: &PostDescriptorJson,
+    application_type: &ApplicationType
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = UnwatchPostRequest {
+        user_id: user_id.to_string(),
+        post_url: String::new(),
+        post_descriptor: Some(post_descriptor.clone()),
+        application_type: application_type.clone()
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "unwatch_post",
+        &body,
+        TEST_MASTER_PASSWORD,
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn mute_thread<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    user_id: &str,
+    thread_url: &str,
+    minutes: i64
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = MuteThreadRequest {
+        user_id: user_id.to_string(),
+        thread_url: thread_url.to_string(),
+        minutes
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "mute_thread",
+        &body,
+        TEST_MASTER_PASSWORD,
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn watch_posts<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    user_id: &str,
+    post_urls: &[&str],
+    application_type: &ApplicationType
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = WatchPostsRequest {
+        user_id: user_id.to_string(),
+        post_urls: post_urls.iter().map(|post_url| post_url.to_string()).collect(),
+        application_type: application_type.clone()
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "watch_posts",
+        &body,
+        TEST_MASTER_PASSWORD,
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn register_and_watch<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    user_id: &str,
+    firebase_token: &str,
+    post_urls: &[&str],
+    application_type: &ApplicationType
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = RegisterAndWatchRequest {
+        user_id: user_id.to_string(),
+        application_type: application_type.clone(),
+        firebase_token: firebase_token.to_string(),
+        post_urls: post_urls.iter().map(|post_url| post_url.to_string()).collect()
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "register_and_watch",
+        &body,
+        ""
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn get_pending_replies<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    user_id: &str,
+    application_type: &ApplicationType
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = GetPendingRepliesRequest {
+        user_id: user_id.to_string(),
+        application_type: application_type.clone()
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "get_pending_replies",
+        &body,
+        TEST_MASTER_PASSWORD,
+    ).await?;
+
+    return Ok(response);
+}
+
+pub async fn get_thread_progress<'a, T : DeserializeOwned + ServerSuccessResponse>(
+    user_id: &str,
+    post_url: &str
+) -> anyhow::Result<ServerResponse<T>> {
+    let request = GetThreadProgressRequest {
+        user_id: user_id.to_string(),
+        post_url: post_url.to_string()
+    };
+
+    let body = serde_json::to_string(&request).unwrap();
+
+    let response = http_client_shared::post_request::<ServerResponse<T>>(
+        "get_thread_progress",
+        &body,
+        TEST_MASTER_PASSWORD,
+    ).await?;
+
+    return Ok(response);
+}
+
 pub async fn get_post_watches_from_database(
     account_id: &AccountId,
     database: &Arc<Database>